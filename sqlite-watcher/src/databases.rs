@@ -0,0 +1,229 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::channel;
+use std::thread;
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use tracing::warn;
+
+use crate::decoder::WalGrowthDecoder;
+use crate::queue::ChangeQueue;
+use crate::wal::{start_wal_watcher, WalWatcherConfig, WalWatcherHandle};
+
+/// A SQLite database the watcher follows, and the ID clients select it by
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WatchedDatabase {
+    pub id: String,
+    pub path: PathBuf,
+}
+
+/// Watcher config file listing databases to follow
+///
+/// ```toml
+/// databases = ["~/app/main.db", "orders=~/app/orders.sqlite", "~/tenants/*.db"]
+/// watch_dirs = ["/data/*.sqlite"]
+/// ```
+#[derive(Debug, Default, Deserialize)]
+pub struct WatcherConfig {
+    /// Same entries as `--db`
+    #[serde(default)]
+    pub databases: Vec<String>,
+    /// Same patterns as `--watch-dir`
+    #[serde(default)]
+    pub watch_dirs: Vec<String>,
+}
+
+/// Read a watcher config file
+pub fn load_config(path: &Path) -> Result<WatcherConfig> {
+    let raw = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read config file {}", path.display()))?;
+    toml::from_str(&raw).with_context(|| format!("failed to parse config file {}", path.display()))
+}
+
+/// Resolve `--db` and config entries into databases with unique IDs
+///
+/// Each entry is `path`, `glob`, or `id=path`. A database's ID defaults to
+/// its file stem; entries naming the same file are followed once.
+pub fn resolve(specs: &[String]) -> Result<Vec<WatchedDatabase>> {
+    let mut by_id: BTreeMap<String, PathBuf> = BTreeMap::new();
+    for spec in specs {
+        let (id, pattern) = match spec.split_once('=') {
+            Some((id, path)) => (Some(id.trim()), path.trim()),
+            None => (None, spec.trim()),
+        };
+        let pattern = expand_home(pattern)?;
+        let paths = if is_glob(&pattern) {
+            if id.is_some() {
+                bail!("database '{spec}': an ID cannot be given to a glob pattern");
+            }
+            let matches = glob::glob(&pattern)
+                .with_context(|| format!("invalid glob pattern '{pattern}'"))?
+                .collect::<Result<Vec<_>, _>>()
+                .with_context(|| format!("failed to expand '{pattern}'"))?;
+            if matches.is_empty() {
+                warn!(pattern = %pattern, "glob matched no databases");
+            }
+            matches
+        } else {
+            vec![PathBuf::from(&pattern)]
+        };
+
+        for path in paths {
+            let path = path
+                .canonicalize()
+                .with_context(|| format!("database {} does not exist", path.display()))?;
+            let id = match id {
+                Some(id) => id.to_string(),
+                None => path
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().into_owned())
+                    .with_context(|| {
+                        format!("database path {} has no file name", path.display())
+                    })?,
+            };
+            validate_id(&id)?;
+            match by_id.get(&id) {
+                Some(existing) if *existing == path => {}
+                Some(existing) => bail!(
+                    "databases {} and {} both have ID '{id}'; name one with --db <id>=<path>",
+                    existing.display(),
+                    path.display()
+                ),
+                None => {
+                    by_id.insert(id, path);
+                }
+            }
+        }
+    }
+    Ok(by_id
+        .into_iter()
+        .map(|(id, path)| WatchedDatabase { id, path })
+        .collect())
+}
+
+/// Queue file for one of several watched databases, next to `queue_db`
+///
+/// `changes.db` becomes `changes-<id>.db`, so each database keeps its own
+/// changes and checkpoints.
+pub fn queue_path_for(queue_db: &Path, id: &str) -> PathBuf {
+    let stem = queue_db
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "changes".to_string());
+    let name = match queue_db.extension() {
+        Some(ext) => format!("{stem}-{id}.{}", ext.to_string_lossy()),
+        None => format!("{stem}-{id}"),
+    };
+    queue_db.with_file_name(name)
+}
+
+/// Follow a database's WAL and write the changes it decodes into `queue_path`
+///
+/// Watching stops when the returned handle is dropped.
+pub fn start_database_watcher(
+    database: &WatchedDatabase,
+    queue_path: &Path,
+    config: WalWatcherConfig,
+) -> Result<WalWatcherHandle> {
+    let queue = ChangeQueue::open(queue_path)?;
+    let (tx, rx) = channel();
+    let handle = start_wal_watcher(&database.path, config, tx)?;
+    let id = database.id.clone();
+    thread::spawn(move || {
+        let decoder = WalGrowthDecoder;
+        for event in rx {
+            for row in decoder.decode(&event) {
+                if let Err(err) = queue.enqueue(&row.into_new_change()) {
+                    warn!(database = %id, error = %err, "failed to enqueue change");
+                }
+            }
+        }
+    });
+    Ok(handle)
+}
+
+pub(crate) fn validate_id(id: &str) -> Result<()> {
+    if id.is_empty()
+        || !id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.')
+    {
+        bail!("invalid database ID '{id}': use letters, digits, '_', '-', or '.'");
+    }
+    Ok(())
+}
+
+pub(crate) fn is_glob(pattern: &str) -> bool {
+    pattern.contains(['*', '?', '['])
+}
+
+pub(crate) fn expand_home(path: &str) -> Result<String> {
+    match path.strip_prefix("~/") {
+        Some(rest) => {
+            let home = dirs::home_dir()
+                .ok_or_else(|| anyhow::anyhow!("Unable to resolve home directory"))?;
+            Ok(home.join(rest).to_string_lossy().into_owned())
+        }
+        None => Ok(path.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn resolves_paths_globs_and_ids() {
+        let dir = tempdir().unwrap();
+        for name in ["a.db", "b.db", "other.sqlite"] {
+            std::fs::write(dir.path().join(name), b"").unwrap();
+        }
+        let root = dir.path().to_string_lossy().into_owned();
+
+        let databases = resolve(&[
+            format!("{root}/*.db"),
+            format!("main={root}/other.sqlite"),
+            format!("{root}/a.db"),
+        ])
+        .unwrap();
+        let ids: Vec<&str> = databases.iter().map(|db| db.id.as_str()).collect();
+        assert_eq!(ids, ["a", "b", "main"]);
+
+        std::fs::create_dir(dir.path().join("nested")).unwrap();
+        std::fs::write(dir.path().join("nested/a.db"), b"").unwrap();
+        let clash = resolve(&[format!("{root}/a.db"), format!("{root}/nested/a.db")]);
+        assert!(clash.is_err());
+
+        assert!(resolve(&[format!("x={root}/*.db")]).is_err());
+        assert!(resolve(&[format!("{root}/missing.db")]).is_err());
+        assert!(resolve(&[format!("bad id={root}/a.db")]).is_err());
+    }
+
+    #[test]
+    fn queue_paths_are_per_database() {
+        assert_eq!(
+            queue_path_for(Path::new("/q/changes.db"), "orders"),
+            PathBuf::from("/q/changes-orders.db")
+        );
+        assert_eq!(
+            queue_path_for(Path::new("/q/queue"), "a"),
+            PathBuf::from("/q/queue-a")
+        );
+    }
+
+    #[test]
+    fn reads_config_entries() {
+        let dir = tempdir().unwrap();
+        let config = dir.path().join("watcher.toml");
+        std::fs::write(
+            &config,
+            "databases = [\"a.db\", \"t/*.db\"]\nwatch_dirs = [\"/data/*.sqlite\"]\n",
+        )
+        .unwrap();
+        let config = load_config(&config).unwrap();
+        assert_eq!(config.databases, ["a.db", "t/*.db"]);
+        assert_eq!(config.watch_dirs, ["/data/*.sqlite"]);
+    }
+}
@@ -21,10 +21,11 @@ impl WalGrowthDecoder {
             payload: Some(json!({
                 "kind": "wal_growth",
                 "bytes_added": event.bytes_added,
+                "frames_added": event.frames_added,
                 "current_size": event.current_size,
                 "recorded_at": now.as_secs_f64(),
             })),
-            wal_frame: None,
+            wal_frame: Some(event.position.to_string()),
             cursor: None,
         }]
     }
@@ -33,6 +34,7 @@ impl WalGrowthDecoder {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::wal::WalPosition;
 
     #[test]
     fn produces_placeholder_row_change() {
@@ -40,9 +42,15 @@ mod tests {
         let rows = decoder.decode(&WalEvent {
             bytes_added: 1024,
             current_size: 2048,
+            frames_added: 1,
+            position: WalPosition {
+                frame: 3,
+                ..Default::default()
+            },
         });
         assert_eq!(rows.len(), 1);
         assert_eq!(rows[0].table_name, "__wal__");
         assert_eq!(rows[0].operation, ChangeOperation::Insert);
+        assert_eq!(rows[0].wal_frame.as_deref(), Some("0:0000000000000000:3"));
     }
 }
@@ -0,0 +1,234 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::channel;
+use std::thread::{self, JoinHandle};
+
+use anyhow::{bail, Context, Result};
+use glob::Pattern;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use tracing::{info, warn};
+
+use crate::databases::{self, WatchedDatabase};
+use crate::server::DatabaseQueues;
+use crate::wal::{WalWatcherConfig, WalWatcherHandle};
+
+/// Files SQLite keeps beside a database, never databases themselves
+const SIDECAR_SUFFIXES: [&str; 3] = ["-wal", "-shm", "-journal"];
+
+/// Follows a directory for SQLite files matching a pattern
+///
+/// Matching files are watched as they appear and retired when they are
+/// deleted. Dropping the handle stops every watcher it started.
+pub struct DirectoryWatcherHandle {
+    watcher: Option<RecommendedWatcher>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Drop for DirectoryWatcherHandle {
+    fn drop(&mut self) {
+        // Dropping the notify watcher closes the event channel, which ends
+        // the thread and drops the database watchers it owns
+        self.watcher.take();
+        if let Some(handle) = self.thread.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Watch every file matching `pattern` (e.g. `/data/*.sqlite`), now and as
+/// files are created, registering each in `queues` under its file stem
+///
+/// Only the file name may contain wildcards; a bare directory matches every
+/// file in it. Each database gets its own queue next to `queue_db`, as
+/// [`databases::queue_path_for`] names it.
+pub fn start_directory_watcher(
+    pattern: &str,
+    queue_db: &Path,
+    queues: DatabaseQueues,
+    config: WalWatcherConfig,
+) -> Result<DirectoryWatcherHandle> {
+    let (dir, file_pattern) = split_pattern(pattern)?;
+    let (tx, rx) = channel();
+    let mut watcher =
+        notify::recommended_watcher(tx).context("failed to create file system watcher")?;
+    watcher
+        .watch(&dir, RecursiveMode::NonRecursive)
+        .with_context(|| format!("failed to watch directory {}", dir.display()))?;
+
+    let mut discovery = Discovery {
+        file_pattern,
+        queue_db: queue_db.to_path_buf(),
+        queues,
+        config,
+        watched: HashMap::new(),
+    };
+    let entries = std::fs::read_dir(&dir)
+        .with_context(|| format!("failed to read directory {}", dir.display()))?;
+    for entry in entries {
+        discovery.reconcile(&entry?.path());
+    }
+
+    let thread = thread::spawn(move || {
+        for event in rx {
+            match event {
+                Ok(event) => {
+                    for path in &event.paths {
+                        discovery.reconcile(path);
+                    }
+                }
+                Err(err) => warn!(error = %err, "file system watcher error"),
+            }
+        }
+    });
+    Ok(DirectoryWatcherHandle {
+        watcher: Some(watcher),
+        thread: Some(thread),
+    })
+}
+
+struct Discovery {
+    file_pattern: Pattern,
+    queue_db: PathBuf,
+    queues: DatabaseQueues,
+    config: WalWatcherConfig,
+    watched: HashMap<PathBuf, (String, WalWatcherHandle)>,
+}
+
+impl Discovery {
+    /// Start or retire the watcher for `path` to match whether it exists
+    ///
+    /// Events are only hints: backends report creates, renames, and deletes
+    /// differently, so the file system is checked each time.
+    fn reconcile(&mut self, path: &Path) {
+        if self.watched.contains_key(path) {
+            if !path.exists() {
+                let (id, handle) = self.watched.remove(path).unwrap();
+                drop(handle);
+                self.queues.remove(&id);
+                info!(database = %id, path = %path.display(), "database removed; watcher retired");
+            }
+            return;
+        }
+        if !path.is_file() || !self.matches(path) {
+            return;
+        }
+        if let Err(err) = self.start(path) {
+            warn!(path = %path.display(), error = %err, "not watching discovered database");
+        }
+    }
+
+    fn start(&mut self, path: &Path) -> Result<()> {
+        let id = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .context("database path has no file name")?;
+        databases::validate_id(&id)?;
+        if self.queues.contains(&id) {
+            bail!("a database with ID '{id}' is already watched");
+        }
+        let database = WatchedDatabase {
+            id: id.clone(),
+            path: path.to_path_buf(),
+        };
+        let queue_path = databases::queue_path_for(&self.queue_db, &id);
+        let handle = databases::start_database_watcher(&database, &queue_path, self.config)?;
+        self.queues.insert(&id, queue_path);
+        info!(database = %id, path = %path.display(), "discovered database");
+        self.watched.insert(path.to_path_buf(), (id, handle));
+        Ok(())
+    }
+
+    fn matches(&self, path: &Path) -> bool {
+        let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+            return false;
+        };
+        !SIDECAR_SUFFIXES.iter().any(|suffix| name.ends_with(suffix))
+            && self.file_pattern.matches(name)
+    }
+}
+
+/// Split `pattern` into the directory to watch and the file name pattern
+fn split_pattern(pattern: &str) -> Result<(PathBuf, Pattern)> {
+    let expanded = PathBuf::from(databases::expand_home(pattern)?);
+    let (dir, file_pattern) = if expanded.is_dir() {
+        (expanded, "*".to_string())
+    } else {
+        let dir = expanded
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .unwrap_or(Path::new("."))
+            .to_path_buf();
+        let file_pattern = expanded
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .with_context(|| format!("watch pattern '{pattern}' has no file name"))?;
+        (dir, file_pattern)
+    };
+    if databases::is_glob(&dir.to_string_lossy()) {
+        bail!("watch pattern '{pattern}': only the file name may contain wildcards");
+    }
+    let dir = dir
+        .canonicalize()
+        .with_context(|| format!("directory {} does not exist", dir.display()))?;
+    let file_pattern = Pattern::new(&file_pattern)
+        .with_context(|| format!("invalid watch pattern '{pattern}'"))?;
+    Ok((dir, file_pattern))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+    use tempfile::tempdir;
+
+    fn wait_for(mut condition: impl FnMut() -> bool) -> bool {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while Instant::now() < deadline {
+            if condition() {
+                return true;
+            }
+            thread::sleep(Duration::from_millis(50));
+        }
+        false
+    }
+
+    #[test]
+    fn discovers_and_retires_databases() {
+        let data = tempdir().unwrap();
+        let queue_dir = tempdir().unwrap();
+        std::fs::write(data.path().join("a.sqlite"), b"").unwrap();
+        std::fs::write(data.path().join("a.sqlite-wal"), b"").unwrap();
+        std::fs::write(data.path().join("notes.txt"), b"").unwrap();
+
+        let queues = DatabaseQueues::new();
+        let pattern = format!("{}/*.sqlite", data.path().display());
+        let _handle = start_directory_watcher(
+            &pattern,
+            &queue_dir.path().join("changes.db"),
+            queues.clone(),
+            WalWatcherConfig::default(),
+        )
+        .unwrap();
+        assert_eq!(queues.ids(), ["a"]);
+        assert!(queue_dir.path().join("changes-a.db").exists());
+
+        std::fs::write(data.path().join("b.sqlite"), b"").unwrap();
+        std::fs::write(data.path().join("b.txt"), b"").unwrap();
+        assert!(wait_for(|| queues.ids() == ["a", "b"]));
+
+        std::fs::remove_file(data.path().join("a.sqlite")).unwrap();
+        assert!(wait_for(|| queues.ids() == ["b"]));
+        // The queue stays on disk for the database to resume into
+        assert!(queue_dir.path().join("changes-a.db").exists());
+    }
+
+    #[test]
+    fn only_the_file_name_may_be_a_pattern() {
+        let data = tempdir().unwrap();
+        assert!(split_pattern(&format!("{}/*/x.db", data.path().display())).is_err());
+        assert!(split_pattern(&format!("{}/missing/*.db", data.path().display())).is_err());
+        let (dir, pattern) = split_pattern(&data.path().to_string_lossy()).unwrap();
+        assert_eq!(dir, data.path().canonicalize().unwrap());
+        assert_eq!(pattern.as_str(), "*");
+    }
+}
@@ -1,5 +1,11 @@
+pub mod change;
+pub mod databases;
+pub mod decoder;
+pub mod discovery;
 pub mod queue;
+pub mod retention;
 pub mod server;
+pub mod wal;
 pub mod watcher_proto {
     tonic::include_proto!("sqlitewatcher");
 }
@@ -2,11 +2,17 @@ use std::path::PathBuf;
 use std::time::Duration;
 
 use anyhow::{bail, Context, Result};
-use clap::{Parser, Subcommand, ValueEnum};
-use sqlite_watcher::queue::{ChangeOperation, ChangeQueue, NewChange};
-use sqlite_watcher::server::spawn_tcp;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use sqlite_watcher::databases::{self, WatchedDatabase};
+use sqlite_watcher::discovery;
+use sqlite_watcher::queue::{ChangeOperation, ChangeQueue, NewChange, RetentionPolicy};
+use sqlite_watcher::retention;
+#[cfg(windows)]
+use sqlite_watcher::server::spawn_pipe;
 #[cfg(unix)]
 use sqlite_watcher::server::spawn_unix;
+use sqlite_watcher::server::{spawn_tcp, DatabaseQueues, DEFAULT_DATABASE};
+use sqlite_watcher::wal::WalWatcherConfig;
 use tokio::signal;
 
 #[derive(Parser)]
@@ -24,12 +30,24 @@ enum Command {
         /// SQLite queue database path
         #[arg(long = "queue-db")]
         queue_db: Option<PathBuf>,
-        /// gRPC listener (unix:/path or tcp:host:port)
+        /// gRPC listener (unix:/path, tcp:host:port, or pipe:name on Windows)
         #[arg(long = "listen", default_value = "unix:/tmp/sqlite-watcher.sock")]
         listen: String,
         /// Shared-secret token file (defaults to ~/.seren/sqlite-watcher/token)
         #[arg(long = "token-file")]
         token_file: Option<PathBuf>,
+        /// SQLite database to watch, as path, glob, or id=path (repeatable)
+        #[arg(long = "db")]
+        dbs: Vec<String>,
+        /// Directory pattern (e.g. /data/*.sqlite) to discover databases in as
+        /// they are created; deleted files stop being watched (repeatable)
+        #[arg(long = "watch-dir")]
+        watch_dirs: Vec<String>,
+        #[command(flatten)]
+        retention: RetentionArgs,
+        /// TOML file with `databases` and `watch_dirs` lists, as --db and --watch-dir
+        #[arg(long = "config")]
+        config: Option<PathBuf>,
     },
     /// Enqueue a test change into the queue database
     Enqueue {
@@ -46,6 +64,28 @@ enum Command {
     },
 }
 
+#[derive(Args)]
+struct RetentionArgs {
+    /// Drop pending changes older than this (e.g. 7d, 12h) if no consumer acks them
+    #[arg(long = "max-queue-age", value_parser = retention::parse_age)]
+    max_queue_age: Option<Duration>,
+    /// Drop the oldest pending changes once a queue holds more than this (e.g. 1GB)
+    #[arg(long = "max-queue-size", value_parser = retention::parse_size)]
+    max_queue_size: Option<u64>,
+    /// How often to compact queues and apply the retention limits
+    #[arg(long = "compact-interval", value_parser = retention::parse_age, default_value = "60s")]
+    compact_interval: Duration,
+}
+
+impl RetentionArgs {
+    fn policy(&self) -> RetentionPolicy {
+        RetentionPolicy {
+            max_age: self.max_queue_age,
+            max_bytes: self.max_queue_size,
+        }
+    }
+}
+
 #[derive(Clone, Copy, ValueEnum)]
 enum ChangeOp {
     Insert,
@@ -71,7 +111,16 @@ async fn main() -> Result<()> {
             queue_db,
             listen,
             token_file,
-        } => serve(queue_db, &listen, token_file).await,
+            dbs,
+            watch_dirs,
+            retention,
+            config,
+        } => {
+            serve(
+                queue_db, &listen, token_file, dbs, watch_dirs, config, retention,
+            )
+            .await
+        }
         Command::Enqueue {
             queue_db,
             table,
@@ -82,40 +131,113 @@ async fn main() -> Result<()> {
     }
 }
 
-async fn serve(queue_db: Option<PathBuf>, listen: &str, token_file: Option<PathBuf>) -> Result<()> {
+async fn serve(
+    queue_db: Option<PathBuf>,
+    listen: &str,
+    token_file: Option<PathBuf>,
+    mut dbs: Vec<String>,
+    mut watch_dirs: Vec<String>,
+    config: Option<PathBuf>,
+    retention: RetentionArgs,
+) -> Result<()> {
     let queue_path = resolve_queue_path(queue_db)?;
     let token_path = resolve_token_path(token_file)?;
     let token = std::fs::read_to_string(&token_path)
         .with_context(|| format!("failed to read token file {}", token_path.display()))?;
-    let queue = ChangeQueue::open(&queue_path)?;
+    if let Some(config) = config {
+        let config = databases::load_config(&expand_path(config)?)?;
+        dbs.extend(config.databases);
+        watch_dirs.extend(config.watch_dirs);
+    }
+    let watched = databases::resolve(&dbs)?;
     let endpoint = WatcherEndpoint::parse(listen)?;
-    println!(
-        "sqlite-watcher serving {listen} using queue {}",
-        queue.path().display()
+
+    // Without databases to watch the queue is only served; one database keeps
+    // the queue path as given, several (or any discovered) get a queue each
+    let queues = DatabaseQueues::new();
+    let mut watchers = Vec::new();
+    let mut dir_watchers = Vec::new();
+    if watched.is_empty() && watch_dirs.is_empty() {
+        let queue = ChangeQueue::open(&queue_path)?;
+        println!(
+            "sqlite-watcher serving {listen} using queue {}",
+            queue.path().display()
+        );
+        queues.insert(DEFAULT_DATABASE, queue.path().to_path_buf());
+    } else {
+        let per_database = watched.len() > 1 || !watch_dirs.is_empty();
+        for database in &watched {
+            let path = if per_database {
+                databases::queue_path_for(&queue_path, &database.id)
+            } else {
+                queue_path.clone()
+            };
+            watchers.push(watch(database, &path)?);
+            queues.insert(&database.id, path);
+        }
+        for pattern in &watch_dirs {
+            dir_watchers.push(discovery::start_directory_watcher(
+                pattern,
+                &queue_path,
+                queues.clone(),
+                WalWatcherConfig::default(),
+            )?);
+            println!("discovering databases matching {pattern}");
+        }
+        println!(
+            "sqlite-watcher serving {listen} for {} database(s)",
+            queues.ids().len()
+        );
+    }
+    let compactor = retention::start_compactor(
+        queues.clone(),
+        retention.policy(),
+        retention.compact_interval,
     );
     let handle = match endpoint {
         WatcherEndpoint::Tcp { host, port } => {
             let addr = format!("{}:{}", host, port)
                 .parse()
                 .context("invalid tcp address")?;
-            spawn_tcp(addr, queue.path().to_path_buf(), token)?
+            spawn_tcp(addr, queues, token)?
         }
         #[cfg(unix)]
-        WatcherEndpoint::Unix(path) => spawn_unix(&path, queue.path().to_path_buf(), token)?,
+        WatcherEndpoint::Unix(path) => spawn_unix(&path, queues, token)?,
         #[cfg(not(unix))]
         WatcherEndpoint::Unix(_) => {
             bail!("Unix sockets are not supported on Windows. Use tcp:host:port instead.")
         }
-        WatcherEndpoint::Pipe(name) => bail!("named pipes are not yet supported ({name})"),
+        #[cfg(windows)]
+        WatcherEndpoint::Pipe(name) => spawn_pipe(&name, queues, token)?,
+        #[cfg(not(windows))]
+        WatcherEndpoint::Pipe(name) => bail!(
+            "named pipes are only supported on Windows (pipe:{name}). Use unix:/path or tcp:host:port instead."
+        ),
     };
     println!("Press Ctrl+C to stop sqlite-watcher");
     let ctrl_c = signal::ctrl_c();
     tokio::pin!(ctrl_c);
     let _ = tokio::time::timeout(Duration::MAX, &mut ctrl_c).await;
     drop(handle);
+    drop(compactor);
+    drop(dir_watchers);
+    drop(watchers);
     Ok(())
 }
 
+fn watch(
+    database: &WatchedDatabase,
+    queue_path: &std::path::Path,
+) -> Result<sqlite_watcher::wal::WalWatcherHandle> {
+    println!(
+        "watching {} as '{}' into queue {}",
+        database.path.display(),
+        database.id,
+        queue_path.display()
+    );
+    databases::start_database_watcher(database, queue_path, WalWatcherConfig::default())
+}
+
 fn enqueue(
     queue_db: Option<PathBuf>,
     table: &str,
@@ -209,6 +331,9 @@ impl WatcherEndpoint {
             });
         }
         if let Some(rest) = value.strip_prefix("pipe:") {
+            if rest.is_empty() {
+                bail!("pipe endpoint requires a pipe name");
+            }
             return Ok(WatcherEndpoint::Pipe(rest.to_string()));
         }
         bail!("unsupported listener endpoint: {value}");
@@ -1,6 +1,7 @@
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::time::Duration;
 
 use anyhow::{anyhow, Context, Result};
 use rusqlite::{params, Connection, OptionalExtension, Row};
@@ -25,8 +26,20 @@ CREATE TABLE IF NOT EXISTS state (
     cursor          TEXT,
     updated_at      TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
 );
+
+CREATE TABLE IF NOT EXISTS counters (
+    name   TEXT PRIMARY KEY,
+    value  INTEGER NOT NULL DEFAULT 0
+);
 "#;
 
+/// Bytes a pending change takes up, for size-based retention
+const CHANGE_SIZE: &str = "LENGTH(table_name) + LENGTH(op) + LENGTH(id) \
+    + COALESCE(LENGTH(payload), 0) + COALESCE(LENGTH(wal_frame), 0) + COALESCE(LENGTH(cursor), 0)";
+
+/// Pending changes the retention policy removed before they were acked
+const DROPPED_CHANGES: &str = "dropped_changes";
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ChangeOperation {
     Insert,
@@ -86,6 +99,41 @@ pub struct QueueState {
     pub cursor: Option<String>,
 }
 
+/// Limits on pending changes, for when no consumer keeps up with the queue
+///
+/// Changes past either limit are dropped oldest first and counted in
+/// [`QueueStats::dropped_changes`], so a consumer can tell it must re-baseline.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    pub max_age: Option<Duration>,
+    pub max_bytes: Option<u64>,
+}
+
+/// What one [`ChangeQueue::compact`] pass removed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CompactionStats {
+    /// Acknowledged changes deleted
+    pub purged: u64,
+    /// Pending changes older than the max age
+    pub expired: u64,
+    /// Pending changes dropped to fit the max size
+    pub trimmed: u64,
+    /// Whether the file was vacuumed to give freed pages back
+    pub vacuumed: bool,
+}
+
+/// Queue depth, for monitoring
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QueueStats {
+    pub pending_changes: u64,
+    pub pending_bytes: u64,
+    /// Age of the oldest pending change, if any
+    pub oldest_pending_age: Option<Duration>,
+    pub last_change_id: i64,
+    /// Pending changes removed by retention since the queue was created
+    pub dropped_changes: u64,
+}
+
 pub struct ChangeQueue {
     path: PathBuf,
     conn: Connection,
@@ -130,11 +178,21 @@ impl ChangeQueue {
     }
 
     pub fn fetch_batch(&self, limit: usize) -> Result<Vec<ChangeRecord>> {
+        self.fetch_batch_after(0, limit)
+    }
+
+    /// Pending changes with an ID above `after_change_id`, so a consumer can
+    /// fetch ahead of what it has acknowledged
+    pub fn fetch_batch_after(
+        &self,
+        after_change_id: i64,
+        limit: usize,
+    ) -> Result<Vec<ChangeRecord>> {
         let mut stmt = self.conn.prepare(
             "SELECT change_id, table_name, op, id, payload, wal_frame, cursor
-             FROM changes WHERE acked = 0 ORDER BY change_id ASC LIMIT ?1",
+             FROM changes WHERE acked = 0 AND change_id > ?1 ORDER BY change_id ASC LIMIT ?2",
         )?;
-        let mut rows = stmt.query([limit as i64])?;
+        let mut rows = stmt.query([after_change_id, limit as i64])?;
         let mut results = Vec::new();
         while let Some(row) = rows.next()? {
             results.push(row_to_change(row)?);
@@ -144,7 +202,7 @@ impl ChangeQueue {
 
     pub fn ack_up_to(&self, change_id: i64) -> Result<u64> {
         let updated = self.conn.execute(
-            "UPDATE changes SET acked = 1 WHERE change_id <= ?1",
+            "UPDATE changes SET acked = 1 WHERE change_id <= ?1 AND acked = 0",
             [change_id],
         )?;
         Ok(updated as u64)
@@ -157,6 +215,106 @@ impl ChangeQueue {
         Ok(deleted as u64)
     }
 
+    /// Delete acknowledged changes, apply `policy` to pending ones, and
+    /// vacuum when at least half the file is free pages
+    pub fn compact(&self, policy: &RetentionPolicy) -> Result<CompactionStats> {
+        let mut stats = CompactionStats {
+            purged: self.purge_acked()?,
+            ..Default::default()
+        };
+        if let Some(max_age) = policy.max_age {
+            stats.expired = self.conn.execute(
+                "DELETE FROM changes
+                 WHERE acked = 0 AND created_at < datetime('now', ?1)",
+                [format!("-{} seconds", max_age.as_secs())],
+            )? as u64;
+        }
+        if let Some(max_bytes) = policy.max_bytes {
+            // Keep the newest changes that fit, drop everything older
+            let cutoff: Option<i64> = self
+                .conn
+                .query_row(
+                    &format!(
+                        "SELECT change_id FROM (
+                             SELECT change_id,
+                                    SUM({CHANGE_SIZE}) OVER (ORDER BY change_id DESC) AS kept_bytes
+                             FROM changes WHERE acked = 0
+                         )
+                         WHERE kept_bytes > ?1 ORDER BY change_id DESC LIMIT 1"
+                    ),
+                    [max_bytes as i64],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            if let Some(cutoff) = cutoff {
+                stats.trimmed = self.conn.execute(
+                    "DELETE FROM changes WHERE acked = 0 AND change_id <= ?1",
+                    [cutoff],
+                )? as u64;
+            }
+        }
+        let dropped = stats.expired + stats.trimmed;
+        if dropped > 0 {
+            self.conn.execute(
+                "INSERT INTO counters(name, value) VALUES (?1, ?2)
+                 ON CONFLICT(name) DO UPDATE SET value = value + excluded.value",
+                params![DROPPED_CHANGES, dropped as i64],
+            )?;
+        }
+
+        let page_count: i64 = self
+            .conn
+            .query_row("PRAGMA page_count", [], |row| row.get(0))?;
+        let free_pages: i64 = self
+            .conn
+            .query_row("PRAGMA freelist_count", [], |row| row.get(0))?;
+        if free_pages > 0 && free_pages * 2 >= page_count {
+            self.conn
+                .execute_batch("VACUUM")
+                .context("failed to vacuum change queue")?;
+            stats.vacuumed = true;
+        }
+        Ok(stats)
+    }
+
+    pub fn stats(&self) -> Result<QueueStats> {
+        let (pending_changes, pending_bytes, oldest_age_secs): (i64, i64, Option<i64>) =
+            self.conn.query_row(
+                &format!(
+                    "SELECT COUNT(*), COALESCE(SUM({CHANGE_SIZE}), 0),
+                            CAST(strftime('%s', 'now') - strftime('%s', MIN(created_at)) AS INTEGER)
+                     FROM changes WHERE acked = 0"
+                ),
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )?;
+        let last_change_id: i64 = self
+            .conn
+            .query_row(
+                "SELECT seq FROM sqlite_sequence WHERE name = 'changes'",
+                [],
+                |row| row.get(0),
+            )
+            .optional()?
+            .unwrap_or(0);
+        let dropped_changes: i64 = self
+            .conn
+            .query_row(
+                "SELECT value FROM counters WHERE name = ?1",
+                [DROPPED_CHANGES],
+                |row| row.get(0),
+            )
+            .optional()?
+            .unwrap_or(0);
+        Ok(QueueStats {
+            pending_changes: pending_changes as u64,
+            pending_bytes: pending_bytes as u64,
+            oldest_pending_age: oldest_age_secs.map(|secs| Duration::from_secs(secs.max(0) as u64)),
+            last_change_id,
+            dropped_changes: dropped_changes as u64,
+        })
+    }
+
     pub fn get_state(&self, table: &str) -> Result<Option<QueueState>> {
         self.conn
             .prepare(
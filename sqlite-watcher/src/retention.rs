@@ -0,0 +1,128 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context, Result};
+use tracing::{debug, warn};
+
+use crate::queue::{ChangeQueue, RetentionPolicy};
+use crate::server::DatabaseQueues;
+
+/// Compacts every served queue on an interval
+///
+/// Dropping the handle stops compaction.
+pub struct CompactorHandle {
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Drop for CompactorHandle {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(handle) = self.thread.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Run [`ChangeQueue::compact`] with `policy` on each queue in `queues`
+/// every `interval`, including databases added after the start
+pub fn start_compactor(
+    queues: DatabaseQueues,
+    policy: RetentionPolicy,
+    interval: Duration,
+) -> CompactorHandle {
+    let stop = Arc::new(AtomicBool::new(false));
+    let thread_stop = Arc::clone(&stop);
+    let thread = thread::spawn(move || {
+        let mut next_run = Instant::now();
+        while !thread_stop.load(Ordering::SeqCst) {
+            if Instant::now() >= next_run {
+                for (id, path) in queues.queue_paths() {
+                    let result = ChangeQueue::open(&path).and_then(|queue| queue.compact(&policy));
+                    match result {
+                        Ok(stats) => {
+                            if stats.expired + stats.trimmed > 0 {
+                                warn!(
+                                    database = %id,
+                                    expired = stats.expired,
+                                    trimmed = stats.trimmed,
+                                    "retention dropped changes no consumer acknowledged"
+                                );
+                            }
+                            debug!(database = %id, ?stats, "compacted change queue");
+                        }
+                        Err(err) => {
+                            warn!(database = %id, error = %err, "failed to compact change queue")
+                        }
+                    }
+                }
+                next_run = Instant::now() + interval;
+            }
+            thread::sleep(Duration::from_millis(200));
+        }
+    });
+    CompactorHandle {
+        stop,
+        thread: Some(thread),
+    }
+}
+
+/// Parse a retention age such as `30m`, `12h`, or `7d`
+pub fn parse_age(value: &str) -> Result<Duration> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: u64 = number
+        .parse()
+        .with_context(|| format!("invalid age '{value}'"))?;
+    let seconds = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => bail!("invalid age '{value}': use a number followed by s, m, h, or d"),
+    };
+    Ok(Duration::from_secs(number * seconds))
+}
+
+/// Parse a size such as `512MB` or `2GB` (powers of 1024)
+pub fn parse_size(value: &str) -> Result<u64> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: u64 = number
+        .parse()
+        .with_context(|| format!("invalid size '{value}'"))?;
+    let multiplier: u64 = match unit.to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" => 1024,
+        "M" | "MB" => 1024 * 1024,
+        "G" | "GB" => 1024 * 1024 * 1024,
+        _ => bail!("invalid size '{value}': use a number followed by B, KB, MB, or GB"),
+    };
+    Ok(number * multiplier)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_ages_and_sizes() {
+        assert_eq!(parse_age("45s").unwrap(), Duration::from_secs(45));
+        assert_eq!(parse_age("7d").unwrap(), Duration::from_secs(7 * 86_400));
+        assert!(parse_age("7").is_err());
+        assert!(parse_age("d").is_err());
+
+        assert_eq!(parse_size("512").unwrap(), 512);
+        assert_eq!(parse_size("64kb").unwrap(), 64 * 1024);
+        assert_eq!(parse_size("2GB").unwrap(), 2 * 1024 * 1024 * 1024);
+        assert!(parse_size("1TB").is_err());
+    }
+}
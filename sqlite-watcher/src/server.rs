@@ -1,6 +1,7 @@
+use std::collections::BTreeMap;
 use std::net::SocketAddr;
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 use std::thread::{self, JoinHandle};
 
 use anyhow::{Context, Result};
@@ -10,19 +11,127 @@ use tokio_stream::wrappers::TcpListenerStream;
 use tonic::service::Interceptor;
 use tonic::{transport::Server, Request, Response, Status};
 
+#[cfg(windows)]
+use tokio::net::windows::named_pipe::{NamedPipeServer, ServerOptions};
 #[cfg(unix)]
 use tokio::net::UnixListener;
+#[cfg(windows)]
+use tokio_stream::wrappers::ReceiverStream;
 #[cfg(unix)]
 use tokio_stream::wrappers::UnixListenerStream;
 
-use crate::queue::{ChangeQueue, QueueState};
+use crate::queue::{ChangeOperation, ChangeQueue, QueueState};
 use crate::watcher_proto::watcher_server::{Watcher, WatcherServer};
 use crate::watcher_proto::{
-    AckChangesRequest, AckChangesResponse, Change, GetStateRequest, GetStateResponse,
-    HealthCheckRequest, HealthCheckResponse, ListChangesRequest, ListChangesResponse,
-    SetStateRequest, SetStateResponse,
+    AckChangesRequest, AckChangesResponse, Change, Database, GetQueueStatsRequest,
+    GetQueueStatsResponse, GetStateRequest, GetStateResponse, HealthCheckRequest,
+    HealthCheckResponse, ListChangesRequest, ListChangesResponse, ListDatabasesRequest,
+    ListDatabasesResponse, Operation, SetStateRequest, SetStateResponse,
 };
 
+/// ID reported for the queue of a server started with a single queue path
+pub const DEFAULT_DATABASE: &str = "default";
+
+/// Queue file of each database a server exposes, keyed by database ID
+///
+/// Clones share the same map, so databases can be added and retired while
+/// a server started with it is running.
+#[derive(Debug, Clone, Default)]
+pub struct DatabaseQueues(Arc<RwLock<BTreeMap<String, PathBuf>>>);
+
+impl DatabaseQueues {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&self, id: impl Into<String>, queue_path: PathBuf) {
+        self.0.write().unwrap().insert(id.into(), queue_path);
+    }
+
+    /// Stop exposing `id`; its queue file is left on disk
+    pub fn remove(&self, id: &str) -> Option<PathBuf> {
+        self.0.write().unwrap().remove(id)
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.0.read().unwrap().contains_key(id)
+    }
+
+    pub fn ids(&self) -> Vec<String> {
+        self.0.read().unwrap().keys().cloned().collect()
+    }
+
+    /// Each database ID with its queue file
+    pub fn queue_paths(&self) -> Vec<(String, PathBuf)> {
+        self.0
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(id, path)| (id.clone(), path.clone()))
+            .collect()
+    }
+
+    /// The queue for `database`; an empty ID picks the only queue when there
+    /// is just one
+    fn get(&self, database: &str) -> Result<PathBuf, QueueError> {
+        let queues = self.0.read().unwrap();
+        if database.is_empty() {
+            return match queues.len() {
+                1 => Ok(queues.values().next().unwrap().clone()),
+                0 => Err(QueueError::NoDatabases),
+                _ => Err(QueueError::Ambiguous),
+            };
+        }
+        queues
+            .get(database)
+            .cloned()
+            .ok_or_else(|| QueueError::Unknown(database.to_string()))
+    }
+
+    /// The ID `database` resolves to, for tagging changes
+    fn id(&self, database: &str) -> String {
+        let queues = self.0.read().unwrap();
+        if database.is_empty() && queues.len() == 1 {
+            queues.keys().next().unwrap().clone()
+        } else {
+            database.to_string()
+        }
+    }
+}
+
+/// Why a request's database has no queue to use
+enum QueueError {
+    NoDatabases,
+    Ambiguous,
+    Unknown(String),
+    Open(anyhow::Error),
+}
+
+impl From<QueueError> for Status {
+    fn from(err: QueueError) -> Self {
+        match err {
+            QueueError::NoDatabases => {
+                Status::failed_precondition("no databases are being watched")
+            }
+            QueueError::Ambiguous => {
+                Status::invalid_argument("database is required when several databases are watched")
+            }
+            QueueError::Unknown(database) => {
+                Status::not_found(format!("unknown database '{database}'"))
+            }
+            QueueError::Open(err) => internal_err(err),
+        }
+    }
+}
+
+impl From<PathBuf> for DatabaseQueues {
+    fn from(queue_path: PathBuf) -> Self {
+        let queues = Self::new();
+        queues.insert(DEFAULT_DATABASE, queue_path);
+        queues
+    }
+}
+
 pub enum ServerHandle {
     Tcp {
         shutdown: Option<oneshot::Sender<()>>,
@@ -34,6 +143,11 @@ pub enum ServerHandle {
         thread: Option<JoinHandle<Result<()>>>,
         path: PathBuf,
     },
+    #[cfg(windows)]
+    Pipe {
+        shutdown: Option<oneshot::Sender<()>>,
+        thread: Option<JoinHandle<Result<()>>>,
+    },
 }
 
 impl Drop for ServerHandle {
@@ -61,11 +175,25 @@ impl Drop for ServerHandle {
                 }
                 let _ = std::fs::remove_file(path);
             }
+            #[cfg(windows)]
+            ServerHandle::Pipe { shutdown, thread } => {
+                if let Some(tx) = shutdown.take() {
+                    let _ = tx.send(());
+                }
+                if let Some(handle) = thread.take() {
+                    let _ = handle.join();
+                }
+            }
         }
     }
 }
 
-pub fn spawn_tcp(addr: SocketAddr, queue_path: PathBuf, token: String) -> Result<ServerHandle> {
+pub fn spawn_tcp(
+    addr: SocketAddr,
+    queues: impl Into<DatabaseQueues>,
+    token: String,
+) -> Result<ServerHandle> {
+    let queues = queues.into();
     let (tx, rx) = oneshot::channel();
     let thread = thread::spawn(move || -> Result<()> {
         let rt = Builder::new_multi_thread().enable_all().build()?;
@@ -73,7 +201,7 @@ pub fn spawn_tcp(addr: SocketAddr, queue_path: PathBuf, token: String) -> Result
             let listener = tokio::net::TcpListener::bind(addr)
                 .await
                 .context("failed to bind tcp listener")?;
-            let service = WatcherService::new(queue_path);
+            let service = WatcherService::new(queues);
             let interceptor = AuthInterceptor::new(token);
             Server::builder()
                 .add_service(WatcherServer::with_interceptor(service, interceptor))
@@ -91,7 +219,12 @@ pub fn spawn_tcp(addr: SocketAddr, queue_path: PathBuf, token: String) -> Result
 }
 
 #[cfg(unix)]
-pub fn spawn_unix(path: &Path, queue_path: PathBuf, token: String) -> Result<ServerHandle> {
+pub fn spawn_unix(
+    path: &Path,
+    queues: impl Into<DatabaseQueues>,
+    token: String,
+) -> Result<ServerHandle> {
+    let queues = queues.into();
     if path.exists() {
         std::fs::remove_file(path)
             .with_context(|| format!("failed to remove stale socket {}", path.display()))?;
@@ -107,7 +240,7 @@ pub fn spawn_unix(path: &Path, queue_path: PathBuf, token: String) -> Result<Ser
         let rt = Builder::new_multi_thread().enable_all().build()?;
         rt.block_on(async move {
             let listener = UnixListener::bind(&path_clone).context("failed to bind unix socket")?;
-            let service = WatcherService::new(queue_path);
+            let service = WatcherService::new(queues);
             let interceptor = AuthInterceptor::new(token);
             Server::builder()
                 .add_service(WatcherServer::with_interceptor(service, interceptor))
@@ -125,20 +258,157 @@ pub fn spawn_unix(path: &Path, queue_path: PathBuf, token: String) -> Result<Ser
     })
 }
 
+/// Full path of a named pipe: `sqlite-watcher` becomes
+/// `\\.\pipe\sqlite-watcher`; names already starting with `\\` are kept
+pub fn pipe_path(name: &str) -> String {
+    if name.starts_with(r"\\") {
+        name.to_string()
+    } else {
+        format!(r"\\.\pipe\{name}")
+    }
+}
+
+#[cfg(windows)]
+pub fn spawn_pipe(
+    name: &str,
+    queues: impl Into<DatabaseQueues>,
+    token: String,
+) -> Result<ServerHandle> {
+    let queues = queues.into();
+    let pipe_name = pipe_path(name);
+    let (tx, rx) = oneshot::channel();
+    let (ready_tx, ready_rx) = std::sync::mpsc::channel();
+    let thread = thread::spawn(move || -> Result<()> {
+        let rt = Builder::new_multi_thread().enable_all().build()?;
+        rt.block_on(async move {
+            // Creating the first instance claims the name, so report whether
+            // that worked before serving
+            let first = ServerOptions::new()
+                .first_pipe_instance(true)
+                .create(&pipe_name)
+                .with_context(|| format!("failed to create named pipe {pipe_name}"));
+            let first = match first {
+                Ok(first) => {
+                    let _ = ready_tx.send(Ok(()));
+                    first
+                }
+                Err(err) => {
+                    let _ = ready_tx.send(Err(anyhow::anyhow!("{err:#}")));
+                    return Err(err);
+                }
+            };
+            let (conn_tx, conn_rx) = tokio::sync::mpsc::channel(16);
+            tokio::spawn(accept_pipe_connections(pipe_name, first, conn_tx));
+            let service = WatcherService::new(queues);
+            let interceptor = AuthInterceptor::new(token);
+            Server::builder()
+                .add_service(WatcherServer::with_interceptor(service, interceptor))
+                .serve_with_incoming_shutdown(ReceiverStream::new(conn_rx), async move {
+                    let _ = rx.await;
+                })
+                .await
+                .context("grpc server exited")
+        })
+    });
+    ready_rx
+        .recv()
+        .context("named pipe server exited before starting")??;
+    Ok(ServerHandle::Pipe {
+        shutdown: Some(tx),
+        thread: Some(thread),
+    })
+}
+
+/// Hand each connected pipe instance to the server, creating the next
+/// instance before waiting on it so clients are never refused
+#[cfg(windows)]
+async fn accept_pipe_connections(
+    pipe_name: String,
+    mut server: NamedPipeServer,
+    connections: tokio::sync::mpsc::Sender<std::io::Result<PipeConnection>>,
+) {
+    loop {
+        if let Err(err) = server.connect().await {
+            let _ = connections.send(Err(err)).await;
+            return;
+        }
+        let connected = server;
+        server = match ServerOptions::new().create(&pipe_name) {
+            Ok(next) => next,
+            Err(err) => {
+                let _ = connections.send(Err(err)).await;
+                return;
+            }
+        };
+        if connections
+            .send(Ok(PipeConnection(connected)))
+            .await
+            .is_err()
+        {
+            return;
+        }
+    }
+}
+
+/// A connected named pipe, as the gRPC server's transport
+#[cfg(windows)]
+struct PipeConnection(NamedPipeServer);
+
+#[cfg(windows)]
+impl tonic::transport::server::Connected for PipeConnection {
+    type ConnectInfo = ();
+
+    fn connect_info(&self) -> Self::ConnectInfo {}
+}
+
+#[cfg(windows)]
+impl tokio::io::AsyncRead for PipeConnection {
+    fn poll_read(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &mut tokio::io::ReadBuf<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        std::pin::Pin::new(&mut self.0).poll_read(cx, buf)
+    }
+}
+
+#[cfg(windows)]
+impl tokio::io::AsyncWrite for PipeConnection {
+    fn poll_write(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &[u8],
+    ) -> std::task::Poll<std::io::Result<usize>> {
+        std::pin::Pin::new(&mut self.0).poll_write(cx, buf)
+    }
+
+    fn poll_flush(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        std::pin::Pin::new(&mut self.0).poll_flush(cx)
+    }
+
+    fn poll_shutdown(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        std::pin::Pin::new(&mut self.0).poll_shutdown(cx)
+    }
+}
+
 #[derive(Clone)]
 struct WatcherService {
-    queue_path: Arc<PathBuf>,
+    queues: DatabaseQueues,
 }
 
 impl WatcherService {
-    fn new(queue_path: PathBuf) -> Self {
-        Self {
-            queue_path: Arc::new(queue_path),
-        }
+    fn new(queues: DatabaseQueues) -> Self {
+        Self { queues }
     }
 
-    fn queue(&self) -> Result<ChangeQueue> {
-        ChangeQueue::open(&*self.queue_path)
+    fn queue(&self, database: &str) -> Result<ChangeQueue, QueueError> {
+        ChangeQueue::open(self.queues.get(database)?).map_err(QueueError::Open)
     }
 }
 
@@ -185,10 +455,17 @@ impl Watcher for WatcherService {
         &self,
         request: Request<ListChangesRequest>,
     ) -> Result<Response<ListChangesResponse>, Status> {
-        let limit = request.get_ref().limit.clamp(1, 10_000) as usize;
-        let queue = self.queue().map_err(internal_err)?;
-        let rows = queue.fetch_batch(limit).map_err(internal_err)?;
-        let changes = rows.into_iter().map(change_to_proto).collect();
+        let request = request.get_ref();
+        let limit = request.limit.clamp(1, 10_000) as usize;
+        let queue = self.queue(&request.database)?;
+        let rows = queue
+            .fetch_batch_after(request.after_change_id, limit)
+            .map_err(internal_err)?;
+        let database = self.queues.id(&request.database);
+        let changes = rows
+            .into_iter()
+            .map(|row| change_to_proto(row, &database))
+            .collect();
         Ok(Response::new(ListChangesResponse { changes }))
     }
 
@@ -197,8 +474,9 @@ impl Watcher for WatcherService {
         request: Request<AckChangesRequest>,
     ) -> Result<Response<AckChangesResponse>, Status> {
         let upto = request.get_ref().up_to_change_id;
-        let queue = self.queue().map_err(internal_err)?;
+        let queue = self.queue(&request.get_ref().database)?;
         let count = queue.ack_up_to(upto).map_err(internal_err)?;
+        queue.purge_acked().map_err(internal_err)?;
         Ok(Response::new(AckChangesResponse {
             acknowledged: count,
         }))
@@ -208,7 +486,7 @@ impl Watcher for WatcherService {
         &self,
         request: Request<GetStateRequest>,
     ) -> Result<Response<GetStateResponse>, Status> {
-        let queue = self.queue().map_err(internal_err)?;
+        let queue = self.queue(&request.get_ref().database)?;
         let state = queue
             .get_state(&request.get_ref().table_name)
             .map_err(internal_err)?;
@@ -237,7 +515,7 @@ impl Watcher for WatcherService {
         if payload.table_name.is_empty() {
             return Err(Status::invalid_argument("table_name is required"));
         }
-        let queue = self.queue().map_err(internal_err)?;
+        let queue = self.queue(&payload.database)?;
         let state = QueueState {
             table_name: payload.table_name,
             last_change_id: payload.last_change_id,
@@ -255,13 +533,56 @@ impl Watcher for WatcherService {
         queue.set_state(&state).map_err(internal_err)?;
         Ok(Response::new(SetStateResponse {}))
     }
+
+    async fn get_queue_stats(
+        &self,
+        request: Request<GetQueueStatsRequest>,
+    ) -> Result<Response<GetQueueStatsResponse>, Status> {
+        let queue = self.queue(&request.get_ref().database)?;
+        let stats = queue.stats().map_err(internal_err)?;
+        Ok(Response::new(GetQueueStatsResponse {
+            pending_changes: stats.pending_changes,
+            pending_bytes: stats.pending_bytes,
+            oldest_pending_age_seconds: stats
+                .oldest_pending_age
+                .map(|age| age.as_secs())
+                .unwrap_or_default(),
+            last_change_id: stats.last_change_id,
+            dropped_changes: stats.dropped_changes,
+        }))
+    }
+
+    async fn list_databases(
+        &self,
+        _: Request<ListDatabasesRequest>,
+    ) -> Result<Response<ListDatabasesResponse>, Status> {
+        let databases = self
+            .queues
+            .ids()
+            .into_iter()
+            .map(|id| Database { id })
+            .collect();
+        Ok(Response::new(ListDatabasesResponse { databases }))
+    }
+}
+
+impl From<ChangeOperation> for Operation {
+    fn from(value: ChangeOperation) -> Self {
+        match value {
+            ChangeOperation::Insert => Operation::Insert,
+            ChangeOperation::Update => Operation::Update,
+            ChangeOperation::Delete => Operation::Delete,
+        }
+    }
 }
 
-fn change_to_proto(row: crate::queue::ChangeRecord) -> Change {
+fn change_to_proto(row: crate::queue::ChangeRecord, database: &str) -> Change {
     Change {
+        database: database.to_string(),
         change_id: row.change_id,
         table_name: row.table_name,
         op: row.operation.as_str().to_string(),
+        operation: Operation::from(row.operation).into(),
         primary_key: row.primary_key,
         payload: row.payload.unwrap_or_default(),
         wal_frame: row.wal_frame.unwrap_or_default(),
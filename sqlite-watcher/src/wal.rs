@@ -1,4 +1,6 @@
 use std::ffi::OsString;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::Sender;
@@ -24,10 +26,47 @@ impl Default for WalWatcherConfig {
     }
 }
 
+/// WAL header magic, for little- and big-endian checksums
+const WAL_MAGIC: [u32; 2] = [0x377f_0682, 0x377f_0683];
+const WAL_HEADER_LEN: u64 = 32;
+const FRAME_HEADER_LEN: u64 = 24;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct WalEvent {
     pub bytes_added: u64,
     pub current_size: u64,
+    /// Committed frames since the previous event
+    pub frames_added: u64,
+    /// Where the WAL stands after this event
+    pub position: WalPosition,
+}
+
+/// A point in the WAL: one generation of the file (a checkpoint that resets
+/// the WAL starts a new one, with new salts) and the committed frames in it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WalPosition {
+    pub checkpoint_seq: u32,
+    pub salt1: u32,
+    pub salt2: u32,
+    /// Frames up to and including the last commit frame
+    pub frame: u64,
+}
+
+impl WalPosition {
+    fn same_generation(&self, other: &WalPosition) -> bool {
+        (self.checkpoint_seq, self.salt1, self.salt2)
+            == (other.checkpoint_seq, other.salt1, other.salt2)
+    }
+}
+
+impl std::fmt::Display for WalPosition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}:{:08x}{:08x}:{}",
+            self.checkpoint_seq, self.salt1, self.salt2, self.frame
+        )
+    }
 }
 
 pub struct WalWatcherHandle {
@@ -66,48 +105,60 @@ pub fn start_wal_watcher<P: AsRef<Path>>(
     let thread_stop = Arc::clone(&stop_flag);
 
     let handle = thread::spawn(move || {
-        let mut last_len = wal_file_size(&wal_path).unwrap_or(0);
+        // Changes already in the WAL when watching starts are not reported
+        let mut last = read_wal(&wal_path, None)
+            .ok()
+            .flatten()
+            .map(|state| state.position)
+            .unwrap_or_default();
         debug!(
             wal = %wal_path.display(),
-            last_len,
+            position = %last,
             "wal watcher started"
         );
         while !thread_stop.load(Ordering::SeqCst) {
-            match wal_file_size(&wal_path) {
-                Ok(len) => {
-                    if len < last_len {
+            match read_wal(&wal_path, Some(&last)) {
+                // No WAL, or one without a header yet: wait for writes. The
+                // next generation has new salts, so nothing is missed
+                Ok(None) => {}
+                Ok(Some(state)) => {
+                    let frames_added = if state.position.same_generation(&last) {
+                        state.position.frame - last.frame
+                    } else {
                         debug!(
                             wal = %wal_path.display(),
-                            prev = last_len,
-                            current = len,
-                            "wal truncated; resetting baseline"
+                            prev = %last,
+                            current = %state.position,
+                            "wal reset after checkpoint"
                         );
-                        last_len = len;
-                    } else if len > last_len {
-                        let delta = len - last_len;
-                        last_len = len;
-                        if delta >= min_event_bytes {
-                            let event = WalEvent {
-                                bytes_added: delta,
-                                current_size: len,
-                            };
-                            if sender.send(event).is_err() {
-                                debug!("wal watcher stopping because receiver closed");
-                                break;
-                            }
+                        if state.position.frame == 0 {
+                            last = state.position;
+                        }
+                        state.position.frame
+                    };
+                    let bytes_added = frames_added * state.frame_len;
+                    // Below the threshold the frames stay unreported until
+                    // more arrive, rather than being dropped
+                    if frames_added > 0 && bytes_added >= min_event_bytes {
+                        last = state.position;
+                        let event = WalEvent {
+                            bytes_added,
+                            current_size: state.len,
+                            frames_added,
+                            position: state.position,
+                        };
+                        if sender.send(event).is_err() {
+                            debug!("wal watcher stopping because receiver closed");
+                            break;
                         }
                     }
                 }
                 Err(err) => {
-                    if err.kind() == std::io::ErrorKind::NotFound {
-                        last_len = 0;
-                    } else {
-                        warn!(
-                            wal = %wal_path.display(),
-                            error = %err,
-                            "failed to read wal metadata"
-                        );
-                    }
+                    warn!(
+                        wal = %wal_path.display(),
+                        error = %err,
+                        "failed to read wal"
+                    );
                 }
             }
 
@@ -129,8 +180,72 @@ fn wal_file_path(db_path: &Path) -> PathBuf {
     PathBuf::from(os_string)
 }
 
-fn wal_file_size(path: &Path) -> std::io::Result<u64> {
-    std::fs::metadata(path).map(|m| m.len())
+struct WalState {
+    position: WalPosition,
+    frame_len: u64,
+    len: u64,
+}
+
+/// Read the WAL header and count committed frames, scanning on from `last`
+/// when the WAL is still in the same generation
+///
+/// Returns `None` when there is no WAL or it has no valid header yet.
+fn read_wal(path: &Path, last: Option<&WalPosition>) -> std::io::Result<Option<WalState>> {
+    let mut file = match File::open(path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err),
+    };
+    let len = file.metadata()?.len();
+    if len < WAL_HEADER_LEN {
+        return Ok(None);
+    }
+    let mut header = [0u8; WAL_HEADER_LEN as usize];
+    file.read_exact(&mut header)?;
+    if !WAL_MAGIC.contains(&be_u32(&header, 0)) {
+        return Ok(None);
+    }
+    let page_size = u64::from(be_u32(&header, 8));
+    let mut position = WalPosition {
+        checkpoint_seq: be_u32(&header, 12),
+        salt1: be_u32(&header, 16),
+        salt2: be_u32(&header, 20),
+        frame: 0,
+    };
+    if let Some(last) = last.filter(|last| last.same_generation(&position)) {
+        position.frame = last.frame;
+    }
+
+    // Frames left from an earlier generation carry its salts; a frame is
+    // committed once a later commit frame (non-zero database size) follows
+    let frame_len = FRAME_HEADER_LEN + page_size;
+    let mut frame = position.frame;
+    loop {
+        let offset = WAL_HEADER_LEN + frame * frame_len;
+        if offset + frame_len > len {
+            break;
+        }
+        let mut frame_header = [0u8; FRAME_HEADER_LEN as usize];
+        file.seek(SeekFrom::Start(offset))?;
+        file.read_exact(&mut frame_header)?;
+        if be_u32(&frame_header, 8) != position.salt1 || be_u32(&frame_header, 12) != position.salt2
+        {
+            break;
+        }
+        frame += 1;
+        if be_u32(&frame_header, 4) != 0 {
+            position.frame = frame;
+        }
+    }
+    Ok(Some(WalState {
+        position,
+        frame_len,
+        len,
+    }))
+}
+
+fn be_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes(bytes[offset..offset + 4].try_into().unwrap())
 }
 
 #[cfg(test)]
@@ -214,7 +329,7 @@ mod tests {
                 .unwrap();
         }
 
-        rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        let before = rx.recv_timeout(Duration::from_secs(5)).unwrap();
 
         writer
             .execute_batch("PRAGMA wal_checkpoint(TRUNCATE);")
@@ -231,6 +346,62 @@ mod tests {
             }
         }
 
+        // Writes after the truncation start a new WAL generation
+        writer
+            .execute("INSERT INTO stuff(value) VALUES ('after')", [])
+            .unwrap();
+        let after = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert!(after.frames_added > 0);
+        assert!(!after.position.same_generation(&before.position));
+
+        drop(handle);
+    }
+
+    #[test]
+    fn reports_growth_when_checkpoint_reuses_wal() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("restart.sqlite");
+        let writer = Connection::open(&db_path).unwrap();
+        writer.pragma_update(None, "journal_mode", &"wal").unwrap();
+        writer
+            .pragma_update(None, "wal_autocheckpoint", &0i64)
+            .unwrap();
+        writer
+            .execute("CREATE TABLE stuff(id INTEGER PRIMARY KEY, value TEXT)", [])
+            .unwrap();
+        for i in 0..20 {
+            writer
+                .execute("INSERT INTO stuff(value) VALUES (?1)", [format!("row-{i}")])
+                .unwrap();
+        }
+
+        let (tx, rx) = channel();
+        let handle = start_wal_watcher(
+            &db_path,
+            WalWatcherConfig {
+                poll_interval: Duration::from_millis(25),
+                min_event_bytes: 1,
+            },
+            tx,
+        )
+        .unwrap();
+        thread::sleep(Duration::from_millis(100));
+
+        // After a full checkpoint the next write rewinds the WAL and
+        // overwrites it from the start, so its size does not change
+        writer
+            .execute_batch("PRAGMA wal_checkpoint(RESTART);")
+            .unwrap();
+        let size_before = std::fs::metadata(wal_file_path(&db_path)).unwrap().len();
+        writer
+            .execute("INSERT INTO stuff(value) VALUES ('after')", [])
+            .unwrap();
+        let event = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(event.current_size, size_before);
+        assert_eq!(event.frames_added, event.position.frame);
+        assert!(event.frames_added > 0);
+        assert!(rx.recv_timeout(Duration::from_millis(200)).is_err());
+
         drop(handle);
     }
 }
@@ -1,4 +1,8 @@
-use sqlite_watcher::queue::{ChangeOperation, ChangeQueue, NewChange, QueueState};
+use std::time::Duration;
+
+use sqlite_watcher::queue::{
+    ChangeOperation, ChangeQueue, CompactionStats, NewChange, QueueState, RetentionPolicy,
+};
 use tempfile::tempdir;
 
 fn new_change(table: &str, id: &str, op: ChangeOperation) -> NewChange {
@@ -26,6 +30,10 @@ fn durable_enqueue_and_ack_flow() {
 
     let batch = queue.fetch_batch(10).unwrap();
     assert_eq!(batch.len(), 3);
+    // Consumers can fetch past changes they have not acknowledged yet
+    let ahead = queue.fetch_batch_after(ids[0], 10).unwrap();
+    assert_eq!(ahead.len(), 2);
+    assert_eq!(ahead[0].change_id, ids[1]);
 
     queue.ack_up_to(ids[1]).unwrap();
     queue.purge_acked().unwrap();
@@ -57,3 +65,51 @@ fn state_round_trip() {
     let fetched = queue.get_state("prices").unwrap().unwrap();
     assert_eq!(fetched, state);
 }
+
+#[test]
+fn compaction_applies_retention() {
+    let dir = tempdir().unwrap();
+    let queue_path = dir.path().join("changes.db");
+    let queue = ChangeQueue::open(&queue_path).unwrap();
+    let mut ids = Vec::new();
+    for i in 0..6 {
+        let change = new_change("vaults", &format!("pk-{i}"), ChangeOperation::Insert);
+        ids.push(queue.enqueue(&change).unwrap());
+    }
+    let stats = queue.stats().unwrap();
+    assert_eq!(stats.pending_changes, 6);
+    assert_eq!(stats.last_change_id, ids[5]);
+    let change_bytes = stats.pending_bytes / 6;
+
+    // Acked changes are purged; the oldest pending one is past the max age
+    assert_eq!(queue.ack_up_to(ids[0]).unwrap(), 1);
+    rusqlite::Connection::open(&queue_path)
+        .unwrap()
+        .execute(
+            "UPDATE changes SET created_at = datetime('now', '-2 hours') WHERE change_id = ?1",
+            [ids[1]],
+        )
+        .unwrap();
+    let policy = RetentionPolicy {
+        max_age: Some(Duration::from_secs(3600)),
+        max_bytes: Some(change_bytes * 3),
+    };
+    let compacted = queue.compact(&policy).unwrap();
+    assert_eq!(compacted.purged, 1);
+    assert_eq!(compacted.expired, 1);
+    assert_eq!(compacted.trimmed, 1);
+
+    let remaining: Vec<i64> = queue
+        .fetch_batch(10)
+        .unwrap()
+        .iter()
+        .map(|change| change.change_id)
+        .collect();
+    assert_eq!(remaining, ids[3..]);
+    let stats = queue.stats().unwrap();
+    assert_eq!(stats.pending_changes, 3);
+    assert_eq!(stats.dropped_changes, 2);
+    assert!(stats.oldest_pending_age.unwrap() < Duration::from_secs(60));
+
+    assert_eq!(queue.compact(&policy).unwrap(), CompactionStats::default());
+}
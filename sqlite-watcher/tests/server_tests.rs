@@ -2,9 +2,12 @@ use std::net::SocketAddr;
 use std::time::Duration;
 
 use sqlite_watcher::queue::{ChangeOperation, ChangeQueue, NewChange};
-use sqlite_watcher::server::spawn_tcp;
+use sqlite_watcher::server::{pipe_path, spawn_tcp, DatabaseQueues};
 use sqlite_watcher::watcher_proto::watcher_client::WatcherClient;
-use sqlite_watcher::watcher_proto::{AckChangesRequest, HealthCheckRequest, ListChangesRequest};
+use sqlite_watcher::watcher_proto::{
+    AckChangesRequest, GetQueueStatsRequest, HealthCheckRequest, ListChangesRequest,
+    ListDatabasesRequest, Operation,
+};
 use tempfile::tempdir;
 use tokio::time::sleep;
 use tonic::metadata::MetadataValue;
@@ -49,10 +52,21 @@ async fn tcp_server_handles_health_and_list() {
         .insert("authorization", header.clone());
     client.health_check(health_req).await.unwrap();
 
-    let mut list_req = tonic::Request::new(ListChangesRequest { limit: 10 });
+    let mut list_req = tonic::Request::new(ListChangesRequest {
+        limit: 10,
+        after_change_id: 0,
+        database: String::new(),
+    });
     list_req.metadata_mut().insert("authorization", header);
-    let resp = client.list_changes(list_req).await.unwrap();
-    assert_eq!(resp.into_inner().changes.len(), 2);
+    let changes = client
+        .list_changes(list_req)
+        .await
+        .unwrap()
+        .into_inner()
+        .changes;
+    assert_eq!(changes.len(), 2);
+    assert_eq!(changes[0].operation(), Operation::Insert);
+    assert_eq!(changes[0].op, "insert");
 }
 
 #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
@@ -71,7 +85,11 @@ async fn unauthenticated_requests_fail() {
         .unwrap();
     let mut client = WatcherClient::new(channel);
 
-    let request = tonic::Request::new(ListChangesRequest { limit: 1 });
+    let request = tonic::Request::new(ListChangesRequest {
+        limit: 1,
+        after_change_id: 0,
+        database: String::new(),
+    });
     let err = client.list_changes(request).await.unwrap_err();
     assert_eq!(err.code(), tonic::Code::Unauthenticated);
 }
@@ -83,7 +101,7 @@ async fn ack_changes_advances_queue() {
     seed_queue(queue_path.to_str().unwrap());
     let addr: SocketAddr = "127.0.0.1:56062".parse().unwrap();
     let token = "secret".to_string();
-    let _handle = spawn_tcp(addr, queue_path, token.clone()).unwrap();
+    let _handle = spawn_tcp(addr, queue_path.clone(), token.clone()).unwrap();
     sleep(Duration::from_millis(200)).await;
 
     let channel = tonic::transport::Channel::from_shared(format!("http://{}", addr))
@@ -94,7 +112,11 @@ async fn ack_changes_advances_queue() {
     let mut client = WatcherClient::new(channel);
     let header = MetadataValue::try_from(format!("Bearer {}", token)).unwrap();
 
-    let mut req = tonic::Request::new(ListChangesRequest { limit: 10 });
+    let mut req = tonic::Request::new(ListChangesRequest {
+        limit: 10,
+        after_change_id: 0,
+        database: String::new(),
+    });
     req.metadata_mut().insert("authorization", header.clone());
     let resp = client.list_changes(req).await.unwrap().into_inner();
     assert_eq!(resp.changes.len(), 2);
@@ -102,7 +124,107 @@ async fn ack_changes_advances_queue() {
 
     let mut ack_req = tonic::Request::new(AckChangesRequest {
         up_to_change_id: highest,
+        database: String::new(),
+    });
+    ack_req
+        .metadata_mut()
+        .insert("authorization", header.clone());
+    let acked = client.ack_changes(ack_req).await.unwrap().into_inner();
+    assert_eq!(acked.acknowledged, 2);
+
+    // Acknowledged changes are gone from the queue
+    let mut stats_req = tonic::Request::new(GetQueueStatsRequest {
+        database: String::new(),
     });
-    ack_req.metadata_mut().insert("authorization", header);
-    client.ack_changes(ack_req).await.unwrap();
+    stats_req.metadata_mut().insert("authorization", header);
+    let stats = client
+        .get_queue_stats(stats_req)
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(stats.pending_changes, 0);
+    assert_eq!(stats.oldest_pending_age_seconds, 0);
+    assert_eq!(stats.last_change_id, highest);
+    assert_eq!(stats.dropped_changes, 0);
+    let queue = ChangeQueue::open(&queue_path).unwrap();
+    assert_eq!(queue.purge_acked().unwrap(), 0);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn changes_are_listed_per_database() {
+    let dir = tempdir().unwrap();
+    let orders = dir.path().join("changes-orders.db");
+    let users = dir.path().join("changes-users.db");
+    seed_queue(orders.to_str().unwrap());
+    ChangeQueue::open(&users).unwrap();
+    let queues = DatabaseQueues::new();
+    queues.insert("orders", orders);
+    queues.insert("users", users);
+
+    let addr: SocketAddr = "127.0.0.1:56063".parse().unwrap();
+    let token = "secret".to_string();
+    let _handle = spawn_tcp(addr, queues.clone(), token.clone()).unwrap();
+    sleep(Duration::from_millis(200)).await;
+
+    let channel = tonic::transport::Channel::from_shared(format!("http://{}", addr))
+        .unwrap()
+        .connect()
+        .await
+        .unwrap();
+    let mut client = WatcherClient::new(channel);
+    let header = MetadataValue::try_from(format!("Bearer {}", token)).unwrap();
+    let list = |database: &str| {
+        let mut req = tonic::Request::new(ListChangesRequest {
+            limit: 10,
+            after_change_id: 0,
+            database: database.to_string(),
+        });
+        req.metadata_mut().insert("authorization", header.clone());
+        req
+    };
+
+    let mut databases_req = tonic::Request::new(ListDatabasesRequest {});
+    databases_req
+        .metadata_mut()
+        .insert("authorization", header.clone());
+    let databases = client
+        .list_databases(databases_req)
+        .await
+        .unwrap()
+        .into_inner()
+        .databases;
+    let ids: Vec<&str> = databases.iter().map(|db| db.id.as_str()).collect();
+    assert_eq!(ids, ["orders", "users"]);
+
+    let changes = client
+        .list_changes(list("orders"))
+        .await
+        .unwrap()
+        .into_inner()
+        .changes;
+    assert_eq!(changes.len(), 2);
+    assert!(changes.iter().all(|change| change.database == "orders"));
+    let changes = client.list_changes(list("users")).await.unwrap();
+    assert!(changes.into_inner().changes.is_empty());
+
+    let err = client.list_changes(list("")).await.unwrap_err();
+    assert_eq!(err.code(), tonic::Code::InvalidArgument);
+    let err = client.list_changes(list("missing")).await.unwrap_err();
+    assert_eq!(err.code(), tonic::Code::NotFound);
+
+    // Databases retired while the server runs are no longer served
+    queues.remove("users");
+    let err = client.list_changes(list("users")).await.unwrap_err();
+    assert_eq!(err.code(), tonic::Code::NotFound);
+    let changes = client.list_changes(list("")).await.unwrap();
+    assert_eq!(changes.into_inner().changes.len(), 2);
+}
+
+#[test]
+fn pipe_names_expand_to_pipe_paths() {
+    assert_eq!(pipe_path("sqlite-watcher"), r"\\.\pipe\sqlite-watcher");
+    assert_eq!(
+        pipe_path(r"\\server\pipe\watcher"),
+        r"\\server\pipe\watcher"
+    );
 }
@@ -0,0 +1,276 @@
+// ABOUTME: Off-platform backups of a target's databases stored in S3
+// ABOUTME: Describes each backup in a manifest and picks backups that fall out of retention
+
+pub mod s3;
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use s3::S3Object;
+
+const MANIFEST_VERSION: u32 = 1;
+
+/// Object written last, marking a backup complete
+pub const MANIFEST_FILE: &str = "backup.json";
+
+/// Schema dump of each database, next to its data manifest and data dump
+pub const SCHEMA_FILE: &str = "schema.sql";
+
+const ID_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+/// ID of a backup started at `at`; IDs sort in the order backups were taken
+pub fn backup_id(at: DateTime<Utc>) -> String {
+    at.format(ID_FORMAT).to_string()
+}
+
+fn parse_backup_id(id: &str) -> Option<DateTime<Utc>> {
+    NaiveDateTime::parse_from_str(id, ID_FORMAT)
+        .ok()
+        .map(|at| at.and_utc())
+}
+
+/// Contents of one backup
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupManifest {
+    version: u32,
+    pub id: String,
+    pub created_at: DateTime<Utc>,
+    /// Host and port of the server that was backed up
+    pub server: String,
+    pub databases: Vec<BackupDatabase>,
+}
+
+impl BackupManifest {
+    pub fn new(id: String, created_at: DateTime<Utc>, server: String) -> Self {
+        Self {
+            version: MANIFEST_VERSION,
+            id,
+            created_at,
+            server,
+            databases: Vec::new(),
+        }
+    }
+
+    pub fn parse(contents: &[u8]) -> Result<Self> {
+        let manifest: Self =
+            serde_json::from_slice(contents).context("Failed to parse backup manifest")?;
+        if manifest.version != MANIFEST_VERSION {
+            bail!(
+                "Backup {} has manifest version {}; this release reads version {}",
+                manifest.id,
+                manifest.version,
+                MANIFEST_VERSION
+            );
+        }
+        Ok(manifest)
+    }
+
+    pub fn total_size(&self) -> u64 {
+        self.databases
+            .iter()
+            .flat_map(|database| &database.files)
+            .map(|file| file.size)
+            .sum()
+    }
+}
+
+/// The files of one database, under a directory named after it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupDatabase {
+    pub name: String,
+    pub files: Vec<BackupFile>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackupFile {
+    /// Path relative to the backup, e.g. `app/data/3347.dat.gz`
+    pub path: String,
+    pub size: u64,
+    pub sha256: String,
+}
+
+/// A backup found under a prefix
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredBackup {
+    pub id: String,
+    pub created_at: DateTime<Utc>,
+    /// Whether its manifest was written; a backup without one was interrupted
+    pub complete: bool,
+    pub keys: Vec<String>,
+}
+
+/// Group the objects under `prefix` by the backup they belong to
+///
+/// Objects outside a directory named like a backup ID are left out. Backups
+/// are returned newest first.
+pub fn stored_backups(prefix: &str, objects: &[S3Object]) -> Vec<StoredBackup> {
+    let mut backups: BTreeMap<String, StoredBackup> = BTreeMap::new();
+    let root = if prefix.is_empty() {
+        String::new()
+    } else {
+        format!("{}/", prefix)
+    };
+    for object in objects {
+        let Some(rest) = object.key.strip_prefix(&root) else {
+            continue;
+        };
+        let Some((id, path)) = rest.split_once('/') else {
+            continue;
+        };
+        let Some(created_at) = parse_backup_id(id) else {
+            continue;
+        };
+        let backup = backups
+            .entry(id.to_string())
+            .or_insert_with(|| StoredBackup {
+                id: id.to_string(),
+                created_at,
+                complete: false,
+                keys: Vec::new(),
+            });
+        backup.complete |= path == MANIFEST_FILE;
+        backup.keys.push(object.key.clone());
+    }
+    backups.into_values().rev().collect()
+}
+
+/// How many backups to keep under a prefix
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Retention {
+    /// Keep only this many of the newest complete backups
+    pub keep: Option<usize>,
+    /// Delete complete backups older than this many days
+    pub keep_days: Option<u64>,
+}
+
+impl Retention {
+    pub fn is_empty(&self) -> bool {
+        self.keep.is_none() && self.keep_days.is_none()
+    }
+
+    /// Backups to delete, out of `backups` sorted newest first
+    ///
+    /// The newest complete backup is always kept. Incomplete backups older
+    /// than it are left over from failed runs and are deleted too; newer ones
+    /// may still be uploading.
+    pub fn expired<'a>(
+        &self,
+        backups: &'a [StoredBackup],
+        now: DateTime<Utc>,
+    ) -> Vec<&'a StoredBackup> {
+        let Some(newest) = backups.iter().find(|backup| backup.complete) else {
+            return Vec::new();
+        };
+        let cutoff = self
+            .keep_days
+            .map(|days| now - chrono::Duration::days(days.min(36_500) as i64));
+        let mut complete = 0;
+        backups
+            .iter()
+            .filter(|backup| {
+                if !backup.complete {
+                    return backup.id < newest.id;
+                }
+                complete += 1;
+                if backup.id == newest.id || self.is_empty() {
+                    return false;
+                }
+                self.keep.is_some_and(|keep| complete > keep.max(1))
+                    || cutoff.is_some_and(|cutoff| backup.created_at < cutoff)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn object(key: &str) -> S3Object {
+        S3Object {
+            key: key.to_string(),
+            size: 1,
+        }
+    }
+
+    #[test]
+    fn test_stored_backups() {
+        let objects = vec![
+            object("prod/20261001T000000Z/app/schema.sql"),
+            object("prod/20261001T000000Z/backup.json"),
+            object("prod/20261015T000000Z/app/schema.sql"),
+            object("prod/20261016T000000Z/app/schema.sql"),
+            object("prod/20261016T000000Z/backup.json"),
+            object("prod/notes.txt"),
+            object("prod/latest/backup.json"),
+        ];
+        let backups = stored_backups("prod", &objects);
+        let ids: Vec<(&str, bool)> = backups
+            .iter()
+            .map(|backup| (backup.id.as_str(), backup.complete))
+            .collect();
+        assert_eq!(
+            ids,
+            vec![
+                ("20261016T000000Z", true),
+                ("20261015T000000Z", false),
+                ("20261001T000000Z", true),
+            ]
+        );
+        assert_eq!(backups[2].keys.len(), 2);
+    }
+
+    #[test]
+    fn test_retention() {
+        let ids = [
+            ("20261020T000000Z", false),
+            ("20261016T000000Z", true),
+            ("20261015T000000Z", false),
+            ("20261010T000000Z", true),
+            ("20261001T000000Z", true),
+        ];
+        let backups: Vec<StoredBackup> = ids
+            .iter()
+            .map(|(id, complete)| StoredBackup {
+                id: id.to_string(),
+                created_at: parse_backup_id(id).unwrap(),
+                complete: *complete,
+                keys: Vec::new(),
+            })
+            .collect();
+        let now = parse_backup_id("20261017T000000Z").unwrap();
+        let expired = |retention: Retention| -> Vec<&str> {
+            retention
+                .expired(&backups, now)
+                .into_iter()
+                .map(|backup| backup.id.as_str())
+                .collect()
+        };
+
+        assert_eq!(expired(Retention::default()), vec!["20261015T000000Z"]);
+        assert_eq!(
+            expired(Retention {
+                keep: Some(2),
+                keep_days: None
+            }),
+            vec!["20261015T000000Z", "20261001T000000Z"]
+        );
+        assert_eq!(
+            expired(Retention {
+                keep: None,
+                keep_days: Some(10)
+            }),
+            vec!["20261015T000000Z", "20261001T000000Z"]
+        );
+        // The newest complete backup survives even when it is too old
+        assert_eq!(
+            expired(Retention {
+                keep: Some(0),
+                keep_days: Some(0)
+            }),
+            vec!["20261015T000000Z", "20261010T000000Z", "20261001T000000Z"]
+        );
+    }
+}
@@ -1,14 +1,15 @@
 // ABOUTME: Persistent checkpoint tracking for long-running operations
-// ABOUTME: Provides init command resume support with hashed identities
+// ABOUTME: Provides init and verify resume support with hashed identities
 
 use anyhow::{bail, Context, Result};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fs;
 use std::path::{Path, PathBuf};
 
 const INIT_CHECKPOINT_VERSION: u32 = 1;
+const VERIFY_CHECKPOINT_VERSION: u32 = 1;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct InitCheckpointMetadata {
@@ -84,23 +85,7 @@ impl InitCheckpoint {
     }
 
     pub fn save(&self, path: &Path) -> Result<()> {
-        if let Some(parent) = path.parent() {
-            fs::create_dir_all(parent).with_context(|| {
-                format!("Failed to create checkpoint directory {}", parent.display())
-            })?;
-        }
-
-        let parent = path.parent().unwrap_or_else(|| Path::new("."));
-        let mut tmp = tempfile::NamedTempFile::new_in(parent)
-            .with_context(|| format!("Failed to create temp checkpoint in {}", parent.display()))?;
-
-        serde_json::to_writer_pretty(tmp.as_file_mut(), &self.data)
-            .with_context(|| format!("Failed to serialize checkpoint at {}", path.display()))?;
-
-        tmp.persist(path)
-            .with_context(|| format!("Failed to persist checkpoint at {}", path.display()))?;
-
-        Ok(())
+        write_checkpoint(path, &self.data)
     }
 
     pub fn databases(&self) -> &[String] {
@@ -144,6 +129,107 @@ impl InitCheckpoint {
     }
 }
 
+/// A table that `verify` found identical on source and target
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct VerifiedTable {
+    pub row_count: i64,
+    pub checksum: String,
+    pub verified_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Tables a `verify` run has already found matching, so a rerun can skip them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerifyCheckpoint {
+    version: u32,
+    source_hash: String,
+    target_hash: String,
+    filter_hash: String,
+    /// Matched tables per database, keyed by `schema.table`
+    databases: BTreeMap<String, BTreeMap<String, VerifiedTable>>,
+}
+
+impl VerifyCheckpoint {
+    pub fn new(source_url: &str, target_url: &str, filter_hash: String) -> Self {
+        Self {
+            version: VERIFY_CHECKPOINT_VERSION,
+            source_hash: hash_string(source_url),
+            target_hash: hash_string(target_url),
+            filter_hash,
+            databases: BTreeMap::new(),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read checkpoint at {}", path.display()))?;
+        let checkpoint: Self = serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse checkpoint JSON at {}", path.display()))?;
+
+        if checkpoint.version != VERIFY_CHECKPOINT_VERSION {
+            bail!(
+                "Verify checkpoint version mismatch (found {}, expected {}). Run without --resume to start fresh.",
+                checkpoint.version,
+                VERIFY_CHECKPOINT_VERSION
+            );
+        }
+
+        Ok(Some(checkpoint))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        write_checkpoint(path, self)
+    }
+
+    /// Whether this checkpoint was written for the same source, target, and filters
+    pub fn is_for(&self, source_url: &str, target_url: &str, filter_hash: &str) -> bool {
+        self.source_hash == hash_string(source_url)
+            && self.target_hash == hash_string(target_url)
+            && self.filter_hash == filter_hash
+    }
+
+    pub fn verified(&self, database: &str, schema: &str, table: &str) -> Option<&VerifiedTable> {
+        self.databases
+            .get(database)?
+            .get(&format!("{}.{}", schema, table))
+    }
+
+    pub fn record_match(
+        &mut self,
+        database: &str,
+        schema: &str,
+        table: &str,
+        row_count: i64,
+        checksum: &str,
+    ) {
+        self.databases
+            .entry(database.to_string())
+            .or_default()
+            .insert(
+                format!("{}.{}", schema, table),
+                VerifiedTable {
+                    row_count,
+                    checksum: checksum.to_string(),
+                    verified_at: chrono::Utc::now(),
+                },
+            );
+    }
+
+    /// Drop a table's earlier match, e.g. after it differed on a later check
+    pub fn forget(&mut self, database: &str, schema: &str, table: &str) {
+        if let Some(tables) = self.databases.get_mut(database) {
+            tables.remove(&format!("{}.{}", schema, table));
+        }
+    }
+
+    pub fn verified_count(&self) -> usize {
+        self.databases.values().map(BTreeMap::len).sum()
+    }
+}
+
 pub fn checkpoint_path(source_url: &str, target_url: &str) -> Result<PathBuf> {
     let base = std::env::temp_dir().join("postgres-seren-replicator-checkpoints");
     fs::create_dir_all(&base).with_context(|| {
@@ -153,21 +239,89 @@ pub fn checkpoint_path(source_url: &str, target_url: &str) -> Result<PathBuf> {
         )
     })?;
 
+    Ok(base.join(format!(
+        "init-{}.json",
+        url_pair_digest(source_url, target_url)
+    )))
+}
+
+/// Where `verify` keeps its progress for a source and target, in the state directory
+pub fn verify_checkpoint_path(source_url: &str, target_url: &str) -> Result<PathBuf> {
+    Ok(crate::state::state_dir()?.join(format!(
+        "verify-{}.json",
+        url_pair_digest(source_url, target_url)
+    )))
+}
+
+fn url_pair_digest(source_url: &str, target_url: &str) -> String {
     let mut hasher = Sha256::new();
     hasher.update(source_url.as_bytes());
     hasher.update(b"::");
     hasher.update(target_url.as_bytes());
     let digest = format!("{:x}", hasher.finalize());
-    let short = &digest[..16.min(digest.len())];
+    digest[..16.min(digest.len())].to_string()
+}
+
+/// Write a checkpoint through a temp file, so a crash never leaves it half written
+fn write_checkpoint<T: Serialize>(path: &Path, value: &T) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).with_context(|| {
+            format!("Failed to create checkpoint directory {}", parent.display())
+        })?;
+    }
+
+    let parent = path.parent().unwrap_or_else(|| Path::new("."));
+    let mut tmp = tempfile::NamedTempFile::new_in(parent)
+        .with_context(|| format!("Failed to create temp checkpoint in {}", parent.display()))?;
+
+    serde_json::to_writer_pretty(tmp.as_file_mut(), value)
+        .with_context(|| format!("Failed to serialize checkpoint at {}", path.display()))?;
 
-    Ok(base.join(format!("init-{}.json", short)))
+    tmp.persist(path)
+        .with_context(|| format!("Failed to persist checkpoint at {}", path.display()))?;
+
+    Ok(())
+}
+
+fn data_artifacts_root(checkpoint_path: &Path, work_dir: Option<&Path>) -> PathBuf {
+    let mut name = checkpoint_path
+        .file_stem()
+        .unwrap_or_default()
+        .to_os_string();
+    name.push("-data");
+    match work_dir {
+        Some(dir) => dir.join(name),
+        None => checkpoint_path.with_file_name(name),
+    }
 }
 
-pub fn remove_checkpoint(path: &Path) -> Result<()> {
+/// Directory that keeps a database's data dump and manifest
+///
+/// This is next to the checkpoint, or in `work_dir` when one is given.
+pub fn data_artifacts_dir(
+    checkpoint_path: &Path,
+    work_dir: Option<&Path>,
+    database: &str,
+) -> PathBuf {
+    data_artifacts_root(checkpoint_path, work_dir).join(database)
+}
+
+/// Remove a checkpoint and any data artifacts kept with it or in `work_dir`
+pub fn remove_checkpoint(path: &Path, work_dir: Option<&Path>) -> Result<()> {
     if path.exists() {
         fs::remove_file(path)
             .with_context(|| format!("Failed to remove checkpoint at {}", path.display()))?;
     }
+    for artifacts in [
+        data_artifacts_root(path, None),
+        data_artifacts_root(path, work_dir),
+    ] {
+        if artifacts.exists() {
+            fs::remove_dir_all(&artifacts).with_context(|| {
+                format!("Failed to remove data artifacts at {}", artifacts.display())
+            })?;
+        }
+    }
     Ok(())
 }
 
@@ -205,6 +359,50 @@ mod tests {
         assert!(!loaded.is_completed("db2"));
     }
 
+    #[test]
+    fn removing_checkpoint_removes_data_artifacts() {
+        let dir = tempdir().unwrap();
+        let work = tempdir().unwrap();
+        let path = dir.path().join("init-abc.json");
+        let artifacts = data_artifacts_dir(&path, None, "app");
+        assert_eq!(artifacts, dir.path().join("init-abc-data").join("app"));
+        let moved = data_artifacts_dir(&path, Some(work.path()), "app");
+        assert_eq!(moved, work.path().join("init-abc-data").join("app"));
+
+        let other = dir.path().join("init-def.json");
+        fs::write(&path, "{}").unwrap();
+        fs::write(&other, "{}").unwrap();
+        fs::create_dir_all(&artifacts).unwrap();
+        fs::create_dir_all(&moved).unwrap();
+        remove_checkpoint(&path, Some(work.path())).unwrap();
+        assert!(!path.exists());
+        assert!(!dir.path().join("init-abc-data").exists());
+        assert!(!work.path().join("init-abc-data").exists());
+        assert!(work.path().exists());
+        assert!(other.exists());
+    }
+
+    #[test]
+    fn verify_checkpoint_roundtrip() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("verify.json");
+        let mut checkpoint = VerifyCheckpoint::new("src", "tgt", "filter".into());
+        checkpoint.record_match("app", "public", "users", 10, "abc");
+        checkpoint.record_match("app", "public", "orders", 5, "def");
+        checkpoint.forget("app", "public", "orders");
+        checkpoint.save(&path).unwrap();
+
+        let loaded = VerifyCheckpoint::load(&path).unwrap().unwrap();
+        assert!(loaded.is_for("src", "tgt", "filter"));
+        assert!(!loaded.is_for("src", "tgt", "other"));
+        assert!(!loaded.is_for("src", "other", "filter"));
+        assert_eq!(loaded.verified_count(), 1);
+        let users = loaded.verified("app", "public", "users").unwrap();
+        assert_eq!((users.row_count, users.checksum.as_str()), (10, "abc"));
+        assert!(loaded.verified("app", "public", "orders").is_none());
+        assert!(loaded.verified("other", "public", "users").is_none());
+    }
+
     #[test]
     fn checkpoint_path_is_deterministic() {
         let path_a = checkpoint_path("postgres://src/db", "postgres://tgt/db").unwrap();
@@ -0,0 +1,432 @@
+// ABOUTME: Backup and restore commands - copy a target's databases to S3 and back
+// ABOUTME: Uses init's directory-format dump so each table is its own compressed file
+
+use anyhow::{bail, Context, Result};
+use bytes::Bytes;
+use chrono::Utc;
+use clap::Args;
+use std::path::{Path, PathBuf};
+
+use crate::backup::s3::{S3Client, S3Location};
+use crate::backup::{
+    self, BackupDatabase, BackupFile, BackupManifest, Retention, StoredBackup, SCHEMA_FILE,
+};
+use crate::filters::ReplicationFilter;
+use crate::migration;
+use crate::postgres::connect_with_retry;
+use crate::utils::replace_database_in_connection_string;
+
+#[derive(Args)]
+pub struct BackupArgs {
+    /// Database server to back up; defaults to the saved target
+    #[arg(long)]
+    pub target: Option<String>,
+    /// Bucket and prefix to store backups under (s3://bucket/prefix)
+    #[arg(long = "to", value_name = "S3_URL")]
+    pub to: String,
+    /// Databases to back up (comma-separated); defaults to every database
+    #[arg(long, value_delimiter = ',')]
+    pub include_databases: Option<Vec<String>>,
+    /// Databases to leave out (comma-separated)
+    #[arg(long, value_delimiter = ',')]
+    pub exclude_databases: Option<Vec<String>>,
+    /// Keep only the newest N backups under the prefix
+    #[arg(long, value_name = "N")]
+    pub keep: Option<usize>,
+    /// Delete backups older than this many days (the newest is always kept)
+    #[arg(long, value_name = "DAYS")]
+    pub keep_days: Option<u64>,
+    /// S3-compatible endpoint such as http://localhost:9000; defaults to AWS S3
+    #[arg(long, env = "AWS_ENDPOINT_URL_S3")]
+    pub endpoint: Option<String>,
+    /// Directory for dump files before upload instead of the system temp directory
+    #[arg(long, value_name = "DIR")]
+    pub work_dir: Option<PathBuf>,
+}
+
+#[derive(Args)]
+pub struct RestoreArgs {
+    /// Database server to restore into; defaults to the saved target
+    #[arg(long)]
+    pub target: Option<String>,
+    /// Prefix the backups were written to (s3://bucket/prefix)
+    #[arg(long = "from", value_name = "S3_URL")]
+    pub from: String,
+    /// Backup to restore; defaults to the newest complete backup
+    #[arg(long, value_name = "ID")]
+    pub backup_id: Option<String>,
+    /// Databases to restore (comma-separated); defaults to every database in the backup
+    #[arg(long, value_delimiter = ',')]
+    pub include_databases: Option<Vec<String>>,
+    /// Databases to leave out (comma-separated)
+    #[arg(long, value_delimiter = ',')]
+    pub exclude_databases: Option<Vec<String>>,
+    /// Drop target databases that already hold tables instead of stopping
+    #[arg(long)]
+    pub drop_existing: bool,
+    /// Tables to restore at once (default 4)
+    #[arg(long)]
+    pub jobs: Option<usize>,
+    /// S3-compatible endpoint such as http://localhost:9000; defaults to AWS S3
+    #[arg(long, env = "AWS_ENDPOINT_URL_S3")]
+    pub endpoint: Option<String>,
+    /// Directory for downloaded dump files instead of the system temp directory
+    #[arg(long, value_name = "DIR")]
+    pub work_dir: Option<PathBuf>,
+}
+
+fn resolve_target(target: Option<String>) -> Result<String> {
+    match target {
+        Some(target) => Ok(target),
+        None => crate::state::load()?.target_url.ok_or_else(|| {
+            anyhow::anyhow!("Target database URL not provided and not set in state. Use `--target` or `database-replicator target set`.")
+        }),
+    }
+}
+
+fn work_dir(base: Option<&Path>) -> Result<PathBuf> {
+    match base {
+        Some(base) => {
+            std::fs::create_dir_all(base)
+                .with_context(|| format!("Failed to create work directory {}", base.display()))?;
+            crate::utils::create_managed_temp_dir_in(base)
+        }
+        None => crate::utils::create_managed_temp_dir(),
+    }
+}
+
+/// Back up the target's databases to S3
+///
+/// Each database is dumped like `init` dumps a source: its schema as SQL and
+/// its data in directory format, one compressed file per table, with the
+/// data manifest `init` restores from. The backup's own manifest lists every
+/// file with its digest and is uploaded last, so an interrupted backup is
+/// never taken for a complete one. Retention runs after a successful backup.
+pub async fn backup(args: BackupArgs) -> Result<()> {
+    let target = resolve_target(args.target)?;
+    let location = S3Location::parse(&args.to)?;
+    let s3 = S3Client::from_env(args.endpoint.as_deref()).await?;
+    let filter =
+        ReplicationFilter::new(args.include_databases, args.exclude_databases, None, None)?;
+    let retention = Retention {
+        keep: args.keep,
+        keep_days: args.keep_days,
+    };
+
+    let client = connect_with_retry(&target).await?;
+    let databases: Vec<String> = migration::list_databases(&client)
+        .await?
+        .into_iter()
+        .map(|db| db.name)
+        .filter(|name| filter.should_replicate_database(name))
+        .collect();
+    drop(client);
+    if databases.is_empty() {
+        bail!("No databases to back up on the target");
+    }
+
+    let created_at = Utc::now();
+    let parts = crate::utils::parse_postgres_url(&target)?;
+    let mut manifest = BackupManifest::new(
+        backup::backup_id(created_at),
+        created_at,
+        format!("{}:{}", parts.host, parts.port),
+    );
+    let backup_location = location.join(&manifest.id);
+    tracing::info!(
+        "Backing up {} database(s) to {}",
+        databases.len(),
+        backup_location
+    );
+
+    let work_dir = work_dir(args.work_dir.as_deref())?;
+    let result = async {
+        for database in &databases {
+            let files = backup_database(
+                &s3,
+                &backup_location,
+                &target,
+                database,
+                &work_dir.join(database),
+                &filter,
+            )
+            .await
+            .with_context(|| format!("Failed to back up database '{}'", database))?;
+            manifest.databases.push(BackupDatabase {
+                name: database.clone(),
+                files,
+            });
+        }
+        s3.put_object(
+            &backup_location.bucket,
+            &backup_location.key(backup::MANIFEST_FILE),
+            Bytes::from(serde_json::to_vec_pretty(&manifest)?),
+        )
+        .await
+        .context("Failed to upload the backup manifest")?;
+        Ok::<(), anyhow::Error>(())
+    }
+    .await;
+    let _ = crate::utils::remove_managed_temp_dir(&work_dir);
+    result?;
+
+    tracing::info!(
+        "✓ Backup {} complete: {} database(s), {}",
+        manifest.id,
+        manifest.databases.len(),
+        migration::format_bytes(manifest.total_size() as i64)
+    );
+
+    let objects = s3.list_objects(&location.bucket, &location.key("")).await?;
+    let stored = backup::stored_backups(&location.prefix, &objects);
+    for expired in retention.expired(&stored, Utc::now()) {
+        delete_backup(&s3, &location, expired).await?;
+    }
+    Ok(())
+}
+
+/// Dump one database into `dir` and upload its files
+async fn backup_database(
+    s3: &S3Client,
+    location: &S3Location,
+    target: &str,
+    database: &str,
+    dir: &Path,
+    filter: &ReplicationFilter,
+) -> Result<Vec<BackupFile>> {
+    tracing::info!("Backing up '{}'...", database);
+    let db_url = replace_database_in_connection_string(target, database)?;
+    let data_as_of = Utc::now();
+    let data_dir = migration::artifacts::prepare_dump_dir(dir)?;
+    let schema_path = dir.join(SCHEMA_FILE);
+    migration::dump_schema(&db_url, database, schema_path.to_str().unwrap(), filter).await?;
+    migration::dump_data(&db_url, database, data_dir.to_str().unwrap(), filter).await?;
+    let toc = migration::artifacts::list_archive(&data_dir).await?;
+    let mut manifest = migration::artifacts::DataManifest::from_toc(database, &toc, data_as_of);
+    manifest.record_checksums(dir)?;
+    manifest.save(dir)?;
+
+    let mut files = Vec::new();
+    for path in files_under(dir)? {
+        let relative = path
+            .strip_prefix(dir)
+            .expect("listed under the database directory")
+            .to_string_lossy()
+            .replace('\\', "/");
+        let backup_path = format!("{}/{}", database, relative);
+        let (size, sha256) = s3
+            .upload_file(&location.bucket, &location.key(&backup_path), &path)
+            .await
+            .with_context(|| format!("Failed to upload {}", backup_path))?;
+        files.push(BackupFile {
+            path: backup_path,
+            size,
+            sha256,
+        });
+    }
+    tracing::info!("  ✓ Uploaded {} file(s) for '{}'", files.len(), database);
+    Ok(files)
+}
+
+/// Every file below `dir`, sorted
+fn files_under(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in std::fs::read_dir(&dir)
+            .with_context(|| format!("Failed to read directory {}", dir.display()))?
+        {
+            let path = entry?.path();
+            if path.is_dir() {
+                pending.push(path);
+            } else {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+async fn delete_backup(s3: &S3Client, location: &S3Location, backup: &StoredBackup) -> Result<()> {
+    tracing::info!(
+        "Deleting {} backup {} (retention)",
+        if backup.complete {
+            "expired"
+        } else {
+            "incomplete"
+        },
+        backup.id
+    );
+    // The manifest goes first, so a partly deleted backup reads as incomplete
+    let mut keys = backup.keys.clone();
+    keys.sort_by_key(|key| !key.ends_with(&format!("/{}", backup::MANIFEST_FILE)));
+    for key in &keys {
+        s3.delete_object(&location.bucket, key)
+            .await
+            .with_context(|| format!("Failed to delete backup {}", backup.id))?;
+    }
+    Ok(())
+}
+
+/// Restore databases from a backup made by [`backup`]
+///
+/// Every file is checked against the digest in the backup manifest before
+/// anything is restored. Databases are created on the target, or must be
+/// empty unless `--drop-existing` is given; data is restored table by table
+/// in foreign key order, like `init`.
+pub async fn restore(args: RestoreArgs) -> Result<()> {
+    let target = resolve_target(args.target)?;
+    let location = S3Location::parse(&args.from)?;
+    let s3 = S3Client::from_env(args.endpoint.as_deref()).await?;
+    let filter =
+        ReplicationFilter::new(args.include_databases, args.exclude_databases, None, None)?;
+
+    let objects = s3.list_objects(&location.bucket, &location.key("")).await?;
+    let stored = backup::stored_backups(&location.prefix, &objects);
+    let chosen = match &args.backup_id {
+        Some(id) => stored.iter().find(|backup| &backup.id == id),
+        None => stored.iter().find(|backup| backup.complete),
+    };
+    let Some(chosen) = chosen else {
+        match &args.backup_id {
+            Some(id) => bail!("Backup {} not found under {}", id, location),
+            None => bail!("No complete backup found under {}", location),
+        }
+    };
+    if !chosen.complete {
+        bail!(
+            "Backup {} under {} is incomplete: it has no manifest",
+            chosen.id,
+            location
+        );
+    }
+    let backup_location = location.join(&chosen.id);
+    let manifest = BackupManifest::parse(
+        &s3.get_object(
+            &backup_location.bucket,
+            &backup_location.key(backup::MANIFEST_FILE),
+        )
+        .await?,
+    )?;
+    let databases: Vec<&BackupDatabase> = manifest
+        .databases
+        .iter()
+        .filter(|database| filter.should_replicate_database(&database.name))
+        .collect();
+    if databases.is_empty() {
+        bail!("Backup {} has no databases to restore", manifest.id);
+    }
+    tracing::info!(
+        "Restoring {} database(s) from backup {} of {} ({})",
+        databases.len(),
+        manifest.id,
+        manifest.server,
+        migration::format_bytes(manifest.total_size() as i64)
+    );
+
+    let jobs =
+        crate::postgres::pool::parallel_jobs(args.jobs, migration::artifacts::DEFAULT_RESTORE_JOBS);
+    let work_dir = work_dir(args.work_dir.as_deref())?;
+    let result = async {
+        for database in databases {
+            let dir = work_dir.join(&database.name);
+            download_database(&s3, &backup_location, database, &work_dir).await?;
+            restore_database(&target, &database.name, &dir, jobs, args.drop_existing)
+                .await
+                .with_context(|| format!("Failed to restore database '{}'", database.name))?;
+            migration::artifacts::remove(&dir)?;
+        }
+        Ok::<(), anyhow::Error>(())
+    }
+    .await;
+    let _ = crate::utils::remove_managed_temp_dir(&work_dir);
+    result?;
+
+    tracing::info!("✓ Restored backup {}", manifest.id);
+    Ok(())
+}
+
+/// Download a database's files into `work_dir`, checking their digests
+async fn download_database(
+    s3: &S3Client,
+    location: &S3Location,
+    database: &BackupDatabase,
+    work_dir: &Path,
+) -> Result<()> {
+    tracing::info!(
+        "Downloading '{}' ({} file(s))...",
+        database.name,
+        database.files.len()
+    );
+    for file in &database.files {
+        if file
+            .path
+            .split('/')
+            .any(|part| part == ".." || part.is_empty())
+        {
+            bail!("Backup manifest has an invalid path '{}'", file.path);
+        }
+        let (size, sha256) = s3
+            .download_file(
+                &location.bucket,
+                &location.key(&file.path),
+                &work_dir.join(&file.path),
+            )
+            .await
+            .with_context(|| format!("Failed to download {}", file.path))?;
+        if size != file.size || sha256 != file.sha256 {
+            bail!(
+                "{} does not match the backup manifest ({} bytes, expected {}); the backup may be corrupt",
+                file.path,
+                size,
+                file.size
+            );
+        }
+    }
+    Ok(())
+}
+
+async fn restore_database(
+    target: &str,
+    database: &str,
+    dir: &Path,
+    jobs: usize,
+    drop_existing: bool,
+) -> Result<()> {
+    crate::utils::validate_postgres_identifier(database)
+        .with_context(|| format!("Invalid database name: '{}'", database))?;
+    let db_url = replace_database_in_connection_string(target, database)?;
+    let create = format!("CREATE DATABASE {}", crate::utils::quote_ident(database));
+    let client = connect_with_retry(target).await?;
+    if let Err(err) = client.execute(&create, &[]).await {
+        if err.as_db_error().map(|db| db.code())
+            != Some(&tokio_postgres::error::SqlState::DUPLICATE_DATABASE)
+        {
+            return Err(err).context(format!("Failed to create database '{}'", database));
+        }
+        let is_empty =
+            crate::commands::init::database_is_empty(&connect_with_retry(&db_url).await?).await?;
+        if !is_empty {
+            if !drop_existing {
+                bail!(
+                    "Database '{}' already exists on the target and holds tables. \
+                     Pass --drop-existing to replace it.",
+                    database
+                );
+            }
+            crate::commands::init::drop_database_if_exists(target, database).await?;
+            client
+                .execute(&create, &[])
+                .await
+                .with_context(|| format!("Failed to create database '{}'", database))?;
+        }
+    }
+    drop(client);
+
+    tracing::info!("Restoring '{}'...", database);
+    migration::restore_schema(&db_url, dir.join(SCHEMA_FILE).to_str().unwrap()).await?;
+    let mut manifest = migration::artifacts::DataManifest::load(dir)?
+        .with_context(|| format!("Backup of '{}' has no data manifest", database))?;
+    migration::artifacts::restore_artifacts(&db_url, dir, &mut manifest, jobs, false).await
+}
@@ -0,0 +1,493 @@
+// ABOUTME: Cutover command - drains replication and verifies the target before switching over
+// ABOUTME: Pauses sync, optionally makes the source read-only, waits for zero lag, then verifies
+
+use crate::commands::verify::{verify_database, VerifyReport};
+use crate::filters::ReplicationFilter;
+use crate::postgres::connect_with_retry;
+use crate::replication::{current_wal_lsn, has_replayed_lsn, list_subscriptions};
+use crate::utils::quote_ident;
+use crate::xmin::{DaemonConfig, SyncDaemon, SyncDatabase};
+use anyhow::{bail, Context, Result};
+use std::time::{Duration, Instant};
+
+/// Subscription name `sync` uses for a single database; suffixed with the
+/// database name when several are replicated
+const SUBSCRIPTION_NAME_TEMPLATE: &str = "seren_migration_sub";
+
+/// Options for [`cutover`]
+#[derive(Debug, Clone, Copy)]
+pub struct CutoverOptions {
+    /// Set `default_transaction_read_only` on each source database before draining
+    pub read_only_source: bool,
+    /// Give up if replication has not caught up within this long
+    pub drain_timeout: Duration,
+    /// Don't ask before making the source read-only
+    pub skip_confirmation: bool,
+}
+
+impl Default for CutoverOptions {
+    fn default() -> Self {
+        Self {
+            read_only_source: false,
+            drain_timeout: Duration::from_secs(600),
+            skip_confirmation: false,
+        }
+    }
+}
+
+/// How a database is kept in sync, which decides how it is drained
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum DrainMethod {
+    /// Logical replication through this subscription on the target
+    Logical { subscription: String },
+    /// xmin-based sync cycles
+    Xmin,
+}
+
+/// What cutover did, for the checklist
+#[derive(Debug, Default)]
+struct CutoverSummary {
+    paused: bool,
+    read_only: Vec<String>,
+    drained: Vec<(String, DrainMethod)>,
+    report: VerifyReport,
+    /// Tracked replication slots that nothing streamed from any more
+    dropped_slots: Vec<String>,
+}
+
+/// Drain replication into the target and verify it, ready to switch over
+///
+/// 1. Pauses xmin-based sync so the daemon starts no new cycles
+/// 2. Optionally sets every source database read-only for new sessions
+/// 3. Waits until the target has everything: for logical replication the
+///    subscriber must replay up to the source's current WAL position; for
+///    xmin-based sync, cycles run until one finds no rows above the stored
+///    xmin high-water mark, followed by one delete reconciliation
+/// 4. Verifies every filtered table with checksums
+/// 5. Prints a checklist of what was done and what is left to do by hand
+///
+/// # Errors
+///
+/// Returns an error if replication does not catch up within the drain
+/// timeout, or if verification finds tables that differ.
+pub async fn cutover(
+    source_url: &str,
+    target_url: &str,
+    filter: ReplicationFilter,
+    options: CutoverOptions,
+) -> Result<()> {
+    crate::utils::validate_source_target_different(source_url, target_url)
+        .context("Source and target validation failed")?;
+
+    let databases = SyncDatabase::plan(source_url, target_url, &filter).await?;
+    if databases.is_empty() {
+        bail!("No databases matched the filter criteria");
+    }
+    let mut summary = CutoverSummary::default();
+
+    // Step 1: stop new sync cycles
+    tracing::info!("Step 1/5: Pausing sync...");
+    summary.paused = crate::daemon::pause()?;
+    tracing::info!("  ✓ xmin-based sync paused; the daemon finishes its current table first");
+
+    // Step 2: stop writes on the source
+    if options.read_only_source {
+        tracing::info!("Step 2/5: Setting source databases read-only...");
+        if !options.skip_confirmation && !confirm_read_only(&databases)? {
+            bail!("Cutover cancelled by user");
+        }
+        let source_client = connect_with_retry(source_url)
+            .await
+            .context("Failed to connect to source database")?;
+        for database in &databases {
+            let name = database.name();
+            // A rerun connects after the first run already made the database
+            // read-only. SET LOCAL keeps this working through transaction poolers.
+            source_client
+                .batch_execute(&format!(
+                    "BEGIN; \
+                     SET LOCAL default_transaction_read_only = off; \
+                     ALTER DATABASE {} SET default_transaction_read_only = on; \
+                     COMMIT",
+                    quote_ident(&name)
+                ))
+                .await
+                .with_context(|| format!("Failed to make source database '{}' read-only", name))?;
+            tracing::info!("  ✓ '{}' is read-only for new sessions", name);
+            summary.read_only.push(name);
+        }
+        tracing::warn!(
+            "⚠ Sessions already connected to the source can still write until they reconnect"
+        );
+    } else {
+        tracing::info!("Step 2/5: Leaving the source writable (--read-only-source not set)");
+        tracing::warn!("⚠ Writes made after the drain will not reach the target");
+    }
+
+    // Step 3: wait for zero lag
+    tracing::info!("Step 3/5: Draining replication...");
+    let deadline = Instant::now() + options.drain_timeout;
+    let mut xmin_databases = Vec::new();
+    for database in &databases {
+        let method = drain_method(database, databases.len()).await?;
+        match method {
+            DrainMethod::Logical { ref subscription } => {
+                drain_logical(database, subscription, deadline).await?;
+                summary.drained.push((database.name(), method));
+            }
+            DrainMethod::Xmin => xmin_databases.push(database.clone()),
+        }
+    }
+    if !xmin_databases.is_empty() {
+        let names: Vec<String> = xmin_databases.iter().map(|db| db.name()).collect();
+        drain_xmin(xmin_databases, deadline).await?;
+        summary
+            .drained
+            .extend(names.into_iter().map(|name| (name, DrainMethod::Xmin)));
+    }
+
+    // Step 4: final verify
+    tracing::info!("Step 4/5: Verifying target...");
+    // Compare exactly the pairs that were drained; a single database may be
+    // replicated under a different name on the target
+    for database in &databases {
+        let name = database.name();
+        let tables = verify_database(
+            &name,
+            &database.source_url,
+            &database.target_url,
+            &filter,
+            None,
+        )
+        .await?;
+        summary.report.databases.push(name);
+        summary.report.tables.extend(tables);
+    }
+
+    // Step 5: checklist, after dropping slots that would keep WAL on the source
+    tracing::info!("Step 5/5: Cutover checklist");
+    match crate::replication::slot::drop_orphaned_slots().await {
+        Ok(dropped) => summary.dropped_slots = dropped,
+        Err(e) => tracing::warn!("⚠ Failed to clean up replication slots: {:#}", e),
+    }
+    println!();
+    for line in checklist(&summary) {
+        println!("{}", line);
+    }
+    println!();
+
+    if !summary.report.is_consistent() {
+        bail!(
+            "Cutover verification failed: {} table(s) differ between source and target",
+            summary.report.mismatches()
+        );
+    }
+    Ok(())
+}
+
+/// Logical replication if the target has this database's subscription, else xmin
+async fn drain_method(database: &SyncDatabase, database_count: usize) -> Result<DrainMethod> {
+    let subscription = subscription_name(&database.name(), database_count);
+    let target_client = connect_with_retry(&database.target_url)
+        .await
+        .with_context(|| format!("Failed to connect to target database '{}'", database.name()))?;
+    let subscriptions = list_subscriptions(&target_client).await?;
+
+    Ok(if subscriptions.contains(&subscription) {
+        DrainMethod::Logical { subscription }
+    } else {
+        DrainMethod::Xmin
+    })
+}
+
+fn subscription_name(database: &str, database_count: usize) -> String {
+    if database_count == 1 {
+        SUBSCRIPTION_NAME_TEMPLATE.to_string()
+    } else {
+        format!("{}_{}", SUBSCRIPTION_NAME_TEMPLATE, database)
+    }
+}
+
+/// Wait until the subscriber has replayed the source's current WAL position
+async fn drain_logical(
+    database: &SyncDatabase,
+    subscription: &str,
+    deadline: Instant,
+) -> Result<()> {
+    let source_client = connect_with_retry(&database.source_url)
+        .await
+        .with_context(|| format!("Failed to connect to source database '{}'", database.name()))?;
+    let lsn = current_wal_lsn(&source_client).await?;
+    tracing::info!(
+        "  Waiting for subscription '{}' to replay up to LSN {}...",
+        subscription,
+        lsn
+    );
+
+    while !has_replayed_lsn(&source_client, subscription, &lsn).await? {
+        if Instant::now() >= deadline {
+            bail!(
+                "Subscription '{}' did not reach LSN {} before the drain timeout.\n\
+                 Check it with `database-replicator status`, then re-run cutover.",
+                subscription,
+                lsn
+            );
+        }
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+
+    tracing::info!("  ✓ '{}' drained (logical replication)", database.name());
+    Ok(())
+}
+
+/// Run xmin sync cycles until one finds nothing new, then reconcile deletes
+async fn drain_xmin(databases: Vec<SyncDatabase>, deadline: Instant) -> Result<()> {
+    let config = DaemonConfig {
+        batch_size: crate::utils::calculate_optimal_batch_size(),
+        // Per-database tables and schemas come from `databases`
+        ..Default::default()
+    };
+    let daemon = SyncDaemon::with_databases(databases, config);
+
+    let mut cycles = 0u64;
+    loop {
+        cycles += 1;
+        let stats = daemon.run_sync_cycle().await?;
+        tracing::info!(
+            "  Drain cycle {}: {} rows synced, {} errors",
+            cycles,
+            stats.rows_synced,
+            stats.errors.len()
+        );
+        if stats.rows_synced == 0 && stats.is_success() {
+            break;
+        }
+        if Instant::now() >= deadline {
+            bail!(
+                "xmin-based sync was still finding changes when the drain timeout expired \
+                 ({} rows in the last cycle, {} errors).\n\
+                 Stop writes on the source (--read-only-source) or raise --drain-timeout.",
+                stats.rows_synced,
+                stats.errors.len()
+            );
+        }
+    }
+
+    // xmin sync only sees inserts and updates; catch deletes before verifying
+    let stats = daemon.run_reconciliation().await?;
+    if !stats.is_success() {
+        bail!("Delete reconciliation failed:\n{}", stats.errors.join("\n"));
+    }
+    tracing::info!(
+        "  ✓ xmin-based sync drained ({} deleted rows reconciled)",
+        stats.rows_deleted
+    );
+    Ok(())
+}
+
+fn confirm_read_only(databases: &[SyncDatabase]) -> Result<bool> {
+    use std::io::{self, Write};
+
+    println!();
+    println!("These source databases will reject writes from new sessions:");
+    for database in databases {
+        println!("  - {}", database.name());
+    }
+    println!();
+
+    crate::interactive::ensure_interactive(
+        "read-only confirmation",
+        "Pass -y/--yes (or set DATABASE_REPLICATOR_YES=1) to proceed without confirming.",
+    )?;
+    print!("Make the source read-only? [y/N]: ");
+    io::stdout().flush()?;
+
+    let mut input = String::new();
+    io::stdin()
+        .read_line(&mut input)
+        .context("Failed to read user input")?;
+
+    Ok(input.trim().to_lowercase() == "y")
+}
+
+/// Lines of the cutover checklist: ✓ done, ✗ failed, ⚠ attention, ☐ left to do
+fn checklist(summary: &CutoverSummary) -> Vec<String> {
+    let mut lines = vec![
+        "========================================".to_string(),
+        "Cutover checklist".to_string(),
+        "========================================".to_string(),
+    ];
+
+    lines.push(if summary.paused {
+        "✓ Sync paused".to_string()
+    } else {
+        "✓ Sync paused (it already was)".to_string()
+    });
+
+    if summary.read_only.is_empty() {
+        lines.push("⚠ Source still accepts writes".to_string());
+    } else {
+        lines.push(format!(
+            "✓ Source read-only for new sessions: {}",
+            summary.read_only.join(", ")
+        ));
+    }
+
+    let drained: Vec<String> = summary
+        .drained
+        .iter()
+        .map(|(name, method)| match method {
+            DrainMethod::Logical { .. } => format!("{} (logical)", name),
+            DrainMethod::Xmin => format!("{} (xmin)", name),
+        })
+        .collect();
+    lines.push(format!("✓ Replication drained: {}", drained.join(", ")));
+
+    let report = &summary.report;
+    if report.is_consistent() {
+        lines.push(format!(
+            "✓ Verified: {} table(s) match",
+            report.tables.len()
+        ));
+    } else {
+        let differing: Vec<String> = report
+            .tables
+            .iter()
+            .filter(|t| !t.is_match())
+            .map(|t| format!("{}.{}.{}", t.database, t.schema, t.table))
+            .collect();
+        lines.push(format!(
+            "✗ Verification failed: {} table(s) differ: {}",
+            differing.len(),
+            differing.join(", ")
+        ));
+    }
+
+    if !summary.dropped_slots.is_empty() {
+        lines.push(format!(
+            "✓ Dropped orphaned replication slots: {}",
+            summary.dropped_slots.join(", ")
+        ));
+    }
+
+    lines.push(
+        "☐ Advance sequences on the target; replication does not carry sequence values".to_string(),
+    );
+    lines.push("☐ Point applications at the target".to_string());
+    for (_, method) in &summary.drained {
+        if let DrainMethod::Logical { subscription } = method {
+            lines.push(format!(
+                "☐ Drop the subscription on the target: DROP SUBSCRIPTION {};",
+                quote_ident(subscription)
+            ));
+        }
+    }
+    if summary
+        .drained
+        .iter()
+        .any(|(_, method)| *method == DrainMethod::Xmin)
+    {
+        lines.push("☐ Stop the sync daemon: database-replicator sync --stop".to_string());
+    }
+    lines.push("☐ To roll back: database-replicator sync --resume".to_string());
+    for name in &summary.read_only {
+        lines.push(format!(
+            "     ALTER DATABASE {} RESET default_transaction_read_only;",
+            quote_ident(name)
+        ));
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::verify::{TableStatus, TableVerification};
+
+    fn table(name: &str, status: TableStatus) -> TableVerification {
+        TableVerification {
+            database: "app".to_string(),
+            schema: "public".to_string(),
+            table: name.to_string(),
+            source_row_count: 1,
+            target_row_count: 1,
+            source_checksum: "a".to_string(),
+            target_checksum: "a".to_string(),
+            status,
+        }
+    }
+
+    #[test]
+    fn test_subscription_name() {
+        assert_eq!(subscription_name("app", 1), "seren_migration_sub");
+        assert_eq!(subscription_name("app", 2), "seren_migration_sub_app");
+    }
+
+    #[test]
+    fn test_checklist_after_clean_cutover() {
+        let summary = CutoverSummary {
+            paused: true,
+            read_only: vec!["app".to_string()],
+            drained: vec![
+                (
+                    "app".to_string(),
+                    DrainMethod::Logical {
+                        subscription: "seren_migration_sub_app".to_string(),
+                    },
+                ),
+                ("analytics".to_string(), DrainMethod::Xmin),
+            ],
+            report: VerifyReport {
+                databases: vec!["app".to_string()],
+                tables: vec![table("users", TableStatus::Match)],
+                ..Default::default()
+            },
+            dropped_slots: vec!["seren_migration_sub_old (app)".to_string()],
+        };
+
+        let lines = checklist(&summary);
+        assert!(lines.contains(&"✓ Source read-only for new sessions: app".to_string()));
+        assert!(lines.contains(
+            &"✓ Dropped orphaned replication slots: seren_migration_sub_old (app)".to_string()
+        ));
+        assert!(
+            lines.contains(&"✓ Replication drained: app (logical), analytics (xmin)".to_string())
+        );
+        assert!(lines.contains(&"✓ Verified: 1 table(s) match".to_string()));
+        assert!(lines.contains(
+            &"☐ Drop the subscription on the target: DROP SUBSCRIPTION \"seren_migration_sub_app\";"
+                .to_string()
+        ));
+        assert!(
+            lines.contains(&"☐ Stop the sync daemon: database-replicator sync --stop".to_string())
+        );
+        assert!(lines.contains(
+            &"     ALTER DATABASE \"app\" RESET default_transaction_read_only;".to_string()
+        ));
+    }
+
+    #[test]
+    fn test_checklist_reports_mismatches() {
+        let summary = CutoverSummary {
+            paused: false,
+            read_only: Vec::new(),
+            drained: vec![("app".to_string(), DrainMethod::Xmin)],
+            report: VerifyReport {
+                databases: vec!["app".to_string()],
+                tables: vec![
+                    table("users", TableStatus::Match),
+                    table("orders", TableStatus::RowCountMismatch),
+                ],
+                ..Default::default()
+            },
+            dropped_slots: Vec::new(),
+        };
+
+        let lines = checklist(&summary);
+        assert!(lines.contains(&"⚠ Source still accepts writes".to_string()));
+        assert!(lines
+            .contains(&"✗ Verification failed: 1 table(s) differ: app.public.orders".to_string()));
+        assert!(!lines.iter().any(|line| line.contains("DROP SUBSCRIPTION")));
+    }
+}
@@ -0,0 +1,606 @@
+// ABOUTME: Doctor command implementation - checks this machine and both servers before a migration
+// ABOUTME: Reports client tools, TLS trust, connectivity and latency, privileges, and replication settings
+
+use anyhow::{bail, Result};
+use clap::Args;
+use serde::Serialize;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use tokio_postgres::Client;
+
+use crate::errors;
+use crate::postgres::{check_source_privileges, check_wal_level};
+
+/// Round trips timed per server to estimate its latency
+const LATENCY_SAMPLES: usize = 5;
+
+/// Round trip above which a server is reported as slow to reach
+const SLOW_ROUND_TRIP: Duration = Duration::from_millis(50);
+
+/// CA bundles OpenSSL reads on common Linux distributions
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+const CA_BUNDLES: &[&str] = &[
+    "/etc/ssl/certs/ca-certificates.crt",
+    "/etc/pki/tls/certs/ca-bundle.crt",
+    "/etc/pki/ca-trust/extracted/pem/tls-ca-bundle.pem",
+    "/etc/ssl/ca-bundle.pem",
+    "/etc/ssl/cert.pem",
+];
+
+#[derive(Args)]
+pub struct DoctorArgs {
+    /// Source database to check
+    #[arg(long, env = "DATABASE_REPLICATOR_SOURCE")]
+    pub source: Option<String>,
+    /// Target database to check; defaults to the saved target
+    #[arg(long, env = "DATABASE_REPLICATOR_TARGET")]
+    pub target: Option<String>,
+    /// Print the report as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Pass,
+    /// Works, but not the way a migration usually wants
+    Warn,
+    Fail,
+}
+
+/// One line of the report
+#[derive(Debug, Clone, Serialize)]
+pub struct DoctorCheck {
+    pub section: String,
+    pub status: CheckStatus,
+    pub message: String,
+    /// What to change, for a warning or failure
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remediation: Option<String>,
+}
+
+/// Everything `doctor` checked, in the order it was checked
+#[derive(Debug, Default, Serialize)]
+pub struct DoctorReport {
+    pub checks: Vec<DoctorCheck>,
+}
+
+impl DoctorReport {
+    fn add(&mut self, section: &str, status: CheckStatus, message: impl Into<String>) {
+        self.checks.push(DoctorCheck {
+            section: section.to_string(),
+            status,
+            message: message.into(),
+            remediation: None,
+        });
+    }
+
+    fn add_with_fix(
+        &mut self,
+        section: &str,
+        status: CheckStatus,
+        message: impl Into<String>,
+        remediation: impl Into<String>,
+    ) {
+        self.checks.push(DoctorCheck {
+            section: section.to_string(),
+            status,
+            message: message.into(),
+            remediation: Some(remediation.into()),
+        });
+    }
+
+    pub fn count(&self, status: CheckStatus) -> usize {
+        self.checks
+            .iter()
+            .filter(|check| check.status == status)
+            .count()
+    }
+
+    pub fn print(&self) {
+        println!();
+        println!("Doctor");
+        println!("{}", "═".repeat(61));
+        let mut section = None;
+        for check in &self.checks {
+            if section != Some(&check.section) {
+                println!();
+                println!("{}:", check.section);
+                section = Some(&check.section);
+            }
+            let icon = match check.status {
+                CheckStatus::Pass => "✓",
+                CheckStatus::Warn => "⚠",
+                CheckStatus::Fail => "✗",
+            };
+            println!("  {} {}", icon, check.message);
+            if let Some(remediation) = &check.remediation {
+                println!("      → {}", remediation);
+            }
+        }
+        println!();
+        println!("{}", "═".repeat(61));
+        let (failed, warned) = (self.count(CheckStatus::Fail), self.count(CheckStatus::Warn));
+        if failed == 0 {
+            println!("PASSED with {} warning(s)", warned);
+        } else {
+            println!("FAILED: {} problem(s), {} warning(s)", failed, warned);
+        }
+    }
+}
+
+/// Check client tools, TLS trust, and the source and target servers
+///
+/// The target defaults to the one saved with `target set`. Servers that are
+/// not given are left out of the report.
+pub async fn command(args: DoctorArgs) -> Result<()> {
+    let target = match args.target {
+        Some(target) => Some(target),
+        None => crate::state::load()?.target_url,
+    };
+
+    let mut report = DoctorReport::default();
+    let pg_dump_version = check_tools(&mut report);
+    check_trust_store(&mut report);
+
+    if let Some(source) = &args.source {
+        if crate::detect_source_type(source)? == crate::SourceType::PostgreSQL {
+            if let Some(client) = check_connection(&mut report, "Source", source).await {
+                let server_version = server_major_version(&client).await;
+                check_tool_version(&mut report, pg_dump_version, server_version);
+                check_source(&mut report, &client).await;
+            }
+        } else {
+            report.add(
+                "Source",
+                CheckStatus::Warn,
+                "Only PostgreSQL sources are checked; skipping the source",
+            );
+        }
+    }
+    if let Some(target) = &target {
+        if let Some(client) = check_connection(&mut report, "Target", target).await {
+            check_target(&mut report, &client).await;
+        }
+    }
+    if args.source.is_none() || target.is_none() {
+        report.add(
+            "Servers",
+            CheckStatus::Warn,
+            format!(
+                "No {} given; pass --source and --target to check the servers",
+                match (args.source.is_none(), target.is_none()) {
+                    (true, true) => "source or target",
+                    (true, false) => "source",
+                    _ => "target",
+                }
+            ),
+        );
+    }
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        report.print();
+    }
+    let failed = report.count(CheckStatus::Fail);
+    if failed > 0 {
+        bail!("Doctor found {} problem(s)", failed);
+    }
+    Ok(())
+}
+
+/// Report each client tool and return pg_dump's major version
+fn check_tools(report: &mut DoctorReport) -> Option<u32> {
+    let mut pg_dump_version = None;
+    for tool in ["pg_dump", "pg_dumpall", "pg_restore", "psql"] {
+        match which::which(tool) {
+            Ok(path) => match crate::utils::get_pg_tool_version(tool) {
+                Ok(version) => {
+                    if tool == "pg_dump" {
+                        pg_dump_version = Some(version);
+                    }
+                    report.add(
+                        "Client tools",
+                        CheckStatus::Pass,
+                        format!("{} {} ({})", tool, version, path.display()),
+                    );
+                }
+                Err(_) => report.add(
+                    "Client tools",
+                    CheckStatus::Warn,
+                    format!("{} found at {}, version unknown", tool, path.display()),
+                ),
+            },
+            Err(_) => report.add_with_fix(
+                "Client tools",
+                CheckStatus::Warn,
+                format!(
+                    "{} not found; init copies without it, leaving out ownership and privileges",
+                    tool
+                ),
+                "Install postgresql-client, or pass --download-pg-tools",
+            ),
+        }
+    }
+    pg_dump_version
+}
+
+fn check_tool_version(report: &mut DoctorReport, pg_dump: Option<u32>, server: Option<u32>) {
+    let (Some(local), Some(server)) = (pg_dump, server) else {
+        return;
+    };
+    if local < server {
+        report.add_with_fix(
+            "Source",
+            CheckStatus::Fail,
+            format!(
+                "pg_dump {} cannot dump the source (PostgreSQL {})",
+                local, server
+            ),
+            format!(
+                "Install PostgreSQL {} client tools, or pass --download-pg-tools",
+                server
+            ),
+        );
+    } else {
+        report.add(
+            "Source",
+            CheckStatus::Pass,
+            format!(
+                "pg_dump {} can dump the source (PostgreSQL {})",
+                local, server
+            ),
+        );
+    }
+}
+
+/// Report where TLS connections find the CA certificates that verify servers
+fn check_trust_store(report: &mut DoctorReport) {
+    const SECTION: &str = "TLS";
+    if let Err(e) = native_tls::TlsConnector::new() {
+        report.add_with_fix(
+            SECTION,
+            CheckStatus::Fail,
+            format!("TLS is not available: {}", e),
+            "Install the system's OpenSSL libraries",
+        );
+        return;
+    }
+    if crate::postgres::connection::allows_invalid_certs() {
+        report.add_with_fix(
+            SECTION,
+            CheckStatus::Warn,
+            "Server certificates are not verified (--allow-self-signed-certs)",
+            "Drop --allow-self-signed-certs once the servers have trusted certificates",
+        );
+    }
+    for var in ["SSL_CERT_FILE", "SSL_CERT_DIR"] {
+        if let Some(path) = std::env::var_os(var) {
+            let path = Path::new(&path);
+            if path.exists() {
+                report.add(
+                    SECTION,
+                    CheckStatus::Pass,
+                    format!("CA certificates from {} ({})", var, path.display()),
+                );
+            } else {
+                report.add_with_fix(
+                    SECTION,
+                    CheckStatus::Fail,
+                    format!("{} names {}, which does not exist", var, path.display()),
+                    format!("Point {} at a CA bundle, or unset it", var),
+                );
+            }
+            return;
+        }
+    }
+    system_trust_store(report);
+}
+
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+fn system_trust_store(report: &mut DoctorReport) {
+    report.add(
+        "TLS",
+        CheckStatus::Pass,
+        "CA certificates from the system trust store",
+    );
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn system_trust_store(report: &mut DoctorReport) {
+    match CA_BUNDLES.iter().find(|path| Path::new(path).exists()) {
+        Some(path) => report.add(
+            "TLS",
+            CheckStatus::Pass,
+            format!("CA certificates from {}", path),
+        ),
+        None => report.add_with_fix(
+            "TLS",
+            CheckStatus::Fail,
+            "No CA bundle found; servers with public certificates cannot be verified",
+            "Install the ca-certificates package, or set SSL_CERT_FILE to a CA bundle",
+        ),
+    }
+}
+
+/// Connect to a server and report how long it took, its round trip, and
+/// whether the connection is encrypted
+async fn check_connection(report: &mut DoctorReport, section: &str, url: &str) -> Option<Client> {
+    let started = Instant::now();
+    let client = match crate::postgres::connect(url).await {
+        Ok(client) => client,
+        Err(e) => {
+            let message = format!("Cannot connect: {}", e);
+            let fix = errors::lookup(&e)
+                .map(|hint| format!("{} See {}", hint.hint, hint.doc_url()))
+                .unwrap_or_else(|| {
+                    "Check the connection string, that the host is reachable, and that its \
+                     firewall allows this machine"
+                        .to_string()
+                });
+            report.add_with_fix(section, CheckStatus::Fail, message, fix);
+            return None;
+        }
+    };
+    let connect_time = started.elapsed();
+
+    let mut round_trips = Vec::with_capacity(LATENCY_SAMPLES);
+    for _ in 0..LATENCY_SAMPLES {
+        let started = Instant::now();
+        if client.simple_query("SELECT 1").await.is_err() {
+            break;
+        }
+        round_trips.push(started.elapsed());
+    }
+    round_trips.sort();
+    let message = match round_trips.get(round_trips.len() / 2) {
+        Some(round_trip) => format!(
+            "Connected in {} ms, round trip {:.1} ms",
+            connect_time.as_millis(),
+            round_trip.as_secs_f64() * 1000.0
+        ),
+        None => format!("Connected in {} ms", connect_time.as_millis()),
+    };
+    match round_trips.get(round_trips.len() / 2) {
+        Some(round_trip) if *round_trip > SLOW_ROUND_TRIP => report.add_with_fix(
+            section,
+            CheckStatus::Warn,
+            message,
+            "High latency slows row-by-row sync; run closer to the servers or use remote execution",
+        ),
+        _ => report.add(section, CheckStatus::Pass, message),
+    }
+
+    let tls = client
+        .query_one(
+            "SELECT ssl, version,
+                    inet_server_addr() IS NULL OR host(inet_server_addr()) IN ('127.0.0.1', '::1')
+             FROM pg_stat_ssl WHERE pid = pg_backend_pid()",
+            &[],
+        )
+        .await;
+    match tls {
+        Ok(row) => {
+            let (ssl, version, local): (bool, Option<String>, Option<bool>) =
+                (row.get(0), row.get(1), row.get(2));
+            if ssl {
+                report.add(
+                    section,
+                    CheckStatus::Pass,
+                    format!("Encrypted with {}", version.unwrap_or_else(|| "TLS".into())),
+                );
+            } else if local.unwrap_or(false) {
+                report.add(
+                    section,
+                    CheckStatus::Pass,
+                    "Not encrypted (local connection)",
+                );
+            } else {
+                report.add_with_fix(
+                    section,
+                    CheckStatus::Warn,
+                    "Not encrypted",
+                    "Add sslmode=require to the connection string",
+                );
+            }
+        }
+        Err(e) => report.add(
+            section,
+            CheckStatus::Warn,
+            format!("Could not check encryption: {}", e),
+        ),
+    }
+    Some(client)
+}
+
+async fn server_major_version(client: &Client) -> Option<u32> {
+    let row = client.query_one("SHOW server_version", &[]).await.ok()?;
+    crate::utils::parse_pg_version_string(&row.get::<_, String>(0)).ok()
+}
+
+/// Remediation that ends with a link to the error catalog entry `code`
+fn with_doc_link(fix: &str, code: &str) -> String {
+    match errors::by_code(code) {
+        Some(hint) => format!("{} See {}", fix, hint.doc_url()),
+        None => fix.to_string(),
+    }
+}
+
+async fn check_source(report: &mut DoctorReport, client: &Client) {
+    const SECTION: &str = "Source";
+    match check_source_privileges(client).await {
+        Ok(privs) if privs.can_replicate() => {
+            report.add(SECTION, CheckStatus::Pass, "Can replicate (REPLICATION)")
+        }
+        Ok(_) => report.add_with_fix(
+            SECTION,
+            CheckStatus::Fail,
+            "Missing REPLICATION privilege",
+            "ALTER USER <username> WITH REPLICATION; on AWS RDS: GRANT rds_replication TO <username>;",
+        ),
+        Err(e) => report.add(
+            SECTION,
+            CheckStatus::Warn,
+            format!("Could not check privileges: {:#}", e),
+        ),
+    }
+
+    let logical = match check_wal_level(client).await {
+        Ok(level) if level == "logical" => {
+            report.add(SECTION, CheckStatus::Pass, "wal_level = logical");
+            true
+        }
+        Ok(level) => {
+            report.add_with_fix(
+                SECTION,
+                CheckStatus::Warn,
+                format!(
+                    "wal_level = {}; sync will poll with xmin instead of logical replication",
+                    level
+                ),
+                with_doc_link("Set wal_level = logical and restart the server.", "DR001"),
+            );
+            false
+        }
+        Err(e) => {
+            report.add(
+                SECTION,
+                CheckStatus::Warn,
+                format!("Could not check wal_level: {:#}", e),
+            );
+            false
+        }
+    };
+    // Slots and WAL senders only matter to logical replication
+    if !logical {
+        return;
+    }
+
+    let capacity = client
+        .query_one(
+            "SELECT current_setting('max_replication_slots')::int,
+                    (SELECT count(*)::int FROM pg_replication_slots),
+                    current_setting('max_wal_senders')::int,
+                    (SELECT count(*)::int FROM pg_stat_replication)",
+            &[],
+        )
+        .await;
+    let row = match capacity {
+        Ok(row) => row,
+        Err(e) => {
+            report.add(
+                SECTION,
+                CheckStatus::Warn,
+                format!("Could not check replication capacity: {}", e),
+            );
+            return;
+        }
+    };
+    let (max_slots, used_slots, max_senders, used_senders): (i32, i32, i32, i32) =
+        (row.get(0), row.get(1), row.get(2), row.get(3));
+    for (setting, max, used, code, fix) in [
+        (
+            "max_replication_slots",
+            max_slots,
+            used_slots,
+            "DR005",
+            "Drop unused slots (`database-replicator slots list`) or raise max_replication_slots.",
+        ),
+        (
+            "max_wal_senders",
+            max_senders,
+            used_senders,
+            "DR004",
+            "Raise max_wal_senders and restart the server.",
+        ),
+    ] {
+        let message = format!("{} = {}, {} in use", setting, max, used);
+        if used >= max {
+            report.add_with_fix(
+                SECTION,
+                CheckStatus::Fail,
+                message,
+                with_doc_link(fix, code),
+            );
+        } else {
+            report.add(SECTION, CheckStatus::Pass, message);
+        }
+    }
+}
+
+async fn check_target(report: &mut DoctorReport, client: &Client) {
+    const SECTION: &str = "Target";
+    match check_source_privileges(client).await {
+        Ok(privs) if privs.has_create_db || privs.is_superuser => report.add(
+            SECTION,
+            CheckStatus::Pass,
+            "Can create databases (CREATEDB)",
+        ),
+        Ok(_) => report.add_with_fix(
+            SECTION,
+            CheckStatus::Fail,
+            "Missing CREATEDB privilege",
+            "ALTER USER <username> CREATEDB;",
+        ),
+        Err(e) => report.add(
+            SECTION,
+            CheckStatus::Warn,
+            format!("Could not check privileges: {:#}", e),
+        ),
+    }
+
+    match check_wal_level(client).await {
+        Ok(level) if level == "logical" => {
+            report.add(SECTION, CheckStatus::Pass, "wal_level = logical")
+        }
+        Ok(level) => report.add_with_fix(
+            SECTION,
+            CheckStatus::Warn,
+            format!(
+                "wal_level = {}; continuous sync through subscriptions needs logical",
+                level
+            ),
+            with_doc_link("Set wal_level = logical and restart the server.", "DR001"),
+        ),
+        Err(e) => report.add(
+            SECTION,
+            CheckStatus::Warn,
+            format!("Could not check wal_level: {:#}", e),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_counts_and_json() {
+        let mut report = DoctorReport::default();
+        report.add(
+            "TLS",
+            CheckStatus::Pass,
+            "CA certificates from /etc/ssl/cert.pem",
+        );
+        report.add_with_fix(
+            "Target",
+            CheckStatus::Fail,
+            "Missing CREATEDB privilege",
+            "ALTER USER <username> CREATEDB;",
+        );
+        check_tool_version(&mut report, Some(15), Some(16));
+        check_tool_version(&mut report, Some(16), None);
+
+        assert_eq!(report.count(CheckStatus::Pass), 1);
+        assert_eq!(report.count(CheckStatus::Fail), 2);
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["checks"][0]["status"], "pass");
+        assert!(json["checks"][0].get("remediation").is_none());
+        assert_eq!(
+            json["checks"][1]["remediation"],
+            "ALTER USER <username> CREATEDB;"
+        );
+    }
+}
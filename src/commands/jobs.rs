@@ -0,0 +1,265 @@
+// ABOUTME: Jobs command implementation - schedule SerenAI cloud jobs to re-run on a cron
+// ABOUTME: Sends the spec of the last completed cloud job back with a schedule, and lists or deletes schedules
+
+use anyhow::{bail, Context, Result};
+use clap::{Args, Subcommand};
+
+use crate::remote::{last_job, RemoteClient, Schedule};
+
+#[derive(Args)]
+pub struct JobsArgs {
+    #[command(subcommand)]
+    command: JobsCommands,
+}
+
+#[derive(Subcommand)]
+enum JobsCommands {
+    /// Re-run the last completed cloud job on a cron schedule, or manage schedules
+    Schedule(ScheduleArgs),
+}
+
+#[derive(Args)]
+#[command(args_conflicts_with_subcommands = true)]
+struct ScheduleArgs {
+    #[command(subcommand)]
+    command: Option<ScheduleCommands>,
+    /// Cron expression in UTC: minute hour day-of-month month day-of-week
+    #[arg(long, value_name = "EXPR")]
+    cron: Option<String>,
+    /// API endpoint for SerenAI cloud execution
+    #[arg(long, global = true, default_value_t = String::from("https://replicate.serendb.com"))]
+    seren_api: String,
+}
+
+#[derive(Subcommand)]
+enum ScheduleCommands {
+    /// List schedules and the status of the last job each one started
+    List,
+    /// Delete a schedule
+    Delete {
+        /// Schedule ID, as shown by `jobs schedule list`
+        id: String,
+    },
+}
+
+pub async fn command(args: JobsArgs) -> Result<()> {
+    let JobsCommands::Schedule(args) = args.command;
+    let api_key = crate::interactive::get_api_key()?;
+    let client = RemoteClient::new(args.seren_api, Some(api_key))?;
+
+    match (args.command, args.cron) {
+        (Some(ScheduleCommands::List), _) => {
+            let schedules = client.list_schedules().await?;
+            if schedules.is_empty() {
+                println!("No schedules. Create one with `database-replicator jobs schedule --cron \"0 3 * * *\"`");
+            }
+            for line in schedule_lines(&schedules) {
+                println!("{}", line);
+            }
+        }
+        (Some(ScheduleCommands::Delete { id }), _) => {
+            client.delete_schedule(&id).await?;
+            println!("✓ Deleted schedule {}", id);
+        }
+        (None, Some(cron)) => {
+            validate_cron(&cron)?;
+            let mut spec = last_job::load()?.context(
+                "No completed cloud job to schedule. Run `database-replicator init` with cloud execution first.",
+            )?;
+            spec.schedule = Some(cron.trim().to_string());
+            let schedule = client.create_schedule(&spec).await?;
+            println!("✓ Created schedule {} ({})", schedule.schedule_id, schedule.cron);
+            if let Some(next_run) = &schedule.next_run_at {
+                println!("  Next run: {}", next_run);
+            }
+        }
+        (None, None) => bail!("Pass --cron to create a schedule, or use `jobs schedule list` or `jobs schedule delete <id>`"),
+    }
+    Ok(())
+}
+
+/// Check that `cron` is a five-field expression with values in range
+///
+/// Each field takes `*`, a number, a range `a-b`, any of these with a step
+/// `/n`, or a comma-separated list of them.
+fn validate_cron(cron: &str) -> Result<()> {
+    const FIELDS: [(&str, u32, u32); 5] = [
+        ("minute", 0, 59),
+        ("hour", 0, 23),
+        ("day of month", 1, 31),
+        ("month", 1, 12),
+        ("day of week", 0, 7),
+    ];
+    let fields: Vec<&str> = cron.split_whitespace().collect();
+    if fields.len() != FIELDS.len() {
+        bail!(
+            "Cron expression '{}' has {} field(s); expected 5: minute hour day-of-month month day-of-week",
+            cron,
+            fields.len()
+        );
+    }
+    for (field, (name, min, max)) in fields.iter().zip(FIELDS) {
+        for item in field.split(',') {
+            let (range, step) = match item.split_once('/') {
+                Some((range, step)) => (range, Some(step)),
+                None => (item, None),
+            };
+            if let Some(step) = step {
+                if !step.parse::<u32>().is_ok_and(|step| step > 0) {
+                    bail!(
+                        "Invalid step '{}' in the {} field of '{}'",
+                        step,
+                        name,
+                        cron
+                    );
+                }
+            }
+            if range == "*" {
+                continue;
+            }
+            let (start, end) = range.split_once('-').unwrap_or((range, range));
+            let in_range = |value: &str| {
+                value
+                    .parse::<u32>()
+                    .ok()
+                    .filter(|v| (min..=max).contains(v))
+            };
+            match (in_range(start), in_range(end)) {
+                (Some(start), Some(end)) if start <= end => {}
+                _ => bail!(
+                    "Invalid {} '{}' in '{}': expected *, a number from {} to {}, or a range",
+                    name,
+                    item,
+                    cron,
+                    min,
+                    max
+                ),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// How the last job a schedule started went
+fn last_run(schedule: &Schedule) -> String {
+    let Some(run) = &schedule.last_run else {
+        return "never run".to_string();
+    };
+    let when = run
+        .completed_at
+        .as_deref()
+        .or(run.started_at.as_deref())
+        .or(run.created_at.as_deref())
+        .unwrap_or("");
+    let mut text = format!("{} {}", run.status, when).trim_end().to_string();
+    if let Some(error) = &run.error {
+        text.push_str(&format!(" ({})", error));
+    }
+    text
+}
+
+/// Aligned table of schedules, header first
+fn schedule_lines(schedules: &[Schedule]) -> Vec<String> {
+    if schedules.is_empty() {
+        return Vec::new();
+    }
+    let mut rows = vec![[
+        "ID".to_string(),
+        "CRON".to_string(),
+        "COMMAND".to_string(),
+        "NEXT RUN".to_string(),
+        "LAST RUN".to_string(),
+    ]];
+    for schedule in schedules {
+        rows.push([
+            schedule.schedule_id.clone(),
+            schedule.cron.clone(),
+            schedule.command.clone(),
+            schedule
+                .next_run_at
+                .clone()
+                .unwrap_or_else(|| "-".to_string()),
+            last_run(schedule),
+        ]);
+    }
+
+    let mut widths = [0usize; 5];
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    rows.iter()
+        .map(|row| {
+            row.iter()
+                .zip(widths)
+                .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::remote::JobStatus;
+
+    #[test]
+    fn test_validate_cron() {
+        assert!(validate_cron("0 3 * * *").is_ok());
+        assert!(validate_cron("*/15 1-5 1,15 * 1-5").is_ok());
+        assert!(validate_cron("0 0 * * 7").is_ok());
+        assert!(validate_cron("0 3 * *").is_err());
+        assert!(validate_cron("60 3 * * *").is_err());
+        assert!(validate_cron("0 3 0 * *").is_err());
+        assert!(validate_cron("0 5-1 * * *").is_err());
+        assert!(validate_cron("*/0 * * * *").is_err());
+        assert!(validate_cron("0 3 * JAN *").is_err());
+    }
+
+    #[test]
+    fn test_schedule_lines() {
+        let failed = JobStatus {
+            job_id: "job-2".into(),
+            status: "failed".into(),
+            created_at: None,
+            started_at: Some("2026-10-16T03:00:00Z".into()),
+            completed_at: Some("2026-10-16T03:12:00Z".into()),
+            progress: None,
+            error: Some("connection refused".into()),
+            stdout: None,
+            stderr: None,
+            logs: None,
+            exit_code: Some(1),
+        };
+        let schedules = vec![
+            Schedule {
+                schedule_id: "sch-1".into(),
+                cron: "0 3 * * *".into(),
+                command: "init".into(),
+                created_at: None,
+                next_run_at: Some("2026-10-17T03:00:00Z".into()),
+                last_run: Some(failed),
+            },
+            Schedule {
+                schedule_id: "sch-22".into(),
+                cron: "30 1 * * 1".into(),
+                command: "init".into(),
+                created_at: None,
+                next_run_at: None,
+                last_run: None,
+            },
+        ];
+        assert_eq!(
+            schedule_lines(&schedules),
+            vec![
+                "ID      CRON        COMMAND  NEXT RUN              LAST RUN",
+                "sch-1   0 3 * * *   init     2026-10-17T03:00:00Z  failed 2026-10-16T03:12:00Z (connection refused)",
+                "sch-22  30 1 * * 1  init     -                     never run",
+            ]
+        );
+    }
+}
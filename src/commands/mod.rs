@@ -1,18 +1,37 @@
 // ABOUTME: Command implementations for each migration phase
 // ABOUTME: Exports validate, init, sync, status, and verify commands
 
+pub mod backup;
+pub mod cutover;
+pub mod doctor;
+pub mod history;
 pub mod init;
+pub mod jobs;
+pub mod mysql_target;
+pub mod serve;
+pub mod slots;
+pub mod sqlite_target;
 pub mod status;
 pub mod sync;
+pub mod sync_mysql;
 #[cfg(feature = "sqlite-sync")]
 pub mod sync_sqlite;
 pub mod target;
+pub mod topology;
 pub mod validate;
 pub mod verify;
 
-pub use init::init;
-pub use status::status;
-pub use sync::sync;
+pub use backup::{backup, restore};
+pub use cutover::{cutover, CutoverOptions};
+pub use doctor::command as doctor;
+pub use history::command as history;
+pub use init::{init, init_with_options, InitOptions};
+pub use jobs::command as jobs;
+pub use serve::command as serve;
+pub use slots::command as slots;
+pub use status::{status, status_with_options, StatusOptions};
+pub use sync::{sync, sync_with_options, watch_for_new_tables, SyncOptions};
 pub use target::command as target;
-pub use validate::validate;
+pub use topology::command as topology;
+pub use validate::{validate, validate_with_options, ValidateOptions};
 pub use verify::verify;
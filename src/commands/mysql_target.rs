@@ -0,0 +1,126 @@
+// ABOUTME: Experimental reverse replication from PostgreSQL into a MySQL target
+// ABOUTME: Snapshot copy and xmin-based incremental sync using ON DUPLICATE KEY UPDATE
+
+use crate::mysql::writer::{pg_row_to_mysql_values, select_expression, MySqlWriter};
+use crate::xmin::target::{self, TargetSyncConfig, TargetWriter};
+use crate::xmin::{BatchReader, SyncState, SyncStats};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use mysql_async::Value;
+use std::path::PathBuf;
+use tokio_postgres::Row;
+
+/// Default state file for MySQL targets
+///
+/// Kept separate from the PostgreSQL xmin state so both targets can be synced
+/// from the same working directory.
+pub fn default_state_path() -> PathBuf {
+    PathBuf::from(".seren-replicator/xmin-mysql-sync-state.json")
+}
+
+/// Configuration for PostgreSQL → MySQL replication, keeping state at [`default_state_path`]
+pub fn default_config() -> TargetSyncConfig {
+    TargetSyncConfig::new(default_state_path())
+}
+
+#[async_trait]
+impl TargetWriter for MySqlWriter {
+    type Values = Vec<Value>;
+    const NAME: &'static str = "MySQL";
+
+    async fn ensure_table(
+        &mut self,
+        table: &str,
+        columns: &[(String, String)],
+        primary_key_columns: &[String],
+    ) -> Result<()> {
+        MySqlWriter::ensure_table(self, table, columns, primary_key_columns).await
+    }
+
+    fn select_changes(&self, batches: BatchReader, columns: &[(String, String)]) -> BatchReader {
+        batches.with_select_list(
+            columns
+                .iter()
+                .map(|(name, udt)| select_expression(name, udt))
+                .collect(),
+        )
+    }
+
+    fn convert_row(&self, row: &Row, columns: &[(String, String)]) -> Result<Self::Values> {
+        pg_row_to_mysql_values(row, columns)
+    }
+
+    async fn apply_batch(
+        &mut self,
+        table: &str,
+        primary_key_columns: &[String],
+        all_columns: &[String],
+        rows: Vec<Self::Values>,
+    ) -> Result<u64> {
+        MySqlWriter::apply_batch(self, table, primary_key_columns, all_columns, rows).await
+    }
+
+    async fn close(self) -> Result<()> {
+        self.disconnect().await
+    }
+}
+
+/// Snapshot selected PostgreSQL tables into a MySQL database
+///
+/// Creates missing tables using the reverse type mapping, copies every row,
+/// and records the xmin high-water mark so a later `sync` only applies changes.
+pub async fn init_postgres_to_mysql(
+    source_url: &str,
+    target_url: &str,
+    config: &TargetSyncConfig,
+) -> Result<SyncStats> {
+    tracing::info!("Starting PostgreSQL to MySQL replication (experimental)...");
+
+    // A fresh state forces a full copy of every table
+    let state = SyncState::new(source_url, target_url);
+    let stats = target::run_cycle(source_url, connect(target_url).await?, config, state).await?;
+
+    tracing::info!(
+        "✅ PostgreSQL to MySQL snapshot complete: {} table(s), {} row(s)",
+        stats.tables_synced,
+        stats.rows_synced
+    );
+    Ok(stats)
+}
+
+/// Run a single incremental sync cycle against a MySQL target
+pub async fn sync_cycle(
+    source_url: &str,
+    target_url: &str,
+    config: &TargetSyncConfig,
+) -> Result<SyncStats> {
+    let state = if config.state_path.exists() {
+        SyncState::load(&config.state_path).await?
+    } else {
+        tracing::info!("No MySQL sync state found, performing full copy");
+        SyncState::new(source_url, target_url)
+    };
+    target::run_cycle(source_url, connect(target_url).await?, config, state).await
+}
+
+/// Run incremental sync cycles until a shutdown signal is received
+///
+/// Deletes are not propagated to MySQL targets; only inserts and updates are applied.
+pub async fn run_sync(
+    source_url: &str,
+    target_url: &str,
+    config: &TargetSyncConfig,
+    shutdown: tokio::sync::broadcast::Receiver<()>,
+) -> Result<()> {
+    target::run_sync("MySQL", config.sync_interval, shutdown, || {
+        sync_cycle(source_url, target_url, config)
+    })
+    .await
+}
+
+async fn connect(target_url: &str) -> Result<MySqlWriter> {
+    let conn = crate::mysql::connect_mysql(target_url)
+        .await
+        .context("Failed to connect to MySQL target")?;
+    Ok(MySqlWriter::new(conn))
+}
@@ -0,0 +1,90 @@
+// ABOUTME: serve command - runs the replicator as a long-lived gRPC service
+// ABOUTME: Jobs are submitted as JobSpecs and run locally, like an on-prem SerenAI cloud
+
+use anyhow::{bail, Context, Result};
+use clap::Args;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tonic::transport::Identity;
+
+use crate::serve::{serve, JobManager};
+
+#[derive(Args)]
+pub struct ServeArgs {
+    /// Address to listen on for gRPC requests
+    #[arg(long, default_value = "127.0.0.1:50051")]
+    listen: SocketAddr,
+    /// Bearer token clients must send (required unless listening on localhost)
+    #[arg(long, env = "DATABASE_REPLICATOR_SERVE_TOKEN", hide_env_values = true)]
+    token: Option<String>,
+    /// PEM certificate chain to serve the API over TLS (required unless listening on localhost)
+    #[arg(long, requires = "tls_key")]
+    tls_cert: Option<PathBuf>,
+    /// PEM private key for --tls-cert
+    #[arg(long, requires = "tls_cert")]
+    tls_key: Option<PathBuf>,
+    /// Finished jobs to keep for status and log queries; older ones are forgotten
+    #[arg(long, default_value_t = crate::serve::jobs::FINISHED_JOBS_KEPT)]
+    keep_finished_jobs: usize,
+    /// Seconds between sync cycles for jobs that keep syncing
+    #[arg(long, default_value = "60")]
+    sync_interval: u64,
+    /// Directory for each job's sync state
+    #[arg(long, default_value = ".seren-replicator/serve")]
+    state_dir: PathBuf,
+    /// Base64 X25519 private key for opening the sealed credentials of submitted jobs
+    #[arg(long, env = "DATABASE_REPLICATOR_SEALING_KEY", hide_env_values = true)]
+    sealing_key: Option<String>,
+}
+
+pub async fn command(args: ServeArgs) -> Result<()> {
+    if !args.listen.ip().is_loopback() {
+        if args.token.is_none() {
+            bail!(
+                "Refusing to listen on {} without a token.\n\
+                 Set --token (or DATABASE_REPLICATOR_SERVE_TOKEN), or listen on 127.0.0.1.",
+                args.listen
+            );
+        }
+        if args.tls_cert.is_none() {
+            bail!(
+                "Refusing to listen on {} without TLS; the token would be sent in plaintext.\n\
+                 Set --tls-cert and --tls-key, or listen on 127.0.0.1.",
+                args.listen
+            );
+        }
+    }
+    let tls = match (&args.tls_cert, &args.tls_key) {
+        (Some(cert), Some(key)) => {
+            let cert = std::fs::read(cert)
+                .with_context(|| format!("Failed to read TLS certificate {}", cert.display()))?;
+            let key = std::fs::read(key)
+                .with_context(|| format!("Failed to read TLS key {}", key.display()))?;
+            Some(Identity::from_pem(cert, key))
+        }
+        _ => None,
+    };
+
+    let listener = tokio::net::TcpListener::bind(args.listen)
+        .await
+        .with_context(|| format!("Failed to listen on {}", args.listen))?;
+    let mut jobs = JobManager::new(args.state_dir, Duration::from_secs(args.sync_interval))
+        .with_finished_job_limit(args.keep_finished_jobs);
+    if let Some(key) = &args.sealing_key {
+        jobs = jobs.with_sealing_key(crate::remote::sealing::parse_secret_key(key)?);
+    }
+    let jobs = Arc::new(jobs);
+
+    tracing::info!(
+        "Replicator service listening on {} ({})",
+        args.listen,
+        if tls.is_some() { "TLS" } else { "plaintext" }
+    );
+    serve(listener, args.token, tls, jobs, async {
+        let _ = tokio::signal::ctrl_c().await;
+        tracing::info!("Received shutdown signal, stopping replicator service");
+    })
+    .await
+}
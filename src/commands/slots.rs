@@ -0,0 +1,191 @@
+// ABOUTME: Slots command implementation - inspect and drop replication slots on the source
+// ABOUTME: Shows the WAL each slot retains and cleans up slots left behind by init or sync
+
+use anyhow::{bail, Result};
+use clap::{Args, Subcommand};
+
+use crate::migration::estimation::format_bytes;
+use crate::postgres::connect_with_retry;
+use crate::replication::slot::{self, SlotInfo};
+
+#[derive(Args)]
+pub struct SlotsArgs {
+    #[command(subcommand)]
+    command: SlotsCommands,
+    /// Source server; defaults to the servers of slots this tool created
+    #[arg(long, global = true)]
+    pub source: Option<String>,
+}
+
+#[derive(Subcommand)]
+enum SlotsCommands {
+    /// List replication slots and the WAL each one keeps on the source
+    List,
+    /// Drop a replication slot, or every orphaned slot this tool created
+    Drop {
+        /// Name of the slot to drop (requires --source)
+        #[arg(required_unless_present = "orphaned", conflicts_with = "orphaned")]
+        name: Option<String>,
+        /// Drop tracked slots that are inactive and whose subscription is gone
+        #[arg(long)]
+        orphaned: bool,
+        /// Disconnect a subscriber that is still using the slot
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+pub async fn command(args: SlotsArgs) -> Result<()> {
+    match args.command {
+        SlotsCommands::List => list(args.source.as_deref()).await,
+        SlotsCommands::Drop { orphaned: true, .. } => {
+            let dropped = slot::drop_orphaned_slots().await?;
+            if dropped.is_empty() {
+                println!("No orphaned replication slots found");
+            } else {
+                println!(
+                    "Dropped {} orphaned replication slot(s): {}",
+                    dropped.len(),
+                    dropped.join(", ")
+                );
+            }
+            Ok(())
+        }
+        SlotsCommands::Drop { name, force, .. } => {
+            let name = name.expect("clap requires a name without --orphaned");
+            let Some(source) = args.source else {
+                bail!("--source is required to drop slot '{}'", name);
+            };
+            let client = connect_with_retry(&source).await?;
+            slot::drop_slot(&client, &name, force).await?;
+            slot::untrack(&name, &source);
+            println!("Dropped replication slot '{}'", name);
+            Ok(())
+        }
+    }
+}
+
+async fn list(source: Option<&str>) -> Result<()> {
+    let tracked = slot::tracked_slots()?;
+    let servers: Vec<String> = match source {
+        Some(source) => vec![source.to_string()],
+        None => {
+            let mut servers: Vec<String> = Vec::new();
+            for slot in &tracked {
+                let key = crate::postgres::pool::host_key(&slot.source_url);
+                if !servers
+                    .iter()
+                    .any(|url| crate::postgres::pool::host_key(url) == key)
+                {
+                    servers.push(slot.source_url.clone());
+                }
+            }
+            servers
+        }
+    };
+    if servers.is_empty() {
+        println!("No tracked replication slots; pass --source to list a server's slots");
+        return Ok(());
+    }
+
+    for server in servers {
+        let key = crate::postgres::pool::host_key(&server);
+        let client = connect_with_retry(&server).await?;
+        let slots = slot::list_slots(&client).await?;
+        println!("{}:", key);
+        if slots.is_empty() {
+            println!("  No replication slots");
+            continue;
+        }
+        let tracked_names: Vec<&str> = tracked
+            .iter()
+            .filter(|slot| crate::postgres::pool::host_key(&slot.source_url) == key)
+            .map(|slot| slot.name.as_str())
+            .collect();
+        for line in slot_lines(&slots, &tracked_names) {
+            println!("  {}", line);
+        }
+    }
+    Ok(())
+}
+
+/// Aligned table of slots, header first
+fn slot_lines(slots: &[SlotInfo], tracked: &[&str]) -> Vec<String> {
+    let mut rows = vec![[
+        "SLOT".to_string(),
+        "TYPE".to_string(),
+        "DATABASE".to_string(),
+        "ACTIVE".to_string(),
+        "RETAINED WAL".to_string(),
+        "CREATED BY".to_string(),
+    ]];
+    for slot in slots {
+        rows.push([
+            slot.name.clone(),
+            slot.slot_type.clone(),
+            slot.database.clone().unwrap_or_else(|| "-".to_string()),
+            if slot.active { "yes" } else { "no" }.to_string(),
+            slot.retained_wal_bytes
+                .map(format_bytes)
+                .unwrap_or_else(|| "-".to_string()),
+            if tracked.contains(&slot.name.as_str()) {
+                "database-replicator"
+            } else {
+                "-"
+            }
+            .to_string(),
+        ]);
+    }
+
+    let mut widths = [0usize; 6];
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    rows.iter()
+        .map(|row| {
+            row.iter()
+                .zip(widths)
+                .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slot_lines() {
+        let slots = vec![
+            SlotInfo {
+                name: "seren_migration_sub".to_string(),
+                database: Some("app".to_string()),
+                slot_type: "logical".to_string(),
+                active: false,
+                retained_wal_bytes: Some(1536),
+            },
+            SlotInfo {
+                name: "standby_1".to_string(),
+                database: None,
+                slot_type: "physical".to_string(),
+                active: true,
+                retained_wal_bytes: None,
+            },
+        ];
+        let lines = slot_lines(&slots, &["seren_migration_sub"]);
+        assert_eq!(
+            lines,
+            vec![
+                "SLOT                 TYPE      DATABASE  ACTIVE  RETAINED WAL  CREATED BY",
+                "seren_migration_sub  logical   app       no      1.5 KB        database-replicator",
+                "standby_1            physical  -         yes     -             -",
+            ]
+        );
+    }
+}
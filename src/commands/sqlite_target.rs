@@ -0,0 +1,115 @@
+// ABOUTME: Experimental PostgreSQL to SQLite file replication for edge/offline replicas
+// ABOUTME: Snapshot copy and xmin-based periodic re-sync into typed SQLite tables
+
+use crate::postgres::postgis::SpatialEncoding;
+use crate::sqlite::writer::{pg_row_to_sqlite_values, SqliteWriter};
+use crate::xmin::target::{self, TargetSyncConfig, TargetWriter};
+use crate::xmin::{BatchReader, SyncState, SyncStats};
+use anyhow::Result;
+use async_trait::async_trait;
+use rusqlite::types::Value;
+use std::path::{Path, PathBuf};
+use tokio_postgres::Row;
+
+/// Default state file for SQLite targets
+pub fn default_state_path() -> PathBuf {
+    PathBuf::from(".seren-replicator/xmin-sqlite-sync-state.json")
+}
+
+/// Configuration for PostgreSQL → SQLite replication, keeping state at [`default_state_path`]
+pub fn default_config() -> TargetSyncConfig {
+    TargetSyncConfig::new(default_state_path())
+}
+
+#[async_trait]
+impl TargetWriter for SqliteWriter {
+    type Values = Vec<Value>;
+    const NAME: &'static str = "SQLite";
+
+    async fn ensure_table(
+        &mut self,
+        table: &str,
+        columns: &[(String, String)],
+        primary_key_columns: &[String],
+    ) -> Result<()> {
+        SqliteWriter::ensure_table(self, table, columns, primary_key_columns)
+    }
+
+    fn select_changes(&self, batches: BatchReader, columns: &[(String, String)]) -> BatchReader {
+        batches.with_column_types(columns, SpatialEncoding::Ewkt)
+    }
+
+    fn convert_row(&self, row: &Row, columns: &[(String, String)]) -> Result<Self::Values> {
+        Ok(pg_row_to_sqlite_values(row, columns))
+    }
+
+    async fn apply_batch(
+        &mut self,
+        table: &str,
+        primary_key_columns: &[String],
+        all_columns: &[String],
+        rows: Vec<Self::Values>,
+    ) -> Result<u64> {
+        SqliteWriter::apply_batch(self, table, primary_key_columns, all_columns, rows)
+    }
+}
+
+/// Snapshot selected PostgreSQL tables into a SQLite file
+///
+/// Creates the file and any missing tables, copies every row, and records the
+/// xmin high-water mark so later re-syncs only apply changed rows.
+pub async fn init_postgres_to_sqlite(
+    source_url: &str,
+    target_path: &Path,
+    config: &TargetSyncConfig,
+) -> Result<SyncStats> {
+    tracing::info!("Starting PostgreSQL to SQLite replication (experimental)...");
+
+    let state = SyncState::new(source_url, &target_label(target_path));
+    let writer = SqliteWriter::open(target_path)?;
+    let stats = target::run_cycle(source_url, writer, config, state).await?;
+
+    tracing::info!(
+        "✅ PostgreSQL to SQLite snapshot complete: {} table(s), {} row(s)",
+        stats.tables_synced,
+        stats.rows_synced
+    );
+    Ok(stats)
+}
+
+/// Run a single re-sync cycle against a SQLite target
+pub async fn sync_cycle(
+    source_url: &str,
+    target_path: &Path,
+    config: &TargetSyncConfig,
+) -> Result<SyncStats> {
+    let state = if config.state_path.exists() && target_path.exists() {
+        SyncState::load(&config.state_path).await?
+    } else {
+        // A missing replica file means the state no longer describes it
+        tracing::info!("No SQLite sync state found, performing full copy");
+        SyncState::new(source_url, &target_label(target_path))
+    };
+    let writer = SqliteWriter::open(target_path)?;
+    target::run_cycle(source_url, writer, config, state).await
+}
+
+/// Run re-sync cycles until a shutdown signal is received
+///
+/// Deletes are not propagated to SQLite targets; only inserts and updates are applied.
+pub async fn run_sync(
+    source_url: &str,
+    target_path: &Path,
+    config: &TargetSyncConfig,
+    shutdown: tokio::sync::broadcast::Receiver<()>,
+) -> Result<()> {
+    target::run_sync("SQLite", config.sync_interval, shutdown, || {
+        sync_cycle(source_url, target_path, config)
+    })
+    .await
+}
+
+/// Target identifier stored in the sync state
+fn target_label(target_path: &Path) -> String {
+    format!("sqlite:{}", target_path.display())
+}
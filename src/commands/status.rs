@@ -1,12 +1,19 @@
 // ABOUTME: Status command implementation - Check replication health
 // ABOUTME: Displays real-time replication lag and subscription status
 
-use crate::replication::{get_replication_lag, get_subscription_status, is_replication_caught_up};
+use crate::replication::slot::SlotWatchdog;
+use crate::replication::{
+    get_replication_lag, get_subscription_status, get_subscription_tables, is_replication_caught_up,
+};
+use crate::xmin::{SyncState, XminReader};
 use crate::{migration, postgres::connect};
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 /// Format milliseconds into a human-readable duration string
-fn format_duration(ms: i64) -> String {
+pub(crate) fn format_duration(ms: i64) -> String {
     if ms < 1000 {
         format!("{}ms", ms)
     } else if ms < 60_000 {
@@ -22,6 +29,65 @@ fn format_duration(ms: i64) -> String {
     }
 }
 
+/// Options for [`status_with_options`]
+#[derive(Debug, Clone, Default)]
+pub struct StatusOptions {
+    /// Re-check on this interval until interrupted or the lag threshold is exceeded
+    pub watch_interval: Option<Duration>,
+    /// Fail when a database's replay lag exceeds this or its replication is not active
+    pub max_lag: Option<Duration>,
+    /// URL that receives a JSON POST when the lag threshold is exceeded
+    pub notify_webhook: Option<String>,
+    /// Print each report as one line of JSON on stdout instead of logging it
+    pub json: bool,
+    /// xmin sync state file to read per-table progress from
+    /// (defaults to [`SyncState::default_path`])
+    pub state_path: Option<PathBuf>,
+}
+
+/// Replication status observed for one database
+#[derive(Debug, Clone, Serialize)]
+struct DatabaseStatus {
+    database: String,
+    subscription: String,
+    /// Whether the source has a walsender for this database's subscription
+    active: bool,
+    /// Largest replay lag reported; `None` once an idle subscriber has caught up
+    replay_lag_ms: Option<i64>,
+    tables: Vec<TableProgress>,
+}
+
+/// Replication progress of one table
+#[derive(Debug, Clone, Serialize)]
+struct TableProgress {
+    schema: String,
+    table: String,
+    /// "logical" or "xmin"
+    method: &'static str,
+    /// Subscription sync state of the table (logical replication)
+    state: Option<String>,
+    /// LSN the table's initial sync finished at (logical replication)
+    sync_lsn: Option<String>,
+    /// Last synced xmin high-water mark (xmin sync)
+    last_xmin: Option<u32>,
+    last_sync_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Source rows changed since `last_xmin`, not yet synced
+    rows_pending: Option<i64>,
+    last_cycle_ms: Option<u64>,
+    rows_per_sec: Option<f64>,
+    /// When `sync --verify-rate` last found the table matching (xmin sync)
+    last_verified_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Repairs `sync --verify-rate` queued after finding drift (xmin sync)
+    drift_repairs: Option<u64>,
+}
+
+/// One status check as printed by `--json`
+#[derive(Serialize)]
+struct StatusReport<'a> {
+    checked_at: chrono::DateTime<chrono::Utc>,
+    databases: &'a [DatabaseStatus],
+}
+
 /// Check replication status and display health information
 ///
 /// This command performs Phase 4 of the migration process:
@@ -87,8 +153,95 @@ pub async fn status(
     source_url: &str,
     target_url: &str,
     filter: Option<crate::filters::ReplicationFilter>,
+) -> Result<()> {
+    status_with_options(source_url, target_url, filter, StatusOptions::default()).await
+}
+
+/// Check replication status, optionally watching it and enforcing a lag limit
+///
+/// Displays the same report as [`status`]. With `watch_interval` set, the
+/// report repeats until Ctrl+C. With `max_lag` set, the first check where a
+/// database lags by more than `max_lag`, or has no active replication, sends
+/// the alert to `notify_webhook` (if set) and returns an error, so cron jobs
+/// and monitoring checks can rely on the exit code.
+///
+/// # Errors
+///
+/// Returns an error if the lag threshold is exceeded, or for the same
+/// reasons as [`status`].
+pub async fn status_with_options(
+    source_url: &str,
+    target_url: &str,
+    filter: Option<crate::filters::ReplicationFilter>,
+    options: StatusOptions,
 ) -> Result<()> {
     let filter = filter.unwrap_or_else(crate::filters::ReplicationFilter::empty);
+    let state_path = options
+        .state_path
+        .clone()
+        .unwrap_or_else(SyncState::default_path);
+
+    let slot_watchdog = SlotWatchdog::new();
+    loop {
+        let databases = check_status(source_url, target_url, &filter, &state_path).await?;
+        if let Err(e) = check_source_slots(source_url, &slot_watchdog).await {
+            tracing::warn!("⚠ Failed to check replication slots on source: {:#}", e);
+        }
+
+        if options.json {
+            let report = StatusReport {
+                checked_at: chrono::Utc::now(),
+                databases: &databases,
+            };
+            println!(
+                "{}",
+                serde_json::to_string(&report).context("Failed to serialize status report")?
+            );
+        }
+
+        if let Some(max_lag) = options.max_lag {
+            let breaches = lag_breaches(&databases, max_lag);
+            if !breaches.is_empty() {
+                for breach in &breaches {
+                    tracing::error!("✗ {}", breach);
+                }
+                if let Some(url) = &options.notify_webhook {
+                    match send_lag_alert(url, max_lag, &breaches).await {
+                        Ok(()) => tracing::info!("Sent lag alert to webhook"),
+                        Err(e) => tracing::warn!("⚠ Failed to send lag alert: {:#}", e),
+                    }
+                }
+                bail!(
+                    "Replication is behind or inactive on {} database(s) (max lag {})",
+                    breaches.len(),
+                    format_duration(max_lag.as_millis() as i64)
+                );
+            }
+        }
+
+        let Some(interval) = options.watch_interval else {
+            return Ok(());
+        };
+        tokio::select! {
+            _ = tokio::time::sleep(interval) => {}
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+        }
+    }
+}
+
+/// Warn about inactive replication slots holding growing amounts of WAL
+async fn check_source_slots(source_url: &str, watchdog: &SlotWatchdog) -> Result<()> {
+    let client = connect(source_url).await?;
+    watchdog.check(source_url, &client).await
+}
+
+/// Display one status report and return what it found for each database
+async fn check_status(
+    source_url: &str,
+    target_url: &str,
+    filter: &crate::filters::ReplicationFilter,
+    state_path: &Path,
+) -> Result<Vec<DatabaseStatus>> {
     let sub_name_template = "seren_migration_sub";
 
     tracing::info!("Checking replication status...");
@@ -121,7 +274,7 @@ pub async fn status(
     if databases.is_empty() {
         tracing::warn!("⚠ No databases matched the filter criteria");
         tracing::warn!("  No replication status to show");
-        return Ok(());
+        return Ok(Vec::new());
     }
 
     tracing::info!("Found {} database(s) to check:", databases.len());
@@ -145,6 +298,7 @@ pub async fn status(
 
     let mut all_caught_up = true;
     let mut any_active = false;
+    let mut statuses = Vec::with_capacity(databases.len());
 
     for db in &databases {
         // Build subscription name for this database
@@ -252,6 +406,48 @@ pub async fn status(
             }
         }
 
+        // Per-table breakdown
+        let mut tables = Vec::new();
+        if !target_stats.is_empty() {
+            let subscription_tables = get_subscription_tables(&target_client, &sub_name)
+                .await
+                .context(format!(
+                    "Failed to query subscription tables for database '{}'",
+                    db.name
+                ))?;
+            tables.extend(subscription_tables.into_iter().map(|t| TableProgress {
+                schema: t.schema,
+                table: t.table,
+                method: "logical",
+                state: Some(t.state),
+                sync_lsn: t.sync_lsn,
+                last_xmin: None,
+                last_sync_at: None,
+                rows_pending: None,
+                last_cycle_ms: None,
+                rows_per_sec: None,
+                last_verified_at: None,
+                drift_repairs: None,
+            }));
+        }
+        tables.extend(xmin_table_progress(source_url, &db.name, state_path, &target_client).await?);
+
+        if !tables.is_empty() {
+            tracing::info!("Tables:");
+            for line in table_progress_lines(&tables) {
+                tracing::info!("  {}", line);
+            }
+            tracing::info!("");
+        }
+
+        statuses.push(DatabaseStatus {
+            database: db.name.clone(),
+            subscription: sub_name.clone(),
+            active: !source_stats.is_empty(),
+            replay_lag_ms: source_stats.iter().filter_map(|s| s.replay_lag_ms).max(),
+            tables,
+        });
+
         // Per-database summary
         if caught_up {
             tracing::info!("✓ Database '{}' is CAUGHT UP", db.name);
@@ -281,9 +477,198 @@ pub async fn status(
     }
     tracing::info!("========================================");
 
+    Ok(statuses)
+}
+
+/// Per-table xmin sync progress for a database, if it has sync state
+///
+/// The state comes from the sync state file, or from the target when sync runs
+/// with `--stateless`. Pending rows are counted on the source with
+/// [`XminReader::estimate_changes`], which scans each table.
+async fn xmin_table_progress(
+    source_url: &str,
+    database: &str,
+    state_path: &Path,
+    target_client: &tokio_postgres::Client,
+) -> Result<Vec<TableProgress>> {
+    let db_url = crate::utils::replace_database_in_connection_string(source_url, database)?;
+    let state = match load_xmin_state(state_path, database).await {
+        Some(state) => state,
+        None => {
+            let source_id = crate::xmin::source_identifier(&db_url)?;
+            match SyncState::load_from_target(target_client, &source_id).await? {
+                Some(state) => state,
+                None => return Ok(Vec::new()),
+            }
+        }
+    };
+
+    let client = connect(&db_url).await.context(format!(
+        "Failed to connect to source database '{}'",
+        database
+    ))?;
+    let reader = XminReader::new(&client);
+
+    let mut table_states: Vec<_> = state.tables.values().collect();
+    table_states.sort_by_key(|t| t.qualified_name());
+
+    let mut tables = Vec::with_capacity(table_states.len());
+    for table in table_states {
+        let rows_pending = match reader
+            .estimate_changes(&table.schema, &table.table, table.last_xmin)
+            .await
+        {
+            Ok(count) => Some(count),
+            Err(e) => {
+                tracing::warn!("⚠ Could not count pending rows: {:#}", e);
+                None
+            }
+        };
+        tables.push(TableProgress {
+            schema: table.schema.clone(),
+            table: table.table.clone(),
+            method: "xmin",
+            state: None,
+            sync_lsn: None,
+            last_xmin: Some(table.last_xmin),
+            last_sync_at: Some(table.last_sync_at),
+            rows_pending,
+            last_cycle_ms: Some(table.last_cycle_ms),
+            rows_per_sec: table.rows_per_sec(),
+            last_verified_at: table.last_verified_at,
+            drift_repairs: Some(table.drift_repairs),
+        });
+    }
+    Ok(tables)
+}
+
+/// Load the xmin sync state that `sync` keeps for `database`
+pub(crate) async fn load_xmin_state(state_path: &Path, database: &str) -> Option<SyncState> {
+    // Several databases synced together each get their own file
+    let per_database = SyncState::database_path(state_path, database);
+    if let Ok(state) = SyncState::load(&per_database).await {
+        return Some(state);
+    }
+    let state = SyncState::load(state_path).await.ok()?;
+    let synced = crate::utils::parse_postgres_url(&state.source_url).ok()?;
+    (synced.database == database).then_some(state)
+}
+
+/// Render per-table progress as aligned text rows, header first
+fn table_progress_lines(tables: &[TableProgress]) -> Vec<String> {
+    let now = chrono::Utc::now();
+    let mut rows = vec![[
+        "TABLE".to_string(),
+        "METHOD".to_string(),
+        "STATE".to_string(),
+        "POSITION".to_string(),
+        "PENDING".to_string(),
+        "LAST CYCLE".to_string(),
+        "ROWS/SEC".to_string(),
+    ]];
+    for t in tables {
+        let state = match (t.state.as_deref(), t.last_sync_at) {
+            (Some(state), _) => subscription_table_state(state).to_string(),
+            (None, Some(at)) => format!(
+                "synced {} ago",
+                format_duration((now - at).num_milliseconds().max(0))
+            ),
+            (None, None) => "-".to_string(),
+        };
+        let position = t
+            .sync_lsn
+            .clone()
+            .or_else(|| t.last_xmin.map(|xmin| format!("xmin {}", xmin)))
+            .unwrap_or_else(|| "-".to_string());
+        let or_dash = |value: Option<String>| value.unwrap_or_else(|| "-".to_string());
+        rows.push([
+            format!("{}.{}", t.schema, t.table),
+            t.method.to_string(),
+            state,
+            position,
+            or_dash(t.rows_pending.map(|n| n.to_string())),
+            or_dash(t.last_cycle_ms.map(|ms| format_duration(ms as i64))),
+            or_dash(t.rows_per_sec.map(|r| format!("{:.1}", r))),
+        ]);
+    }
+
+    let mut widths = [0usize; 7];
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    rows.iter()
+        .map(|row| {
+            row.iter()
+                .zip(widths)
+                .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        })
+        .collect()
+}
+
+/// Human-readable pg_subscription_rel state
+fn subscription_table_state(state: &str) -> &str {
+    match state {
+        "i" => "initializing",
+        "d" => "copying data",
+        "f" => "copy finished",
+        "s" => "synchronized",
+        "r" => "ready",
+        other => other,
+    }
+}
+
+/// Describe each database whose replication violates `max_lag`
+fn lag_breaches(lags: &[DatabaseStatus], max_lag: Duration) -> Vec<String> {
+    let max_ms = max_lag.as_millis() as i64;
+    lags.iter()
+        .filter_map(|lag| match lag.replay_lag_ms {
+            _ if !lag.active => Some(format!(
+                "Database '{}' has no active replication",
+                lag.database
+            )),
+            Some(ms) if ms > max_ms => Some(format!(
+                "Database '{}' replay lag {} exceeds {}",
+                lag.database,
+                format_duration(ms),
+                format_duration(max_ms)
+            )),
+            _ => None,
+        })
+        .collect()
+}
+
+/// POST a lag alert to a webhook as JSON
+async fn send_lag_alert(url: &str, max_lag: Duration, breaches: &[String]) -> Result<()> {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()
+        .context("Failed to create HTTP client")?;
+    client
+        .post(url)
+        .json(&lag_alert_payload(max_lag, breaches))
+        .send()
+        .await
+        .context("Failed to reach webhook")?
+        .error_for_status()
+        .context("Webhook rejected the alert")?;
     Ok(())
 }
 
+fn lag_alert_payload(max_lag: Duration, breaches: &[String]) -> serde_json::Value {
+    serde_json::json!({
+        "event": "replication_lag",
+        "max_lag_seconds": max_lag.as_secs_f64(),
+        "text": format!("database-replicator: {}", breaches.join("; ")),
+        "breaches": breaches,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -303,6 +688,119 @@ mod tests {
         assert_eq!(format_duration(3660000), "1h 1m");
     }
 
+    fn lag(database: &str, active: bool, replay_lag_ms: Option<i64>) -> DatabaseStatus {
+        DatabaseStatus {
+            database: database.to_string(),
+            subscription: "seren_migration_sub".to_string(),
+            active,
+            replay_lag_ms,
+            tables: Vec::new(),
+        }
+    }
+
+    fn progress(table: &str, method: &'static str) -> TableProgress {
+        TableProgress {
+            schema: "public".to_string(),
+            table: table.to_string(),
+            method,
+            state: None,
+            sync_lsn: None,
+            last_xmin: None,
+            last_sync_at: None,
+            rows_pending: None,
+            last_cycle_ms: None,
+            rows_per_sec: None,
+            last_verified_at: None,
+            drift_repairs: None,
+        }
+    }
+
+    #[test]
+    fn test_table_progress_lines() {
+        let logical = TableProgress {
+            state: Some("r".to_string()),
+            sync_lsn: Some("0/16B3748".to_string()),
+            ..progress("orders", "logical")
+        };
+        let xmin = TableProgress {
+            last_xmin: Some(1262),
+            last_sync_at: Some(chrono::Utc::now() - chrono::Duration::seconds(5)),
+            rows_pending: Some(42),
+            last_cycle_ms: Some(1500),
+            rows_per_sec: Some(200.0),
+            ..progress("events", "xmin")
+        };
+
+        let lines = table_progress_lines(&[logical, xmin]);
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("TABLE          METHOD   STATE"));
+        assert!(lines[1].starts_with("public.orders  logical  ready"));
+        assert!(lines[1].contains("0/16B3748"));
+        assert!(lines[1].ends_with('-'));
+        assert!(lines[2].starts_with("public.events  xmin     synced "));
+        assert!(lines[2].contains("xmin 1262"));
+        assert!(lines[2].ends_with("42       1.5s        200.0"));
+    }
+
+    #[test]
+    fn test_status_report_json() {
+        let mut status = lag("app", true, Some(250));
+        status.tables.push(TableProgress {
+            last_xmin: Some(7),
+            rows_pending: Some(3),
+            drift_repairs: Some(1),
+            ..progress("users", "xmin")
+        });
+        let report = StatusReport {
+            checked_at: chrono::Utc::now(),
+            databases: &[status],
+        };
+
+        let json = serde_json::to_value(&report).unwrap();
+        let database = &json["databases"][0];
+        assert_eq!(database["database"], "app");
+        assert_eq!(database["replay_lag_ms"], 250);
+        assert_eq!(database["tables"][0]["method"], "xmin");
+        assert_eq!(database["tables"][0]["last_xmin"], 7);
+        assert_eq!(database["tables"][0]["rows_pending"], 3);
+        assert!(database["tables"][0]["sync_lsn"].is_null());
+        assert_eq!(database["tables"][0]["drift_repairs"], 1);
+    }
+
+    #[test]
+    fn test_lag_breaches() {
+        let lags = [
+            lag("caught_up", true, None),
+            lag("within", true, Some(10_000)),
+            lag("behind", true, Some(12_500)),
+            lag("stopped", false, None),
+        ];
+
+        let breaches = lag_breaches(&lags, Duration::from_secs(10));
+        assert_eq!(
+            breaches,
+            vec![
+                "Database 'behind' replay lag 12.5s exceeds 10.0s".to_string(),
+                "Database 'stopped' has no active replication".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lag_alert_payload() {
+        let payload = lag_alert_payload(
+            Duration::from_secs(30),
+            &["Database 'app' has no active replication".to_string()],
+        );
+        assert_eq!(payload["event"], "replication_lag");
+        assert_eq!(payload["max_lag_seconds"], 30.0);
+        assert_eq!(
+            payload["text"],
+            "database-replicator: Database 'app' has no active replication"
+        );
+        assert_eq!(payload["breaches"].as_array().unwrap().len(), 1);
+    }
+
     #[tokio::test]
     #[ignore]
     async fn test_status_command() {
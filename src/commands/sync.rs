@@ -3,12 +3,14 @@
 
 use crate::migration;
 use crate::postgres::connect;
+use crate::replication::slot::slot_exists;
 use crate::replication::{
-    create_publication, create_subscription, detect_subscription_state, drop_subscription,
-    wait_for_sync, SubscriptionState,
+    add_to_publication, create_publication, create_subscription, create_subscription_for_slot,
+    detect_subscription_state, drop_subscription, get_subscription_tables, list_subscriptions,
+    publication_diff, refresh_subscription, update_publication, wait_for_sync, SubscriptionState,
 };
 use crate::serendb::{resolve_target_mode, ConsoleClient, TargetMode};
-use anyhow::{anyhow, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 
 /// Set up logical replication between source and target databases
 ///
@@ -95,10 +97,86 @@ pub async fn sync(
     sync_timeout_secs: Option<u64>,
     force: bool,
 ) -> Result<()> {
-    let pub_name_template = publication_name.unwrap_or("seren_migration_pub");
-    let sub_name_template = subscription_name.unwrap_or("seren_migration_sub");
+    sync_with_options(
+        source_url,
+        target_url,
+        filter,
+        publication_name,
+        subscription_name,
+        sync_timeout_secs,
+        force,
+        SyncOptions::default(),
+    )
+    .await
+}
+
+/// Optional logical replication setup behavior
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SyncOptions {
+    /// Create the `_replicator` audit schema on each target database and
+    /// record the setup there (see [`crate::audit`])
+    pub audit_schema: bool,
+    /// `init` already created each database's replication slot, named after
+    /// its subscription, and loaded the target from the slot's snapshot; new
+    /// subscriptions stream from that slot instead of copying tables again
+    pub use_existing_slots: bool,
+    /// `init` only copied each database's high-priority tables, and those they
+    /// reference, and left the other tables out of the slot's publication.
+    /// Once a subscription streams from the slot, the other tables are
+    /// published and the subscription copies them in the background.
+    pub copy_remaining_tables: bool,
+    /// Bring existing publications and subscriptions in line with the filter:
+    /// newly selected tables get their schema on the target, are published,
+    /// and are copied before streaming; deselected tables are unpublished
+    pub refresh_publication: bool,
+}
+
+/// Publication and subscription name templates used when none are given
+pub(crate) const DEFAULT_PUBLICATION_NAME: &str = "seren_migration_pub";
+pub(crate) const DEFAULT_SUBSCRIPTION_NAME: &str = "seren_migration_sub";
+
+/// The publication or subscription name for one database
+///
+/// A single database uses the template as-is; with several, the database
+/// name is appended to keep the names apart.
+pub(crate) fn replication_object_name(
+    template: &str,
+    db_name: &str,
+    database_count: usize,
+) -> String {
+    if database_count == 1 {
+        template.to_string()
+    } else {
+        format!("{}_{}", template, db_name)
+    }
+}
+
+/// Set up logical replication, with the extra behavior in `options`
+///
+/// Same as [`sync`]. With `options.audit_schema`, each target database gets
+/// the `_replicator` schema and a `sync` run once its subscription is active;
+/// `_replicator.table_freshness` then follows the subscription's progress.
+#[allow(clippy::too_many_arguments)]
+pub async fn sync_with_options(
+    source_url: &str,
+    target_url: &str,
+    filter: Option<crate::filters::ReplicationFilter>,
+    publication_name: Option<&str>,
+    subscription_name: Option<&str>,
+    sync_timeout_secs: Option<u64>,
+    force: bool,
+    options: SyncOptions,
+) -> Result<()> {
+    let pub_name_template = publication_name.unwrap_or(DEFAULT_PUBLICATION_NAME);
+    let sub_name_template = subscription_name.unwrap_or(DEFAULT_SUBSCRIPTION_NAME);
     let timeout = sync_timeout_secs.unwrap_or(300); // 5 minutes default
     let filter = filter.unwrap_or_else(crate::filters::ReplicationFilter::empty);
+    if filter.table_rules().requires_xmin_sync() {
+        bail!(
+            "Column transforms, table sources, renamed tables or columns, time filters, and retention cannot be applied to logical replication, which streams rows unchanged into same-named tables and cannot filter rows by NOW().\n\
+             The sync command polls by xmin instead when any of them is configured."
+        );
+    }
 
     tracing::info!("Starting logical replication setup...");
 
@@ -195,21 +273,8 @@ pub async fn sync(
         ))?;
 
         // Build database-specific publication and subscription names
-        let pub_name = if databases.len() == 1 {
-            // Single database - use template name as-is
-            pub_name_template.to_string()
-        } else {
-            // Multiple databases - append database name to avoid conflicts
-            format!("{}_{}", pub_name_template, db.name)
-        };
-
-        let sub_name = if databases.len() == 1 {
-            // Single database - use template name as-is
-            sub_name_template.to_string()
-        } else {
-            // Multiple databases - append database name to avoid conflicts
-            format!("{}_{}", sub_name_template, db.name)
-        };
+        let pub_name = replication_object_name(pub_name_template, &db.name, databases.len());
+        let sub_name = replication_object_name(sub_name_template, &db.name, databases.len());
 
         tracing::info!("Publication: '{}'", pub_name);
         tracing::info!("Subscription: '{}'", sub_name);
@@ -229,6 +294,14 @@ pub async fn sync(
         ))?;
         tracing::info!("✓ Connected to target");
 
+        crate::topology::check_hop(
+            &source_db_client,
+            &source_db_url,
+            &target_db_client,
+            &target_db_url,
+        )
+        .await?;
+
         // Create publication on source database
         tracing::info!("Creating publication on source database...");
         create_publication(&source_db_client, &db.name, &pub_name, &filter)
@@ -247,6 +320,39 @@ pub async fn sync(
                 sub_name
             ))?;
 
+        // An existing publication keeps its tables until asked to follow the filter
+        if sub_state != SubscriptionState::NotFound && options.refresh_publication {
+            refresh_publication(
+                &source_db_client,
+                &target_db_client,
+                &source_db_url,
+                &target_db_url,
+                &db.name,
+                &pub_name,
+                &sub_name,
+                &filter,
+                true,
+            )
+            .await
+            .with_context(|| format!("Failed to refresh publication for database '{}'", db.name))?;
+        } else if options.copy_remaining_tables {
+            // The slot's publication leaves out the tables copied below
+        } else if let Some(diff) =
+            publication_diff(&source_db_client, &db.name, &pub_name, &filter).await?
+        {
+            if !diff.is_empty() {
+                tracing::warn!(
+                    "⚠ Publication '{}' differs from the filter: {} table(s) to add ({}), {} to remove ({})",
+                    pub_name,
+                    diff.added.len(),
+                    diff.added.join(", "),
+                    diff.removed.len(),
+                    diff.removed.join(", ")
+                );
+                tracing::warn!("  Run sync again with --refresh-publication to apply the change");
+            }
+        }
+
         match sub_state {
             SubscriptionState::Streaming => {
                 if force {
@@ -341,12 +447,39 @@ pub async fn sync(
             }
             SubscriptionState::NotFound => {
                 tracing::info!("Creating subscription on target database...");
-                create_subscription(&target_db_client, &sub_name, &source_db_url, &pub_name)
+                let existing_slot =
+                    options.use_existing_slots && slot_exists(&source_db_client, &sub_name).await?;
+                if existing_slot {
+                    tracing::info!(
+                        "  Streaming from replication slot '{}' created with the snapshot",
+                        sub_name
+                    );
+                    create_subscription_for_slot(
+                        &target_db_client,
+                        &sub_name,
+                        &source_db_url,
+                        &pub_name,
+                        &sub_name,
+                    )
                     .await
-                    .context(format!(
-                        "Failed to create subscription on target database '{}'",
-                        db.name
-                    ))?;
+                } else {
+                    if options.use_existing_slots {
+                        anyhow::bail!(
+                            "Replication slot '{}' created with the snapshot of database '{}' is gone.\n\
+                             A new subscription would copy every table again into the loaded target, \
+                             failing on duplicate keys or duplicating rows.\n\
+                             Re-run init with --drop-existing to take a new snapshot.",
+                            sub_name,
+                            db.name
+                        );
+                    }
+                    create_subscription(&target_db_client, &sub_name, &source_db_url, &pub_name)
+                        .await
+                }
+                .context(format!(
+                    "Failed to create subscription on target database '{}'",
+                    db.name
+                ))?;
                 tracing::info!(
                     "Waiting for initial sync to complete (timeout: {}s)...",
                     timeout
@@ -357,10 +490,80 @@ pub async fn sync(
                         "Failed to wait for initial sync on database '{}'",
                         db.name
                     ))?;
+                if existing_slot && options.copy_remaining_tables {
+                    // Each table's copy is handed over to streaming on its
+                    // own, so there is nothing to wait for here
+                    let copying = refresh_publication(
+                        &source_db_client,
+                        &target_db_client,
+                        &source_db_url,
+                        &target_db_url,
+                        &db.name,
+                        &pub_name,
+                        &sub_name,
+                        &filter,
+                        false,
+                    )
+                    .await
+                    .with_context(|| {
+                        format!(
+                            "Failed to hand the remaining tables of database '{}' to subscription '{}'",
+                            db.name, sub_name
+                        )
+                    })?;
+                    if copying > 0 {
+                        tracing::info!(
+                            "  Changes to the priority tables already stream; run 'status' to follow the copy of the other {} table(s)",
+                            copying
+                        );
+                    }
+                }
             }
         }
 
+        // The subscription's slot on the source is named after it
+        crate::replication::slot::track(&sub_name, &source_db_url, &target_db_url);
+
+        crate::replication::monitor::record_subscription_progress(
+            &target_db_client,
+            &source_db_url,
+            &sub_name,
+        )
+        .await
+        .with_context(|| {
+            format!(
+                "Failed to update _replicator_progress in database '{}'",
+                db.name
+            )
+        })?;
+
         tracing::info!("✓ Replication active for database '{}'", db.name);
+
+        crate::topology::record_hop(
+            &source_db_client,
+            &source_db_url,
+            &target_db_client,
+            &target_db_url,
+            crate::topology::HopMethod::Logical,
+        )
+        .await
+        .with_context(|| {
+            format!(
+                "Failed to update _replicator_origins in database '{}'",
+                db.name
+            )
+        })?;
+
+        if options.audit_schema {
+            record_audit_run(&target_db_client, &source_db_url)
+                .await
+                .with_context(|| {
+                    format!(
+                        "Failed to update _replicator schema in database '{}'",
+                        db.name
+                    )
+                })?;
+        }
     }
 
     tracing::info!("");
@@ -382,6 +585,26 @@ pub async fn sync(
     Ok(())
 }
 
+/// Record a finished logical replication setup in the target's audit schema
+async fn record_audit_run(
+    target_client: &tokio_postgres::Client,
+    source_db_url: &str,
+) -> Result<()> {
+    use crate::audit::{self, RunMethod, RunOutcome};
+
+    let source_id = crate::xmin::source_identifier(source_db_url)?;
+    audit::ensure_audit_schema(target_client).await?;
+    let run_id = audit::start_run(
+        target_client,
+        "sync",
+        RunMethod::Logical,
+        &source_id,
+        chrono::Utc::now(),
+    )
+    .await?;
+    audit::finish_run(target_client, run_id, &RunOutcome::Succeeded).await
+}
+
 /// Resolve the effective target URL for sync, honoring saved SerenDB state when using API keys.
 pub async fn resolve_target_for_sync(
     target: Option<String>,
@@ -402,7 +625,7 @@ pub async fn resolve_target_for_sync(
             if !state.source_matches(source_url) {
                 eprintln!("\u{26A0}  Warning: Source database has changed since the last init run");
                 eprintln!("   Saved for: {}", state.source_url_hash);
-                eprintln!("   Current:   {}", source_url);
+                eprintln!("   Current:   {}", crate::redact::redact_url(source_url));
                 eprintln!();
             }
 
@@ -472,6 +695,288 @@ pub fn replace_database_in_url(url: &str, new_db_name: &str) -> Result<String> {
     Ok(new_url)
 }
 
+/// Bring a publication and its subscription in line with the filter
+///
+/// Tables new to the subscription get their schema on the target if it is
+/// missing, and are then copied by the subscription before their changes
+/// stream. A table that already holds rows on the target stops the refresh,
+/// since copying it again would duplicate them.
+///
+/// Without `remove_deselected`, tables are only added: published tables the
+/// filter no longer selects stay, and new tables without a primary key or
+/// replica identity are skipped so the source keeps accepting their UPDATEs.
+/// Returns how many tables the subscription started copying.
+#[allow(clippy::too_many_arguments)]
+async fn refresh_publication(
+    source_client: &tokio_postgres::Client,
+    target_client: &tokio_postgres::Client,
+    source_db_url: &str,
+    target_db_url: &str,
+    db_name: &str,
+    pub_name: &str,
+    sub_name: &str,
+    filter: &crate::filters::ReplicationFilter,
+    remove_deselected: bool,
+) -> Result<usize> {
+    use crate::replication::publication::{
+        is_all_tables_publication, lacks_replica_identity, published_tables, selected_tables,
+    };
+
+    if remove_deselected {
+        tracing::info!("Refreshing publication '{}'...", pub_name);
+    }
+    let all_tables = is_all_tables_publication(source_client, pub_name).await?;
+    let published = if all_tables {
+        published_tables(source_client, pub_name).await?
+    } else {
+        selected_tables(source_client, db_name, filter).await?
+    };
+    let subscribed: Vec<String> = get_subscription_tables(target_client, sub_name)
+        .await?
+        .into_iter()
+        .map(|table| format!("{}.{}", table.schema, table.table))
+        .collect();
+    let mut new_tables: Vec<String> = published
+        .into_iter()
+        .filter(|table| !subscribed.contains(table))
+        .collect();
+
+    if !remove_deselected {
+        if !all_tables {
+            let mut publishable = Vec::new();
+            for table in new_tables {
+                if lacks_replica_identity(source_client, &table).await? {
+                    tracing::warn!(
+                        "⚠ Not adding {} to publication '{}': it has no primary key or replica identity",
+                        table,
+                        pub_name
+                    );
+                } else {
+                    publishable.push(table);
+                }
+            }
+            new_tables = publishable;
+        }
+        if new_tables.is_empty() {
+            return Ok(0);
+        }
+        tracing::info!(
+            "Adding {} new table(s) to publication '{}': {}",
+            new_tables.len(),
+            pub_name,
+            new_tables.join(", ")
+        );
+    }
+
+    let mut missing = Vec::new();
+    let mut populated = Vec::new();
+    for table in &new_tables {
+        let row = target_client
+            .query_one("SELECT to_regclass($1)::text", &[&quote_qualified(table)])
+            .await
+            .with_context(|| format!("Failed to look up table {} on the target", table))?;
+        if row.get::<_, Option<String>>(0).is_none() {
+            missing.push(table.clone());
+            continue;
+        }
+        let has_rows = target_client
+            .query_one(
+                &format!("SELECT EXISTS (SELECT 1 FROM {})", quote_qualified(table)),
+                &[],
+            )
+            .await
+            .with_context(|| format!("Failed to check table {} on the target", table))?
+            .get::<_, bool>(0);
+        if has_rows {
+            populated.push(table.clone());
+        }
+    }
+    if !populated.is_empty() {
+        anyhow::bail!(
+            "Table(s) new to subscription '{}' already hold rows on the target: {}\n\
+             The subscription copies new tables in full; truncate them on the target \
+             or exclude them from the filter, then refresh again.",
+            sub_name,
+            populated.join(", ")
+        );
+    }
+
+    if !missing.is_empty() {
+        tracing::info!(
+            "  Creating {} table(s) on the target: {}",
+            missing.len(),
+            missing.join(", ")
+        );
+        create_target_tables(source_db_url, target_db_url, db_name, &missing).await?;
+    }
+
+    if all_tables {
+        // New tables are already published
+    } else if remove_deselected {
+        update_publication(source_client, db_name, pub_name, filter).await?;
+    } else {
+        let current = published_tables(source_client, pub_name).await?;
+        let unpublished: Vec<String> = new_tables
+            .iter()
+            .filter(|table| !current.contains(table))
+            .cloned()
+            .collect();
+        add_to_publication(source_client, db_name, pub_name, filter, &unpublished).await?;
+    }
+    refresh_subscription(target_client, sub_name, true).await?;
+    tracing::info!(
+        "✓ Subscription '{}' now copies {} new table(s) before streaming them",
+        sub_name,
+        new_tables.len()
+    );
+    Ok(new_tables.len())
+}
+
+/// Keep adding tables that newly match the filter to running subscriptions
+///
+/// Every `interval`, name patterns in `filter` are resolved again and each
+/// database's publication gains the tables it now selects, as with
+/// [`SyncOptions::refresh_publication`] but without removing any, and each
+/// subscription's applied LSN is recorded in `_replicator_progress`. Runs until
+/// `shutdown` fires; a database that cannot be updated is retried on the
+/// next pass. Databases created after sync started are not picked up.
+pub async fn watch_for_new_tables(
+    source_url: &str,
+    target_url: &str,
+    filter: &crate::filters::ReplicationFilter,
+    publication_name: Option<&str>,
+    subscription_name: Option<&str>,
+    interval: std::time::Duration,
+    mut shutdown: tokio::sync::broadcast::Receiver<()>,
+) -> Result<()> {
+    let pub_name_template = publication_name.unwrap_or(DEFAULT_PUBLICATION_NAME);
+    let sub_name_template = subscription_name.unwrap_or(DEFAULT_SUBSCRIPTION_NAME);
+
+    let resolved = filter.resolve_patterns(source_url).await?;
+    let source_client = connect(source_url)
+        .await
+        .context("Failed to connect to source database")?;
+    let databases: Vec<String> = migration::list_databases(&source_client)
+        .await
+        .context("Failed to list databases on source")?
+        .into_iter()
+        .filter(|db| resolved.should_replicate_database(&db.name))
+        .map(|db| db.name)
+        .collect();
+    drop(source_client);
+
+    tracing::info!(
+        "Checking for new tables every {}s (Ctrl+C to stop)",
+        interval.as_secs()
+    );
+    let mut ticker = tokio::time::interval(interval);
+    // The first tick fires at once, and setup has just run
+    ticker.tick().await;
+    loop {
+        tokio::select! {
+            biased;
+            _ = shutdown.recv() => {
+                tracing::info!("Shutdown signal received, no longer watching for new tables");
+                return Ok(());
+            }
+            _ = ticker.tick() => {}
+        }
+
+        let resolved = match filter.resolve_patterns(source_url).await {
+            Ok(resolved) => resolved,
+            Err(e) => {
+                tracing::warn!("⚠ Could not resolve filter patterns: {:#}", e);
+                continue;
+            }
+        };
+        for db_name in &databases {
+            let pub_name = replication_object_name(pub_name_template, db_name, databases.len());
+            let sub_name = replication_object_name(sub_name_template, db_name, databases.len());
+            let result = async {
+                let source_db_url = replace_database_in_url(source_url, db_name)?;
+                let target_db_url = replace_database_in_url(target_url, db_name)?;
+                let source_db_client = connect(&source_db_url).await?;
+                let target_db_client = connect(&target_db_url).await?;
+                if !list_subscriptions(&target_db_client)
+                    .await?
+                    .contains(&sub_name)
+                {
+                    return Ok(0);
+                }
+                let copying = refresh_publication(
+                    &source_db_client,
+                    &target_db_client,
+                    &source_db_url,
+                    &target_db_url,
+                    db_name,
+                    &pub_name,
+                    &sub_name,
+                    &resolved,
+                    false,
+                )
+                .await?;
+                crate::replication::monitor::record_subscription_progress(
+                    &target_db_client,
+                    &source_db_url,
+                    &sub_name,
+                )
+                .await?;
+                anyhow::Ok(copying)
+            }
+            .await;
+            if let Err(e) = result {
+                tracing::warn!(
+                    "⚠ Could not add new tables in database '{}': {:#}",
+                    db_name,
+                    e
+                );
+            }
+        }
+    }
+}
+
+/// `"schema"."table"` for a `schema.table` name
+fn quote_qualified(table: &str) -> String {
+    let (schema, name) = table.split_once('.').unwrap_or(("public", table));
+    format!(
+        "{}.{}",
+        crate::utils::quote_ident(schema),
+        crate::utils::quote_ident(name)
+    )
+}
+
+/// Copy the schema of `tables` (`schema.table`) from source to target
+pub(crate) async fn create_target_tables(
+    source_db_url: &str,
+    target_db_url: &str,
+    db_name: &str,
+    tables: &[String],
+) -> Result<()> {
+    let include: Vec<String> = tables
+        .iter()
+        .map(|table| format!("{}.{}", db_name, table))
+        .collect();
+    let filter = crate::filters::ReplicationFilter::new(None, None, Some(include), None)?;
+    let temp_path =
+        crate::utils::create_managed_temp_dir().context("Failed to create temp directory")?;
+    let schema_file = temp_path.join(format!("{}_refresh_schema.sql", db_name));
+    let result = async {
+        migration::dump_schema(
+            source_db_url,
+            db_name,
+            schema_file.to_str().unwrap(),
+            &filter,
+        )
+        .await?;
+        migration::restore_schema(target_db_url, schema_file.to_str().unwrap()).await
+    }
+    .await;
+    if let Err(e) = crate::utils::remove_managed_temp_dir(&temp_path) {
+        tracing::warn!("Failed to clean up temp directory: {}", e);
+    }
+    result
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,437 @@
+// ABOUTME: Continuous MySQL/MariaDB to PostgreSQL sync driven by the source binlog
+// ABOUTME: Applies row changes to the JSONB tables created by init and records GTIDs
+
+use anyhow::{bail, Context, Result};
+use futures::StreamExt;
+use mysql_async::binlog::events::{EventData, RowsEventData, TableMapEvent};
+use mysql_async::{prelude::*, BinlogStream, Conn, Value};
+use serde_json::Value as JsonValue;
+use std::collections::{HashMap, HashSet};
+use tokio::sync::broadcast;
+
+use crate::filters::ReplicationFilter;
+use crate::jsonb::writer::{delete_jsonb_rows, upsert_jsonb_rows};
+use crate::mysql::binlog::{self, key_text, KeyColumn};
+use crate::mysql::converter::{self, document_id, id_column, id_text};
+use crate::mysql::flavor::{self, Gtid, GtidPosition, ServerInfo};
+use crate::postgres::ConnectionManager;
+use crate::xmin::progress::{self, PositionKind};
+
+/// JSONB `_source_type` of rows written by MySQL init and sync
+const SOURCE_TYPE: &str = "mysql";
+
+pub struct SyncMysqlOptions {
+    pub source: String,
+    pub target: String,
+    /// Tables to sync, matched against the source database name
+    pub filter: ReplicationFilter,
+    /// Replica server ID announced to the source; unique among its replicas
+    pub server_id: u32,
+    /// Apply the binlog up to its current end and exit
+    pub once: bool,
+}
+
+/// Stream the source binlog into the target's JSONB tables until shutdown
+///
+/// Sync resumes after the GTID position that `init` (or an earlier sync)
+/// recorded in `_replicator_progress`. Changes are applied at least once:
+/// after a crash, transactions since the last recorded position are applied
+/// again, which leaves the same rows behind.
+pub async fn run(opts: SyncMysqlOptions, mut shutdown: broadcast::Receiver<()>) -> Result<()> {
+    if opts.filter.has_schema_filters() {
+        bail!("Schema filters are not supported for MySQL sources");
+    }
+
+    let mut conn = crate::mysql::connect_mysql(&opts.source)
+        .await
+        .context("MySQL connection failed")?;
+    let info = flavor::detect_server_info(&mut conn)
+        .await
+        .context("Failed to detect MySQL server flavor")?;
+    info.ensure_supported()?;
+    let settings = info.binlog_settings(&mut conn).await?;
+    info.check_binlog_settings(&settings)?;
+    tracing::info!("Streaming binlog from {}", info.describe());
+
+    let db_name = crate::mysql::extract_database_name(&opts.source)
+        .context("MySQL URL must include database name (e.g., mysql://host:3306/dbname)")?;
+    let tables: HashSet<String> = crate::mysql::reader::list_tables(&mut conn, &db_name)
+        .await?
+        .into_iter()
+        .filter(|table| opts.filter.should_replicate_table(&db_name, table))
+        .collect();
+    if tables.is_empty() {
+        bail!("No tables in MySQL database '{}' match the filter", db_name);
+    }
+
+    let target = ConnectionManager::connect(&opts.target, "target").await?;
+    progress::ensure_progress_table(&target.client()).await?;
+    let source_id = binlog::source_identifier(&opts.source)?;
+    let position = load_start_position(&target, &source_id, &info).await?;
+    tracing::info!("Resuming {} after GTID position '{}'", source_id, position);
+
+    // Changed rows are read back over a second connection while the first streams
+    let mut reader = crate::mysql::connect_mysql(&opts.source).await?;
+    info.prepare_session(&mut reader).await?;
+    // Key literals are escaped with backslashes
+    reader
+        .query_drop(
+            "SET SESSION sql_mode = REPLACE(@@SESSION.sql_mode, 'NO_BACKSLASH_ESCAPES', '')",
+        )
+        .await
+        .context("Failed to prepare MySQL session for row reads")?;
+
+    let mut stream = binlog::open_stream(conn, &info, opts.server_id, &position, opts.once).await?;
+    let mut applier = BinlogApplier {
+        info,
+        db_name,
+        source_id,
+        tables,
+        reader,
+        target,
+        position,
+        table_keys: HashMap::new(),
+        current_gtid: None,
+        changes: Vec::new(),
+    };
+
+    let result = loop {
+        let event = tokio::select! {
+            _ = shutdown.recv() => {
+                tracing::info!("Shutdown requested; stopping binlog sync");
+                break Ok(());
+            }
+            event = stream.next() => event,
+        };
+        match event {
+            Some(Ok(event)) => {
+                if let Err(e) = applier.handle_event(&stream, &event).await {
+                    break Err(e);
+                }
+            }
+            Some(Err(e)) => break Err(anyhow::Error::new(e).context("Failed to read binlog event")),
+            None if opts.once => {
+                tracing::info!("Reached the end of the binlog");
+                break Ok(());
+            }
+            None => break Err(anyhow::anyhow!("Source closed the binlog stream")),
+        }
+    };
+
+    let _ = stream.close().await;
+    result?;
+    // Transactions that touched no synced table still move the position forward
+    applier.save_position().await
+}
+
+/// Combine the GTID positions recorded for every table of the source
+async fn load_start_position(
+    target: &ConnectionManager,
+    source_id: &str,
+    info: &ServerInfo,
+) -> Result<GtidPosition> {
+    let recorded = progress::load_source_progress(&target.client(), source_id).await?;
+    let mut position = GtidPosition::empty(info.flavor);
+    let mut found = false;
+    for table in recorded
+        .iter()
+        .filter(|table| table.position_kind == PositionKind::Gtid.as_str())
+    {
+        let table_position = GtidPosition::parse(info.flavor, &table.position)
+            .with_context(|| format!("Invalid GTID position recorded for {}", table.table_name))?;
+        position.merge(&table_position)?;
+        found = true;
+    }
+    if !found {
+        bail!(
+            "No binlog position is recorded for {} in the target.\n\
+             Run `database-replicator init` first so sync knows where the snapshot ended.",
+            source_id
+        );
+    }
+    Ok(position)
+}
+
+/// How binlog row images map onto JSONB document IDs for one table
+#[derive(Debug, Clone)]
+struct TableKey {
+    /// The ID column, one of [`converter::ID_COLUMNS`]
+    column: String,
+    /// Position of the ID column in row images
+    index: usize,
+    key: KeyColumn,
+    /// MariaDB JSON columns stored as text
+    json_columns: Vec<String>,
+}
+
+/// Row changes from one rows event, applied when its transaction commits
+struct RowChange {
+    table: String,
+    /// IDs whose current row is read back from the source and upserted
+    upserts: Vec<String>,
+    /// IDs whose documents are deleted
+    deletes: Vec<String>,
+}
+
+struct BinlogApplier {
+    info: ServerInfo,
+    db_name: String,
+    source_id: String,
+    tables: HashSet<String>,
+    reader: Conn,
+    target: ConnectionManager,
+    position: GtidPosition,
+    /// `None` for tables without an ID column, which are skipped
+    table_keys: HashMap<String, Option<TableKey>>,
+    current_gtid: Option<Gtid>,
+    changes: Vec<RowChange>,
+}
+
+impl BinlogApplier {
+    async fn handle_event(
+        &mut self,
+        stream: &BinlogStream,
+        event: &mysql_async::binlog::events::Event,
+    ) -> Result<()> {
+        if let Some(gtid) = binlog::mariadb_gtid(event)? {
+            self.current_gtid = Some(gtid);
+            return Ok(());
+        }
+
+        match event.read_data().context("Failed to decode binlog event")? {
+            Some(EventData::GtidEvent(gtid)) => self.current_gtid = Some(binlog::mysql_gtid(&gtid)),
+            Some(EventData::RowsEvent(rows)) => {
+                let tme = stream.get_tme(rows.table_id()).with_context(|| {
+                    format!("Binlog row event for unknown table id {}", rows.table_id())
+                })?;
+                self.collect_rows(&rows, tme).await?;
+            }
+            Some(EventData::XidEvent(_)) => self.commit().await?,
+            Some(EventData::QueryEvent(query)) => {
+                let sql = query.query();
+                if !binlog::is_begin(&sql) {
+                    self.commit().await?;
+                    if !binlog::is_commit(&sql) {
+                        // DDL may have moved or renamed the ID column
+                        self.table_keys.clear();
+                    }
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Record the document IDs a rows event inserts, updates, or deletes
+    async fn collect_rows(
+        &mut self,
+        rows: &RowsEventData<'_>,
+        tme: &TableMapEvent<'_>,
+    ) -> Result<()> {
+        let table = tme.table_name().to_string();
+        if tme.database_name() != self.db_name.as_str() || !self.tables.contains(&table) {
+            return Ok(());
+        }
+        let Some(key) = self.table_key(&table).await? else {
+            return Ok(());
+        };
+
+        let mut change = RowChange {
+            table: table.clone(),
+            upserts: Vec::new(),
+            deletes: Vec::new(),
+        };
+        for row in rows.rows(tme) {
+            let (before, after) =
+                row.with_context(|| format!("Failed to decode binlog row for '{}'", table))?;
+            let key_of = |row: &mysql_async::binlog::row::BinlogRow| -> Result<String> {
+                let value = row.as_ref(key.index).with_context(|| {
+                    format!(
+                        "Binlog row for '{}' lacks its ID column; set binlog_row_image=FULL",
+                        table
+                    )
+                })?;
+                key_text(value, &key.key)
+                    .with_context(|| format!("Failed to read ID of a '{}' row", table))
+            };
+            let before = before.as_ref().map(key_of).transpose()?;
+            let after = after.as_ref().map(key_of).transpose()?;
+            // An update that changes the ID moves the document
+            if let Some(before) = before.filter(|id| after.as_ref() != Some(id)) {
+                change.deletes.push(id_text(&JsonValue::String(before)));
+            }
+            if let Some(after) = after {
+                change.upserts.push(after);
+            }
+        }
+        self.changes.push(change);
+        Ok(())
+    }
+
+    async fn table_key(&mut self, table: &str) -> Result<Option<TableKey>> {
+        if let Some(key) = self.table_keys.get(table) {
+            return Ok(key.clone());
+        }
+
+        let columns = converter::get_column_names(&mut self.reader, &self.db_name, table).await?;
+        let key = match id_column(&columns) {
+            Some(id) => {
+                let key =
+                    binlog::key_column(&mut self.reader, &self.info, &self.db_name, table, id)
+                        .await?;
+                let json_columns =
+                    flavor::json_alias_columns(&mut self.reader, &self.info, &self.db_name, table)
+                        .await?;
+                Some(TableKey {
+                    column: id.to_string(),
+                    index: columns
+                        .iter()
+                        .position(|name| name == id)
+                        .expect("id_column returns a listed column"),
+                    key,
+                    json_columns,
+                })
+            }
+            None => {
+                tracing::warn!(
+                    "⚠ Skipping changes to '{}': binlog sync needs an id column to match rows",
+                    table
+                );
+                None
+            }
+        };
+        self.table_keys.insert(table.to_string(), key.clone());
+        Ok(key)
+    }
+
+    /// Apply the finished transaction's changes and record its GTID
+    async fn commit(&mut self) -> Result<()> {
+        let mut next = self.position.clone();
+        if let Some(gtid) = self.current_gtid.take() {
+            next.advance(&gtid)?;
+        }
+        let changes = std::mem::take(&mut self.changes);
+        if changes.is_empty() {
+            self.position = next;
+            return Ok(());
+        }
+
+        // Rows are read in their committed state, so a row changed again by a
+        // later transaction is written once that transaction is applied too
+        let mut batches = Vec::with_capacity(changes.len());
+        for change in changes {
+            let rows = self.read_rows(&change.table, &change.upserts).await?;
+            batches.push((change, rows));
+        }
+
+        let position = next.to_string();
+        let mut replays = 0;
+        loop {
+            let client = self.target.client();
+            let result = async {
+                client.batch_execute("BEGIN").await?;
+                let mut applied: HashMap<&str, u64> = HashMap::new();
+                for (change, rows) in &batches {
+                    delete_jsonb_rows(&client, &change.table, &change.deletes).await?;
+                    upsert_jsonb_rows(&client, &change.table, rows, SOURCE_TYPE).await?;
+                    *applied.entry(change.table.as_str()).or_default() +=
+                        (change.deletes.len() + rows.len()) as u64;
+                }
+                for (table, rows) in applied {
+                    progress::record_progress(
+                        &client,
+                        &self.source_id,
+                        &self.db_name,
+                        table,
+                        PositionKind::Gtid,
+                        &position,
+                        rows,
+                    )
+                    .await?;
+                }
+                client.batch_execute("COMMIT").await?;
+                Ok(())
+            }
+            .await;
+            match result {
+                Ok(()) => {
+                    self.position = next;
+                    return Ok(());
+                }
+                Err(e) => {
+                    let _ = client.batch_execute("ROLLBACK").await;
+                    self.target.recover(e, &mut replays).await?;
+                }
+            }
+        }
+    }
+
+    /// Read the current rows for `ids` with the text protocol init used
+    async fn read_rows(&mut self, table: &str, ids: &[String]) -> Result<Vec<(String, JsonValue)>> {
+        let Some(Some(key)) = self.table_keys.get(table).cloned() else {
+            return Ok(Vec::new());
+        };
+        // Several changes to one row within a transaction need one upsert
+        let mut unique: Vec<&String> = Vec::with_capacity(ids.len());
+        for id in ids {
+            if !unique.contains(&id) {
+                unique.push(id);
+            }
+        }
+        if unique.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let literals: Vec<String> = unique
+            .iter()
+            .map(|id| Value::Bytes(id.as_bytes().to_vec()).as_sql(false))
+            .collect();
+        let query = format!(
+            "SELECT * FROM {}.{} WHERE {} IN ({})",
+            crate::utils::quote_mysql_ident(&self.db_name),
+            crate::utils::quote_mysql_ident(table),
+            crate::utils::quote_mysql_ident(&key.column),
+            literals.join(", ")
+        );
+        let rows: Vec<mysql_async::Row> = self
+            .reader
+            .query(&query)
+            .await
+            .with_context(|| format!("Failed to read changed rows from '{}'", table))?;
+
+        let mut documents = Vec::with_capacity(rows.len());
+        for row in rows {
+            let columns: Vec<String> = row
+                .columns_ref()
+                .iter()
+                .map(|column| column.name_str().to_string())
+                .collect();
+            let mut data = converter::mysql_row_to_json(&row, &columns)
+                .with_context(|| format!("Failed to convert row in table '{}'", table))?;
+            converter::decode_json_text_columns(&mut data, &key.json_columns);
+            if let Some(id) = document_id(&data) {
+                documents.push((id, data));
+            }
+        }
+        Ok(documents)
+    }
+
+    /// Record the current position for every synced table
+    async fn save_position(&self) -> Result<()> {
+        let client = self.target.client();
+        let position = self.position.to_string();
+        for table in &self.tables {
+            progress::record_progress(
+                &client,
+                &self.source_id,
+                &self.db_name,
+                table,
+                PositionKind::Gtid,
+                &position,
+                0,
+            )
+            .await?;
+        }
+        Ok(())
+    }
+}
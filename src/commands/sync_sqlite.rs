@@ -1,11 +1,19 @@
 use anyhow::{anyhow, bail, Context, Result};
 use clap::ValueEnum;
+use sqlite_watcher::queue::ChangeOperation;
 use sqlite_watcher::watcher_proto::watcher_client::WatcherClient;
+use sqlite_watcher::watcher_proto::Operation;
 use sqlite_watcher::watcher_proto::{
-    AckChangesRequest, GetStateRequest, HealthCheckRequest, ListChangesRequest, SetStateRequest,
+    AckChangesRequest, GetQueueStatsRequest, GetStateRequest, HealthCheckRequest,
+    ListChangesRequest, SetStateRequest,
 };
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, OwnedSemaphorePermit, Semaphore};
+use tokio::time::Instant;
+use tokio_postgres::types::ToSql;
 use tokio_postgres::Client;
 use tonic::codegen::InterceptedService;
 use tonic::service::Interceptor;
@@ -13,7 +21,13 @@ use tonic::transport::{Channel, Endpoint};
 use tonic::{Request, Status};
 use tower::service_fn;
 
-use crate::jsonb::writer::{delete_jsonb_rows, insert_jsonb_batch, upsert_jsonb_rows};
+use crate::filters::ReplicationFilter;
+use crate::jsonb::promote::{promote_jsonb_keys, PromotedKey};
+use crate::jsonb::schema::{DocumentSchema, SchemaRegistry};
+use crate::jsonb::writer::{create_jsonb_table, delete_jsonb_rows, upsert_jsonb_rows};
+use crate::postgres::{ConnectionManager, ReconnectPolicy};
+use crate::sqlite::SQLITE_DATABASE_NAME;
+use crate::table_rules::TargetNames;
 
 const GLOBAL_STATE_KEY: &str = "_global";
 
@@ -23,136 +37,498 @@ pub enum IncrementalMode {
     AppendDeduped,
 }
 
+/// What a delete does to the row in a `*_latest` table
+#[derive(Debug, Clone, Copy, ValueEnum, PartialEq, Eq)]
+pub enum LatestDeletes {
+    /// Remove the row
+    Delete,
+    /// Keep the row and set its `_deleted_at` column
+    Tombstone,
+}
+
 pub struct SyncSqliteOptions {
     pub target: String,
+    /// Further targets that receive the same changes, for redundancy; changes
+    /// are acked once every target has committed them
+    pub mirror_targets: Vec<String>,
     pub watcher_endpoint: String,
     pub token_file: Option<PathBuf>,
+    /// Watcher database ID; empty when the watcher follows a single database
+    pub watcher_database: String,
     pub incremental_mode: IncrementalMode,
     pub batch_size: u32,
+    /// Batches fetched from the watcher but not yet applied and acked
+    pub max_in_flight: usize,
+    /// Longest fetched changes wait for more to fill a transaction
+    pub flush_interval: Duration,
+    /// Most changes applied in one target transaction
+    pub transaction_size: usize,
+    /// Tables to sync (as `main.table`) and where they land, via renames and
+    /// identifier case from a replication config
+    pub filter: ReplicationFilter,
+    /// How deletes show up in `*_latest` tables
+    pub latest_deletes: LatestDeletes,
+    /// Compact history rows older than this once the queue is drained
+    pub compact_history: Option<Duration>,
 }
 
 pub async fn run(opts: SyncSqliteOptions) -> Result<()> {
+    let mapping = TableMapping::new(&opts.filter)?;
     let token = load_token(opts.token_file.as_deref())?;
     let endpoint = WatcherEndpoint::parse(&opts.watcher_endpoint)?;
     let mut watcher = connect_watcher(endpoint, token.clone()).await?;
 
-    let client = crate::postgres::connect(&opts.target)
-        .await
-        .context("failed to connect to target PostgreSQL")?;
-    ensure_state_table(&client).await?;
-    ensure_baseline_exists(&client).await?;
+    let mut targets = Vec::new();
+    let urls = std::iter::once((&opts.target, "target"))
+        .chain(opts.mirror_targets.iter().map(|url| (url, "mirror target")));
+    for (url, role) in urls {
+        let connection = ConnectionManager::connect(url, role).await?;
+        ensure_state_table(&connection.client()).await?;
+        ensure_baseline_exists(&connection.client())
+            .await
+            .with_context(|| format!("{role} has no sqlite baseline"))?;
+        targets.push(SyncTarget {
+            connection,
+            prepared_tables: HashSet::new(),
+            schemas: SchemaRegistry::default(),
+        });
+    }
 
     tracing::info!("Connecting to sqlite-watcher...");
     watcher
         .health_check(Request::new(HealthCheckRequest {}))
         .await
         .context("watcher health check failed")?;
-    let _ = watcher
+    let global_state = watcher
         .get_state(Request::new(GetStateRequest {
             table_name: GLOBAL_STATE_KEY.to_string(),
+            database: opts.watcher_database.clone(),
         }))
-        .await?;
+        .await?
+        .into_inner();
+    // Older watchers have no queue stats; the check is only informational
+    match watcher
+        .get_queue_stats(Request::new(GetQueueStatsRequest {
+            database: opts.watcher_database.clone(),
+        }))
+        .await
+    {
+        Ok(stats) => {
+            let stats = stats.into_inner();
+            tracing::info!(
+                "sqlite-watcher queue: {} pending change(s), oldest {}s old",
+                stats.pending_changes,
+                stats.oldest_pending_age_seconds
+            );
+        }
+        Err(status) if status.code() == tonic::Code::Unimplemented => {}
+        Err(status) => return Err(status).context("failed to read watcher queue stats"),
+    }
+    let last_applied = global_state.exists.then_some(global_state.last_change_id);
 
     tracing::info!(
-        "Starting incremental sync (mode: {:?})",
-        opts.incremental_mode
+        "Starting incremental sync (mode: {:?}, {} target(s))",
+        opts.incremental_mode,
+        targets.len()
     );
-    let mut processed_any = false;
 
-    loop {
-        let req = Request::new(ListChangesRequest {
-            limit: opts.batch_size.max(1),
-        });
-        let changes = watcher
-            .list_changes(req)
-            .await
-            .context("failed to list changes from watcher")?
-            .into_inner()
-            .changes;
+    // Batches are fetched ahead while earlier ones are applied; each holds a
+    // permit until acknowledged, bounding the changes held in memory
+    let max_in_flight = opts.max_in_flight.max(1);
+    let transaction_size = opts.transaction_size.max(1);
+    let permits = Arc::new(Semaphore::new(max_in_flight));
+    let (sender, mut batches) = mpsc::channel(max_in_flight);
+    let fetcher = tokio::spawn(fetch_batches(
+        watcher.clone(),
+        opts.watcher_database.clone(),
+        opts.batch_size.max(1),
+        last_applied,
+        Arc::clone(&permits),
+        max_in_flight as u32,
+        sender,
+    ));
 
-        if changes.is_empty() {
-            if !processed_any {
-                tracing::info!("No pending sqlite-watcher changes");
-            }
+    let mut pending = Vec::new();
+    let mut held_permits = Vec::new();
+    let mut flush_at = None;
+    let mut fetching = true;
+    let mut processed_any = false;
+    loop {
+        if pending.is_empty() && !fetching {
             break;
         }
+        let full = pending.len() >= transaction_size || held_permits.len() >= max_in_flight;
+        if fetching && !full {
+            let received = match flush_at {
+                Some(deadline) => tokio::time::timeout_at(deadline, batches.recv()).await.ok(),
+                None => Some(batches.recv().await),
+            };
+            match received {
+                Some(Some(FetchedBatch { changes, permit })) => {
+                    flush_at.get_or_insert_with(|| Instant::now() + opts.flush_interval);
+                    pending.extend(changes);
+                    held_permits.push(permit);
+                    continue;
+                }
+                Some(None) => {
+                    fetching = false;
+                    continue;
+                }
+                // The flush interval elapsed; apply what has arrived
+                None => {}
+            }
+        }
 
-        apply_changes(&client, &changes, opts.incremental_mode).await?;
+        for chunk in pending.chunks(transaction_size) {
+            for target in targets.iter_mut() {
+                target.apply(chunk, &opts, &mapping).await?;
+            }
+            ack_changes(&mut watcher, &opts.watcher_database, chunk).await?;
+        }
         processed_any = true;
+        pending.clear();
+        held_permits.clear();
+        flush_at = None;
+    }
+    fetcher
+        .await
+        .context("sqlite-watcher fetch task failed")??;
+    if !processed_any {
+        tracing::info!("No pending sqlite-watcher changes");
+    }
+
+    if let Some(retention) = opts.compact_history {
+        for target in targets.iter_mut() {
+            compact_synced_tables(
+                &target.connection.client(),
+                &mapping,
+                &mut target.prepared_tables,
+                retention,
+            )
+            .await?;
+        }
+    }
+
+    tracing::info!("sqlite-watcher sync completed");
+    Ok(())
+}
+
+/// A PostgreSQL database receiving the change stream
+struct SyncTarget {
+    connection: ConnectionManager,
+    /// Target tables already prepared this run
+    prepared_tables: HashSet<String>,
+    /// Document schemas recorded in the target's registry
+    schemas: SchemaRegistry,
+}
 
-        let max_id = changes
+impl SyncTarget {
+    /// Apply changes in one transaction
+    ///
+    /// Changes are only acked once applied, so replaying them after a lost
+    /// connection gives the same at-least-once delivery as a restart.
+    async fn apply(
+        &mut self,
+        changes: &[sqlite_watcher::watcher_proto::Change],
+        opts: &SyncSqliteOptions,
+        mapping: &TableMapping,
+    ) -> Result<()> {
+        let mut replays = 0;
+        loop {
+            let client = self.connection.client();
+            // Tables prepared and schemas recorded by a rolled-back
+            // transaction need doing again
+            let mut prepared_tables = self.prepared_tables.clone();
+            let mut schemas = self.schemas.clone();
+            let result = async {
+                client.batch_execute("BEGIN").await?;
+                apply_changes(
+                    &client,
+                    changes,
+                    opts,
+                    mapping,
+                    &mut prepared_tables,
+                    &mut schemas,
+                )
+                .await?;
+                client.batch_execute("COMMIT").await?;
+                Ok(())
+            }
+            .await;
+            match result {
+                Ok(()) => {
+                    self.prepared_tables = prepared_tables;
+                    self.schemas = schemas;
+                    return Ok(());
+                }
+                Err(e) => {
+                    let _ = client.batch_execute("ROLLBACK").await;
+                    self.connection.recover(e, &mut replays).await?;
+                }
+            }
+        }
+    }
+}
+
+struct FetchedBatch {
+    changes: Vec<sqlite_watcher::watcher_proto::Change>,
+    permit: OwnedSemaphorePermit,
+}
+
+/// Fetch batches of pending changes until the queue is drained
+async fn fetch_batches(
+    mut watcher: WatcherClientWithAuth,
+    database: String,
+    batch_size: u32,
+    mut last_fetched: Option<i64>,
+    permits: Arc<Semaphore>,
+    max_in_flight: u32,
+    sender: mpsc::Sender<FetchedBatch>,
+) -> Result<()> {
+    let mut after_change_id = 0;
+    loop {
+        let permit = Arc::clone(&permits).acquire_owned().await?;
+        let listed = list_changes(&mut watcher, &database, batch_size, after_change_id).await?;
+        let drained = listed.len() < batch_size as usize;
+        // Watchers predating `after_change_id` list unacked changes again
+        let changes: Vec<_> = listed
+            .into_iter()
+            .filter(|change| change.change_id > after_change_id)
+            .collect();
+        if changes.is_empty() {
+            if drained {
+                return Ok(());
+            }
+            // Wait for everything fetched to be acked before listing again
+            drop(permit);
+            drop(permits.acquire_many(max_in_flight).await?);
+            continue;
+        }
+
+        if let Some(missing) = missing_changes(last_fetched, &changes) {
+            tracing::warn!(
+                "sqlite-watcher dropped {} change(s) after change {} before they were applied \
+                 (queue retention limits); re-run 'database-replicator init' to re-baseline",
+                missing,
+                last_fetched.unwrap_or_default()
+            );
+        }
+        after_change_id = changes
             .iter()
             .map(|c| c.change_id)
             .max()
             .unwrap_or_default();
-        watcher
-            .ack_changes(Request::new(AckChangesRequest {
-                up_to_change_id: max_id,
-            }))
-            .await
-            .context("failed to ack changes")?;
-
-        let last_change = changes.last().unwrap();
-        watcher
-            .set_state(Request::new(SetStateRequest {
-                table_name: GLOBAL_STATE_KEY.to_string(),
-                last_change_id: max_id,
-                last_wal_frame: last_change.wal_frame.clone(),
-                cursor: last_change.cursor.clone(),
-            }))
-            .await
-            .context("failed to update watcher state")?;
+        last_fetched = Some(after_change_id);
 
-        if changes.len() < opts.batch_size as usize {
-            break;
+        if sender.send(FetchedBatch { changes, permit }).await.is_err() || drained {
+            return Ok(());
         }
     }
+}
 
-    tracing::info!("sqlite-watcher sync completed");
+async fn list_changes(
+    watcher: &mut WatcherClientWithAuth,
+    database: &str,
+    limit: u32,
+    after_change_id: i64,
+) -> Result<Vec<sqlite_watcher::watcher_proto::Change>> {
+    let policy = ReconnectPolicy::default();
+    // The channel reconnects on its own; keep asking while the watcher is unavailable
+    let mut attempt = 0;
+    loop {
+        let req = Request::new(ListChangesRequest {
+            limit,
+            database: database.to_string(),
+            after_change_id,
+        });
+        match watcher.list_changes(req).await {
+            Ok(response) => return Ok(response.into_inner().changes),
+            Err(status)
+                if status.code() == tonic::Code::Unavailable
+                    && attempt + 1 < policy.max_attempts =>
+            {
+                attempt += 1;
+                let delay = policy.delay(attempt);
+                tracing::warn!(
+                    "sqlite-watcher unavailable ({}), retrying in {:?}",
+                    status.message(),
+                    delay
+                );
+                tokio::time::sleep(delay).await;
+            }
+            Err(status) => {
+                return Err(status).context("failed to list changes from watcher");
+            }
+        }
+    }
+}
+
+/// Acknowledge changes applied to every target and record the position
+async fn ack_changes(
+    watcher: &mut WatcherClientWithAuth,
+    database: &str,
+    changes: &[sqlite_watcher::watcher_proto::Change],
+) -> Result<()> {
+    let Some(last_change) = changes.iter().max_by_key(|c| c.change_id) else {
+        return Ok(());
+    };
+    watcher
+        .ack_changes(Request::new(AckChangesRequest {
+            up_to_change_id: last_change.change_id,
+            database: database.to_string(),
+        }))
+        .await
+        .context("failed to ack changes")?;
+    watcher
+        .set_state(Request::new(SetStateRequest {
+            table_name: GLOBAL_STATE_KEY.to_string(),
+            last_change_id: last_change.change_id,
+            last_wal_frame: last_change.wal_frame.clone(),
+            cursor: last_change.cursor.clone(),
+            database: database.to_string(),
+        }))
+        .await
+        .context("failed to update watcher state")?;
     Ok(())
 }
 
+/// Changes to one target table
+#[derive(Default)]
 struct TableBatch {
-    upserts: Vec<(String, serde_json::Value)>,
-    deletes: Vec<String>,
+    /// Every change in watcher order, appended to the raw history table
+    history: Vec<HistoryRow>,
+    /// Final row per primary key for `*_latest`; `None` when the batch ends
+    /// with a delete
+    latest: HashMap<String, Option<serde_json::Value>>,
+    /// Keys promoted to typed columns in the target tables
+    promotions: Vec<PromotedKey>,
+}
+
+struct HistoryRow {
+    change_id: i64,
+    op: ChangeOperation,
+    id: String,
+    data: serde_json::Value,
 }
 
 impl TableBatch {
-    fn new() -> Self {
-        Self {
-            upserts: Vec::new(),
-            deletes: Vec::new(),
+    fn push(&mut self, change_id: i64, op: ChangeOperation, id: String, data: serde_json::Value) {
+        let latest = (op != ChangeOperation::Delete).then(|| data.clone());
+        self.latest.insert(id.clone(), latest);
+        self.history.push(HistoryRow {
+            change_id,
+            op,
+            id,
+            data,
+        });
+    }
+
+    /// Rows to upsert into `*_latest` and primary keys to delete from it
+    fn latest_changes(&self) -> (Vec<(String, serde_json::Value)>, Vec<String>) {
+        let mut upserts = Vec::new();
+        let mut deletes = Vec::new();
+        for (id, data) in &self.latest {
+            match data {
+                Some(data) => upserts.push((id.clone(), data.clone())),
+                None => deletes.push(id.clone()),
+            }
         }
+        (upserts, deletes)
     }
 }
 
-async fn apply_changes(
-    client: &Client,
+/// Operation of a watcher change; older watchers only send it as text
+fn change_operation(change: &sqlite_watcher::watcher_proto::Change) -> Result<ChangeOperation> {
+    Ok(match change.operation() {
+        Operation::Insert => ChangeOperation::Insert,
+        Operation::Update => ChangeOperation::Update,
+        Operation::Delete => ChangeOperation::Delete,
+        Operation::Unspecified => change.op.parse()?,
+    })
+}
+
+/// Which watcher tables are synced, and the target table each lands in
+struct TableMapping {
+    filter: ReplicationFilter,
+    names: TargetNames,
+}
+
+impl TableMapping {
+    fn new(filter: &ReplicationFilter) -> Result<Self> {
+        let database = SQLITE_DATABASE_NAME;
+        // Only renames and identifier case make sense for a change feed;
+        // silently skipping masking in particular would leak data
+        if !filter.transformed_tables(database).is_empty() {
+            bail!("sync-sqlite cannot apply column transforms; remove them from --config");
+        }
+        if !filter.predicate_tables(database).is_empty()
+            || !filter.sample_tables(database).is_empty()
+            || !filter.schema_only_tables(database).is_empty()
+            || !filter.source_tables(database).is_empty()
+            || !filter.column_override_tables(database).is_empty()
+        {
+            bail!(
+                "sync-sqlite only supports table renames and identifier_case from --config; \
+                 table filters, samples, schema-only tables, sources, and column overrides apply to 'init'"
+            );
+        }
+        Ok(Self {
+            filter: filter.clone(),
+            names: filter.target_names(database),
+        })
+    }
+
+    /// Keys of a watcher table promoted to typed columns on its target
+    fn promotions(&self, table: &str) -> &[PromotedKey] {
+        self.filter
+            .promotions(SQLITE_DATABASE_NAME, "public", table)
+    }
+
+    /// Target table for a watcher table, as `table` or `schema.table`, or
+    /// `None` when the table is filtered out
+    fn target(&self, table: &str) -> Option<String> {
+        if !self
+            .filter
+            .should_replicate_table(SQLITE_DATABASE_NAME, table)
+        {
+            return None;
+        }
+        Some(match self.names.resolve("public", table) {
+            (schema, table) if schema == "public" => table,
+            (schema, table) => format!("{schema}.{table}"),
+        })
+    }
+}
+
+/// Group a batch of changes by target table, skipping filtered-out tables,
+/// along with the sync state to record per source table
+fn group_changes(
     changes: &[sqlite_watcher::watcher_proto::Change],
-    mode: IncrementalMode,
-) -> Result<()> {
+    mapping: &TableMapping,
+) -> Result<(HashMap<String, TableBatch>, HashMap<String, TableState>)> {
     let mut per_table: HashMap<String, TableBatch> = HashMap::new();
     let mut table_state: HashMap<String, TableState> = HashMap::new();
 
     for change in changes {
-        let entry = per_table
-            .entry(change.table_name.clone())
-            .or_insert_with(TableBatch::new);
-        match change.op.as_str() {
-            "insert" | "update" => {
-                let payload = if change.payload.is_empty() {
-                    serde_json::Value::Null
-                } else {
-                    serde_json::from_slice(&change.payload)
-                        .context("failed to parse change payload")?
-                };
-                entry.upserts.push((change.primary_key.clone(), payload));
-            }
-            "delete" => {
-                entry.deletes.push(change.primary_key.clone());
-            }
-            other => bail!("unknown change operation '{other}'"),
-        }
+        // Filtered-out changes are still acked with the batch
+        let Some(target_table) = mapping.target(&change.table_name) else {
+            continue;
+        };
+        let data = if change.payload.is_empty() {
+            serde_json::Value::Null
+        } else {
+            serde_json::from_slice(&change.payload).context("failed to parse change payload")?
+        };
+        let batch = per_table.entry(target_table).or_insert_with(|| TableBatch {
+            promotions: mapping.promotions(&change.table_name).to_vec(),
+            ..Default::default()
+        });
+        batch.push(
+            change.change_id,
+            change_operation(change)?,
+            change.primary_key.clone(),
+            data,
+        );
         table_state.insert(
             change.table_name.clone(),
             TableState {
@@ -162,45 +538,269 @@ async fn apply_changes(
             },
         );
     }
+    Ok((per_table, table_state))
+}
+
+/// Apply a batch of changes; `prepared_tables` remembers target tables
+/// already prepared this run, and `schemas` their recorded document schemas
+async fn apply_changes(
+    client: &Client,
+    changes: &[sqlite_watcher::watcher_proto::Change],
+    opts: &SyncSqliteOptions,
+    mapping: &TableMapping,
+    prepared_tables: &mut HashSet<String>,
+    schemas: &mut SchemaRegistry,
+) -> Result<()> {
+    let (per_table, table_state) = group_changes(changes, mapping)?;
 
     for (table, batch) in per_table.iter() {
-        if !batch.upserts.is_empty() {
-            insert_jsonb_batch(client, table, batch.upserts.clone(), "sqlite").await?;
-            if mode == IncrementalMode::AppendDeduped {
-                let latest_table = format!("{}_latest", table);
-                ensure_latest_table(client, table, &latest_table).await?;
-                upsert_jsonb_rows(client, &latest_table, &batch.upserts, "sqlite").await?;
+        let latest_table = format!("{}_latest", table);
+        if prepared_tables.insert(table.clone()) {
+            ensure_history_table(client, table, &batch.promotions).await?;
+            if opts.incremental_mode == IncrementalMode::AppendDeduped {
+                ensure_latest_table(
+                    client,
+                    table,
+                    &latest_table,
+                    opts.latest_deletes,
+                    &batch.promotions,
+                )
+                .await?;
             }
         }
-        if !batch.deletes.is_empty() {
-            delete_jsonb_rows(client, table, &batch.deletes).await?;
-            if mode == IncrementalMode::AppendDeduped {
-                let latest_table = format!("{}_latest", table);
-                ensure_latest_table(client, table, &latest_table).await?;
-                delete_jsonb_rows(client, &latest_table, &batch.deletes).await?;
+        let mut schema = DocumentSchema::default();
+        for row in batch.history.iter() {
+            if row.op != ChangeOperation::Delete {
+                schema.observe(&row.data);
+            }
+        }
+        schemas.record(client, table, "sqlite", &schema).await?;
+        append_history(client, table, &batch.history).await?;
+        if opts.incremental_mode == IncrementalMode::AppendDeduped {
+            let (upserts, deletes) = batch.latest_changes();
+            upsert_jsonb_rows(client, &latest_table, &upserts, "sqlite").await?;
+            match opts.latest_deletes {
+                LatestDeletes::Delete => delete_jsonb_rows(client, &latest_table, &deletes).await?,
+                LatestDeletes::Tombstone => {
+                    let quoted = crate::jsonb::quote_table_name(&latest_table)?;
+                    let restored: Vec<&String> = upserts.iter().map(|(id, _)| id).collect();
+                    client
+                        .execute(
+                            &format!(
+                                "UPDATE {} SET _deleted_at = NULL WHERE id = ANY($1) AND _deleted_at IS NOT NULL",
+                                quoted
+                            ),
+                            &[&restored],
+                        )
+                        .await?;
+                    client
+                        .execute(
+                            &format!(
+                                "UPDATE {} SET _deleted_at = NOW() WHERE id = ANY($1) AND _deleted_at IS NULL",
+                                quoted
+                            ),
+                            &[&deletes],
+                        )
+                        .await?;
+                }
             }
         }
     }
 
-    persist_state(client, &table_state, mode).await?;
+    persist_state(client, &table_state, opts.incremental_mode).await?;
+    Ok(())
+}
+
+/// Turn a target table into an append-only history of changes
+///
+/// The baseline from `init` keys rows by primary key; history rows add the
+/// watcher change ID and operation (baseline rows keep `_op = 'snapshot'`),
+/// so the key constraint is replaced by indexes.
+async fn ensure_history_table(
+    client: &Client,
+    table: &str,
+    promotions: &[PromotedKey],
+) -> Result<()> {
+    if let Some((schema, _)) = table.split_once('.') {
+        crate::jsonb::validate_table_name(schema)?;
+        client
+            .execute(&format!(r#"CREATE SCHEMA IF NOT EXISTS "{}""#, schema), &[])
+            .await?;
+    }
+    create_jsonb_table(client, table, "sqlite").await?;
+
+    let quoted = crate::jsonb::quote_table_name(table)?;
+    client
+        .batch_execute(&format!(
+            "ALTER TABLE {quoted} ADD COLUMN IF NOT EXISTS _change_id BIGINT, \
+             ADD COLUMN IF NOT EXISTS _op TEXT NOT NULL DEFAULT 'snapshot'"
+        ))
+        .await?;
+    let primary_keys = client
+        .query(
+            "SELECT conname FROM pg_constraint WHERE conrelid = to_regclass($1::text) AND contype = 'p'",
+            &[&quoted],
+        )
+        .await?;
+    for row in primary_keys {
+        let name: String = row.get(0);
+        client
+            .batch_execute(&format!(
+                r#"ALTER TABLE {quoted} DROP CONSTRAINT "{}""#,
+                name.replace('"', "\"\"")
+            ))
+            .await?;
+    }
+    let index_prefix = table.replace('.', "_");
+    client
+        .batch_execute(&format!(
+            r#"CREATE UNIQUE INDEX IF NOT EXISTS "{index_prefix}_change_id_idx" ON {quoted} (_change_id);
+               CREATE INDEX IF NOT EXISTS "{index_prefix}_id_change_idx" ON {quoted} (id, _change_id)"#
+        ))
+        .await?;
+    promote_jsonb_keys(client, table, promotions).await
+}
+
+/// Append changes to a history table; replayed changes are skipped
+async fn append_history(client: &Client, table: &str, rows: &[HistoryRow]) -> Result<()> {
+    let quoted = crate::jsonb::quote_table_name(table)?;
+    // Stay well under PostgreSQL's bind parameter limit
+    for chunk in rows.chunks(1000) {
+        let mut value_placeholders = Vec::with_capacity(chunk.len());
+        let ops: Vec<&str> = chunk.iter().map(|row| row.op.as_str()).collect();
+        let mut params: Vec<&(dyn ToSql + Sync)> = Vec::with_capacity(chunk.len() * 4);
+        for (idx, row) in chunk.iter().enumerate() {
+            let base = idx * 4 + 1;
+            value_placeholders.push(format!(
+                "(${}, ${}, 'sqlite', ${}, ${})",
+                base,
+                base + 1,
+                base + 2,
+                base + 3
+            ));
+            params.push(&row.id);
+            params.push(&row.data);
+            params.push(&row.change_id);
+            params.push(&ops[idx]);
+        }
+        let sql = format!(
+            "INSERT INTO {} (id, data, _source_type, _change_id, _op) VALUES {} \
+             ON CONFLICT (_change_id) DO NOTHING",
+            quoted,
+            value_placeholders.join(", ")
+        );
+        client.execute(&sql, &params).await?;
+    }
     Ok(())
 }
 
+/// Create the deduplicated `*_latest` table for a history table, seeded with
+/// the newest live row per primary key
 async fn ensure_latest_table(
     client: &Client,
-    source_table: &str,
+    table: &str,
     latest_table: &str,
+    deletes: LatestDeletes,
+    promotions: &[PromotedKey],
 ) -> Result<()> {
-    crate::jsonb::validate_table_name(source_table)?;
-    crate::jsonb::validate_table_name(latest_table)?;
-    let sql = format!(
-        r#"CREATE TABLE IF NOT EXISTS "{}" (LIKE "{}" INCLUDING ALL)"#,
-        latest_table, source_table
-    );
-    client.execute(&sql, &[]).await?;
+    let quoted = crate::jsonb::quote_table_name(table)?;
+    let quoted_latest = crate::jsonb::quote_table_name(latest_table)?;
+    let exists: bool = client
+        .query_one(
+            "SELECT to_regclass($1::text) IS NOT NULL",
+            &[&quoted_latest],
+        )
+        .await?
+        .get(0);
+    create_jsonb_table(client, latest_table, "sqlite").await?;
+    if !exists {
+        client
+            .execute(
+                &format!(
+                    "INSERT INTO {quoted_latest} (id, data, _source_type) \
+                     SELECT id, data, _source_type FROM ( \
+                         SELECT DISTINCT ON (id) id, data, _source_type, _op FROM {quoted} \
+                         ORDER BY id, _change_id DESC NULLS LAST \
+                     ) newest WHERE _op <> 'delete' \
+                     ON CONFLICT (id) DO NOTHING"
+                ),
+                &[],
+            )
+            .await?;
+    }
+    if deletes == LatestDeletes::Tombstone {
+        client
+            .batch_execute(&format!(
+                "ALTER TABLE {quoted_latest} ADD COLUMN IF NOT EXISTS _deleted_at TIMESTAMP"
+            ))
+            .await?;
+    }
+    promote_jsonb_keys(client, latest_table, promotions).await
+}
+
+/// Compact the history table of every synced table with a baseline
+async fn compact_synced_tables(
+    client: &Client,
+    mapping: &TableMapping,
+    prepared_tables: &mut HashSet<String>,
+    retention: Duration,
+) -> Result<()> {
+    let rows = client
+        .query(
+            "SELECT table_name FROM sqlite_sync_state ORDER BY table_name",
+            &[],
+        )
+        .await?;
+    let targets: BTreeMap<String, &str> = rows
+        .iter()
+        .filter_map(|row| {
+            let source: &str = row.get(0);
+            Some((mapping.target(source)?, source))
+        })
+        .collect();
+    for (table, source) in targets {
+        if prepared_tables.insert(table.clone()) {
+            ensure_history_table(client, &table, mapping.promotions(source)).await?;
+        }
+        let removed = compact_history(client, &table, retention).await?;
+        if removed > 0 {
+            tracing::info!("Compacted {} history row(s) from '{}'", removed, table);
+        }
+    }
     Ok(())
 }
 
+/// Drop history rows older than `retention` that a newer row, also older
+/// than `retention`, supersedes, along with expired deletes
+///
+/// History within the retention window, and the state of every row as of
+/// its start, is kept.
+async fn compact_history(client: &Client, table: &str, retention: Duration) -> Result<u64> {
+    let quoted = crate::jsonb::quote_table_name(table)?;
+    let seconds = retention.as_secs_f64();
+    let superseded = client
+        .execute(
+            &format!(
+                "DELETE FROM {quoted} AS old USING {quoted} AS newer \
+                 WHERE newer.id = old.id \
+                   AND COALESCE(newer._change_id, 0) > COALESCE(old._change_id, 0) \
+                   AND newer._migrated_at < NOW() - make_interval(secs => $1)"
+            ),
+            &[&seconds],
+        )
+        .await?;
+    let deleted = client
+        .execute(
+            &format!(
+                "DELETE FROM {quoted} \
+                 WHERE _op = 'delete' AND _migrated_at < NOW() - make_interval(secs => $1)"
+            ),
+            &[&seconds],
+        )
+        .await?;
+    Ok(superseded + deleted)
+}
+
 struct TableState {
     last_change_id: i64,
     wal_frame: Option<String>,
@@ -225,6 +825,19 @@ async fn persist_state(
     Ok(())
 }
 
+/// Changes missing between the last applied change and the next batch
+///
+/// The watcher's change IDs have no gaps except where its retention policy
+/// dropped changes nobody acknowledged.
+fn missing_changes(
+    last_applied: Option<i64>,
+    changes: &[sqlite_watcher::watcher_proto::Change],
+) -> Option<i64> {
+    let first = changes.iter().map(|change| change.change_id).min()?;
+    let missing = first - last_applied? - 1;
+    (missing > 0).then_some(missing)
+}
+
 fn mode_string(mode: IncrementalMode) -> &'static str {
     match mode {
         IncrementalMode::Append => "append",
@@ -327,6 +940,9 @@ impl WatcherEndpoint {
             });
         }
         if let Some(rest) = value.strip_prefix("pipe:") {
+            if rest.is_empty() {
+                bail!("pipe endpoint requires a pipe name");
+            }
             return Ok(WatcherEndpoint::Pipe(rest.to_string()));
         }
         bail!("unsupported watcher endpoint: {value}");
@@ -383,57 +999,190 @@ async fn connect_watcher(
             }
         }
         WatcherEndpoint::Pipe(name) => {
-            bail!("named pipe endpoints are not supported yet: {name}")
+            #[cfg(windows)]
+            {
+                let pipe_name = sqlite_watcher::server::pipe_path(&name);
+                Endpoint::try_from("http://[::]:50051")?
+                    .connect_with_connector(service_fn(move |_| {
+                        let pipe_name = pipe_name.clone();
+                        async move { open_pipe(&pipe_name).await }
+                    }))
+                    .await?
+            }
+            #[cfg(not(windows))]
+            {
+                bail!("named pipes are only supported on Windows (pipe:{name})")
+            }
         }
     };
 
     Ok(WatcherClient::with_interceptor(channel, interceptor))
 }
 
+/// Open a client end of the watcher's named pipe, waiting while every
+/// instance is busy serving another client
+#[cfg(windows)]
+async fn open_pipe(
+    pipe_name: &str,
+) -> std::io::Result<tokio::net::windows::named_pipe::NamedPipeClient> {
+    const ERROR_PIPE_BUSY: i32 = 231;
+    loop {
+        match tokio::net::windows::named_pipe::ClientOptions::new().open(pipe_name) {
+            Err(err) if err.raw_os_error() == Some(ERROR_PIPE_BUSY) => {
+                tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            }
+            result => return result,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::table_rules::QualifiedTable;
     use sqlite_watcher::watcher_proto::Change;
 
+    #[test]
+    fn detects_changes_dropped_by_retention() {
+        let batch = |ids: &[i64]| -> Vec<Change> {
+            ids.iter()
+                .map(|&change_id| Change {
+                    change_id,
+                    ..Default::default()
+                })
+                .collect()
+        };
+        assert_eq!(missing_changes(None, &batch(&[7, 8])), None);
+        assert_eq!(missing_changes(Some(6), &batch(&[7, 8])), None);
+        assert_eq!(missing_changes(Some(3), &batch(&[7, 8])), Some(3));
+        assert_eq!(missing_changes(Some(3), &[]), None);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn fetches_ahead_within_in_flight_limit() {
+        use sqlite_watcher::queue::{ChangeQueue, NewChange};
+
+        let dir = tempfile::tempdir().unwrap();
+        let queue_path = dir.path().join("queue.db");
+        let queue = ChangeQueue::open(&queue_path).unwrap();
+        for i in 0..7 {
+            queue
+                .enqueue(&NewChange {
+                    table_name: "foo".into(),
+                    operation: ChangeOperation::Insert,
+                    primary_key: i.to_string(),
+                    payload: None,
+                    wal_frame: None,
+                    cursor: None,
+                })
+                .unwrap();
+        }
+        let addr = "127.0.0.1:56090".parse().unwrap();
+        let _server =
+            sqlite_watcher::server::spawn_tcp(addr, queue_path, "secret".to_string()).unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        let watcher = connect_watcher(
+            WatcherEndpoint::Tcp {
+                host: "127.0.0.1".into(),
+                port: 56090,
+            },
+            "secret".to_string(),
+        )
+        .await
+        .unwrap();
+
+        let permits = Arc::new(Semaphore::new(2));
+        let (sender, mut batches) = mpsc::channel(2);
+        let fetcher = tokio::spawn(fetch_batches(
+            watcher,
+            String::new(),
+            3,
+            None,
+            Arc::clone(&permits),
+            2,
+            sender,
+        ));
+        let first = batches.recv().await.unwrap();
+        let second = batches.recv().await.unwrap();
+        // Nothing was acked, yet the second batch continues after the first
+        let ids = |batch: &FetchedBatch| -> Vec<i64> {
+            batch.changes.iter().map(|c| c.change_id).collect()
+        };
+        assert_eq!(ids(&first), [1, 2, 3]);
+        assert_eq!(ids(&second), [4, 5, 6]);
+        // Both permits are held, so the last batch waits for one to be released
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert!(batches.try_recv().is_err());
+        drop(first);
+        let third = batches.recv().await.unwrap();
+        assert_eq!(ids(&third), [7]);
+        fetcher.await.unwrap().unwrap();
+        assert!(batches.recv().await.is_none());
+    }
+
+    #[test]
+    fn maps_filtered_tables_to_targets() {
+        let mut rules = crate::table_rules::TableRules::default();
+        rules
+            .add_rename(
+                QualifiedTable::parse("main.public.users").unwrap(),
+                QualifiedTable::parse("app.people").unwrap(),
+            )
+            .unwrap();
+        let filter =
+            ReplicationFilter::new(None, None, None, Some(vec!["main.sessions".to_string()]))
+                .unwrap()
+                .with_table_rules(rules);
+        let mapping = TableMapping::new(&filter).unwrap();
+        assert_eq!(mapping.target("users").as_deref(), Some("app.people"));
+        assert_eq!(mapping.target("orders").as_deref(), Some("orders"));
+        assert_eq!(mapping.target("sessions"), None);
+
+        let mut rules = crate::table_rules::TableRules::default();
+        rules
+            .apply_table_filter_cli(&["main.public.orders:id > 10".to_string()])
+            .unwrap();
+        let filter = ReplicationFilter::empty().with_table_rules(rules);
+        assert!(TableMapping::new(&filter).is_err());
+    }
+
     #[test]
     fn group_changes_by_table() {
+        let change = |change_id: i64, op: Operation, key: &str| Change {
+            change_id,
+            table_name: "foo".into(),
+            op: String::new(),
+            operation: op.into(),
+            primary_key: key.into(),
+            payload: serde_json::to_vec(&serde_json::json!({"a": change_id})).unwrap(),
+            ..Default::default()
+        };
         let changes = vec![
+            change(1, Operation::Insert, "1"),
+            change(2, Operation::Insert, "2"),
+            change(3, Operation::Delete, "2"),
+            change(4, Operation::Delete, "1"),
+            change(5, Operation::Insert, "1"),
+            // Older watchers only send the operation as text
             Change {
-                change_id: 1,
-                table_name: "foo".into(),
-                op: "insert".into(),
-                primary_key: "1".into(),
-                payload: serde_json::to_vec(&serde_json::json!({"a":1})).unwrap(),
-                wal_frame: String::new(),
-                cursor: String::new(),
-            },
-            Change {
-                change_id: 2,
-                table_name: "foo".into(),
                 op: "delete".into(),
-                primary_key: "2".into(),
-                payload: Vec::new(),
-                wal_frame: String::new(),
-                cursor: String::new(),
+                ..change(6, Operation::Unspecified, "3")
             },
         ];
-        let mut per_table: HashMap<String, TableBatch> = HashMap::new();
-        for change in changes {
-            let entry = per_table
-                .entry(change.table_name.clone())
-                .or_insert_with(TableBatch::new);
-            match change.op.as_str() {
-                "insert" | "update" => {
-                    entry
-                        .upserts
-                        .push((change.primary_key.clone(), serde_json::Value::Null));
-                }
-                "delete" => entry.deletes.push(change.primary_key.clone()),
-                _ => {}
-            }
-        }
+        let mapping = TableMapping::new(&ReplicationFilter::empty()).unwrap();
+        let (per_table, state) = group_changes(&changes, &mapping).unwrap();
+
         let foo = per_table.get("foo").unwrap();
-        assert_eq!(foo.upserts.len(), 1);
-        assert_eq!(foo.deletes.len(), 1);
+        assert_eq!(foo.history.len(), 6);
+        assert_eq!(foo.history[2].op, ChangeOperation::Delete);
+        assert_eq!(foo.history[5].op, ChangeOperation::Delete);
+        let (upserts, mut deletes) = foo.latest_changes();
+        deletes.sort();
+        assert_eq!(
+            upserts,
+            vec![("1".to_string(), serde_json::json!({"a": 5}))]
+        );
+        assert_eq!(deletes, ["2", "3"]);
+        assert_eq!(state.get("foo").unwrap().last_change_id, 6);
     }
 }
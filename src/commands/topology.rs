@@ -0,0 +1,238 @@
+// ABOUTME: Topology command implementation - show the chain of hops feeding a target
+// ABOUTME: Reads _replicator_origins and measures the lag of each hop it can reach
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use clap::Args;
+use std::collections::BTreeMap;
+use tokio_postgres::Client;
+
+use crate::commands::status::format_duration;
+use crate::postgres::connect_with_retry;
+use crate::topology::{self, Hop, HopMethod, Node};
+use crate::utils::replace_database_in_connection_string;
+
+#[derive(Args)]
+pub struct TopologyArgs {
+    /// Database server at the end of the chain; defaults to the saved target
+    #[arg(long)]
+    pub target: Option<String>,
+    /// Replica further up the chain, to measure the lag of the hop into it too (repeatable)
+    #[arg(long = "via", value_name = "URL")]
+    pub via: Vec<String>,
+    /// Databases to show (comma-separated); defaults to every database with recorded hops
+    #[arg(long, value_delimiter = ',')]
+    pub include_databases: Option<Vec<String>>,
+}
+
+pub async fn command(args: TopologyArgs) -> Result<()> {
+    let target = match args.target {
+        Some(target) => target,
+        None => crate::state::load()?.target_url.ok_or_else(|| {
+            anyhow::anyhow!("Target database URL not provided and not set in state. Use `--target` or `database-replicator target set`.")
+        })?,
+    };
+    let explicit = args.include_databases.is_some();
+    let databases = match args.include_databases {
+        Some(databases) => databases,
+        None => {
+            let client = connect_with_retry(&target).await?;
+            crate::migration::list_databases(&client)
+                .await?
+                .into_iter()
+                .map(|db| db.name)
+                .collect()
+        }
+    };
+
+    let mut shown = 0;
+    for database in &databases {
+        let target_db_url = replace_database_in_connection_string(&target, database)?;
+        let client = connect_with_retry(&target_db_url).await?;
+        let mut hops = topology::load_hops(&client).await?;
+        if hops.is_empty() {
+            if explicit {
+                println!("{}: no hops recorded", database);
+            }
+            continue;
+        }
+
+        let mut lags = BTreeMap::new();
+        let node = Node::identify(&client, &target_db_url).await?;
+        lags.insert(node.id, logical_lag(&client).await?);
+        for via in &args.via {
+            let via_db_url = replace_database_in_connection_string(via, database)?;
+            let client = connect_with_retry(&via_db_url).await?;
+            let node = Node::identify(&client, &via_db_url).await?;
+            lags.insert(node.id, logical_lag(&client).await?);
+            merge_hops(&mut hops, topology::load_hops(&client).await?);
+        }
+
+        println!("{}:", database);
+        for line in hop_lines(&hops, &lags, Utc::now()) {
+            println!("  {}", line);
+        }
+        shown += 1;
+    }
+    if shown == 0 && !explicit {
+        println!("No replicated databases found; run init or sync into the target first");
+    }
+    Ok(())
+}
+
+/// Milliseconds since the least current subscription in this database heard
+/// from its publisher; `None` when no subscription is streaming
+async fn logical_lag(client: &Client) -> Result<Option<i64>> {
+    let row = client
+        .query_one(
+            "SELECT (EXTRACT(EPOCH FROM max(now() - s.latest_end_time)) * 1000)::bigint
+             FROM pg_stat_subscription s
+             JOIN pg_subscription sub ON sub.oid = s.subid
+             WHERE s.relid IS NULL
+               AND sub.subdbid = (SELECT oid FROM pg_database WHERE datname = current_database())",
+            &[],
+        )
+        .await
+        .context("Failed to query subscription lag")?;
+    Ok(row.get(0))
+}
+
+/// Take the method and sync time of hops a replica recorded itself, which are
+/// fresher than the copies made when the next hop last ran
+fn merge_hops(hops: &mut [Hop], recorded: Vec<Hop>) {
+    for fresh in recorded {
+        if let Some(hop) = hops.iter_mut().find(|hop| {
+            hop.source.id == fresh.source.id
+                && hop.target.id == fresh.target.id
+                && hop.synced_at < fresh.synced_at
+        }) {
+            hop.method = fresh.method;
+            hop.synced_at = fresh.synced_at;
+        }
+    }
+}
+
+/// How far behind a hop is, as far as this run can tell
+///
+/// `lags` holds the logical lag of each database this run connected to.
+fn hop_lag(hop: &Hop, lags: &BTreeMap<String, Option<i64>>, now: DateTime<Utc>) -> String {
+    let age = format_duration((now - hop.synced_at).num_milliseconds().max(0));
+    match hop.method {
+        HopMethod::Logical => match lags.get(&hop.target.id) {
+            Some(Some(ms)) => format_duration(*ms),
+            Some(None) => "not streaming".to_string(),
+            None => "unknown (pass --via to measure)".to_string(),
+        },
+        HopMethod::Xmin => format!("synced {} ago", age),
+        HopMethod::Snapshot => format!("copied {} ago", age),
+    }
+}
+
+/// Aligned table of hops, origin first, header first
+fn hop_lines(
+    hops: &[Hop],
+    lags: &BTreeMap<String, Option<i64>>,
+    now: DateTime<Utc>,
+) -> Vec<String> {
+    let mut ordered: Vec<&Hop> = hops.iter().collect();
+    ordered.sort_by(|a, b| {
+        b.distance
+            .cmp(&a.distance)
+            .then_with(|| a.source.label.cmp(&b.source.label))
+    });
+
+    let mut rows = vec![[
+        "FROM".to_string(),
+        "TO".to_string(),
+        "METHOD".to_string(),
+        "LAG".to_string(),
+    ]];
+    for hop in ordered {
+        rows.push([
+            hop.source.label.clone(),
+            hop.target.label.clone(),
+            hop.method.as_str().to_string(),
+            hop_lag(hop, lags, now),
+        ]);
+    }
+
+    let mut widths = [0usize; 4];
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    rows.iter()
+        .map(|row| {
+            row.iter()
+                .zip(widths)
+                .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(host: &str) -> Node {
+        Node {
+            id: format!("7000/{}", host),
+            label: format!("{}:5432/app", host),
+        }
+    }
+
+    fn hop(source: &str, target: &str, method: HopMethod, distance: i32, age: i64) -> Hop {
+        Hop {
+            source: node(source),
+            target: node(target),
+            method,
+            distance,
+            synced_at: DateTime::from_timestamp(1_000_000 - age, 0).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_hop_lines() {
+        let now = DateTime::from_timestamp(1_000_000, 0).unwrap();
+        let hops = vec![
+            hop("b", "c", HopMethod::Xmin, 1, 12),
+            hop("a", "b", HopMethod::Logical, 2, 3600),
+            hop("z", "b", HopMethod::Snapshot, 2, 7200),
+        ];
+        let lags = BTreeMap::from([(node("c").id, None)]);
+        assert_eq!(
+            hop_lines(&hops, &lags, now),
+            vec![
+                "FROM        TO          METHOD    LAG",
+                "a:5432/app  b:5432/app  logical   unknown (pass --via to measure)",
+                "z:5432/app  b:5432/app  snapshot  copied 2h 0m ago",
+                "b:5432/app  c:5432/app  xmin      synced 12.0s ago",
+            ]
+        );
+
+        let lags = BTreeMap::from([(node("b").id, Some(250))]);
+        assert_eq!(hop_lag(&hops[1], &lags, now), "250ms");
+        let lags = BTreeMap::from([(node("b").id, None)]);
+        assert_eq!(hop_lag(&hops[1], &lags, now), "not streaming");
+    }
+
+    #[test]
+    fn test_merge_hops_keeps_fresher_records() {
+        let mut hops = vec![hop("a", "b", HopMethod::Snapshot, 2, 3600)];
+        merge_hops(
+            &mut hops,
+            vec![
+                hop("a", "b", HopMethod::Xmin, 1, 5),
+                hop("x", "a", HopMethod::Xmin, 2, 5),
+            ],
+        );
+        assert_eq!(hops.len(), 1);
+        assert_eq!(hops[0].method, HopMethod::Xmin);
+        assert_eq!(hops[0].distance, 2);
+    }
+}
@@ -1,6 +1,9 @@
 // ABOUTME: Pre-flight validation command for migration readiness
 // ABOUTME: Checks connectivity, privileges, and version compatibility
 
+use crate::postgres::compatibility::Severity;
+use crate::replication::identity::set_replica_identity_full;
+use crate::replication::publication::replica_identity_problems;
 use crate::{migration, postgres, utils};
 use anyhow::{bail, Context, Result};
 
@@ -14,8 +17,15 @@ use anyhow::{bail, Context, Result};
 /// - Shows which databases will be replicated
 /// - Verifies source user has REPLICATION privilege
 /// - Verifies target user has CREATEDB privilege
-/// - Confirms PostgreSQL major versions match
-/// - Validates extension compatibility and preload requirements
+/// - Compares source and target server versions with the pg_dump, pg_restore,
+///   and psql versions, and reports the pg_dump flags init picks for the target
+/// - Reports extension, custom type, and collation compatibility with remediation hints,
+///   and warns when encoding, locale, or collation versions differ from the target
+/// - Warns about tables whose UPDATEs and DELETEs logical replication cannot
+///   publish, or needs `REPLICA IDENTITY FULL` for (when the source is logical)
+/// - Warns about `UNLOGGED` tables, which logical replication cannot publish
+/// - Warns about views, functions, and triggers that depend on objects the
+///   filters leave out
 ///
 /// # Arguments
 ///
@@ -36,7 +46,9 @@ use anyhow::{bail, Context, Result};
 /// - No databases match filter criteria
 /// - Source user lacks REPLICATION privilege
 /// - Target user lacks CREATEDB privilege
-/// - PostgreSQL major versions don't match
+/// - pg_dump is older than the source server, or pg_restore older than pg_dump
+/// - Source objects use features or settings the target's version lacks
+/// - The target is missing extensions or collations used by a source database
 ///
 /// # Examples
 ///
@@ -71,13 +83,46 @@ pub async fn validate(
     source_url: &str,
     target_url: &str,
     filter: crate::filters::ReplicationFilter,
+) -> Result<()> {
+    validate_with_options(source_url, target_url, filter, ValidateOptions::default()).await
+}
+
+/// Optional validation behavior
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ValidateOptions {
+    /// Set `REPLICA IDENTITY FULL` on replicated tables whose UPDATEs and
+    /// DELETEs logical replication could not publish otherwise
+    pub fix_replica_identity: bool,
+    /// Replication mode whose privileges to check; `None` picks the mode sync
+    /// would use for the source's `wal_level`
+    pub mode: Option<postgres::grants::ReplicationMode>,
+}
+
+/// Validate migration readiness, with the extra behavior in `options`
+///
+/// Same as [`validate`]. With `options.fix_replica_identity`, tables the
+/// replica identity check flags are altered on the source instead of only
+/// reported; tables the source user does not own are still reported.
+pub async fn validate_with_options(
+    source_url: &str,
+    target_url: &str,
+    filter: crate::filters::ReplicationFilter,
+    options: ValidateOptions,
 ) -> Result<()> {
     tracing::info!("Starting validation...");
 
     // Step 0a: Check for required tools
     tracing::info!("Checking for required PostgreSQL client tools...");
-    utils::check_required_tools().context("Required tools check failed")?;
-    tracing::info!("✓ Required tools found (pg_dump, pg_dumpall, psql)");
+    let missing_tools = utils::missing_client_tools();
+    if missing_tools.is_empty() {
+        tracing::info!("✓ Required tools found (pg_dump, pg_dumpall, pg_restore, psql)");
+    } else {
+        tracing::warn!(
+            "⚠ Missing {}: init will copy schema and data over plain connections instead",
+            missing_tools.join(", ")
+        );
+        tracing::warn!("  Rerun with --download-pg-tools to fetch the PostgreSQL client tools");
+    }
 
     // Step 0b: Validate connection strings
     tracing::info!("Validating connection strings...");
@@ -149,26 +194,33 @@ pub async fn validate(
         .context("Failed to connect to target database")?;
     tracing::info!("✓ Connected to target");
 
-    // Step 4: Check source privileges
-    tracing::info!("Checking source privileges...");
-    let source_privs = postgres::check_source_privileges(&source_client).await?;
-    if !source_privs.has_replication && !source_privs.is_superuser {
-        bail!("Source user lacks REPLICATION privilege. Grant with: ALTER USER <user> WITH REPLICATION;");
-    }
-    tracing::info!("✓ Source has replication privileges");
-
-    // Step 5: Check target privileges
-    tracing::info!("Checking target privileges...");
-    let target_privs = postgres::check_target_privileges(&target_client).await?;
-    if !target_privs.has_create_db && !target_privs.is_superuser {
+    // Step 4: Check the privileges the planned replication needs
+    let mode = match options.mode {
+        Some(mode) => mode,
+        None => postgres::grants::ReplicationMode::for_wal_level(
+            &postgres::check_wal_level(&source_client).await?,
+        ),
+    };
+    tracing::info!("Checking privileges for {} replication...", mode);
+    let database_names: Vec<String> = databases.iter().map(|db| db.name.clone()).collect();
+    let grants =
+        postgres::grants::analyze(source_url, target_url, &database_names, &filter, mode).await?;
+    grants.print();
+    if grants.missing_count() > 0 {
         bail!(
-            "Target user lacks CREATE DATABASE privilege. Grant with: ALTER USER <user> CREATEDB;"
+            "Source or target user lacks {} privilege(s) that {} replication needs. \
+             Run the GRANT statements above.",
+            grants.missing_count(),
+            mode
         );
     }
+    tracing::info!("✓ Source and target users have every privilege needed");
+
+    // Step 5: Check target role privileges
+    let target_privs = postgres::check_target_privileges(&target_client).await?;
     if !target_privs.has_create_role && !target_privs.is_superuser {
         tracing::warn!("⚠ Target user lacks CREATE ROLE privilege. Role migration may fail.");
     }
-    tracing::info!("✓ Target has sufficient privileges");
 
     // Step 5a: Check target wal_level for logical replication
     tracing::info!("Checking target wal_level setting...");
@@ -184,164 +236,165 @@ pub async fn validate(
         tracing::warn!("  You can still perform initial snapshot replication");
     }
 
-    // Step 6: Check PostgreSQL versions
+    // Step 6: Check server and client tool versions against each other
     tracing::info!("Checking PostgreSQL versions...");
-    let source_version = get_pg_version(&source_client).await?;
-    let target_version = get_pg_version(&target_client).await?;
-
-    if source_version.major != target_version.major {
+    let versions =
+        postgres::versions::VersionMatrix::detect(&source_client, &target_client).await?;
+    tracing::info!("  {}", versions);
+    let version_issues = versions.issues();
+    for issue in &version_issues {
+        match issue.severity {
+            Severity::Error => tracing::error!("✗ {}", issue.message),
+            Severity::Warning => tracing::warn!("⚠ {}", issue.message),
+        }
+        tracing::info!("    Fix: {}", issue.remediation);
+    }
+    for flag in versions.schema_dump_flags() {
+        tracing::info!("  init will pass {} to pg_dump", flag);
+    }
+    for setting in versions.unsupported_dump_settings() {
+        tracing::info!("  init will leave SET {} out of the schema dump", setting);
+    }
+    let version_errors = version_issues
+        .iter()
+        .filter(|issue| issue.severity == Severity::Error)
+        .count();
+    if version_errors > 0 {
         bail!(
-            "PostgreSQL major version mismatch: source={}.{}, target={}.{}. Logical replication requires same major version.",
-            source_version.major, source_version.minor,
-            target_version.major, target_version.minor
+            "{} PostgreSQL version incompatibility(ies). See the messages above.",
+            version_errors
         );
     }
-    tracing::info!(
-        "✓ Version compatibility confirmed (both {}.{})",
-        source_version.major,
-        source_version.minor
-    );
-
-    // Step 7: Check extension compatibility
-    tracing::info!("Checking extension compatibility...");
-    check_extension_compatibility(&source_client, &target_client).await?;
-    tracing::info!("✓ Extension compatibility confirmed");
-
-    tracing::info!("");
-    tracing::info!("✅ Validation complete - ready for migration");
-    tracing::info!("");
-    tracing::info!(
-        "The following {} database(s) will be replicated:",
-        databases.len()
-    );
-    for db in &databases {
-        tracing::info!("  ✓ {}", db.name);
-    }
-    Ok(())
-}
-
-struct PgVersion {
-    major: u32,
-    minor: u32,
-}
-
-async fn get_pg_version(client: &tokio_postgres::Client) -> Result<PgVersion> {
-    let row = client
-        .query_one("SHOW server_version", &[])
-        .await
-        .context("Failed to get PostgreSQL version")?;
-
-    let version_str: String = row.get(0);
-
-    // Parse version string like "16.2 (Debian 16.2-1.pgdg120+1)"
-    let parts: Vec<&str> = version_str
-        .split_whitespace()
-        .next()
-        .unwrap_or("0.0")
-        .split('.')
-        .collect();
-
-    let major = parts.first().and_then(|s| s.parse().ok()).unwrap_or(0);
-    let minor = parts.get(1).and_then(|s| s.parse().ok()).unwrap_or(0);
-
-    Ok(PgVersion { major, minor })
-}
-
-async fn check_extension_compatibility(
-    source_client: &tokio_postgres::Client,
-    target_client: &tokio_postgres::Client,
-) -> Result<()> {
-    // Get installed extensions from source
-    let source_extensions = postgres::get_installed_extensions(source_client)
-        .await
-        .context("Failed to get source extensions")?;
-
-    // If no extensions on source (besides plpgsql), skip checks
-    if source_extensions.is_empty() {
-        tracing::info!("  No extensions found on source database");
-        return Ok(());
+    tracing::info!("✓ Version compatibility confirmed");
+
+    // Step 7: Check extension, custom type, collation, and feature compatibility
+    tracing::info!("Checking extension, type, collation, and feature compatibility...");
+    let report = postgres::compatibility::build_report(
+        &database_names,
+        |db| utils::replace_database_in_connection_string(source_url, db),
+        &target_client,
+        &versions,
+    )
+    .await?;
+    report.print();
+    if report.has_errors() {
+        bail!(
+            "Target is not compatible with {} source object(s). See the compatibility report above.",
+            report.error_count()
+        );
     }
-
-    tracing::info!(
-        "  Found {} extension(s) on source: {}",
-        source_extensions.len(),
-        source_extensions
-            .iter()
-            .map(|e| &e.name)
-            .cloned()
-            .collect::<Vec<_>>()
-            .join(", ")
-    );
-
-    // Get available extensions on target
-    let target_available = postgres::get_available_extensions(target_client)
-        .await
-        .context("Failed to get target available extensions")?;
-
-    // Get preloaded libraries on target
-    let target_preloaded = postgres::get_preloaded_libraries(target_client)
-        .await
-        .context("Failed to get target preloaded libraries")?;
-
-    let mut errors = Vec::new();
-    let mut warnings = Vec::new();
-
-    // Check each source extension
-    for source_ext in &source_extensions {
-        // Check if extension is available on target
-        let target_ext = target_available.iter().find(|e| e.name == source_ext.name);
-
-        match target_ext {
-            None => {
-                errors.push(format!(
-                    "Extension '{}' (version {}) is required but not available on target",
-                    source_ext.name, source_ext.version
-                ));
-            }
-            Some(target) => {
-                // Check if extension requires preloading
-                if postgres::requires_preload(&source_ext.name) {
-                    let is_preloaded = target_preloaded.iter().any(|lib| lib == &source_ext.name);
-
-                    if !is_preloaded {
-                        errors.push(format!(
-                            "Extension '{}' requires preloading but is not in shared_preload_libraries on target. \
-                             Add to postgresql.conf: shared_preload_libraries = '{}' and restart PostgreSQL.",
-                            source_ext.name, source_ext.name
-                        ));
-                    }
+    tracing::info!("✓ Extension, type, collation, and version compatibility confirmed");
+
+    // Step 8: Check that logical replication can publish every table's changes
+    if postgres::check_wal_level(&source_client).await? == "logical" {
+        tracing::info!("Checking replica identity of replicated tables...");
+        let mut unfixed = 0;
+        let mut fixed = 0;
+        for db in &databases {
+            let db_url = utils::replace_database_in_connection_string(source_url, &db.name)?;
+            let db_client = postgres::connect(&db_url)
+                .await
+                .with_context(|| format!("Failed to connect to source database '{}'", db.name))?;
+            for problem in replica_identity_problems(&db_client, &db.name, &filter).await? {
+                if !options.fix_replica_identity {
+                    tracing::warn!("⚠ [{}] {}", db.name, problem.message);
+                    unfixed += 1;
+                    continue;
                 }
-
-                // Warn on version mismatch
-                if let Some(target_version) = &target.default_version {
-                    let source_major = source_ext.version.split('.').next().unwrap_or("0");
-                    let target_major = target_version.split('.').next().unwrap_or("0");
-
-                    if source_major != target_major {
-                        warnings.push(format!(
-                            "Extension '{}' version mismatch: source={}, target default={}",
-                            source_ext.name, source_ext.version, target_version
-                        ));
+                let table = format!("{}.{}", problem.table.schema, problem.table.name);
+                match set_replica_identity_full(&db_client, &problem.table).await {
+                    Ok(()) => {
+                        tracing::info!("✓ [{}] Set REPLICA IDENTITY FULL on {}", db.name, table);
+                        fixed += 1;
+                    }
+                    Err(e) => {
+                        tracing::warn!("⚠ [{}] {}", db.name, problem.message);
+                        tracing::warn!("  {:#}", e);
+                        unfixed += 1;
                     }
                 }
             }
         }
+        if fixed > 0 {
+            tracing::info!("✓ Set REPLICA IDENTITY FULL on {} table(s)", fixed);
+        }
+        if unfixed == 0 {
+            tracing::info!("✓ Every replicated table has a usable replica identity");
+        } else {
+            tracing::warn!(
+                "⚠ {} table(s) will not replicate UPDATEs and DELETEs correctly with logical replication",
+                unfixed
+            );
+            if !options.fix_replica_identity {
+                tracing::warn!(
+                    "  Run validate with --fix-replica-identity to set REPLICA IDENTITY FULL on them"
+                );
+            }
+        }
     }
 
-    // Report warnings
-    for warning in &warnings {
-        tracing::warn!("  ⚠ {}", warning);
+    // Step 9: Find UNLOGGED tables, which logical replication cannot publish
+    tracing::info!("Checking for UNLOGGED tables...");
+    let mut unlogged = Vec::new();
+    for db in &databases {
+        let db_url = utils::replace_database_in_connection_string(source_url, &db.name)?;
+        let db_client = postgres::connect(&db_url)
+            .await
+            .with_context(|| format!("Failed to connect to source database '{}'", db.name))?;
+        for (schema, table) in
+            migration::selected_unlogged_tables(&db_client, &db.name, &filter).await?
+        {
+            unlogged.push(format!("{}.{}.{}", db.name, schema, table));
+        }
+    }
+    if unlogged.is_empty() {
+        tracing::info!("✓ No UNLOGGED tables selected");
+    } else {
+        tracing::warn!(
+            "⚠ {} UNLOGGED table(s) bypass the WAL: {}",
+            unlogged.len(),
+            unlogged.join(", ")
+        );
+        tracing::warn!(
+            "  init copies only their schema and logical replication cannot sync them; \
+             pass --include-unlogged to init and sync to copy them and sync them by xmin polling"
+        );
     }
 
-    // Report errors and fail if any
-    if !errors.is_empty() {
-        tracing::error!("Extension compatibility check failed:");
-        for error in &errors {
-            tracing::error!("  ✗ {}", error);
+    // Step 10: Find views, functions, and triggers that depend on filtered-out objects
+    tracing::info!("Checking dependencies on filtered-out objects...");
+    let mut broken_count = 0;
+    for db in &databases {
+        let db_url = utils::replace_database_in_connection_string(source_url, &db.name)?;
+        for broken in migration::dependencies::broken_by_filter(&db_url, &filter, &db.name).await? {
+            tracing::warn!(
+                "⚠ [{}] {} depends on {}, which the filters leave out",
+                db.name,
+                broken.object,
+                broken.missing
+            );
+            broken_count += 1;
         }
-        bail!("Target database is missing required extensions or configuration. See errors above.");
+    }
+    if broken_count == 0 {
+        tracing::info!("✓ No selected object depends on a filtered-out object");
+    } else {
+        tracing::warn!(
+            "  init skips these {} object(s); include what they depend on to copy them",
+            broken_count
+        );
     }
 
+    tracing::info!("");
+    tracing::info!("✅ Validation complete - ready for migration");
+    tracing::info!("");
+    tracing::info!(
+        "The following {} database(s) will be replicated:",
+        databases.len()
+    );
+    for db in &databases {
+        tracing::info!("  ✓ {}", db.name);
+    }
     Ok(())
 }
 
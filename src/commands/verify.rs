@@ -1,11 +1,26 @@
 // ABOUTME: Verify command implementation - Validate data integrity
 // ABOUTME: Compares table checksums between source and target databases
 
-use crate::migration::{self, compare_tables, list_tables};
-use crate::postgres::connect;
-use anyhow::{Context, Result};
+use crate::checkpoint::{self, VerifiedTable, VerifyCheckpoint};
+use crate::migration::parity::{self, ParityRules, Severity, StructureFinding};
+use crate::migration::{self, compare_renamed_table, list_tables};
+use crate::postgres::snapshot::{begin_at_snapshot, ExportedSnapshot};
+use crate::postgres::{connect, PgPool};
+use crate::replicator::Progress;
+use crate::utils::quote_ident;
+use crate::xmin::SyncState;
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
 use futures::stream::{self, StreamExt};
 use indicatif::{ProgressBar, ProgressStyle};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// Tables compared at once, each using one source and one target connection
+const VERIFY_CONCURRENCY: usize = 4;
+
+/// How long to wait for a logical subscriber to apply the source snapshot
+const CATCH_UP_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(60);
 
 /// Verify data integrity between source and target databases
 ///
@@ -17,7 +32,8 @@ use indicatif::{ProgressBar, ProgressStyle};
 ///    - Reports any mismatches or missing tables
 /// 3. Provides overall validation summary across all databases
 ///
-/// Uses parallel verification (up to 4 concurrent table checks) with progress bars
+/// Uses parallel verification (up to 4 concurrent table checks, fewer when
+/// `--max-connections-per-host` is lower) with progress bars
 /// for efficient processing of large databases.
 ///
 /// # Arguments
@@ -71,9 +87,325 @@ pub async fn verify(
     source_url: &str,
     target_url: &str,
     filter: Option<crate::filters::ReplicationFilter>,
+) -> Result<()> {
+    verify_with_options(source_url, target_url, filter, VerifyOptions::default()).await
+}
+
+/// Options for [`verify_with_options`]
+#[derive(Debug, Clone, Default)]
+pub struct VerifyOptions {
+    /// Skip tables that matched in an earlier run against the same source,
+    /// target, and filters
+    pub resume: bool,
+    /// Only verify tables that xmin sync wrote rows to after this time
+    pub tables_changed_since: Option<DateTime<Utc>>,
+    /// xmin sync state file to read table changes from
+    /// (defaults to [`SyncState::default_path`])
+    pub state_path: Option<PathBuf>,
+    /// Also compare indexes, constraints, triggers, and sequence values,
+    /// reporting differences as these rules say; None compares data only
+    pub structure: Option<ParityRules>,
+}
+
+/// Progress of one `verify` run and the tables it is narrowed to
+struct VerifyRun {
+    checkpoint_path: PathBuf,
+    checkpoint: Mutex<VerifyCheckpoint>,
+    tables_changed_since: Option<DateTime<Utc>>,
+    state_path: PathBuf,
+    structure: Option<ParityRules>,
+}
+
+impl VerifyRun {
+    /// Record a table's result, dropping an earlier match if it now differs
+    fn record(&self, result: &TableVerification) {
+        let mut checkpoint = self.checkpoint.lock().unwrap();
+        if result.is_match() {
+            checkpoint.record_match(
+                &result.database,
+                &result.schema,
+                &result.table,
+                result.source_row_count,
+                &result.source_checksum,
+            );
+        } else {
+            checkpoint.forget(&result.database, &result.schema, &result.table);
+        }
+        if let Err(e) = checkpoint.save(&self.checkpoint_path) {
+            tracing::warn!("⚠ Failed to save verify progress: {:#}", e);
+        }
+    }
+}
+
+/// Verify data integrity, saving progress so an interrupted run can resume
+///
+/// Same as [`verify`], with these additions from `options`:
+/// - Each table that matches is recorded in a checkpoint in the state
+///   directory as soon as it is compared. With `resume`, tables recorded by
+///   an earlier run against the same source, target, and filters are not
+///   compared again; tables that differed or failed are. The checkpoint is
+///   removed once every table matches.
+/// - `tables_changed_since` limits the check to tables that xmin sync wrote
+///   rows to after that time. Databases without xmin sync state, such as
+///   those using logical replication, are verified in full.
+pub async fn verify_with_options(
+    source_url: &str,
+    target_url: &str,
+    filter: Option<crate::filters::ReplicationFilter>,
+    options: VerifyOptions,
 ) -> Result<()> {
     let filter = filter.unwrap_or_else(crate::filters::ReplicationFilter::empty);
 
+    let checkpoint_path = checkpoint::verify_checkpoint_path(source_url, target_url)?;
+    let filter_hash = filter.fingerprint();
+    let checkpoint = match VerifyCheckpoint::load(&checkpoint_path)? {
+        Some(existing) if options.resume => {
+            if existing.is_for(source_url, target_url, &filter_hash) {
+                tracing::info!(
+                    "Resuming verification: {} table(s) already verified",
+                    existing.verified_count()
+                );
+                existing
+            } else {
+                tracing::warn!(
+                    "⚠ Verify checkpoint was written with different filters; verifying every table"
+                );
+                VerifyCheckpoint::new(source_url, target_url, filter_hash)
+            }
+        }
+        _ => {
+            if options.resume {
+                tracing::info!("No verify checkpoint found; verifying every table");
+            }
+            VerifyCheckpoint::new(source_url, target_url, filter_hash)
+        }
+    };
+    let run = VerifyRun {
+        checkpoint_path,
+        checkpoint: Mutex::new(checkpoint),
+        tables_changed_since: options.tables_changed_since,
+        state_path: options.state_path.unwrap_or_else(SyncState::default_path),
+        structure: options.structure,
+    };
+
+    // One progress bar per database, driven by the verification events
+    let progress: std::sync::Mutex<Option<ProgressBar>> = std::sync::Mutex::new(None);
+    let on_progress = |event: &Progress| {
+        let mut bar = progress.lock().unwrap();
+        match event {
+            Progress::DatabaseStarted {
+                tables: Some(count),
+                ..
+            } => {
+                let pb = ProgressBar::new(*count as u64);
+                pb.set_style(
+                    ProgressStyle::default_bar()
+                        .template("[{elapsed_precise}] {bar:40.cyan/blue} {pos}/{len} {msg}")
+                        .unwrap()
+                        .progress_chars("##-"),
+                );
+                *bar = Some(pb);
+            }
+            Progress::TableVerified(table) => {
+                if let Some(pb) = bar.as_ref() {
+                    pb.inc(1);
+                    pb.set_message(format!("Verified {}.{}", table.schema, table.table));
+                }
+            }
+            Progress::DatabaseFinished { database } => {
+                if let Some(pb) = bar.take() {
+                    pb.finish_with_message(format!(
+                        "Verification complete for database '{}'",
+                        database
+                    ));
+                }
+            }
+            _ => {}
+        }
+    };
+
+    let report = verify_report(
+        source_url,
+        target_url,
+        &filter,
+        Some(&on_progress),
+        Some(&run),
+    )
+    .await?;
+    if report.is_consistent() {
+        if let Err(e) = std::fs::remove_file(&run.checkpoint_path) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                tracing::warn!("⚠ Failed to remove verify checkpoint: {}", e);
+            }
+        }
+    }
+    if report.databases.is_empty() {
+        return Ok(());
+    }
+
+    // Display overall summary
+    tracing::info!("========================================");
+    tracing::info!("Overall Verification Summary");
+    tracing::info!("========================================");
+    tracing::info!("Databases verified: {}", report.databases.len());
+    tracing::info!("Total tables: {}", report.tables.len());
+    tracing::info!("✓ Matches: {}", report.matches());
+    tracing::info!("✗ Mismatches: {}", report.mismatches());
+    let fail_on = run
+        .structure
+        .as_ref()
+        .map_or(Severity::Error, ParityRules::fail_on);
+    let structure_failures = report.structure_failures(fail_on);
+    if run.structure.is_some() {
+        tracing::info!(
+            "Structure differences: {} ({} at {} or above)",
+            report.structure.len(),
+            structure_failures,
+            fail_on.as_str()
+        );
+    }
+    tracing::info!("========================================");
+    tracing::info!("");
+
+    if !report.is_consistent() {
+        tracing::error!("⚠ DATA INTEGRITY ISSUES DETECTED!");
+        tracing::error!("  {} table(s) have mismatched data", report.mismatches());
+        tracing::error!("  Review the logs above for details");
+        tracing::info!("");
+        tracing::info!("Possible causes:");
+        tracing::info!("  - Replication is still catching up (check 'status' command)");
+        tracing::info!("  - Data was modified on target after migration");
+        tracing::info!("  - Migration errors occurred during 'init' or 'sync'");
+        tracing::info!("");
+        tracing::info!("Rerun with --resume to check only the tables that did not match");
+        tracing::info!("");
+
+        anyhow::bail!("{} table(s) failed verification", report.mismatches());
+    } else if structure_failures > 0 {
+        tracing::error!("⚠ TARGET STRUCTURE DIFFERS FROM SOURCE!");
+        tracing::error!(
+            "  {} structural difference(s) at {} or above",
+            structure_failures,
+            fail_on.as_str()
+        );
+        tracing::info!("  Data matches; review the differences listed above, or suppress");
+        tracing::info!("  expected ones in the [verify] section of --config");
+        tracing::info!("");
+
+        anyhow::bail!(
+            "{} structural difference(s) failed verification",
+            structure_failures
+        );
+    } else {
+        tracing::info!("✓ ALL TABLES VERIFIED SUCCESSFULLY!");
+        tracing::info!(
+            "  All {} tables match between source and target",
+            report.matches()
+        );
+        tracing::info!("  Your migration data is intact and ready for cutover");
+    }
+
+    Ok(())
+}
+
+/// Result of comparing one table between source and target
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TableStatus {
+    /// Checksums and row counts match
+    Match,
+    /// Checksums match but row counts differ
+    RowCountMismatch,
+    /// Checksums differ
+    ChecksumMismatch,
+    /// The table could not be compared
+    Error(String),
+}
+
+/// Verification outcome for a single table
+#[derive(Debug, Clone)]
+pub struct TableVerification {
+    pub database: String,
+    pub schema: String,
+    pub table: String,
+    pub source_row_count: i64,
+    pub target_row_count: i64,
+    /// Table checksums; empty when the comparison failed
+    pub source_checksum: String,
+    pub target_checksum: String,
+    pub status: TableStatus,
+}
+
+impl TableVerification {
+    /// Whether source and target hold the same data
+    pub fn is_match(&self) -> bool {
+        self.status == TableStatus::Match
+    }
+}
+
+/// Verification results across all filtered databases
+#[derive(Debug, Clone, Default)]
+pub struct VerifyReport {
+    /// Databases that were verified
+    pub databases: Vec<String>,
+    /// One entry per compared table
+    pub tables: Vec<TableVerification>,
+    /// Index, constraint, trigger, and sequence differences, when compared
+    pub structure: Vec<StructureFinding>,
+}
+
+impl VerifyReport {
+    /// Number of tables whose data matches
+    pub fn matches(&self) -> usize {
+        self.tables.iter().filter(|t| t.is_match()).count()
+    }
+
+    /// Number of tables that differ or could not be compared
+    pub fn mismatches(&self) -> usize {
+        self.tables.len() - self.matches()
+    }
+
+    /// Whether every compared table matches
+    pub fn is_consistent(&self) -> bool {
+        self.mismatches() == 0
+    }
+
+    /// Number of structural differences at `severity` or above
+    pub fn structure_failures(&self, severity: Severity) -> usize {
+        self.structure
+            .iter()
+            .filter(|finding| finding.severity >= severity)
+            .count()
+    }
+}
+
+/// Compare source and target tables and return the results
+///
+/// Does the work of [`verify`] without progress bars and without failing on
+/// mismatches, so callers can inspect each table's result. Progress is
+/// reported to `on_progress` as each database starts and finishes and as
+/// each table is compared.
+///
+/// # Errors
+///
+/// Returns an error if a database cannot be reached or its tables cannot be
+/// listed. Failures comparing a single table are recorded as
+/// [`TableStatus::Error`] instead.
+pub async fn verify_with_report(
+    source_url: &str,
+    target_url: &str,
+    filter: &crate::filters::ReplicationFilter,
+    on_progress: Option<&(dyn Fn(&Progress) + Send + Sync)>,
+) -> Result<VerifyReport> {
+    verify_report(source_url, target_url, filter, on_progress, None).await
+}
+
+async fn verify_report(
+    source_url: &str,
+    target_url: &str,
+    filter: &crate::filters::ReplicationFilter,
+    on_progress: Option<&(dyn Fn(&Progress) + Send + Sync)>,
+    run: Option<&VerifyRun>,
+) -> Result<VerifyReport> {
     tracing::info!("Starting data integrity verification...");
     tracing::info!("");
 
@@ -101,10 +433,12 @@ pub async fn verify(
         .filter(|db| filter.should_replicate_database(&db.name))
         .collect();
 
+    let mut report = VerifyReport::default();
+
     if databases.is_empty() {
         tracing::warn!("⚠ No databases matched the filter criteria");
         tracing::warn!("  No verification to perform");
-        return Ok(());
+        return Ok(report);
     }
 
     tracing::info!("Found {} database(s) to verify:", databases.len());
@@ -113,16 +447,12 @@ pub async fn verify(
     }
     tracing::info!("");
 
-    // Overall statistics across all databases
-    let mut total_matches = 0;
-    let mut total_mismatches = 0;
-    let mut total_tables = 0;
-
     // Verify each database
     for db in &databases {
         tracing::info!("========================================");
         tracing::info!("Database: '{}'", db.name);
         tracing::info!("========================================");
+        report.databases.push(db.name.clone());
 
         // Build database-specific connection URLs
         let source_db_url = replace_database_in_url(source_url, &db.name).context(format!(
@@ -134,212 +464,602 @@ pub async fn verify(
             db.name
         ))?;
 
-        // Connect to the specific database on source and target
-        tracing::info!("Connecting to database '{}'...", db.name);
-        let source_db_client = connect(&source_db_url).await.context(format!(
-            "Failed to connect to source database '{}'",
-            db.name
-        ))?;
-        let target_db_client = connect(&target_db_url).await.context(format!(
-            "Failed to connect to target database '{}'",
-            db.name
-        ))?;
+        let tables = verify_database_tables(
+            &db.name,
+            &source_db_url,
+            &target_db_url,
+            filter,
+            on_progress,
+            run,
+        )
+        .await?;
+        report.tables.extend(tables);
 
-        // List tables from source
-        tracing::info!("Discovering tables...");
-        let all_tables = list_tables(&source_db_client)
-            .await
-            .context(format!("Failed to list tables from database '{}'", db.name))?;
-
-        // Filter tables based on filter rules
-        let tables: Vec<_> = all_tables
-            .into_iter()
-            .filter(|table| {
-                // Build full table name in "database.table" format for filtering
-                let table_name = if table.schema == "public" {
-                    table.name.clone()
-                } else {
-                    format!("{}.{}", table.schema, table.name)
-                };
-                filter.should_replicate_table(&db.name, &table_name)
-            })
-            .collect();
-
-        if tables.is_empty() {
-            tracing::warn!("⚠ No tables found to verify in database '{}'", db.name);
-            tracing::info!("");
-            continue;
+        if let Some(rules) = run.and_then(|run| run.structure.as_ref()) {
+            let findings =
+                verify_structure(&db.name, &source_db_url, &target_db_url, filter, rules).await?;
+            report.structure.extend(findings);
         }
+    }
 
-        tracing::info!("Found {} tables to verify", tables.len());
-        tracing::info!("Using parallel verification (concurrency: 4)");
-        tracing::info!("");
+    Ok(report)
+}
 
-        // Create progress bar
-        let progress = ProgressBar::new(tables.len() as u64);
-        progress.set_style(
-            ProgressStyle::default_bar()
-                .template("[{elapsed_precise}] {bar:40.cyan/blue} {pos}/{len} {msg}")
-                .unwrap()
-                .progress_chars("##-"),
+/// Compare indexes, constraints, triggers, and sequences of one database
+///
+/// Only objects on tables the filter selects are compared, along with
+/// sequences no column owns.
+async fn verify_structure(
+    db_name: &str,
+    source_db_url: &str,
+    target_db_url: &str,
+    filter: &crate::filters::ReplicationFilter,
+    rules: &ParityRules,
+) -> Result<Vec<StructureFinding>> {
+    tracing::info!("Comparing structure of database '{}'...", db_name);
+    let source_client = connect(source_db_url).await.context(format!(
+        "Failed to connect to source database '{}'",
+        db_name
+    ))?;
+    let target_client = connect(target_db_url).await.context(format!(
+        "Failed to connect to target database '{}'",
+        db_name
+    ))?;
+
+    let mut selected: std::collections::HashSet<(String, String)> = list_tables(&source_client)
+        .await
+        .context(format!("Failed to list tables from database '{}'", db_name))?
+        .into_iter()
+        .filter(|table| is_table_selected(filter, db_name, table))
+        .map(|table| (table.schema, table.name))
+        .collect();
+
+    // Object definitions name their table and columns, so renamed ones would never match
+    let target_names = filter.target_names(db_name);
+    let before = selected.len();
+    selected.retain(|(schema, table)| {
+        target_names.resolve(schema, table) == (schema.clone(), table.clone())
+            && !target_names.renames_columns(schema, table)
+    });
+    if selected.len() < before {
+        tracing::info!(
+            "  Skipping the structure of {} table(s) with renamed tables or columns",
+            before - selected.len()
         );
+    }
+    let findings = parity::compare_structure(
+        db_name,
+        &source_client,
+        &target_client,
+        |schema, table| selected.contains(&(schema.to_string(), table.to_string())),
+        rules,
+    )
+    .await
+    .context(format!(
+        "Failed to compare structure of database '{}'",
+        db_name
+    ))?;
 
-        // Create additional connections for parallel processing (3 more pairs for concurrency=4)
-        let source_db_client2 = connect(&source_db_url).await.context(format!(
-            "Failed to create additional source connection 2 for database '{}'",
-            db.name
-        ))?;
-        let target_db_client2 = connect(&target_db_url).await.context(format!(
-            "Failed to create additional target connection 2 for database '{}'",
-            db.name
-        ))?;
-        let source_db_client3 = connect(&source_db_url).await.context(format!(
-            "Failed to create additional source connection 3 for database '{}'",
-            db.name
-        ))?;
-        let target_db_client3 = connect(&target_db_url).await.context(format!(
-            "Failed to create additional target connection 3 for database '{}'",
-            db.name
-        ))?;
-        let source_db_client4 = connect(&source_db_url).await.context(format!(
-            "Failed to create additional source connection 4 for database '{}'",
-            db.name
-        ))?;
-        let target_db_client4 = connect(&target_db_url).await.context(format!(
-            "Failed to create additional target connection 4 for database '{}'",
-            db.name
-        ))?;
+    if findings.is_empty() {
+        tracing::info!("  ✓ Indexes, constraints, triggers, and sequences match");
+    }
+    for finding in &findings {
+        log_structure_finding(finding);
+    }
+    tracing::info!("");
+    Ok(findings)
+}
 
-        // Store clients in an array for round-robin access (4 connections to match concurrency=4)
-        let source_clients = [
-            source_db_client,
-            source_db_client2,
-            source_db_client3,
-            source_db_client4,
-        ];
-        let target_clients = [
-            target_db_client,
-            target_db_client2,
-            target_db_client3,
-            target_db_client4,
-        ];
-
-        // Process tables in parallel with limited concurrency
-        let verification_results: Vec<_> = stream::iter(tables.iter().enumerate())
-            .map(|(idx, table)| {
-                let schema = table.schema.clone();
-                let name = table.name.clone();
-                let source_client = &source_clients[idx % source_clients.len()];
-                let target_client = &target_clients[idx % target_clients.len()];
-                let pb = progress.clone();
-
-                async move {
-                    let result = compare_tables(source_client, target_client, &schema, &name).await;
-                    pb.inc(1);
-                    pb.set_message(format!("Verified {}.{}", schema, name));
-                    (schema, name, result)
-                }
-            })
-            .buffer_unordered(4) // Process up to 4 tables concurrently
-            .collect()
-            .await;
+fn log_structure_finding(finding: &StructureFinding) {
+    let line = format!(
+        "{} {}: {}",
+        finding.kind.as_str(),
+        finding.object_name(),
+        finding.describe()
+    );
+    match finding.severity {
+        Severity::Error => tracing::error!("  ✗ {}", line),
+        Severity::Warning => tracing::warn!("  ⚠ {}", line),
+        Severity::Info => tracing::info!("  - {}", line),
+    }
+}
+
+/// Whether the filter selects a table for verification
+fn is_table_selected(
+    filter: &crate::filters::ReplicationFilter,
+    db_name: &str,
+    table: &migration::TableInfo,
+) -> bool {
+    // Build full table name in "database.table" format for filtering
+    let table_name = if table.schema == "public" {
+        table.name.clone()
+    } else {
+        format!("{}.{}", table.schema, table.name)
+    };
+    filter.should_replicate_table(db_name, &table_name)
+}
+
+/// Compare the filtered tables of one database between two exact URLs
+///
+/// Unlike [`verify_with_report`], the target database name is not derived
+/// from the source, so callers can verify a database that was replicated
+/// under a different name.
+pub async fn verify_database(
+    db_name: &str,
+    source_db_url: &str,
+    target_db_url: &str,
+    filter: &crate::filters::ReplicationFilter,
+    on_progress: Option<&(dyn Fn(&Progress) + Send + Sync)>,
+) -> Result<Vec<TableVerification>> {
+    verify_database_tables(
+        db_name,
+        source_db_url,
+        target_db_url,
+        filter,
+        on_progress,
+        None,
+    )
+    .await
+}
+
+async fn verify_database_tables(
+    db_name: &str,
+    source_db_url: &str,
+    target_db_url: &str,
+    filter: &crate::filters::ReplicationFilter,
+    on_progress: Option<&(dyn Fn(&Progress) + Send + Sync)>,
+    run: Option<&VerifyRun>,
+) -> Result<Vec<TableVerification>> {
+    let report_progress = |event: Progress| {
+        if let Some(callback) = on_progress {
+            callback(&event);
+        }
+    };
+
+    // Connect to the specific database on source and target; each table
+    // check borrows one connection per side from these pools
+    tracing::info!("Connecting to database '{}'...", db_name);
+    let (source_pool, target_pool) =
+        PgPool::new_pair(source_db_url, target_db_url, VERIFY_CONCURRENCY)?;
+    let source_db_client = source_pool.get().await.context(format!(
+        "Failed to connect to source database '{}'",
+        db_name
+    ))?;
+    let _ = target_pool.get().await.context(format!(
+        "Failed to connect to target database '{}'",
+        db_name
+    ))?;
+
+    // List tables from source
+    tracing::info!("Discovering tables...");
+    let all_tables = list_tables(&source_db_client)
+        .await
+        .context(format!("Failed to list tables from database '{}'", db_name))?;
+
+    // Filter tables based on filter rules
+    let mut tables: Vec<_> = all_tables
+        .into_iter()
+        .filter(|table| is_table_selected(filter, db_name, table))
+        .collect();
+
+    // Transformed and custom-source tables differ from the source by design
+    let transformed = filter.transformed_tables(db_name);
+    let sourced = filter.source_tables(db_name);
+    let before = tables.len();
+    tables.retain(|table| {
+        let key = format!(
+            "{}.{}",
+            quote_ident(&table.schema),
+            quote_ident(&table.name)
+        );
+        !transformed.contains_key(&key) && !sourced.contains_key(&key)
+    });
+    if tables.len() < before {
+        tracing::info!(
+            "Skipping {} table(s) with transformed columns or custom sources",
+            before - tables.len()
+        );
+    }
 
-        progress.finish_with_message(format!("Verification complete for database '{}'", db.name));
+    if tables.is_empty() {
+        tracing::warn!("⚠ No tables found to verify in database '{}'", db_name);
         tracing::info!("");
+        return Ok(Vec::new());
+    }
+
+    drop(source_db_client);
+
+    // Tables matched by an earlier run keep their result without a new comparison
+    let mut resumed = Vec::new();
+    if let Some(run) = run {
+        if let Some(since) = run.tables_changed_since {
+            tables = changed_tables(db_name, tables, since, &run.state_path).await;
+        }
+        let checkpoint = run.checkpoint.lock().unwrap();
+        tables.retain(
+            |table| match checkpoint.verified(db_name, &table.schema, &table.name) {
+                Some(verified) => {
+                    resumed.push(resumed_verification(db_name, table, verified));
+                    false
+                }
+                None => true,
+            },
+        );
+        if !resumed.is_empty() {
+            tracing::info!(
+                "Skipping {} table(s) verified by an earlier run",
+                resumed.len()
+            );
+        }
+    }
 
-        // Process results for this database
-        let mut db_mismatches = 0;
-        let mut db_matches = 0;
-
-        for (schema, name, result) in verification_results {
-            match result {
-                Ok(checksum_result) => {
-                    if checksum_result.is_valid() {
-                        tracing::info!(
-                            "  ✓ {}.{}: Match ({} rows, checksum: {})",
-                            schema,
-                            name,
-                            checksum_result.source_row_count,
-                            &checksum_result.source_checksum[..8]
-                        );
-                        db_matches += 1;
-                    } else if checksum_result.matches {
-                        tracing::warn!(
-                            "  ⚠ {}.{}: Checksum matches but row count differs: source={}, target={}",
-                            schema,
-                            name,
-                            checksum_result.source_row_count,
-                            checksum_result.target_row_count
-                        );
-                        db_mismatches += 1;
-                    } else {
-                        tracing::error!(
-                            "  ✗ {}.{}: MISMATCH: source={} ({}), target={} ({})",
-                            schema,
-                            name,
-                            &checksum_result.source_checksum[..8],
-                            checksum_result.source_row_count,
-                            &checksum_result.target_checksum[..8],
-                            checksum_result.target_row_count
-                        );
-                        db_mismatches += 1;
+    let target_names = filter.target_names(db_name);
+    let concurrency = source_pool.max_size().min(target_pool.max_size());
+    tracing::info!("Found {} tables to verify", tables.len());
+    tracing::info!("Using parallel verification (concurrency: {})", concurrency);
+    tracing::info!("");
+
+    let snapshots = if tables.is_empty() {
+        None
+    } else {
+        comparable_snapshots(db_name, source_db_url, target_db_url).await
+    };
+
+    report_progress(Progress::DatabaseStarted {
+        database: db_name.to_string(),
+        tables: Some(tables.len()),
+    });
+
+    // Process tables in parallel with limited concurrency
+    let verification_results: Vec<TableVerification> = stream::iter(tables.iter())
+        .map(|table| {
+            let (source_pool, target_pool) = (&source_pool, &target_pool);
+            let snapshots = snapshots.as_ref();
+            let report_progress = &report_progress;
+            let (target_schema, target_table) = target_names.resolve(&table.schema, &table.name);
+
+            async move {
+                let result = async {
+                    let source_client = source_pool.get().await?;
+                    let target_client = target_pool.get().await?;
+                    let compared = async {
+                        if let Some((source_snapshot, target_snapshot)) = snapshots {
+                            begin_at_snapshot(&source_client, source_snapshot.id()).await?;
+                            begin_at_snapshot(&target_client, target_snapshot.id()).await?;
+                        }
+                        compare_renamed_table(
+                            &source_client,
+                            &target_client,
+                            &table.schema,
+                            &table.name,
+                            &target_schema,
+                            &target_table,
+                        )
+                        .await
+                    }
+                    .await;
+                    // Pooled connections go back without an open transaction
+                    if snapshots.is_some() {
+                        let _ = source_client.batch_execute("COMMIT").await;
+                        let _ = target_client.batch_execute("COMMIT").await;
                     }
+                    compared
                 }
-                Err(e) => {
-                    let error_msg = format!("{}.{}: {}", schema, name, e);
-                    tracing::error!("  ✗ ERROR: {}", error_msg);
-                    db_mismatches += 1;
+                .await;
+                let verification = table_verification(db_name, table, result);
+                if let Some(run) = run {
+                    run.record(&verification);
                 }
+                report_progress(Progress::TableVerified(verification.clone()));
+                verification
             }
-        }
+        })
+        .buffer_unordered(concurrency)
+        .collect()
+        .await;
+    let verification_results: Vec<TableVerification> =
+        resumed.into_iter().chain(verification_results).collect();
 
-        // Display summary for this database
-        tracing::info!("");
-        tracing::info!("Database '{}' Summary:", db.name);
-        tracing::info!("  Total tables: {}", tables.len());
-        tracing::info!("  ✓ Matches: {}", db_matches);
-        tracing::info!("  ✗ Mismatches: {}", db_mismatches);
-        tracing::info!("");
+    report_progress(Progress::DatabaseFinished {
+        database: db_name.to_string(),
+    });
+    tracing::info!("");
 
-        // Update overall statistics
-        total_tables += tables.len();
-        total_matches += db_matches;
-        total_mismatches += db_mismatches;
+    // Log results for this database
+    for result in &verification_results {
+        log_table_verification(result);
     }
+    let db_matches = verification_results.iter().filter(|t| t.is_match()).count();
 
-    // Display overall summary
-    tracing::info!("========================================");
-    tracing::info!("Overall Verification Summary");
-    tracing::info!("========================================");
-    tracing::info!("Databases verified: {}", databases.len());
-    tracing::info!("Total tables: {}", total_tables);
-    tracing::info!("✓ Matches: {}", total_matches);
-    tracing::info!("✗ Mismatches: {}", total_mismatches);
-    tracing::info!("========================================");
+    // Display summary for this database
+    tracing::info!("");
+    tracing::info!("Database '{}' Summary:", db_name);
+    tracing::info!("  Total tables: {}", verification_results.len());
+    tracing::info!("  ✓ Matches: {}", db_matches);
+    tracing::info!(
+        "  ✗ Mismatches: {}",
+        verification_results.len() - db_matches
+    );
     tracing::info!("");
 
-    if total_mismatches > 0 {
-        tracing::error!("⚠ DATA INTEGRITY ISSUES DETECTED!");
-        tracing::error!("  {} table(s) have mismatched data", total_mismatches);
-        tracing::error!("  Review the logs above for details");
-        tracing::info!("");
-        tracing::info!("Possible causes:");
-        tracing::info!("  - Replication is still catching up (check 'status' command)");
-        tracing::info!("  - Data was modified on target after migration");
-        tracing::info!("  - Migration errors occurred during 'init' or 'sync'");
-        tracing::info!("");
+    Ok(verification_results)
+}
 
-        anyhow::bail!("{} table(s) failed verification", total_mismatches);
-    } else {
-        tracing::info!("✓ ALL TABLES VERIFIED SUCCESSFULLY!");
-        tracing::info!(
-            "  All {} tables match between source and target",
-            total_matches
+/// Snapshots of the source and target that every table comparison reads
+///
+/// Tables are then compared at the same pair of points instead of at the
+/// moment each check starts. A logical subscriber is first given time to
+/// apply everything the source snapshot holds. When the target was copied
+/// once by `init --no-sync`, it holds the source only as of its recorded
+/// consistency point, so changes made since are reported as expected
+/// differences. Returns `None`, comparing live data, when a snapshot cannot
+/// be taken.
+async fn comparable_snapshots(
+    db_name: &str,
+    source_db_url: &str,
+    target_db_url: &str,
+) -> Option<(ExportedSnapshot, ExportedSnapshot)> {
+    match export_comparable_snapshots(db_name, source_db_url, target_db_url).await {
+        Ok(snapshots) => Some(snapshots),
+        Err(e) => {
+            tracing::warn!(
+                "⚠ Comparing live data in '{}'; tables may be read at different moments: {:#}",
+                db_name,
+                e
+            );
+            None
+        }
+    }
+}
+
+async fn export_comparable_snapshots(
+    db_name: &str,
+    source_db_url: &str,
+    target_db_url: &str,
+) -> Result<(ExportedSnapshot, ExportedSnapshot)> {
+    use crate::topology::{self, HopMethod, Node};
+
+    let source_client = connect(source_db_url).await?;
+    let target_client = connect(target_db_url).await?;
+    let source = Node::identify(&source_client, source_db_url).await?;
+    let recorded = topology::load_consistency_point(&target_client, &source.id).await?;
+    let method = topology::load_hops(&target_client)
+        .await?
+        .into_iter()
+        .find(|hop| hop.distance == 1 && hop.source.id == source.id)
+        .map(|hop| hop.method);
+    if let Some(recorded) = &recorded {
+        tracing::info!("Target was copied from '{}' as of {}", db_name, recorded);
+    }
+
+    let source_snapshot = ExportedSnapshot::export(source_db_url).await?;
+    let current = source_snapshot.consistency_point();
+    match method {
+        Some(HopMethod::Logical) => {
+            wait_for_subscriptions(&source_client, &target_client, &current.lsn).await?
+        }
+        Some(HopMethod::Snapshot) => {
+            let assigned = recorded
+                .as_ref()
+                .and_then(|recorded| current.xmax()?.checked_sub(recorded.xmax()?));
+            if let Some(assigned) = assigned.filter(|assigned| *assigned > 0) {
+                tracing::warn!(
+                    "⚠ The source server has assigned {} transaction ID(s) since that snapshot; tables written since will differ",
+                    assigned
+                );
+            }
+        }
+        _ => {}
+    }
+    let target_snapshot = ExportedSnapshot::export(target_db_url).await?;
+    tracing::info!(
+        "Comparing the source at LSN {} with the target at LSN {}",
+        current.lsn,
+        target_snapshot.consistency_point().lsn
+    );
+    Ok((source_snapshot, target_snapshot))
+}
+
+/// Wait until the target's subscriptions have confirmed the source WAL up to `lsn`
+///
+/// Subscriptions are matched to the source slots of the same name. Gives up
+/// with a warning after [`CATCH_UP_TIMEOUT`], leaving recent changes to show
+/// as differences.
+async fn wait_for_subscriptions(
+    source_client: &tokio_postgres::Client,
+    target_client: &tokio_postgres::Client,
+    lsn: &str,
+) -> Result<()> {
+    let subscriptions: Vec<String> = target_client
+        .query(
+            "SELECT subname::text FROM pg_subscription
+             WHERE subdbid = (SELECT oid FROM pg_database WHERE datname = current_database())",
+            &[],
+        )
+        .await
+        .context("Failed to list subscriptions on the target")?
+        .iter()
+        .map(|row| row.get(0))
+        .collect();
+    let started = std::time::Instant::now();
+    loop {
+        let behind: i64 = source_client
+            .query_one(
+                "SELECT count(*) FROM pg_replication_slots
+                 WHERE slot_name = ANY($1)
+                   AND (confirmed_flush_lsn IS NULL OR confirmed_flush_lsn < $2::text::pg_lsn)",
+                &[&subscriptions, &lsn],
+            )
+            .await
+            .context("Failed to read replication slot progress on the source")?
+            .get(0);
+        if behind == 0 {
+            return Ok(());
+        }
+        if started.elapsed() >= CATCH_UP_TIMEOUT {
+            tracing::warn!(
+                "⚠ The target has not applied the source up to LSN {} after {}s; recent changes may differ",
+                lsn,
+                CATCH_UP_TIMEOUT.as_secs()
+            );
+            return Ok(());
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+}
+
+/// Narrow `tables` to those xmin sync wrote rows to after `since`
+///
+/// Without xmin sync state for the database, such as when it uses logical
+/// replication, there is nothing to narrow by and every table is kept.
+async fn changed_tables(
+    db_name: &str,
+    tables: Vec<migration::TableInfo>,
+    since: DateTime<Utc>,
+    state_path: &std::path::Path,
+) -> Vec<migration::TableInfo> {
+    let Some(state) = super::status::load_xmin_state(state_path, db_name).await else {
+        tracing::warn!(
+            "⚠ No xmin sync state for database '{}'; verifying every table",
+            db_name
         );
-        tracing::info!("  Your migration data is intact and ready for cutover");
+        return tables;
+    };
+    let total = tables.len();
+    let changed: Vec<_> = tables
+        .into_iter()
+        .filter(|table| {
+            state
+                .get_table(&table.schema, &table.name)
+                .is_some_and(|synced| synced.last_sync_at >= since)
+        })
+        .collect();
+    tracing::info!(
+        "{} of {} table(s) changed since {}",
+        changed.len(),
+        total,
+        since.to_rfc3339()
+    );
+    changed
+}
+
+/// The result an earlier run recorded for a table that matched
+fn resumed_verification(
+    database: &str,
+    table: &migration::TableInfo,
+    verified: &VerifiedTable,
+) -> TableVerification {
+    TableVerification {
+        database: database.to_string(),
+        schema: table.schema.clone(),
+        table: table.name.clone(),
+        source_row_count: verified.row_count,
+        target_row_count: verified.row_count,
+        source_checksum: verified.checksum.clone(),
+        target_checksum: verified.checksum.clone(),
+        status: TableStatus::Match,
+    }
+}
+
+/// Parse a `--tables-changed-since` value into a point in time
+///
+/// Accepts an RFC 3339 timestamp, or a duration back from `now` written as
+/// an amount and a unit: `90s`, `30 minutes`, `6h`, `2 days`, `1w`.
+pub fn parse_changed_since(value: &str, now: DateTime<Utc>) -> Result<DateTime<Utc>> {
+    let value = value.trim();
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(value) {
+        return Ok(timestamp.with_timezone(&Utc));
     }
 
-    Ok(())
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (amount, unit) = value.split_at(split);
+    let amount: i64 = amount.parse().with_context(|| {
+        format!(
+            "Invalid --tables-changed-since '{}': expected an RFC 3339 timestamp or a duration such as '6 hours'",
+            value
+        )
+    })?;
+    let seconds = match unit.trim().to_lowercase().as_str() {
+        "s" | "sec" | "secs" | "second" | "seconds" => 1,
+        "m" | "min" | "mins" | "minute" | "minutes" => 60,
+        "h" | "hr" | "hrs" | "hour" | "hours" => 3600,
+        "d" | "day" | "days" => 86_400,
+        "w" | "week" | "weeks" => 604_800,
+        other => bail!(
+            "Unsupported --tables-changed-since unit '{}'. Use seconds/minutes/hours/days/weeks",
+            other
+        ),
+    };
+    Ok(now - chrono::Duration::seconds(amount.saturating_mul(seconds)))
+}
+
+/// Turn a checksum comparison into a table verification result
+fn table_verification(
+    database: &str,
+    table: &migration::TableInfo,
+    result: Result<migration::ChecksumResult>,
+) -> TableVerification {
+    let mut verification = TableVerification {
+        database: database.to_string(),
+        schema: table.schema.clone(),
+        table: table.name.clone(),
+        source_row_count: 0,
+        target_row_count: 0,
+        source_checksum: String::new(),
+        target_checksum: String::new(),
+        status: TableStatus::Match,
+    };
+    match result {
+        Ok(checksum) => {
+            verification.status = if checksum.is_valid() {
+                TableStatus::Match
+            } else if checksum.matches {
+                TableStatus::RowCountMismatch
+            } else {
+                TableStatus::ChecksumMismatch
+            };
+            verification.source_row_count = checksum.source_row_count;
+            verification.target_row_count = checksum.target_row_count;
+            verification.source_checksum = checksum.source_checksum;
+            verification.target_checksum = checksum.target_checksum;
+        }
+        Err(e) => verification.status = TableStatus::Error(e.to_string()),
+    }
+    verification
+}
+
+fn log_table_verification(result: &TableVerification) {
+    let short = |checksum: &str| checksum.chars().take(8).collect::<String>();
+    match &result.status {
+        TableStatus::Match => tracing::info!(
+            "  ✓ {}.{}: Match ({} rows, checksum: {})",
+            result.schema,
+            result.table,
+            result.source_row_count,
+            short(&result.source_checksum)
+        ),
+        TableStatus::RowCountMismatch => tracing::warn!(
+            "  ⚠ {}.{}: Checksum matches but row count differs: source={}, target={}",
+            result.schema,
+            result.table,
+            result.source_row_count,
+            result.target_row_count
+        ),
+        TableStatus::ChecksumMismatch => tracing::error!(
+            "  ✗ {}.{}: MISMATCH: source={} ({}), target={} ({})",
+            result.schema,
+            result.table,
+            short(&result.source_checksum),
+            result.source_row_count,
+            short(&result.target_checksum),
+            result.target_row_count
+        ),
+        TableStatus::Error(e) => {
+            tracing::error!("  ✗ ERROR: {}.{}: {}", result.schema, result.table, e)
+        }
+    }
 }
 
 /// Replace the database name in a PostgreSQL connection URL
@@ -403,6 +1123,47 @@ mod tests {
         // Even if it finds mismatches, that's a valid result
     }
 
+    #[test]
+    fn test_parse_changed_since() {
+        let now = DateTime::parse_from_rfc3339("2024-05-10T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let ago = |value: &str| (now - parse_changed_since(value, now).unwrap()).num_seconds();
+
+        assert_eq!(ago("90s"), 90);
+        assert_eq!(ago("30 minutes"), 1800);
+        assert_eq!(ago("6h"), 6 * 3600);
+        assert_eq!(ago(" 2 days "), 2 * 86_400);
+        assert_eq!(ago("1w"), 604_800);
+        assert_eq!(
+            parse_changed_since("2024-05-09T08:30:00+02:00", now).unwrap(),
+            DateTime::parse_from_rfc3339("2024-05-09T06:30:00Z").unwrap()
+        );
+        assert!(parse_changed_since("yesterday", now).is_err());
+        assert!(parse_changed_since("3 months", now).is_err());
+        assert!(parse_changed_since("", now).is_err());
+    }
+
+    #[test]
+    fn test_resumed_verification_is_a_match() {
+        let table = migration::TableInfo {
+            schema: "public".to_string(),
+            name: "users".to_string(),
+            row_count_estimate: 0,
+            size_bytes: 0,
+            unlogged: false,
+        };
+        let verified = VerifiedTable {
+            row_count: 42,
+            checksum: "abc".to_string(),
+            verified_at: Utc::now(),
+        };
+        let result = resumed_verification("app", &table, &verified);
+        assert!(result.is_match());
+        assert_eq!(result.target_row_count, 42);
+        assert_eq!(result.target_checksum, "abc");
+    }
+
     #[test]
     fn test_replace_database_in_url() {
         // Basic URL
@@ -1,8 +1,13 @@
 // ABOUTME: Parses replication configuration files for table-level rules
 // ABOUTME: Converts TOML format into TableRules structures
 
-use crate::table_rules::{QualifiedTable, TableRules};
-use anyhow::{Context, Result};
+use crate::jsonb::promote::{PromotedKey, PromotedType};
+use crate::migration::parity::{ObjectKind, ParityRules, Severity};
+use crate::table_rules::{
+    ColumnOverride, IdentifierCase, QualifiedTable, SampleRule, TableRules, TableSource,
+};
+use crate::transform::{ColumnTransform, Transform};
+use anyhow::{bail, Context, Result};
 use serde::Deserialize;
 use std::collections::HashMap;
 use std::fs;
@@ -11,16 +16,69 @@ use std::fs;
 struct ReplicationConfig {
     #[serde(default)]
     databases: HashMap<String, DatabaseConfig>,
+    #[serde(default)]
+    verify: VerifyConfig,
+    #[serde(default)]
+    sync: SyncSettings,
+}
+
+/// Cycle intervals of xmin-based sync, read again when a running sync
+/// receives SIGHUP; flags given on the command line take precedence
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+pub struct SyncSettings {
+    /// Seconds between sync cycles
+    #[serde(default)]
+    pub interval: Option<u64>,
+    /// Seconds between reconciliation (delete detection) cycles
+    #[serde(default)]
+    pub reconcile_interval: Option<u64>,
+}
+
+/// Structural checks made by `verify`
+#[derive(Debug, Deserialize, Default)]
+struct VerifyConfig {
+    /// Findings to drop, as `kind:pattern` or `pattern`
+    #[serde(default)]
+    ignore: Vec<String>,
+    /// Severity for every finding of an object kind, e.g. `trigger = "error"`
+    #[serde(default)]
+    severity: HashMap<String, Severity>,
+    /// Lowest severity that fails verify
+    #[serde(default)]
+    fail_on: Option<Severity>,
 }
 
 #[derive(Debug, Deserialize, Default)]
 struct DatabaseConfig {
     #[serde(default)]
     schema_only: Vec<String>,
+    /// Tables init copies first, with the tables they reference
+    #[serde(default)]
+    priority: Vec<String>,
     #[serde(default)]
     table_filters: Vec<TableFilterConfig>,
     #[serde(default)]
     time_filters: Vec<TimeFilterConfig>,
+    /// Partitioned tables whose old partitions sync drops from the target
+    #[serde(default)]
+    retention: Vec<RetentionConfig>,
+    #[serde(default)]
+    samples: Vec<SampleConfig>,
+    #[serde(default)]
+    transforms: Vec<TransformConfig>,
+    #[serde(default)]
+    sources: Vec<SourceConfig>,
+    #[serde(default)]
+    renames: Vec<RenameConfig>,
+    /// "preserve", "lower", or "upper" for target schema and table names
+    #[serde(default)]
+    identifier_case: Option<String>,
+    #[serde(default)]
+    columns: Vec<ColumnConfig>,
+    /// JSON keys of JSONB target tables kept in typed, indexed columns
+    #[serde(default)]
+    promote: Vec<PromoteConfig>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -41,11 +99,100 @@ struct TimeFilterConfig {
     last: String,
 }
 
-pub fn load_table_rules_from_file(path: &str) -> Result<TableRules> {
+#[derive(Debug, Deserialize)]
+struct RetentionConfig {
+    table: String,
+    #[serde(default)]
+    schema: Option<String>,
+    keep: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct SampleConfig {
+    table: String,
+    #[serde(default)]
+    schema: Option<String>,
+    /// Percentage of rows, e.g. "10%"
+    #[serde(default)]
+    sample: Option<String>,
+    /// Fixed number of rows
+    #[serde(default)]
+    sample_rows: Option<u64>,
+    #[serde(default)]
+    seed: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct TransformConfig {
+    table: String,
+    #[serde(default)]
+    schema: Option<String>,
+    column: String,
+    /// e.g. "fake_email", "shuffle", "hash:sha256", or "const:REDACTED"
+    transform: String,
+    #[serde(default)]
+    seed: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct SourceConfig {
+    table: String,
+    #[serde(default)]
+    schema: Option<String>,
+    /// SELECT list evaluated over the table's rows
+    #[serde(default)]
+    select: Option<String>,
+    /// Source view read instead of the table, as "view" or "schema.view"
+    #[serde(default)]
+    view: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RenameConfig {
+    table: String,
+    #[serde(default)]
+    schema: Option<String>,
+    /// Target name as "table", "schema.table", or "database.schema.table"
+    rename_to: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ColumnConfig {
+    table: String,
+    #[serde(default)]
+    schema: Option<String>,
+    column: String,
+    #[serde(default)]
+    rename_to: Option<String>,
+    /// SQL type of the target column, e.g. "text"
+    #[serde(default, rename = "type")]
+    target_type: Option<String>,
+    #[serde(default)]
+    nullable: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PromoteConfig {
+    table: String,
+    #[serde(default)]
+    schema: Option<String>,
+    key: String,
+    /// Column name; defaults to the key
+    #[serde(default)]
+    column: Option<String>,
+    /// "text", "bigint", "numeric", or "boolean"
+    #[serde(rename = "type")]
+    column_type: String,
+}
+
+fn read_config(path: &str) -> Result<ReplicationConfig> {
     let raw = fs::read_to_string(path)
         .with_context(|| format!("Failed to read config file at {}", path))?;
-    let parsed: ReplicationConfig =
-        toml::from_str(&raw).with_context(|| format!("Failed to parse TOML config at {}", path))?;
+    toml::from_str(&raw).with_context(|| format!("Failed to parse TOML config at {}", path))
+}
+
+pub fn load_table_rules_from_file(path: &str) -> Result<TableRules> {
+    let parsed = read_config(path)?;
 
     let mut rules = TableRules::default();
     for (db_name, db) in parsed.databases {
@@ -53,6 +200,10 @@ pub fn load_table_rules_from_file(path: &str) -> Result<TableRules> {
             let qualified = QualifiedTable::parse(&table)?.with_database(Some(db_name.clone()));
             rules.add_schema_only_table(qualified)?;
         }
+        for table in db.priority {
+            let qualified = QualifiedTable::parse(&table)?.with_database(Some(db_name.clone()));
+            rules.add_priority_table(qualified)?;
+        }
         for filter in db.table_filters {
             // If explicit schema field is provided, use it; otherwise parse from table name
             let qualified = if let Some(schema) = filter.schema {
@@ -73,11 +224,135 @@ pub fn load_table_rules_from_file(path: &str) -> Result<TableRules> {
             };
             rules.add_time_filter(qualified, filter.column, filter.last)?;
         }
+        for retention in db.retention {
+            let qualified = if let Some(schema) = retention.schema {
+                QualifiedTable::new(Some(db_name.clone()), schema, retention.table)
+            } else {
+                QualifiedTable::parse(&retention.table)?.with_database(Some(db_name.clone()))
+            };
+            rules.add_retention(qualified, retention.keep)?;
+        }
+        for sample in db.samples {
+            let qualified = if let Some(schema) = sample.schema {
+                QualifiedTable::new(Some(db_name.clone()), schema, sample.table)
+            } else {
+                QualifiedTable::parse(&sample.table)?.with_database(Some(db_name.clone()))
+            };
+            let rule = match (sample.sample, sample.sample_rows) {
+                (Some(percent), None) => SampleRule::percent(&percent, sample.seed)?,
+                (None, Some(rows)) => SampleRule::rows(rows, sample.seed)?,
+                _ => bail!(
+                    "Sample for table '{}' must set exactly one of 'sample' or 'sample_rows'",
+                    qualified.schema_qualified()
+                ),
+            };
+            rules.add_sample(qualified, rule)?;
+        }
+        for transform in db.transforms {
+            let qualified = if let Some(schema) = transform.schema {
+                QualifiedTable::new(Some(db_name.clone()), schema, transform.table)
+            } else {
+                QualifiedTable::parse(&transform.table)?.with_database(Some(db_name.clone()))
+            };
+            let column_transform = ColumnTransform {
+                transform: Transform::parse(&transform.transform)?,
+                seed: transform.seed,
+            };
+            rules.add_transform(qualified, transform.column, column_transform)?;
+        }
+        for source in db.sources {
+            let qualified = if let Some(schema) = source.schema {
+                QualifiedTable::new(Some(db_name.clone()), schema, source.table)
+            } else {
+                QualifiedTable::parse(&source.table)?.with_database(Some(db_name.clone()))
+            };
+            let table_source = match (source.select, source.view) {
+                (Some(select), None) => TableSource::select(&select)?,
+                (None, Some(view)) => TableSource::view(&view)?,
+                _ => bail!(
+                    "Source for table '{}' must set exactly one of 'select' or 'view'",
+                    qualified.schema_qualified()
+                ),
+            };
+            rules.add_source(qualified, table_source)?;
+        }
+        for rename in db.renames {
+            let qualified = if let Some(schema) = rename.schema {
+                QualifiedTable::new(Some(db_name.clone()), schema, rename.table)
+            } else {
+                QualifiedTable::parse(&rename.table)?.with_database(Some(db_name.clone()))
+            };
+            // A bare target name keeps the table in its schema
+            let target = QualifiedTable::parse(&rename.rename_to)?;
+            let target = if rename.rename_to.contains('.') {
+                target
+            } else {
+                QualifiedTable::new(None, qualified.schema.clone(), target.table)
+            };
+            rules.add_rename(qualified, target)?;
+        }
+        for column in db.columns {
+            let qualified = if let Some(schema) = column.schema {
+                QualifiedTable::new(Some(db_name.clone()), schema, column.table)
+            } else {
+                QualifiedTable::parse(&column.table)?.with_database(Some(db_name.clone()))
+            };
+            let column_override = ColumnOverride {
+                rename_to: column.rename_to,
+                target_type: column.target_type,
+                nullable: column.nullable,
+            };
+            rules.add_column_override(qualified, column.column, column_override)?;
+        }
+        for promote in db.promote {
+            let qualified = if let Some(schema) = promote.schema {
+                QualifiedTable::new(Some(db_name.clone()), schema, promote.table)
+            } else {
+                QualifiedTable::parse(&promote.table)?.with_database(Some(db_name.clone()))
+            };
+            let promoted = PromotedKey::new(
+                &promote.key,
+                promote.column.as_deref(),
+                PromotedType::parse(&promote.column_type)?,
+            )?;
+            rules.add_promotion(qualified, promoted)?;
+        }
+        if let Some(case) = db.identifier_case {
+            rules.set_identifier_case(Some(db_name.clone()), IdentifierCase::parse(&case)?);
+        }
     }
 
     Ok(rules)
 }
 
+/// Load the `[sync]` section, which sets the intervals of xmin-based sync
+pub fn load_sync_settings_from_file(path: &str) -> Result<SyncSettings> {
+    let settings = read_config(path)?.sync;
+    if settings.interval == Some(0) || settings.reconcile_interval == Some(0) {
+        bail!("[sync] intervals in {} must be at least 1 second", path);
+    }
+    Ok(settings)
+}
+
+/// Load the `[verify]` section, which tunes the structural checks of `verify`
+pub fn load_parity_rules_from_file(path: &str) -> Result<ParityRules> {
+    let parsed = read_config(path)?;
+    let mut rules = ParityRules::default();
+    for spec in &parsed.verify.ignore {
+        rules.ignore(spec)?;
+    }
+    for (kind, severity) in parsed.verify.severity {
+        rules.set_severity(
+            ObjectKind::parse(&kind).context("Invalid [verify.severity] entry")?,
+            severity,
+        );
+    }
+    if let Some(severity) = parsed.verify.fail_on {
+        rules.set_fail_on(severity);
+    }
+    Ok(rules)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -89,6 +364,7 @@ mod tests {
         let contents = r#"
             [databases.kong]
             schema_only = ["evmlog_strides", "price"]
+            priority = ["accounts", "billing.invoices"]
 
             [[databases.kong.table_filters]]
             table = "output"
@@ -98,6 +374,10 @@ mod tests {
             table = "metrics"
             column = "created_at"
             last = "1 year"
+
+            [[databases.kong.retention]]
+            table = "analytics.events"
+            keep = "90 days"
         "#;
         use std::io::Write;
         write!(tmp, "{}", contents).unwrap();
@@ -107,8 +387,241 @@ mod tests {
             rules.schema_only_tables("kong"),
             vec!["\"public\".\"evmlog_strides\"", "\"public\".\"price\""]
         );
+        assert_eq!(
+            rules.priority_tables("kong"),
+            vec!["\"billing\".\"invoices\"", "\"public\".\"accounts\""]
+        );
         assert!(rules.table_filter("kong", "public", "output").is_some());
         assert!(rules.time_filter("kong", "public", "metrics").is_some());
+        assert_eq!(
+            rules.retention_tables("kong"),
+            vec![(
+                "analytics".to_string(),
+                "events".to_string(),
+                "90 day".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_toml_sync_settings() {
+        let mut tmp = NamedTempFile::new().unwrap();
+        use std::io::Write;
+        write!(tmp, "[sync]\ninterval = 300\n").unwrap();
+        let path = tmp.path().to_str().unwrap();
+        assert_eq!(
+            load_sync_settings_from_file(path).unwrap(),
+            SyncSettings {
+                interval: Some(300),
+                reconcile_interval: None
+            }
+        );
+
+        std::fs::write(path, "[sync]\ninterval = 0\n").unwrap();
+        assert!(load_sync_settings_from_file(path).is_err());
+        std::fs::write(path, "[sync]\nintervl = 60\n").unwrap();
+        assert!(load_sync_settings_from_file(path).is_err());
+        std::fs::write(path, "[databases.app]\n").unwrap();
+        assert_eq!(
+            load_sync_settings_from_file(path).unwrap(),
+            SyncSettings::default()
+        );
+    }
+
+    #[test]
+    fn test_toml_samples() {
+        let mut tmp = NamedTempFile::new().unwrap();
+        let contents = r#"
+            [databases.app]
+
+            [[databases.app.samples]]
+            table = "events"
+            sample = "10%"
+
+            [[databases.app.samples]]
+            table = "orders"
+            schema = "sales"
+            sample_rows = 100000
+            seed = 7
+        "#;
+        use std::io::Write;
+        write!(tmp, "{}", contents).unwrap();
+
+        let rules = load_table_rules_from_file(tmp.path().to_str().unwrap()).unwrap();
+        assert_eq!(
+            rules.sample("app", "public", "events").unwrap().to_string(),
+            "10%"
+        );
+        assert_eq!(
+            rules.sample("app", "sales", "orders").unwrap().to_string(),
+            "100000 rows (seed 7)"
+        );
+    }
+
+    #[test]
+    fn test_toml_sample_requires_one_size() {
+        let mut tmp = NamedTempFile::new().unwrap();
+        let contents = r#"
+            [[databases.app.samples]]
+            table = "events"
+            sample = "10%"
+            sample_rows = 10
+        "#;
+        use std::io::Write;
+        write!(tmp, "{}", contents).unwrap();
+
+        assert!(load_table_rules_from_file(tmp.path().to_str().unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_toml_transforms() {
+        let mut tmp = NamedTempFile::new().unwrap();
+        let contents = r#"
+            [[databases.app.transforms]]
+            table = "users"
+            column = "email"
+            transform = "fake_email"
+
+            [[databases.app.transforms]]
+            table = "crm.contacts"
+            column = "phone"
+            transform = "hash:sha256"
+            seed = 42
+        "#;
+        use std::io::Write;
+        write!(tmp, "{}", contents).unwrap();
+
+        let rules = load_table_rules_from_file(tmp.path().to_str().unwrap()).unwrap();
+        let users = rules.transforms("app", "public", "users").unwrap();
+        assert_eq!(users.get("email").unwrap().to_string(), "fake_email");
+        let contacts = rules.transforms("app", "crm", "contacts").unwrap();
+        assert_eq!(
+            contacts.get("phone").unwrap().to_string(),
+            "hash:sha256 (seed 42)"
+        );
+
+        let mut invalid = NamedTempFile::new().unwrap();
+        write!(
+            invalid,
+            "[[databases.app.transforms]]\ntable = \"users\"\ncolumn = \"email\"\ntransform = \"scramble\"\n"
+        )
+        .unwrap();
+        assert!(load_table_rules_from_file(invalid.path().to_str().unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_toml_renames() {
+        let mut tmp = NamedTempFile::new().unwrap();
+        let contents = r#"
+            [databases.app]
+            identifier_case = "lower"
+
+            [[databases.app.renames]]
+            table = "events"
+            rename_to = "analytics.events_v2"
+
+            [[databases.app.renames]]
+            table = "Orders"
+            schema = "sales"
+            rename_to = "orders_archive"
+        "#;
+        use std::io::Write;
+        write!(tmp, "{}", contents).unwrap();
+
+        let rules = load_table_rules_from_file(tmp.path().to_str().unwrap()).unwrap();
+        let names = rules.target_names("app");
+        assert_eq!(
+            names.resolve("public", "events"),
+            ("analytics".to_string(), "events_v2".to_string())
+        );
+        assert_eq!(
+            names.resolve("sales", "Orders"),
+            ("sales".to_string(), "orders_archive".to_string())
+        );
+        assert_eq!(
+            names.resolve("public", "UserAccounts"),
+            ("public".to_string(), "useraccounts".to_string())
+        );
+
+        let mut invalid = NamedTempFile::new().unwrap();
+        write!(
+            invalid,
+            "[[databases.app.renames]]\ntable = \"events\"\nrename_to = \"other.public.events\"\n"
+        )
+        .unwrap();
+        assert!(load_table_rules_from_file(invalid.path().to_str().unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_toml_column_overrides() {
+        let mut tmp = NamedTempFile::new().unwrap();
+        let contents = r#"
+            [[databases.app.columns]]
+            table = "users"
+            column = "Email"
+            rename_to = "email"
+            type = "text"
+
+            [[databases.app.columns]]
+            table = "crm.contacts"
+            column = "phone"
+            nullable = true
+        "#;
+        use std::io::Write;
+        write!(tmp, "{}", contents).unwrap();
+
+        let rules = load_table_rules_from_file(tmp.path().to_str().unwrap()).unwrap();
+        let users = rules.column_overrides("app", "public", "users").unwrap();
+        assert_eq!(users["Email"].to_string(), "renamed to email, type text");
+        let contacts = rules.column_overrides("app", "crm", "contacts").unwrap();
+        assert_eq!(contacts["phone"].nullable, Some(true));
+
+        let mut invalid = NamedTempFile::new().unwrap();
+        write!(
+            invalid,
+            "[[databases.app.columns]]\ntable = \"users\"\ncolumn = \"email\"\n"
+        )
+        .unwrap();
+        assert!(load_table_rules_from_file(invalid.path().to_str().unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_toml_sources() {
+        let mut tmp = NamedTempFile::new().unwrap();
+        let contents = r#"
+            [[databases.app.sources]]
+            table = "users"
+            select = "id, lower(email) AS email"
+
+            [[databases.app.sources]]
+            table = "orders"
+            schema = "sales"
+            view = "staging.orders_export"
+        "#;
+        use std::io::Write;
+        write!(tmp, "{}", contents).unwrap();
+
+        let rules = load_table_rules_from_file(tmp.path().to_str().unwrap()).unwrap();
+        assert_eq!(
+            rules.source("app", "public", "users"),
+            Some(&TableSource::Select(
+                "id, lower(email) AS email".to_string()
+            ))
+        );
+        assert_eq!(
+            rules.source("app", "sales", "orders"),
+            Some(&TableSource::View(
+                "\"staging\".\"orders_export\"".to_string()
+            ))
+        );
+
+        let mut both = NamedTempFile::new().unwrap();
+        write!(
+            both,
+            "[[databases.app.sources]]\ntable = \"users\"\nselect = \"id\"\nview = \"users_v\"\n"
+        )
+        .unwrap();
+        assert!(load_table_rules_from_file(both.path().to_str().unwrap()).is_err());
     }
 
     #[test]
@@ -187,4 +700,63 @@ mod tests {
         // Check default to public when no schema
         assert!(rules.table_filter("db1", "public", "logs").is_some());
     }
+
+    #[test]
+    fn parse_promote_config() {
+        let mut tmp = NamedTempFile::new().unwrap();
+        let contents = r#"
+            [[databases.main.promote]]
+            table = "users"
+            key = "email"
+            type = "text"
+
+            [[databases.main.promote]]
+            table = "users"
+            key = "signup-score"
+            column = "signup_score"
+            type = "numeric"
+        "#;
+        use std::io::Write;
+        write!(tmp, "{}", contents).unwrap();
+
+        let rules = load_table_rules_from_file(tmp.path().to_str().unwrap()).unwrap();
+        let promoted = rules.promotions("main", "public", "users");
+        assert_eq!(promoted.len(), 2);
+        assert_eq!(promoted[1].column, "signup_score");
+        assert_eq!(promoted[1].column_type, PromotedType::Numeric);
+        assert!(rules.promotions("main", "public", "orders").is_empty());
+
+        let mut tmp = NamedTempFile::new().unwrap();
+        write!(
+            tmp,
+            "[[databases.main.promote]]\ntable = \"users\"\nkey = \"seen\"\ntype = \"timestamptz\"\n"
+        )
+        .unwrap();
+        assert!(load_table_rules_from_file(tmp.path().to_str().unwrap()).is_err());
+    }
+
+    #[test]
+    fn parse_verify_config() {
+        let mut tmp = NamedTempFile::new().unwrap();
+        let contents = r#"
+            [verify]
+            ignore = ["trigger:public.orders.audit_*", "index:*.tmp_*"]
+            fail_on = "warning"
+
+            [verify.severity]
+            index = "info"
+        "#;
+        use std::io::Write;
+        write!(tmp, "{}", contents).unwrap();
+
+        let path = tmp.path().to_str().unwrap();
+        let rules = load_parity_rules_from_file(path).unwrap();
+        assert_eq!(rules.fail_on(), Severity::Warning);
+        // Table rules from the same file ignore the section
+        assert!(load_table_rules_from_file(path).is_ok());
+
+        let mut tmp = NamedTempFile::new().unwrap();
+        write!(tmp, "[verify.severity]\nview = \"error\"\n").unwrap();
+        assert!(load_parity_rules_from_file(tmp.path().to_str().unwrap()).is_err());
+    }
 }
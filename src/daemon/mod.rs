@@ -0,0 +1,813 @@
+// ABOUTME: Daemon mode support for running sync as a background service
+// ABOUTME: Cross-platform: Unix (fork) and Windows (detached process with a control pipe)
+
+#[cfg(windows)]
+mod windows;
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use tokio::sync::watch;
+
+/// Get the directory for storing daemon state files.
+/// Returns ~/.seren-replicator/ on Unix or %APPDATA%\seren-replicator\ on Windows
+pub fn get_daemon_dir() -> Result<PathBuf> {
+    #[cfg(windows)]
+    let daemon_dir = {
+        let app_data = dirs::data_local_dir().context("Failed to determine AppData directory")?;
+        app_data.join("seren-replicator")
+    };
+
+    #[cfg(not(windows))]
+    let daemon_dir = {
+        let home = dirs::home_dir().context("Failed to determine home directory")?;
+        home.join(".seren-replicator")
+    };
+
+    // Create directory if it doesn't exist
+    if !daemon_dir.exists() {
+        fs::create_dir_all(&daemon_dir)
+            .with_context(|| format!("Failed to create daemon directory: {:?}", daemon_dir))?;
+    }
+
+    Ok(daemon_dir)
+}
+
+/// Get the path to the PID file.
+pub fn get_pid_file_path() -> Result<PathBuf> {
+    Ok(get_daemon_dir()?.join("sync.pid"))
+}
+
+/// Get the path to the log file for daemon mode.
+pub fn get_log_file_path() -> Result<PathBuf> {
+    Ok(get_daemon_dir()?.join("sync.log"))
+}
+
+/// Open the daemon log file for appending, creating it if needed.
+pub fn open_log_file() -> Result<fs::File> {
+    let log_file = get_log_file_path()?;
+    fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&log_file)
+        .with_context(|| format!("Failed to open log file: {:?}", log_file))
+}
+
+/// Get the path to the pause marker; sync applies nothing while it exists.
+pub fn get_pause_file_path() -> Result<PathBuf> {
+    Ok(get_daemon_dir()?.join("sync.paused"))
+}
+
+/// Pause xmin-based sync. Returns false if it was already paused.
+///
+/// A running daemon stops before its next table and skips cycles until
+/// [`resume`] is called. Sync state is untouched, so it picks up where it
+/// left off.
+pub fn pause() -> Result<bool> {
+    let pause_file = get_pause_file_path()?;
+    if pause_file.exists() {
+        return Ok(false);
+    }
+
+    fs::write(&pause_file, chrono::Utc::now().to_rfc3339())
+        .with_context(|| format!("Failed to write pause file: {:?}", pause_file))?;
+    Ok(true)
+}
+
+/// Resume paused sync. Returns false if it was not paused.
+pub fn resume() -> Result<bool> {
+    let pause_file = get_pause_file_path()?;
+    if !pause_file.exists() {
+        return Ok(false);
+    }
+
+    fs::remove_file(&pause_file)
+        .with_context(|| format!("Failed to remove pause file: {:?}", pause_file))?;
+    Ok(true)
+}
+
+/// When sync was paused, or None if it is not paused.
+pub fn paused_since() -> Result<Option<String>> {
+    let pause_file = get_pause_file_path()?;
+    if !pause_file.exists() {
+        return Ok(None);
+    }
+
+    let since = fs::read_to_string(&pause_file)
+        .with_context(|| format!("Failed to read pause file: {:?}", pause_file))?;
+    Ok(Some(since.trim().to_string()))
+}
+
+/// Request sent to a running daemon over its control pipe (Windows).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControlRequest {
+    /// Finish the current cycle, clean up, and exit
+    Stop,
+    /// Report when the daemon started and whether it is paused or stopping
+    Status,
+}
+
+impl ControlRequest {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ControlRequest::Stop => "stop",
+            ControlRequest::Status => "status",
+        }
+    }
+
+    pub fn parse(request: &str) -> Option<Self> {
+        match request {
+            "stop" => Some(ControlRequest::Stop),
+            "status" => Some(ControlRequest::Status),
+            _ => None,
+        }
+    }
+}
+
+/// Daemon lifecycle events reported to the Windows event log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DaemonEvent {
+    Started,
+    Stopped,
+    Failed,
+}
+
+/// Record a lifecycle event of the daemon child.
+///
+/// On Windows this writes to the Application event log under the
+/// `database-replicator` source; elsewhere the log file is the only record.
+/// Credentials in `message` are redacted first.
+pub fn report_event(event: DaemonEvent, message: &str) {
+    #[cfg(windows)]
+    windows::report_event(event, &crate::redact::redact(message));
+    #[cfg(not(windows))]
+    let _ = (event, message);
+}
+
+fn stop_sender() -> &'static watch::Sender<bool> {
+    static STOP: OnceLock<watch::Sender<bool>> = OnceLock::new();
+    STOP.get_or_init(|| watch::channel(false).0)
+}
+
+/// Ask this process to shut down as if Ctrl+C had been pressed.
+pub fn request_stop() {
+    stop_sender().send_replace(true);
+}
+
+/// Whether a stop was requested with [`request_stop`].
+pub fn stop_requested() -> bool {
+    *stop_sender().borrow()
+}
+
+async fn ctrl_c() {
+    if let Err(e) = tokio::signal::ctrl_c().await {
+        tracing::debug!("Not listening for Ctrl+C: {}", e);
+        std::future::pending::<()>().await;
+    }
+}
+
+/// Wait for a signal asking the process to stop, and return its name.
+#[cfg(unix)]
+async fn termination_signal() -> &'static str {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    // Container runtimes and `sync --stop` send SIGTERM
+    match signal(SignalKind::terminate()) {
+        Ok(mut sigterm) => tokio::select! {
+            _ = ctrl_c() => "Ctrl+C",
+            _ = sigterm.recv() => "SIGTERM",
+        },
+        Err(e) => {
+            tracing::debug!("Not listening for SIGTERM: {}", e);
+            ctrl_c().await;
+            "Ctrl+C"
+        }
+    }
+}
+
+#[cfg(not(unix))]
+async fn termination_signal() -> &'static str {
+    ctrl_c().await;
+    "Ctrl+C"
+}
+
+/// Wait for Ctrl+C, SIGTERM (Unix), or a stop request from `sync --stop`
+/// (Windows daemon), and return what it was.
+///
+/// A detached process may have no console to receive Ctrl+C from; it then
+/// waits for a stop request only.
+pub async fn shutdown_signal() -> &'static str {
+    let mut stop = stop_sender().subscribe();
+    tokio::select! {
+        signal = termination_signal() => signal,
+        _ = stop.wait_for(|stop| *stop) => "stop request",
+    }
+}
+
+/// Send on `shutdown` at the first [`shutdown_signal`], letting sync finish
+/// the table in progress and save its state; exit at once on a second
+/// Ctrl+C or SIGTERM.
+pub fn spawn_shutdown_handler(shutdown: tokio::sync::broadcast::Sender<()>) {
+    tokio::spawn(async move {
+        let signal = shutdown_signal().await;
+        tracing::info!(
+            "Received {}; shutting down (send it again to exit immediately)",
+            signal
+        );
+        let _ = shutdown.send(());
+        let signal = termination_signal().await;
+        tracing::warn!("Received {} again; exiting without saving state", signal);
+        std::process::exit(130);
+    });
+}
+
+/// SIGHUP, which asks a running sync to reload its config file.
+///
+/// Once listening, SIGHUP no longer terminates the process. Windows has no
+/// SIGHUP, so there [`recv`](Self::recv) never returns.
+pub struct ReloadSignal {
+    #[cfg(unix)]
+    hangup: Option<tokio::signal::unix::Signal>,
+}
+
+impl ReloadSignal {
+    #[cfg(unix)]
+    pub fn listen() -> Self {
+        use tokio::signal::unix::{signal, SignalKind};
+        let hangup = signal(SignalKind::hangup())
+            .map_err(|e| tracing::warn!("Not listening for SIGHUP: {}", e))
+            .ok();
+        Self { hangup }
+    }
+
+    #[cfg(not(unix))]
+    pub fn listen() -> Self {
+        Self {}
+    }
+
+    /// Wait for the next SIGHUP.
+    pub async fn recv(&mut self) {
+        #[cfg(unix)]
+        if let Some(hangup) = self.hangup.as_mut() {
+            hangup.recv().await;
+            return;
+        }
+        std::future::pending::<()>().await
+    }
+}
+
+/// Keep SIGHUP from terminating a sync that can't reload its settings, and
+/// tell whoever sent it to restart instead.
+pub fn spawn_reload_unsupported(reason: &'static str) {
+    let mut reload = ReloadSignal::listen();
+    tokio::spawn(async move {
+        loop {
+            reload.recv().await;
+            tracing::warn!(
+                "Received SIGHUP, but {} can't reload its settings; restart sync to apply changes",
+                reason
+            );
+        }
+    });
+}
+
+/// Check if a process with the given PID is running.
+#[cfg(unix)]
+pub(crate) fn is_process_running(pid: i32) -> bool {
+    // Send signal 0 to check if process exists
+    unsafe { libc::kill(pid, 0) == 0 }
+}
+
+#[cfg(windows)]
+pub(crate) fn is_process_running(pid: i32) -> bool {
+    // OpenProcess with PROCESS_QUERY_LIMITED_INFORMATION
+    const PROCESS_QUERY_LIMITED_INFORMATION: u32 = 0x1000;
+    const SYNCHRONIZE: u32 = 0x00100000;
+
+    unsafe {
+        let handle = OpenProcess(
+            PROCESS_QUERY_LIMITED_INFORMATION | SYNCHRONIZE,
+            0,
+            pid as u32,
+        );
+        if handle.is_null() {
+            return false;
+        }
+
+        // Check if process is still running
+        let mut exit_code: u32 = 0;
+        let result = GetExitCodeProcess(handle, &mut exit_code);
+        CloseHandle(handle);
+
+        // STILL_ACTIVE = 259
+        result != 0 && exit_code == 259
+    }
+}
+
+#[cfg(windows)]
+extern "system" {
+    fn OpenProcess(
+        dwDesiredAccess: u32,
+        bInheritHandle: i32,
+        dwProcessId: u32,
+    ) -> *mut std::ffi::c_void;
+    fn GetExitCodeProcess(hProcess: *mut std::ffi::c_void, lpExitCode: *mut u32) -> i32;
+    fn CloseHandle(hObject: *mut std::ffi::c_void) -> i32;
+    fn TerminateProcess(hProcess: *mut std::ffi::c_void, uExitCode: u32) -> i32;
+}
+
+/// Read the PID from the PID file.
+pub fn read_pid() -> Result<Option<i32>> {
+    let pid_file = get_pid_file_path()?;
+
+    if !pid_file.exists() {
+        return Ok(None);
+    }
+
+    let content = fs::read_to_string(&pid_file)
+        .with_context(|| format!("Failed to read PID file: {:?}", pid_file))?;
+
+    let pid: i32 = content
+        .trim()
+        .parse()
+        .with_context(|| format!("Invalid PID in file: {}", content.trim()))?;
+
+    Ok(Some(pid))
+}
+
+/// Write the current process PID to the PID file.
+pub fn write_pid() -> Result<()> {
+    let pid_file = get_pid_file_path()?;
+    let pid = std::process::id();
+
+    fs::write(&pid_file, pid.to_string())
+        .with_context(|| format!("Failed to write PID file: {:?}", pid_file))?;
+
+    Ok(())
+}
+
+/// Remove the PID file.
+pub fn remove_pid_file() -> Result<()> {
+    let pid_file = get_pid_file_path()?;
+
+    if pid_file.exists() {
+        fs::remove_file(&pid_file)
+            .with_context(|| format!("Failed to remove PID file: {:?}", pid_file))?;
+    }
+
+    Ok(())
+}
+
+/// Status information about the daemon.
+#[derive(Debug)]
+pub struct DaemonStatus {
+    pub running: bool,
+    pub pid: Option<i32>,
+    pub pid_file_exists: bool,
+}
+
+/// Check the status of the daemon.
+pub fn check_status() -> Result<DaemonStatus> {
+    let pid_file = get_pid_file_path()?;
+    let pid_file_exists = pid_file.exists();
+
+    let (running, pid) = match read_pid()? {
+        Some(pid) => {
+            let running = is_process_running(pid);
+            (running, Some(pid))
+        }
+        None => (false, None),
+    };
+
+    Ok(DaemonStatus {
+        running,
+        pid,
+        pid_file_exists,
+    })
+}
+
+/// How long `sync --stop` waits for the daemon to finish its table and exit
+const STOP_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Stop the running daemon.
+#[cfg(unix)]
+pub fn stop_daemon() -> Result<bool> {
+    let status = check_status()?;
+
+    if !status.running {
+        if status.pid_file_exists {
+            remove_pid_file()?;
+            println!("Removed stale PID file (process was not running)");
+        }
+        return Ok(false);
+    }
+
+    let pid = status.pid.unwrap();
+    println!("Sending SIGTERM to daemon (PID: {})", pid);
+
+    let result = unsafe { libc::kill(pid, libc::SIGTERM) };
+
+    if result != 0 {
+        anyhow::bail!(
+            "Failed to send SIGTERM to process {}: {}",
+            pid,
+            std::io::Error::last_os_error()
+        );
+    }
+
+    // Wait for process to exit; it finishes the table in progress first
+    let start = std::time::Instant::now();
+    let timeout = STOP_TIMEOUT;
+
+    while is_process_running(pid) {
+        if start.elapsed() > timeout {
+            println!(
+                "Process didn't exit within {} seconds, sending SIGKILL",
+                timeout.as_secs()
+            );
+            unsafe { libc::kill(pid, libc::SIGKILL) };
+            std::thread::sleep(std::time::Duration::from_millis(500));
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(100));
+    }
+
+    remove_pid_file()?;
+    Ok(true)
+}
+
+#[cfg(windows)]
+pub fn stop_daemon() -> Result<bool> {
+    let status = check_status()?;
+
+    if !status.running {
+        if status.pid_file_exists {
+            remove_pid_file()?;
+            println!("Removed stale PID file (process was not running)");
+        }
+        return Ok(false);
+    }
+
+    let pid = status.pid.unwrap();
+    println!("Asking daemon (PID: {}) to stop", pid);
+
+    match windows::send_request(pid as u32, ControlRequest::Stop) {
+        Ok(_) => {
+            // Wait for the table in progress to finish and the process to exit
+            let start = std::time::Instant::now();
+            let timeout = STOP_TIMEOUT;
+            while is_process_running(pid) {
+                if start.elapsed() > timeout {
+                    println!(
+                        "Process didn't exit within {} seconds, terminating it",
+                        timeout.as_secs()
+                    );
+                    terminate_process(pid)?;
+                    break;
+                }
+                std::thread::sleep(std::time::Duration::from_millis(100));
+            }
+        }
+        Err(e) => {
+            println!(
+                "Control pipe unavailable ({:#}), terminating the process",
+                e
+            );
+            terminate_process(pid)?;
+        }
+    }
+
+    remove_pid_file()?;
+    Ok(true)
+}
+
+#[cfg(windows)]
+fn terminate_process(pid: i32) -> Result<()> {
+    const PROCESS_TERMINATE: u32 = 0x0001;
+
+    unsafe {
+        let handle = OpenProcess(PROCESS_TERMINATE, 0, pid as u32);
+        if handle.is_null() {
+            anyhow::bail!(
+                "Failed to open process {}: {}",
+                pid,
+                std::io::Error::last_os_error()
+            );
+        }
+
+        let result = TerminateProcess(handle, 0);
+        CloseHandle(handle);
+
+        if result == 0 {
+            anyhow::bail!(
+                "Failed to terminate process {}: {}",
+                pid,
+                std::io::Error::last_os_error()
+            );
+        }
+    }
+
+    // Wait briefly for process to exit
+    std::thread::sleep(std::time::Duration::from_millis(500));
+    Ok(())
+}
+
+/// Daemonize the current process (Unix).
+#[cfg(unix)]
+pub fn daemonize() -> Result<()> {
+    use daemonize::Daemonize;
+
+    let pid_file = get_pid_file_path()?;
+    let log_file = get_log_file_path()?;
+
+    // Check if daemon is already running
+    let status = check_status()?;
+    if status.running {
+        anyhow::bail!(
+            "Daemon is already running (PID: {}). Use --stop to stop it first.",
+            status.pid.unwrap()
+        );
+    }
+
+    // Clean up stale PID file if present
+    if status.pid_file_exists {
+        remove_pid_file()?;
+    }
+
+    // Open log file for stdout/stderr
+    let stdout = open_log_file()?;
+    let stderr = open_log_file()?;
+
+    println!("Starting daemon...");
+    println!("PID file: {:?}", pid_file);
+    println!("Log file: {:?}", log_file);
+
+    let daemonize = Daemonize::new()
+        .pid_file(&pid_file)
+        .chown_pid_file(true)
+        .working_directory(".")
+        .stdout(stdout)
+        .stderr(stderr);
+
+    daemonize.start().context("Failed to daemonize process")?;
+
+    tracing::info!("Daemon started (PID: {})", std::process::id());
+    Ok(())
+}
+
+/// Daemonize by spawning a detached process (Windows).
+///
+/// The child runs the same command with `--daemon-child`, writes its own
+/// PID file and log, and serves the control pipe. This process exits once
+/// the child is up.
+#[cfg(windows)]
+pub fn daemonize() -> Result<()> {
+    use std::os::windows::process::CommandExt;
+    use std::process::{Command, Stdio};
+
+    let pid_file = get_pid_file_path()?;
+    let log_file = get_log_file_path()?;
+
+    // Check if daemon is already running
+    let status = check_status()?;
+    if status.running {
+        anyhow::bail!(
+            "Daemon is already running (PID: {}). Use --stop to stop it first.",
+            status.pid.unwrap()
+        );
+    }
+
+    // Clean up stale PID file
+    if status.pid_file_exists {
+        remove_pid_file()?;
+    }
+
+    // Get current executable path
+    let exe = std::env::current_exe().context("Failed to get current executable path")?;
+
+    // Get original command line args, removing --daemon flag, and mark the
+    // child so it sets up daemon logging and control instead of detaching again
+    let mut daemon_args: Vec<String> = std::env::args()
+        .skip(1) // Skip executable name
+        .filter(|arg| arg != "--daemon")
+        .collect();
+    daemon_args.push("--daemon-child".to_string());
+
+    println!("Starting daemon...");
+    println!("PID file: {:?}", pid_file);
+    println!("Log file: {:?}", log_file);
+
+    // No console, and Ctrl+C in this console doesn't reach the child
+    const DETACHED_PROCESS: u32 = 0x00000008;
+    const CREATE_NEW_PROCESS_GROUP: u32 = 0x00000200;
+
+    // Anything the child prints outside of tracing lands in the log too
+    let mut child = Command::new(exe)
+        .args(&daemon_args)
+        .stdin(Stdio::null())
+        .stdout(open_log_file()?)
+        .stderr(open_log_file()?)
+        .creation_flags(DETACHED_PROCESS | CREATE_NEW_PROCESS_GROUP)
+        .spawn()
+        .context("Failed to spawn daemon process")?;
+    let pid = child.id() as i32;
+
+    // The child writes its PID file once it is running
+    let start = std::time::Instant::now();
+    while read_pid().ok().flatten() != Some(pid) {
+        if let Some(exit) = child.try_wait()? {
+            anyhow::bail!(
+                "Daemon process exited during startup ({}); see {:?}",
+                exit,
+                log_file
+            );
+        }
+        if start.elapsed() > std::time::Duration::from_secs(10) {
+            println!(
+                "Daemon hasn't written its PID file yet; check {:?}",
+                log_file
+            );
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(100));
+    }
+
+    println!("Daemon started with PID: {}", pid);
+    std::process::exit(0);
+}
+
+/// Check if we're running as a daemon child process (Windows).
+/// On Unix this is handled by the daemonize crate.
+pub fn is_daemon_child() -> bool {
+    std::env::args().any(|arg| arg == "--daemon-child")
+}
+
+/// Initialize daemon child process: write the PID file, serve the control
+/// pipe (Windows), and report the start to the event log.
+/// Call this at startup if is_daemon_child() returns true.
+pub fn init_daemon_child() -> Result<PathBuf> {
+    let log_file = get_log_file_path()?;
+
+    // Serve the pipe before the PID file announces this process
+    #[cfg(windows)]
+    windows::start_control_server(chrono::Utc::now().to_rfc3339())?;
+
+    // Write PID file
+    write_pid()?;
+
+    report_event(
+        DaemonEvent::Started,
+        &format!(
+            "Sync daemon started (PID: {}), logging to {}",
+            std::process::id(),
+            log_file.display()
+        ),
+    );
+    Ok(log_file)
+}
+
+/// What a running daemon reports over its control pipe, if it can be asked.
+#[cfg(windows)]
+fn query_status(pid: i32) -> Option<Result<String>> {
+    Some(windows::send_request(pid as u32, ControlRequest::Status))
+}
+
+#[cfg(not(windows))]
+fn query_status(_pid: i32) -> Option<Result<String>> {
+    None
+}
+
+/// Print daemon status to stdout.
+pub fn print_status() -> Result<()> {
+    let status = check_status()?;
+    let log_file = get_log_file_path()?;
+
+    let paused_since = paused_since()?;
+
+    if status.running {
+        match paused_since {
+            Some(ref since) => println!("Daemon status: PAUSED (since {})", since),
+            None => println!("Daemon status: RUNNING"),
+        }
+        println!("PID: {}", status.pid.unwrap());
+        match query_status(status.pid.unwrap()) {
+            Some(Ok(reply)) => println!("Control: {}", reply),
+            Some(Err(e)) => println!("Control: not responding ({:#})", e),
+            None => {}
+        }
+        println!("Log file: {:?}", log_file);
+
+        // Show last few lines of log
+        if log_file.exists() {
+            println!("\nRecent log entries:");
+            println!("-------------------");
+            let content = fs::read_to_string(&log_file)?;
+            let lines: Vec<&str> = content.lines().collect();
+            let start = if lines.len() > 10 {
+                lines.len() - 10
+            } else {
+                0
+            };
+            for line in &lines[start..] {
+                println!("{}", line);
+            }
+        }
+    } else {
+        println!("Daemon status: NOT RUNNING");
+        if status.pid_file_exists {
+            println!(
+                "Note: Stale PID file exists (PID {} is not running)",
+                status.pid.unwrap_or(0)
+            );
+            println!("Run with --stop to clean up the stale PID file");
+        }
+        if let Some(ref since) = paused_since {
+            println!(
+                "Sync is paused (since {}); run with --resume to undo",
+                since
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Clean up daemon resources (call on normal shutdown).
+pub fn cleanup() -> Result<()> {
+    if is_daemon_child() {
+        report_event(
+            DaemonEvent::Stopped,
+            &format!("Sync daemon stopped (PID: {})", std::process::id()),
+        );
+    }
+    remove_pid_file()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_daemon_dir_creation() {
+        let dir = get_daemon_dir();
+        assert!(dir.is_ok());
+        let path = dir.unwrap();
+        assert!(path.to_string_lossy().contains("seren-replicator"));
+    }
+
+    #[test]
+    fn test_pid_file_path() {
+        let path = get_pid_file_path();
+        assert!(path.is_ok());
+        let path = path.unwrap();
+        assert!(path.to_string_lossy().ends_with("sync.pid"));
+    }
+
+    #[test]
+    fn test_pause_file_path() {
+        let path = get_pause_file_path();
+        assert!(path.is_ok());
+        let path = path.unwrap();
+        assert!(path.to_string_lossy().ends_with("sync.paused"));
+    }
+
+    #[test]
+    fn test_log_file_path() {
+        let path = get_log_file_path();
+        assert!(path.is_ok());
+        let path = path.unwrap();
+        assert!(path.to_string_lossy().ends_with("sync.log"));
+    }
+
+    #[test]
+    fn test_control_request_round_trip() {
+        for request in [ControlRequest::Stop, ControlRequest::Status] {
+            assert_eq!(ControlRequest::parse(request.as_str()), Some(request));
+        }
+        assert_eq!(ControlRequest::parse("restart"), None);
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_signal_after_stop_request() {
+        request_stop();
+        tokio::time::timeout(std::time::Duration::from_secs(5), shutdown_signal())
+            .await
+            .expect("stop request should end the wait");
+    }
+
+    #[test]
+    fn test_check_status_no_daemon() {
+        let status = check_status();
+        assert!(status.is_ok());
+    }
+
+    #[test]
+    fn test_is_daemon_child_false() {
+        // In normal test execution, --daemon-child won't be present
+        // Note: This test may not be reliable if test runner adds unexpected args
+        let result = is_daemon_child();
+        // Just verify it doesn't panic
+        let _ = result;
+    }
+}
@@ -0,0 +1,198 @@
+// ABOUTME: Windows daemon control: a named pipe for stop/status requests and Event Log reports
+// ABOUTME: The detached sync process serves the pipe; `sync --stop` and `--daemon-status` connect to it
+
+use anyhow::{bail, Context, Result};
+use std::ffi::c_void;
+use std::io::{BufRead, BufReader, Write};
+use std::ptr::null_mut;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+use tokio::net::windows::named_pipe::{NamedPipeServer, ServerOptions};
+
+use super::{ControlRequest, DaemonEvent};
+
+/// Source name the daemon's entries carry in the Application event log
+const EVENT_SOURCE: &str = "database-replicator";
+
+const EVENTLOG_ERROR_TYPE: u16 = 0x0001;
+const EVENTLOG_INFORMATION_TYPE: u16 = 0x0004;
+
+/// Returned by CreateFile while every instance of the pipe is busy
+const ERROR_PIPE_BUSY: i32 = 231;
+
+#[link(name = "advapi32")]
+extern "system" {
+    fn RegisterEventSourceW(lpUNCServerName: *const u16, lpSourceName: *const u16) -> *mut c_void;
+    fn ReportEventW(
+        hEventLog: *mut c_void,
+        wType: u16,
+        wCategory: u16,
+        dwEventID: u32,
+        lpUserSid: *mut c_void,
+        wNumStrings: u16,
+        dwDataSize: u32,
+        lpStrings: *const *const u16,
+        lpRawData: *mut c_void,
+    ) -> i32;
+    fn DeregisterEventSource(hEventLog: *mut c_void) -> i32;
+}
+
+/// Control pipe served by the daemon with process ID `pid`
+fn pipe_name(pid: u32) -> String {
+    format!(r"\\.\pipe\seren-replicator-sync-{}", pid)
+}
+
+/// Serve stop and status requests on this process's control pipe
+///
+/// Must be called from within the Tokio runtime; the pipe is served by a
+/// background task for as long as the process runs.
+pub(super) fn start_control_server(started_at: String) -> Result<()> {
+    let name = pipe_name(std::process::id());
+    let server = ServerOptions::new()
+        .first_pipe_instance(true)
+        .create(&name)
+        .with_context(|| format!("Failed to create control pipe {}", name))?;
+    tracing::info!("Listening for control requests on {}", name);
+
+    tokio::spawn(async move {
+        let mut server = server;
+        loop {
+            if let Err(e) = server.connect().await {
+                tracing::warn!("Control pipe connection failed: {}", e);
+                continue;
+            }
+            // Open the next instance before serving this client, so a
+            // second client never finds the pipe missing
+            let client = server;
+            server = match ServerOptions::new().create(&name) {
+                Ok(next) => next,
+                Err(e) => {
+                    tracing::error!("Failed to reopen control pipe {}: {}", name, e);
+                    return;
+                }
+            };
+            let started_at = started_at.clone();
+            tokio::spawn(async move {
+                if let Err(e) = serve_request(client, &started_at).await {
+                    tracing::warn!("Control request failed: {:#}", e);
+                }
+            });
+        }
+    });
+    Ok(())
+}
+
+async fn serve_request(pipe: NamedPipeServer, started_at: &str) -> Result<()> {
+    let (read, mut write) = tokio::io::split(pipe);
+    let mut line = String::new();
+    tokio::io::BufReader::new(read)
+        .read_line(&mut line)
+        .await
+        .context("Failed to read control request")?;
+
+    let reply = match ControlRequest::parse(line.trim()) {
+        Some(ControlRequest::Stop) => {
+            tracing::info!("Stop requested over the control pipe");
+            super::request_stop();
+            "stopping".to_string()
+        }
+        Some(ControlRequest::Status) => status_reply(started_at),
+        None => format!("error: unknown request '{}'", line.trim()),
+    };
+    write
+        .write_all(format!("{}\n", reply).as_bytes())
+        .await
+        .context("Failed to send control reply")?;
+    write.flush().await?;
+    Ok(())
+}
+
+fn status_reply(started_at: &str) -> String {
+    if super::stop_requested() {
+        return format!("stopping (running since {})", started_at);
+    }
+    match super::paused_since() {
+        Ok(Some(since)) => format!("running since {}, paused since {}", started_at, since),
+        _ => format!("running since {}", started_at),
+    }
+}
+
+/// Send `request` to the daemon with process ID `pid` and return its reply
+pub(super) fn send_request(pid: u32, request: ControlRequest) -> Result<String> {
+    let name = pipe_name(pid);
+    let mut attempts = 0;
+    let mut pipe = loop {
+        match std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&name)
+        {
+            Ok(pipe) => break pipe,
+            Err(e) if e.raw_os_error() == Some(ERROR_PIPE_BUSY) && attempts < 20 => {
+                attempts += 1;
+                std::thread::sleep(std::time::Duration::from_millis(100));
+            }
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to open control pipe {}", name))
+            }
+        }
+    };
+
+    writeln!(pipe, "{}", request.as_str()).context("Failed to send control request")?;
+    let mut reply = String::new();
+    BufReader::new(pipe)
+        .read_line(&mut reply)
+        .context("Failed to read control reply")?;
+    let reply = reply.trim().to_string();
+    if let Some(message) = reply.strip_prefix("error: ") {
+        bail!("Daemon rejected '{}': {}", request.as_str(), message);
+    }
+    Ok(reply)
+}
+
+/// Write an entry for `event` to the Application event log
+///
+/// Failures are only logged; the event log is a convenience next to the log file.
+pub(super) fn report_event(event: DaemonEvent, message: &str) {
+    let (kind, id) = match event {
+        DaemonEvent::Started => (EVENTLOG_INFORMATION_TYPE, 1),
+        DaemonEvent::Stopped => (EVENTLOG_INFORMATION_TYPE, 2),
+        DaemonEvent::Failed => (EVENTLOG_ERROR_TYPE, 3),
+    };
+    let source = wide(EVENT_SOURCE);
+    let text = wide(message);
+    let strings = [text.as_ptr()];
+
+    unsafe {
+        let handle = RegisterEventSourceW(std::ptr::null(), source.as_ptr());
+        if handle.is_null() {
+            tracing::debug!(
+                "Failed to open the event log: {}",
+                std::io::Error::last_os_error()
+            );
+            return;
+        }
+        let result = ReportEventW(
+            handle,
+            kind,
+            0,
+            id,
+            null_mut(),
+            strings.len() as u16,
+            0,
+            strings.as_ptr(),
+            null_mut(),
+        );
+        if result == 0 {
+            tracing::debug!(
+                "Failed to write to the event log: {}",
+                std::io::Error::last_os_error()
+            );
+        }
+        DeregisterEventSource(handle);
+    }
+}
+
+/// NUL-terminated UTF-16 copy of `text`
+fn wide(text: &str) -> Vec<u16> {
+    text.encode_utf16().chain(std::iter::once(0)).collect()
+}
@@ -0,0 +1,340 @@
+// ABOUTME: At-rest encryption for local state files that hold connection details
+// ABOUTME: ChaCha20-Poly1305 with an Argon2id key from SEREN_STATE_KEY or the OS keyring
+
+use anyhow::{bail, Context, Result};
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use std::io::Write;
+use std::path::Path;
+
+/// Environment variable holding the state encryption passphrase
+///
+/// When set it takes precedence over the OS keyring, which suits CI and
+/// headless servers. Any string works; use at least 32 random characters.
+pub const STATE_KEY_ENV: &str = "SEREN_STATE_KEY";
+
+/// Prefix that marks an encrypted state file; anything else is legacy plaintext
+const ENCRYPTED_PREFIX: &str = "seren-enc:v2:";
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+#[cfg(any(target_os = "macos", target_os = "windows", target_os = "linux"))]
+const KEYRING_SERVICE: &str = "database-replicator";
+#[cfg(any(target_os = "macos", target_os = "windows", target_os = "linux"))]
+const KEYRING_USER: &str = "state-encryption-key";
+
+/// 256-bit key used to encrypt state files
+pub type StateKey = [u8; 32];
+
+/// Where state file keys come from
+#[derive(Clone)]
+enum KeySource {
+    /// `SEREN_STATE_KEY`; each file's key is derived from it with Argon2id
+    /// and the salt stored in that file
+    Passphrase(String),
+    /// A random key from the OS keyring, used as-is
+    Keyring(StateKey),
+}
+
+impl KeySource {
+    /// The key for a file with the given salt
+    fn file_key(&self, salt: &[u8]) -> Result<StateKey> {
+        match self {
+            KeySource::Passphrase(passphrase) => derive_key(passphrase, salt),
+            KeySource::Keyring(key) => Ok(*key),
+        }
+    }
+}
+
+/// Read a state file, decrypting it if needed
+///
+/// Returns `Ok(None)` when the file does not exist. A file written before
+/// encryption was added is returned as-is and encrypted when a key is
+/// available, so existing installs migrate on first use.
+pub fn read_state_file(path: &Path) -> Result<Option<String>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let key = state_key();
+    let (contents, plaintext) = read_with_key(path, key.as_ref())?;
+
+    if plaintext {
+        if let Some(key) = key {
+            match write_with_key(path, &contents, Some(&key)) {
+                Ok(()) => tracing::info!("Encrypted existing state file {}", path.display()),
+                Err(e) => tracing::warn!(
+                    "Failed to encrypt existing state file {}: {:#}",
+                    path.display(),
+                    e
+                ),
+            }
+        }
+    }
+    Ok(Some(contents))
+}
+
+/// Write a state file, encrypted when a key is available
+///
+/// Without `SEREN_STATE_KEY` or a usable OS keyring the file is written in
+/// plaintext, as before, with a warning. Either way it is readable only by
+/// its owner on Unix.
+pub fn write_state_file(path: &Path, contents: &str) -> Result<()> {
+    let key = state_key();
+    if key.is_none() {
+        tracing::warn!(
+            "No state encryption key available; writing {} unencrypted. \
+             Set {} or make an OS keyring available to encrypt it.",
+            path.display(),
+            STATE_KEY_ENV
+        );
+    }
+    write_with_key(path, contents, key.as_ref())
+}
+
+/// Read a file with an explicit key; the flag is true for legacy plaintext
+fn read_with_key(path: &Path, key: Option<&KeySource>) -> Result<(String, bool)> {
+    let raw = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    if !is_encrypted(&raw) {
+        return Ok((raw, true));
+    }
+    let Some(key) = key else {
+        bail!(
+            "{} is encrypted but no key is available. Set {} to the passphrase it was \
+             written with, or run on the machine whose OS keyring holds the key.",
+            path.display(),
+            STATE_KEY_ENV
+        );
+    };
+    let contents = decrypt(key, &raw).with_context(|| {
+        format!(
+            "Failed to decrypt {}. It was written with a different key; \
+             delete it or set {} to the original passphrase.",
+            path.display(),
+            STATE_KEY_ENV
+        )
+    })?;
+    Ok((contents, false))
+}
+
+/// Write through a temp file in the same directory, renamed into place
+///
+/// The temp file is created with mode 0600 on Unix, so the contents are never
+/// readable by other users, and a crash never leaves the file half written.
+fn write_with_key(path: &Path, contents: &str, key: Option<&KeySource>) -> Result<()> {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    std::fs::create_dir_all(parent)
+        .with_context(|| format!("Failed to create directory {}", parent.display()))?;
+    let data = match key {
+        Some(key) => encrypt(key, contents)?,
+        None => contents.to_string(),
+    };
+
+    let mut tmp = tempfile::NamedTempFile::new_in(parent)
+        .with_context(|| format!("Failed to create temp file in {}", parent.display()))?;
+    tmp.write_all(data.as_bytes())
+        .and_then(|()| tmp.as_file().sync_all())
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    tmp.persist(path)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(())
+}
+
+fn is_encrypted(contents: &str) -> bool {
+    contents.starts_with(ENCRYPTED_PREFIX)
+}
+
+/// Encrypt to `seren-enc:v2:` followed by base64 of salt, nonce, and ciphertext
+fn encrypt(key: &KeySource, plaintext: &str) -> Result<String> {
+    let salt: [u8; SALT_LEN] = rand::random();
+    let file_key = key.file_key(&salt)?;
+    let cipher = ChaCha20Poly1305::new(Key::from_slice(&file_key));
+    let nonce: [u8; NONCE_LEN] = rand::random();
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), plaintext.as_bytes())
+        .map_err(|_| anyhow::anyhow!("Failed to encrypt state"))?;
+
+    let mut payload = salt.to_vec();
+    payload.extend_from_slice(&nonce);
+    payload.extend_from_slice(&ciphertext);
+    Ok(format!("{}{}", ENCRYPTED_PREFIX, STANDARD.encode(payload)))
+}
+
+/// Decrypt a `seren-enc:v2:` file
+fn decrypt(key: &KeySource, contents: &str) -> Result<String> {
+    let Some(encoded) = contents.trim_end().strip_prefix(ENCRYPTED_PREFIX) else {
+        bail!("Not an encrypted state file");
+    };
+    let payload = STANDARD
+        .decode(encoded)
+        .context("Encrypted state is not valid base64")?;
+    if payload.len() < SALT_LEN + NONCE_LEN {
+        bail!("Encrypted state is truncated");
+    }
+    let (salt, rest) = payload.split_at(SALT_LEN);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+    let file_key = key.file_key(salt)?;
+
+    let cipher = ChaCha20Poly1305::new(Key::from_slice(&file_key));
+    let plaintext = cipher
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| anyhow::anyhow!("Wrong key or corrupted state"))?;
+    String::from_utf8(plaintext).context("Decrypted state is not valid UTF-8")
+}
+
+/// Derive a file key from a passphrase with Argon2id (RFC 9106 defaults:
+/// 19 MiB, 2 passes), so a stolen file cannot be cheaply brute-forced
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<StateKey> {
+    let mut key = StateKey::default();
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, Params::default())
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| anyhow::anyhow!("Failed to derive state key: {}", e))?;
+    Ok(key)
+}
+
+/// The passphrase from `SEREN_STATE_KEY`, else the OS keyring key (created on
+/// first use)
+fn state_key() -> Option<KeySource> {
+    match std::env::var(STATE_KEY_ENV) {
+        Ok(passphrase) if !passphrase.is_empty() => Some(KeySource::Passphrase(passphrase)),
+        _ => keyring_key().map(KeySource::Keyring),
+    }
+}
+
+#[cfg(any(target_os = "macos", target_os = "windows", target_os = "linux"))]
+fn keyring_key() -> Option<StateKey> {
+    use std::sync::OnceLock;
+    static KEY: OnceLock<Option<StateKey>> = OnceLock::new();
+
+    *KEY.get_or_init(|| {
+        // The Secret Service backend runs its own async runtime, which cannot
+        // be started from a thread that is already driving tokio
+        let result = std::thread::spawn(load_or_create_keyring_key)
+            .join()
+            .unwrap_or_else(|_| Err(anyhow::anyhow!("keyring access panicked")));
+        match result {
+            Ok(key) => Some(key),
+            Err(e) => {
+                tracing::debug!("OS keyring unavailable for state encryption: {:#}", e);
+                None
+            }
+        }
+    })
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+fn keyring_key() -> Option<StateKey> {
+    None
+}
+
+#[cfg(any(target_os = "macos", target_os = "windows", target_os = "linux"))]
+fn load_or_create_keyring_key() -> Result<StateKey> {
+    let entry = keyring::Entry::new(KEYRING_SERVICE, KEYRING_USER)?;
+    match entry.get_password() {
+        Ok(encoded) => {
+            let bytes = STANDARD
+                .decode(encoded.trim())
+                .context("State key in OS keyring is not valid base64")?;
+            StateKey::try_from(bytes.as_slice())
+                .map_err(|_| anyhow::anyhow!("State key in OS keyring has the wrong length"))
+        }
+        Err(keyring::Error::NoEntry) => {
+            let key: StateKey = rand::random();
+            entry.set_password(&STANDARD.encode(key))?;
+            tracing::info!("Created a state encryption key in the OS keyring");
+            Ok(key)
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn passphrase(value: &str) -> KeySource {
+        KeySource::Passphrase(value.to_string())
+    }
+
+    #[test]
+    fn test_encrypt_round_trip() {
+        let key = passphrase("correct horse battery staple");
+        let encrypted = encrypt(&key, r#"{"target_url":"postgresql://u:secret@h/db"}"#).unwrap();
+
+        assert!(encrypted.starts_with(ENCRYPTED_PREFIX));
+        assert!(!encrypted.contains("secret"));
+        assert_eq!(
+            decrypt(&key, &encrypted).unwrap(),
+            r#"{"target_url":"postgresql://u:secret@h/db"}"#
+        );
+        // A fresh salt and nonce each time
+        assert_ne!(encrypted, encrypt(&key, "{}").unwrap());
+
+        let keyring = KeySource::Keyring([7; 32]);
+        let encrypted = encrypt(&keyring, "{}").unwrap();
+        assert_eq!(decrypt(&keyring, &encrypted).unwrap(), "{}");
+    }
+
+    #[test]
+    fn test_passphrase_keys_are_salted() {
+        let one = derive_key("same passphrase", &[1; SALT_LEN]).unwrap();
+        let two = derive_key("same passphrase", &[2; SALT_LEN]).unwrap();
+        assert_ne!(one, two);
+    }
+
+    #[test]
+    fn test_decrypt_rejects_wrong_key_and_tampering() {
+        let key = passphrase("one");
+        let encrypted = encrypt(&key, "{}").unwrap();
+
+        assert!(decrypt(&passphrase("two"), &encrypted).is_err());
+
+        let mut tampered = encrypted.into_bytes();
+        let last = tampered.len() - 2;
+        tampered[last] = if tampered[last] == b'A' { b'B' } else { b'A' };
+        assert!(decrypt(&key, &String::from_utf8(tampered).unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_plaintext_files_migrate() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state.json");
+        std::fs::write(&path, r#"{"target_url":null}"#).unwrap();
+        let key = passphrase("key");
+
+        let (contents, plaintext) = read_with_key(&path, Some(&key)).unwrap();
+        assert!(plaintext);
+        write_with_key(&path, &contents, Some(&key)).unwrap();
+
+        assert!(is_encrypted(&std::fs::read_to_string(&path).unwrap()));
+        let (contents, plaintext) = read_with_key(&path, Some(&key)).unwrap();
+        assert!(!plaintext);
+        assert_eq!(contents, r#"{"target_url":null}"#);
+
+        let err = read_with_key(&path, None).unwrap_err();
+        assert!(err.to_string().contains(STATE_KEY_ENV));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_written_files_are_owner_only() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state.json");
+        std::fs::write(&path, "old").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+
+        write_with_key(&path, "{}", None).unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "{}");
+    }
+}
@@ -0,0 +1,223 @@
+// ABOUTME: Catalog of common failures with stable error codes, hints, and documentation links
+// ABOUTME: Looked up from an error's causes so every command prints the same advice
+
+use serde::Serialize;
+use std::sync::RwLock;
+
+/// Page whose `### <code>` headings document each catalog entry
+pub const DOCS_URL: &str =
+    "https://github.com/serenorg/database-replicator/blob/main/README-PostgreSQL.md";
+
+/// A known failure and what to do about it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ErrorHint {
+    /// Stable code such as `DR001`; never reused for a different failure
+    pub code: &'static str,
+    pub title: &'static str,
+    pub hint: &'static str,
+    /// Lowercase text that identifies the failure in an error message; `*`
+    /// stands for any text
+    #[serde(skip)]
+    pub patterns: &'static [&'static str],
+}
+
+impl ErrorHint {
+    /// Link to the entry's section of the documentation
+    pub fn doc_url(&self) -> String {
+        format!("{}#{}", DOCS_URL, self.code.to_lowercase())
+    }
+
+    fn matches(&self, message: &str) -> bool {
+        self.patterns.iter().any(|pattern| {
+            let mut rest = message;
+            pattern.split('*').all(|part| match rest.find(part) {
+                Some(at) => {
+                    rest = &rest[at + part.len()..];
+                    true
+                }
+                None => false,
+            })
+        })
+    }
+}
+
+/// Failures the replicator recognizes, checked in order
+pub const CATALOG: &[ErrorHint] = &[
+    ErrorHint {
+        code: "DR001",
+        title: "Logical replication is not enabled (wal_level)",
+        hint: "Set wal_level = logical on the server and restart it. On managed services, \
+               turn on logical replication in the provider's settings. Sync from a source \
+               without it falls back to xmin-based polling.",
+        patterns: &[
+            "requires wal_level",
+            "wal_level >= logical",
+            "wal_level is set to",
+            "wal_level is still",
+            "wal_level is insufficient",
+            "wal_level must be",
+        ],
+    },
+    ErrorHint {
+        code: "DR002",
+        title: "An extension the schema uses is missing on the target",
+        hint: "Install the extension's package on the target server, or ask the provider to \
+               enable it, then run the command again. `validate` lists the extensions the \
+               source uses.",
+        patterns: &[
+            "could not open extension control file",
+            "extension \"*\" is not available",
+        ],
+    },
+    ErrorHint {
+        code: "DR003",
+        title: "No privilege to create objects in schema public",
+        hint: "Since PostgreSQL 15 only the database owner can create objects in schema \
+               public. Run `GRANT CREATE ON SCHEMA public TO <user>` as the owner, or connect \
+               as the owner.",
+        patterns: &["permission denied for schema public"],
+    },
+    ErrorHint {
+        code: "DR004",
+        title: "The source has no free WAL sender (max_wal_senders)",
+        hint: "Raise max_wal_senders on the source and restart it, or stop other replicas and \
+               replication tools that stream from it.",
+        patterns: &["max_wal_senders"],
+    },
+    ErrorHint {
+        code: "DR005",
+        title: "No free replication slot or worker",
+        hint: "Drop replication slots that are no longer used (`database-replicator slots` \
+               lists them), or raise max_replication_slots and \
+               max_logical_replication_workers and restart the server.",
+        patterns: &[
+            "all replication slots are in use",
+            "could not find free replication state slot",
+            "out of logical replication worker slots",
+            "max_replication_slots",
+        ],
+    },
+    ErrorHint {
+        code: "DR006",
+        title: "The server has no free connections",
+        hint: "Lower --max-connections-per-host, close idle sessions, or raise the server's \
+               max_connections or the role's connection limit.",
+        patterns: &[
+            "too many connections",
+            "remaining connection slots are reserved",
+        ],
+    },
+];
+
+/// The catalog entry with the given code
+pub fn by_code(code: &str) -> Option<&'static ErrorHint> {
+    CATALOG.iter().find(|hint| hint.code == code)
+}
+
+/// Entries added by [`register`], checked before the catalog
+static REGISTERED: RwLock<Vec<ErrorHint>> = RwLock::new(Vec::new());
+
+/// Add a hint for failures the catalog does not know
+///
+/// Lets programs that embed the library give their own codes and advice.
+/// Registered hints take precedence over the built-in catalog.
+pub fn register(hint: ErrorHint) {
+    REGISTERED
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .push(hint);
+}
+
+/// The hint for an error, if any of its causes is a known failure
+///
+/// Causes are checked from the innermost outward, using the first line of
+/// each, so the causes this crate lists under an error do not match.
+pub fn lookup(error: &anyhow::Error) -> Option<ErrorHint> {
+    let registered = REGISTERED.read().unwrap_or_else(|e| e.into_inner());
+    let causes: Vec<_> = error.chain().collect();
+    causes.iter().rev().find_map(|cause| {
+        let message = match cause
+            .downcast_ref::<tokio_postgres::Error>()
+            .and_then(|e| e.as_db_error())
+        {
+            Some(db) => db.message().to_string(),
+            None => cause.to_string(),
+        };
+        let first_line = message.lines().next().unwrap_or_default().to_lowercase();
+        registered
+            .iter()
+            .chain(CATALOG)
+            .find(|hint| hint.matches(&first_line))
+            .copied()
+    })
+}
+
+/// The hint lines printed after an error, or `None` when it is not a known failure
+pub fn render(error: &anyhow::Error) -> Option<String> {
+    let hint = lookup(error)?;
+    Some(format!(
+        "{}: {}\nHint: {}\nSee: {}",
+        hint.code,
+        hint.title,
+        hint.hint,
+        hint.doc_url()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    #[test]
+    fn test_catalog_codes_are_unique() {
+        let mut codes: Vec<_> = CATALOG.iter().map(|hint| hint.code).collect();
+        codes.sort();
+        codes.dedup();
+        assert_eq!(codes.len(), CATALOG.len());
+    }
+
+    #[test]
+    fn test_lookup() {
+        let code = |error: anyhow::Error| lookup(&error).map(|hint| hint.code);
+        assert_eq!(
+            code(
+                anyhow!("ERROR: logical decoding requires wal_level >= logical")
+                    .context("Failed to create replication slot")
+            ),
+            Some("DR001")
+        );
+        assert_eq!(
+            code(anyhow!(
+                "psql (restore schema) failed with exit code 3: psql:schema.sql:4: ERROR:  extension \"postgis\" is not available"
+            )),
+            Some("DR002")
+        );
+        assert_eq!(
+            code(anyhow!("permission denied for schema public")),
+            Some("DR003")
+        );
+        assert_eq!(
+            code(anyhow!(
+                "FATAL: number of requested standby connections exceeds max_wal_senders (currently 10)"
+            )),
+            Some("DR004")
+        );
+        assert_eq!(code(anyhow!("Failed to connect")), None);
+
+        // Causes listed after the first line do not match
+        assert_eq!(
+            code(anyhow!(
+                "Schema restoration failed.\n- Too many connections"
+            )),
+            None
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        let rendered = render(&anyhow!("FATAL: sorry, too many connections already")).unwrap();
+        assert!(rendered.starts_with("DR006: The server has no free connections\nHint: "));
+        assert!(rendered.ends_with("README-PostgreSQL.md#dr006"));
+    }
+}
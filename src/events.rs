@@ -0,0 +1,160 @@
+// ABOUTME: EventSink trait for granular replication events (tables, rows, cycles, errors)
+// ABOUTME: Sinks are installed per task with `scope` and receive events from init and sync
+
+use std::future::Future;
+use std::sync::Arc;
+
+use crate::xmin::SyncStats;
+
+/// Receiver for granular replication events.
+///
+/// Embedders implement this to follow a run without scraping log output.
+/// Every method has a no-op default, so a sink only overrides what it needs.
+/// Methods are called inline on the replication task and should return
+/// quickly; hand the event off to a channel for anything slow.
+///
+/// Tables are named `schema.table` for PostgreSQL sources and by their plain
+/// name for SQLite, MySQL, and MongoDB sources.
+pub trait EventSink: Send + Sync {
+    /// A table (or collection) is about to be copied or synced.
+    fn on_table_start(&self, _database: &str, _table: &str) {}
+
+    /// `rows` more rows were written to the target for a table.
+    ///
+    /// Called once per batch, so the counts for a table add up to its total.
+    fn on_rows_copied(&self, _database: &str, _table: &str, _rows: u64) {}
+
+    /// A sync or reconciliation cycle finished across all databases.
+    fn on_cycle_complete(&self, _stats: &SyncStats) {}
+
+    /// A table or database failed and the run carried on without it.
+    ///
+    /// `table` is `None` when the whole database failed.
+    fn on_error(&self, _database: &str, _table: Option<&str>, _message: &str) {}
+}
+
+tokio::task_local! {
+    static SINK: Arc<dyn EventSink>;
+}
+
+/// Run `future` with `sink` receiving every event it emits.
+///
+/// The sink applies to the future's own task; work spawned onto other tasks
+/// does not report to it.
+pub async fn scope<F: Future>(sink: Arc<dyn EventSink>, future: F) -> F::Output {
+    SINK.scope(sink, future).await
+}
+
+/// Deliver an event to the sink of the current scope, if there is one.
+pub(crate) fn emit(event: impl FnOnce(&dyn EventSink)) {
+    let _ = SINK.try_with(|sink| event(sink.as_ref()));
+}
+
+/// The CLI's sink: writes each event as a debug-level log line.
+///
+/// Info-level output already summarises progress, so these lines only show up
+/// with `--log debug` (or `RUST_LOG=database_replicator::events=debug`).
+#[derive(Debug, Default, Clone, Copy)]
+pub struct LogSink;
+
+impl EventSink for LogSink {
+    fn on_table_start(&self, database: &str, table: &str) {
+        tracing::debug!(target: "database_replicator::events", "table start: {}/{}", database, table);
+    }
+
+    fn on_rows_copied(&self, database: &str, table: &str, rows: u64) {
+        tracing::debug!(
+            target: "database_replicator::events",
+            "rows copied: {}/{} +{}",
+            database,
+            table,
+            rows
+        );
+    }
+
+    fn on_cycle_complete(&self, stats: &SyncStats) {
+        tracing::debug!(
+            target: "database_replicator::events",
+            "cycle complete: {} tables, {} rows synced, {} rows deleted, {} errors in {}ms",
+            stats.tables_synced,
+            stats.rows_synced,
+            stats.rows_deleted,
+            stats.errors.len(),
+            stats.duration_ms
+        );
+    }
+
+    fn on_error(&self, database: &str, table: Option<&str>, message: &str) {
+        match table {
+            Some(table) => tracing::debug!(
+                target: "database_replicator::events",
+                "error: {}/{}: {}",
+                database,
+                table,
+                message
+            ),
+            None => tracing::debug!(
+                target: "database_replicator::events",
+                "error: {}: {}",
+                database,
+                message
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct RecordingSink {
+        events: Mutex<Vec<String>>,
+    }
+
+    impl EventSink for RecordingSink {
+        fn on_table_start(&self, database: &str, table: &str) {
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("start {}/{}", database, table));
+        }
+
+        fn on_rows_copied(&self, database: &str, table: &str, rows: u64) {
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("rows {}/{} {}", database, table, rows));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_emit_reaches_scoped_sink() {
+        let sink = Arc::new(RecordingSink::default());
+
+        scope(sink.clone(), async {
+            emit(|s| s.on_table_start("app", "public.users"));
+            tokio::task::yield_now().await;
+            emit(|s| s.on_rows_copied("app", "public.users", 42));
+            // Methods the sink doesn't override fall back to the no-op default
+            emit(|s| s.on_error("app", None, "boom"));
+        })
+        .await;
+
+        assert_eq!(
+            *sink.events.lock().unwrap(),
+            vec!["start app/public.users", "rows app/public.users 42"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_emit_without_scope_is_noop() {
+        let sink = Arc::new(RecordingSink::default());
+        scope(sink.clone(), async {}).await;
+
+        emit(|s| s.on_table_start("app", "public.users"));
+
+        assert!(sink.events.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,715 @@
+// ABOUTME: Classifies command and client tool failures as retryable connection errors or fatal ones
+// ABOUTME: Maps them to exit codes and writes a JSON summary for Kubernetes termination messages
+
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// File Kubernetes reads a container's termination message from by default
+pub const KUBERNETES_TERMINATION_LOG: &str = "/dev/termination-log";
+
+/// Kubernetes keeps at most this many bytes of a termination message
+const TERMINATION_MESSAGE_LIMIT: usize = 4096;
+
+/// Longest error text kept in a summary, leaving room for the other fields
+const MAX_ERROR_LEN: usize = 3072;
+
+/// What kind of problem stopped a command
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FailureKind {
+    /// A server could not be reached or dropped the connection; retrying may succeed
+    Connection,
+    /// Credentials were rejected or the user lacks a privilege
+    Auth,
+    /// The schema could not be dumped or restored, or an object is missing
+    Schema,
+    /// Anything else, such as invalid arguments
+    Other,
+}
+
+impl FailureKind {
+    pub const ALL: [FailureKind; 4] = [
+        FailureKind::Connection,
+        FailureKind::Auth,
+        FailureKind::Schema,
+        FailureKind::Other,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FailureKind::Connection => "connection",
+            FailureKind::Auth => "auth",
+            FailureKind::Schema => "schema",
+            FailureKind::Other => "other",
+        }
+    }
+
+    /// Whether running the command again unchanged can succeed
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, FailureKind::Connection)
+    }
+
+    /// Exit code used unless `--exit-code-map` changes it (from sysexits.h)
+    pub fn default_exit_code(&self) -> i32 {
+        match self {
+            FailureKind::Connection => 75, // EX_TEMPFAIL
+            FailureKind::Auth => 77,       // EX_NOPERM
+            FailureKind::Schema => 65,     // EX_DATAERR
+            FailureKind::Other => 1,
+        }
+    }
+}
+
+impl FromStr for FailureKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match FailureKind::ALL.iter().find(|kind| kind.as_str() == s) {
+            Some(kind) => Ok(*kind),
+            None => bail!(
+                "Unknown failure kind '{}'; expected one of: connection, auth, schema, other",
+                s
+            ),
+        }
+    }
+}
+
+/// An error whose kind is known where it is raised
+///
+/// [`classify`] takes the kind from it instead of reading its message.
+#[derive(Debug, Clone)]
+pub struct Failure {
+    pub kind: FailureKind,
+    message: String,
+}
+
+impl Failure {
+    pub fn new(kind: FailureKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for Failure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for Failure {}
+
+// Matched against the first line of each error message, lowercased. Later
+// lines of this crate's errors list possible causes and would mislead.
+const AUTH_PATTERNS: &[&str] = &[
+    "authentication failed",
+    "password authentication",
+    "no pg_hba.conf entry",
+    "access denied",
+    "permission denied",
+];
+
+const CONNECTION_PATTERNS: &[&str] = &[
+    "connection refused",
+    "could not connect",
+    "failed to connect",
+    "connection timeout",
+    "timed out",
+    "connection reset",
+    "connection closed",
+    "server closed the connection",
+    "terminating connection",
+    "broken pipe",
+    "could not translate host name",
+    "failed to lookup address",
+    "no route to host",
+    "network is unreachable",
+    "too many connections",
+    "the database system is starting up",
+    "the database system is shutting down",
+];
+
+const SCHEMA_PATTERNS: &[&str] = &["schema", "syntax error", "does not exist", "already exists"];
+
+/// Classify a command's error
+///
+/// The innermost cause that can be recognized decides, so a connection lost
+/// while restoring the schema counts as a connection failure. A [`Failure`]
+/// carries its kind, PostgreSQL errors are classified by SQLSTATE, and other
+/// causes by their message.
+pub fn classify(error: &anyhow::Error) -> FailureKind {
+    for cause in error.chain().rev() {
+        if let Some(kind) = classify_cause(cause) {
+            return kind;
+        }
+    }
+    FailureKind::Other
+}
+
+fn classify_cause(cause: &(dyn std::error::Error + 'static)) -> Option<FailureKind> {
+    if let Some(failure) = cause.downcast_ref::<Failure>() {
+        return Some(failure.kind);
+    }
+    if let Some(e) = cause.downcast_ref::<tokio_postgres::Error>() {
+        if let Some(db_error) = e.as_db_error() {
+            if let Some(kind) = classify_sqlstate(db_error.code().code()) {
+                return Some(kind);
+            }
+        } else if e.is_closed() {
+            return Some(FailureKind::Connection);
+        }
+    }
+    if let Some(e) = cause.downcast_ref::<std::io::Error>() {
+        use std::io::ErrorKind;
+        if matches!(
+            e.kind(),
+            ErrorKind::ConnectionRefused
+                | ErrorKind::ConnectionReset
+                | ErrorKind::ConnectionAborted
+                | ErrorKind::NotConnected
+                | ErrorKind::TimedOut
+                | ErrorKind::BrokenPipe
+        ) {
+            return Some(FailureKind::Connection);
+        }
+    }
+    if cause.is::<tokio::time::error::Elapsed>() {
+        return Some(FailureKind::Connection);
+    }
+    classify_message(&cause.to_string())
+}
+
+/// Kind of a PostgreSQL SQLSTATE, if it says anything about retrying
+fn classify_sqlstate(code: &str) -> Option<FailureKind> {
+    match code {
+        // too_many_connections, admin_shutdown, crash_shutdown, cannot_connect_now
+        "53300" | "57P01" | "57P02" | "57P03" => Some(FailureKind::Connection),
+        // insufficient_privilege
+        "42501" => Some(FailureKind::Auth),
+        _ => match &code[..code.len().min(2)] {
+            "08" => Some(FailureKind::Connection),
+            "28" => Some(FailureKind::Auth),
+            // syntax errors and undefined objects, invalid schema names,
+            // dependent objects, unsupported features
+            "42" | "3F" | "2B" | "0A" => Some(FailureKind::Schema),
+            _ => None,
+        },
+    }
+}
+
+fn classify_message(message: &str) -> Option<FailureKind> {
+    let first_line = message.lines().next().unwrap_or_default();
+    // Connection errors keep the SQLSTATE as "PostgreSQL error 28P01: ..."
+    if let Some(kind) = first_line
+        .split_once("PostgreSQL error ")
+        .and_then(|(_, rest)| rest.get(..5))
+        .and_then(classify_sqlstate)
+    {
+        return Some(kind);
+    }
+    let first_line = first_line.to_lowercase();
+    let matches = |patterns: &[&str]| patterns.iter().any(|p| first_line.contains(p));
+    if matches(AUTH_PATTERNS) {
+        Some(FailureKind::Auth)
+    } else if matches(CONNECTION_PATTERNS) {
+        Some(FailureKind::Connection)
+    } else if matches(SCHEMA_PATTERNS) {
+        Some(FailureKind::Schema)
+    } else {
+        None
+    }
+}
+
+/// Why a PostgreSQL client tool such as pg_dump or psql failed, read from its stderr
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToolErrorClass {
+    /// The server rejected the credentials, or pg_hba.conf refused the client
+    AuthFailed,
+    /// A role named by the connection or by the dump does not exist
+    MissingRole,
+    /// The user lacks a privilege the command needs
+    PermissionDenied,
+    /// A disk ran out of space, on this machine or on the server
+    DiskFull,
+    /// The server could not be reached or dropped the connection
+    ConnectionLost,
+    Unknown,
+}
+
+// Matched against each stderr line, lowercased, in the order of
+// TOOL_ERROR_PATTERNS. A rejected login also reads "connection to server ...
+// failed", so credentials are checked before the connection.
+const DISK_FULL_PATTERNS: &[&str] = &[
+    "no space left on device",
+    "could not extend file",
+    "disk full",
+    "disk quota exceeded",
+];
+
+const AUTH_FAILED_PATTERNS: &[&str] = &[
+    "password authentication failed",
+    "authentication failed",
+    "no password supplied",
+    "password is required",
+    "no pg_hba.conf entry",
+];
+
+const PERMISSION_DENIED_PATTERNS: &[&str] = &[
+    "permission denied",
+    "must be owner of",
+    "must be superuser",
+    "must be member of role",
+];
+
+const CONNECTION_LOST_PATTERNS: &[&str] = &[
+    "connection reset",
+    "connection refused",
+    "could not connect",
+    "server closed the connection",
+    "connection closed",
+    "could not receive data from server",
+    "could not send data to server",
+    "ssl syscall error",
+    "timeout expired",
+    "timed out",
+    "terminating connection due to administrator command",
+    "too many connections",
+    "remaining connection slots are reserved",
+    "the database system is starting up",
+    "the database system is shutting down",
+    "the database system is in recovery mode",
+    "could not translate host name",
+    "no route to host",
+    "network is unreachable",
+    "broken pipe",
+];
+
+const TOOL_ERROR_PATTERNS: &[(ToolErrorClass, &[&str])] = &[
+    (ToolErrorClass::DiskFull, DISK_FULL_PATTERNS),
+    (ToolErrorClass::AuthFailed, AUTH_FAILED_PATTERNS),
+    (ToolErrorClass::PermissionDenied, PERMISSION_DENIED_PATTERNS),
+    (ToolErrorClass::ConnectionLost, CONNECTION_LOST_PATTERNS),
+];
+
+impl ToolErrorClass {
+    pub fn describe(&self) -> &'static str {
+        match self {
+            ToolErrorClass::AuthFailed => "authentication failed",
+            ToolErrorClass::MissingRole => "missing role",
+            ToolErrorClass::PermissionDenied => "permission denied",
+            ToolErrorClass::DiskFull => "disk full",
+            ToolErrorClass::ConnectionLost => "connection lost",
+            ToolErrorClass::Unknown => "unrecognized error",
+        }
+    }
+
+    /// Whether running the tool again unchanged can succeed
+    pub fn is_transient(&self) -> bool {
+        matches!(self, ToolErrorClass::ConnectionLost)
+    }
+
+    /// What to change before running the command again
+    pub fn suggestion(&self) -> Option<&'static str> {
+        match self {
+            ToolErrorClass::AuthFailed => Some(
+                "Check the username and password in the connection URL, and that the \
+                 server's pg_hba.conf allows this client to connect.",
+            ),
+            ToolErrorClass::MissingRole => Some(
+                "Check the username in the connection URL. If the dump refers to the role, \
+                 create it on the target first or copy roles with `init --include-roles`.",
+            ),
+            ToolErrorClass::PermissionDenied => Some(
+                "Grant the user the privileges the command needs, or connect as the owner \
+                 of the objects.",
+            ),
+            ToolErrorClass::DiskFull => Some(
+                "Free disk space or use --work-dir to put dump files on a larger volume. \
+                 If the server reported it, grow the target's storage.",
+            ),
+            ToolErrorClass::ConnectionLost => Some(
+                "Check that the server is reachable and accepting connections, then run \
+                 the command again.",
+            ),
+            ToolErrorClass::Unknown => None,
+        }
+    }
+
+    fn kind(&self) -> Option<FailureKind> {
+        match self {
+            ToolErrorClass::AuthFailed
+            | ToolErrorClass::MissingRole
+            | ToolErrorClass::PermissionDenied => Some(FailureKind::Auth),
+            ToolErrorClass::ConnectionLost => Some(FailureKind::Connection),
+            ToolErrorClass::DiskFull => Some(FailureKind::Other),
+            ToolErrorClass::Unknown => None,
+        }
+    }
+}
+
+/// A failed run of a client tool, with the stderr line that explains it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ToolError {
+    pub class: ToolErrorClass,
+    /// The line the class was read from, or the last error line when none
+    /// was recognized
+    pub line: Option<String>,
+}
+
+impl ToolError {
+    /// Classify a tool's stderr
+    pub fn from_stderr(stderr: &str) -> Self {
+        let lines: Vec<&str> = stderr
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .collect();
+        let find = |matches: &dyn Fn(&str) -> bool| {
+            lines
+                .iter()
+                .rev()
+                .find(|line| matches(&line.to_lowercase()))
+                .map(|line| line.to_string())
+        };
+        for (class, patterns) in TOOL_ERROR_PATTERNS {
+            if *class == ToolErrorClass::PermissionDenied {
+                let missing_role =
+                    find(&|line| line.contains("role \"") && line.contains("does not exist"));
+                if missing_role.is_some() {
+                    return Self {
+                        class: ToolErrorClass::MissingRole,
+                        line: missing_role,
+                    };
+                }
+            }
+            if let Some(line) = find(&|line| patterns.iter().any(|p| line.contains(p))) {
+                return Self {
+                    class: *class,
+                    line: Some(line),
+                };
+            }
+        }
+        Self {
+            class: ToolErrorClass::Unknown,
+            line: find(&|line| line.contains("error") || line.contains("fatal")),
+        }
+    }
+
+    /// The error to report once `operation` has failed for good
+    ///
+    /// A recognized class becomes a [`Failure`] of the matching kind, so
+    /// [`classify`] does not have to guess from the text.
+    pub fn into_error(self, operation: &str, exit_code: Option<i32>) -> anyhow::Error {
+        let mut message = format!(
+            "{} failed with exit code {}",
+            operation,
+            exit_code.unwrap_or(-1)
+        );
+        if self.class != ToolErrorClass::Unknown {
+            message.push_str(&format!(" ({})", self.class.describe()));
+        }
+        if let Some(line) = &self.line {
+            message.push_str(&format!(": {}", line));
+        }
+        if let Some(suggestion) = self.class.suggestion() {
+            message.push_str(&format!("\nSuggestion: {}", suggestion));
+        }
+        match self.class.kind() {
+            Some(kind) => anyhow::Error::new(Failure::new(kind, message)),
+            None => anyhow::anyhow!(message),
+        }
+    }
+}
+
+/// Exit code for each kind of failure, as given to `--exit-code-map`
+///
+/// Parsed from `kind=code` pairs separated by commas, e.g.
+/// `connection=75,schema=2`. Kinds that are not listed keep their
+/// [`FailureKind::default_exit_code`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExitCodeMap {
+    overrides: Vec<(FailureKind, i32)>,
+}
+
+impl ExitCodeMap {
+    pub fn exit_code(&self, kind: FailureKind) -> i32 {
+        self.overrides
+            .iter()
+            .rev()
+            .find(|(k, _)| *k == kind)
+            .map(|(_, code)| *code)
+            .unwrap_or_else(|| kind.default_exit_code())
+    }
+}
+
+impl FromStr for ExitCodeMap {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut overrides = Vec::new();
+        for entry in s.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let Some((kind, code)) = entry.split_once('=') else {
+                bail!("Invalid exit code mapping '{}'; expected KIND=CODE", entry);
+            };
+            let kind: FailureKind = kind.trim().parse()?;
+            let code: i32 = code
+                .trim()
+                .parse()
+                .with_context(|| format!("Invalid exit code in '{}'", entry))?;
+            if !(1..=255).contains(&code) {
+                bail!(
+                    "Exit code for {} must be between 1 and 255, got {}",
+                    kind.as_str(),
+                    code
+                );
+            }
+            overrides.push((kind, code));
+        }
+        Ok(Self { overrides })
+    }
+}
+
+/// Outcome of a command, written as JSON where a job controller can read it
+#[derive(Debug, Clone, Serialize)]
+pub struct RunSummary {
+    pub command: String,
+    /// `succeeded` or `failed`
+    pub status: &'static str,
+    /// Kind of failure; absent on success
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<FailureKind>,
+    pub retryable: bool,
+    pub exit_code: i32,
+    /// Error with its causes, passwords already removed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Code, hint, and documentation link from the error catalog, when the
+    /// error is a known failure
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hint: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub doc_url: Option<String>,
+    pub finished_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl RunSummary {
+    pub fn succeeded(command: &str) -> Self {
+        Self {
+            command: command.to_string(),
+            status: "succeeded",
+            kind: None,
+            retryable: false,
+            exit_code: 0,
+            error: None,
+            code: None,
+            hint: None,
+            doc_url: None,
+            finished_at: chrono::Utc::now(),
+        }
+    }
+
+    /// Summary of a failed command, exiting with the code `exit_codes` gives its kind
+    pub fn failed(command: &str, error: &anyhow::Error, exit_codes: &ExitCodeMap) -> Self {
+        let kind = classify(error);
+        let mut message = format!("{:#}", error);
+        if message.len() > MAX_ERROR_LEN {
+            let mut end = MAX_ERROR_LEN;
+            while !message.is_char_boundary(end) {
+                end -= 1;
+            }
+            message.truncate(end);
+            message.push('…');
+        }
+        let hint = crate::errors::lookup(error);
+        Self {
+            command: command.to_string(),
+            status: "failed",
+            kind: Some(kind),
+            retryable: kind.is_retryable(),
+            exit_code: exit_codes.exit_code(kind),
+            error: Some(message),
+            code: hint.map(|hint| hint.code),
+            hint: hint.map(|hint| hint.hint),
+            doc_url: hint.map(|hint| hint.doc_url()),
+            finished_at: chrono::Utc::now(),
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string(self).context("Failed to serialize run summary")
+    }
+}
+
+/// Where to write the termination message: the given path, or Kubernetes'
+/// default file when running in a container that has it
+pub fn termination_log_path(explicit: Option<&Path>) -> Option<PathBuf> {
+    match explicit {
+        Some(path) => Some(path.to_path_buf()),
+        None => {
+            let default = Path::new(KUBERNETES_TERMINATION_LOG);
+            default.exists().then(|| default.to_path_buf())
+        }
+    }
+}
+
+/// Write `summary` as the termination message at `path`
+pub fn write_termination_log(path: &Path, summary: &RunSummary) -> Result<()> {
+    let mut json = summary.to_json()?;
+    if json.len() > TERMINATION_MESSAGE_LIMIT {
+        // Only the error text can grow this large
+        let mut shortened = summary.clone();
+        shortened.error = shortened
+            .error
+            .map(|e| e.lines().next().unwrap_or("").to_string());
+        json = shortened.to_json()?;
+    }
+    std::fs::write(path, json)
+        .with_context(|| format!("Failed to write termination message to {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    #[test]
+    fn test_tool_error_from_stderr() {
+        let classify = |stderr: &str| ToolError::from_stderr(stderr).class;
+        assert_eq!(
+            classify("pg_dump: dumping contents of table \"public.orders\"\npg_dump: error: could not write to output file: No space left on device"),
+            ToolErrorClass::DiskFull
+        );
+        assert_eq!(
+            classify("psql: error: connection to server at \"db\" (10.0.0.1), port 5432 failed: FATAL:  no pg_hba.conf entry for host \"10.0.0.2\""),
+            ToolErrorClass::AuthFailed
+        );
+        assert_eq!(
+            classify("psql:schema.sql:12: ERROR:  role \"app_owner\" does not exist"),
+            ToolErrorClass::MissingRole
+        );
+        assert_eq!(
+            classify("pg_dump: error: query failed: ERROR:  permission denied for table orders"),
+            ToolErrorClass::PermissionDenied
+        );
+        assert_eq!(
+            classify("pg_dump: error: query failed: SSL SYSCALL error: Connection reset by peer"),
+            ToolErrorClass::ConnectionLost
+        );
+
+        let unknown = ToolError::from_stderr(
+            "pg_dump: last built-in OID is 16383\npg_dump: error: invalid snapshot identifier\n",
+        );
+        assert_eq!(unknown.class, ToolErrorClass::Unknown);
+        assert_eq!(
+            unknown.line.as_deref(),
+            Some("pg_dump: error: invalid snapshot identifier")
+        );
+        let err = unknown.into_error("pg_dump", Some(1));
+        assert_eq!(
+            err.to_string(),
+            "pg_dump failed with exit code 1: pg_dump: error: invalid snapshot identifier"
+        );
+    }
+
+    #[test]
+    fn test_classify_messages() {
+        let refused = anyhow!("Connection refused: Unable to reach database server.\nError: x");
+        assert_eq!(classify(&refused), FailureKind::Connection);
+
+        let auth = anyhow!("Authentication failed: Invalid username or password.");
+        assert_eq!(classify(&auth), FailureKind::Auth);
+
+        // The SQLSTATE wins over the wording around it
+        let role = anyhow!(
+            "Failed to connect to database: PostgreSQL error 28000: role \"app\" does not exist"
+        );
+        assert_eq!(classify(&role), FailureKind::Auth);
+
+        // The causes listed after the first line must not decide the kind
+        let schema = anyhow!("psql (restore schema) failed with exit code: 3").context(
+            "Schema restoration failed.\n\nCommon causes:\n- Connection timeout or network issues",
+        );
+        assert_eq!(classify(&schema), FailureKind::Schema);
+
+        assert_eq!(
+            classify(&anyhow!("Invalid --time-filter")),
+            FailureKind::Other
+        );
+    }
+
+    #[test]
+    fn test_classify_prefers_innermost_cause() {
+        let error = anyhow!("server closed the connection unexpectedly")
+            .context("Schema restoration failed.");
+        assert_eq!(classify(&error), FailureKind::Connection);
+
+        let io = std::io::Error::from(std::io::ErrorKind::ConnectionReset);
+        let error = anyhow::Error::new(io).context("Failed to copy table");
+        assert_eq!(classify(&error), FailureKind::Connection);
+    }
+
+    #[test]
+    fn test_classify_sqlstate() {
+        assert_eq!(classify_sqlstate("08006"), Some(FailureKind::Connection));
+        assert_eq!(classify_sqlstate("57P03"), Some(FailureKind::Connection));
+        assert_eq!(classify_sqlstate("28P01"), Some(FailureKind::Auth));
+        assert_eq!(classify_sqlstate("42501"), Some(FailureKind::Auth));
+        assert_eq!(classify_sqlstate("42P01"), Some(FailureKind::Schema));
+        assert_eq!(classify_sqlstate("23505"), None);
+    }
+
+    #[test]
+    fn test_exit_code_map() {
+        let defaults = ExitCodeMap::default();
+        assert_eq!(defaults.exit_code(FailureKind::Connection), 75);
+        assert_eq!(defaults.exit_code(FailureKind::Other), 1);
+
+        let map: ExitCodeMap = "connection=3, schema=4".parse().unwrap();
+        assert_eq!(map.exit_code(FailureKind::Connection), 3);
+        assert_eq!(map.exit_code(FailureKind::Schema), 4);
+        assert_eq!(map.exit_code(FailureKind::Auth), 77);
+
+        assert!("connection".parse::<ExitCodeMap>().is_err());
+        assert!("network=3".parse::<ExitCodeMap>().is_err());
+        assert!("schema=0".parse::<ExitCodeMap>().is_err());
+        assert!("schema=256".parse::<ExitCodeMap>().is_err());
+    }
+
+    #[test]
+    fn test_failed_summary() {
+        let map: ExitCodeMap = "connection=3".parse().unwrap();
+        let error = anyhow!("could not connect to server").context("Failed to dump schema");
+        let summary = RunSummary::failed("init", &error, &map);
+        assert_eq!(summary.kind, Some(FailureKind::Connection));
+        assert!(summary.retryable);
+        assert_eq!(summary.exit_code, 3);
+
+        let json: serde_json::Value = serde_json::from_str(&summary.to_json().unwrap()).unwrap();
+        assert_eq!(json["status"], "failed");
+        assert_eq!(json["kind"], "connection");
+        assert_eq!(
+            json["error"],
+            "Failed to dump schema: could not connect to server"
+        );
+    }
+
+    #[test]
+    fn test_write_termination_log() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("termination-log");
+        let error = anyhow!("syntax error at or near \"x\"\n{}", "detail ".repeat(1000));
+        let summary = RunSummary::failed("init", &error, &ExitCodeMap::default());
+        write_termination_log(&path, &summary).unwrap();
+
+        let written = std::fs::read_to_string(&path).unwrap();
+        assert!(written.len() <= TERMINATION_MESSAGE_LIMIT);
+        let json: serde_json::Value = serde_json::from_str(&written).unwrap();
+        assert_eq!(json["kind"], "schema");
+        assert_eq!(json["exit_code"], 65);
+    }
+}
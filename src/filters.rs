@@ -1,19 +1,235 @@
 // ABOUTME: Central filtering logic for selective replication
 // ABOUTME: Handles database and table include/exclude patterns
 
-use crate::table_rules::TableRules;
-use anyhow::{bail, Result};
+use crate::jsonb::promote::PromotedKey;
+use crate::table_rules::{
+    ColumnOverrides, SampleRule, TableRules, TableSource, TargetNames, TimeFilterRule,
+};
+use crate::transform::TableTransforms;
+use anyhow::{bail, Context, Result};
+use regex::Regex;
 use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
 use tokio_postgres::Client;
 
+/// A database or table name from an include/exclude filter
+///
+/// Plain names match exactly. `*` (any run of characters) and `?` (one
+/// character) make the name a glob, and a `re:` prefix makes the rest a regular
+/// expression. Globs and regular expressions must match the whole name.
+#[derive(Debug, Clone)]
+pub struct NamePattern {
+    raw: String,
+    regex: Option<Regex>,
+}
+
+impl NamePattern {
+    /// Parse a filter entry
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use database_replicator::filters::NamePattern;
+    /// let glob = NamePattern::parse("tmp_*").unwrap();
+    /// assert!(glob.matches("tmp_2024"));
+    /// assert!(!glob.matches("prod"));
+    ///
+    /// let regex = NamePattern::parse(r"re:events_\d+").unwrap();
+    /// assert!(regex.matches("events_42"));
+    /// assert!(!regex.matches("events_archive"));
+    /// ```
+    pub fn parse(spec: &str) -> Result<Self> {
+        let regex = if let Some(expression) = spec.strip_prefix("re:") {
+            Some(
+                Regex::new(&format!("^(?:{})$", expression))
+                    .with_context(|| format!("Invalid regular expression in filter '{}'", spec))?,
+            )
+        } else if spec.contains(['*', '?']) {
+            let translated: String = spec
+                .chars()
+                .map(|c| match c {
+                    '*' => ".*".to_string(),
+                    '?' => ".".to_string(),
+                    other => regex::escape(&other.to_string()),
+                })
+                .collect();
+            Some(Regex::new(&format!("^{}$", translated))?)
+        } else {
+            None
+        };
+        Ok(Self {
+            raw: spec.to_string(),
+            regex,
+        })
+    }
+
+    /// Check whether a name matches this entry
+    pub fn matches(&self, name: &str) -> bool {
+        match &self.regex {
+            Some(regex) => regex.is_match(name),
+            None => self.raw == name,
+        }
+    }
+
+    /// Whether this entry is a glob or regular expression rather than a name
+    pub fn is_pattern(&self) -> bool {
+        self.regex.is_some()
+    }
+
+    /// The entry as written
+    pub fn as_str(&self) -> &str {
+        &self.raw
+    }
+}
+
+/// A `database.table` filter entry; each side may be a [`NamePattern`]
+///
+/// The entry is split at the first `.`, so the table side can itself be
+/// `schema.table` (e.g. `analytics.sales.orders_*`).
+#[derive(Debug, Clone)]
+struct TablePattern {
+    database: NamePattern,
+    table: NamePattern,
+}
+
+impl TablePattern {
+    fn parse(spec: &str) -> Result<Self> {
+        let (database, table) = spec.split_once('.').with_context(|| {
+            format!(
+                "Table must be specified as 'database.table', got '{}'",
+                spec
+            )
+        })?;
+        Ok(Self {
+            database: NamePattern::parse(database)?,
+            table: NamePattern::parse(table)?,
+        })
+    }
+
+    fn is_pattern(&self) -> bool {
+        self.database.is_pattern() || self.table.is_pattern()
+    }
+
+    /// Match a table given as `table` (public schema) or `schema.table`
+    fn matches(&self, db_name: &str, table_name: &str) -> bool {
+        if !self.database.matches(db_name) {
+            return false;
+        }
+        if self.table.matches(table_name) {
+            return true;
+        }
+        // Public tables can be written with or without their schema
+        match table_name.strip_prefix("public.") {
+            Some(bare) => self.table.matches(bare),
+            None => {
+                !table_name.contains('.') && self.table.matches(&format!("public.{}", table_name))
+            }
+        }
+    }
+}
+
+/// A schema filter entry: `schema` for every database, or `database.schema`
+#[derive(Debug, Clone)]
+struct SchemaPattern {
+    database: Option<NamePattern>,
+    schema: NamePattern,
+}
+
+impl SchemaPattern {
+    fn parse(spec: &str) -> Result<Self> {
+        match spec.split_once('.') {
+            Some((database, schema)) => Ok(Self {
+                database: Some(NamePattern::parse(database)?),
+                schema: NamePattern::parse(schema)?,
+            }),
+            None => Ok(Self {
+                database: None,
+                schema: NamePattern::parse(spec)?,
+            }),
+        }
+    }
+
+    fn matches(&self, db_name: &str, schema: &str) -> bool {
+        let database_matches = match &self.database {
+            Some(database) => database.matches(db_name),
+            None => true,
+        };
+        database_matches && self.schema.matches(schema)
+    }
+}
+
+/// Compiled forms of the include/exclude lists
+#[derive(Debug, Clone, Default)]
+struct CompiledPatterns {
+    include_databases: Option<Vec<NamePattern>>,
+    exclude_databases: Option<Vec<NamePattern>>,
+    include_tables: Option<Vec<TablePattern>>,
+    exclude_tables: Option<Vec<TablePattern>>,
+    include_schemas: Option<Vec<SchemaPattern>>,
+    exclude_schemas: Option<Vec<SchemaPattern>>,
+}
+
+impl CompiledPatterns {
+    fn compile(
+        include_databases: &Option<Vec<String>>,
+        exclude_databases: &Option<Vec<String>>,
+        include_tables: &Option<Vec<String>>,
+        exclude_tables: &Option<Vec<String>>,
+    ) -> Result<Self> {
+        fn databases(list: &Option<Vec<String>>) -> Result<Option<Vec<NamePattern>>> {
+            list.as_ref()
+                .map(|items| items.iter().map(|item| NamePattern::parse(item)).collect())
+                .transpose()
+        }
+        fn tables(list: &Option<Vec<String>>) -> Result<Option<Vec<TablePattern>>> {
+            list.as_ref()
+                .map(|items| items.iter().map(|item| TablePattern::parse(item)).collect())
+                .transpose()
+        }
+        Ok(Self {
+            include_databases: databases(include_databases)?,
+            exclude_databases: databases(exclude_databases)?,
+            include_tables: tables(include_tables)?,
+            exclude_tables: tables(exclude_tables)?,
+            include_schemas: None,
+            exclude_schemas: None,
+        })
+    }
+
+    fn compile_schemas(list: &Option<Vec<String>>) -> Result<Option<Vec<SchemaPattern>>> {
+        list.as_ref()
+            .map(|items| {
+                items
+                    .iter()
+                    .map(|item| SchemaPattern::parse(item))
+                    .collect()
+            })
+            .transpose()
+    }
+
+    fn has_patterns(&self) -> bool {
+        let databases =
+            |list: &Option<Vec<NamePattern>>| list.iter().flatten().any(NamePattern::is_pattern);
+        let tables =
+            |list: &Option<Vec<TablePattern>>| list.iter().flatten().any(TablePattern::is_pattern);
+        databases(&self.include_databases)
+            || databases(&self.exclude_databases)
+            || tables(&self.include_tables)
+            || tables(&self.exclude_tables)
+    }
+}
+
 /// Represents replication filtering rules
 #[derive(Debug, Clone, Default)]
 pub struct ReplicationFilter {
     include_databases: Option<Vec<String>>,
     exclude_databases: Option<Vec<String>>,
-    include_tables: Option<Vec<String>>, // Format: "db.table"
-    exclude_tables: Option<Vec<String>>, // Format: "db.table"
+    include_tables: Option<Vec<String>>,  // Format: "db.table"
+    exclude_tables: Option<Vec<String>>,  // Format: "db.table"
+    include_schemas: Option<Vec<String>>, // Format: "schema" or "db.schema"
+    exclude_schemas: Option<Vec<String>>, // Format: "schema" or "db.schema"
     table_rules: TableRules,
+    patterns: CompiledPatterns,
 }
 
 impl ReplicationFilter {
@@ -32,37 +248,45 @@ impl ReplicationFilter {
             bail!("Cannot use both --include-tables and --exclude-tables");
         }
 
-        // Validate table format (must be "database.table")
-        if let Some(ref tables) = include_tables {
-            for table in tables {
-                if !table.contains('.') {
-                    bail!(
-                        "Table must be specified as 'database.table', got '{}'",
-                        table
-                    );
-                }
-            }
-        }
-        if let Some(ref tables) = exclude_tables {
-            for table in tables {
-                if !table.contains('.') {
-                    bail!(
-                        "Table must be specified as 'database.table', got '{}'",
-                        table
-                    );
-                }
-            }
-        }
+        // Validates table format (must be "database.table") and compiles patterns
+        let patterns = CompiledPatterns::compile(
+            &include_databases,
+            &exclude_databases,
+            &include_tables,
+            &exclude_tables,
+        )?;
 
         Ok(Self {
             include_databases,
             exclude_databases,
             include_tables,
             exclude_tables,
+            include_schemas: None,
+            exclude_schemas: None,
             table_rules: TableRules::default(),
+            patterns,
         })
     }
 
+    /// Restrict replication to (or away from) whole schemas
+    ///
+    /// Entries are `schema` to apply to every database or `database.schema` for
+    /// one, and may use the same patterns as the other filters.
+    pub fn with_schemas(
+        mut self,
+        include_schemas: Option<Vec<String>>,
+        exclude_schemas: Option<Vec<String>>,
+    ) -> Result<Self> {
+        if include_schemas.is_some() && exclude_schemas.is_some() {
+            bail!("Cannot use both --include-schemas and --exclude-schemas");
+        }
+        self.patterns.include_schemas = CompiledPatterns::compile_schemas(&include_schemas)?;
+        self.patterns.exclude_schemas = CompiledPatterns::compile_schemas(&exclude_schemas)?;
+        self.include_schemas = include_schemas;
+        self.exclude_schemas = exclude_schemas;
+        Ok(self)
+    }
+
     /// Creates an empty filter (replicate everything)
     pub fn empty() -> Self {
         Self::default()
@@ -74,6 +298,7 @@ impl ReplicationFilter {
             && self.exclude_databases.is_none()
             && self.include_tables.is_none()
             && self.exclude_tables.is_none()
+            && !self.has_schema_filters()
             && self.table_rules.is_empty()
     }
 
@@ -102,6 +327,13 @@ impl ReplicationFilter {
         hasher.update(b"#");
         hash_option_list(&mut hasher, &self.exclude_tables);
         hasher.update(b"#");
+        // Only hashed when set, so fingerprints from before schema filters still match
+        if self.has_schema_filters() {
+            hash_option_list(&mut hasher, &self.include_schemas);
+            hasher.update(b"#");
+            hash_option_list(&mut hasher, &self.exclude_schemas);
+            hasher.update(b"#");
+        }
         hasher.update(self.table_rules.fingerprint().as_bytes());
 
         format!("{:x}", hasher.finalize())
@@ -120,10 +352,46 @@ impl ReplicationFilter {
         self.table_rules.schema_only_tables(database)
     }
 
+    pub fn priority_tables(&self, database: &str) -> Vec<String> {
+        self.table_rules.priority_tables(database)
+    }
+
     pub fn predicate_tables(&self, database: &str) -> Vec<(String, String)> {
         self.table_rules.predicate_tables(database)
     }
 
+    pub fn sample_tables(&self, database: &str) -> Vec<(String, SampleRule)> {
+        self.table_rules.sample_tables(database)
+    }
+
+    pub fn transformed_tables(&self, database: &str) -> BTreeMap<String, TableTransforms> {
+        self.table_rules.transformed_tables(database)
+    }
+
+    pub fn retention_tables(&self, database: &str) -> Vec<(String, String, String)> {
+        self.table_rules.retention_tables(database)
+    }
+
+    pub fn time_filter_tables(&self, database: &str) -> BTreeMap<String, TimeFilterRule> {
+        self.table_rules.time_filter_tables(database)
+    }
+
+    pub fn source_tables(&self, database: &str) -> BTreeMap<String, TableSource> {
+        self.table_rules.source_tables(database)
+    }
+
+    pub fn target_names(&self, database: &str) -> TargetNames {
+        self.table_rules.target_names(database)
+    }
+
+    pub fn column_override_tables(&self, database: &str) -> BTreeMap<String, ColumnOverrides> {
+        self.table_rules.column_override_tables(database)
+    }
+
+    pub fn promotions(&self, database: &str, schema: &str, table: &str) -> &[PromotedKey] {
+        self.table_rules.promotions(database, schema, table)
+    }
+
     /// Gets the list of databases to include
     pub fn include_databases(&self) -> Option<&Vec<String>> {
         self.include_databases.as_ref()
@@ -144,6 +412,21 @@ impl ReplicationFilter {
         self.exclude_tables.as_ref()
     }
 
+    /// Gets the list of schemas to include
+    pub fn include_schemas(&self) -> Option<&Vec<String>> {
+        self.include_schemas.as_ref()
+    }
+
+    /// Gets the list of schemas to exclude
+    pub fn exclude_schemas(&self) -> Option<&Vec<String>> {
+        self.exclude_schemas.as_ref()
+    }
+
+    /// Whether --include-schemas or --exclude-schemas is set
+    pub fn has_schema_filters(&self) -> bool {
+        self.include_schemas.is_some() || self.exclude_schemas.is_some()
+    }
+
     /// Gets the explicit list of databases to check/replicate
     ///
     /// Returns databases from:
@@ -182,25 +465,27 @@ impl ReplicationFilter {
     ///
     /// AND it's not in the exclude_databases list.
     pub fn should_replicate_database(&self, db_name: &str) -> bool {
-        // If include_databases list exists, database must be in it
-        if let Some(ref include) = self.include_databases {
-            if !include.contains(&db_name.to_string()) {
+        let patterns = &self.patterns;
+
+        // If include_databases list exists, database must match it
+        if let Some(ref include) = patterns.include_databases {
+            if !include.iter().any(|pattern| pattern.matches(db_name)) {
                 return false;
             }
-        } else if let Some(ref include_tables) = self.include_tables {
+        } else if let Some(ref include_tables) = patterns.include_tables {
             // If include_tables is specified but include_databases is not,
             // only replicate databases referenced in include_tables
             let db_referenced = include_tables
                 .iter()
-                .any(|table| table.split('.').next() == Some(db_name));
+                .any(|table| table.database.matches(db_name));
             if !db_referenced {
                 return false;
             }
         }
 
-        // If exclude list exists, database must not be in it
-        if let Some(ref exclude) = self.exclude_databases {
-            if exclude.contains(&db_name.to_string()) {
+        // If exclude list exists, database must not match it
+        if let Some(ref exclude) = patterns.exclude_databases {
+            if exclude.iter().any(|pattern| pattern.matches(db_name)) {
                 return false;
             }
         }
@@ -209,19 +494,35 @@ impl ReplicationFilter {
     }
 
     /// Determines if a table should be replicated
+    ///
+    /// `table_name` is `table` for the public schema or `schema.table` otherwise.
     pub fn should_replicate_table(&self, db_name: &str, table_name: &str) -> bool {
-        let full_name = format!("{}.{}", db_name, table_name);
+        let patterns = &self.patterns;
+
+        let schema = match table_name.split_once('.') {
+            Some((schema, _)) => schema,
+            None => "public",
+        };
+        if !self.should_replicate_schema(db_name, schema) {
+            return false;
+        }
 
-        // If include list exists, table must be in it
-        if let Some(ref include) = self.include_tables {
-            if !include.contains(&full_name) {
+        // If include list exists, table must match it
+        if let Some(ref include) = patterns.include_tables {
+            if !include
+                .iter()
+                .any(|pattern| pattern.matches(db_name, table_name))
+            {
                 return false;
             }
         }
 
-        // If exclude list exists, table must not be in it
-        if let Some(ref exclude) = self.exclude_tables {
-            if exclude.contains(&full_name) {
+        // If exclude list exists, table must not match it
+        if let Some(ref exclude) = patterns.exclude_tables {
+            if exclude
+                .iter()
+                .any(|pattern| pattern.matches(db_name, table_name))
+            {
                 return false;
             }
         }
@@ -229,6 +530,203 @@ impl ReplicationFilter {
         true
     }
 
+    /// Determines if tables in a schema should be replicated
+    pub fn should_replicate_schema(&self, db_name: &str, schema: &str) -> bool {
+        let patterns = &self.patterns;
+
+        if let Some(ref include) = patterns.include_schemas {
+            if !include
+                .iter()
+                .any(|pattern| pattern.matches(db_name, schema))
+            {
+                return false;
+            }
+        }
+
+        if let Some(ref exclude) = patterns.exclude_schemas {
+            if exclude
+                .iter()
+                .any(|pattern| pattern.matches(db_name, schema))
+            {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// Whether any include/exclude entry is a glob or regular expression
+    pub fn has_patterns(&self) -> bool {
+        self.patterns.has_patterns()
+    }
+
+    /// Replace glob and regex entries with the matching names on the source
+    ///
+    /// Commands that hand exact names to `pg_dump` or build per-database table
+    /// lists call this first. Exact entries are kept as written. Fails, naming
+    /// the pattern, when any include pattern matches nothing: a typo in one of
+    /// several patterns would otherwise silently drop what it was meant to
+    /// select, and a lone unmatched pattern would widen the selection to
+    /// everything.
+    pub async fn resolve_patterns(&self, source_url: &str) -> Result<ReplicationFilter> {
+        if !self.has_patterns() {
+            return Ok(self.clone());
+        }
+
+        let source_client = crate::postgres::connect_with_retry(source_url)
+            .await
+            .context("Failed to connect to source to resolve filter patterns")?;
+        let databases: Vec<String> = crate::migration::schema::list_databases(&source_client)
+            .await?
+            .into_iter()
+            .map(|db| db.name)
+            .collect();
+
+        let resolve_databases = |list: &Option<Vec<NamePattern>>, flag: &str, include: bool| {
+            list.as_ref()
+                .map(|patterns| -> Result<Vec<String>> {
+                    let mut resolved = Vec::new();
+                    for pattern in patterns {
+                        if !pattern.is_pattern() {
+                            resolved.push(pattern.as_str().to_string());
+                            continue;
+                        }
+                        let matched: Vec<String> = databases
+                            .iter()
+                            .filter(|name| pattern.matches(name))
+                            .cloned()
+                            .collect();
+                        if include && matched.is_empty() {
+                            bail!(
+                                "{} pattern '{}' matches no source database",
+                                flag,
+                                pattern.as_str()
+                            );
+                        }
+                        tracing::info!(
+                            "{} '{}' matched {} database(s)",
+                            flag,
+                            pattern.as_str(),
+                            matched.len()
+                        );
+                        resolved.extend(matched);
+                    }
+                    resolved.sort();
+                    resolved.dedup();
+                    Ok(resolved)
+                })
+                .transpose()
+        };
+        let include_databases = resolve_databases(
+            &self.patterns.include_databases,
+            "--include-databases",
+            true,
+        )?;
+        let exclude_databases = resolve_databases(
+            &self.patterns.exclude_databases,
+            "--exclude-databases",
+            false,
+        )?;
+        if include_databases
+            .as_ref()
+            .is_some_and(|list| list.is_empty())
+        {
+            bail!("No source databases match --include-databases");
+        }
+
+        let include_tables = self
+            .resolve_tables(
+                source_url,
+                &databases,
+                &self.patterns.include_tables,
+                "--include-tables",
+                true,
+            )
+            .await?;
+        let exclude_tables = self
+            .resolve_tables(
+                source_url,
+                &databases,
+                &self.patterns.exclude_tables,
+                "--exclude-tables",
+                false,
+            )
+            .await?;
+        if include_tables.as_ref().is_some_and(|list| list.is_empty()) {
+            bail!("No source tables match --include-tables");
+        }
+
+        Ok(ReplicationFilter::new(
+            include_databases,
+            exclude_databases,
+            include_tables,
+            exclude_tables,
+        )?
+        .with_schemas(self.include_schemas.clone(), self.exclude_schemas.clone())?
+        .with_table_rules(self.table_rules.clone()))
+    }
+
+    async fn resolve_tables(
+        &self,
+        source_url: &str,
+        databases: &[String],
+        list: &Option<Vec<TablePattern>>,
+        flag: &str,
+        include: bool,
+    ) -> Result<Option<Vec<String>>> {
+        let Some(patterns) = list else {
+            return Ok(None);
+        };
+
+        let mut resolved = Vec::new();
+        for pattern in patterns {
+            if !pattern.is_pattern() {
+                resolved.push(format!(
+                    "{}.{}",
+                    pattern.database.as_str(),
+                    pattern.table.as_str()
+                ));
+                continue;
+            }
+            let mut matched = 0;
+            for db_name in databases.iter().filter(|db| pattern.database.matches(db)) {
+                let db_url = crate::commands::sync::replace_database_in_url(source_url, db_name)?;
+                let db_client = crate::postgres::connect_with_retry(&db_url)
+                    .await
+                    .with_context(|| format!("Failed to connect to database '{}'", db_name))?;
+                for table in crate::migration::schema::list_tables(&db_client).await? {
+                    let identifier = if table.schema == "public" {
+                        table.name.clone()
+                    } else {
+                        format!("{}.{}", table.schema, table.name)
+                    };
+                    if pattern.matches(db_name, &identifier) {
+                        resolved.push(format!("{}.{}", db_name, identifier));
+                        matched += 1;
+                    }
+                }
+            }
+            if include && matched == 0 {
+                bail!(
+                    "{} pattern '{}.{}' matches no source table",
+                    flag,
+                    pattern.database.as_str(),
+                    pattern.table.as_str()
+                );
+            }
+            tracing::info!(
+                "{} '{}.{}' matched {} table(s)",
+                flag,
+                pattern.database.as_str(),
+                pattern.table.as_str(),
+                matched
+            );
+        }
+        resolved.sort();
+        resolved.dedup();
+        Ok(Some(resolved))
+    }
+
     /// Gets list of databases to replicate (queries source if needed)
     pub async fn get_databases_to_replicate(&self, source_conn: &Client) -> Result<Vec<String>> {
         // Get all databases from source
@@ -282,6 +780,10 @@ impl ReplicationFilter {
                     None
                 }
             })
+            .filter(|table: &String| {
+                let schema = table.split('.').next().unwrap_or("public");
+                self.should_replicate_schema(db_name, schema)
+            })
             .collect();
 
         if tables.is_empty() {
@@ -309,6 +811,56 @@ impl ReplicationFilter {
 
         Ok(filtered)
     }
+
+    /// Lists the user schemas in a database that the schema filters keep
+    pub async fn schemas_to_replicate(
+        &self,
+        source_conn: &Client,
+        db_name: &str,
+    ) -> Result<Vec<String>> {
+        Ok(list_schemas(source_conn)
+            .await?
+            .into_iter()
+            .filter(|schema| self.should_replicate_schema(db_name, schema))
+            .collect())
+    }
+
+    /// Lists the schemas in a database that the schema filters leave out
+    ///
+    /// Used to pass `--exclude-schema` to `pg_dump`, which keeps extensions and
+    /// other database-wide objects in the dump (unlike `--schema`).
+    pub async fn excluded_schemas(
+        &self,
+        source_conn: &Client,
+        db_name: &str,
+    ) -> Result<Vec<String>> {
+        if !self.has_schema_filters() {
+            return Ok(Vec::new());
+        }
+
+        Ok(list_schemas(source_conn)
+            .await?
+            .into_iter()
+            .filter(|schema| !self.should_replicate_schema(db_name, schema))
+            .collect())
+    }
+}
+
+/// Lists user schemas, skipping the system catalogs
+async fn list_schemas(client: &Client) -> Result<Vec<String>> {
+    let rows = client
+        .query(
+            "SELECT nspname FROM pg_catalog.pg_namespace
+             WHERE nspname NOT IN ('pg_catalog', 'information_schema')
+               AND nspname NOT LIKE 'pg\\_toast%'
+               AND nspname NOT LIKE 'pg\\_temp\\_%'
+             ORDER BY nspname",
+            &[],
+        )
+        .await
+        .context("Failed to list schemas")?;
+
+    Ok(rows.iter().map(|row| row.get(0)).collect())
 }
 
 #[cfg(test)]
@@ -577,4 +1129,285 @@ mod tests {
         assert_eq!(tables.len(), 1);
         assert!(tables.contains(&"analytics.events".to_string()));
     }
+
+    #[test]
+    fn test_name_pattern_exact_glob_and_regex() {
+        let exact = NamePattern::parse("orders").unwrap();
+        assert!(!exact.is_pattern());
+        assert!(exact.matches("orders"));
+        assert!(!exact.matches("orders_2024"));
+
+        let glob = NamePattern::parse("events_202?_*").unwrap();
+        assert!(glob.is_pattern());
+        assert!(glob.matches("events_2024_q1"));
+        assert!(!glob.matches("events_24_q1"));
+
+        // Regex metacharacters in globs are literal
+        let dotted = NamePattern::parse("a.b*").unwrap();
+        assert!(dotted.matches("a.bc"));
+        assert!(!dotted.matches("axbc"));
+
+        let regex = NamePattern::parse("re:tmp_(a|b)").unwrap();
+        assert!(regex.matches("tmp_a"));
+        assert!(!regex.matches("tmp_ab"));
+
+        assert!(NamePattern::parse("re:(unclosed").is_err());
+    }
+
+    #[test]
+    fn test_database_patterns() {
+        let filter =
+            ReplicationFilter::new(None, Some(vec!["tmp_*".to_string()]), None, None).unwrap();
+        assert!(!filter.should_replicate_database("tmp_scratch"));
+        assert!(filter.should_replicate_database("production"));
+
+        let filter =
+            ReplicationFilter::new(Some(vec!["re:app_\\d+".to_string()]), None, None, None)
+                .unwrap();
+        assert!(filter.should_replicate_database("app_1"));
+        assert!(!filter.should_replicate_database("app_x"));
+    }
+
+    #[test]
+    fn test_table_patterns() {
+        let filter = ReplicationFilter::new(
+            None,
+            None,
+            Some(vec![
+                "analytics.events_*".to_string(),
+                "*.audit.log_?".to_string(),
+            ]),
+            None,
+        )
+        .unwrap();
+        assert!(filter.has_patterns());
+
+        // Database inferred from the table pattern
+        assert!(filter.should_replicate_database("analytics"));
+        assert!(filter.should_replicate_database("billing"));
+
+        assert!(filter.should_replicate_table("analytics", "events_2024"));
+        assert!(filter.should_replicate_table("analytics", "public.events_2024"));
+        assert!(!filter.should_replicate_table("analytics", "users"));
+        assert!(filter.should_replicate_table("billing", "audit.log_1"));
+        assert!(!filter.should_replicate_table("billing", "audit.log_10"));
+
+        let filter = ReplicationFilter::new(
+            None,
+            None,
+            None,
+            Some(vec!["app.re:.*_(bak|old)".to_string()]),
+        )
+        .unwrap();
+        assert!(!filter.should_replicate_table("app", "orders_bak"));
+        assert!(filter.should_replicate_table("app", "orders"));
+        assert!(filter.should_replicate_table("other", "orders_bak"));
+    }
+
+    #[test]
+    fn test_exact_names_are_not_patterns() {
+        let filter = ReplicationFilter::new(
+            Some(vec!["db1".to_string()]),
+            None,
+            Some(vec!["db1.public.users".to_string()]),
+            None,
+        )
+        .unwrap();
+        assert!(!filter.has_patterns());
+        assert!(filter.should_replicate_table("db1", "users"));
+    }
+
+    #[test]
+    fn test_schema_filters() {
+        let filter = ReplicationFilter::empty()
+            .with_schemas(
+                Some(vec!["sales".to_string(), "app.audit".to_string()]),
+                None,
+            )
+            .unwrap();
+        assert!(!filter.is_empty());
+        assert!(filter.should_replicate_schema("app", "sales"));
+        assert!(filter.should_replicate_schema("app", "audit"));
+        assert!(!filter.should_replicate_schema("other", "audit"));
+        assert!(!filter.should_replicate_schema("app", "public"));
+
+        assert!(filter.should_replicate_table("app", "sales.orders"));
+        assert!(!filter.should_replicate_table("app", "users"));
+
+        let filter = ReplicationFilter::empty()
+            .with_schemas(None, Some(vec!["staging_*".to_string()]))
+            .unwrap();
+        assert!(filter.should_replicate_table("app", "users"));
+        assert!(!filter.should_replicate_table("app", "staging_2024.orders"));
+    }
+
+    #[test]
+    fn test_schema_filters_are_mutually_exclusive() {
+        let result = ReplicationFilter::empty().with_schemas(
+            Some(vec!["sales".to_string()]),
+            Some(vec!["audit".to_string()]),
+        );
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("Cannot use both --include-schemas and --exclude-schemas"));
+    }
+
+    #[test]
+    fn test_schema_filters_apply_to_tables_for_database() {
+        let filter = ReplicationFilter::new(
+            None,
+            None,
+            Some(vec![
+                "db1.users".to_string(),
+                "db1.sales.orders".to_string(),
+            ]),
+            None,
+        )
+        .unwrap()
+        .with_schemas(None, Some(vec!["sales".to_string()]))
+        .unwrap();
+        assert_eq!(
+            filter.tables_for_database("db1").unwrap(),
+            vec!["public.users".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_fingerprint_unchanged_without_schema_filters() {
+        let plain =
+            ReplicationFilter::new(Some(vec!["db1".to_string()]), None, None, None).unwrap();
+        let with_none = plain.clone().with_schemas(None, None).unwrap();
+        assert_eq!(plain.fingerprint(), with_none.fingerprint());
+
+        let with_schema = plain
+            .clone()
+            .with_schemas(Some(vec!["sales".to_string()]), None)
+            .unwrap();
+        assert_ne!(plain.fingerprint(), with_schema.fingerprint());
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_resolve_patterns_expands_to_source_tables() {
+        let source_url = std::env::var("TEST_SOURCE_URL").expect("TEST_SOURCE_URL must be set");
+        let db_name = crate::utils::parse_postgres_url(&source_url)
+            .unwrap()
+            .database;
+
+        let client = crate::postgres::connect(&source_url).await.unwrap();
+        client
+            .batch_execute(
+                "DROP SCHEMA IF EXISTS resolve_pat CASCADE;
+                 CREATE SCHEMA resolve_pat;
+                 CREATE TABLE resolve_pat.events_a (id int);
+                 CREATE TABLE resolve_pat.events_b (id int);
+                 CREATE TABLE resolve_pat.users (id int);",
+            )
+            .await
+            .unwrap();
+
+        let filter = ReplicationFilter::new(
+            None,
+            None,
+            Some(vec![format!("{}.resolve_pat.events_*", db_name)]),
+            None,
+        )
+        .unwrap();
+        let resolved = filter.resolve_patterns(&source_url).await.unwrap();
+        assert!(!resolved.has_patterns());
+        assert_eq!(
+            resolved.include_tables().unwrap(),
+            &[
+                format!("{}.resolve_pat.events_a", db_name),
+                format!("{}.resolve_pat.events_b", db_name),
+            ]
+        );
+
+        let filter = ReplicationFilter::new(
+            None,
+            None,
+            Some(vec![format!("{}.resolve_pat.missing_*", db_name)]),
+            None,
+        )
+        .unwrap();
+        assert!(filter.resolve_patterns(&source_url).await.is_err());
+
+        client
+            .batch_execute("DROP SCHEMA resolve_pat CASCADE;")
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_resolve_patterns_names_each_unmatched_include() {
+        let source_url = std::env::var("TEST_SOURCE_URL").expect("TEST_SOURCE_URL must be set");
+        let db_name = crate::utils::parse_postgres_url(&source_url)
+            .unwrap()
+            .database;
+
+        let client = crate::postgres::connect(&source_url).await.unwrap();
+        client
+            .batch_execute(
+                "DROP SCHEMA IF EXISTS resolve_mixed CASCADE;
+                 CREATE SCHEMA resolve_mixed;
+                 CREATE TABLE resolve_mixed.\"Orders_2024\" (id int);
+                 CREATE TABLE resolve_mixed.orders_2025 (id int);",
+            )
+            .await
+            .unwrap();
+
+        // Globs are case-sensitive, like the identifiers they match
+        let filter = ReplicationFilter::new(
+            None,
+            None,
+            Some(vec![format!("{}.resolve_mixed.Orders_*", db_name)]),
+            None,
+        )
+        .unwrap();
+        let resolved = filter.resolve_patterns(&source_url).await.unwrap();
+        assert_eq!(
+            resolved.include_tables().unwrap(),
+            &[format!("{}.resolve_mixed.Orders_2024", db_name)]
+        );
+
+        // One matching and one unmatched pattern: the unmatched one is named
+        let filter = ReplicationFilter::new(
+            None,
+            None,
+            Some(vec![
+                format!("{}.resolve_mixed.orders_*", db_name),
+                format!("{}.resolve_mixed.ORDERS_*", db_name),
+            ]),
+            None,
+        )
+        .unwrap();
+        let err = filter.resolve_patterns(&source_url).await.unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "--include-tables pattern '{}.resolve_mixed.ORDERS_*' matches no source table",
+                db_name
+            )
+        );
+
+        let filter = ReplicationFilter::new(
+            Some(vec![db_name.clone(), "Missing_*".to_string()]),
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        let err = filter.resolve_patterns(&source_url).await.unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "--include-databases pattern 'Missing_*' matches no source database"
+        );
+
+        client
+            .batch_execute("DROP SCHEMA resolve_mixed CASCADE;")
+            .await
+            .unwrap();
+    }
 }
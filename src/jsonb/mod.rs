@@ -1,6 +1,8 @@
 // ABOUTME: JSONB utilities for storing non-PostgreSQL database data
 // ABOUTME: Provides schema creation and validation for SQLite, MongoDB, and MySQL data storage
 
+pub mod promote;
+pub mod schema;
 pub mod writer;
 
 use anyhow::{bail, Result};
@@ -86,6 +88,33 @@ pub fn validate_table_name(table_name: &str) -> Result<()> {
     Ok(())
 }
 
+/// Validate and quote a JSONB table name for use in SQL
+///
+/// Accepts `table` or `schema.table`; each part must pass
+/// [`validate_table_name`]. A bare name resolves through the search path.
+///
+/// # Examples
+///
+/// ```
+/// # use database_replicator::jsonb::quote_table_name;
+/// assert_eq!(quote_table_name("users").unwrap(), r#""users""#);
+/// assert_eq!(quote_table_name("app.users").unwrap(), r#""app"."users""#);
+/// assert!(quote_table_name("a.b.c").is_err());
+/// ```
+pub fn quote_table_name(table_name: &str) -> Result<String> {
+    match table_name.split_once('.') {
+        Some((schema, table)) => {
+            validate_table_name(schema)?;
+            validate_table_name(table)?;
+            Ok(format!(r#""{}"."{}""#, schema, table))
+        }
+        None => {
+            validate_table_name(table_name)?;
+            Ok(format!(r#""{}""#, table_name))
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -123,4 +152,16 @@ mod tests {
         assert!(validate_table_name("table").is_err());
         assert!(validate_table_name("drop").is_err());
     }
+
+    #[test]
+    fn test_quote_table_name() {
+        assert_eq!(quote_table_name("users").unwrap(), "\"users\"");
+        assert_eq!(
+            quote_table_name("analytics.users").unwrap(),
+            "\"analytics\".\"users\""
+        );
+        assert!(quote_table_name("analytics.").is_err());
+        assert!(quote_table_name("a.b.c").is_err());
+        assert!(quote_table_name("app.users; DROP").is_err());
+    }
 }
@@ -0,0 +1,201 @@
+// ABOUTME: Promotes frequently queried JSON keys of JSONB tables to typed columns
+// ABOUTME: Columns are generated from `data`, so PostgreSQL keeps them current on every write
+
+use anyhow::{bail, Context, Result};
+use tokio_postgres::Client;
+
+/// SQL type of a promoted key
+///
+/// Generated columns need immutable expressions, so only types whose cast
+/// from text does not depend on session settings are offered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PromotedType {
+    Text,
+    Bigint,
+    Numeric,
+    Boolean,
+}
+
+impl PromotedType {
+    pub fn parse(value: &str) -> Result<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "text" => Ok(Self::Text),
+            "bigint" | "int8" | "integer" => Ok(Self::Bigint),
+            "numeric" | "decimal" => Ok(Self::Numeric),
+            "boolean" | "bool" => Ok(Self::Boolean),
+            other => bail!(
+                "Unsupported promoted column type '{}'; use text, bigint, numeric, or boolean",
+                other
+            ),
+        }
+    }
+
+    /// Name as reported by `format_type`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Text => "text",
+            Self::Bigint => "bigint",
+            Self::Numeric => "numeric",
+            Self::Boolean => "boolean",
+        }
+    }
+}
+
+/// A JSON key of a JSONB table kept in a typed, indexed column
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PromotedKey {
+    /// Top-level key in the row's `data` document
+    pub key: String,
+    /// Name of the generated column
+    pub column: String,
+    pub column_type: PromotedType,
+}
+
+impl PromotedKey {
+    pub fn new(key: &str, column: Option<&str>, column_type: PromotedType) -> Result<Self> {
+        if key.is_empty() {
+            bail!("Promoted key cannot be empty");
+        }
+        let column = column.unwrap_or(key);
+        super::validate_table_name(column)
+            .with_context(|| format!("Invalid column name for promoted key '{}'", key))?;
+        if column.starts_with('_') || column == "id" || column == "data" {
+            bail!(
+                "Promoted column '{}' clashes with the JSONB table's own columns; set 'column'",
+                column
+            );
+        }
+        Ok(Self {
+            key: key.to_string(),
+            column: column.to_string(),
+            column_type,
+        })
+    }
+
+    /// Generation expression reading the key from `data`
+    ///
+    /// Values that do not fit the type become NULL instead of failing the
+    /// write.
+    pub fn expression(&self) -> String {
+        let key = self.key.replace('\'', "''");
+        match self.column_type {
+            PromotedType::Text => format!("(data ->> '{key}')"),
+            PromotedType::Bigint => format!(
+                "(CASE WHEN (data ->> '{key}') ~ '^-?[0-9]{{1,18}}$' \
+                 THEN (data ->> '{key}')::bigint END)"
+            ),
+            PromotedType::Numeric => format!(
+                "(CASE WHEN jsonb_typeof(data -> '{key}') = 'number' \
+                 THEN (data ->> '{key}')::numeric END)"
+            ),
+            PromotedType::Boolean => format!(
+                "(CASE WHEN jsonb_typeof(data -> '{key}') = 'boolean' \
+                 THEN (data ->> '{key}')::boolean END)"
+            ),
+        }
+    }
+}
+
+impl std::fmt::Display for PromotedKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} as {} {}",
+            self.key,
+            self.column,
+            self.column_type.as_str()
+        )
+    }
+}
+
+/// Add or update the generated columns and indexes of promoted keys
+///
+/// Idempotent: matching columns are left alone, while a column whose type
+/// changed is regenerated. Columns of keys no longer promoted are kept.
+pub async fn promote_jsonb_keys(
+    client: &Client,
+    table_name: &str,
+    keys: &[PromotedKey],
+) -> Result<()> {
+    if keys.is_empty() {
+        return Ok(());
+    }
+    let quoted = super::quote_table_name(table_name)?;
+    let index_prefix = table_name.replace('.', "_");
+
+    for promoted in keys {
+        let existing = client
+            .query_opt(
+                "SELECT format_type(atttypid, atttypmod), attgenerated <> '' \
+                 FROM pg_attribute \
+                 WHERE attrelid = to_regclass($1::text) AND attname = $2 AND NOT attisdropped",
+                &[&quoted, &promoted.column],
+            )
+            .await?;
+        match existing {
+            Some(row) => {
+                let column_type: String = row.get(0);
+                let generated: bool = row.get(1);
+                if !generated {
+                    bail!(
+                        "Column '{}' of '{}' exists and is not a promoted key",
+                        promoted.column,
+                        table_name
+                    );
+                }
+                if column_type == promoted.column_type.as_str() {
+                    continue;
+                }
+                tracing::info!(
+                    "Changing promoted column '{}' of '{}' from {} to {}",
+                    promoted.column,
+                    table_name,
+                    column_type,
+                    promoted.column_type.as_str()
+                );
+                client
+                    .batch_execute(&format!(
+                        r#"ALTER TABLE {} DROP COLUMN "{}""#,
+                        quoted, promoted.column
+                    ))
+                    .await?;
+            }
+            None => {
+                tracing::info!("Promoting {} in '{}'", promoted, table_name);
+            }
+        }
+        let sql = format!(
+            r#"ALTER TABLE {quoted} ADD COLUMN "{column}" {column_type} GENERATED ALWAYS AS {expression} STORED;
+               CREATE INDEX IF NOT EXISTS "{index_prefix}_{column}_idx" ON {quoted} ("{column}")"#,
+            column = promoted.column,
+            column_type = promoted.column_type.as_str(),
+            expression = promoted.expression(),
+        );
+        client
+            .batch_execute(&sql)
+            .await
+            .with_context(|| format!("Failed to promote {} in '{}'", promoted, table_name))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_promoted_key_validation() {
+        let key = PromotedKey::new("email", None, PromotedType::Text).unwrap();
+        assert_eq!(key.column, "email");
+        assert_eq!(key.expression(), "(data ->> 'email')");
+
+        let key = PromotedKey::new("it's", Some("its"), PromotedType::Boolean).unwrap();
+        assert!(key.expression().contains("data -> 'it''s'"));
+
+        assert!(PromotedKey::new("id", None, PromotedType::Text).is_err());
+        assert!(PromotedKey::new("_op", None, PromotedType::Text).is_err());
+        assert!(PromotedKey::new("user-name", None, PromotedType::Text).is_err());
+        assert!(PromotedType::parse("timestamptz").is_err());
+        assert_eq!(PromotedType::parse("INT8").unwrap(), PromotedType::Bigint);
+    }
+}
@@ -0,0 +1,354 @@
+// ABOUTME: Infers the key/type schema of documents written to JSONB tables
+// ABOUTME: Versions it in `_replicator_schemas` and warns on incompatible type changes
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use tokio_postgres::Client;
+
+/// Nested objects are tracked as dotted key paths down to this depth
+const MAX_DEPTH: usize = 4;
+
+/// JSON type of a value as reported by `jsonb_typeof`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JsonKind {
+    Null,
+    Boolean,
+    Number,
+    String,
+    Array,
+    Object,
+}
+
+impl JsonKind {
+    pub fn of(value: &serde_json::Value) -> Self {
+        match value {
+            serde_json::Value::Null => Self::Null,
+            serde_json::Value::Bool(_) => Self::Boolean,
+            serde_json::Value::Number(_) => Self::Number,
+            serde_json::Value::String(_) => Self::String,
+            serde_json::Value::Array(_) => Self::Array,
+            serde_json::Value::Object(_) => Self::Object,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Null => "null",
+            Self::Boolean => "boolean",
+            Self::Number => "number",
+            Self::String => "string",
+            Self::Array => "array",
+            Self::Object => "object",
+        }
+    }
+}
+
+/// Key paths of a table's documents and every type seen for each
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct DocumentSchema {
+    keys: BTreeMap<String, BTreeSet<JsonKind>>,
+}
+
+impl DocumentSchema {
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Types seen for a key path such as `address.city`
+    pub fn kinds(&self, path: &str) -> Option<&BTreeSet<JsonKind>> {
+        self.keys.get(path)
+    }
+
+    /// Add the keys and types of a document
+    pub fn observe(&mut self, document: &serde_json::Value) {
+        if let serde_json::Value::Object(map) = document {
+            self.observe_object(map, "", 1);
+        }
+    }
+
+    fn observe_object(
+        &mut self,
+        map: &serde_json::Map<String, serde_json::Value>,
+        prefix: &str,
+        depth: usize,
+    ) {
+        for (key, value) in map {
+            let path = if prefix.is_empty() {
+                key.clone()
+            } else {
+                format!("{}.{}", prefix, key)
+            };
+            if let serde_json::Value::Object(nested) = value {
+                if depth < MAX_DEPTH {
+                    self.observe_object(nested, &path, depth + 1);
+                }
+            }
+            self.keys
+                .entry(path)
+                .or_default()
+                .insert(JsonKind::of(value));
+        }
+    }
+
+    pub fn merge(&mut self, other: &DocumentSchema) {
+        for (path, kinds) in &other.keys {
+            self.keys
+                .entry(path.clone())
+                .or_default()
+                .extend(kinds.iter().copied());
+        }
+    }
+
+    /// Keys and types in `self` that `previous` has not seen
+    ///
+    /// Keys missing from `self` are not reported: a batch of documents
+    /// rarely contains every optional key.
+    pub fn changes_since(&self, previous: &DocumentSchema) -> Vec<SchemaChange> {
+        let mut changes = Vec::new();
+        for (path, kinds) in &self.keys {
+            match previous.keys.get(path) {
+                None => changes.push(SchemaChange::KeyAdded {
+                    key: path.clone(),
+                    kinds: kinds.iter().copied().collect(),
+                }),
+                Some(known) => {
+                    let added: Vec<JsonKind> = kinds.difference(known).copied().collect();
+                    if !added.is_empty() {
+                        changes.push(SchemaChange::TypeChanged {
+                            key: path.clone(),
+                            from: known.iter().copied().collect(),
+                            to: added,
+                        });
+                    }
+                }
+            }
+        }
+        changes
+    }
+}
+
+/// Difference between two versions of a document schema
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SchemaChange {
+    KeyAdded {
+        key: String,
+        kinds: Vec<JsonKind>,
+    },
+    /// A known key arrived with types it has not had before
+    TypeChanged {
+        key: String,
+        from: Vec<JsonKind>,
+        to: Vec<JsonKind>,
+    },
+}
+
+impl SchemaChange {
+    /// Whether consumers reading the key's earlier type may break
+    ///
+    /// New keys and values becoming null, or a key that has only been null
+    /// gaining a type, are compatible.
+    pub fn is_incompatible(&self) -> bool {
+        match self {
+            Self::KeyAdded { .. } => false,
+            Self::TypeChanged { from, to, .. } => {
+                from.iter().any(|kind| *kind != JsonKind::Null)
+                    && to.iter().any(|kind| *kind != JsonKind::Null)
+            }
+        }
+    }
+}
+
+fn kind_list(kinds: &[JsonKind]) -> String {
+    kinds
+        .iter()
+        .map(JsonKind::as_str)
+        .collect::<Vec<_>>()
+        .join("|")
+}
+
+impl std::fmt::Display for SchemaChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::KeyAdded { key, kinds } => write!(f, "added '{}' ({})", key, kind_list(kinds)),
+            Self::TypeChanged { key, from, to } => write!(
+                f,
+                "'{}' changed from {} to {}",
+                key,
+                kind_list(from),
+                kind_list(to)
+            ),
+        }
+    }
+}
+
+/// Latest recorded schema per table, saving a registry read per batch
+///
+/// Clone it before a transaction and keep the clone only on commit, as
+/// versions recorded by a rolled-back transaction are lost.
+#[derive(Debug, Clone, Default)]
+pub struct SchemaRegistry {
+    known: HashMap<String, DocumentSchema>,
+}
+
+impl SchemaRegistry {
+    /// Record the schema of documents written to a table
+    ///
+    /// Stores a new version in `_replicator_schemas` when the documents
+    /// bring keys or types not seen before, and warns about incompatible
+    /// type changes. Returns the changes against the previous version.
+    pub async fn record(
+        &mut self,
+        client: &Client,
+        table_name: &str,
+        source_type: &str,
+        observed: &DocumentSchema,
+    ) -> Result<Vec<SchemaChange>> {
+        if observed.is_empty() {
+            return Ok(Vec::new());
+        }
+        if let Some(known) = self.known.get(table_name) {
+            if observed.changes_since(known).is_empty() {
+                return Ok(Vec::new());
+            }
+        }
+
+        ensure_schema_table(client).await?;
+        let latest = client
+            .query_opt(
+                "SELECT version, schema FROM _replicator_schemas \
+                 WHERE table_name = $1 ORDER BY version DESC LIMIT 1",
+                &[&table_name],
+            )
+            .await
+            .context("Failed to read the document schema registry")?;
+        let (version, mut schema) = match latest {
+            Some(row) => {
+                let version: i32 = row.get(0);
+                let schema: serde_json::Value = row.get(1);
+                let schema: DocumentSchema = serde_json::from_value(schema).with_context(|| {
+                    format!("Invalid schema version {} for '{}'", version, table_name)
+                })?;
+                (version, schema)
+            }
+            None => (0, DocumentSchema::default()),
+        };
+
+        // The first version has nothing to compare against
+        let changes = if version > 0 {
+            observed.changes_since(&schema)
+        } else {
+            Vec::new()
+        };
+        if version > 0 && changes.is_empty() {
+            self.known.insert(table_name.to_string(), schema);
+            return Ok(changes);
+        }
+
+        schema.merge(observed);
+        let incompatible = changes.iter().any(SchemaChange::is_incompatible);
+        let descriptions: Vec<String> = changes.iter().map(ToString::to_string).collect();
+        client
+            .execute(
+                "INSERT INTO _replicator_schemas \
+                 (table_name, version, source_type, schema, changes, incompatible) \
+                 VALUES ($1, $2, $3, $4, $5, $6) \
+                 ON CONFLICT (table_name, version) DO NOTHING",
+                &[
+                    &table_name,
+                    &(version + 1),
+                    &source_type,
+                    &serde_json::to_value(&schema)?,
+                    &descriptions,
+                    &incompatible,
+                ],
+            )
+            .await
+            .with_context(|| format!("Failed to record document schema of '{}'", table_name))?;
+
+        for change in &changes {
+            if change.is_incompatible() {
+                tracing::warn!(
+                    "⚠ Incompatible document change in '{}': {}",
+                    table_name,
+                    change
+                );
+            }
+        }
+        if version > 0 {
+            tracing::info!(
+                "Document schema of '{}' is now version {} ({} change(s))",
+                table_name,
+                version + 1,
+                changes.len()
+            );
+        }
+        self.known.insert(table_name.to_string(), schema);
+        Ok(changes)
+    }
+}
+
+async fn ensure_schema_table(client: &Client) -> Result<()> {
+    client
+        .execute(
+            r#"CREATE TABLE IF NOT EXISTS _replicator_schemas (
+                table_name TEXT NOT NULL,
+                version INTEGER NOT NULL,
+                source_type TEXT NOT NULL,
+                schema JSONB NOT NULL,
+                changes TEXT[] NOT NULL DEFAULT '{}',
+                incompatible BOOLEAN NOT NULL DEFAULT FALSE,
+                recorded_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                PRIMARY KEY (table_name, version)
+            )"#,
+            &[],
+        )
+        .await
+        .context("Failed to create _replicator_schemas")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_schema_changes() {
+        let mut previous = DocumentSchema::default();
+        previous.observe(&json!({"name": "Alice", "address": "Main St", "note": null}));
+
+        let mut observed = DocumentSchema::default();
+        observed.observe(&json!({"name": null, "address": {"city": "Oslo"}, "note": "hi"}));
+        observed.observe(&json!({"name": "Bob", "age": 30}));
+        assert_eq!(
+            observed.kinds("address.city").unwrap(),
+            &BTreeSet::from([JsonKind::String])
+        );
+
+        let changes = observed.changes_since(&previous);
+        let incompatible: Vec<String> = changes
+            .iter()
+            .filter(|change| change.is_incompatible())
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            incompatible,
+            vec!["'address' changed from string to object"]
+        );
+        assert!(changes.contains(&SchemaChange::KeyAdded {
+            key: "age".to_string(),
+            kinds: vec![JsonKind::Number],
+        }));
+        assert!(changes.contains(&SchemaChange::TypeChanged {
+            key: "note".to_string(),
+            from: vec![JsonKind::Null],
+            to: vec![JsonKind::String],
+        }));
+
+        previous.merge(&observed);
+        assert!(observed.changes_since(&previous).is_empty());
+    }
+}
@@ -47,7 +47,7 @@ pub async fn create_jsonb_table(
     source_type: &str,
 ) -> Result<()> {
     // Validate table name to prevent SQL injection
-    crate::jsonb::validate_table_name(table_name)
+    let quoted = crate::jsonb::quote_table_name(table_name)
         .context("Invalid table name for JSONB table creation")?;
 
     tracing::info!(
@@ -57,17 +57,17 @@ pub async fn create_jsonb_table(
     );
 
     // Create table with JSONB schema
-    // Note: table_name is validated above, so it's safe to use in SQL
+    // Note: table_name is validated and quoted above, so it's safe to use in SQL
     let create_table_sql = format!(
         r#"
-        CREATE TABLE IF NOT EXISTS "{}" (
+        CREATE TABLE IF NOT EXISTS {} (
             id TEXT PRIMARY KEY,
             data JSONB NOT NULL,
             _source_type TEXT NOT NULL,
             _migrated_at TIMESTAMP NOT NULL DEFAULT NOW()
         )
         "#,
-        table_name
+        quoted
     );
 
     client
@@ -75,10 +75,13 @@ pub async fn create_jsonb_table(
         .await
         .with_context(|| format!("Failed to create JSONB table '{}'", table_name))?;
 
+    // Index names are unqualified; they land in the table's schema
+    let index_prefix = table_name.replace('.', "_");
+
     // Create GIN index on data column for efficient JSONB queries
     let create_gin_index_sql = format!(
-        r#"CREATE INDEX IF NOT EXISTS "idx_{}_data" ON "{}" USING GIN (data)"#,
-        table_name, table_name
+        r#"CREATE INDEX IF NOT EXISTS "idx_{}_data" ON {} USING GIN (data)"#,
+        index_prefix, quoted
     );
 
     client
@@ -88,8 +91,8 @@ pub async fn create_jsonb_table(
 
     // Create index on _migrated_at for temporal queries
     let create_time_index_sql = format!(
-        r#"CREATE INDEX IF NOT EXISTS "idx_{}_migrated" ON "{}" (_migrated_at)"#,
-        table_name, table_name
+        r#"CREATE INDEX IF NOT EXISTS "idx_{}_migrated" ON {} (_migrated_at)"#,
+        index_prefix, quoted
     );
 
     client
@@ -125,22 +128,19 @@ pub async fn create_jsonb_table(
 /// CRITICAL: table_name MUST be validated with validate_table_name() before calling.
 pub async fn truncate_jsonb_table(client: &Client, table_name: &str) -> Result<()> {
     // Validate table name to prevent SQL injection
-    crate::jsonb::validate_table_name(table_name)
+    let quoted = crate::jsonb::quote_table_name(table_name)
         .context("Invalid table name for JSONB table truncation")?;
 
     tracing::info!("Truncating JSONB table '{}'", table_name);
 
-    let truncate_sql = format!(
-        r#"TRUNCATE TABLE "{}" RESTART IDENTITY CASCADE"#,
-        table_name
-    );
+    let truncate_sql = format!(r#"TRUNCATE TABLE {} RESTART IDENTITY CASCADE"#, quoted);
 
     client
         .execute(&truncate_sql, &[])
         .await
         .with_context(|| format!("Failed to truncate JSONB table '{}'", table_name))?;
 
-    let verify_sql = format!(r#"SELECT COUNT(*) FROM "{}""#, table_name);
+    let verify_sql = format!(r#"SELECT COUNT(*) FROM {}"#, quoted);
     let remaining_rows: i64 = client
         .query_one(&verify_sql, &[])
         .await
@@ -234,7 +234,8 @@ pub async fn copy_jsonb_batch(
     source_type: &str,
 ) -> Result<()> {
     // Validate table name to prevent SQL injection
-    crate::jsonb::validate_table_name(table_name).context("Invalid table name for JSONB COPY")?;
+    let quoted =
+        crate::jsonb::quote_table_name(table_name).context("Invalid table name for JSONB COPY")?;
 
     if rows.is_empty() {
         return Ok(());
@@ -250,8 +251,8 @@ pub async fn copy_jsonb_batch(
     // Start COPY command - we use text format for simplicity
     // Columns: id (text), data (jsonb), _source_type (text)
     let copy_sql = format!(
-        r#"COPY "{}" (id, data, _source_type) FROM STDIN WITH (FORMAT text)"#,
-        table_name
+        r#"COPY {} (id, data, _source_type) FROM STDIN WITH (FORMAT text)"#,
+        quoted
     );
 
     // Get the COPY sink
@@ -323,12 +324,12 @@ pub async fn copy_jsonb_batch(
 
 /// Drop a JSONB table if it exists.
 pub async fn drop_jsonb_table(client: &Client, table_name: &str) -> Result<()> {
-    crate::jsonb::validate_table_name(table_name)
+    let quoted = crate::jsonb::quote_table_name(table_name)
         .context("Invalid table name for JSONB table drop")?;
 
     tracing::info!("Dropping JSONB table '{}'", table_name);
 
-    let drop_sql = format!(r#"DROP TABLE IF EXISTS "{}" CASCADE"#, table_name);
+    let drop_sql = format!(r#"DROP TABLE IF EXISTS {} CASCADE"#, quoted);
 
     client
         .execute(&drop_sql, &[])
@@ -379,14 +380,14 @@ pub async fn insert_jsonb_row(
     source_type: &str,
 ) -> Result<()> {
     // Validate table name to prevent SQL injection
-    crate::jsonb::validate_table_name(table_name)
+    let quoted = crate::jsonb::quote_table_name(table_name)
         .context("Invalid table name for JSONB row insert")?;
 
     // Use parameterized query for data and metadata (safe from injection)
     // Note: table_name is validated above
     let insert_sql = format!(
-        r#"INSERT INTO "{}" (id, data, _source_type) VALUES ($1, $2, $3)"#,
-        table_name
+        r#"INSERT INTO {} (id, data, _source_type) VALUES ($1, $2, $3)"#,
+        quoted
     );
 
     client
@@ -442,6 +443,8 @@ async fn execute_batch_insert(
     rows: &[(String, serde_json::Value)],
     source_type: &str,
 ) -> Result<()> {
+    let quoted = crate::jsonb::quote_table_name(table_name)?;
+
     // Build parameterized multi-value INSERT
     let mut value_placeholders = Vec::with_capacity(rows.len());
     let mut params: Vec<&(dyn tokio_postgres::types::ToSql + Sync)> =
@@ -461,8 +464,8 @@ async fn execute_batch_insert(
     }
 
     let insert_sql = format!(
-        r#"INSERT INTO "{}" (id, data, _source_type) VALUES {}"#,
-        table_name,
+        r#"INSERT INTO {} (id, data, _source_type) VALUES {}"#,
+        quoted,
         value_placeholders.join(", ")
     );
 
@@ -518,7 +521,7 @@ pub async fn insert_jsonb_batch(
     source_type: &str,
 ) -> Result<()> {
     // Validate table name to prevent SQL injection
-    crate::jsonb::validate_table_name(table_name)
+    crate::jsonb::quote_table_name(table_name)
         .context("Invalid table name for JSONB batch insert")?;
 
     if rows.is_empty() {
@@ -635,8 +638,8 @@ pub async fn delete_jsonb_rows(client: &Client, table_name: &str, ids: &[String]
     if ids.is_empty() {
         return Ok(());
     }
-    crate::jsonb::validate_table_name(table_name)?;
-    let sql = format!(r#"DELETE FROM "{}" WHERE id = ANY($1)"#, table_name);
+    let quoted = crate::jsonb::quote_table_name(table_name)?;
+    let sql = format!(r#"DELETE FROM {} WHERE id = ANY($1)"#, quoted);
     client.execute(&sql, &[&ids]).await?;
     Ok(())
 }
@@ -651,7 +654,7 @@ pub async fn upsert_jsonb_rows(
     if rows.is_empty() {
         return Ok(());
     }
-    crate::jsonb::validate_table_name(table_name)?;
+    let quoted = crate::jsonb::quote_table_name(table_name)?;
 
     let mut value_placeholders = Vec::with_capacity(rows.len());
     let mut params: Vec<&(dyn ToSql + Sync)> = Vec::with_capacity(rows.len() * 3);
@@ -665,8 +668,8 @@ pub async fn upsert_jsonb_rows(
     }
 
     let sql = format!(
-        r#"INSERT INTO "{}" (id, data, _source_type) VALUES {} ON CONFLICT (id) DO UPDATE SET data = EXCLUDED.data, _source_type = EXCLUDED._source_type, _migrated_at = NOW()"#,
-        table_name,
+        r#"INSERT INTO {} (id, data, _source_type) VALUES {} ON CONFLICT (id) DO UPDATE SET data = EXCLUDED.data, _source_type = EXCLUDED._source_type, _migrated_at = NOW()"#,
+        quoted,
         value_placeholders.join(", ")
     );
     client.execute(&sql, &params).await?;
@@ -4,6 +4,9 @@
 use anyhow::Context;
 use clap::{Args, Parser, Subcommand};
 use database_replicator::commands;
+use database_replicator::postgres::iam::SourceAuth;
+use database_replicator::postgres::pooler::PoolerMode;
+use database_replicator::redact::RedactingWriter;
 #[cfg(feature = "sqlite-sync")]
 use std::path::PathBuf;
 
@@ -19,12 +22,47 @@ struct Cli {
         default_value_t = false
     )]
     allow_self_signed_certs: bool,
+    /// How to authenticate to the source database (aws-iam: RDS IAM tokens, gcp-iam: Cloud SQL IAM)
+    #[arg(
+        long = "source-auth",
+        global = true,
+        value_enum,
+        default_value = "password"
+    )]
+    source_auth: SourceAuth,
+    /// Most connections to open to one database server at once (parallel copy and verify)
+    #[arg(
+        long = "max-connections-per-host",
+        global = true,
+        default_value_t = database_replicator::postgres::pool::DEFAULT_MAX_CONNECTIONS_PER_HOST
+    )]
+    max_connections_per_host: usize,
+    /// Whether connections go through a transaction-mode pooler such as PgBouncer (auto: detect)
+    #[arg(
+        long = "pooler-mode",
+        global = true,
+        value_enum,
+        default_value = "auto"
+    )]
+    pooler_mode: PoolerMode,
+    /// How rows streamed from source to target are compressed in memory while
+    /// they wait for the target (zstd, lz4, none). Does not reduce network traffic
+    #[arg(long, global = true, value_enum, default_value = "none")]
+    compression: database_replicator::migration::transfer::Compression,
+    /// Ask pg_dump, pg_restore, and psql for TLS compression (insecure: exposes
+    /// connections to CRIME-style attacks, and most servers decline it)
+    #[arg(long = "ssl-compression", global = true)]
+    ssl_compression: bool,
     /// Set the log level (error, warn, info, debug, trace)
     #[arg(long, global = true, default_value = "info")]
     log: String,
     /// SerenDB API key for interactive target selection (falls back to SEREN_API_KEY env)
     #[arg(long = "api-key", env = "SEREN_API_KEY", global = true)]
     api_key: Option<String>,
+    /// When pg_dump, pg_restore, or psql are missing or older than the source,
+    /// download pinned PostgreSQL client tools into ~/.seren/tools and use them
+    #[arg(long = "download-pg-tools", global = true)]
+    download_pg_tools: bool,
     #[command(subcommand)]
     command: Commands,
 }
@@ -34,19 +72,39 @@ struct TableRuleArgs {
     /// Tables (optionally db.table) to replicate as schema-only
     #[arg(long = "schema-only-tables", value_delimiter = ',')]
     schema_only_tables: Vec<String>,
+    /// Tables (optionally db.schema.table) that init copies first, with the
+    /// tables they reference. With continuous sync, the other tables are copied
+    /// by the subscription while changes to these already stream
+    #[arg(long = "priority-tables", value_delimiter = ',')]
+    priority_tables: Vec<String>,
     /// Table-level filters in the form [db.]table:SQL-predicate (repeatable)
     #[arg(long = "table-filter")]
     table_filters: Vec<String>,
     /// Time filters in the form [db.]table:column:window (e.g., db.metrics:created_at:6 months)
     #[arg(long = "time-filter")]
     time_filters: Vec<String>,
+    /// Drop target partitions of a range-partitioned table once they are older
+    /// than a window, in the form [db.]table:window (e.g., db.public.events:90 days).
+    /// Applied by xmin-based sync
+    #[arg(long = "retention")]
+    retention: Vec<String>,
     /// Path to replication-config.toml describing advanced table rules
-    #[arg(long = "config")]
+    #[arg(long = "config", env = "DATABASE_REPLICATOR_CONFIG")]
     config_path: Option<String>,
 }
 
 #[derive(Subcommand)]
 enum Commands {
+    /// Check this machine and the servers before a migration
+    ///
+    /// Reports client tools and their versions, the TLS trust store, connection
+    /// time and latency to the source and target, the privileges replication
+    /// needs, wal_level, and free replication slots and WAL senders, each with
+    /// what to change when it fails.
+    Doctor {
+        #[command(flatten)]
+        args: commands::doctor::DoctorArgs,
+    },
     /// Validate source and target databases are ready for replication
     Validate {
         #[arg(long)]
@@ -65,9 +123,23 @@ enum Commands {
         /// Exclude these tables (format: database.table, comma-separated)
         #[arg(long, value_delimiter = ',')]
         exclude_tables: Option<Vec<String>>,
+        /// Include only tables in these schemas (format: schema or database.schema, comma-separated)
+        #[arg(long, value_delimiter = ',')]
+        include_schemas: Option<Vec<String>>,
+        /// Exclude tables in these schemas (format: schema or database.schema, comma-separated)
+        #[arg(long, value_delimiter = ',')]
+        exclude_schemas: Option<Vec<String>>,
         /// Disable interactive mode (use CLI filter flags instead)
-        #[arg(long)]
+        #[arg(long, env = "DATABASE_REPLICATOR_NO_INTERACTIVE")]
         no_interactive: bool,
+        /// Set REPLICA IDENTITY FULL on source tables whose UPDATEs and DELETEs
+        /// logical replication cannot publish (e.g. tables without a primary key)
+        #[arg(long)]
+        fix_replica_identity: bool,
+        /// Check privileges for this replication mode (default: logical when the
+        /// source has wal_level=logical, xmin otherwise)
+        #[arg(long, value_enum)]
+        mode: Option<database_replicator::postgres::grants::ReplicationMode>,
     },
     /// Initialize replication with snapshot copy of schema and data
     Init {
@@ -76,7 +148,7 @@ enum Commands {
         #[arg(long)]
         target: Option<String>,
         /// Skip confirmation prompt
-        #[arg(short = 'y', long)]
+        #[arg(short = 'y', long, env = "DATABASE_REPLICATOR_YES")]
         yes: bool,
         /// Include only these databases (comma-separated)
         #[arg(long, value_delimiter = ',')]
@@ -90,8 +162,14 @@ enum Commands {
         /// Exclude these tables (format: database.table, comma-separated)
         #[arg(long, value_delimiter = ',')]
         exclude_tables: Option<Vec<String>>,
+        /// Include only tables in these schemas (format: schema or database.schema, comma-separated)
+        #[arg(long, value_delimiter = ',')]
+        include_schemas: Option<Vec<String>>,
+        /// Exclude tables in these schemas (format: schema or database.schema, comma-separated)
+        #[arg(long, value_delimiter = ',')]
+        exclude_schemas: Option<Vec<String>>,
         /// Disable interactive mode (use CLI filter flags instead)
-        #[arg(long)]
+        #[arg(long, env = "DATABASE_REPLICATOR_NO_INTERACTIVE")]
         no_interactive: bool,
         #[command(flatten)]
         table_rules: TableRuleArgs,
@@ -107,6 +185,16 @@ enum Commands {
         /// Ignore any previous checkpoint and start a fresh run
         #[arg(long)]
         no_resume: bool,
+        /// Copy even when the data exceeds the SerenDB project's storage quota
+        #[arg(long)]
+        skip_quota_check: bool,
+        /// Replicate into a new SerenDB branch with this name, copied from the
+        /// target's branch, and verify it; the target's branch is left untouched
+        #[arg(long, value_name = "NAME")]
+        new_branch: Option<String>,
+        /// With --new-branch, make the new branch the project's default once it verifies
+        #[arg(long, requires = "new_branch")]
+        promote: bool,
         /// Execute on SerenAI's managed cloud infrastructure (requires SerenDB target)
         #[arg(long)]
         seren: bool,
@@ -119,6 +207,106 @@ enum Commands {
         /// Maximum job duration in seconds before timeout (default: 28800 = 8 hours)
         #[arg(long, default_value_t = 28800)]
         job_timeout: u64,
+        /// Allow experimental non-PostgreSQL targets (mysql://, sqlite:)
+        #[arg(long)]
+        experimental_targets: bool,
+        /// Create roles explicitly and reassign object ownership to match the source
+        #[arg(long)]
+        include_roles: bool,
+        /// Apply the source's GRANTs on schemas, tables, sequences, and functions
+        #[arg(long)]
+        include_grants: bool,
+        /// Give replicated login roles generated passwords (requires --include-roles)
+        #[arg(long, requires = "include_roles")]
+        generate_role_passwords: bool,
+        /// Leave row-level security policies out of the target schema (tables keep RLS enabled)
+        #[arg(long)]
+        skip_policies: bool,
+        /// Policies are copied by default; accepted so existing scripts keep working
+        #[arg(long, hide = true, conflicts_with = "skip_policies")]
+        include_policies: bool,
+        /// Leave triggers and event triggers out of the target schema
+        #[arg(long)]
+        skip_triggers: bool,
+        /// Record runs and per-table freshness in a `_replicator` schema on the target
+        #[arg(long)]
+        audit_schema: bool,
+        /// Read bulk data from this physical replica of the source (repeat to list fallbacks)
+        #[arg(long = "source-replica")]
+        source_replica: Vec<String>,
+        /// Copy the data of UNLOGGED tables (only their schema is copied by default)
+        #[arg(long)]
+        include_unlogged: bool,
+        /// Create materialized views the schema copy left out, and refresh all of
+        /// them after the data load in dependency order
+        #[arg(long)]
+        refresh_matviews: bool,
+        /// Rebuild indexes that sort by a collation whose locale or version
+        /// differs from the source after the data load
+        #[arg(long)]
+        reindex_after_restore: bool,
+        /// Create objects from a source tablespace in a target tablespace, as
+        /// SOURCE=TARGET (repeatable). Tablespaces are left out by default
+        #[arg(long = "map-tablespace", value_name = "SOURCE=TARGET")]
+        map_tablespace: Vec<String>,
+        /// Drop indexes and primary key/unique constraints before the data load
+        /// and rebuild them in parallel afterwards
+        #[arg(long)]
+        defer_indexes: bool,
+        /// Indexes to build at once with --defer-indexes (default 4)
+        #[arg(long, value_name = "N", requires = "defer_indexes")]
+        index_build_jobs: Option<usize>,
+        /// Don't ANALYZE the copied tables after the load (autovacuum collects
+        /// statistics later)
+        #[arg(long, conflicts_with_all = ["vacuum_after_load", "analyze_jobs"])]
+        skip_analyze: bool,
+        /// Run VACUUM (ANALYZE) instead of ANALYZE on the copied tables after the load
+        #[arg(long)]
+        vacuum_after_load: bool,
+        /// Tables to analyze at once after the load (default 4)
+        #[arg(long, value_name = "N")]
+        analyze_jobs: Option<usize>,
+        /// Tables to restore at once during the data load (default 4)
+        #[arg(long, value_name = "N")]
+        restore_jobs: Option<usize>,
+        /// Stream each table's rows from source to target with COPY instead
+        /// of writing a data dump to disk first (--restore-jobs tables at once)
+        #[arg(long)]
+        stream_copy: bool,
+        /// Copy again only the tables whose row count or checksum changed on
+        /// the source since the last --differential run; the first run copies
+        /// everything and records each table's state on the target
+        #[arg(long)]
+        differential: bool,
+        /// With --differential, keep tables whose data changed but whose row
+        /// count moved by no more than this percentage
+        #[arg(
+            long,
+            value_name = "PERCENT",
+            default_value_t = 0,
+            requires = "differential"
+        )]
+        drift_threshold: u32,
+        /// Directory for dump files instead of the system temp directory.
+        /// Use the same directory when resuming
+        #[arg(long, value_name = "DIR")]
+        work_dir: Option<std::path::PathBuf>,
+        /// Print the bytes transferred per phase as one line of JSON on stdout
+        /// (logs go to stderr)
+        #[arg(long)]
+        json: bool,
+        /// Exit codes for each kind of failure as KIND=CODE pairs, e.g.
+        /// connection=75,schema=65 (kinds: connection, auth, schema, other)
+        #[arg(
+            long,
+            value_name = "KIND=CODE,...",
+            env = "DATABASE_REPLICATOR_EXIT_CODE_MAP"
+        )]
+        exit_code_map: Option<database_replicator::failure::ExitCodeMap>,
+        /// Write a JSON summary of how the run ended to this file (default:
+        /// /dev/termination-log when it exists, as in Kubernetes containers)
+        #[arg(long, value_name = "PATH", env = "DATABASE_REPLICATOR_TERMINATION_LOG")]
+        termination_log: Option<std::path::PathBuf>,
     },
     /// Set up continuous replication from source to target (auto-detects best method)
     ///
@@ -126,48 +314,117 @@ enum Commands {
     /// - If source has wal_level=logical: uses PostgreSQL logical replication (fastest)
     /// - If source has wal_level=replica: uses xmin-based polling (no config required)
     Sync {
-        #[arg(long)]
+        #[arg(long, env = "DATABASE_REPLICATOR_SOURCE")]
         source: Option<String>,
-        #[arg(long)]
+        #[arg(long, env = "DATABASE_REPLICATOR_TARGET")]
         target: Option<String>,
         /// Include only these databases (comma-separated)
-        #[arg(long, value_delimiter = ',')]
+        #[arg(
+            long,
+            value_delimiter = ',',
+            env = "DATABASE_REPLICATOR_INCLUDE_DATABASES"
+        )]
         include_databases: Option<Vec<String>>,
         /// Exclude these databases (comma-separated)
-        #[arg(long, value_delimiter = ',')]
+        #[arg(
+            long,
+            value_delimiter = ',',
+            env = "DATABASE_REPLICATOR_EXCLUDE_DATABASES"
+        )]
         exclude_databases: Option<Vec<String>>,
         /// Include only these tables (format: database.table, comma-separated)
-        #[arg(long, value_delimiter = ',')]
+        #[arg(
+            long,
+            value_delimiter = ',',
+            env = "DATABASE_REPLICATOR_INCLUDE_TABLES"
+        )]
         include_tables: Option<Vec<String>>,
         /// Exclude these tables (format: database.table, comma-separated)
-        #[arg(long, value_delimiter = ',')]
+        #[arg(
+            long,
+            value_delimiter = ',',
+            env = "DATABASE_REPLICATOR_EXCLUDE_TABLES"
+        )]
         exclude_tables: Option<Vec<String>>,
+        /// Include only tables in these schemas (format: schema or database.schema, comma-separated)
+        #[arg(
+            long,
+            value_delimiter = ',',
+            env = "DATABASE_REPLICATOR_INCLUDE_SCHEMAS"
+        )]
+        include_schemas: Option<Vec<String>>,
+        /// Exclude tables in these schemas (format: schema or database.schema, comma-separated)
+        #[arg(
+            long,
+            value_delimiter = ',',
+            env = "DATABASE_REPLICATOR_EXCLUDE_SCHEMAS"
+        )]
+        exclude_schemas: Option<Vec<String>>,
         /// Disable interactive mode (use CLI filter flags instead)
-        #[arg(long)]
+        #[arg(long, env = "DATABASE_REPLICATOR_NO_INTERACTIVE")]
         no_interactive: bool,
         #[command(flatten)]
         table_rules: TableRuleArgs,
         /// Force recreate subscriptions even if they already exist
         #[arg(long)]
         force: bool,
+        /// Update existing publications to the tables the filter selects and copy
+        /// newly added tables (logical replication)
+        #[arg(long)]
+        refresh_publication: bool,
+        /// Keep running and add tables that newly match the filter: create them on
+        /// the target, copy them, and sync them (checked every --sync-interval)
+        #[arg(
+            long,
+            conflicts_with = "once",
+            env = "DATABASE_REPLICATOR_AUTO_ADD_TABLES"
+        )]
+        auto_add_tables: bool,
+        /// Sync UNLOGGED tables, which logical replication cannot publish, by
+        /// xmin polling alongside the subscription
+        #[arg(long, env = "DATABASE_REPLICATOR_INCLUDE_UNLOGGED")]
+        include_unlogged: bool,
+        /// Keep running and refresh the target's materialized views every N seconds
+        #[arg(
+            long,
+            value_name = "SECONDS",
+            conflicts_with = "once",
+            env = "DATABASE_REPLICATOR_REFRESH_MATVIEWS_INTERVAL"
+        )]
+        refresh_matviews_interval: Option<u64>,
         /// SerenDB project ID (for auto-enabling logical replication)
         #[arg(long)]
         project_id: Option<String>,
         /// SerenDB Console API URL (defaults to https://api.serendb.com)
         #[arg(long, default_value = "https://api.serendb.com")]
         console_api: String,
-        /// Sync interval in seconds for xmin-based sync (default: 3600 = 1 hour)
-        #[arg(long, default_value_t = 3600)]
-        sync_interval: u64,
-        /// Reconciliation interval in seconds for xmin-based sync (default: 86400 = 1 day)
-        #[arg(long, default_value_t = 86400)]
-        reconcile_interval: u64,
+        /// Enable logical replication on the SerenDB project, and restart an
+        /// endpoint that does not pick it up, without prompting
+        #[arg(long, env = "DATABASE_REPLICATOR_ASSUME_YES_LOGICAL_REPLICATION")]
+        assume_yes_logical_replication: bool,
+        /// Sync interval in seconds for xmin-based sync, and how often
+        /// --auto-add-tables looks for new tables (default: `interval` in the
+        /// [sync] section of --config, or 3600 = 1 hour)
+        #[arg(long, env = "DATABASE_REPLICATOR_SYNC_INTERVAL")]
+        sync_interval: Option<u64>,
+        /// Reconciliation interval in seconds for xmin-based sync (default:
+        /// `reconcile_interval` in the [sync] section of --config, or 86400 = 1 day)
+        #[arg(long, env = "DATABASE_REPLICATOR_RECONCILE_INTERVAL")]
+        reconcile_interval: Option<u64>,
         /// Run a single sync cycle and exit (don't run continuously)
-        #[arg(long)]
+        #[arg(long, env = "DATABASE_REPLICATOR_ONCE")]
         once: bool,
         /// Disable reconciliation (delete detection) for xmin-based sync
-        #[arg(long)]
+        #[arg(long, env = "DATABASE_REPLICATOR_NO_RECONCILE")]
         no_reconcile: bool,
+        /// Delete target rows that fall out of their --time-filter window, so
+        /// tables holding e.g. the last 90 days stay bounded (xmin-based sync)
+        #[arg(long, env = "DATABASE_REPLICATOR_PURGE_AGED_OUT")]
+        purge_aged_out: bool,
+        /// Let the target generate non-key GENERATED ALWAYS identity columns instead of
+        /// copying source values with OVERRIDING SYSTEM VALUE (xmin-based sync)
+        #[arg(long, env = "DATABASE_REPLICATOR_NO_IDENTITY_OVERRIDE")]
+        no_identity_override: bool,
         /// Run sync as a background daemon (detaches from terminal)
         #[arg(long)]
         daemon: bool,
@@ -177,6 +434,44 @@ enum Commands {
         /// Show status of the sync daemon
         #[arg(long)]
         daemon_status: bool,
+        /// Pause xmin-based sync without stopping the daemon (e.g. for target maintenance)
+        #[arg(long, conflicts_with = "resume")]
+        pause: bool,
+        /// Resume xmin-based sync paused with --pause
+        #[arg(long)]
+        resume: bool,
+        /// Set on the detached process started by --daemon on Windows
+        #[arg(long, hide = true)]
+        daemon_child: bool,
+        /// Allow experimental non-PostgreSQL targets (mysql://, sqlite:)
+        #[arg(long)]
+        experimental_targets: bool,
+        /// Replica server ID announced when streaming a MySQL/MariaDB binlog; must
+        /// differ from every other replica of the source (default: derived from the PID)
+        #[arg(long, env = "DATABASE_REPLICATOR_BINLOG_SERVER_ID")]
+        binlog_server_id: Option<u32>,
+        /// Record runs and per-table freshness in a `_replicator` schema on the target
+        #[arg(long, env = "DATABASE_REPLICATOR_AUDIT_SCHEMA")]
+        audit_schema: bool,
+        /// Keep comparing table checksums in the background of xmin-based sync,
+        /// reading at most this much per second from each side (e.g. 5MB), and
+        /// repair tables that drifted
+        #[arg(
+            long,
+            value_name = "RATE",
+            conflicts_with = "once",
+            env = "DATABASE_REPLICATOR_VERIFY_RATE"
+        )]
+        verify_rate: Option<String>,
+        /// Keep xmin sync state in the target database instead of local files and
+        /// write nothing under the home directory, e.g. to run in a container
+        /// that can be rescheduled (requires --target)
+        #[arg(
+            long,
+            env = "DATABASE_REPLICATOR_STATELESS",
+            conflicts_with_all = ["daemon", "pause", "resume"]
+        )]
+        stateless: bool,
     },
     /// Consume sqlite-watcher change batches and apply them to SerenDB JSONB tables
     #[cfg(feature = "sqlite-sync")]
@@ -184,18 +479,50 @@ enum Commands {
         /// Target PostgreSQL/Seren connection string
         #[arg(long)]
         target: String,
-        /// sqlite-watcher endpoint (unix:/path or tcp:host:port)
+        /// Another PostgreSQL target to apply the same changes to, for redundancy (repeatable)
+        #[arg(long = "mirror-target")]
+        mirror_targets: Vec<String>,
+        /// sqlite-watcher endpoint (unix:/path, tcp:host:port, or pipe:name on Windows)
         #[arg(long, default_value = "unix:/tmp/sqlite-watcher.sock")]
         watcher_endpoint: String,
         /// Optional shared-secret token file (defaults to ~/.seren/sqlite-watcher/token)
         #[arg(long)]
         token_file: Option<PathBuf>,
+        /// Watched database to sync, by its watcher ID (needed when the watcher follows several)
+        #[arg(long)]
+        watcher_database: Option<String>,
         /// Incremental mode: append (raw only) or append_deduped (maintains *_latest tables)
         #[arg(long, value_enum, default_value = "append")]
         incremental_mode: commands::sync_sqlite::IncrementalMode,
+        /// How deletes reach *_latest tables: delete the row, or tombstone it via _deleted_at
+        #[arg(long, value_enum, default_value = "delete")]
+        latest_deletes: commands::sync_sqlite::LatestDeletes,
+        /// After syncing, compact history rows older than this (e.g. 7d) down to
+        /// the latest version of each row
+        #[arg(long, value_name = "AGE", value_parser = sqlite_watcher::retention::parse_age)]
+        compact_history: Option<std::time::Duration>,
         /// Number of watcher rows to pull per batch
         #[arg(long, default_value_t = 500)]
         batch_size: u32,
+        /// Batches to fetch ahead of the one being applied, including it
+        #[arg(long, default_value_t = 4)]
+        max_in_flight: usize,
+        /// Longest fetched changes wait for more before being applied (e.g. 1s)
+        #[arg(long, value_parser = sqlite_watcher::retention::parse_age, default_value = "1s")]
+        flush_interval: std::time::Duration,
+        /// Most changes applied in one target transaction
+        #[arg(long, default_value_t = 2000)]
+        transaction_size: usize,
+        /// Sync only these SQLite tables (format: table or main.table, comma-separated)
+        #[arg(long, value_delimiter = ',')]
+        include_tables: Option<Vec<String>>,
+        /// Skip these SQLite tables (format: table or main.table, comma-separated)
+        #[arg(long, value_delimiter = ',')]
+        exclude_tables: Option<Vec<String>>,
+        /// Path to replication-config.toml whose [databases.main] renames map
+        /// SQLite tables to target schemas/tables
+        #[arg(long = "config")]
+        config_path: Option<String>,
     },
     /// Check replication status and lag in real-time
     Status {
@@ -209,6 +536,22 @@ enum Commands {
         /// Exclude these databases (comma-separated)
         #[arg(long, value_delimiter = ',')]
         exclude_databases: Option<Vec<String>>,
+        /// Keep re-checking every N seconds until interrupted
+        #[arg(long, value_name = "SECONDS", num_args = 0..=1, default_missing_value = "10")]
+        watch: Option<u64>,
+        /// Exit non-zero when replay lag exceeds N seconds or replication is not active
+        #[arg(long, value_name = "N")]
+        max_lag_seconds: Option<u64>,
+        /// POST a JSON alert to this URL when --max-lag-seconds is exceeded
+        #[arg(
+            long,
+            env = "DATABASE_REPLICATOR_NOTIFY_WEBHOOK",
+            requires = "max_lag_seconds"
+        )]
+        notify_webhook: Option<String>,
+        /// Print each report as a line of JSON on stdout (logs go to stderr)
+        #[arg(long)]
+        json: bool,
     },
     /// Verify data integrity between source and target
     Verify {
@@ -228,12 +571,125 @@ enum Commands {
         /// Exclude these tables (format: database.table, comma-separated)
         #[arg(long, value_delimiter = ',')]
         exclude_tables: Option<Vec<String>>,
+        /// Include only tables in these schemas (format: schema or database.schema, comma-separated)
+        #[arg(long, value_delimiter = ',')]
+        include_schemas: Option<Vec<String>>,
+        /// Exclude tables in these schemas (format: schema or database.schema, comma-separated)
+        #[arg(long, value_delimiter = ',')]
+        exclude_schemas: Option<Vec<String>>,
+        /// Skip tables that matched in an earlier, unfinished run with the same
+        /// source, target, and filters
+        #[arg(long)]
+        resume: bool,
+        /// Verify only tables xmin sync changed since this time: an RFC 3339
+        /// timestamp or a duration back from now (e.g. "6 hours", "2d")
+        #[arg(long, value_name = "WHEN")]
+        tables_changed_since: Option<String>,
+        /// Compare table data only, not indexes, constraints, triggers, and sequences
+        #[arg(long)]
+        skip_structure: bool,
+        /// Path to replication-config.toml; its [verify] section sets severities
+        /// and suppressions for the structure comparison, tables with transformed
+        /// columns or custom sources are left out of the data comparison, and
+        /// renamed tables are compared with their target names
+        #[arg(long = "config")]
+        config_path: Option<String>,
+    },
+    /// Drain replication, verify the target, and print a cutover checklist
+    ///
+    /// Pauses sync, optionally makes the source read-only, waits until the
+    /// target has caught up (LSN for logical replication, xmin high-water mark
+    /// for xmin-based sync), and runs a final verify.
+    Cutover {
+        #[arg(long)]
+        source: String,
+        #[arg(long)]
+        target: Option<String>,
+        /// Include only these databases (comma-separated)
+        #[arg(long, value_delimiter = ',')]
+        include_databases: Option<Vec<String>>,
+        /// Exclude these databases (comma-separated)
+        #[arg(long, value_delimiter = ',')]
+        exclude_databases: Option<Vec<String>>,
+        /// Include only these tables (format: database.table, comma-separated)
+        #[arg(long, value_delimiter = ',')]
+        include_tables: Option<Vec<String>>,
+        /// Exclude these tables (format: database.table, comma-separated)
+        #[arg(long, value_delimiter = ',')]
+        exclude_tables: Option<Vec<String>>,
+        /// Include only tables in these schemas (format: schema or database.schema, comma-separated)
+        #[arg(long, value_delimiter = ',')]
+        include_schemas: Option<Vec<String>>,
+        /// Exclude tables in these schemas (format: schema or database.schema, comma-separated)
+        #[arg(long, value_delimiter = ',')]
+        exclude_schemas: Option<Vec<String>>,
+        /// Make the source databases read-only for new sessions before draining
+        #[arg(long)]
+        read_only_source: bool,
+        /// Seconds to wait for replication to catch up
+        #[arg(long, default_value = "600")]
+        drain_timeout: u64,
+        /// Skip the confirmation before making the source read-only
+        #[arg(short = 'y', long, env = "DATABASE_REPLICATOR_YES")]
+        yes: bool,
     },
     /// Manage the target database URL
     Target {
         #[command(flatten)]
         args: commands::target::TargetArgs,
     },
+    /// Show past init, sync, and verify runs
+    History {
+        #[command(flatten)]
+        args: commands::history::HistoryArgs,
+    },
+    /// List or drop replication slots on the source
+    ///
+    /// Shows how much WAL each slot keeps on the source, and drops slots left
+    /// behind by init or sync once nothing streams from them.
+    Slots {
+        #[command(flatten)]
+        args: commands::slots::SlotsArgs,
+    },
+    /// Show the chain of replication hops feeding the target, and each hop's lag
+    ///
+    /// A replica can be the source of a further hop; every hop records its
+    /// origins in the target's _replicator_origins table.
+    Topology {
+        #[command(flatten)]
+        args: commands::topology::TopologyArgs,
+    },
+    /// Re-run SerenAI cloud jobs on a schedule
+    ///
+    /// `jobs schedule --cron` sends the spec of the last cloud job that
+    /// completed to the orchestrator, which re-runs it on the cron schedule.
+    Jobs {
+        #[command(flatten)]
+        args: commands::jobs::JobsArgs,
+    },
+    /// Back up the target's databases to S3
+    ///
+    /// Writes per-table compressed dumps and a manifest under
+    /// s3://bucket/prefix/<backup id>/, then deletes backups that fall out of
+    /// --keep/--keep-days. Credentials come from AWS_ACCESS_KEY_ID and
+    /// AWS_SECRET_ACCESS_KEY, or else from the aws CLI's configuration.
+    Backup {
+        #[command(flatten)]
+        args: commands::backup::BackupArgs,
+    },
+    /// Restore databases from a backup made with `backup`
+    Restore {
+        #[command(flatten)]
+        args: commands::backup::RestoreArgs,
+    },
+    /// Run as a long-lived service that accepts replication jobs over gRPC
+    ///
+    /// Jobs use the same JSON JobSpec as the SerenAI cloud and can be queried,
+    /// paused, resumed, cancelled, and followed through a log stream.
+    Serve {
+        #[command(flatten)]
+        args: commands::serve::ServeArgs,
+    },
 }
 
 #[tokio::main]
@@ -241,6 +697,10 @@ async fn main() -> anyhow::Result<()> {
     // We need to parse CLI args early to get the log level
     let cli = Cli::parse();
     let global_api_key = cli.api_key.clone();
+    // Make --api-key visible to every place that looks the key up
+    if let Some(ref key) = global_api_key {
+        std::env::set_var("SEREN_API_KEY", key);
+    }
 
     // Initialize logging
     // 1. RUST_LOG environment variable has highest precedence
@@ -249,7 +709,30 @@ async fn main() -> anyhow::Result<()> {
     let env_filter = tracing_subscriber::EnvFilter::try_from_default_env()
         .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new(cli.log.clone()));
 
-    tracing_subscriber::fmt().with_env_filter(env_filter).init();
+    // Keep stdout clean for commands that print machine-readable output
+    if database_replicator::daemon::is_daemon_child() {
+        // The detached Windows daemon has no console; it logs to the daemon log file
+        let log_file = database_replicator::daemon::open_log_file()?;
+        tracing_subscriber::fmt()
+            .with_env_filter(env_filter)
+            .with_ansi(false)
+            .with_writer(RedactingWriter::new(std::sync::Mutex::new(log_file)))
+            .init();
+    } else if matches!(
+        cli.command,
+        Commands::Status { json: true, .. } | Commands::Init { json: true, .. }
+    ) || matches!(&cli.command, Commands::Doctor { args } if args.json)
+    {
+        tracing_subscriber::fmt()
+            .with_env_filter(env_filter)
+            .with_writer(RedactingWriter::new(std::io::stderr))
+            .init();
+    } else {
+        tracing_subscriber::fmt()
+            .with_env_filter(env_filter)
+            .with_writer(RedactingWriter::new(std::io::stdout))
+            .init();
+    }
 
     // Clean up stale temp directories from previous runs (older than 24 hours)
     // This handles temp files left behind by processes killed with SIGKILL
@@ -260,8 +743,55 @@ async fn main() -> anyhow::Result<()> {
 
     // Initialize TLS policy using thread-safe OnceLock
     database_replicator::postgres::connection::init_tls_policy(cli.allow_self_signed_certs);
-
-    match cli.command {
+    database_replicator::postgres::pool::init_connection_limit(cli.max_connections_per_host);
+    database_replicator::postgres::pooler::init_pooler_mode(cli.pooler_mode);
+    database_replicator::migration::transfer::init_compression(cli.compression);
+    database_replicator::postgres::connection::init_ssl_compression(cli.ssl_compression);
+
+    // The CLI renders library events as debug log lines
+    let log_sink: std::sync::Arc<dyn database_replicator::events::EventSink> =
+        std::sync::Arc::new(database_replicator::events::LogSink);
+    // Runs that copy or check data are also recorded for `database-replicator history`
+    let recorder = history_run(&cli.command).and_then(|run| {
+        database_replicator::history::History::open_default()
+            .and_then(|history| {
+                database_replicator::history::RunRecorder::start(history, &run, log_sink.clone())
+            })
+            .map_err(|e| tracing::warn!("Failed to record run history: {:#}", e))
+            .ok()
+    });
+    let sink = match &recorder {
+        Some(recorder) => {
+            recorder.clone() as std::sync::Arc<dyn database_replicator::events::EventSink>
+        }
+        None => log_sink,
+    };
+    // Init reports how it ended for job controllers such as Kubernetes
+    let init_report = match &cli.command {
+        Commands::Init {
+            exit_code_map,
+            termination_log,
+            json,
+            ..
+        } => Some(InitReport {
+            exit_codes: exit_code_map.clone().unwrap_or_default(),
+            termination_log: database_replicator::failure::termination_log_path(
+                termination_log.as_deref(),
+            ),
+            json: *json,
+        }),
+        _ => None,
+    };
+    let result = database_replicator::events::scope(sink, async move {
+    let mut command = cli.command;
+    resolve_connection_refs(&mut command).await?;
+    if let Some(source) = command_source(&command) {
+        database_replicator::postgres::iam::init(cli.source_auth, source)?;
+    }
+    if cli.download_pg_tools {
+        database_replicator::pg_tools::ensure(command_source(&command)).await?;
+    }
+    match command {
         Commands::Validate {
             source,
             target,
@@ -269,7 +799,11 @@ async fn main() -> anyhow::Result<()> {
             exclude_databases,
             include_tables,
             exclude_tables,
+            include_schemas,
+            exclude_schemas,
             no_interactive,
+            fix_replica_identity,
+            mode,
         } => {
             let state = database_replicator::state::load()?;
             let target = target.or(state.target_url).ok_or_else(|| {
@@ -282,21 +816,37 @@ async fn main() -> anyhow::Result<()> {
             let is_postgres_source =
                 matches!(source_type, database_replicator::SourceType::PostgreSQL);
 
+            let cli_filter = database_replicator::filters::ReplicationFilter::new(
+                include_databases,
+                exclude_databases,
+                include_tables,
+                exclude_tables,
+            )?
+            .with_schemas(include_schemas, exclude_schemas)?;
             let filter = if !no_interactive && is_postgres_source {
-                // Interactive mode (default) - prompt user to select databases and tables
+                // Interactive mode (default) - prompt user to select databases and tables,
+                // offering only those the filter flags allow
                 let (filter, rules) =
-                    database_replicator::interactive::select_databases_and_tables(&source).await?;
+                    database_replicator::interactive::select_databases_and_tables(
+                        &source,
+                        &cli_filter,
+                    )
+                    .await?;
                 filter.with_table_rules(rules)
             } else {
                 // CLI mode - use provided filter arguments
-                database_replicator::filters::ReplicationFilter::new(
-                    include_databases,
-                    exclude_databases,
-                    include_tables,
-                    exclude_tables,
-                )?
+                cli_filter
             };
-            commands::validate(&source, &target, filter).await
+            commands::validate_with_options(
+                &source,
+                &target,
+                filter,
+                commands::ValidateOptions {
+                    fix_replica_identity,
+                    mode,
+                },
+            )
+            .await
         }
         Commands::Init {
             source,
@@ -306,17 +856,95 @@ async fn main() -> anyhow::Result<()> {
             exclude_databases,
             include_tables,
             exclude_tables,
+            include_schemas,
+            exclude_schemas,
             no_interactive,
             table_rules,
             drop_existing,
             sync: _, // sync is the default behavior, no_sync overrides it
             no_sync,
             no_resume,
+            skip_quota_check,
+            new_branch,
+            promote,
             seren,
             local,
             seren_api,
             job_timeout,
+            experimental_targets,
+            include_roles,
+            include_grants,
+            generate_role_passwords,
+            skip_policies,
+            include_policies: _,
+            skip_triggers,
+            audit_schema,
+            source_replica,
+            include_unlogged,
+            refresh_matviews,
+            reindex_after_restore,
+            map_tablespace,
+            defer_indexes,
+            index_build_jobs,
+            skip_analyze,
+            vacuum_after_load,
+            analyze_jobs,
+            restore_jobs,
+            stream_copy,
+            differential,
+            drift_threshold,
+            work_dir,
+            json,
+            exit_code_map: _,
+            termination_log: _,
         } => {
+            let mut init_options = commands::InitOptions {
+                access: database_replicator::migration::roles::AccessOptions {
+                    include_roles,
+                    include_grants,
+                    generate_passwords: generate_role_passwords,
+                },
+                skip_policies,
+                skip_triggers,
+                audit_schema,
+                source_replicas: source_replica,
+                include_unlogged,
+                refresh_matviews,
+                reindex_after_restore,
+                tablespace_map: database_replicator::migration::TablespaceMap::from_cli(
+                    &map_tablespace,
+                )?,
+                defer_indexes,
+                index_build_jobs,
+                skip_analyze,
+                vacuum_after_load,
+                analyze_jobs,
+                restore_jobs,
+                stream_copy,
+                differential,
+                drift_threshold,
+                work_dir,
+                json,
+                seren_project_id: None,
+                skip_quota_check,
+            };
+            // Options the remote service does not support
+            let local_only = init_options.access.is_enabled()
+                || skip_policies
+                || skip_triggers
+                || audit_schema
+                || !init_options.source_replicas.is_empty()
+                || include_unlogged
+                || refresh_matviews
+                || reindex_after_restore
+                || !init_options.tablespace_map.is_empty()
+                || vacuum_after_load
+                || stream_copy
+                || differential
+                || init_options.work_dir.is_some()
+                || json
+                || include_schemas.is_some()
+                || exclude_schemas.is_some();
             let mut state = database_replicator::state::load()?;
             let mut target = target.or(state.target_url);
             let mut seren_target_state: Option<database_replicator::serendb::TargetState> = None;
@@ -329,6 +957,7 @@ async fn main() -> anyhow::Result<()> {
                 target = Some(conn_str);
                 // Save target state for use by subsequent commands (sync, status, etc.)
                 database_replicator::serendb::save_target_state(&target_state)?;
+                init_options.seren_project_id = Some(target_state.project_id.clone());
                 seren_target_state = Some(target_state);
             }
 
@@ -341,37 +970,84 @@ async fn main() -> anyhow::Result<()> {
                 }
             }
 
-            let target = target.ok_or_else(|| {
+            let mut target = target.ok_or_else(|| {
                 anyhow::anyhow!("Target database URL not provided. Use `--target` to specify a target database, or remove `--local` to use interactive SerenDB project selection.")
             })?;
 
+            // Replicate into a fresh branch, so the target's branch stays as it was
+            // until the copy has verified
+            let mut run_branch = None;
+            if let Some(name) = &new_branch {
+                let api_key = database_replicator::interactive::get_api_key()?;
+                let client = database_replicator::serendb::ConsoleClient::new(None, api_key);
+                let branch = database_replicator::serendb::create_run_branch(
+                    &client,
+                    name,
+                    &target,
+                    seren_target_state.as_ref(),
+                    &source,
+                )
+                .await?;
+                database_replicator::serendb::save_target_state(&branch.state)?;
+                target = branch.connection_string.clone();
+                init_options.seren_project_id = Some(branch.state.project_id.clone());
+                seren_target_state = Some(branch.state.clone());
+                run_branch = Some((client, branch));
+            }
+            // The new branch holds copies of the parent's databases, which the run replaces
+            let drop_existing = drop_existing || run_branch.is_some();
+
+            let target_type = database_replicator::detect_target_type(&target)?;
+            database_replicator::ensure_target_allowed(&target_type, experimental_targets)?;
+            if target_type.is_experimental() {
+                let filter = database_replicator::filters::ReplicationFilter::new(
+                    include_databases,
+                    exclude_databases,
+                    include_tables,
+                    exclude_tables,
+                )?
+                .with_schemas(include_schemas, exclude_schemas)?;
+                experimental_target_init(&target_type, &source, &target, &filter).await?;
+                state.target_url = Some(target);
+                database_replicator::state::save(&state)?;
+                return Ok(());
+            }
+
             // Check if CLI filter flags were provided (skip interactive if so)
             let has_cli_filters = include_databases.is_some()
                 || exclude_databases.is_some()
                 || include_tables.is_some()
-                || exclude_tables.is_some();
+                || exclude_tables.is_some()
+                || include_schemas.is_some()
+                || exclude_schemas.is_some();
 
             // Detect source type early to determine if interactive mode is supported
             let source_type = database_replicator::detect_source_type(&source)
                 .context("Failed to detect source database type")?;
             let is_postgres_source =
                 matches!(source_type, database_replicator::SourceType::PostgreSQL);
+            let supports_interactive =
+                !matches!(source_type, database_replicator::SourceType::MongoDB);
 
             // Interactive mode is default unless:
             // - --no-interactive flag is set
             // - --yes flag is set (implies automation)
             // - CLI filter flags are provided
-            // - Source is not PostgreSQL (interactive mode only works with PostgreSQL sources)
+            // - Source is MongoDB (the wizard lists PostgreSQL, MySQL, and SQLite tables)
             // Run this BEFORE remote execution check so interactive mode works for both local and remote
             let (
                 final_include_databases,
                 final_exclude_databases,
                 final_include_tables,
                 final_exclude_tables,
-            ) = if !no_interactive && !yes && !has_cli_filters && is_postgres_source {
+            ) = if !no_interactive && !yes && !has_cli_filters && supports_interactive {
                 // Interactive mode (default) - prompt user to select databases and tables
                 let (filter, _rules) =
-                    database_replicator::interactive::select_databases_and_tables(&source).await?;
+                    database_replicator::interactive::select_databases_and_tables(
+                        &source,
+                        &database_replicator::filters::ReplicationFilter::empty(),
+                    )
+                    .await?;
 
                 // Extract filter values to pass to init_remote or local init
                 (
@@ -402,11 +1078,45 @@ async fn main() -> anyhow::Result<()> {
             } else if !is_postgres_source {
                 // Non-PostgreSQL sources require local execution - remote can't access local files
                 false
+            } else if cli.source_auth != SourceAuth::Password {
+                // IAM tokens are generated with this machine's AWS credentials
+                false
             } else {
                 // Auto-detect: SerenDB targets default to remote execution
                 database_replicator::utils::is_serendb_target(&target)
             };
 
+            if use_remote && cli.source_auth != SourceAuth::Password {
+                anyhow::bail!(
+                    "IAM source authentication is only supported with local execution. \
+                     Add --local to run init on this machine."
+                );
+            }
+
+            if use_remote && local_only {
+                anyhow::bail!(
+                    "--include-roles, --include-grants, --skip-triggers, --source-replica, and \
+                     schema filters are only supported with local execution. Add --local to run \
+                     init on this machine."
+                );
+            }
+
+            // The filter the run used, to verify the new branch with
+            let verify_filter = match run_branch {
+                Some(_) => Some(
+                    database_replicator::filters::ReplicationFilter::new(
+                        final_include_databases.clone(),
+                        final_exclude_databases.clone(),
+                        final_include_tables.clone(),
+                        final_exclude_tables.clone(),
+                    )?
+                    .with_schemas(include_schemas.clone(), exclude_schemas.clone())?
+                    .with_table_rules(build_table_rules(&table_rules)?),
+                ),
+                None => None,
+            };
+            let verify_source = source.clone();
+
             if use_remote {
                 tracing::info!("Using SerenAI cloud execution");
                 init_remote(
@@ -418,8 +1128,12 @@ async fn main() -> anyhow::Result<()> {
                     final_exclude_databases,
                     final_include_tables,
                     final_exclude_tables,
+                    build_table_rules(&table_rules)?,
+                    &init_options,
+                    cli.max_connections_per_host,
                     drop_existing,
                     no_sync,
+                    skip_quota_check,
                     seren_api,
                     job_timeout,
                     cli.log,
@@ -432,20 +1146,22 @@ async fn main() -> anyhow::Result<()> {
                 let fallback_exclude_dbs = final_exclude_databases.clone();
                 let fallback_include_tables = final_include_tables.clone();
                 let fallback_exclude_tables = final_exclude_tables.clone();
+                let fallback_init_options = init_options.clone();
 
                 let filter = database_replicator::filters::ReplicationFilter::new(
                     final_include_databases,
                     final_exclude_databases,
                     final_include_tables,
                     final_exclude_tables,
-                )?;
+                )?
+                .with_schemas(include_schemas, exclude_schemas)?;
                 let table_rule_data = build_table_rules(&table_rules)?;
                 let filter = filter.with_table_rules(table_rule_data);
 
                 let enable_sync = !no_sync; // Invert the flag: by default sync is enabled
 
                 // Run init with pre-flight checks, handle fallback to remote
-                match commands::init(
+                match commands::init_with_options(
                     &source,
                     &target,
                     yes,
@@ -454,10 +1170,21 @@ async fn main() -> anyhow::Result<()> {
                     enable_sync,
                     !no_resume,
                     local, // Pass whether --local was explicit
+                    init_options,
                 )
                 .await
                 {
                     Ok(_) => {}
+                    Err(e)
+                        if e.to_string().contains("PREFLIGHT_FALLBACK_TO_REMOTE") && local_only =>
+                    {
+                        anyhow::bail!(
+                            "Local tool versions are incompatible with the source, and \
+                             --include-roles/--include-grants/--skip-triggers and schema filters \
+                             cannot run remotely. \
+                             Install matching PostgreSQL client tools and retry."
+                        );
+                    }
                     Err(e) if e.to_string().contains("PREFLIGHT_FALLBACK_TO_REMOTE") => {
                         // Auto-fallback to remote execution
                         init_remote(
@@ -469,8 +1196,12 @@ async fn main() -> anyhow::Result<()> {
                             fallback_exclude_dbs,
                             fallback_include_tables,
                             fallback_exclude_tables,
+                            build_table_rules(&table_rules)?,
+                            &fallback_init_options,
+                            cli.max_connections_per_host,
                             drop_existing,
                             no_sync,
+                            skip_quota_check,
                             seren_api,
                             job_timeout,
                             cli.log,
@@ -480,6 +1211,34 @@ async fn main() -> anyhow::Result<()> {
                     Err(e) => return Err(e),
                 }
             }
+            if let Some((client, branch)) = &run_branch {
+                println!();
+                println!(
+                    "Verifying branch '{}' against the source...",
+                    branch.state.branch_name
+                );
+                commands::verify::verify_with_options(
+                    &verify_source,
+                    &target,
+                    verify_filter,
+                    commands::verify::VerifyOptions::default(),
+                )
+                .await
+                .with_context(|| {
+                    format!(
+                        "Branch '{}' did not verify; branch '{}' was left as it was",
+                        branch.state.branch_name, branch.parent_name
+                    )
+                })?;
+                if promote {
+                    database_replicator::serendb::promote_run_branch(client, branch).await?;
+                } else {
+                    println!(
+                        "✓ Branch '{}' verified; promote it in the console, or pass --promote next time",
+                        branch.state.branch_name
+                    );
+                }
+            }
             state.target_url = Some(target);
             database_replicator::state::save(&state)?;
             Ok(())
@@ -491,52 +1250,112 @@ async fn main() -> anyhow::Result<()> {
             exclude_databases,
             include_tables,
             exclude_tables,
+            include_schemas,
+            exclude_schemas,
             no_interactive,
             table_rules,
             force,
+            refresh_publication,
+            auto_add_tables,
+            include_unlogged,
+            refresh_matviews_interval,
             project_id,
             console_api,
+            assume_yes_logical_replication,
             sync_interval,
             reconcile_interval,
             once,
             no_reconcile,
+            purge_aged_out,
+            no_identity_override,
             daemon,
             stop,
             daemon_status,
+            pause,
+            resume,
+            daemon_child,
+            experimental_targets,
+            binlog_server_id,
+            audit_schema,
+            verify_rate,
+            stateless,
         } => {
             // Handle daemon control commands first (don't require source/target)
             if stop {
-                return match database_replicator::daemon::stop_daemon()? {
-                    true => {
-                        println!("Daemon stopped successfully");
-                        Ok(())
-                    }
-                    false => {
-                        println!("No daemon was running");
-                        Ok(())
-                    }
-                };
+                match database_replicator::daemon::stop_daemon()? {
+                    true => println!("Daemon stopped successfully"),
+                    false => println!("No daemon was running"),
+                }
+                // Slots left behind by init or sync keep WAL on the source
+                let dropped = database_replicator::replication::slot::drop_orphaned_slots().await?;
+                if !dropped.is_empty() {
+                    println!(
+                        "Dropped {} orphaned replication slot(s): {}",
+                        dropped.len(),
+                        dropped.join(", ")
+                    );
+                }
+                return Ok(());
             }
 
             if daemon_status {
                 return database_replicator::daemon::print_status();
             }
 
+            if pause {
+                if !database_replicator::daemon::pause()? {
+                    println!("Sync is already paused");
+                    return Ok(());
+                }
+                let status = database_replicator::daemon::check_status()?;
+                match status.pid.filter(|_| status.running) {
+                    Some(pid) => println!(
+                        "Sync paused: daemon (PID: {}) stops before its next table",
+                        pid
+                    ),
+                    _ => println!("Sync paused: no daemon is running; sync will wait when started"),
+                }
+                println!("Run `database-replicator sync --resume` to continue");
+                return Ok(());
+            }
+
+            if resume {
+                match database_replicator::daemon::resume()? {
+                    true => println!("Sync resumed; the next cycle runs at its scheduled time"),
+                    false => println!("Sync was not paused"),
+                }
+                return Ok(());
+            }
+
             // For actual sync, source is required
             let source = source.ok_or_else(|| {
                 anyhow::anyhow!(
                     "Source database URL is required for sync.\n\
                      Use --source to specify a source database.\n\
-                     (Use --stop to stop a running daemon, or --daemon-status to check status)"
+                     (Use --stop to stop a running daemon, --pause/--resume to pause it, \
+                     or --daemon-status to check status)"
                 )
             })?;
+            let verify_rate = verify_rate
+                .as_deref()
+                .map(database_replicator::xmin::verifier::parse_rate)
+                .transpose()?;
+            // Interval flags win over the [sync] section of --config, also on reload
+            let cli_intervals = (sync_interval, reconcile_interval);
+            let file_intervals = match &table_rules.config_path {
+                Some(path) => database_replicator::config::load_sync_settings_from_file(path)?,
+                None => Default::default(),
+            };
+            let sync_interval = sync_interval
+                .or(file_intervals.interval)
+                .unwrap_or(DEFAULT_SYNC_INTERVAL);
+            let reconcile_interval = reconcile_interval
+                .or(file_intervals.reconcile_interval)
+                .unwrap_or(DEFAULT_RECONCILE_INTERVAL);
 
             // Handle daemon child process initialization (Windows)
-            #[cfg(windows)]
-            if database_replicator::daemon::is_daemon_child() {
-                let _log_file = database_replicator::daemon::init_daemon_child()?;
-                // Note: We can't easily re-initialize the global subscriber on Windows,
-                // so we just proceed with existing logging (logs go to parent's console)
+            if daemon_child {
+                database_replicator::daemon::init_daemon_child()?;
                 tracing::info!("Daemon child process started (PID: {})", std::process::id());
             }
 
@@ -546,22 +1365,62 @@ async fn main() -> anyhow::Result<()> {
                 // After daemonize(), we're running in the child process
             }
 
-            let mut app_state = database_replicator::state::load()?;
+            // Stateless sync has no terminal to prompt on and no home directory
+            // to remember the target in
+            let no_interactive = no_interactive || stateless;
+            let mut app_state = if stateless {
+                if target.is_none() {
+                    anyhow::bail!(
+                        "--target (or DATABASE_REPLICATOR_TARGET) is required with --stateless"
+                    );
+                }
+                Default::default()
+            } else {
+                database_replicator::state::load()?
+            };
             let target_candidate = target.or(app_state.target_url.clone());
+
+            // Experimental targets bypass SerenDB resolution and logical replication
+            if let Some(candidate) = target_candidate.as_deref() {
+                let target_type = database_replicator::detect_target_type(candidate)?;
+                database_replicator::ensure_target_allowed(&target_type, experimental_targets)?;
+                if target_type.is_experimental() {
+                    let filter = database_replicator::filters::ReplicationFilter::new(
+                        include_databases,
+                        exclude_databases,
+                        include_tables,
+                        exclude_tables,
+                    )?
+                    .with_schemas(include_schemas, exclude_schemas)?;
+                    return experimental_target_sync(
+                        &target_type,
+                        &source,
+                        candidate,
+                        &filter,
+                        sync_interval,
+                        once,
+                    )
+                    .await;
+                }
+            }
             let resolved_target = database_replicator::commands::sync::resolve_target_for_sync(
                 target_candidate,
                 global_api_key.clone(),
                 &source,
             )
             .await?;
-            app_state.target_url = Some(resolved_target.clone());
-            database_replicator::state::save(&app_state)?;
+            if !stateless {
+                app_state.target_url = Some(resolved_target.clone());
+                database_replicator::state::save(&app_state)?;
+            }
 
             // Check if CLI filter flags were provided (skip interactive if so)
             let has_cli_filters = include_databases.is_some()
                 || exclude_databases.is_some()
                 || include_tables.is_some()
-                || exclude_tables.is_some();
+                || exclude_tables.is_some()
+                || include_schemas.is_some()
+                || exclude_schemas.is_some();
 
             // Detect source type - interactive mode only works with PostgreSQL
             let source_type = database_replicator::detect_source_type(&source)
@@ -569,11 +1428,19 @@ async fn main() -> anyhow::Result<()> {
             let is_postgres_source =
                 matches!(source_type, database_replicator::SourceType::PostgreSQL);
 
-            let filter = if !no_interactive && !has_cli_filters && is_postgres_source {
+            // Only filters given as flags can be rebuilt when the config is reloaded
+            let (filter, reload_filter) = if !no_interactive
+                && !has_cli_filters
+                && is_postgres_source
+            {
                 // Interactive mode (default) - prompt user to select databases and tables
                 let (filter, rules) =
-                    database_replicator::interactive::select_databases_and_tables(&source).await?;
-                filter.with_table_rules(rules)
+                    database_replicator::interactive::select_databases_and_tables(
+                        &source,
+                        &database_replicator::filters::ReplicationFilter::empty(),
+                    )
+                    .await?;
+                (filter.with_table_rules(rules), None)
             } else {
                 // CLI mode - use provided filter arguments
                 let filter = database_replicator::filters::ReplicationFilter::new(
@@ -581,11 +1448,43 @@ async fn main() -> anyhow::Result<()> {
                     exclude_databases,
                     include_tables,
                     exclude_tables,
-                )?;
+                )?
+                .with_schemas(include_schemas, exclude_schemas)?;
                 let table_rule_data = build_table_rules(&table_rules)?;
-                filter.with_table_rules(table_rule_data)
+                (filter.clone().with_table_rules(table_rule_data), Some(filter))
+            };
+            // --auto-add-tables resolves name patterns again to find new tables
+            let pattern_filter = auto_add_tables.then(|| filter.clone());
+            let filter = if is_postgres_source {
+                // Publications and xmin table lists need concrete names
+                filter.resolve_patterns(&source).await?
+            } else {
+                filter
             };
 
+            // MySQL and MariaDB sources stream their binlog into the JSONB tables init created
+            if source_type == database_replicator::SourceType::MySQL {
+                let (shutdown_tx, shutdown_rx) = tokio::sync::broadcast::channel::<()>(1);
+                if !once {
+                    database_replicator::daemon::spawn_shutdown_handler(shutdown_tx.clone());
+                    database_replicator::daemon::spawn_reload_unsupported("MySQL binlog sync");
+                }
+                let result = commands::sync_mysql::run(
+                    commands::sync_mysql::SyncMysqlOptions {
+                        source,
+                        target: resolved_target,
+                        filter,
+                        server_id: binlog_server_id
+                            .unwrap_or_else(database_replicator::mysql::binlog::default_server_id),
+                        once,
+                    },
+                    shutdown_rx,
+                )
+                .await;
+                drop(shutdown_tx);
+                return result;
+            }
+
             // Get project_id from CLI, saved target state, or discover from target URL
             let mut effective_project_id = project_id.or_else(|| {
                 database_replicator::serendb::load_target_state()
@@ -627,6 +1526,13 @@ async fn main() -> anyhow::Result<()> {
                                     parts.host
                                 );
                             }
+                            Err(e)
+                                if e
+                                    .downcast_ref::<database_replicator::serendb::ConsoleError>()
+                                    .is_some_and(|e| e.is_fatal()) =>
+                            {
+                                return Err(e);
+                            }
                             Err(e) => {
                                 tracing::warn!(
                                     "Failed to discover project from hostname: {}. \
@@ -650,6 +1556,7 @@ async fn main() -> anyhow::Result<()> {
                         project_id,
                         &console_api,
                         &resolved_target,
+                        assume_yes_logical_replication,
                     )
                     .await?;
                 }
@@ -665,11 +1572,47 @@ async fn main() -> anyhow::Result<()> {
                 .unwrap_or_else(|_| "unknown".to_string());
             drop(source_client); // Release connection before sync
 
-            if source_wal_level == "logical" {
+            // Logical replication streams rows untouched into same-named tables, so
+            // transforms, custom table sources, renames, time windows, and retention need xmin polling
+            let requires_xmin_sync = filter.table_rules().requires_xmin_sync();
+            if source_wal_level == "logical" && !requires_xmin_sync {
                 tracing::info!("Source has wal_level=logical (logical replication available)");
                 tracing::info!("Using PostgreSQL logical replication (fastest method)");
 
-                commands::sync(
+                let plan = database_replicator::xmin::SyncDatabase::plan(
+                    &source,
+                    &resolved_target,
+                    &filter,
+                )
+                .await?;
+                let target_db_urls: Vec<String> = plan
+                    .iter()
+                    .map(|database| database.target_url.clone())
+                    .collect();
+                // UNLOGGED tables bypass the WAL, so publications leave them out
+                let unlogged =
+                    database_replicator::xmin::SyncDatabase::unlogged_only(plan, &filter).await?;
+                let unlogged_tables: Vec<String> = unlogged
+                    .iter()
+                    .flat_map(|database| {
+                        let db_name = database.name();
+                        database
+                            .tables
+                            .iter()
+                            .map(move |table| format!("{}.{}", db_name, table))
+                    })
+                    .collect();
+                if !unlogged_tables.is_empty() && !include_unlogged {
+                    tracing::warn!(
+                        "⚠ Not syncing {} UNLOGGED table(s), which logical replication cannot publish: {}",
+                        unlogged_tables.len(),
+                        unlogged_tables.join(", ")
+                    );
+                    tracing::warn!("  Run sync with --include-unlogged to sync them by xmin polling");
+                }
+                let unlogged = if include_unlogged { unlogged } else { Vec::new() };
+
+                commands::sync_with_options(
                     &source,
                     &resolved_target,
                     Some(filter),
@@ -677,54 +1620,138 @@ async fn main() -> anyhow::Result<()> {
                     None,
                     None,
                     force,
+                    commands::SyncOptions {
+                        audit_schema,
+                        refresh_publication,
+                        ..Default::default()
+                    },
                 )
-                .await
+                .await?;
+
+                // Loops that keep sync running until Ctrl+C
+                let keeps_running = pattern_filter.is_some() || refresh_matviews_interval.is_some();
+                let (shutdown_tx, _) = tokio::sync::broadcast::channel::<()>(1);
+                if keeps_running {
+                    database_replicator::daemon::spawn_shutdown_handler(shutdown_tx.clone());
+                }
+                if (keeps_running || !unlogged.is_empty()) && !once {
+                    database_replicator::daemon::spawn_reload_unsupported(
+                        "logical replication sync",
+                    );
+                }
+                let watch = async {
+                    let Some(pattern_filter) = pattern_filter else {
+                        return Ok(());
+                    };
+                    commands::watch_for_new_tables(
+                        &source,
+                        &resolved_target,
+                        &pattern_filter,
+                        None,
+                        None,
+                        std::time::Duration::from_secs(sync_interval),
+                        shutdown_tx.subscribe(),
+                    )
+                    .await
+                };
+                let refresh_matviews = async {
+                    let Some(interval) = refresh_matviews_interval else {
+                        return Ok(());
+                    };
+                    database_replicator::migration::matviews::refresh_periodically(
+                        &target_db_urls,
+                        std::time::Duration::from_secs(interval),
+                        shutdown_tx.subscribe(),
+                    )
+                    .await
+                };
+                let poll_unlogged = async {
+                    if unlogged.is_empty() {
+                        return Ok(());
+                    }
+                    tracing::info!(
+                        "Syncing {} UNLOGGED table(s) by xmin polling: {}",
+                        unlogged_tables.len(),
+                        unlogged_tables.join(", ")
+                    );
+                    xmin_sync(
+                        unlogged,
+                        sync_interval,
+                        reconcile_interval,
+                        database_replicator::utils::calculate_optimal_batch_size(),
+                        None,
+                        stateless,
+                        once,
+                        no_reconcile,
+                        !no_identity_override,
+                        audit_schema,
+                        None,
+                        None,
+                        verify_rate,
+                        purge_aged_out,
+                        None,
+                    )
+                    .await
+                };
+                tokio::try_join!(watch, refresh_matviews, poll_unlogged)?;
+                if keeps_running {
+                    if let Err(e) = database_replicator::daemon::cleanup() {
+                        tracing::warn!("Failed to clean up daemon PID file: {}", e);
+                    }
+                }
+                Ok(())
             } else {
-                tracing::info!(
-                    "Source has wal_level={} (logical replication not available)",
-                    source_wal_level
-                );
+                if requires_xmin_sync {
+                    tracing::info!(
+                        "Column transforms, table sources, renames, time filters, or retention are configured (logical replication cannot apply them)"
+                    );
+                } else {
+                    tracing::info!(
+                        "Source has wal_level={} (logical replication not available)",
+                        source_wal_level
+                    );
+                }
                 tracing::info!("Using xmin-based sync (no source configuration required)");
 
-                // Extract tables from filter for xmin sync
-                // Filter stores "db.table" format, we need just table names for the source db
-                let source_parts = database_replicator::utils::parse_postgres_url(&source)?;
-                let source_db = &source_parts.database;
+                let databases = database_replicator::xmin::SyncDatabase::plan(
+                    &source,
+                    &resolved_target,
+                    &filter,
+                )
+                .await?;
 
-                let tables_to_sync: Option<Vec<String>> = filter.include_tables().map(|tables| {
-                    tables
-                        .iter()
-                        .filter_map(|qualified| {
-                            // Split "db.table" into parts
-                            let parts: Vec<&str> = qualified.splitn(2, '.').collect();
-                            if parts.len() == 2 {
-                                let (db, table) = (parts[0], parts[1]);
-                                // Only include tables from the source database
-                                if db == source_db {
-                                    Some(table.to_string())
-                                } else {
-                                    None
-                                }
-                            } else {
-                                // No dot, treat as plain table name
-                                Some(qualified.clone())
-                            }
-                        })
-                        .collect()
+                // SIGHUP plans the databases again from the flags and the reloaded --config
+                let reload = reload_filter.map(|filter| SyncReload {
+                    source: source.clone(),
+                    target: resolved_target.clone(),
+                    filter,
+                    table_rules: table_rules.clone(),
+                    cli_intervals,
+                    no_reconcile,
                 });
+                if reload.is_none() && !once {
+                    database_replicator::daemon::spawn_reload_unsupported(
+                        "sync with interactively selected tables",
+                    );
+                }
 
                 // Use CLI-provided intervals or defaults
                 xmin_sync(
-                    source,
-                    resolved_target,
-                    "public".to_string(), // Default schema
-                    tables_to_sync,       // Tables from filter
-                    sync_interval,        // CLI: --sync-interval (default 60s)
-                    reconcile_interval,   // CLI: --reconcile-interval (default 3600s)
+                    databases,          // Databases, tables, and schemas from the filter
+                    sync_interval,      // CLI: --sync-interval, or [sync] interval in --config
+                    reconcile_interval, // CLI: --reconcile-interval, or [sync] in --config
                     database_replicator::utils::calculate_optimal_batch_size(), // Auto-detect based on available memory
-                    None,         // State file: use default
-                    once,         // CLI: --once (run single cycle)
-                    no_reconcile, // CLI: --no-reconcile (disable delete detection)
+                    None,                  // State file: use default
+                    stateless,             // CLI: --stateless (state kept in the target)
+                    once,                  // CLI: --once (run single cycle)
+                    no_reconcile,          // CLI: --no-reconcile (disable delete detection)
+                    !no_identity_override, // CLI: --no-identity-override
+                    audit_schema,          // CLI: --audit-schema
+                    pattern_filter,        // CLI: --auto-add-tables
+                    refresh_matviews_interval, // CLI: --refresh-matviews-interval
+                    verify_rate,               // CLI: --verify-rate
+                    purge_aged_out,            // CLI: --purge-aged-out
+                    reload,                    // Rebuilds databases and intervals on SIGHUP
                 )
                 .await
             }
@@ -734,6 +1761,10 @@ async fn main() -> anyhow::Result<()> {
             target,
             include_databases,
             exclude_databases,
+            watch,
+            max_lag_seconds,
+            notify_webhook,
+            json,
         } => {
             let state = database_replicator::state::load()?;
             let target = target.or(state.target_url).ok_or_else(|| {
@@ -746,7 +1777,14 @@ async fn main() -> anyhow::Result<()> {
                 None,
                 None,
             )?;
-            commands::status(&source, &target, Some(filter)).await
+            let options = commands::StatusOptions {
+                watch_interval: watch.map(std::time::Duration::from_secs),
+                max_lag: max_lag_seconds.map(std::time::Duration::from_secs),
+                notify_webhook,
+                json,
+                state_path: None,
+            };
+            commands::status_with_options(&source, &target, Some(filter), options).await
         }
         Commands::Verify {
             source,
@@ -755,6 +1793,12 @@ async fn main() -> anyhow::Result<()> {
             exclude_databases,
             include_tables,
             exclude_tables,
+            include_schemas,
+            exclude_schemas,
+            resume,
+            tables_changed_since,
+            skip_structure,
+            config_path,
         } => {
             let state = database_replicator::state::load()?;
             let target = target.or(state.target_url).ok_or_else(|| {
@@ -766,35 +1810,343 @@ async fn main() -> anyhow::Result<()> {
                 exclude_databases,
                 include_tables,
                 exclude_tables,
-            )?;
-            commands::verify(&source, &target, Some(filter)).await
+            )?
+            .with_schemas(include_schemas, exclude_schemas)?;
+            let filter = match &config_path {
+                Some(path) => filter.with_table_rules(
+                    database_replicator::config::load_table_rules_from_file(path)?,
+                ),
+                None => filter,
+            };
+            let options = commands::verify::VerifyOptions {
+                resume,
+                tables_changed_since: tables_changed_since
+                    .map(|value| {
+                        commands::verify::parse_changed_since(&value, chrono::Utc::now())
+                    })
+                    .transpose()?,
+                state_path: None,
+                structure: if skip_structure {
+                    None
+                } else {
+                    Some(match &config_path {
+                        Some(path) => database_replicator::config::load_parity_rules_from_file(
+                            path,
+                        )?,
+                        None => Default::default(),
+                    })
+                },
+            };
+            commands::verify::verify_with_options(&source, &target, Some(filter), options).await
+        }
+        Commands::Cutover {
+            source,
+            target,
+            include_databases,
+            exclude_databases,
+            include_tables,
+            exclude_tables,
+            include_schemas,
+            exclude_schemas,
+            read_only_source,
+            drain_timeout,
+            yes,
+        } => {
+            let state = database_replicator::state::load()?;
+            let target = target.or(state.target_url).ok_or_else(|| {
+                anyhow::anyhow!("Target database URL not provided and not set in state. Use `--target` or `database-replicator target set`.")
+            })?;
+
+            let filter = database_replicator::filters::ReplicationFilter::new(
+                include_databases,
+                exclude_databases,
+                include_tables,
+                exclude_tables,
+            )?
+            .with_schemas(include_schemas, exclude_schemas)?;
+            let options = commands::CutoverOptions {
+                read_only_source,
+                drain_timeout: std::time::Duration::from_secs(drain_timeout),
+                skip_confirmation: yes,
+            };
+            commands::cutover(&source, &target, filter, options).await
         }
         #[cfg(feature = "sqlite-sync")]
         Commands::SyncSqlite {
             target,
+            mirror_targets,
             watcher_endpoint,
             token_file,
+            watcher_database,
             incremental_mode,
+            latest_deletes,
+            compact_history,
             batch_size,
+            max_in_flight,
+            flush_interval,
+            transaction_size,
+            include_tables,
+            exclude_tables,
+            config_path,
         } => {
+            // SQLite tables live under the `main` database; allow bare names
+            let qualify = |tables: Option<Vec<String>>| {
+                tables.map(|tables| {
+                    tables
+                        .into_iter()
+                        .map(|table| {
+                            if table.contains('.') {
+                                table
+                            } else {
+                                format!("{}.{}", database_replicator::sqlite::SQLITE_DATABASE_NAME, table)
+                            }
+                        })
+                        .collect::<Vec<_>>()
+                })
+            };
+            let mut filter = database_replicator::filters::ReplicationFilter::new(
+                None,
+                None,
+                qualify(include_tables),
+                qualify(exclude_tables),
+            )?;
+            if let Some(path) = &config_path {
+                filter = filter.with_table_rules(
+                    database_replicator::config::load_table_rules_from_file(path)?,
+                );
+            }
             commands::sync_sqlite::run(commands::sync_sqlite::SyncSqliteOptions {
                 target,
+                mirror_targets,
                 watcher_endpoint,
                 token_file,
+                watcher_database: watcher_database.unwrap_or_default(),
                 incremental_mode,
                 batch_size,
+                max_in_flight,
+                flush_interval,
+                transaction_size,
+                filter,
+                latest_deletes,
+                compact_history,
             })
             .await
         }
+        Commands::Doctor { args } => commands::doctor(args).await,
         Commands::Target { args } => commands::target(args).await,
+        Commands::Serve { args } => commands::serve(args).await,
+        Commands::History { args } => commands::history(args).await,
+        Commands::Slots { args } => commands::slots(args).await,
+        Commands::Topology { args } => commands::topology(args).await,
+        Commands::Jobs { args } => commands::jobs(args).await,
+        Commands::Backup { args } => commands::backup(args).await,
+        Commands::Restore { args } => commands::restore(args).await,
+    }
+    })
+    .await
+    // Errors often quote the URL that failed; never print its password
+    .map_err(database_replicator::redact::redact_error);
+
+    if let Some(recorder) = recorder {
+        recorder.finish(&result);
+    }
+    if let Err(e) = &result {
+        if database_replicator::daemon::is_daemon_child() {
+            database_replicator::daemon::report_event(
+                database_replicator::daemon::DaemonEvent::Failed,
+                &format!("Sync daemon failed: {:#}", e),
+            );
+        }
+    }
+    match init_report {
+        Some(report) => report.finish(result),
+        None => result.or_else(|e| {
+            print_error(&e);
+            std::process::exit(1)
+        }),
+    }
+}
+
+/// How `init` reports its outcome, from `--exit-code-map`, `--termination-log`, and `--json`
+struct InitReport {
+    exit_codes: database_replicator::failure::ExitCodeMap,
+    termination_log: Option<PathBuf>,
+    json: bool,
+}
+
+impl InitReport {
+    /// Write the run summary and, on failure, exit with the code for the kind of error
+    fn finish(self, result: anyhow::Result<()>) -> anyhow::Result<()> {
+        use database_replicator::failure::{write_termination_log, RunSummary};
+
+        let summary = match &result {
+            Ok(()) => RunSummary::succeeded("init"),
+            Err(e) => RunSummary::failed("init", e, &self.exit_codes),
+        };
+        if let Some(path) = &self.termination_log {
+            if let Err(e) = write_termination_log(path, &summary) {
+                tracing::warn!("{:#}", e);
+            }
+        }
+        let Err(e) = result else {
+            return Ok(());
+        };
+
+        if self.json {
+            match summary.to_json() {
+                Ok(json) => println!("{}", json),
+                Err(e) => tracing::warn!("{:#}", e),
+            }
+        }
+        if let Some(kind) = summary.kind {
+            tracing::error!(
+                "Init failed with a {} error ({}); exiting with code {}",
+                kind.as_str(),
+                if summary.retryable {
+                    "retryable"
+                } else {
+                    "not retryable"
+                },
+                summary.exit_code
+            );
+        }
+        print_error(&e);
+        std::process::exit(summary.exit_code)
+    }
+}
+
+/// Print an error the way returning it from main does, followed by its
+/// code, hint, and documentation link when it is a known failure
+fn print_error(e: &anyhow::Error) {
+    eprintln!("Error: {:?}", e);
+    if let Some(hint) = database_replicator::errors::render(e) {
+        eprintln!("\n{}", hint);
+    }
+}
+
+/// Replace `secret://` references in `--source` and `--target`, and a
+/// `cloudsql://` source, with the connection strings they name
+async fn resolve_connection_refs(command: &mut Commands) -> anyhow::Result<()> {
+    use database_replicator::postgres::cloudsql;
+    use database_replicator::secrets::{is_secret_ref, SecretResolver};
+
+    let (source, target) = match command {
+        Commands::Validate { source, target, .. }
+        | Commands::Init { source, target, .. }
+        | Commands::Status { source, target, .. }
+        | Commands::Verify { source, target, .. }
+        | Commands::Cutover { source, target, .. } => (Some(source), target.as_mut()),
+        Commands::Sync { source, target, .. } => (source.as_mut(), target.as_mut()),
+        Commands::Doctor { args } => (args.source.as_mut(), args.target.as_mut()),
+        Commands::Slots { args } => (args.source.as_mut(), None),
+        Commands::Topology { args } => (None, args.target.as_mut()),
+        Commands::Backup { args } => (None, args.target.as_mut()),
+        Commands::Restore { args } => (None, args.target.as_mut()),
+        #[cfg(feature = "sqlite-sync")]
+        Commands::SyncSqlite { target, .. } => (None, Some(target)),
+        _ => return Ok(()),
+    };
+    let resolver = SecretResolver::default();
+    for (flag, value) in [("--source", source), ("--target", target)] {
+        let Some(value) = value else { continue };
+        if is_secret_ref(value) {
+            *value = resolver
+                .resolve(value)
+                .await
+                .with_context(|| format!("Failed to resolve {}", flag))?;
+        }
+        if flag == "--source" {
+            *value = cloudsql::resolve(value)
+                .await
+                .context("Failed to resolve the Cloud SQL source")?;
+        }
+    }
+    if let Commands::Init { source_replica, .. } = command {
+        for value in source_replica {
+            if is_secret_ref(value) {
+                *value = resolver
+                    .resolve(value)
+                    .await
+                    .context("Failed to resolve --source-replica")?;
+            }
+            *value = cloudsql::resolve(value)
+                .await
+                .context("Failed to resolve the Cloud SQL source replica")?;
+        }
+    }
+    #[cfg(feature = "sqlite-sync")]
+    if let Commands::SyncSqlite { mirror_targets, .. } = command {
+        for value in mirror_targets {
+            if is_secret_ref(value) {
+                *value = resolver
+                    .resolve(value)
+                    .await
+                    .context("Failed to resolve --mirror-target")?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The `--source` of a command that connects to a source database
+fn command_source(command: &Commands) -> Option<&str> {
+    match command {
+        Commands::Validate { source, .. }
+        | Commands::Init { source, .. }
+        | Commands::Status { source, .. }
+        | Commands::Verify { source, .. }
+        | Commands::Cutover { source, .. } => Some(source),
+        Commands::Sync { source, .. } => source.as_deref(),
+        Commands::Doctor { args } => args.source.as_deref(),
+        _ => None,
     }
 }
 
+/// The history entry for a command that copies or checks data
+fn history_run(command: &Commands) -> Option<database_replicator::history::NewRun> {
+    use database_replicator::history::{redact_args, NewRun};
+    use database_replicator::redact::redact_url;
+
+    let (name, source, target) = match command {
+        Commands::Init { source, target, .. } => ("init", Some(source), target),
+        Commands::Sync {
+            source,
+            target,
+            stop,
+            daemon_status,
+            pause,
+            resume,
+            daemon,
+            stateless,
+            ..
+        } => {
+            // Stateless sync keeps no local files, run history included
+            if *stop || *daemon_status || *pause || *resume || *stateless {
+                return None;
+            }
+            // The detached child process records the run instead
+            if cfg!(windows) && *daemon && !database_replicator::daemon::is_daemon_child() {
+                return None;
+            }
+            ("sync", source.as_ref(), target)
+        }
+        Commands::Verify { source, target, .. } => ("verify", Some(source), target),
+        _ => return None,
+    };
+    Some(NewRun {
+        command: name.to_string(),
+        args: redact_args(std::env::args().skip(1)),
+        source: source.map(|url| redact_url(url)),
+        target: target.as_deref().map(redact_url),
+    })
+}
+
 /// Check if logical replication is enabled on SerenDB project and offer to enable it
 async fn check_and_enable_logical_replication(
     project_id: &str,
     console_api: &str,
     target_url: &str,
+    assume_yes: bool,
 ) -> anyhow::Result<()> {
     use database_replicator::serendb::ConsoleClient;
     use dialoguer::{theme::ColorfulTheme, Confirm};
@@ -837,7 +2189,7 @@ async fn check_and_enable_logical_replication(
         // Fall through to wait for wal_level to become 'logical'
         println!();
         println!("⏳ Waiting for endpoint to restart with wal_level=logical...");
-        wait_for_wal_level_logical(target_url).await?;
+        wait_for_wal_level_logical(&client, project_id, target_url, assume_yes).await?;
         return Ok(());
     }
 
@@ -860,10 +2212,20 @@ async fn check_and_enable_logical_replication(
     println!("   • Once enabled, logical replication CANNOT be disabled");
     println!();
 
-    let confirm = Confirm::with_theme(&ColorfulTheme::default())
-        .with_prompt("Enable logical replication for this project?")
-        .default(true)
-        .interact()?;
+    let confirm = if assume_yes {
+        tracing::info!("Enabling logical replication (--assume-yes-logical-replication)");
+        true
+    } else {
+        database_replicator::interactive::ensure_interactive(
+            "confirmation to enable logical replication",
+            "Pass --assume-yes-logical-replication (or set \
+             DATABASE_REPLICATOR_ASSUME_YES_LOGICAL_REPLICATION=1) to enable it without prompting.",
+        )?;
+        Confirm::with_theme(&ColorfulTheme::default())
+            .with_prompt("Enable logical replication for this project?")
+            .default(true)
+            .interact()?
+    };
 
     if !confirm {
         anyhow::bail!(
@@ -885,7 +2247,7 @@ async fn check_and_enable_logical_replication(
         println!();
         println!("⏳ Waiting for endpoint to restart with wal_level=logical...");
 
-        wait_for_wal_level_logical(target_url).await?;
+        wait_for_wal_level_logical(&client, project_id, target_url, assume_yes).await?;
     } else {
         anyhow::bail!(
             "Failed to enable logical replication. The API call succeeded but the setting was not updated.\n\
@@ -898,8 +2260,97 @@ async fn check_and_enable_logical_replication(
     Ok(())
 }
 
+/// Wait for the endpoint to come back with wal_level=logical
+///
+/// When the endpoint has not restarted within a minute, offer to restart it
+/// through the Console API and wait another minute before giving up.
+async fn wait_for_wal_level_logical(
+    client: &database_replicator::serendb::ConsoleClient,
+    project_id: &str,
+    target_url: &str,
+    assume_yes: bool,
+) -> anyhow::Result<()> {
+    if poll_wal_level_logical(target_url).await {
+        return Ok(());
+    }
+    println!();
+    println!();
+    println!("⚠️  The endpoint still has not picked up wal_level=logical.");
+    if restart_target_endpoint(client, project_id, target_url, assume_yes).await?
+        && poll_wal_level_logical(target_url).await
+    {
+        return Ok(());
+    }
+
+    println!();
+    println!();
+    println!("⚠️  Timed out waiting for wal_level to become 'logical'.");
+    println!();
+    println!("The SerenDB endpoint may need to be manually restarted:");
+    println!("  1. Go to https://console.serendb.com");
+    println!("  2. Navigate to your project's Compute endpoints");
+    println!("  3. Click 'Restart' on the endpoint");
+    println!("  4. Wait for the endpoint to become available");
+    println!("  5. Re-run this command");
+    println!();
+    anyhow::bail!(
+        "Endpoint wal_level is still 'replica' after enabling logical replication. \
+         The endpoint may need to be manually restarted via the SerenDB console."
+    )
+}
+
+/// Restart the endpoint serving `target_url` after confirmation
+///
+/// Returns false when the endpoint cannot be found or the user declines.
+async fn restart_target_endpoint(
+    client: &database_replicator::serendb::ConsoleClient,
+    project_id: &str,
+    target_url: &str,
+    assume_yes: bool,
+) -> anyhow::Result<bool> {
+    use dialoguer::{theme::ColorfulTheme, Confirm};
+
+    let host = database_replicator::utils::parse_postgres_url(target_url)?.host;
+    let Some(endpoint) = client.find_endpoint_by_hostname(project_id, &host).await? else {
+        println!(
+            "⚠️  Could not find the endpoint for {} in project {}",
+            host, project_id
+        );
+        return Ok(false);
+    };
+
+    let confirm = if assume_yes {
+        tracing::info!(
+            "Restarting endpoint {} (--assume-yes-logical-replication)",
+            endpoint.id
+        );
+        true
+    } else {
+        database_replicator::interactive::ensure_interactive(
+            "confirmation to restart the endpoint",
+            "Pass --assume-yes-logical-replication (or set \
+             DATABASE_REPLICATOR_ASSUME_YES_LOGICAL_REPLICATION=1) to restart it without prompting.",
+        )?;
+        Confirm::with_theme(&ColorfulTheme::default())
+            .with_prompt(format!(
+                "Restart endpoint {} now? Open connections to it will drop",
+                endpoint.id
+            ))
+            .default(true)
+            .interact()?
+    };
+    if !confirm {
+        return Ok(false);
+    }
+
+    client.restart_endpoint(project_id, &endpoint.id).await?;
+    println!("✓ Restart requested for endpoint {}", endpoint.id);
+    println!("⏳ Waiting for endpoint to restart with wal_level=logical...");
+    Ok(true)
+}
+
 /// Poll the database until wal_level becomes 'logical' (up to 60 seconds)
-async fn wait_for_wal_level_logical(target_url: &str) -> anyhow::Result<()> {
+async fn poll_wal_level_logical(target_url: &str) -> bool {
     let max_attempts = 12;
     let poll_interval = tokio::time::Duration::from_secs(5);
 
@@ -916,7 +2367,7 @@ async fn wait_for_wal_level_logical(target_url: &str) -> anyhow::Result<()> {
                     Ok(level) if level == "logical" => {
                         println!();
                         tracing::info!("✓ Endpoint is ready with wal_level=logical");
-                        return Ok(());
+                        return true;
                     }
                     Ok(level) => {
                         print!(
@@ -944,21 +2395,7 @@ async fn wait_for_wal_level_logical(target_url: &str) -> anyhow::Result<()> {
         }
     }
 
-    println!();
-    println!();
-    println!("⚠️  Timed out waiting for wal_level to become 'logical'.");
-    println!();
-    println!("The SerenDB endpoint may need to be manually restarted:");
-    println!("  1. Go to https://console.serendb.com");
-    println!("  2. Navigate to your project's Compute endpoints");
-    println!("  3. Click 'Restart' on the endpoint");
-    println!("  4. Wait for the endpoint to become available");
-    println!("  5. Re-run this command");
-    println!();
-    anyhow::bail!(
-        "Endpoint wal_level is still 'replica' after enabling logical replication. \
-         The endpoint may need to be manually restarted via the SerenDB console."
-    )
+    false
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -966,22 +2403,33 @@ async fn init_remote(
     source: String,
     target: String,
     target_state: Option<database_replicator::serendb::TargetState>,
-    _yes: bool,
+    yes: bool,
     include_databases: Option<Vec<String>>,
     exclude_databases: Option<Vec<String>>,
     include_tables: Option<Vec<String>>,
     exclude_tables: Option<Vec<String>>,
+    table_rules: database_replicator::table_rules::TableRules,
+    init_options: &commands::InitOptions,
+    max_connections_per_host: usize,
     drop_existing: bool,
     no_sync: bool,
+    skip_quota_check: bool,
     seren_api: String,
     job_timeout: u64,
     log_level: String,
 ) -> anyhow::Result<()> {
     use database_replicator::migration;
     use database_replicator::postgres;
-    use database_replicator::remote::{FilterSpec, JobSpec, RemoteClient};
+    use database_replicator::remote::{FilterSpec, JobSpec, RemoteClient, SCHEMA_VERSION};
     use std::collections::HashMap;
 
+    // Checked before anything is submitted, so unsupported rules fail fast
+    let table_rules = if table_rules.is_empty() {
+        None
+    } else {
+        Some(table_rules.to_spec()?)
+    };
+
     println!("🌐 SerenAI cloud execution enabled");
     println!("API endpoint: {}", seren_api);
 
@@ -1070,6 +2518,17 @@ async fn init_remote(
                 "Total estimated size: {}",
                 migration::format_bytes(total_bytes)
             );
+            if !skip_quota_check && database_replicator::utils::is_serendb_target(&target) {
+                let names: Vec<String> = databases.iter().map(|db| db.name.clone()).collect();
+                database_replicator::serendb::check_storage_quota(
+                    &target,
+                    target_project_id.as_deref(),
+                    total_bytes,
+                    &names,
+                    yes,
+                )
+                .await?;
+            }
             total_bytes
         }
     };
@@ -1110,40 +2569,77 @@ async fn init_remote(
         "job_timeout_seconds".to_string(),
         serde_json::Value::Number(serde_json::Number::from(job_timeout as i64)),
     );
+    // Parallelism, as the local init flags of the same names set it
+    options.insert(
+        "max_connections_per_host".to_string(),
+        serde_json::Value::from(max_connections_per_host),
+    );
+    if init_options.defer_indexes {
+        options.insert("defer_indexes".to_string(), serde_json::Value::Bool(true));
+    }
+    for (name, jobs) in [
+        ("index_build_jobs", init_options.index_build_jobs),
+        ("analyze_jobs", init_options.analyze_jobs),
+        ("restore_jobs", init_options.restore_jobs),
+    ] {
+        if let Some(jobs) = jobs {
+            options.insert(name.to_string(), serde_json::Value::from(jobs));
+        }
+    }
     // Note: "yes" is client-side only, not sent to server
 
     let job_spec = match connection_string_mode {
         SerenTargetMode::Project => JobSpec {
-            version: "1.0".to_string(),
+            version: SCHEMA_VERSION.to_string(),
             command: "init".to_string(),
-            source_url: source,
-            target_url: resolved_target_url.clone(),
+            source_url: source.into(),
+            target_url: resolved_target_url.clone().map(Into::into),
             target_project_id,
             target_branch_id,
             target_databases,
-            seren_api_key: Some(api_key.clone()),
+            seren_api_key: Some(api_key.clone().into()),
             filter,
+            table_rules: table_rules.clone(),
             options,
+            schedule: None,
+            sealed: None,
+            artifact_checksums: None,
         },
         SerenTargetMode::Url => JobSpec {
-            version: "1.0".to_string(),
+            version: SCHEMA_VERSION.to_string(),
             command: "init".to_string(),
-            source_url: source,
+            source_url: source.into(),
             target_url: Some(
                 resolved_target_url
-                    .expect("Seren target URL must exist when using connection string mode"),
+                    .expect("Seren target URL must exist when using connection string mode")
+                    .into(),
             ),
             target_project_id: None,
             target_branch_id: None,
             target_databases: None,
             seren_api_key: None,
             filter,
+            table_rules,
             options,
+            schedule: None,
+            sealed: None,
+            artifact_checksums: None,
         },
     };
 
-    // Submit job
     let client = RemoteClient::new(seren_api, Some(remote_api_key))?;
+
+    // Show what the job will run on and cost before anything is billed
+    let estimate = match client.estimate_job(&job_spec).await? {
+        Some(estimate) => estimate,
+        None => {
+            tracing::debug!("Remote service has no estimate endpoint; estimating locally");
+            database_replicator::remote::JobEstimate::from_size(estimated_size_bytes)
+        }
+    };
+    database_replicator::remote::estimate::confirm_estimate(&estimate, estimated_size_bytes, yes)?;
+
+    // Submit job
     println!("Submitting replication job...");
     tracing::debug!("Job spec: {:?}", job_spec);
 
@@ -1181,6 +2677,12 @@ async fn init_remote(
     match final_status.status.as_str() {
         "completed" => {
             println!("\n✓ Replication completed successfully");
+            match database_replicator::remote::last_job::save(&job_spec) {
+                Ok(()) => println!(
+                    "  Re-run it on a schedule with: database-replicator jobs schedule --cron \"0 3 * * *\""
+                ),
+                Err(e) => tracing::warn!("⚠ Failed to save the job spec for scheduling: {}", e),
+            }
             Ok(())
         }
         "failed" => {
@@ -1272,153 +2774,367 @@ fn build_table_rules(
         rules.merge(from_file);
     }
     rules.apply_schema_only_cli(&args.schema_only_tables)?;
+    rules.apply_priority_cli(&args.priority_tables)?;
     rules.apply_table_filter_cli(&args.table_filters)?;
     rules.apply_time_filter_cli(&args.time_filters)?;
+    rules.apply_retention_cli(&args.retention)?;
     Ok(rules)
 }
 
+/// Snapshot PostgreSQL tables into an experimental (non-PostgreSQL) target
+async fn experimental_target_init(
+    target_type: &database_replicator::TargetType,
+    source: &str,
+    target: &str,
+    filter: &database_replicator::filters::ReplicationFilter,
+) -> anyhow::Result<()> {
+    use database_replicator::commands::{mysql_target, sqlite_target};
+    use database_replicator::TargetType;
+
+    let source_type = database_replicator::detect_source_type(source)
+        .context("Failed to detect source database type")?;
+    if source_type != database_replicator::SourceType::PostgreSQL {
+        anyhow::bail!(
+            "{:?} targets are only supported with PostgreSQL sources",
+            target_type
+        );
+    }
+
+    tracing::warn!(
+        "⚠ {:?} targets are experimental: deletes are not propagated",
+        target_type
+    );
+    let tables = database_replicator::xmin::target::tables_from_filter(source, filter).await?;
+    let batch_size = database_replicator::utils::calculate_optimal_batch_size();
+
+    let stats = match target_type {
+        TargetType::MySQL => {
+            let config = database_replicator::xmin::target::TargetSyncConfig {
+                tables,
+                batch_size,
+                ..mysql_target::default_config()
+            };
+            mysql_target::init_postgres_to_mysql(source, target, &config).await?
+        }
+        TargetType::SQLite => {
+            let path = database_replicator::sqlite::writer::parse_sqlite_target(target)?;
+            let config = database_replicator::xmin::target::TargetSyncConfig {
+                tables,
+                batch_size,
+                ..sqlite_target::default_config()
+            };
+            sqlite_target::init_postgres_to_sqlite(source, &path, &config).await?
+        }
+        TargetType::PostgreSQL => anyhow::bail!("PostgreSQL targets use the standard init path"),
+    };
+
+    if !stats.is_success() {
+        anyhow::bail!(
+            "{} table(s) failed to replicate to {:?}:\n  {}",
+            stats.errors.len(),
+            target_type,
+            stats.errors.join("\n  ")
+        );
+    }
+    Ok(())
+}
+
+/// Run xmin-based sync from PostgreSQL into an experimental (non-PostgreSQL) target
+async fn experimental_target_sync(
+    target_type: &database_replicator::TargetType,
+    source: &str,
+    target: &str,
+    filter: &database_replicator::filters::ReplicationFilter,
+    sync_interval: u64,
+    once: bool,
+) -> anyhow::Result<()> {
+    use database_replicator::commands::{mysql_target, sqlite_target};
+    use database_replicator::TargetType;
+
+    tracing::warn!(
+        "⚠ {:?} targets are experimental: deletes are not propagated",
+        target_type
+    );
+    let tables = database_replicator::xmin::target::tables_from_filter(source, filter).await?;
+    let batch_size = database_replicator::utils::calculate_optimal_batch_size();
+    let sync_interval = std::time::Duration::from_secs(sync_interval);
+
+    let (shutdown_tx, shutdown_rx) = tokio::sync::broadcast::channel::<()>(1);
+    if !once {
+        database_replicator::daemon::spawn_shutdown_handler(shutdown_tx);
+        database_replicator::daemon::spawn_reload_unsupported("sync to an experimental target");
+    }
+
+    match target_type {
+        TargetType::MySQL => {
+            let config = database_replicator::xmin::target::TargetSyncConfig {
+                tables,
+                batch_size,
+                sync_interval,
+                ..mysql_target::default_config()
+            };
+            if once {
+                let stats = mysql_target::sync_cycle(source, target, &config).await?;
+                println!(
+                    "MySQL sync cycle complete: {} table(s), {} row(s)",
+                    stats.tables_synced, stats.rows_synced
+                );
+                return Ok(());
+            }
+            mysql_target::run_sync(source, target, &config, shutdown_rx).await
+        }
+        TargetType::SQLite => {
+            let path = database_replicator::sqlite::writer::parse_sqlite_target(target)?;
+            let config = database_replicator::xmin::target::TargetSyncConfig {
+                tables,
+                batch_size,
+                sync_interval,
+                ..sqlite_target::default_config()
+            };
+            if once {
+                let stats = sqlite_target::sync_cycle(source, &path, &config).await?;
+                println!(
+                    "SQLite sync cycle complete: {} table(s), {} row(s)",
+                    stats.tables_synced, stats.rows_synced
+                );
+                return Ok(());
+            }
+            sqlite_target::run_sync(source, &path, &config, shutdown_rx).await
+        }
+        TargetType::PostgreSQL => anyhow::bail!("PostgreSQL targets use the standard sync path"),
+    }
+}
+
 /// Internal mode to track whether we're using project-based or URL-based target
 enum SerenTargetMode {
     Project,
     Url,
 }
 
+/// Pre-flight check: verify the target database has tables to sync.
+///
+/// This prevents confusing errors like "relation does not exist". If the target
+/// is empty, auto-switch to the matching source database name (init preserves names).
+async fn resolve_xmin_target(source: &str, target: String) -> anyhow::Result<String> {
+    let target_client = database_replicator::postgres::connect(&target)
+        .await
+        .context("Failed to connect to target database")?;
+
+    let target_tables = database_replicator::migration::list_tables(&target_client).await?;
+
+    if target_tables.is_empty() {
+        // Target database has no tables - likely user specified wrong database
+        let target_parts = database_replicator::utils::parse_postgres_url(&target)?;
+        let target_db_name = &target_parts.database;
+
+        // Get source database name - init preserves source names on target
+        let source_parts = database_replicator::utils::parse_postgres_url(source)?;
+        let source_db_name = &source_parts.database;
+
+        // Connect to postgres database to check if source-named database exists on target
+        let server_url =
+            database_replicator::commands::sync::replace_database_in_url(&target, "postgres")?;
+        let server_client = database_replicator::postgres::connect(&server_url)
+            .await
+            .context("Failed to connect to target server")?;
+
+        let available_dbs = database_replicator::migration::list_databases(&server_client)
+            .await
+            .unwrap_or_default();
+
+        // Look for a database on target that matches source database name
+        let matching_db = available_dbs.iter().find(|db| db.name == *source_db_name);
+
+        if let Some(db) = matching_db {
+            // Found matching database - check if it has tables
+            let db_url =
+                database_replicator::commands::sync::replace_database_in_url(&target, &db.name)?;
+            let table_count =
+                if let Ok(db_client) = database_replicator::postgres::connect(&db_url).await {
+                    database_replicator::migration::list_tables(&db_client)
+                        .await
+                        .map(|t| t.len())
+                        .unwrap_or(0)
+                } else {
+                    0
+                };
+
+            if table_count > 0 {
+                // Auto-switch to the matching database
+                println!();
+                println!("========================================");
+                println!("⚠️  Target database '{}' has no tables", target_db_name);
+                println!("========================================");
+                println!();
+                println!(
+                    "Found matching database '{}' with {} tables.",
+                    source_db_name, table_count
+                );
+                println!("(init preserves source database names on target)");
+                println!();
+                println!("✓ Automatically switching to '{}'", source_db_name);
+                tracing::info!(
+                    "Auto-switched target from '{}' to '{}' (source name match)",
+                    target_db_name,
+                    source_db_name
+                );
+
+                database_replicator::commands::sync::replace_database_in_url(
+                    &target,
+                    source_db_name,
+                )
+            } else {
+                // Matching database exists but is empty
+                anyhow::bail!(
+                    "Target database '{}' has no tables, and the matching source database \
+                     '{}' on target is also empty.\n\n\
+                     Did you run 'init' first to copy data from source to target?",
+                    target_db_name,
+                    source_db_name
+                );
+            }
+        } else {
+            // No matching database found - user must run init first
+            anyhow::bail!(
+                "Database '{}' does not exist on target server.\n\n\
+                 Sync requires the target database name to match the source.\n\
+                 Run 'init' first to copy the source database to target.",
+                source_db_name
+            );
+        }
+    } else {
+        Ok(target)
+    }
+}
+
+/// Seconds between xmin sync cycles without --sync-interval or a [sync] interval
+const DEFAULT_SYNC_INTERVAL: u64 = 3600;
+
+/// Seconds between reconciliation cycles without --reconcile-interval or a [sync] setting
+const DEFAULT_RECONCILE_INTERVAL: u64 = 86400;
+
+/// What a running xmin sync needs to plan its databases again on SIGHUP
+struct SyncReload {
+    source: String,
+    target: String,
+    /// Database, table, and schema filters given as flags
+    filter: database_replicator::filters::ReplicationFilter,
+    /// Rule flags and --config, whose table rules and [sync] section are read again
+    table_rules: TableRuleArgs,
+    /// --sync-interval and --reconcile-interval, which win over the config file
+    cli_intervals: (Option<u64>, Option<u64>),
+    no_reconcile: bool,
+}
+
+impl SyncReload {
+    /// Databases and intervals from the current config file
+    async fn load(
+        &self,
+    ) -> anyhow::Result<(
+        Vec<database_replicator::xmin::SyncDatabase>,
+        std::time::Duration,
+        Option<std::time::Duration>,
+    )> {
+        let file_intervals = match &self.table_rules.config_path {
+            Some(path) => database_replicator::config::load_sync_settings_from_file(path)?,
+            None => Default::default(),
+        };
+        let filter = self
+            .filter
+            .clone()
+            .with_table_rules(build_table_rules(&self.table_rules)?)
+            .resolve_patterns(&self.source)
+            .await?;
+        let mut databases =
+            database_replicator::xmin::SyncDatabase::plan(&self.source, &self.target, &filter)
+                .await?;
+        if let [database] = databases.as_mut_slice() {
+            database.target_url =
+                resolve_xmin_target(&database.source_url, database.target_url.clone()).await?;
+        }
+
+        let sync_interval = self
+            .cli_intervals
+            .0
+            .or(file_intervals.interval)
+            .unwrap_or(DEFAULT_SYNC_INTERVAL);
+        let reconcile_interval = self
+            .cli_intervals
+            .1
+            .or(file_intervals.reconcile_interval)
+            .unwrap_or(DEFAULT_RECONCILE_INTERVAL);
+        Ok((
+            databases,
+            std::time::Duration::from_secs(sync_interval),
+            (!self.no_reconcile).then(|| std::time::Duration::from_secs(reconcile_interval)),
+        ))
+    }
+
+    fn describe(&self) -> String {
+        match &self.table_rules.config_path {
+            Some(path) => path.clone(),
+            None => "the command-line filters".to_string(),
+        }
+    }
+}
+
 /// Run xmin-based incremental sync between source and target databases
 #[allow(clippy::too_many_arguments)]
 async fn xmin_sync(
-    source: String,
-    target: String,
-    schema: String,
-    tables: Option<Vec<String>>,
+    mut databases: Vec<database_replicator::xmin::SyncDatabase>,
     interval: u64,
     reconcile_interval: u64,
     batch_size: usize,
     state_file: Option<String>,
+    state_in_target: bool,
     once: bool,
     no_reconcile: bool,
+    overriding_system_value: bool,
+    audit_schema: bool,
+    auto_add_tables: Option<database_replicator::filters::ReplicationFilter>,
+    matview_refresh_interval: Option<u64>,
+    verify_rate: Option<u64>,
+    purge_aged_out: bool,
+    reload: Option<SyncReload>,
 ) -> anyhow::Result<()> {
+    use database_replicator::audit::RunOutcome;
     use database_replicator::xmin::{DaemonConfig, SyncDaemon, SyncState};
     use std::path::PathBuf;
     use std::time::Duration;
 
     tracing::info!("Starting xmin-based sync...");
-    tracing::info!(
-        "Source: {}",
-        database_replicator::utils::strip_password_from_url(&source)
-            .unwrap_or_else(|_| source.clone())
-    );
-    tracing::info!(
-        "Target: {}",
-        database_replicator::utils::strip_password_from_url(&target)
-            .unwrap_or_else(|_| target.clone())
-    );
-    tracing::info!("Schema: {}", schema);
-    if let Some(ref t) = tables {
-        tracing::info!("Tables: {}", t.join(", "));
-    } else {
-        tracing::info!("Tables: all");
-    }
+    for database in &databases {
+        tracing::info!(
+            "Source: {}",
+            database_replicator::utils::strip_password_from_url(&database.source_url)
+                .unwrap_or_else(|_| database.source_url.clone())
+        );
+        tracing::info!(
+            "Target: {}",
+            database_replicator::utils::strip_password_from_url(&database.target_url)
+                .unwrap_or_else(|_| database.target_url.clone())
+        );
+        tracing::info!("Schemas: {}", database.schemas.join(", "));
+        if database.tables.is_empty() {
+            tracing::info!("Tables: all");
+        } else {
+            tracing::info!("Tables: {}", database.tables.join(", "));
+        }
 
-    // CRITICAL: Ensure source and target are different to prevent data loss
-    database_replicator::utils::validate_source_target_different(&source, &target)
+        // CRITICAL: Ensure source and target are different to prevent data loss
+        database_replicator::utils::validate_source_target_different(
+            &database.source_url,
+            &database.target_url,
+        )
         .context("Source and target validation failed")?;
+    }
     tracing::info!("Verified source and target are different databases");
 
-    // Pre-flight check: verify target database has tables to sync
-    // This prevents confusing errors like "relation does not exist"
-    // If target is empty, auto-switch to matching source database name (init preserves names)
-    let target = {
-        let target_client = database_replicator::postgres::connect(&target)
-            .await
-            .context("Failed to connect to target database")?;
-
-        let target_tables = database_replicator::migration::list_tables(&target_client).await?;
-
-        if target_tables.is_empty() {
-            // Target database has no tables - likely user specified wrong database
-            let target_parts = database_replicator::utils::parse_postgres_url(&target)?;
-            let target_db_name = &target_parts.database;
-
-            // Get source database name - init preserves source names on target
-            let source_parts = database_replicator::utils::parse_postgres_url(&source)?;
-            let source_db_name = &source_parts.database;
-
-            // Connect to postgres database to check if source-named database exists on target
-            let server_url =
-                database_replicator::commands::sync::replace_database_in_url(&target, "postgres")?;
-            let server_client = database_replicator::postgres::connect(&server_url)
-                .await
-                .context("Failed to connect to target server")?;
-
-            let available_dbs = database_replicator::migration::list_databases(&server_client)
-                .await
-                .unwrap_or_default();
-
-            // Look for a database on target that matches source database name
-            let matching_db = available_dbs.iter().find(|db| db.name == *source_db_name);
-
-            if let Some(db) = matching_db {
-                // Found matching database - check if it has tables
-                let db_url = database_replicator::commands::sync::replace_database_in_url(
-                    &target, &db.name,
-                )?;
-                let table_count =
-                    if let Ok(db_client) = database_replicator::postgres::connect(&db_url).await {
-                        database_replicator::migration::list_tables(&db_client)
-                            .await
-                            .map(|t| t.len())
-                            .unwrap_or(0)
-                    } else {
-                        0
-                    };
-
-                if table_count > 0 {
-                    // Auto-switch to the matching database
-                    println!();
-                    println!("========================================");
-                    println!("⚠️  Target database '{}' has no tables", target_db_name);
-                    println!("========================================");
-                    println!();
-                    println!(
-                        "Found matching database '{}' with {} tables.",
-                        source_db_name, table_count
-                    );
-                    println!("(init preserves source database names on target)");
-                    println!();
-                    println!("✓ Automatically switching to '{}'", source_db_name);
-                    tracing::info!(
-                        "Auto-switched target from '{}' to '{}' (source name match)",
-                        target_db_name,
-                        source_db_name
-                    );
-
-                    database_replicator::commands::sync::replace_database_in_url(
-                        &target,
-                        source_db_name,
-                    )?
-                } else {
-                    // Matching database exists but is empty
-                    anyhow::bail!(
-                        "Target database '{}' has no tables, and the matching source database \
-                         '{}' on target is also empty.\n\n\
-                         Did you run 'init' first to copy data from source to target?",
-                        target_db_name,
-                        source_db_name
-                    );
-                }
-            } else {
-                // No matching database found - user must run init first
-                anyhow::bail!(
-                    "Database '{}' does not exist on target server.\n\n\
-                     Sync requires the target database name to match the source.\n\
-                     Run 'init' first to copy the source database to target.",
-                    source_db_name
-                );
-            }
-        } else {
-            target
-        }
-    };
+    // A single database may have been given with the wrong target database name
+    if let [database] = databases.as_mut_slice() {
+        database.target_url =
+            resolve_xmin_target(&database.source_url, database.target_url.clone()).await?;
+    }
 
     // Build daemon config
     let state_path = state_file
@@ -1435,9 +3151,23 @@ async fn xmin_sync(
         sync_interval: Duration::from_secs(interval),
         reconcile_interval: reconcile_interval_duration,
         state_path,
+        state_in_target,
         batch_size,
-        tables: tables.unwrap_or_default(),
-        schema,
+        overriding_system_value,
+        audit_schema,
+        auto_add_tables,
+        matview_refresh_interval: matview_refresh_interval.map(Duration::from_secs),
+        verify_rate,
+        purge_aged_out,
+        // `sync --pause` only applies to continuous sync; --once always runs,
+        // and stateless sync keeps no files to pause with
+        pause_file: if once || state_in_target {
+            None
+        } else {
+            Some(database_replicator::daemon::get_pause_file_path()?)
+        },
+        // Per-database tables and schemas come from `databases`
+        ..Default::default()
     };
 
     tracing::info!("Sync interval: {}s", interval);
@@ -1447,16 +3177,45 @@ async fn xmin_sync(
         tracing::info!("Reconciliation disabled");
     }
     tracing::info!("Batch size: {}", batch_size);
-    tracing::info!("State file: {:?}", config.state_path);
+    if config.state_in_target {
+        tracing::info!(
+            "Sync state: {} table in each target database",
+            database_replicator::xmin::state::STATE_TABLE
+        );
+    } else {
+        tracing::info!("State file: {:?}", config.state_path);
+    }
+    if config.auto_add_tables.is_some() {
+        tracing::info!("New tables matching the filter are added each cycle");
+    }
+    if let Some(ref interval) = config.matview_refresh_interval {
+        tracing::info!(
+            "Materialized view refresh interval: {}s",
+            interval.as_secs()
+        );
+    }
+    if let Some(rate) = config.verify_rate {
+        tracing::info!(
+            "Background verification rate: {}/s",
+            database_replicator::migration::format_bytes(rate as i64)
+        );
+    }
 
     // Create the daemon
-    let daemon = SyncDaemon::new(source.clone(), target.clone(), config);
+    let daemon = SyncDaemon::with_databases(databases, config);
 
     if once {
         // Run a single sync cycle
         tracing::info!("Running single sync cycle...");
 
-        let stats = daemon.run_sync_cycle().await?;
+        let result = daemon.run_sync_cycle().await;
+        let outcome = match &result {
+            Ok(stats) if stats.errors.is_empty() => RunOutcome::Succeeded,
+            Ok(stats) => RunOutcome::Failed(stats.errors.join("\n")),
+            Err(e) => RunOutcome::Failed(e.to_string()),
+        };
+        daemon.finish_audit_runs(&outcome).await;
+        let stats = result?;
 
         tracing::info!("Sync cycle complete:");
         tracing::info!("  Tables synced: {}", stats.tables_synced);
@@ -1493,17 +3252,34 @@ async fn xmin_sync(
         // Create shutdown channel
         let (shutdown_tx, shutdown_rx) = tokio::sync::broadcast::channel::<()>(1);
 
-        // Set up Ctrl+C handler
-        let shutdown_tx_clone = shutdown_tx.clone();
-        tokio::spawn(async move {
-            tokio::signal::ctrl_c()
-                .await
-                .expect("Failed to listen for Ctrl+C");
-            tracing::info!("Received shutdown signal");
-            let _ = shutdown_tx_clone.send(());
-        });
+        // Ctrl+C or SIGTERM finishes the table in progress and saves state
+        database_replicator::daemon::spawn_shutdown_handler(shutdown_tx);
 
-        daemon.run(shutdown_rx).await?;
+        // SIGHUP swaps in freshly planned databases and intervals between cycles
+        let reloads = async {
+            let Some(reload) = reload else {
+                return std::future::pending::<()>().await;
+            };
+            let mut hangup = database_replicator::daemon::ReloadSignal::listen();
+            loop {
+                hangup.recv().await;
+                tracing::info!("Received SIGHUP; reloading {}", reload.describe());
+                match reload.load().await {
+                    Ok((databases, sync_interval, reconcile_interval)) => {
+                        daemon.reload(databases, sync_interval, reconcile_interval)
+                    }
+                    Err(e) => tracing::error!(
+                        "Failed to reload {}; keeping the current settings: {:#}",
+                        reload.describe(),
+                        e
+                    ),
+                }
+            }
+        };
+        tokio::select! {
+            result = daemon.run(shutdown_rx) => result?,
+            _ = reloads => {}
+        }
 
         // Clean up daemon PID file on graceful shutdown
         if let Err(e) = database_replicator::daemon::cleanup() {
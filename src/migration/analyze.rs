@@ -0,0 +1,132 @@
+// ABOUTME: Collects planner statistics on the target after the bulk load
+// ABOUTME: Runs ANALYZE, or VACUUM (ANALYZE), on the copied tables over several connections
+
+use crate::postgres;
+use anyhow::{Context, Result};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+/// Tables processed at once when no job count is given
+pub const DEFAULT_ANALYZE_JOBS: usize = 4;
+
+// Largest first, so the longest runs start early. Unpopulated materialized
+// views have nothing to sample.
+const TABLES_QUERY: &str = "
+SELECT format('%I.%I', n.nspname, c.relname)
+FROM pg_class c
+JOIN pg_namespace n ON n.oid = c.relnamespace
+WHERE c.relkind IN ('r', 'p', 'm')
+  AND (c.relkind <> 'm' OR c.relispopulated)
+  AND n.nspname NOT IN ('pg_catalog', 'information_schema')
+  AND n.nspname NOT LIKE 'pg_toast%'
+  AND n.nspname NOT LIKE 'pg_temp%'
+  AND NOT EXISTS (
+      SELECT 1 FROM pg_depend d
+      WHERE d.classid = 'pg_class'::regclass AND d.objid = c.oid AND d.deptype = 'e'
+  )
+  AND ($1::text[] IS NULL
+       OR c.oid = ANY (SELECT to_regclass(t) FROM unnest($1::text[]) AS t))
+ORDER BY pg_total_relation_size(c.oid) DESC, 1";
+
+fn statement(table: &str, vacuum: bool) -> String {
+    if vacuum {
+        format!("VACUUM (ANALYZE) {}", table)
+    } else {
+        format!("ANALYZE {}", table)
+    }
+}
+
+fn error_reason(e: &tokio_postgres::Error) -> String {
+    e.as_db_error()
+        .map(|db| db.message().to_string())
+        .unwrap_or_else(|| e.to_string())
+}
+
+/// Analyze the user tables of a target database, `jobs` at a time
+///
+/// With `only_tables`, just those tables (as `table` or `schema.table`) are
+/// processed. `vacuum` runs `VACUUM (ANALYZE)`, which also sets the visibility
+/// map so index-only scans work right away. Failures are logged and counted
+/// instead of stopping, since the target is usable without statistics.
+/// Returns how many tables were processed and how many failed.
+pub async fn analyze_tables(
+    target_url: &str,
+    only_tables: Option<&[String]>,
+    vacuum: bool,
+    jobs: usize,
+) -> Result<(usize, usize)> {
+    let client = postgres::connect_with_retry(target_url)
+        .await
+        .context("Failed to connect to target database to analyze tables")?;
+    let only_tables: Option<Vec<String>> = only_tables.map(<[String]>::to_vec);
+    let tables: Vec<String> = client
+        .query(TABLES_QUERY, &[&only_tables])
+        .await
+        .context("Failed to list tables to analyze")?
+        .iter()
+        .map(|row| row.get(0))
+        .collect();
+    if tables.is_empty() {
+        return Ok((0, 0));
+    }
+
+    let jobs = jobs.clamp(1, tables.len());
+    let mut clients = vec![client];
+    for _ in 1..jobs {
+        clients.push(
+            postgres::connect_with_retry(target_url)
+                .await
+                .context("Failed to connect to target database to analyze tables")?,
+        );
+    }
+    tracing::info!(
+        "  {} {} table(s) with {} connection(s)...",
+        if vacuum {
+            "Vacuuming and analyzing"
+        } else {
+            "Analyzing"
+        },
+        tables.len(),
+        jobs
+    );
+
+    let next = AtomicUsize::new(0);
+    let failed = AtomicUsize::new(0);
+    futures::future::join_all(clients.iter().map(|client| async {
+        while let Some(table) = tables.get(next.fetch_add(1, Ordering::Relaxed)) {
+            let start = Instant::now();
+            match client.batch_execute(&statement(table, vacuum)).await {
+                Ok(()) => tracing::debug!(
+                    "Analyzed {} in {:.1}s",
+                    table,
+                    start.elapsed().as_secs_f64()
+                ),
+                Err(e) => {
+                    tracing::warn!("  ⚠ Failed to analyze {}: {}", table, error_reason(&e));
+                    failed.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        }
+    }))
+    .await;
+
+    let failed = failed.into_inner();
+    Ok((tables.len() - failed, failed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_statement() {
+        assert_eq!(
+            statement("\"public\".\"orders\"", false),
+            "ANALYZE \"public\".\"orders\""
+        );
+        assert_eq!(
+            statement("\"public\".\"orders\"", true),
+            "VACUUM (ANALYZE) \"public\".\"orders\""
+        );
+    }
+}
@@ -0,0 +1,857 @@
+// ABOUTME: Tracks the per-table files of a data dump in a manifest so a failed restore can resume
+// ABOUTME: Restores each table in its own transaction, in foreign key order, several at a time
+
+use crate::migration::indexes::DeferredIndex;
+use crate::postgres;
+use crate::postgres::pgvector::VectorIndex;
+use crate::postgres::snapshot::ConsistencyPoint;
+use crate::table_rules::{foreign_key_parents, prioritized_waves, ForeignKey};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::process::Command;
+use tokio_postgres::Client;
+
+const MANIFEST_VERSION: u32 = 1;
+const MANIFEST_FILE: &str = "manifest.json";
+const DUMP_DIR: &str = "data";
+
+/// Table restores run at once when no job count is given
+pub const DEFAULT_RESTORE_JOBS: usize = 4;
+
+/// Rounds of restores before giving up on an artifact
+const RESTORE_ATTEMPTS: u32 = 3;
+
+/// Progress of one artifact
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArtifactStatus {
+    Pending,
+    Restored,
+    Failed,
+}
+
+/// Part of a data dump that is restored in one transaction
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DataArtifact {
+    /// Schema and name of the table whose rows this holds; `None` for
+    /// sequence values and large objects
+    pub table: Option<(String, String)>,
+    /// Lines of the archive's table of contents to restore
+    pub toc_entries: Vec<String>,
+    pub status: ArtifactStatus,
+    pub attempts: u32,
+    pub error: Option<String>,
+    /// SHA-256 digest of each dump file holding this artifact, by file name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub checksums: BTreeMap<String, String>,
+}
+
+impl DataArtifact {
+    fn qualified_table(&self) -> Option<String> {
+        self.table.as_ref().map(|(schema, table)| {
+            format!(
+                "{}.{}",
+                crate::utils::quote_ident(schema),
+                crate::utils::quote_ident(table)
+            )
+        })
+    }
+}
+
+impl std::fmt::Display for DataArtifact {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.table {
+            Some((schema, table)) => write!(f, "{}.{}", schema, table),
+            None => write!(f, "sequence values and large objects"),
+        }
+    }
+}
+
+/// The artifacts of one database's data dump and how far their restore got
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DataManifest {
+    version: u32,
+    pub database: String,
+    pub data_as_of: DateTime<Utc>,
+    /// The source snapshot the dump was taken at, when one was shared
+    #[serde(default)]
+    pub consistency_point: Option<ConsistencyPoint>,
+    /// Whether a later run may restore what is left. A copy that shares a
+    /// replication slot's snapshot or has separately copied tables cannot be
+    /// finished from the dump alone.
+    pub resumable: bool,
+    /// Indexes dropped before the load, rebuilt once it finishes
+    pub deferred_indexes: Vec<DeferredIndex>,
+    pub deferred_vector_indexes: Vec<VectorIndex>,
+    /// High-priority tables, as `"schema"."table"`, restored before the rest
+    /// together with the tables they reference
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub priority_tables: Vec<String>,
+    pub artifacts: Vec<DataArtifact>,
+    /// SHA-256 digest of the dump files every artifact needs, such as the
+    /// archive's table of contents
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub archive_checksums: BTreeMap<String, String>,
+}
+
+impl DataManifest {
+    /// Build a manifest from the table of contents of a directory-format dump
+    ///
+    /// Each table's data becomes its own artifact. Sequence values and large
+    /// objects are grouped into one artifact restored after the tables.
+    pub fn from_toc(database: &str, toc: &str, data_as_of: DateTime<Utc>) -> Self {
+        let mut artifacts = Vec::new();
+        let mut others = Vec::new();
+        for line in toc.lines() {
+            let line = line.trim_end();
+            if line.is_empty() || line.starts_with(';') {
+                continue;
+            }
+            match parse_table_data(line) {
+                Some(table) => artifacts.push(DataArtifact {
+                    table: Some(table),
+                    toc_entries: vec![line.to_string()],
+                    status: ArtifactStatus::Pending,
+                    attempts: 0,
+                    error: None,
+                    checksums: BTreeMap::new(),
+                }),
+                None => others.push(line.to_string()),
+            }
+        }
+        if !others.is_empty() {
+            artifacts.push(DataArtifact {
+                table: None,
+                toc_entries: others,
+                status: ArtifactStatus::Pending,
+                attempts: 0,
+                error: None,
+                checksums: BTreeMap::new(),
+            });
+        }
+        Self {
+            version: MANIFEST_VERSION,
+            database: database.to_string(),
+            data_as_of,
+            consistency_point: None,
+            resumable: false,
+            deferred_indexes: Vec::new(),
+            deferred_vector_indexes: Vec::new(),
+            priority_tables: Vec::new(),
+            artifacts,
+            archive_checksums: BTreeMap::new(),
+        }
+    }
+
+    /// Record the SHA-256 digest of every file of the dump kept in `dir`
+    ///
+    /// A table's data file, named after its entry in the table of contents,
+    /// goes with that table's artifact and large object files with the
+    /// artifact that has no table. Anything else is needed by every artifact.
+    pub fn record_checksums(&mut self, dir: &Path) -> Result<()> {
+        let data_dir = dump_dir(dir);
+        let mut entries: Vec<_> = fs::read_dir(&data_dir)
+            .with_context(|| format!("Failed to read dump directory {}", data_dir.display()))?
+            .collect::<std::io::Result<_>>()
+            .with_context(|| format!("Failed to read dump directory {}", data_dir.display()))?;
+        entries.sort_by_key(|entry| entry.file_name());
+        for artifact in &mut self.artifacts {
+            artifact.checksums.clear();
+        }
+        self.archive_checksums.clear();
+        for entry in entries {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let checksum = file_sha256(&entry.path())?;
+            match self.artifact_for_file(&name) {
+                Some(i) => self.artifacts[i].checksums.insert(name, checksum),
+                None => self.archive_checksums.insert(name, checksum),
+            };
+        }
+        Ok(())
+    }
+
+    /// Check the dump files of the given artifacts, and those every artifact
+    /// needs, against the digests recorded when the dump was taken
+    ///
+    /// A manifest written before checksums were recorded has none to check.
+    pub fn verify_checksums(&self, dir: &Path, artifacts: &[usize]) -> Result<()> {
+        let data_dir = dump_dir(dir);
+        let checksums = artifacts
+            .iter()
+            .flat_map(|&i| &self.artifacts[i].checksums)
+            .chain(&self.archive_checksums);
+        for (name, expected) in checksums {
+            let path = data_dir.join(name);
+            if !path.exists() {
+                anyhow::bail!(
+                    "Dump file {} is missing; the dump for '{}' is incomplete",
+                    path.display(),
+                    self.database
+                );
+            }
+            let actual = file_sha256(&path)?;
+            if &actual != expected {
+                anyhow::bail!(
+                    "Dump file {} has SHA-256 {}, but the manifest records {}; \
+                     the dump for '{}' is corrupt or was changed after it was taken",
+                    path.display(),
+                    actual,
+                    expected,
+                    self.database
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// SHA-256 digest of every dump file, by its path relative to the
+    /// manifest's directory
+    pub fn checksums(&self) -> BTreeMap<String, String> {
+        self.artifacts
+            .iter()
+            .flat_map(|artifact| &artifact.checksums)
+            .chain(&self.archive_checksums)
+            .map(|(name, checksum)| (format!("{}/{}", DUMP_DIR, name), checksum.clone()))
+            .collect()
+    }
+
+    /// Index of the artifact a dump file belongs to
+    ///
+    /// Table data files are named `<dump id>.dat`, with a suffix when
+    /// compressed; large objects live in `blob_<oid>.dat` and `blobs*.toc`.
+    fn artifact_for_file(&self, name: &str) -> Option<usize> {
+        if name.starts_with("blob") {
+            return self.artifacts.iter().position(|a| a.table.is_none());
+        }
+        let (id, _) = name.split_once('.')?;
+        if id.is_empty() || !id.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        let prefix = format!("{};", id);
+        self.artifacts.iter().position(|artifact| {
+            artifact
+                .toc_entries
+                .iter()
+                .any(|entry| entry.starts_with(&prefix))
+        })
+    }
+
+    /// Load the manifest in `dir`, if there is one this version understands
+    pub fn load(dir: &Path) -> Result<Option<Self>> {
+        let path = dir.join(MANIFEST_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let contents = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read data manifest at {}", path.display()))?;
+        let manifest: Self = serde_json::from_str(&contents)
+            .with_context(|| format!("Failed to parse data manifest at {}", path.display()))?;
+        if manifest.version != MANIFEST_VERSION {
+            tracing::warn!(
+                "⚠ Ignoring data manifest at {} (version {}, expected {})",
+                path.display(),
+                manifest.version,
+                MANIFEST_VERSION
+            );
+            return Ok(None);
+        }
+        Ok(Some(manifest))
+    }
+
+    /// Write the manifest to `dir`, replacing the previous one atomically
+    pub fn save(&self, dir: &Path) -> Result<()> {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create artifact directory {}", dir.display()))?;
+        let path = dir.join(MANIFEST_FILE);
+        let mut tmp = tempfile::NamedTempFile::new_in(dir)
+            .with_context(|| format!("Failed to create temp manifest in {}", dir.display()))?;
+        serde_json::to_writer_pretty(tmp.as_file_mut(), self)
+            .with_context(|| format!("Failed to serialize data manifest at {}", path.display()))?;
+        tmp.persist(&path)
+            .with_context(|| format!("Failed to persist data manifest at {}", path.display()))?;
+        Ok(())
+    }
+
+    /// Number of artifacts restored so far
+    pub fn restored_count(&self) -> usize {
+        self.artifacts
+            .iter()
+            .filter(|artifact| artifact.status == ArtifactStatus::Restored)
+            .count()
+    }
+
+    fn unfinished(&self) -> Vec<usize> {
+        (0..self.artifacts.len())
+            .filter(|&i| self.artifacts[i].status != ArtifactStatus::Restored)
+            .collect()
+    }
+
+    /// Deferred indexes the target does not have
+    ///
+    /// A failed run rebuilds its deferred indexes before stopping, unless it was
+    /// killed first, so a resumed run only rebuilds the ones still missing.
+    pub async fn missing_indexes(
+        &self,
+        client: &Client,
+    ) -> Result<(Vec<DeferredIndex>, Vec<VectorIndex>)> {
+        let mut indexes = Vec::new();
+        for index in &self.deferred_indexes {
+            if !index_exists(client, &index.schema, &index.name).await? {
+                indexes.push(index.clone());
+            }
+        }
+        let mut vector_indexes = Vec::new();
+        for index in &self.deferred_vector_indexes {
+            if !index_exists(client, &index.schema, &index.name).await? {
+                vector_indexes.push(index.clone());
+            }
+        }
+        Ok((indexes, vector_indexes))
+    }
+}
+
+/// Schema and table named by a `TABLE DATA` line of `pg_restore --list`
+///
+/// Lines look like `3347; 0 16390 TABLE DATA public orders owner`. Names are
+/// not quoted, so a schema name containing a space is split wrongly; such a
+/// table is still restored, just without waiting on its parents.
+fn parse_table_data(line: &str) -> Option<(String, String)> {
+    let (_, entry) = line.split_once("; ")?;
+    let mut fields = entry.splitn(3, ' ');
+    fields.next()?;
+    fields.next()?;
+    let rest = fields.next()?.strip_prefix("TABLE DATA ")?;
+    let (schema, rest) = rest.split_once(' ')?;
+    let (table, _owner) = rest.rsplit_once(' ')?;
+    Some((schema.to_string(), table.to_string()))
+}
+
+async fn index_exists(client: &Client, schema: &str, name: &str) -> Result<bool> {
+    let row = client
+        .query_one(
+            "SELECT to_regclass(format('%I.%I', $1::text, $2::text)) IS NOT NULL",
+            &[&schema, &name],
+        )
+        .await
+        .with_context(|| format!("Failed to look up index {}.{}", schema, name))?;
+    Ok(row.get(0))
+}
+
+/// Hex SHA-256 digest of a file's contents
+fn file_sha256(path: &Path) -> Result<String> {
+    let mut file =
+        fs::File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut digest = Sha256::new();
+    std::io::copy(&mut file, &mut digest)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(digest
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect())
+}
+
+fn dump_dir(dir: &Path) -> PathBuf {
+    dir.join(DUMP_DIR)
+}
+
+/// Clear out `dir` for a new dump and return the path to dump into
+pub fn prepare_dump_dir(dir: &Path) -> Result<PathBuf> {
+    remove(dir)?;
+    fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create artifact directory {}", dir.display()))?;
+    Ok(dump_dir(dir))
+}
+
+/// Delete a database's dump files and manifest
+pub fn remove(dir: &Path) -> Result<()> {
+    if dir.exists() {
+        fs::remove_dir_all(dir)
+            .with_context(|| format!("Failed to remove data artifacts at {}", dir.display()))?;
+    }
+    Ok(())
+}
+
+/// Read the table of contents of a directory-format dump
+pub async fn list_archive(dump_dir: &Path) -> Result<String> {
+    let output = Command::new("pg_restore")
+        .arg("--list")
+        .arg(dump_dir)
+        .output()
+        .await
+        .context("Failed to execute pg_restore. Is PostgreSQL client installed?")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "Failed to list dump at {}: {}",
+            dump_dir.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Group artifacts into waves whose tables only reference tables in earlier waves
+///
+/// Tables in a foreign key cycle go into the same wave. The waves of the
+/// `priority` tables and the tables they reference come first, and the
+/// artifact without a table comes last, on its own.
+fn restore_waves(
+    artifacts: &[DataArtifact],
+    pending: &[usize],
+    foreign_keys: &[ForeignKey],
+    priority: &[String],
+) -> Vec<Vec<usize>> {
+    let (tables, others): (Vec<usize>, Vec<usize>) =
+        pending.iter().partition(|&&i| artifacts[i].table.is_some());
+    let names: Vec<String> = tables
+        .iter()
+        .filter_map(|&i| artifacts[i].qualified_table())
+        .collect();
+    let mut waves: Vec<Vec<usize>> = prioritized_waves(&names, foreign_keys, priority)
+        .into_iter()
+        .map(|wave| wave.into_iter().map(|i| tables[i]).collect())
+        .collect();
+    if !others.is_empty() {
+        waves.push(others);
+    }
+    waves
+}
+
+fn error_reason(e: &tokio_postgres::Error) -> String {
+    e.as_db_error()
+        .map(|db| db.message().to_string())
+        .unwrap_or_else(|| e.to_string())
+}
+
+/// Restore one artifact with `pg_restore --single-transaction`
+///
+/// A failure rolls the whole artifact back, so it can be restored again.
+async fn restore_artifact(
+    parts: &crate::utils::PostgresUrlParts,
+    pgpass: &Path,
+    dir: &Path,
+    artifact: &DataArtifact,
+) -> Result<()> {
+    let mut list = tempfile::NamedTempFile::new_in(dir)
+        .with_context(|| format!("Failed to create restore list in {}", dir.display()))?;
+    std::io::Write::write_all(
+        list.as_file_mut(),
+        artifact.toc_entries.join("\n").as_bytes(),
+    )
+    .context("Failed to write restore list")?;
+
+    let mut cmd = Command::new("pg_restore");
+    cmd.arg("--data-only")
+        .arg("--no-owner")
+        .arg("--single-transaction")
+        .arg("--format=directory")
+        .arg(format!("--use-list={}", list.path().display()))
+        .arg("--host")
+        .arg(&parts.host)
+        .arg("--port")
+        .arg(parts.port.to_string())
+        .arg("--dbname")
+        .arg(&parts.database)
+        .arg(dump_dir(dir))
+        .env("PGPASSFILE", pgpass);
+    if let Some(user) = &parts.user {
+        cmd.arg("--username").arg(user);
+    }
+    for (env_var, value) in parts.to_pg_env_vars() {
+        cmd.env(env_var, value);
+    }
+    for (env_var, value) in crate::utils::get_keepalive_env_vars() {
+        cmd.env(env_var, value);
+    }
+    cmd.env("PGCONNECT_TIMEOUT", "30");
+
+    let output = cmd
+        .output()
+        .await
+        .context("Failed to execute pg_restore. Is PostgreSQL client installed?")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let message = stderr
+            .lines()
+            .rfind(|line| line.contains("error:") || line.contains("ERROR:"))
+            .or_else(|| stderr.lines().next_back())
+            .unwrap_or("pg_restore failed")
+            .trim()
+            .to_string();
+        anyhow::bail!(message);
+    }
+    Ok(())
+}
+
+/// Restore the unfinished artifacts of a manifest kept in `dir`, `jobs` at a time
+///
+/// Tables are restored in foreign key order, each in one transaction, and the
+/// manifest is saved after every artifact. Artifacts that fail are retried
+/// after the rest. With `resumed`, a table that already holds rows is taken as
+/// restored by the run that was interrupted before recording it.
+///
+/// The dump files are checked against the manifest's checksums before
+/// anything is restored, so a corrupt dump never reaches the target.
+pub async fn restore_artifacts(
+    target_url: &str,
+    dir: &Path,
+    manifest: &mut DataManifest,
+    jobs: usize,
+    resumed: bool,
+) -> Result<()> {
+    manifest.verify_checksums(dir, &manifest.unfinished())?;
+    let parts = crate::utils::parse_postgres_url(target_url).with_context(|| {
+        format!(
+            "Failed to parse target URL: {}",
+            crate::redact::redact(target_url)
+        )
+    })?;
+    let pgpass = crate::utils::PgPassFile::new(&parts)
+        .context("Failed to create .pgpass file for authentication")?;
+    let client = postgres::connect_with_retry(target_url)
+        .await
+        .context("Failed to connect to target database to restore data")?;
+    let foreign_keys = crate::migration::filtered::list_foreign_keys(&client).await?;
+
+    if resumed {
+        for artifact in manifest
+            .artifacts
+            .iter_mut()
+            .filter(|artifact| artifact.status != ArtifactStatus::Restored)
+        {
+            let Some(table) = artifact.qualified_table() else {
+                continue;
+            };
+            let has_rows: bool = client
+                .query_one(&format!("SELECT EXISTS (SELECT 1 FROM {})", table), &[])
+                .await
+                .map_err(|e| anyhow::anyhow!("{}", error_reason(&e)))
+                .with_context(|| format!("Failed to check {} for restored rows", artifact))?
+                .get(0);
+            if has_rows {
+                tracing::info!(
+                    "    {} already holds rows; not restoring it again",
+                    artifact
+                );
+                artifact.status = ArtifactStatus::Restored;
+                artifact.error = None;
+            }
+        }
+        manifest.save(dir)?;
+    }
+
+    let database = manifest.database.clone();
+    let parents = foreign_key_parents(&foreign_keys);
+    let started = Instant::now();
+    for round in 0..RESTORE_ATTEMPTS {
+        let pending = manifest.unfinished();
+        if pending.is_empty() {
+            break;
+        }
+        if round > 0 {
+            tracing::info!("  Retrying {} failed artifact(s)...", pending.len());
+            tokio::time::sleep(Duration::from_secs(1 << round)).await;
+        }
+        let waves = restore_waves(
+            &manifest.artifacts,
+            &pending,
+            &foreign_keys,
+            &manifest.priority_tables,
+        );
+        let jobs = jobs.max(1);
+        tracing::info!(
+            "  Restoring {} artifact(s) in {} wave(s) with {} process(es)...",
+            pending.len(),
+            waves.len(),
+            jobs.min(pending.len())
+        );
+
+        // A table whose parent failed this round would only fail on its foreign key
+        let failed_tables: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+        let finished = AtomicUsize::new(0);
+        let shared = Mutex::new(&mut *manifest);
+        for wave in &waves {
+            let next = AtomicUsize::new(0);
+            let results = futures::future::join_all((0..jobs.min(wave.len())).map(|_| async {
+                while let Some(&i) = wave.get(next.fetch_add(1, Ordering::Relaxed)) {
+                    let artifact = shared.lock().unwrap().artifacts[i].clone();
+                    let table = artifact.qualified_table();
+                    let failed_parent = table
+                        .as_deref()
+                        .and_then(|table| parents.get(table))
+                        .and_then(|parents| {
+                            let failed = failed_tables.lock().unwrap();
+                            parents
+                                .iter()
+                                .find(|parent| failed.contains(**parent))
+                                .map(|parent| parent.to_string())
+                        });
+                    if let Some(parent) = failed_parent {
+                        let done = finished.fetch_add(1, Ordering::Relaxed) + 1;
+                        tracing::warn!(
+                            "    [{}/{}] Skipping {}: {} was not restored",
+                            done,
+                            pending.len(),
+                            artifact,
+                            parent
+                        );
+                        failed_tables.lock().unwrap().extend(table);
+                        let mut manifest = shared.lock().unwrap();
+                        let entry = &mut manifest.artifacts[i];
+                        entry.status = ArtifactStatus::Failed;
+                        entry.error = Some(format!("waiting on {}", parent));
+                        manifest.save(dir)?;
+                        continue;
+                    }
+
+                    if artifact.table.is_some() {
+                        let table = artifact.to_string();
+                        crate::events::emit(|sink| sink.on_table_start(&database, &table));
+                    }
+                    let start = Instant::now();
+                    let result = restore_artifact(&parts, pgpass.path(), dir, &artifact).await;
+                    let done = finished.fetch_add(1, Ordering::Relaxed) + 1;
+                    if result.is_err() {
+                        failed_tables.lock().unwrap().extend(table);
+                    }
+
+                    let mut manifest = shared.lock().unwrap();
+                    let entry = &mut manifest.artifacts[i];
+                    entry.attempts += 1;
+                    match &result {
+                        Ok(()) => {
+                            tracing::info!(
+                                "    [{}/{}] Restored {} in {:.1}s",
+                                done,
+                                pending.len(),
+                                artifact,
+                                start.elapsed().as_secs_f64()
+                            );
+                            entry.status = ArtifactStatus::Restored;
+                            entry.error = None;
+                        }
+                        Err(e) => {
+                            tracing::warn!(
+                                "    [{}/{}] Failed to restore {}: {:#}",
+                                done,
+                                pending.len(),
+                                artifact,
+                                e
+                            );
+                            entry.status = ArtifactStatus::Failed;
+                            entry.error = Some(format!("{:#}", e));
+                        }
+                    }
+                    manifest.save(dir)?;
+                }
+                Ok::<(), anyhow::Error>(())
+            }))
+            .await;
+            results.into_iter().collect::<Result<Vec<_>>>()?;
+        }
+    }
+
+    let failed: Vec<String> = manifest
+        .artifacts
+        .iter()
+        .filter(|artifact| artifact.status != ArtifactStatus::Restored)
+        .map(|artifact| {
+            format!(
+                "  - {}: {}",
+                artifact,
+                artifact.error.as_deref().unwrap_or("not restored")
+            )
+        })
+        .collect();
+    if !failed.is_empty() {
+        let hint = if manifest.resumable {
+            "Tables that were restored are kept. Run init again with the same arguments to restore the rest."
+        } else {
+            "Re-run init with --drop-existing to start again from a clean database."
+        };
+        anyhow::bail!(
+            "Failed to restore {} of {} artifact(s) for '{}':\n{}\n{}",
+            failed.len(),
+            manifest.artifacts.len(),
+            database,
+            failed.join("\n"),
+            hint
+        );
+    }
+    tracing::info!(
+        "  ✓ Restored {} artifact(s) in {:.1}s",
+        manifest.artifacts.len(),
+        started.elapsed().as_secs_f64()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOC: &str = "\
+;
+; Archive created at 2026-10-17 04:12:36 UTC
+;     dbname: app
+;
+; Selected TOC Entries:
+;
+3350; 2613 16659 BLOB - 16659 postgres
+3348; 0 16655 TABLE DATA public order items postgres
+3347; 0 16390 TABLE DATA public orders postgres
+3349; 0 16400 TABLE DATA sales customers postgres
+3358; 0 0 SEQUENCE SET public orders_id_seq postgres
+3351; 0 0 BLOBS - BLOBS
+";
+
+    fn fk(child: &str, parent: &str) -> ForeignKey {
+        ForeignKey {
+            child: child.to_string(),
+            child_columns: vec!["id".to_string()],
+            parent: parent.to_string(),
+            parent_columns: vec!["id".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_parse_table_data() {
+        assert_eq!(
+            parse_table_data("3348; 0 16655 TABLE DATA public order items postgres"),
+            Some(("public".to_string(), "order items".to_string()))
+        );
+        assert_eq!(
+            parse_table_data("3358; 0 0 SEQUENCE SET public orders_id_seq postgres"),
+            None
+        );
+        assert_eq!(parse_table_data("3351; 0 0 BLOBS - BLOBS "), None);
+    }
+
+    #[test]
+    fn test_manifest_from_toc() {
+        let manifest = DataManifest::from_toc("app", TOC, Utc::now());
+        assert_eq!(manifest.artifacts.len(), 4);
+        assert_eq!(manifest.artifacts[0].to_string(), "public.order items");
+        assert_eq!(manifest.artifacts[2].to_string(), "sales.customers");
+        assert_eq!(
+            manifest.artifacts[3].toc_entries,
+            vec![
+                "3350; 2613 16659 BLOB - 16659 postgres",
+                "3358; 0 0 SEQUENCE SET public orders_id_seq postgres",
+                "3351; 0 0 BLOBS - BLOBS",
+            ]
+        );
+        assert!(manifest
+            .artifacts
+            .iter()
+            .all(|artifact| artifact.status == ArtifactStatus::Pending));
+    }
+
+    #[test]
+    fn test_manifest_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(DataManifest::load(dir.path()).unwrap().is_none());
+
+        let mut manifest = DataManifest::from_toc("app", TOC, Utc::now());
+        manifest.resumable = true;
+        manifest.artifacts[1].status = ArtifactStatus::Restored;
+        manifest.save(dir.path()).unwrap();
+
+        let loaded = DataManifest::load(dir.path()).unwrap().unwrap();
+        assert!(loaded.resumable);
+        assert_eq!(loaded.artifacts, manifest.artifacts);
+        assert_eq!(loaded.restored_count(), 1);
+        assert_eq!(loaded.unfinished(), vec![0, 2, 3]);
+    }
+
+    #[test]
+    fn test_checksums() {
+        let dir = tempfile::tempdir().unwrap();
+        let data_dir = dump_dir(dir.path());
+        fs::create_dir_all(&data_dir).unwrap();
+        for name in [
+            "toc.dat",
+            "3347.dat.gz",
+            "3349.dat.gz",
+            "blobs.toc",
+            "blob_16659.dat",
+        ] {
+            fs::write(data_dir.join(name), name).unwrap();
+        }
+
+        let mut manifest = DataManifest::from_toc("app", TOC, Utc::now());
+        manifest.record_checksums(dir.path()).unwrap();
+        assert_eq!(
+            manifest.archive_checksums.keys().collect::<Vec<_>>(),
+            vec!["toc.dat"]
+        );
+        assert!(manifest.artifacts[0].checksums.is_empty());
+        assert!(manifest.artifacts[1].checksums.contains_key("3347.dat.gz"));
+        assert!(manifest.artifacts[2].checksums.contains_key("3349.dat.gz"));
+        assert_eq!(manifest.artifacts[3].checksums.len(), 2);
+        assert_eq!(
+            manifest.checksums()["data/toc.dat"],
+            "1142af4f8d1edbffb474fee44bfe9b12126cef1914526b1561c998826fda4dce"
+        );
+        manifest
+            .verify_checksums(dir.path(), &[0, 1, 2, 3])
+            .unwrap();
+
+        // A changed file only fails the artifacts that use it
+        fs::write(data_dir.join("3349.dat.gz"), "truncated").unwrap();
+        manifest.verify_checksums(dir.path(), &[1, 3]).unwrap();
+        let err = manifest.verify_checksums(dir.path(), &[2]).unwrap_err();
+        assert!(err.to_string().contains("3349.dat.gz"));
+
+        fs::remove_file(data_dir.join("toc.dat")).unwrap();
+        let err = manifest.verify_checksums(dir.path(), &[]).unwrap_err();
+        assert!(err.to_string().contains("missing"));
+    }
+
+    #[test]
+    fn test_restore_waves() {
+        let manifest = DataManifest::from_toc("app", TOC, Utc::now());
+        let foreign_keys = vec![
+            fk("\"public\".\"order items\"", "\"public\".\"orders\""),
+            fk("\"public\".\"orders\"", "\"sales\".\"customers\""),
+            fk("\"public\".\"orders\"", "\"public\".\"orders\""),
+        ];
+        let waves = restore_waves(&manifest.artifacts, &[0, 1, 2, 3], &foreign_keys, &[]);
+        assert_eq!(waves, vec![vec![2], vec![1], vec![0], vec![3]]);
+
+        // Restored parents are not waited on
+        let waves = restore_waves(&manifest.artifacts, &[0, 1], &foreign_keys, &[]);
+        assert_eq!(waves, vec![vec![1], vec![0]]);
+    }
+
+    #[test]
+    fn test_restore_waves_priority() {
+        let manifest = DataManifest::from_toc("app", TOC, Utc::now());
+        let foreign_keys = vec![fk("\"public\".\"order items\"", "\"public\".\"orders\"")];
+        let waves = restore_waves(&manifest.artifacts, &[0, 1, 2, 3], &foreign_keys, &[]);
+        assert_eq!(waves, vec![vec![1, 2], vec![0], vec![3]]);
+
+        let priority = vec!["\"sales\".\"customers\"".to_string()];
+        let waves = restore_waves(&manifest.artifacts, &[0, 1, 2, 3], &foreign_keys, &priority);
+        assert_eq!(waves, vec![vec![2], vec![1], vec![0], vec![3]]);
+    }
+
+    #[test]
+    fn test_restore_waves_cycle() {
+        let manifest = DataManifest::from_toc("app", TOC, Utc::now());
+        let foreign_keys = vec![
+            fk("\"public\".\"orders\"", "\"sales\".\"customers\""),
+            fk("\"sales\".\"customers\"", "\"public\".\"orders\""),
+        ];
+        let waves = restore_waves(&manifest.artifacts, &[0, 1, 2], &foreign_keys, &[]);
+        assert_eq!(waves, vec![vec![0], vec![1, 2]]);
+    }
+}
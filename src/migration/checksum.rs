@@ -2,8 +2,11 @@
 // ABOUTME: Computes and compares table checksums for data integrity verification
 
 use anyhow::{Context, Result};
+use tokio_postgres::types::Type;
 use tokio_postgres::Client;
 
+use crate::postgres::pooler;
+
 /// Result of a checksum comparison between source and target tables
 #[derive(Debug, Clone, PartialEq)]
 pub struct ChecksumResult {
@@ -45,10 +48,13 @@ pub async fn compute_table_checksum(
         ORDER BY ordinal_position
     ";
 
-    let column_rows = client
-        .query(column_query, &[&schema, &table])
-        .await
-        .context(format!("Failed to get columns for {}.{}", schema, table))?;
+    let column_rows = pooler::query(
+        client,
+        column_query,
+        &[(&schema, Type::TEXT), (&table, Type::TEXT)],
+    )
+    .await
+    .context(format!("Failed to get columns for {}.{}", schema, table))?;
 
     if column_rows.is_empty() {
         anyhow::bail!("Table {}.{} has no columns", schema, table);
@@ -85,8 +91,7 @@ pub async fn compute_table_checksum(
         concat_expr, order_by_clause, schema, table
     );
 
-    let result = client
-        .query_one(&checksum_query, &[])
+    let result = pooler::query_one(client, &checksum_query, &[])
         .await
         .context(format!(
             "Failed to compute checksum for {}.{}",
@@ -117,11 +122,36 @@ pub async fn compare_tables(
     schema: &str,
     table: &str,
 ) -> Result<ChecksumResult> {
-    tracing::info!("Comparing table {}.{}", schema, table);
+    compare_renamed_table(source_client, target_client, schema, table, schema, table).await
+}
+
+/// Compare a source table with the target table it was written to under
+/// another schema or name
+///
+/// The result is named after the source table.
+pub async fn compare_renamed_table(
+    source_client: &Client,
+    target_client: &Client,
+    schema: &str,
+    table: &str,
+    target_schema: &str,
+    target_table: &str,
+) -> Result<ChecksumResult> {
+    if (schema, table) == (target_schema, target_table) {
+        tracing::info!("Comparing table {}.{}", schema, table);
+    } else {
+        tracing::info!(
+            "Comparing table {}.{} with {}.{}",
+            schema,
+            table,
+            target_schema,
+            target_table
+        );
+    }
 
     // Compute checksums in parallel
     let source_future = compute_table_checksum(source_client, schema, table);
-    let target_future = compute_table_checksum(target_client, schema, table);
+    let target_future = compute_table_checksum(target_client, target_schema, target_table);
 
     let (source_result, target_result) = tokio::try_join!(source_future, target_future)?;
 
@@ -0,0 +1,148 @@
+// ABOUTME: Applies per-column overrides to tables loaded on the target
+// ABOUTME: Changes types and nullability, then renames columns, before tables are renamed
+
+use crate::table_rules::{ColumnOverride, ColumnOverrides};
+use crate::utils::quote_ident;
+use anyhow::{bail, Context, Result};
+use std::collections::BTreeMap;
+use tokio_postgres::Client;
+
+/// Statements applying `column_override` to `column` of `table` (`"schema"."table"`)
+///
+/// The type and nullability change under the column's current name, and the
+/// rename comes last.
+fn statements(table: &str, column: &str, column_override: &ColumnOverride) -> Vec<String> {
+    let mut statements = Vec::new();
+    if let Some(target_type) = &column_override.target_type {
+        statements.push(format!(
+            "ALTER TABLE {} ALTER COLUMN {} TYPE {} USING {}::{}",
+            table,
+            quote_ident(column),
+            target_type,
+            quote_ident(column),
+            target_type
+        ));
+    }
+    match column_override.nullable {
+        Some(true) => statements.push(format!(
+            "ALTER TABLE {} ALTER COLUMN {} DROP NOT NULL",
+            table,
+            quote_ident(column)
+        )),
+        Some(false) => statements.push(format!(
+            "ALTER TABLE {} ALTER COLUMN {} SET NOT NULL",
+            table,
+            quote_ident(column)
+        )),
+        None => {}
+    }
+    if let Some(name) = column_override.rename_to.as_deref() {
+        if name != column {
+            statements.push(format!(
+                "ALTER TABLE {} RENAME COLUMN {} TO {}",
+                table,
+                quote_ident(column),
+                quote_ident(name)
+            ));
+        }
+    }
+    statements
+}
+
+async fn table_columns(client: &Client, table: &str) -> Result<Option<Vec<String>>> {
+    let rows = client
+        .query(
+            "SELECT a.attname::text FROM pg_attribute a
+             WHERE a.attrelid = to_regclass($1) AND a.attnum > 0 AND NOT a.attisdropped",
+            &[&table],
+        )
+        .await
+        .with_context(|| format!("Failed to get columns for {}", table))?;
+    if rows.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(rows.iter().map(|row| row.get(0)).collect()))
+}
+
+/// Apply column overrides to tables on the target, returning how many columns changed
+///
+/// `tables` are keyed by `"schema"."table"` under the names the tables were
+/// loaded with. Tables not on the target are skipped, and a column an earlier
+/// run already renamed is found by its new name. Each table changes in one
+/// transaction.
+pub async fn apply(client: &Client, tables: &BTreeMap<String, ColumnOverrides>) -> Result<usize> {
+    let mut changed = 0;
+    for (table, overrides) in tables {
+        let Some(columns) = table_columns(client, table).await? else {
+            continue;
+        };
+        let mut batch = Vec::new();
+        let mut table_changed = 0;
+        for (column, column_override) in overrides {
+            let current = if columns.contains(column) {
+                column.as_str()
+            } else if let Some(name) = column_override
+                .rename_to
+                .as_deref()
+                .filter(|name| columns.iter().any(|c| c == name))
+            {
+                name
+            } else {
+                bail!("Column {} of {} not found on the target", column, table);
+            };
+            let column_statements = statements(table, current, column_override);
+            if !column_statements.is_empty() {
+                batch.extend(column_statements);
+                table_changed += 1;
+            }
+        }
+        if batch.is_empty() {
+            continue;
+        }
+        client
+            .batch_execute(&format!("BEGIN; {}; COMMIT", batch.join("; ")))
+            .await
+            .with_context(|| format!("Failed to apply column overrides to {}", table))?;
+        tracing::info!(
+            "  Applied {} column override(s) to {}",
+            table_changed,
+            table
+        );
+        changed += table_changed;
+    }
+    Ok(changed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_statements_rename_last() {
+        let column_override = ColumnOverride {
+            rename_to: Some("user_name".to_string()),
+            target_type: Some("text".to_string()),
+            nullable: Some(false),
+        };
+        assert_eq!(
+            statements("\"public\".\"users\"", "UserName", &column_override),
+            vec![
+                "ALTER TABLE \"public\".\"users\" ALTER COLUMN \"UserName\" TYPE text USING \"UserName\"::text",
+                "ALTER TABLE \"public\".\"users\" ALTER COLUMN \"UserName\" SET NOT NULL",
+                "ALTER TABLE \"public\".\"users\" RENAME COLUMN \"UserName\" TO \"user_name\"",
+            ]
+        );
+        // Already renamed by an earlier run
+        assert_eq!(
+            statements(
+                "\"public\".\"users\"",
+                "user_name",
+                &ColumnOverride {
+                    rename_to: Some("user_name".to_string()),
+                    ..Default::default()
+                }
+            ),
+            Vec::<String>::new()
+        );
+    }
+}
@@ -0,0 +1,480 @@
+// ABOUTME: Dependency graph of views, functions, and triggers in a source database
+// ABOUTME: Finds objects a filtered schema copy would break and orders objects for creation
+
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use tokio_postgres::Client;
+
+use crate::filters::ReplicationFilter;
+
+/// The kinds of schema objects the graph tracks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ObjectKind {
+    Table,
+    View,
+    MaterializedView,
+    Function,
+    Procedure,
+    Trigger,
+    /// An index on a materialized view
+    Index,
+}
+
+impl ObjectKind {
+    /// The `Type:` pg_dump writes in an object's header
+    fn dump_type(self) -> &'static str {
+        match self {
+            ObjectKind::Table => "TABLE",
+            ObjectKind::View => "VIEW",
+            ObjectKind::MaterializedView => "MATERIALIZED VIEW",
+            ObjectKind::Function => "FUNCTION",
+            ObjectKind::Procedure => "PROCEDURE",
+            ObjectKind::Trigger => "TRIGGER",
+            ObjectKind::Index => "INDEX",
+        }
+    }
+
+    fn is_relation(self) -> bool {
+        matches!(
+            self,
+            ObjectKind::Table | ObjectKind::View | ObjectKind::MaterializedView
+        )
+    }
+}
+
+/// A view, function, trigger, or the table or index they involve
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaObject {
+    pub kind: ObjectKind,
+    pub schema: String,
+    /// Relation or trigger name; functions include their argument types, as
+    /// pg_dump names them (`total(integer, public.mood)`)
+    pub name: String,
+    /// The table a trigger fires on, or the materialized view an index is on
+    pub table: Option<String>,
+}
+
+impl SchemaObject {
+    /// The name pg_dump writes in the object's `-- Name:` header
+    fn dump_name(&self) -> String {
+        match (self.kind, &self.table) {
+            (ObjectKind::Trigger, Some(table)) => format!("{} {}", table, self.name),
+            _ => self.name.clone(),
+        }
+    }
+
+    /// Whether a schema dump header belongs to this object or to a comment on it
+    pub fn matches_dump_entry(&self, name: &str, kind: &str, schema: &str) -> bool {
+        if schema != self.schema {
+            return false;
+        }
+        if kind == self.kind.dump_type() {
+            return name == self.dump_name();
+        }
+        if kind != "COMMENT" {
+            return false;
+        }
+        let commented = match (self.kind, &self.table) {
+            (ObjectKind::Trigger, Some(table)) => format!("TRIGGER {} ON {}", self.name, table),
+            _ => format!("{} {}", self.kind.dump_type(), self.name),
+        };
+        name == commented
+            || (self.kind.is_relation() && name.starts_with(&format!("COLUMN {}.", self.name)))
+    }
+}
+
+impl std::fmt::Display for SchemaObject {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let kind = self.kind.dump_type().to_lowercase();
+        match (self.kind, &self.table) {
+            (ObjectKind::Trigger | ObjectKind::Index, Some(table)) => {
+                write!(f, "{} {} on {}.{}", kind, self.name, self.schema, table)
+            }
+            _ => write!(f, "{} {}.{}", kind, self.schema, self.name),
+        }
+    }
+}
+
+/// An object that cannot be created because something it needs is left out
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BrokenObject {
+    pub object: SchemaObject,
+    /// The left-out object it depends on, directly or through other objects
+    pub missing: SchemaObject,
+}
+
+/// Views, functions, and triggers with the objects each one needs to exist first
+#[derive(Debug, Clone, Default)]
+pub struct DependencyGraph {
+    objects: Vec<SchemaObject>,
+    /// Indexes into `objects` of what each object depends on
+    dependencies: Vec<Vec<usize>>,
+}
+
+/// Tracked objects, keyed by catalog (`c` relation, `p` function, `t` trigger) and oid.
+/// Objects belonging to extensions are left out, as pg_dump leaves them out.
+const OBJECTS_QUERY: &str = "SELECT 'c'::text, c.oid::int8, c.relkind::text, n.nspname::text, \
+         c.relname::text, NULL::text \
+     FROM pg_class c JOIN pg_namespace n ON n.oid = c.relnamespace \
+     WHERE c.relkind IN ('r', 'p', 'v', 'm') \
+       AND NOT EXISTS (SELECT 1 FROM pg_depend e WHERE e.classid = 'pg_class'::regclass \
+                       AND e.objid = c.oid AND e.deptype = 'e') \
+       AND n.nspname NOT IN ('pg_catalog', 'information_schema') \
+       AND n.nspname NOT LIKE 'pg\\_%' \
+     UNION ALL \
+     SELECT 'p', p.oid::int8, p.prokind::text, n.nspname::text, \
+         p.proname || '(' || array_to_string(ARRAY( \
+             SELECT format_type(a.type, NULL) \
+             FROM unnest(p.proargtypes) WITH ORDINALITY AS a(type, position) \
+             ORDER BY a.position), ', ') || ')', \
+         NULL \
+     FROM pg_proc p JOIN pg_namespace n ON n.oid = p.pronamespace \
+     WHERE p.prokind IN ('f', 'p') \
+       AND NOT EXISTS (SELECT 1 FROM pg_depend e WHERE e.classid = 'pg_proc'::regclass \
+                       AND e.objid = p.oid AND e.deptype = 'e') \
+       AND n.nspname NOT IN ('pg_catalog', 'information_schema') \
+       AND n.nspname NOT LIKE 'pg\\_%' \
+     UNION ALL \
+     SELECT 't', t.oid::int8, 'T', n.nspname::text, t.tgname::text, c.relname::text \
+     FROM pg_trigger t \
+     JOIN pg_class c ON c.oid = t.tgrelid \
+     JOIN pg_namespace n ON n.oid = c.relnamespace \
+     WHERE NOT t.tgisinternal \
+       AND n.nspname NOT IN ('pg_catalog', 'information_schema') \
+       AND n.nspname NOT LIKE 'pg\\_%' \
+     UNION ALL \
+     SELECT 'c', i.indexrelid::int8, 'I', n.nspname::text, ic.relname::text, c.relname::text \
+     FROM pg_index i \
+     JOIN pg_class c ON c.oid = i.indrelid AND c.relkind = 'm' \
+     JOIN pg_class ic ON ic.oid = i.indexrelid \
+     JOIN pg_namespace n ON n.oid = c.relnamespace \
+     WHERE n.nspname NOT IN ('pg_catalog', 'information_schema') \
+       AND n.nspname NOT LIKE 'pg\\_%'";
+
+/// (dependent catalog, oid, referenced catalog, oid): what view queries,
+/// SQL-standard function bodies, function signatures, triggers, and
+/// materialized view indexes refer to
+const EDGES_QUERY: &str = "SELECT 'c'::text, r.ev_class::int8, \
+         CASE d.refclassid WHEN 'pg_class'::regclass THEN 'c' ELSE 'p' END, d.refobjid::int8 \
+     FROM pg_rewrite r \
+     JOIN pg_depend d ON d.classid = 'pg_rewrite'::regclass AND d.objid = r.oid \
+     WHERE d.refclassid IN ('pg_class'::regclass, 'pg_proc'::regclass) \
+       AND d.refobjid <> r.ev_class \
+     UNION ALL \
+     SELECT 'p', d.objid::int8, \
+         CASE d.refclassid WHEN 'pg_class'::regclass THEN 'c' ELSE 'p' END, d.refobjid::int8 \
+     FROM pg_depend d \
+     WHERE d.classid = 'pg_proc'::regclass \
+       AND d.refclassid IN ('pg_class'::regclass, 'pg_proc'::regclass) \
+       AND d.deptype = 'n' AND d.refobjid <> d.objid \
+     UNION ALL \
+     SELECT 'p', d.objid::int8, 'c', ty.typrelid::int8 \
+     FROM pg_depend d JOIN pg_type ty ON ty.oid = d.refobjid \
+     WHERE d.classid = 'pg_proc'::regclass AND d.refclassid = 'pg_type'::regclass \
+       AND ty.typrelid <> 0 \
+     UNION ALL \
+     SELECT 't', t.oid::int8, 'c', t.tgrelid::int8 FROM pg_trigger t WHERE NOT t.tgisinternal \
+     UNION ALL \
+     SELECT 't', t.oid::int8, 'p', t.tgfoid::int8 FROM pg_trigger t WHERE NOT t.tgisinternal \
+     UNION ALL \
+     SELECT 'c', i.indexrelid::int8, 'c', i.indrelid::int8 \
+     FROM pg_index i JOIN pg_class c ON c.oid = i.indrelid AND c.relkind = 'm'";
+
+impl DependencyGraph {
+    /// Read the graph from the connected database
+    pub async fn load(client: &Client) -> Result<Self> {
+        // Qualify type names in function signatures the way pg_dump does
+        client
+            .batch_execute("SET search_path = pg_catalog")
+            .await
+            .context("Failed to set search_path")?;
+        let rows = async {
+            let objects = client.query(OBJECTS_QUERY, &[]).await?;
+            let edges = client.query(EDGES_QUERY, &[]).await?;
+            Ok::<_, tokio_postgres::Error>((objects, edges))
+        }
+        .await;
+        client
+            .batch_execute("RESET search_path")
+            .await
+            .context("Failed to reset search_path")?;
+        let (object_rows, edge_rows) = rows.context("Failed to read object dependencies")?;
+
+        let mut graph = DependencyGraph::default();
+        let mut index: HashMap<(String, i64), usize> = HashMap::new();
+        for row in &object_rows {
+            let kind = match row.get::<_, String>(2).as_str() {
+                "r" | "p" if row.get::<_, String>(0) == "c" => ObjectKind::Table,
+                "v" => ObjectKind::View,
+                "m" => ObjectKind::MaterializedView,
+                "f" => ObjectKind::Function,
+                "p" => ObjectKind::Procedure,
+                "T" => ObjectKind::Trigger,
+                "I" => ObjectKind::Index,
+                _ => continue,
+            };
+            index.insert((row.get(0), row.get(1)), graph.objects.len());
+            graph.add(SchemaObject {
+                kind,
+                schema: row.get(3),
+                name: row.get(4),
+                table: row.get(5),
+            });
+        }
+        for row in &edge_rows {
+            let dependent = index.get(&(row.get(0), row.get(1)));
+            let dependency = index.get(&(row.get(2), row.get(3)));
+            if let (Some(&dependent), Some(&dependency)) = (dependent, dependency) {
+                graph.depend(dependent, dependency);
+            }
+        }
+        Ok(graph)
+    }
+
+    fn add(&mut self, object: SchemaObject) -> usize {
+        self.objects.push(object);
+        self.dependencies.push(Vec::new());
+        self.objects.len() - 1
+    }
+
+    fn depend(&mut self, dependent: usize, dependency: usize) {
+        if dependent != dependency && !self.dependencies[dependent].contains(&dependency) {
+            self.dependencies[dependent].push(dependency);
+        }
+    }
+
+    /// Object indexes ordered so each comes after everything it depends on
+    ///
+    /// Objects keep their loaded order otherwise. PostgreSQL does not allow
+    /// dependency cycles among these objects, but any would be appended last.
+    fn creation_order(&self) -> Vec<usize> {
+        let mut placed = vec![false; self.objects.len()];
+        let mut order = Vec::with_capacity(self.objects.len());
+        loop {
+            let ready: Vec<usize> = (0..self.objects.len())
+                .filter(|&i| !placed[i] && self.dependencies[i].iter().all(|&d| placed[d]))
+                .collect();
+            if ready.is_empty() {
+                order.extend((0..self.objects.len()).filter(|&i| !placed[i]));
+                return order;
+            }
+            for &i in &ready {
+                placed[i] = true;
+            }
+            order.extend(ready);
+        }
+    }
+
+    /// Objects that `included` keeps but that depend, directly or through
+    /// other objects, on one it leaves out; in creation order
+    pub fn broken_objects(&self, included: impl Fn(&SchemaObject) -> bool) -> Vec<BrokenObject> {
+        let mut missing: Vec<Option<usize>> = vec![None; self.objects.len()];
+        let mut broken = Vec::new();
+        for i in self.creation_order() {
+            if !included(&self.objects[i]) {
+                continue;
+            }
+            let cause = self.dependencies[i].iter().find_map(|&d| {
+                if !included(&self.objects[d]) {
+                    Some(d)
+                } else {
+                    missing[d]
+                }
+            });
+            if let Some(cause) = cause {
+                missing[i] = Some(cause);
+                broken.push(BrokenObject {
+                    object: self.objects[i].clone(),
+                    missing: self.objects[cause].clone(),
+                });
+            }
+        }
+        broken
+    }
+}
+
+/// What a filtered `pg_dump --schema-only` of one database writes
+///
+/// Mirrors the flags [`dump_schema`](super::dump_schema) passes: excluded
+/// schemas and tables are left out, and with `--include-tables` only the
+/// listed tables and their triggers and indexes are written.
+#[derive(Debug, Clone, Default)]
+pub struct DumpScope {
+    excluded_schemas: Vec<String>,
+    /// `"schema"."table"`
+    excluded_tables: Vec<String>,
+    /// `"schema"."table"`; `None` dumps every relation
+    included_tables: Option<Vec<String>>,
+}
+
+impl DumpScope {
+    /// The scope of the schema dump for `db_name`
+    pub async fn for_database(
+        source_url: &str,
+        filter: &ReplicationFilter,
+        db_name: &str,
+    ) -> Result<Self> {
+        let unquote = |schema: String| schema.trim_matches('"').to_string();
+        Ok(Self {
+            excluded_schemas: super::dump::get_excluded_schemas_for_db(source_url, filter, db_name)
+                .await?
+                .into_iter()
+                .map(unquote)
+                .collect(),
+            excluded_tables: super::dump::get_schema_excluded_tables_for_db(filter, db_name)
+                .unwrap_or_default(),
+            included_tables: super::dump::get_included_tables_for_db(filter, db_name)
+                .filter(|tables| !tables.is_empty()),
+        })
+    }
+
+    fn includes_relation(&self, schema: &str, name: &str) -> bool {
+        let quoted = format!("\"{}\".\"{}\"", schema, name);
+        match &self.included_tables {
+            Some(included) => included.contains(&quoted),
+            None => !self.excluded_tables.contains(&quoted),
+        }
+    }
+
+    /// Whether the schema dump writes `object`
+    pub fn includes(&self, object: &SchemaObject) -> bool {
+        if self.excluded_schemas.contains(&object.schema) {
+            return false;
+        }
+        match object.kind {
+            ObjectKind::Table | ObjectKind::View | ObjectKind::MaterializedView => {
+                self.includes_relation(&object.schema, &object.name)
+            }
+            ObjectKind::Trigger | ObjectKind::Index => object
+                .table
+                .as_deref()
+                .is_some_and(|table| self.includes_relation(&object.schema, table)),
+            // pg_dump --table writes no functions
+            ObjectKind::Function | ObjectKind::Procedure => self.included_tables.is_none(),
+        }
+    }
+
+    /// Whether the filters leave anything out of the schema dump
+    pub fn is_filtered(&self) -> bool {
+        !self.excluded_schemas.is_empty()
+            || !self.excluded_tables.is_empty()
+            || self.included_tables.is_some()
+    }
+}
+
+/// Objects in `db_name` that the filtered schema copy would fail to create,
+/// because they depend on objects the filters leave out
+pub async fn broken_by_filter(
+    source_url: &str,
+    filter: &ReplicationFilter,
+    db_name: &str,
+) -> Result<Vec<BrokenObject>> {
+    let scope = DumpScope::for_database(source_url, filter, db_name).await?;
+    if !scope.is_filtered() {
+        return Ok(Vec::new());
+    }
+    let client = crate::postgres::connect_with_retry(source_url)
+        .await
+        .context("Failed to connect to source to read object dependencies")?;
+    let graph = DependencyGraph::load(&client).await?;
+    Ok(graph.broken_objects(|object| scope.includes(object)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn object(kind: ObjectKind, schema: &str, name: &str) -> SchemaObject {
+        SchemaObject {
+            kind,
+            schema: schema.to_string(),
+            name: name.to_string(),
+            table: None,
+        }
+    }
+
+    /// ext.x <- v1 <- v2 <- mv <- mv_idx, plus trigger trg on a calling trgf()
+    fn graph() -> DependencyGraph {
+        let mut graph = DependencyGraph::default();
+        let mv_idx = graph.add(SchemaObject {
+            table: Some("mv".to_string()),
+            ..object(ObjectKind::Index, "public", "mv_idx")
+        });
+        let mv = graph.add(object(ObjectKind::MaterializedView, "public", "mv"));
+        let v2 = graph.add(object(ObjectKind::View, "public", "v2"));
+        let v1 = graph.add(object(ObjectKind::View, "public", "v1"));
+        let x = graph.add(object(ObjectKind::Table, "ext", "x"));
+        let a = graph.add(object(ObjectKind::Table, "public", "a"));
+        let trgf = graph.add(object(ObjectKind::Function, "public", "trgf()"));
+        let trg = graph.add(SchemaObject {
+            table: Some("a".to_string()),
+            ..object(ObjectKind::Trigger, "public", "trg")
+        });
+        graph.depend(mv_idx, mv);
+        graph.depend(mv, v2);
+        graph.depend(v2, v1);
+        graph.depend(v1, x);
+        graph.depend(trg, a);
+        graph.depend(trg, trgf);
+        graph
+    }
+
+    #[test]
+    fn test_creation_order() {
+        let graph = graph();
+        let names: Vec<&str> = graph
+            .creation_order()
+            .into_iter()
+            .map(|i| graph.objects[i].name.as_str())
+            .collect();
+        assert_eq!(
+            names,
+            vec!["x", "a", "trgf()", "v1", "trg", "v2", "mv", "mv_idx"]
+        );
+    }
+
+    #[test]
+    fn test_broken_objects_follow_dependencies() {
+        let scope = DumpScope {
+            excluded_schemas: vec!["ext".to_string()],
+            ..Default::default()
+        };
+        let broken = graph().broken_objects(|object| scope.includes(object));
+        let names: Vec<&str> = broken
+            .iter()
+            .map(|broken| broken.object.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["v1", "v2", "mv", "mv_idx"]);
+        assert!(broken.iter().all(|broken| broken.missing.name == "x"));
+
+        // --include-tables writes the trigger but not the function it calls
+        let scope = DumpScope {
+            included_tables: Some(vec![r#""public"."a""#.to_string()]),
+            ..Default::default()
+        };
+        let broken = graph().broken_objects(|object| scope.includes(object));
+        assert_eq!(broken.len(), 1);
+        assert_eq!(broken[0].object.to_string(), "trigger trg on public.a");
+        assert_eq!(broken[0].missing.to_string(), "function public.trgf()");
+    }
+
+    #[test]
+    fn test_matches_dump_entry() {
+        let view = object(ObjectKind::View, "public", "v1");
+        assert!(view.matches_dump_entry("v1", "VIEW", "public"));
+        assert!(view.matches_dump_entry("VIEW v1", "COMMENT", "public"));
+        assert!(view.matches_dump_entry("COLUMN v1.id", "COMMENT", "public"));
+        assert!(!view.matches_dump_entry("v1", "VIEW", "other"));
+        assert!(!view.matches_dump_entry("v10", "VIEW", "public"));
+
+        let trigger = SchemaObject {
+            table: Some("a".to_string()),
+            ..object(ObjectKind::Trigger, "public", "trg")
+        };
+        assert!(trigger.matches_dump_entry("a trg", "TRIGGER", "public"));
+        assert!(trigger.matches_dump_entry("TRIGGER trg ON a", "COMMENT", "public"));
+
+        let function = object(ObjectKind::Function, "public", "f(integer, public.mood)");
+        assert!(function.matches_dump_entry("f(integer, public.mood)", "FUNCTION", "public"));
+    }
+}
@@ -0,0 +1,323 @@
+// ABOUTME: Per-table row counts and checksums kept on the target for init --differential
+// ABOUTME: Picks the tables whose source data drifted since they were last copied
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use anyhow::{Context, Result};
+use tokio_postgres::Client;
+
+use crate::table_rules::ForeignKey;
+
+/// Name of the table, in each target's `public` schema, holding the state of
+/// every source table as of its last copy
+pub const TABLE_SNAPSHOTS_TABLE: &str = "_replicator_table_snapshots";
+
+/// Row count and checksum of one source table
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableState {
+    pub schema: String,
+    pub table: String,
+    pub row_count: i64,
+    pub checksum: String,
+}
+
+impl TableState {
+    /// The table as `"schema"."table"`
+    pub fn quoted(&self) -> String {
+        format!(
+            "{}.{}",
+            crate::utils::quote_ident(&self.schema),
+            crate::utils::quote_ident(&self.table)
+        )
+    }
+}
+
+/// States of `tables` (`"schema"."table"`) as `client` sees them, keyed by name
+///
+/// Run it in a transaction at the copy's snapshot to describe exactly what
+/// was copied.
+pub async fn table_states(
+    client: &Client,
+    tables: &[String],
+) -> Result<BTreeMap<String, TableState>> {
+    let mut states = BTreeMap::new();
+    for table in tables {
+        let row = client
+            .query_one(
+                "SELECT n.nspname::text, c.relname::text
+                 FROM pg_catalog.pg_class c
+                 JOIN pg_catalog.pg_namespace n ON n.oid = c.relnamespace
+                 WHERE c.oid = $1::text::regclass",
+                &[table],
+            )
+            .await
+            .with_context(|| format!("Failed to look up table {}", table))?;
+        let (schema, name): (String, String) = (row.get(0), row.get(1));
+        let (checksum, row_count) =
+            super::checksum::compute_table_checksum(client, &schema, &name).await?;
+        states.insert(
+            table.clone(),
+            TableState {
+                schema,
+                table: name,
+                row_count,
+                checksum,
+            },
+        );
+    }
+    Ok(states)
+}
+
+/// Whether a table drifted from its recorded state by more than `threshold` percent
+///
+/// An unchanged checksum is never drift. A changed one is when the row count
+/// moved by more than `threshold` percent of the recorded count, so with a
+/// threshold of 0 any change is.
+pub fn drifted(recorded: &TableState, current: &TableState, threshold: u32) -> bool {
+    if recorded.checksum == current.checksum {
+        return false;
+    }
+    let moved = current.row_count.abs_diff(recorded.row_count);
+    threshold == 0 || moved * 100 > u64::from(threshold) * recorded.row_count.max(1) as u64
+}
+
+/// Tables to copy again, as `"schema"."table"`
+///
+/// These are the `current` tables that drifted or have no recorded state,
+/// and every current table that references one of them through a foreign
+/// key, since emptying a parent empties its children too.
+pub fn tables_to_refresh(
+    current: &BTreeMap<String, TableState>,
+    recorded: &BTreeMap<String, TableState>,
+    foreign_keys: &[ForeignKey],
+    threshold: u32,
+) -> Vec<String> {
+    let mut refresh: BTreeSet<String> = current
+        .iter()
+        .filter(|(table, state)| {
+            recorded
+                .get(*table)
+                .is_none_or(|recorded| drifted(recorded, state, threshold))
+        })
+        .map(|(table, _)| table.clone())
+        .collect();
+    loop {
+        let pulled: BTreeSet<String> = foreign_keys
+            .iter()
+            .filter(|fk| refresh.contains(&fk.parent) && !refresh.contains(&fk.child))
+            .filter(|fk| current.contains_key(&fk.child))
+            .map(|fk| fk.child.clone())
+            .collect();
+        if pulled.is_empty() {
+            break;
+        }
+        refresh.extend(pulled);
+    }
+    refresh.into_iter().collect()
+}
+
+/// Tables outside `refresh` that reference one of its tables through a
+/// foreign key, as `"schema"."table"`
+///
+/// They keep the target from emptying `refresh`, since their rows would point
+/// at rows that are gone.
+pub fn referencing_tables(refresh: &[String], foreign_keys: &[ForeignKey]) -> Vec<String> {
+    foreign_keys
+        .iter()
+        .filter(|fk| refresh.contains(&fk.parent) && !refresh.contains(&fk.child))
+        .map(|fk| fk.child.clone())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+/// The table states recorded in a target for the source `source_id`; `None`
+/// when none were
+pub async fn load_states(
+    client: &Client,
+    source_id: &str,
+) -> Result<Option<BTreeMap<String, TableState>>> {
+    let exists: bool = client
+        .query_one(
+            &format!(
+                "SELECT to_regclass('public.\"{}\"') IS NOT NULL",
+                TABLE_SNAPSHOTS_TABLE
+            ),
+            &[],
+        )
+        .await
+        .context("Failed to look up _replicator_table_snapshots")?
+        .get(0);
+    if !exists {
+        return Ok(None);
+    }
+    let rows = client
+        .query(
+            &format!(
+                "SELECT schema_name, table_name, row_count, checksum
+                 FROM public.\"{}\" WHERE source_id = $1",
+                TABLE_SNAPSHOTS_TABLE
+            ),
+            &[&source_id],
+        )
+        .await
+        .context("Failed to read _replicator_table_snapshots")?;
+    if rows.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(
+        rows.iter()
+            .map(|row| {
+                let state = TableState {
+                    schema: row.get(0),
+                    table: row.get(1),
+                    row_count: row.get(2),
+                    checksum: row.get(3),
+                };
+                (state.quoted(), state)
+            })
+            .collect(),
+    ))
+}
+
+/// Replace the table states recorded in a target for the source `source_id`
+pub async fn record_states(
+    client: &Client,
+    source_id: &str,
+    states: &BTreeMap<String, TableState>,
+) -> Result<()> {
+    client
+        .batch_execute(&format!(
+            "CREATE TABLE IF NOT EXISTS public.\"{}\" (
+                source_id TEXT NOT NULL,
+                schema_name TEXT NOT NULL,
+                table_name TEXT NOT NULL,
+                row_count BIGINT NOT NULL,
+                checksum TEXT NOT NULL,
+                recorded_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+                PRIMARY KEY (source_id, schema_name, table_name)
+            )",
+            TABLE_SNAPSHOTS_TABLE
+        ))
+        .await
+        .context("Failed to create _replicator_table_snapshots table in target")?;
+
+    let schemas: Vec<&str> = states.values().map(|state| state.schema.as_str()).collect();
+    let tables: Vec<&str> = states.values().map(|state| state.table.as_str()).collect();
+    let row_counts: Vec<i64> = states.values().map(|state| state.row_count).collect();
+    let checksums: Vec<&str> = states
+        .values()
+        .map(|state| state.checksum.as_str())
+        .collect();
+    client
+        .batch_execute("BEGIN")
+        .await
+        .context("Failed to start transaction for _replicator_table_snapshots")?;
+    let result = async {
+        client
+            .execute(
+                &format!(
+                    "DELETE FROM public.\"{}\" WHERE source_id = $1",
+                    TABLE_SNAPSHOTS_TABLE
+                ),
+                &[&source_id],
+            )
+            .await?;
+        client
+            .execute(
+                &format!(
+                    "INSERT INTO public.\"{}\" (source_id, schema_name, table_name, row_count, checksum)
+                     SELECT $1, * FROM unnest($2::text[], $3::text[], $4::bigint[], $5::text[])",
+                    TABLE_SNAPSHOTS_TABLE
+                ),
+                &[&source_id, &schemas, &tables, &row_counts, &checksums],
+            )
+            .await?;
+        client.batch_execute("COMMIT").await
+    }
+    .await;
+    if result.is_err() {
+        let _ = client.batch_execute("ROLLBACK").await;
+    }
+    result.context("Failed to record table states in _replicator_table_snapshots")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(table: &str, row_count: i64, checksum: &str) -> (String, TableState) {
+        let state = TableState {
+            schema: "public".to_string(),
+            table: table.to_string(),
+            row_count,
+            checksum: checksum.to_string(),
+        };
+        (state.quoted(), state)
+    }
+
+    #[test]
+    fn test_drifted() {
+        let (_, recorded) = state("orders", 1000, "a");
+        let (_, same) = state("orders", 1000, "a");
+        let (_, updated) = state("orders", 1000, "b");
+        let (_, grown) = state("orders", 1100, "c");
+        assert!(!drifted(&recorded, &same, 0));
+        assert!(drifted(&recorded, &updated, 0));
+        assert!(!drifted(&recorded, &updated, 5));
+        assert!(drifted(&recorded, &grown, 5));
+        assert!(!drifted(&recorded, &grown, 10));
+    }
+
+    #[test]
+    fn test_tables_to_refresh() {
+        let recorded: BTreeMap<_, _> = [
+            state("users", 10, "a"),
+            state("orders", 100, "b"),
+            state("items", 500, "c"),
+            state("logs", 50, "d"),
+        ]
+        .into_iter()
+        .collect();
+        let current: BTreeMap<_, _> = [
+            state("users", 11, "changed"),
+            state("orders", 100, "b"),
+            state("items", 500, "c"),
+            state("logs", 50, "d"),
+            state("tags", 3, "e"),
+        ]
+        .into_iter()
+        .collect();
+        let fk = |child: &str, parent: &str| ForeignKey {
+            child: format!("\"public\".\"{}\"", child),
+            child_columns: vec!["id".to_string()],
+            parent: format!("\"public\".\"{}\"", parent),
+            parent_columns: vec!["id".to_string()],
+        };
+        let foreign_keys = vec![
+            fk("orders", "users"),
+            fk("items", "orders"),
+            fk("archived", "users"),
+        ];
+        assert_eq!(
+            tables_to_refresh(&current, &recorded, &foreign_keys, 0),
+            vec![
+                "\"public\".\"items\"",
+                "\"public\".\"orders\"",
+                "\"public\".\"tags\"",
+                "\"public\".\"users\""
+            ]
+        );
+        assert_eq!(
+            tables_to_refresh(&current, &recorded, &foreign_keys, 20),
+            vec!["\"public\".\"tags\""]
+        );
+
+        let refresh = tables_to_refresh(&current, &recorded, &foreign_keys, 0);
+        assert_eq!(
+            referencing_tables(&refresh, &foreign_keys),
+            vec!["\"public\".\"archived\""]
+        );
+    }
+}
@@ -13,8 +13,13 @@ pub async fn dump_globals(source_url: &str, output_path: &str) -> Result<()> {
     tracing::info!("Dumping global objects to {}", output_path);
 
     // Parse URL and create .pgpass file for secure authentication
-    let parts = crate::utils::parse_postgres_url(source_url)
-        .with_context(|| format!("Failed to parse source URL: {}", source_url))?;
+    let mut parts = crate::utils::parse_postgres_url(source_url).with_context(|| {
+        format!(
+            "Failed to parse source URL: {}",
+            crate::redact::redact(source_url)
+        )
+    })?;
+    crate::postgres::iam::apply_to_parts(&mut parts).await?;
     let pgpass = crate::utils::PgPassFile::new(&parts)
         .context("Failed to create .pgpass file for authentication")?;
 
@@ -37,7 +42,7 @@ pub async fn dump_globals(source_url: &str, output_path: &str) -> Result<()> {
                 .arg(format!("--file={}", output_path_owned))
                 .env("PGPASSFILE", pgpass.path())
                 .stdout(Stdio::inherit())
-                .stderr(Stdio::inherit());
+                .stderr(Stdio::piped());
 
             // Add username if specified
             if let Some(user) = &parts.user {
@@ -57,7 +62,7 @@ pub async fn dump_globals(source_url: &str, output_path: &str) -> Result<()> {
             // Set connection timeout to prevent hangs on pool exhaustion
             cmd.env("PGCONNECT_TIMEOUT", "30"); // Fail after 30 seconds
 
-            cmd.status().context(
+            cmd.spawn().context(
                 "Failed to execute pg_dumpall. Is PostgreSQL client installed?\n\
                  Install with:\n\
                  - Ubuntu/Debian: sudo apt-get install postgresql-client\n\
@@ -439,12 +444,119 @@ fn escape_single_quotes(value: &str) -> String {
     value.replace('\'', "''")
 }
 
+/// Where objects stored in custom source tablespaces are created on the target
+///
+/// Managed targets rarely have the source's tablespaces, so schema dumps leave
+/// tablespaces out unless a mapping is given. With one, objects in a mapped
+/// tablespace are created in its target tablespace and all others in the
+/// target database's default.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TablespaceMap {
+    mappings: Vec<(String, String)>,
+}
+
+impl TablespaceMap {
+    /// Parse `--map-tablespace` values of the form `source=target`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use database_replicator::migration::dump::TablespaceMap;
+    /// let map = TablespaceMap::from_cli(&["fastssd=pg_default".to_string()]).unwrap();
+    /// assert_eq!(map.target_for("fastssd"), Some("pg_default"));
+    /// assert!(TablespaceMap::from_cli(&["fastssd".to_string()]).is_err());
+    /// ```
+    pub fn from_cli(values: &[String]) -> Result<Self> {
+        let mut mappings: Vec<(String, String)> = Vec::new();
+        for value in values {
+            let (source, target) = value
+                .split_once('=')
+                .map(|(source, target)| (source.trim(), target.trim()))
+                .filter(|(source, target)| !source.is_empty() && !target.is_empty())
+                .with_context(|| {
+                    format!(
+                        "Invalid --map-tablespace '{}': expected SOURCE=TARGET, e.g. fastssd=pg_default",
+                        value
+                    )
+                })?;
+            if mappings.iter().any(|(existing, _)| existing == source) {
+                anyhow::bail!("Tablespace '{}' is mapped more than once", source);
+            }
+            mappings.push((source.to_string(), target.to_string()));
+        }
+        Ok(Self { mappings })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.mappings.is_empty()
+    }
+
+    /// Target tablespace for a source tablespace, if it is mapped
+    pub fn target_for(&self, source: &str) -> Option<&str> {
+        self.mappings
+            .iter()
+            .find(|(mapped, _)| mapped == source)
+            .map(|(_, target)| target.as_str())
+    }
+
+    /// Fail unless every mapped target tablespace exists on the target
+    pub async fn check_targets(&self, target_client: &tokio_postgres::Client) -> Result<()> {
+        let rows = target_client
+            .query("SELECT spcname FROM pg_tablespace", &[])
+            .await
+            .context("Failed to list target tablespaces")?;
+        let existing: Vec<String> = rows.iter().map(|row| row.get(0)).collect();
+        let missing: Vec<&str> = self
+            .mappings
+            .iter()
+            .map(|(_, target)| target.as_str())
+            .filter(|target| !existing.iter().any(|name| name == target))
+            .collect();
+        if !missing.is_empty() {
+            anyhow::bail!(
+                "Tablespace(s) {} from --map-tablespace do not exist on the target. \
+                 Available: {}",
+                missing.join(", "),
+                existing.join(", ")
+            );
+        }
+        Ok(())
+    }
+}
+
 /// Dump schema (DDL) for a specific database
+///
+/// Tablespace assignments are left out, so every object is created in the
+/// target database's default tablespace.
 pub async fn dump_schema(
     source_url: &str,
     database: &str,
     output_path: &str,
     filter: &ReplicationFilter,
+) -> Result<()> {
+    dump_schema_with_tablespaces(
+        source_url,
+        database,
+        output_path,
+        filter,
+        &TablespaceMap::default(),
+        None,
+    )
+    .await
+}
+
+/// Dump schema like [`dump_schema`], creating objects from the tablespaces in
+/// `tablespaces` in their mapped target tablespace
+///
+/// With `versions`, options and session settings the target's version does
+/// not have are kept out of the dump.
+pub async fn dump_schema_with_tablespaces(
+    source_url: &str,
+    database: &str,
+    output_path: &str,
+    filter: &ReplicationFilter,
+    tablespaces: &TablespaceMap,
+    versions: Option<&crate::postgres::versions::VersionMatrix>,
 ) -> Result<()> {
     tracing::info!(
         "Dumping schema for database '{}' to {}",
@@ -453,8 +565,13 @@ pub async fn dump_schema(
     );
 
     // Parse URL and create .pgpass file for secure authentication
-    let parts = crate::utils::parse_postgres_url(source_url)
-        .with_context(|| format!("Failed to parse source URL: {}", source_url))?;
+    let mut parts = crate::utils::parse_postgres_url(source_url).with_context(|| {
+        format!(
+            "Failed to parse source URL: {}",
+            crate::redact::redact(source_url)
+        )
+    })?;
+    crate::postgres::iam::apply_to_parts(&mut parts).await?;
     let pgpass = crate::utils::PgPassFile::new(&parts)
         .context("Failed to create .pgpass file for authentication")?;
 
@@ -464,6 +581,14 @@ pub async fn dump_schema(
     // Collect filter options
     let exclude_tables = get_schema_excluded_tables_for_db(filter, database);
     let include_tables = get_included_tables_for_db(filter, database);
+    let exclude_schemas = get_excluded_schemas_for_db(source_url, filter, database).await?;
+    let version_flags = versions.map(|v| v.schema_dump_flags()).unwrap_or_default();
+    if !version_flags.is_empty() {
+        tracing::info!(
+            "  Passing {} to pg_dump for the older target",
+            version_flags.join(" ")
+        );
+    }
 
     // Wrap subprocess execution with retry logic
     crate::utils::retry_subprocess_with_backoff(
@@ -474,6 +599,12 @@ pub async fn dump_schema(
                 .arg("--no-privileges") // We'll handle privileges separately
                 .arg("--verbose"); // Show progress
 
+            // Tablespaces are only dumped when they are rewritten afterwards
+            if tablespaces.is_empty() {
+                cmd.arg("--no-tablespaces");
+            }
+            cmd.args(&version_flags);
+
             // Add table filtering if specified
             // Only exclude explicit exclude_tables from schema dump (NOT schema_only or predicate tables)
             if let Some(ref exclude) = exclude_tables {
@@ -493,6 +624,16 @@ pub async fn dump_schema(
                 }
             }
 
+            for schema in &exclude_schemas {
+                cmd.arg("--exclude-schema").arg(schema);
+            }
+
+            // This tool's metadata stays with the replica that holds it
+            cmd.arg("--exclude-schema")
+                .arg(crate::audit::AUDIT_SCHEMA)
+                .arg("--exclude-table")
+                .arg(crate::topology::METADATA_TABLE_PATTERN);
+
             cmd.arg("--host")
                 .arg(&parts.host)
                 .arg("--port")
@@ -502,7 +643,7 @@ pub async fn dump_schema(
                 .arg(format!("--file={}", output_path_owned))
                 .env("PGPASSFILE", pgpass.path())
                 .stdout(Stdio::inherit())
-                .stderr(Stdio::inherit());
+                .stderr(Stdio::piped());
 
             // Add username if specified
             if let Some(user) = &parts.user {
@@ -522,7 +663,7 @@ pub async fn dump_schema(
             // Set connection timeout to prevent hangs on pool exhaustion
             cmd.env("PGCONNECT_TIMEOUT", "30"); // Fail after 30 seconds
 
-            cmd.status().context(
+            cmd.spawn().context(
                 "Failed to execute pg_dump. Is PostgreSQL client installed?\n\
                  Install with:\n\
                  - Ubuntu/Debian: sudo apt-get install postgresql-client\n\
@@ -549,10 +690,272 @@ pub async fn dump_schema(
         )
     })?;
 
+    if !tablespaces.is_empty() {
+        map_tablespaces_in_schema(output_path, tablespaces)?;
+    }
+    let settings = versions
+        .map(|v| v.unsupported_dump_settings())
+        .unwrap_or_default();
+    if remove_session_settings(output_path, &settings)? > 0 {
+        tracing::info!(
+            "  Left out {} from the schema dump; the target does not have them",
+            settings.join(", ")
+        );
+    }
+
     tracing::info!("✓ Schema dumped successfully");
     Ok(())
 }
 
+/// Comments out the `SET <name> = ...;` lines of a dump for each of `names`
+///
+/// Returns the number of lines commented out.
+pub fn remove_session_settings(path: &str, names: &[&str]) -> Result<usize> {
+    if names.is_empty() {
+        return Ok(0);
+    }
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read schema dump at {}", path))?;
+
+    let mut updated = String::with_capacity(content.len());
+    let mut removed = 0;
+    for line in content.lines() {
+        let is_setting = names
+            .iter()
+            .any(|name| line.starts_with(&format!("SET {} = ", name)));
+        if is_setting {
+            updated.push_str("-- ");
+            removed += 1;
+        }
+        updated.push_str(line);
+        updated.push('\n');
+    }
+
+    if removed > 0 {
+        fs::write(path, updated)
+            .with_context(|| format!("Failed to write schema dump to {}", path))?;
+    }
+    Ok(removed)
+}
+
+/// Rewrites the `SET default_tablespace` statements of a schema dump
+///
+/// Mapped tablespaces are replaced by their targets; unmapped ones, and
+/// targets that are `pg_default`, become the database default. Partitioned
+/// tables cannot name the default tablespace explicitly, so it is never
+/// written out.
+fn map_tablespaces_in_schema(path: &str, tablespaces: &TablespaceMap) -> Result<()> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read schema dump at {}", path))?;
+
+    let mut updated = String::with_capacity(content.len());
+    let mut unmapped = BTreeSet::new();
+    for line in content.lines() {
+        match parse_default_tablespace(line) {
+            Some(source) if !source.is_empty() => {
+                let target = match tablespaces.target_for(&source) {
+                    Some("pg_default") => String::new(),
+                    Some(target) => target.to_string(),
+                    None => {
+                        unmapped.insert(source);
+                        String::new()
+                    }
+                };
+                updated.push_str(&format!(
+                    "SET default_tablespace = '{}';",
+                    escape_single_quotes(&target)
+                ));
+            }
+            _ => updated.push_str(line),
+        }
+        updated.push('\n');
+    }
+
+    if !unmapped.is_empty() {
+        tracing::warn!(
+            "  ⚠ No --map-tablespace for {}; those objects use the target's default tablespace",
+            unmapped.into_iter().collect::<Vec<_>>().join(", ")
+        );
+    }
+
+    fs::write(path, updated).with_context(|| format!("Failed to write schema dump to {}", path))
+}
+
+/// Tablespace named by a `SET default_tablespace = ...;` line
+///
+/// pg_dump writes the name as an identifier (quoted when needed) and the
+/// default as `''`.
+fn parse_default_tablespace(line: &str) -> Option<String> {
+    let value = line
+        .strip_prefix("SET default_tablespace = ")?
+        .strip_suffix(';')?;
+    if value == "''" {
+        return Some(String::new());
+    }
+    match value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+        Some(quoted) => Some(quoted.replace("\"\"", "\"")),
+        None => Some(value.to_string()),
+    }
+}
+
+/// Comments out row-level security policies in a schema dump.
+///
+/// Removes the `POLICY` sections (and their comments) plus `FORCE ROW LEVEL
+/// SECURITY` statements. `ENABLE ROW LEVEL SECURITY` is kept, so tables stay
+/// closed to non-owner roles instead of exposing every row, while the table
+/// owner that sync connects as can still write. Returns the number of policies
+/// commented out.
+pub fn remove_policies_from_schema(path: &str) -> Result<usize> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read schema dump at {}", path))?;
+
+    let (updated, removed) = comment_out_toc_sections(&content, |name, kind, _| {
+        kind == "POLICY" || (kind == "COMMENT" && name.starts_with("POLICY "))
+    });
+    let mut forced = false;
+    let updated: String = updated
+        .lines()
+        .map(|line| {
+            if line.trim_end().ends_with("FORCE ROW LEVEL SECURITY;") && !line.starts_with("--") {
+                forced = true;
+                format!("-- {}\n", line)
+            } else {
+                format!("{}\n", line)
+            }
+        })
+        .collect();
+
+    if removed > 0 || forced {
+        fs::write(path, updated)
+            .with_context(|| format!("Failed to write filtered schema dump to {}", path))?;
+    }
+
+    Ok(removed)
+}
+
+/// Tables a schema dump enables row-level security on
+pub fn row_security_tables(path: &str) -> Result<Vec<String>> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read schema dump at {}", path))?;
+    Ok(content
+        .lines()
+        .filter_map(|line| {
+            let table = line
+                .strip_prefix("ALTER TABLE ")?
+                .strip_suffix(" ENABLE ROW LEVEL SECURITY;")?;
+            Some(table.strip_prefix("ONLY ").unwrap_or(table).to_string())
+        })
+        .collect())
+}
+
+/// Warn that a table keeps row-level security enabled while its policies are skipped
+pub fn warn_row_security_without_policies(table: &str) {
+    tracing::warn!(
+        "  ⚠ {} keeps row-level security enabled without its policies; only its owner and BYPASSRLS roles can read it",
+        table
+    );
+}
+
+/// Comments out triggers and event triggers in a schema dump.
+///
+/// xmin sync applies rows with plain `INSERT`/`UPDATE`, so triggers copied to
+/// the target fire a second time and repeat application side effects such as
+/// audit rows or queue entries. Returns the number of triggers commented out.
+pub fn remove_triggers_from_schema(path: &str) -> Result<usize> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read schema dump at {}", path))?;
+
+    let (updated, removed) = comment_out_toc_sections(&content, |name, kind, _| {
+        kind == "TRIGGER"
+            || kind == "EVENT TRIGGER"
+            || (kind == "COMMENT"
+                && (name.starts_with("TRIGGER ") || name.starts_with("EVENT TRIGGER ")))
+    });
+
+    if removed > 0 {
+        fs::write(path, updated)
+            .with_context(|| format!("Failed to write filtered schema dump to {}", path))?;
+    }
+
+    Ok(removed)
+}
+
+/// Comments out the given views, functions, triggers, and indexes in a schema
+/// dump, with any comments on them.
+///
+/// Used for objects that depend on something the filters leave out (see
+/// [`super::dependencies`]), which would otherwise stop the restore. Returns
+/// the number of objects commented out.
+pub fn remove_objects_from_schema(
+    path: &str,
+    objects: &[super::dependencies::SchemaObject],
+) -> Result<usize> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read schema dump at {}", path))?;
+
+    let (updated, removed) = comment_out_toc_sections(&content, |name, kind, schema| {
+        objects
+            .iter()
+            .any(|object| object.matches_dump_entry(name, kind, schema))
+    });
+
+    if removed > 0 {
+        fs::write(path, updated)
+            .with_context(|| format!("Failed to write filtered schema dump to {}", path))?;
+    }
+
+    Ok(removed)
+}
+
+/// Comment out whole objects in a plain-format `pg_dump` file.
+///
+/// Each object starts with a `-- Name: <name>; Type: <type>; ...` header and runs
+/// until the next header. `SET` lines are kept because pg_dump emits them for the
+/// *next* object before its header. Returns the updated dump and the number of
+/// non-comment objects removed.
+fn comment_out_toc_sections(
+    content: &str,
+    should_remove: impl Fn(&str, &str, &str) -> bool,
+) -> (String, usize) {
+    let mut updated = String::with_capacity(content.len());
+    let mut removing = false;
+    let mut removed = 0;
+
+    for line in content.lines() {
+        if let Some((name, kind, schema)) = parse_toc_header(line) {
+            removing = should_remove(name, kind, schema);
+            if removing && kind != "COMMENT" {
+                removed += 1;
+            }
+        } else if line.starts_with("-- PostgreSQL database dump complete") {
+            removing = false;
+        }
+
+        let keep = !removing
+            || line.is_empty()
+            || line.starts_with("--")
+            || line.starts_with("SET ")
+            || line.starts_with("SELECT pg_catalog.set_config");
+        if !keep {
+            updated.push_str("-- ");
+        }
+        updated.push_str(line);
+        updated.push('\n');
+    }
+
+    (updated, removed)
+}
+
+/// Parse `-- Name: <name>; Type: <type>; Schema: <schema>; ...` into
+/// `(name, type, schema)`
+fn parse_toc_header(line: &str) -> Option<(&str, &str, &str)> {
+    let rest = line.strip_prefix("-- Name: ")?;
+    let (name, rest) = rest.split_once("; Type: ")?;
+    let (kind, rest) = rest.split_once("; Schema: ")?;
+    let schema = rest.split_once(';').map_or(rest, |(schema, _)| schema);
+    Some((name, kind, schema))
+}
+
 /// Dump data for a specific database using optimized directory format
 ///
 /// Uses PostgreSQL directory format dump with:
@@ -568,10 +971,30 @@ pub async fn dump_data(
     output_path: &str,
     filter: &ReplicationFilter,
 ) -> Result<()> {
-    // Determine optimal number of parallel jobs (number of CPUs, capped at 8)
+    dump_data_excluding(source_url, database, output_path, filter, &[], None).await
+}
+
+/// Dump data like [`dump_data`], also leaving out the data of `extra_excluded`
+/// tables (given as `"schema"."table"`), which are copied separately.
+///
+/// With `snapshot`, pg_dump reads at that exported snapshot (see
+/// [`ExportedSnapshot`](crate::postgres::snapshot::ExportedSnapshot)), so the
+/// separately copied tables can be read at the same point.
+pub async fn dump_data_excluding(
+    source_url: &str,
+    database: &str,
+    output_path: &str,
+    filter: &ReplicationFilter,
+    extra_excluded: &[String],
+    snapshot: Option<&str>,
+) -> Result<()> {
+    // Determine optimal number of parallel jobs (number of CPUs, capped at 8).
+    // pg_dump opens one connection per job plus a leader, all on the source
     let num_cpus = std::thread::available_parallelism()
         .map(|n| n.get().min(8))
-        .unwrap_or(4);
+        .unwrap_or(4)
+        .min(crate::postgres::pool::max_connections_per_host() - 1)
+        .max(1);
 
     tracing::info!(
         "Dumping data for database '{}' to {} (parallel={}, compression=9, format=directory)",
@@ -581,8 +1004,13 @@ pub async fn dump_data(
     );
 
     // Parse URL and create .pgpass file for secure authentication
-    let parts = crate::utils::parse_postgres_url(source_url)
-        .with_context(|| format!("Failed to parse source URL: {}", source_url))?;
+    let mut parts = crate::utils::parse_postgres_url(source_url).with_context(|| {
+        format!(
+            "Failed to parse source URL: {}",
+            crate::redact::redact(source_url)
+        )
+    })?;
+    crate::postgres::iam::apply_to_parts(&mut parts).await?;
     let pgpass = crate::utils::PgPassFile::new(&parts)
         .context("Failed to create .pgpass file for authentication")?;
 
@@ -590,8 +1018,17 @@ pub async fn dump_data(
     let output_path_owned = output_path.to_string();
 
     // Collect filter options
-    let exclude_tables = get_data_excluded_tables_for_db(filter, database);
+    let mut exclude_tables = get_data_excluded_tables_for_db(filter, database);
+    if !extra_excluded.is_empty() {
+        let tables = exclude_tables.get_or_insert_with(Vec::new);
+        for table in extra_excluded {
+            if !tables.contains(table) {
+                tables.push(table.clone());
+            }
+        }
+    }
     let include_tables = get_included_tables_for_db(filter, database);
+    let exclude_schemas = get_excluded_schemas_for_db(source_url, filter, database).await?;
 
     // Wrap subprocess execution with retry logic
     crate::utils::retry_subprocess_with_backoff(
@@ -604,6 +1041,9 @@ pub async fn dump_data(
                 .arg("--compress=9") // Maximum compression for smaller dump size
                 .arg(format!("--jobs={}", num_cpus)) // Parallel dump jobs
                 .arg("--verbose"); // Show progress
+            if let Some(snapshot) = snapshot {
+                cmd.arg(format!("--snapshot={}", snapshot));
+            }
 
             // Add table filtering if specified
             // Exclude explicit excludes, schema_only tables, and predicate tables from data dump
@@ -624,6 +1064,16 @@ pub async fn dump_data(
                 }
             }
 
+            for schema in &exclude_schemas {
+                cmd.arg("--exclude-schema").arg(schema);
+            }
+
+            // This tool's metadata stays with the replica that holds it
+            cmd.arg("--exclude-schema")
+                .arg(crate::audit::AUDIT_SCHEMA)
+                .arg("--exclude-table")
+                .arg(crate::topology::METADATA_TABLE_PATTERN);
+
             cmd.arg("--host")
                 .arg(&parts.host)
                 .arg("--port")
@@ -633,7 +1083,7 @@ pub async fn dump_data(
                 .arg(format!("--file={}", output_path_owned))
                 .env("PGPASSFILE", pgpass.path())
                 .stdout(Stdio::inherit())
-                .stderr(Stdio::inherit());
+                .stderr(Stdio::piped());
 
             // Add username if specified
             if let Some(user) = &parts.user {
@@ -653,7 +1103,7 @@ pub async fn dump_data(
             // Set connection timeout to prevent hangs on pool exhaustion
             cmd.env("PGCONNECT_TIMEOUT", "30"); // Fail after 30 seconds
 
-            cmd.status().context(
+            cmd.spawn().context(
                 "Failed to execute pg_dump. Is PostgreSQL client installed?\n\
                  Install with:\n\
                  - Ubuntu/Debian: sudo apt-get install postgresql-client\n\
@@ -693,7 +1143,7 @@ pub async fn dump_data(
 /// Only excludes explicit exclude_tables - NOT schema_only or predicate tables
 /// (those need their schema created, just not bulk data copied)
 /// Returns schema-qualified names in format: "schema"."table"
-fn get_schema_excluded_tables_for_db(
+pub(crate) fn get_schema_excluded_tables_for_db(
     filter: &ReplicationFilter,
     db_name: &str,
 ) -> Option<Vec<String>> {
@@ -703,10 +1153,8 @@ fn get_schema_excluded_tables_for_db(
     // These tables are completely excluded (no schema, no data)
     if let Some(explicit) = filter.exclude_tables() {
         for full_name in explicit {
-            let parts: Vec<&str> = full_name.split('.').collect();
-            if parts.len() == 2 && parts[0] == db_name {
-                // Format as "public"."table" for consistency
-                tables.insert(format!("\"public\".\"{}\"", parts[1]));
+            if let Some(table) = qualified_table_for_db(full_name, db_name) {
+                tables.insert(table);
             }
         }
     }
@@ -732,10 +1180,8 @@ fn get_data_excluded_tables_for_db(
     // Default to public schema for backward compatibility
     if let Some(explicit) = filter.exclude_tables() {
         for full_name in explicit {
-            let parts: Vec<&str> = full_name.split('.').collect();
-            if parts.len() == 2 && parts[0] == db_name {
-                // Format as "public"."table" for consistency
-                tables.insert(format!("\"public\".\"{}\"", parts[1]));
+            if let Some(table) = qualified_table_for_db(full_name, db_name) {
+                tables.insert(table);
             }
         }
     }
@@ -749,6 +1195,18 @@ fn get_data_excluded_tables_for_db(
         tables.insert(table);
     }
 
+    for (table, _) in filter.sample_tables(db_name) {
+        tables.insert(table);
+    }
+
+    for table in filter.transformed_tables(db_name).into_keys() {
+        tables.insert(table);
+    }
+
+    for table in filter.source_tables(db_name).into_keys() {
+        tables.insert(table);
+    }
+
     if tables.is_empty() {
         None
     } else {
@@ -758,23 +1216,54 @@ fn get_data_excluded_tables_for_db(
 
 /// Extract table names for a specific database from include_tables filter
 /// Returns schema-qualified names in format: "schema"."table"
-fn get_included_tables_for_db(filter: &ReplicationFilter, db_name: &str) -> Option<Vec<String>> {
+pub(crate) fn get_included_tables_for_db(
+    filter: &ReplicationFilter,
+    db_name: &str,
+) -> Option<Vec<String>> {
     filter.include_tables().map(|tables| {
         tables
             .iter()
-            .filter_map(|full_name| {
-                let parts: Vec<&str> = full_name.split('.').collect();
-                if parts.len() == 2 && parts[0] == db_name {
-                    // Format as "public"."table" for consistency
-                    Some(format!("\"public\".\"{}\"", parts[1]))
-                } else {
-                    None
-                }
+            .filter(|full_name| {
+                // --table overrides --exclude-schema, so drop filtered schemas here
+                let table = full_name.split_once('.').map_or("", |(_, table)| table);
+                filter.should_replicate_table(db_name, table)
             })
+            .filter_map(|full_name| qualified_table_for_db(full_name, db_name))
             .collect()
     })
 }
 
+/// Schemas left out by --include-schemas/--exclude-schemas, quoted for pg_dump
+pub(crate) async fn get_excluded_schemas_for_db(
+    source_url: &str,
+    filter: &ReplicationFilter,
+    db_name: &str,
+) -> Result<Vec<String>> {
+    if !filter.has_schema_filters() {
+        return Ok(Vec::new());
+    }
+    let client = crate::postgres::connect_with_retry(source_url)
+        .await
+        .context("Failed to connect to source to list schemas")?;
+    Ok(filter
+        .excluded_schemas(&client, db_name)
+        .await?
+        .iter()
+        .map(|schema| format!("\"{}\"", schema))
+        .collect())
+}
+
+/// Convert a "database.table" or "database.schema.table" filter entry into
+/// "schema"."table" when it belongs to `db_name` (schema defaults to public)
+fn qualified_table_for_db(full_name: &str, db_name: &str) -> Option<String> {
+    let (db, table_part) = full_name.split_once('.')?;
+    if db != db_name {
+        return None;
+    }
+    let (schema, table) = table_part.split_once('.').unwrap_or(("public", table_part));
+    Some(format!("\"{}\".\"{}\"", schema, table))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -900,6 +1389,26 @@ mod tests {
         assert!(tables.is_none() || tables.unwrap().is_empty());
     }
 
+    #[test]
+    fn test_get_included_tables_for_db_with_schema() {
+        let filter = crate::filters::ReplicationFilter::new(
+            None,
+            None,
+            Some(vec![
+                "db1.analytics.events_2024".to_string(),
+                "db1.users".to_string(),
+            ]),
+            None,
+        )
+        .unwrap();
+
+        let tables = get_included_tables_for_db(&filter, "db1").unwrap();
+        assert_eq!(
+            tables,
+            vec!["\"analytics\".\"events_2024\"", "\"public\".\"users\""]
+        );
+    }
+
     #[test]
     fn test_get_schema_excluded_tables_for_db_with_empty_filter() {
         let filter = crate::filters::ReplicationFilter::empty();
@@ -992,4 +1501,178 @@ GRANT SELECT ON TABLE orders TO myuser GRANTED BY postgres;
         assert!(result.contains("CREATE ROLE myuser;"));
         assert!(result.contains("ALTER ROLE myuser WITH LOGIN;"));
     }
+
+    const SCHEMA_DUMP: &str = r#"--
+-- Name: orders; Type: TABLE; Schema: public; Owner: -
+--
+
+CREATE TABLE public.orders (
+    id integer NOT NULL
+);
+
+ALTER TABLE ONLY public.orders FORCE ROW LEVEL SECURITY;
+
+--
+-- Name: orders audit_orders; Type: TRIGGER; Schema: public; Owner: -
+--
+
+CREATE TRIGGER audit_orders AFTER INSERT ON public.orders FOR EACH ROW EXECUTE FUNCTION public.audit();
+
+ALTER TABLE public.orders ENABLE ALWAYS TRIGGER audit_orders;
+
+SET default_tablespace = '';
+
+--
+-- Name: TRIGGER audit_orders ON orders; Type: COMMENT; Schema: public; Owner: -
+--
+
+COMMENT ON TRIGGER audit_orders ON public.orders IS 'audit';
+
+--
+-- Name: orders tenant_isolation; Type: POLICY; Schema: public; Owner: -
+--
+
+CREATE POLICY tenant_isolation ON public.orders
+    USING ((tenant = CURRENT_USER));
+
+--
+-- Name: orders; Type: ROW SECURITY; Schema: public; Owner: -
+--
+
+ALTER TABLE public.orders ENABLE ROW LEVEL SECURITY;
+
+--
+-- Name: ddl_guard; Type: EVENT TRIGGER; Schema: -; Owner: -
+--
+
+CREATE EVENT TRIGGER ddl_guard ON ddl_command_start
+   EXECUTE FUNCTION public.guard();
+
+--
+-- PostgreSQL database dump complete
+--
+"#;
+
+    #[test]
+    fn test_tablespace_map_from_cli() {
+        let map = TablespaceMap::from_cli(&[
+            "fastssd=pg_default".to_string(),
+            " archive = cold ".to_string(),
+        ])
+        .unwrap();
+        assert_eq!(map.target_for("fastssd"), Some("pg_default"));
+        assert_eq!(map.target_for("archive"), Some("cold"));
+        assert_eq!(map.target_for("other"), None);
+
+        assert!(TablespaceMap::from_cli(&["=cold".to_string()]).is_err());
+        assert!(
+            TablespaceMap::from_cli(&["fastssd=a".to_string(), "fastssd=b".to_string()]).is_err()
+        );
+        assert!(TablespaceMap::from_cli(&[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_map_tablespaces_in_schema() {
+        let dump = "\
+SET default_tablespace = '';
+CREATE TABLE public.a (id integer);
+SET default_tablespace = fastssd;
+CREATE TABLE public.b (id integer);
+SET default_tablespace = \"Cold Storage\";
+CREATE INDEX b_idx ON public.b USING btree (id);
+SET default_tablespace = scratch;
+CREATE TABLE public.c (id integer);
+";
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("schema.sql");
+        std::fs::write(&path, dump).unwrap();
+
+        let map = TablespaceMap::from_cli(&[
+            "fastssd=pg_default".to_string(),
+            "Cold Storage=archive".to_string(),
+        ])
+        .unwrap();
+        map_tablespaces_in_schema(path.to_str().unwrap(), &map).unwrap();
+
+        let result = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            result,
+            "\
+SET default_tablespace = '';
+CREATE TABLE public.a (id integer);
+SET default_tablespace = '';
+CREATE TABLE public.b (id integer);
+SET default_tablespace = 'archive';
+CREATE INDEX b_idx ON public.b USING btree (id);
+SET default_tablespace = '';
+CREATE TABLE public.c (id integer);
+"
+        );
+    }
+
+    #[test]
+    fn test_remove_session_settings() {
+        let dump = "\
+SET statement_timeout = 0;
+SET transaction_timeout = 0;
+SET default_table_access_method = heap;
+CREATE TABLE public.a (id integer);
+";
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("schema.sql");
+        std::fs::write(&path, dump).unwrap();
+
+        let removed =
+            remove_session_settings(path.to_str().unwrap(), &["transaction_timeout"]).unwrap();
+        assert_eq!(removed, 1);
+        let result = std::fs::read_to_string(&path).unwrap();
+        assert!(result.contains("\n-- SET transaction_timeout = 0;\n"));
+        assert!(result.starts_with("SET statement_timeout = 0;"));
+        assert!(result.contains("\nSET default_table_access_method = heap;"));
+    }
+
+    #[test]
+    fn test_remove_triggers_from_schema() {
+        let dir = tempdir().unwrap();
+        let schema_file = dir.path().join("schema.sql");
+        std::fs::write(&schema_file, SCHEMA_DUMP).unwrap();
+
+        let removed = remove_triggers_from_schema(schema_file.to_str().unwrap()).unwrap();
+        assert_eq!(removed, 2);
+
+        let result = std::fs::read_to_string(&schema_file).unwrap();
+        assert!(result.contains("\n-- CREATE TRIGGER audit_orders"));
+        assert!(result.contains("\n-- ALTER TABLE public.orders ENABLE ALWAYS TRIGGER"));
+        assert!(result.contains("\n-- COMMENT ON TRIGGER audit_orders"));
+        assert!(result.contains("\n-- CREATE EVENT TRIGGER ddl_guard"));
+        assert!(result.contains("\n--    EXECUTE FUNCTION public.guard();"));
+
+        // SET lines belong to the next object and everything else is untouched
+        assert!(result.contains("\nSET default_tablespace = '';"));
+        assert!(result.contains("\nCREATE POLICY tenant_isolation"));
+        assert!(result.contains("\nCREATE TABLE public.orders ("));
+    }
+
+    #[test]
+    fn test_remove_policies_from_schema() {
+        let dir = tempdir().unwrap();
+        let schema_file = dir.path().join("schema.sql");
+        std::fs::write(&schema_file, SCHEMA_DUMP).unwrap();
+
+        let removed = remove_policies_from_schema(schema_file.to_str().unwrap()).unwrap();
+        assert_eq!(removed, 1);
+
+        let result = std::fs::read_to_string(&schema_file).unwrap();
+        assert!(result.contains("\n-- CREATE POLICY tenant_isolation ON public.orders"));
+        assert!(result.contains("\n--     USING ((tenant = CURRENT_USER));"));
+        assert!(result.contains("\n-- ALTER TABLE ONLY public.orders FORCE ROW LEVEL SECURITY;"));
+
+        // Tables stay closed to non-owners, and triggers are untouched
+        assert!(result.contains("\nALTER TABLE public.orders ENABLE ROW LEVEL SECURITY;"));
+        assert!(result.contains("\nCREATE TRIGGER audit_orders"));
+        assert_eq!(
+            row_security_tables(schema_file.to_str().unwrap()).unwrap(),
+            vec!["public.orders"]
+        );
+    }
 }
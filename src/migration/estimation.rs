@@ -210,6 +210,31 @@ fn replace_database_in_url(url: &str, new_database: &str) -> Result<String> {
     Ok(new_url)
 }
 
+/// Estimate local disk space needed to dump a database
+///
+/// Uses half the on-disk size of the database. Indexes and free space in
+/// the table files are not part of the dump, and the dump is compressed, so
+/// this is a conservative upper bound in practice.
+///
+/// # Arguments
+///
+/// * `size_bytes` - Database size in bytes
+///
+/// # Returns
+///
+/// Estimated bytes of dump files written to the temp location
+///
+/// # Examples
+///
+/// ```
+/// # use database_replicator::migration::estimate_dump_space;
+/// assert_eq!(estimate_dump_space(1073741824), 536870912);
+/// assert_eq!(estimate_dump_space(-1), 0);
+/// ```
+pub fn estimate_dump_space(size_bytes: i64) -> u64 {
+    (size_bytes.max(0) as u64).div_ceil(2)
+}
+
 /// Estimate replication duration based on database size
 ///
 /// Uses a conservative estimate of 20 GB/hour for total replication time,
@@ -334,6 +359,14 @@ mod tests {
         assert_eq!(format_duration(Duration::from_secs(172800)), "~2.0 days");
     }
 
+    #[test]
+    fn test_estimate_dump_space() {
+        assert_eq!(estimate_dump_space(0), 0);
+        assert_eq!(estimate_dump_space(-5), 0);
+        assert_eq!(estimate_dump_space(3), 2);
+        assert_eq!(estimate_dump_space(21474836480), 10737418240);
+    }
+
     #[test]
     fn test_estimate_replication_duration() {
         // 1 GB should take ~3 minutes (20 GB/hour = 0.05 hours for 1 GB)
@@ -1,10 +1,14 @@
 // ABOUTME: Handles filtered table replication using COPY streaming
 // ABOUTME: Applies table-level predicates and time filters during init snapshots
 
+use super::transfer::{pipe_copy, TransferBytes};
+use crate::filters::ReplicationFilter;
 use crate::postgres;
+use crate::table_rules::{fk_consistent_subset, ForeignKey, SampleRule, TableSource};
+use crate::transform::TableTransforms;
+use crate::utils::quote_ident;
 use anyhow::{bail, Context, Result};
-use futures::{pin_mut, SinkExt, StreamExt};
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use tokio_postgres::Client;
 
 /// Parse schema-qualified table name into (schema, table)
@@ -63,18 +67,186 @@ async fn get_cascade_targets(
     Ok(rows.iter().map(|row| (row.get(0), row.get(1))).collect())
 }
 
-pub async fn copy_filtered_tables(
+/// Resolve sample rules into WHERE predicates for [`copy_filtered_tables`]
+///
+/// Each table's rows are hashed on its primary key so the sample is stable
+/// across runs; tables without a primary key hash the whole row instead.
+pub async fn sample_predicates(
     source_url: &str,
+    samples: &[(String, SampleRule)],
+) -> Result<Vec<(String, String)>> {
+    if samples.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let source_client = postgres::connect_with_retry(source_url)
+        .await
+        .context("Failed to connect to source database to plan sampled tables")?;
+
+    let mut predicates = Vec::with_capacity(samples.len());
+    for (table, rule) in samples {
+        let (schema, table_name) = parse_schema_table(table)?;
+        let pk_columns =
+            crate::xmin::writer::get_primary_key_columns(&source_client, &schema, &table_name)
+                .await?;
+        let key = sample_key(&table_name, &pk_columns);
+        tracing::info!("  Sampling {} of '{}'", rule, table);
+        predicates.push((table.clone(), rule.predicate(table, &key)));
+    }
+    Ok(predicates)
+}
+
+/// List the foreign keys between user tables in a database
+pub async fn list_foreign_keys(client: &Client) -> Result<Vec<ForeignKey>> {
+    let rows = client
+        .query(
+            "SELECT cn.nspname, c.relname,
+                    ARRAY(SELECT a.attname::text
+                          FROM unnest(con.conkey) WITH ORDINALITY AS k(attnum, ord)
+                          JOIN pg_attribute a ON a.attrelid = con.conrelid AND a.attnum = k.attnum
+                          ORDER BY k.ord),
+                    pn.nspname, p.relname,
+                    ARRAY(SELECT a.attname::text
+                          FROM unnest(con.confkey) WITH ORDINALITY AS k(attnum, ord)
+                          JOIN pg_attribute a ON a.attrelid = con.confrelid AND a.attnum = k.attnum
+                          ORDER BY k.ord)
+             FROM pg_constraint con
+             JOIN pg_class c ON c.oid = con.conrelid
+             JOIN pg_namespace cn ON cn.oid = c.relnamespace
+             JOIN pg_class p ON p.oid = con.confrelid
+             JOIN pg_namespace pn ON pn.oid = p.relnamespace
+             WHERE con.contype = 'f'
+               AND con.conparentid = 0
+               AND cn.nspname NOT IN ('pg_catalog', 'information_schema')
+             ORDER BY cn.nspname, c.relname, con.conname",
+            &[],
+        )
+        .await
+        .context("Failed to list foreign keys")?;
+
+    Ok(rows
+        .iter()
+        .map(|row| ForeignKey {
+            child: format!(
+                "{}.{}",
+                quote_ident(row.get::<_, &str>(0)),
+                quote_ident(row.get::<_, &str>(1))
+            ),
+            child_columns: row.get(2),
+            parent: format!(
+                "{}.{}",
+                quote_ident(row.get::<_, &str>(3)),
+                quote_ident(row.get::<_, &str>(4))
+            ),
+            parent_columns: row.get(5),
+        })
+        .collect())
+}
+
+/// Plan a referentially consistent filtered copy
+///
+/// Reads the foreign keys from the target's restored schema and expands
+/// `tables` with [`fk_consistent_subset`]. Only tables this run replicates with
+/// data can be pulled into the plan. The result lists every table that must be
+/// left out of the bulk data restore and copied with [`copy_filtered_tables`],
+/// parents first.
+pub async fn plan_consistent_subset(
     target_url: &str,
+    db_name: &str,
     tables: &[(String, String)],
-) -> Result<()> {
+    filter: &ReplicationFilter,
+) -> Result<Vec<(String, String)>> {
     if tables.is_empty() {
-        return Ok(());
+        return Ok(Vec::new());
     }
 
-    let source_client = postgres::connect_with_retry(source_url)
+    let target_client = postgres::connect_with_retry(target_url)
         .await
-        .context("Failed to connect to source database for filtered copy")?;
+        .context("Failed to connect to target database to plan filtered copy")?;
+    let foreign_keys = list_foreign_keys(&target_client).await?;
+
+    let mut data_less: BTreeSet<String> = filter.schema_only_tables(db_name).into_iter().collect();
+    for fk in &foreign_keys {
+        let (schema, table) = parse_schema_table(&fk.child)?;
+        let identifier = if schema == "public" {
+            table
+        } else {
+            format!("{}.{}", schema, table)
+        };
+        if !filter.should_replicate_table(db_name, &identifier) {
+            data_less.insert(fk.child.clone());
+        }
+    }
+    fk_consistent_subset(tables, &foreign_keys, &data_less)
+}
+
+/// Text expression hashed to pick sampled rows
+fn sample_key(table: &str, pk_columns: &[String]) -> String {
+    match pk_columns {
+        // Whole-row reference by table name
+        [] => format!("{}::text", quote_ident(table)),
+        [column] => format!("{}::text", quote_ident(column)),
+        columns => format!(
+            "ROW({})::text",
+            columns
+                .iter()
+                .map(|c| quote_ident(c))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
+
+/// Tables copied at once by [`copy_filtered_tables_at_snapshot`]
+const COPY_WORKERS: usize = 4;
+
+/// Copy filtered tables, reading all of them at one snapshot of the source
+///
+/// Returns the bytes moved from source to target.
+pub async fn copy_filtered_tables(
+    source_url: &str,
+    target_url: &str,
+    tables: &[(String, String)],
+) -> Result<TransferBytes> {
+    if tables.is_empty() {
+        return Ok(TransferBytes::default());
+    }
+    let snapshot = postgres::snapshot::ExportedSnapshot::export(source_url)
+        .await
+        .context("Failed to export a source snapshot for filtered copy")?;
+    copy_filtered_tables_at_snapshot(
+        source_url,
+        target_url,
+        tables,
+        &BTreeMap::new(),
+        &BTreeMap::new(),
+        snapshot.id(),
+    )
+    .await
+}
+
+/// Copy filtered tables with parallel workers that all read at `snapshot`
+///
+/// `tables` must list parents before children, as [`plan_consistent_subset`]
+/// does. Every worker imports the same exported snapshot, so a child row and
+/// the parent it references are always read at the same point in time. Tables
+/// are copied in waves: each wave only references tables from earlier waves,
+/// so the target's foreign keys are satisfied as rows arrive. Tables in
+/// `sources`, keyed like `tables`, are read through their custom source, and
+/// tables in `transforms` have their columns rewritten as they are read.
+/// Returns the bytes moved.
+pub async fn copy_filtered_tables_at_snapshot(
+    source_url: &str,
+    target_url: &str,
+    tables: &[(String, String)],
+    transforms: &BTreeMap<String, TableTransforms>,
+    sources: &BTreeMap<String, TableSource>,
+    snapshot: &str,
+) -> Result<TransferBytes> {
+    if tables.is_empty() {
+        return Ok(TransferBytes::default());
+    }
+
     let target_client = postgres::connect_with_retry(target_url)
         .await
         .context("Failed to connect to target database for filtered copy")?;
@@ -124,58 +296,223 @@ pub async fn copy_filtered_tables(
         tracing::info!("✓ All CASCADE targets are included in replication scope");
     }
 
-    // Step 4: Proceed with TRUNCATE CASCADE and filtered copy
-    for (table, predicate) in tables {
-        tracing::info!(
-            "  Applying filtered copy for table '{}' with predicate: {}",
-            table,
-            predicate
-        );
-
+    // Step 4: Empty every table first, parents first. Truncating inside the
+    // copy loop would let a parent's TRUNCATE CASCADE empty a child that a
+    // parallel worker had already copied.
+    for (table, _) in tables {
         // Table is already schema-qualified and quoted (e.g., "public"."table")
-        let quoted_table = table;
-
-        // Use TRUNCATE CASCADE to handle FK dependencies
-        let truncate_sql = format!("TRUNCATE TABLE {} CASCADE", quoted_table);
+        let truncate_sql = format!("TRUNCATE TABLE {} CASCADE", table);
         target_client
             .execute(&truncate_sql, &[])
             .await
             .with_context(|| format!("Failed to truncate target table '{}'", table))?;
+    }
 
-        let copy_out_sql = format!(
-            "COPY (SELECT * FROM {} WHERE {}) TO STDOUT BINARY",
-            quoted_table, predicate
-        );
-        let reader = source_client
-            .copy_out(&copy_out_sql)
+    // Step 5: Copy wave by wave, spreading each wave over the workers
+    let foreign_keys = list_foreign_keys(&target_client).await?;
+    let waves = copy_waves(tables, &foreign_keys);
+    let worker_count = COPY_WORKERS
+        .min(postgres::pool::max_connections_per_host() / 2)
+        .clamp(1, tables.len());
+    let mut workers = Vec::with_capacity(worker_count);
+    for _ in 0..worker_count {
+        let source_client = postgres::connect_with_retry(source_url)
             .await
-            .with_context(|| format!("Failed to copy data from source table '{}'", table))?;
-
-        let copy_in_sql = format!("COPY {} FROM STDIN BINARY", quoted_table);
-        let writer = target_client
-            .copy_in(&copy_in_sql)
+            .context("Failed to connect to source database for filtered copy")?;
+        postgres::snapshot::begin_at_snapshot(&source_client, snapshot).await?;
+        let target_client = postgres::connect_with_retry(target_url)
             .await
-            .with_context(|| format!("Failed to copy data into target table '{}'", table))?;
-
-        pin_mut!(reader);
-        pin_mut!(writer);
+            .context("Failed to connect to target database for filtered copy")?;
+        workers.push((source_client, target_client));
+    }
+    tracing::info!(
+        "  Copying {} filtered table(s) in {} wave(s) with {} worker(s)",
+        tables.len(),
+        waves.len(),
+        worker_count
+    );
 
-        while let Some(chunk) = reader.next().await {
-            let data = chunk?;
-            writer.as_mut().send(data).await?;
+    let database = crate::utils::parse_postgres_url(target_url)
+        .map(|parts| parts.database)
+        .unwrap_or_default();
+    let mut transferred = TransferBytes::default();
+    for wave in &waves {
+        let moved = futures::future::try_join_all(workers.iter().enumerate().map(
+            |(worker, (source_client, target_client))| {
+                let database = &database;
+                async move {
+                    let mut moved = TransferBytes::default();
+                    for (table, predicate) in wave.iter().skip(worker).step_by(worker_count) {
+                        moved += copy_table(
+                            source_client,
+                            target_client,
+                            database,
+                            table,
+                            predicate,
+                            transforms.get(table),
+                            sources.get(table),
+                        )
+                        .await?;
+                    }
+                    Ok::<_, anyhow::Error>(moved)
+                }
+            },
+        ))
+        .await?;
+        for bytes in moved {
+            transferred += bytes;
         }
+    }
 
-        writer.finish().await?;
-        tracing::info!("  ✓ Filtered copy complete for '{}'", table);
+    Ok(transferred)
+}
+
+/// Group a parents-first copy plan into waves that only reference earlier waves
+fn copy_waves<'a>(
+    tables: &'a [(String, String)],
+    foreign_keys: &[ForeignKey],
+) -> Vec<Vec<&'a (String, String)>> {
+    let mut wave_of: HashMap<&str, usize> = HashMap::new();
+    let mut waves: Vec<Vec<&(String, String)>> = Vec::new();
+    for entry in tables {
+        let table = entry.0.as_str();
+        // Parents later in the plan (cycles) and self-references are not waited on
+        let wave = foreign_keys
+            .iter()
+            .filter(|fk| fk.child == table && fk.parent != table)
+            .filter_map(|fk| wave_of.get(fk.parent.as_str()))
+            .map(|wave| wave + 1)
+            .max()
+            .unwrap_or(0);
+        wave_of.insert(table, wave);
+        if waves.len() <= wave {
+            waves.resize_with(wave + 1, Vec::new);
+        }
+        waves[wave].push(entry);
     }
+    waves
+}
 
-    Ok(())
+/// Stream one table's matching rows from the source into the target
+///
+/// Rows pass through [`pipe_copy`], so they are compressed in flight like a
+/// streamed load. A custom source replaces the table in the source's SELECT,
+/// and transformed columns are rewritten by it.
+async fn copy_table(
+    source_client: &Client,
+    target_client: &Client,
+    database: &str,
+    table: &str,
+    predicate: &str,
+    transforms: Option<&TableTransforms>,
+    source: Option<&TableSource>,
+) -> Result<TransferBytes> {
+    tracing::info!(
+        "  Applying filtered copy for table '{}' with predicate: {}",
+        table,
+        predicate
+    );
+    let (schema, table_name) = parse_schema_table(table)?;
+    let event_table = format!("{}.{}", schema, table_name);
+    crate::events::emit(|sink| sink.on_table_start(database, &event_table));
+
+    let relation = match source {
+        Some(source) => {
+            tracing::info!("  Reading '{}' through {}", table, source);
+            source.relation(table, &table_name)
+        }
+        None => table.to_string(),
+    };
+    let (select_list, target_columns) = if transforms.is_some() || source.is_some() {
+        let columns =
+            crate::transform::sql::copy_columns(source_client, &schema, &table_name).await?;
+        // Custom sources may compute columns with other types than the table's
+        let mut expressions: Vec<String> = columns
+            .iter()
+            .map(|(name, sql_type)| match source {
+                Some(_) => format!(
+                    "{}::{} AS {}",
+                    quote_ident(name),
+                    sql_type,
+                    quote_ident(name)
+                ),
+                None => quote_ident(name),
+            })
+            .collect();
+        if let Some(transforms) = transforms {
+            tracing::info!("  Transforming '{}': {}", table, transforms);
+            transforms
+                .apply(&columns, &mut expressions)
+                .with_context(|| format!("Cannot transform table '{}'", table))?;
+        }
+        let names: Vec<String> = columns.iter().map(|(name, _)| quote_ident(name)).collect();
+        (expressions.join(", "), format!(" ({})", names.join(", ")))
+    } else {
+        ("*".to_string(), String::new())
+    };
+    let copy_out_sql = format!(
+        "COPY (SELECT {} FROM {} WHERE {}) TO STDOUT BINARY",
+        select_list, relation, predicate
+    );
+    let reader = source_client
+        .copy_out(&copy_out_sql)
+        .await
+        .with_context(|| format!("Failed to copy data from source table '{}'", table))?;
+
+    let copy_in_sql = format!("COPY {}{} FROM STDIN BINARY", table, target_columns);
+    let writer = target_client
+        .copy_in(&copy_in_sql)
+        .await
+        .with_context(|| format!("Failed to copy data into target table '{}'", table))?;
+
+    let (bytes, rows) = pipe_copy(reader, writer).await?;
+    crate::events::emit(|sink| sink.on_rows_copied(database, &event_table, rows));
+    tracing::info!("  ✓ Filtered copy complete for '{}'", table);
+    Ok(bytes)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_copy_waves_follow_foreign_keys() {
+        let fk = |child: &str, parent: &str| ForeignKey {
+            child: child.to_string(),
+            child_columns: vec!["parent_id".to_string()],
+            parent: parent.to_string(),
+            parent_columns: vec!["id".to_string()],
+        };
+        let tables: Vec<(String, String)> = [
+            "\"public\".\"customers\"",
+            "\"public\".\"products\"",
+            "\"public\".\"orders\"",
+            "\"public\".\"order_items\"",
+        ]
+        .iter()
+        .map(|table| (table.to_string(), "true".to_string()))
+        .collect();
+        let foreign_keys = vec![
+            fk("\"public\".\"orders\"", "\"public\".\"customers\""),
+            fk("\"public\".\"order_items\"", "\"public\".\"orders\""),
+            fk("\"public\".\"order_items\"", "\"public\".\"products\""),
+            fk("\"public\".\"customers\"", "\"public\".\"customers\""),
+        ];
+
+        let waves: Vec<Vec<&str>> = copy_waves(&tables, &foreign_keys)
+            .iter()
+            .map(|wave| wave.iter().map(|(table, _)| table.as_str()).collect())
+            .collect();
+        assert_eq!(
+            waves,
+            vec![
+                vec!["\"public\".\"customers\"", "\"public\".\"products\""],
+                vec!["\"public\".\"orders\""],
+                vec!["\"public\".\"order_items\""],
+            ]
+        );
+    }
+
     #[test]
     fn test_parse_schema_table_valid() {
         let result = parse_schema_table("\"public\".\"users\"").unwrap();
@@ -200,6 +537,16 @@ mod tests {
         assert_eq!(result.unwrap(), ("public".to_string(), "users".to_string()));
     }
 
+    #[test]
+    fn test_sample_key() {
+        assert_eq!(sample_key("events", &[]), "\"events\"::text");
+        assert_eq!(sample_key("events", &["id".to_string()]), "\"id\"::text");
+        assert_eq!(
+            sample_key("events", &["tenant".to_string(), "id".to_string()]),
+            "ROW(\"tenant\", \"id\")::text"
+        );
+    }
+
     #[tokio::test]
     #[ignore]
     async fn test_cascade_targets_detected() {
@@ -413,4 +760,308 @@ mod tests {
                 .unwrap();
         }
     }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_sampled_copy_is_deterministic() {
+        let source_url = std::env::var("TEST_SOURCE_URL")
+            .expect("TEST_SOURCE_URL must be set for integration tests");
+        let target_url = std::env::var("TEST_TARGET_URL")
+            .expect("TEST_TARGET_URL must be set for integration tests");
+
+        let source_client = postgres::connect(&source_url).await.unwrap();
+        let target_client = postgres::connect(&target_url).await.unwrap();
+
+        for client in &[&source_client, &target_client] {
+            client
+                .batch_execute(
+                    "DROP TABLE IF EXISTS sample_events, sample_logs;
+                     CREATE TABLE sample_events (id INTEGER PRIMARY KEY, payload TEXT);
+                     CREATE TABLE sample_logs (line TEXT)",
+                )
+                .await
+                .unwrap();
+        }
+        source_client
+            .batch_execute(
+                "INSERT INTO sample_events SELECT g, 'event ' || g FROM generate_series(1, 2000) g;
+                 INSERT INTO sample_logs SELECT 'line ' || g FROM generate_series(1, 500) g",
+            )
+            .await
+            .unwrap();
+
+        let samples = vec![
+            (
+                "\"public\".\"sample_events\"".to_string(),
+                SampleRule::percent("10%", 0).unwrap(),
+            ),
+            (
+                "\"public\".\"sample_logs\"".to_string(),
+                SampleRule::rows(50, 3).unwrap(),
+            ),
+        ];
+
+        let mut copied = Vec::new();
+        for _ in 0..2 {
+            let tables = sample_predicates(&source_url, &samples).await.unwrap();
+            copy_filtered_tables(&source_url, &target_url, &tables)
+                .await
+                .unwrap();
+            let ids: Vec<i32> = target_client
+                .query("SELECT id FROM sample_events ORDER BY id", &[])
+                .await
+                .unwrap()
+                .iter()
+                .map(|row| row.get(0))
+                .collect();
+            copied.push(ids);
+        }
+
+        // Roughly 10% of rows, and the same rows on every run
+        assert!(
+            (120..=280).contains(&copied[0].len()),
+            "Expected about 200 sampled rows, got {}",
+            copied[0].len()
+        );
+        assert_eq!(copied[0], copied[1]);
+
+        let rows = target_client
+            .query("SELECT COUNT(*) FROM sample_logs", &[])
+            .await
+            .unwrap();
+        let count: i64 = rows[0].get(0);
+        assert_eq!(count, 50, "Row sample without a primary key");
+
+        for client in &[&source_client, &target_client] {
+            client
+                .batch_execute("DROP TABLE IF EXISTS sample_events, sample_logs")
+                .await
+                .unwrap();
+        }
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_transformed_copy_keeps_joins() {
+        use crate::transform::{ColumnTransform, Transform};
+
+        let source_url = std::env::var("TEST_SOURCE_URL")
+            .expect("TEST_SOURCE_URL must be set for integration tests");
+        let target_url = std::env::var("TEST_TARGET_URL")
+            .expect("TEST_TARGET_URL must be set for integration tests");
+
+        let source_client = postgres::connect(&source_url).await.unwrap();
+        let target_client = postgres::connect(&target_url).await.unwrap();
+
+        for client in &[&source_client, &target_client] {
+            client
+                .batch_execute(
+                    "DROP TABLE IF EXISTS transform_orders, transform_users;
+                     CREATE TABLE transform_users (
+                         email VARCHAR(64) PRIMARY KEY, name TEXT, note TEXT,
+                         name_length INTEGER GENERATED ALWAYS AS (length(name)) STORED);
+                     CREATE TABLE transform_orders (
+                         id INTEGER PRIMARY KEY,
+                         email VARCHAR(64) REFERENCES transform_users(email))",
+                )
+                .await
+                .unwrap();
+        }
+        source_client
+            .batch_execute(
+                "INSERT INTO transform_users (email, name, note)
+                 SELECT 'person' || g || '@corp.test', 'Name ' || g, CASE WHEN g % 2 = 0 THEN 'x' END
+                 FROM generate_series(1, 20) g;
+                 INSERT INTO transform_orders SELECT g, 'person' || (g % 20 + 1) || '@corp.test'
+                 FROM generate_series(1, 100) g",
+            )
+            .await
+            .unwrap();
+
+        let column = |spec: &str| ColumnTransform {
+            transform: Transform::parse(spec).unwrap(),
+            seed: 9,
+        };
+        let mut users = TableTransforms::default();
+        users
+            .insert("email".to_string(), column("fake_email"))
+            .unwrap();
+        users.insert("name".to_string(), column("shuffle")).unwrap();
+        users
+            .insert("note".to_string(), column("const:REDACTED"))
+            .unwrap();
+        let mut orders = TableTransforms::default();
+        orders
+            .insert("email".to_string(), column("fake_email"))
+            .unwrap();
+        let transforms = BTreeMap::from([
+            ("\"public\".\"transform_users\"".to_string(), users),
+            ("\"public\".\"transform_orders\"".to_string(), orders),
+        ]);
+        let tables = vec![
+            (
+                "\"public\".\"transform_users\"".to_string(),
+                "TRUE".to_string(),
+            ),
+            (
+                "\"public\".\"transform_orders\"".to_string(),
+                "TRUE".to_string(),
+            ),
+        ];
+        let snapshot = postgres::snapshot::ExportedSnapshot::export(&source_url)
+            .await
+            .unwrap();
+        copy_filtered_tables_at_snapshot(
+            &source_url,
+            &target_url,
+            &tables,
+            &transforms,
+            &BTreeMap::new(),
+            snapshot.id(),
+        )
+        .await
+        .unwrap();
+
+        let row = target_client
+            .query_one(
+                "SELECT count(*) FILTER (WHERE email LIKE 'user\\_%@example.com'),
+                        count(*) FILTER (WHERE name LIKE 'Name %'),
+                        count(*) FILTER (WHERE note = 'REDACTED'),
+                        count(*) FILTER (WHERE note IS NULL),
+                        count(*) FILTER (WHERE name_length = length(name))
+                 FROM transform_users",
+                &[],
+            )
+            .await
+            .unwrap();
+        let counts: Vec<i64> = (0..5).map(|i| row.get(i)).collect();
+        assert_eq!(counts[0], 20, "Every email is fake");
+        assert!(counts[1] < 20, "Names are shuffled");
+        assert_eq!((counts[2], counts[3]), (10, 10), "NULLs stay NULL");
+        assert_eq!(counts[4], 20, "Generated columns are computed");
+
+        // Each order still joins to the user it belonged to
+        let joined: i64 = target_client
+            .query_one(
+                "SELECT count(*) FROM transform_orders o JOIN transform_users u USING (email)",
+                &[],
+            )
+            .await
+            .unwrap()
+            .get(0);
+        assert_eq!(joined, 100);
+
+        for client in &[&source_client, &target_client] {
+            client
+                .batch_execute("DROP TABLE IF EXISTS transform_orders, transform_users")
+                .await
+                .unwrap();
+        }
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_consistent_subset_keeps_referenced_parents() {
+        let source_url = std::env::var("TEST_SOURCE_URL")
+            .expect("TEST_SOURCE_URL must be set for integration tests");
+        let target_url = std::env::var("TEST_TARGET_URL")
+            .expect("TEST_TARGET_URL must be set for integration tests");
+
+        let source_client = postgres::connect(&source_url).await.unwrap();
+        let target_client = postgres::connect(&target_url).await.unwrap();
+
+        for client in &[&source_client, &target_client] {
+            client
+                .batch_execute(
+                    "DROP TABLE IF EXISTS subset_items, subset_orders, subset_users;
+                     CREATE TABLE subset_users (id INTEGER PRIMARY KEY, active BOOLEAN);
+                     CREATE TABLE subset_orders (
+                         id INTEGER PRIMARY KEY,
+                         user_id INTEGER REFERENCES subset_users(id),
+                         amount INTEGER
+                     );
+                     CREATE TABLE subset_items (
+                         id INTEGER PRIMARY KEY,
+                         order_id INTEGER REFERENCES subset_orders(id)
+                     )",
+                )
+                .await
+                .unwrap();
+        }
+        source_client
+            .batch_execute(
+                "INSERT INTO subset_users SELECT g, g % 10 = 0 FROM generate_series(1, 100) g;
+                 INSERT INTO subset_orders SELECT g, g, g FROM generate_series(1, 100) g;
+                 INSERT INTO subset_items SELECT g, g FROM generate_series(1, 100) g",
+            )
+            .await
+            .unwrap();
+
+        // Only active users (ids 10, 20, ...) and orders over 95 (ids 96-100).
+        // subset_items is unfiltered, so every order and user it references is needed.
+        let tables = vec![
+            (
+                "\"public\".\"subset_users\"".to_string(),
+                "active".to_string(),
+            ),
+            (
+                "\"public\".\"subset_orders\"".to_string(),
+                "amount > 95".to_string(),
+            ),
+        ];
+        let plan = plan_consistent_subset(&target_url, "tgt", &tables, &ReplicationFilter::empty())
+            .await
+            .unwrap();
+        let order: Vec<&str> = plan.iter().map(|(table, _)| table.as_str()).collect();
+        assert_eq!(
+            order,
+            vec![
+                "\"public\".\"subset_users\"",
+                "\"public\".\"subset_orders\"",
+                "\"public\".\"subset_items\""
+            ]
+        );
+
+        copy_filtered_tables(&source_url, &target_url, &plan)
+            .await
+            .unwrap();
+
+        for table in ["subset_users", "subset_orders", "subset_items"] {
+            let rows = target_client
+                .query(&format!("SELECT COUNT(*) FROM {}", table), &[])
+                .await
+                .unwrap();
+            let count: i64 = rows[0].get(0);
+            assert_eq!(count, 100, "{} keeps every referenced row", table);
+        }
+
+        // Without the unfiltered child, only the referenced parents are added
+        for client in &[&source_client, &target_client] {
+            client
+                .batch_execute("DROP TABLE subset_items")
+                .await
+                .unwrap();
+        }
+        let plan = plan_consistent_subset(&target_url, "tgt", &tables, &ReplicationFilter::empty())
+            .await
+            .unwrap();
+        assert_eq!(plan.len(), 2);
+        copy_filtered_tables(&source_url, &target_url, &plan)
+            .await
+            .unwrap();
+        let rows = target_client
+            .query("SELECT COUNT(*) FROM subset_users", &[])
+            .await
+            .unwrap();
+        let count: i64 = rows[0].get(0);
+        // 10 active users plus the owners of orders 96-100 (100 is already active)
+        assert_eq!(count, 14);
+
+        for client in &[&source_client, &target_client] {
+            client
+                .batch_execute("DROP TABLE IF EXISTS subset_items, subset_orders, subset_users")
+                .await
+                .unwrap();
+        }
+    }
 }
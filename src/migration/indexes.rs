@@ -0,0 +1,334 @@
+// ABOUTME: Defers index and key constraint creation until after the bulk data load
+// ABOUTME: Rebuilds the deferred indexes with several parallel connections and reports progress
+
+use crate::postgres;
+use crate::utils::quote_ident;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+use tokio_postgres::Client;
+
+/// Index builds run at once when no job count is given
+pub const DEFAULT_BUILD_JOBS: usize = 4;
+
+/// Primary key or unique constraint backed by a deferred index
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyConstraint {
+    pub name: String,
+    pub primary_key: bool,
+    pub deferrable: bool,
+    pub initially_deferred: bool,
+    pub comment: Option<String>,
+}
+
+/// An index dropped before the data load, with what is needed to rebuild it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeferredIndex {
+    pub schema: String,
+    pub table: String,
+    pub name: String,
+    /// `CREATE INDEX` statement from `pg_get_indexdef`
+    pub definition: String,
+    pub tablespace: Option<String>,
+    pub constraint: Option<KeyConstraint>,
+    pub comment: Option<String>,
+}
+
+impl DeferredIndex {
+    fn qualified_table(&self) -> String {
+        format!("{}.{}", quote_ident(&self.schema), quote_ident(&self.table))
+    }
+
+    fn qualified_name(&self) -> String {
+        format!("{}.{}", quote_ident(&self.schema), quote_ident(&self.name))
+    }
+
+    fn drop_sql(&self) -> String {
+        match &self.constraint {
+            Some(constraint) => format!(
+                "ALTER TABLE {} DROP CONSTRAINT {}",
+                self.qualified_table(),
+                quote_ident(&constraint.name)
+            ),
+            None => format!("DROP INDEX {}", self.qualified_name()),
+        }
+    }
+
+    /// Statements that recreate the index, its constraint, and their comments
+    ///
+    /// Key constraints are added `USING INDEX`, so the index build is the only
+    /// scan of the table.
+    fn build_sql(&self) -> String {
+        let mut sql = format!(
+            "SET default_tablespace = '{}';\n{};\nRESET default_tablespace;\n",
+            self.tablespace
+                .as_deref()
+                .unwrap_or_default()
+                .replace('\'', "''"),
+            self.definition
+        );
+        if let Some(comment) = &self.comment {
+            sql.push_str(&format!(
+                "COMMENT ON INDEX {} IS {};\n",
+                self.qualified_name(),
+                quote_literal(comment)
+            ));
+        }
+        if let Some(constraint) = &self.constraint {
+            sql.push_str(&format!(
+                "ALTER TABLE {} ADD CONSTRAINT {} {} USING INDEX {}",
+                self.qualified_table(),
+                quote_ident(&constraint.name),
+                if constraint.primary_key {
+                    "PRIMARY KEY"
+                } else {
+                    "UNIQUE"
+                },
+                quote_ident(&self.name)
+            ));
+            if constraint.deferrable {
+                sql.push_str(" DEFERRABLE");
+            }
+            if constraint.initially_deferred {
+                sql.push_str(" INITIALLY DEFERRED");
+            }
+            sql.push_str(";\n");
+            if let Some(comment) = &constraint.comment {
+                sql.push_str(&format!(
+                    "COMMENT ON CONSTRAINT {} ON {} IS {};\n",
+                    quote_ident(&constraint.name),
+                    self.qualified_table(),
+                    quote_literal(comment)
+                ));
+            }
+        }
+        sql
+    }
+}
+
+impl std::fmt::Display for DeferredIndex {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.schema, self.name)
+    }
+}
+
+fn error_reason(e: &tokio_postgres::Error) -> String {
+    e.as_db_error()
+        .map(|db| db.message().to_string())
+        .unwrap_or_else(|| e.to_string())
+}
+
+fn quote_literal(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+// Left in place: indexes on partitions and partitioned tables, which are
+// attached to each other; indexes a foreign key needs during the load;
+// exclusion constraints, which cannot be added USING INDEX; and indexes used
+// for replica identity or CLUSTER, which dropping would reset.
+const DEFERRABLE_INDEXES_QUERY: &str = "
+SELECT n.nspname, t.relname, ic.relname,
+       pg_get_indexdef(i.indexrelid),
+       ts.spcname,
+       con.conname,
+       con.contype = 'p',
+       COALESCE(con.condeferrable, false),
+       COALESCE(con.condeferred, false),
+       obj_description(i.indexrelid, 'pg_class'),
+       obj_description(con.oid, 'pg_constraint')
+FROM pg_index i
+JOIN pg_class ic ON ic.oid = i.indexrelid
+JOIN pg_class t ON t.oid = i.indrelid
+JOIN pg_namespace n ON n.oid = t.relnamespace
+LEFT JOIN pg_tablespace ts ON ts.oid = ic.reltablespace
+LEFT JOIN pg_constraint con
+       ON con.conindid = i.indexrelid AND con.conrelid = i.indrelid
+      AND con.contype IN ('p', 'u', 'x')
+WHERE n.nspname NOT IN ('pg_catalog', 'information_schema')
+  AND n.nspname NOT LIKE 'pg_toast%'
+  AND t.relkind = 'r'
+  AND NOT t.relispartition
+  AND i.indisvalid
+  AND NOT i.indisreplident
+  AND NOT i.indisclustered
+  AND (con.oid IS NULL OR con.contype IN ('p', 'u'))
+  AND NOT EXISTS (
+      SELECT 1 FROM pg_constraint fk
+      WHERE fk.contype = 'f' AND fk.conindid = i.indexrelid
+  )
+  AND NOT EXISTS (
+      SELECT 1 FROM pg_depend d
+      WHERE d.classid = 'pg_class'::regclass AND d.objid = t.oid AND d.deptype = 'e'
+  )
+ORDER BY n.nspname, t.relname, ic.relname";
+
+/// Drop the indexes and key constraints that can be rebuilt after the data load
+///
+/// Returns what was dropped, for [`build_indexes`]. Indexes a foreign key
+/// relies on stay, so the target's foreign keys keep working during the load.
+pub async fn defer_indexes(client: &Client) -> Result<Vec<DeferredIndex>> {
+    let rows = client
+        .query(DEFERRABLE_INDEXES_QUERY, &[])
+        .await
+        .context("Failed to list indexes to defer")?;
+    let indexes: Vec<DeferredIndex> = rows
+        .iter()
+        .map(|row| DeferredIndex {
+            schema: row.get(0),
+            table: row.get(1),
+            name: row.get(2),
+            definition: row.get(3),
+            tablespace: row.get(4),
+            constraint: row.get::<_, Option<String>>(5).map(|name| KeyConstraint {
+                name,
+                primary_key: row.get(6),
+                deferrable: row.get(7),
+                initially_deferred: row.get(8),
+                comment: row.get(10),
+            }),
+            comment: row.get(9),
+        })
+        .collect();
+
+    for index in &indexes {
+        client
+            .batch_execute(&index.drop_sql())
+            .await
+            .with_context(|| format!("Failed to drop index {} before bulk load", index))?;
+        tracing::debug!("Deferred index: {}", index.definition);
+    }
+    Ok(indexes)
+}
+
+/// Rebuild indexes dropped by [`defer_indexes`], `jobs` at a time
+///
+/// Each index is built on its own connection. Every index is attempted even if
+/// an earlier one fails; the error lists the statements that still need to be
+/// run by hand.
+pub async fn build_indexes(target_url: &str, indexes: &[DeferredIndex], jobs: usize) -> Result<()> {
+    if indexes.is_empty() {
+        return Ok(());
+    }
+    let jobs = jobs.clamp(1, indexes.len());
+    let mut clients = Vec::with_capacity(jobs);
+    for _ in 0..jobs {
+        clients.push(
+            postgres::connect_with_retry(target_url)
+                .await
+                .context("Failed to connect to target database to build indexes")?,
+        );
+    }
+    tracing::info!(
+        "  Building {} deferred index(es) with {} connection(s)...",
+        indexes.len(),
+        jobs
+    );
+
+    let started = Instant::now();
+    let next = AtomicUsize::new(0);
+    let finished = AtomicUsize::new(0);
+    let results = futures::future::join_all(clients.iter().map(|client| async {
+        let mut failed = Vec::new();
+        while let Some(index) = indexes.get(next.fetch_add(1, Ordering::Relaxed)) {
+            let start = Instant::now();
+            let result = client.batch_execute(&index.build_sql()).await;
+            let done = finished.fetch_add(1, Ordering::Relaxed) + 1;
+            match result {
+                Ok(()) => tracing::info!(
+                    "    [{}/{}] Built {} in {:.1}s",
+                    done,
+                    indexes.len(),
+                    index,
+                    start.elapsed().as_secs_f64()
+                ),
+                Err(e) => {
+                    tracing::error!(
+                        "    [{}/{}] Failed to build {}: {}",
+                        done,
+                        indexes.len(),
+                        index,
+                        error_reason(&e)
+                    );
+                    failed.push(index.build_sql());
+                }
+            }
+        }
+        failed
+    }))
+    .await;
+
+    let failed: Vec<String> = results.into_iter().flatten().collect();
+    if !failed.is_empty() {
+        anyhow::bail!(
+            "Failed to build {} deferred index(es). Run these on the target:\n{}",
+            failed.len(),
+            failed.join("\n")
+        );
+    }
+    tracing::info!(
+        "  ✓ Built {} index(es) in {:.1}s",
+        indexes.len(),
+        started.elapsed().as_secs_f64()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index(constraint: Option<KeyConstraint>) -> DeferredIndex {
+        DeferredIndex {
+            schema: "public".to_string(),
+            table: "orders".to_string(),
+            name: "orders_pkey".to_string(),
+            definition: "CREATE UNIQUE INDEX orders_pkey ON public.orders USING btree (id)"
+                .to_string(),
+            tablespace: None,
+            constraint,
+            comment: None,
+        }
+    }
+
+    #[test]
+    fn test_plain_index_sql() {
+        let mut index = index(None);
+        index.tablespace = Some("fast'ssd".to_string());
+        index.comment = Some("lookup by id".to_string());
+
+        assert_eq!(index.drop_sql(), "DROP INDEX \"public\".\"orders_pkey\"");
+        assert_eq!(
+            index.build_sql(),
+            "SET default_tablespace = 'fast''ssd';\n\
+             CREATE UNIQUE INDEX orders_pkey ON public.orders USING btree (id);\n\
+             RESET default_tablespace;\n\
+             COMMENT ON INDEX \"public\".\"orders_pkey\" IS 'lookup by id';\n"
+        );
+    }
+
+    #[test]
+    fn test_key_constraint_sql() {
+        let index = index(Some(KeyConstraint {
+            name: "orders_pkey".to_string(),
+            primary_key: true,
+            deferrable: true,
+            initially_deferred: true,
+            comment: Some("it's the key".to_string()),
+        }));
+
+        assert_eq!(
+            index.drop_sql(),
+            "ALTER TABLE \"public\".\"orders\" DROP CONSTRAINT \"orders_pkey\""
+        );
+        let sql = index.build_sql();
+        assert!(sql.starts_with("SET default_tablespace = '';\nCREATE UNIQUE INDEX"));
+        assert!(sql.contains(
+            "ALTER TABLE \"public\".\"orders\" ADD CONSTRAINT \"orders_pkey\" PRIMARY KEY \
+             USING INDEX \"orders_pkey\" DEFERRABLE INITIALLY DEFERRED;\n"
+        ));
+        assert!(sql.ends_with(
+            "COMMENT ON CONSTRAINT \"orders_pkey\" ON \"public\".\"orders\" IS 'it''s the key';\n"
+        ));
+    }
+}
@@ -0,0 +1,390 @@
+// ABOUTME: Materialized view definitions and refreshes on the target
+// ABOUTME: Creates views the schema copy left out and refreshes them in dependency order
+
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::time::Duration;
+use tokio_postgres::Client;
+
+/// A materialized view and the materialized views it reads from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MaterializedView {
+    pub schema: String,
+    pub name: String,
+    /// Whether the view holds data (it is created `WITH NO DATA` by a schema restore)
+    pub populated: bool,
+    /// Whether a unique index on plain columns allows `REFRESH ... CONCURRENTLY`
+    pub has_unique_index: bool,
+    /// Materialized views this one reads, directly or through plain views,
+    /// as `schema.name`
+    pub depends_on: Vec<String>,
+}
+
+impl MaterializedView {
+    fn qualified_name(&self) -> String {
+        format!("{}.{}", self.schema, self.name)
+    }
+
+    fn quoted_name(&self) -> String {
+        format!(
+            "{}.{}",
+            crate::utils::quote_ident(&self.schema),
+            crate::utils::quote_ident(&self.name)
+        )
+    }
+
+    /// The refresh statement, concurrent when readers need not be blocked
+    ///
+    /// `CONCURRENTLY` needs a unique index and a view that already has data.
+    pub fn refresh_sql(&self) -> String {
+        if self.populated && self.has_unique_index {
+            format!(
+                "REFRESH MATERIALIZED VIEW CONCURRENTLY {}",
+                self.quoted_name()
+            )
+        } else {
+            format!("REFRESH MATERIALIZED VIEW {}", self.quoted_name())
+        }
+    }
+}
+
+/// Materialized views, each with the materialized views its query reads,
+/// following plain views in between
+const MATVIEW_QUERY: &str = "WITH RECURSIVE deps(matview, ref) AS ( \
+         SELECT r.ev_class, d.refobjid \
+         FROM pg_rewrite r \
+         JOIN pg_class m ON m.oid = r.ev_class AND m.relkind = 'm' \
+         JOIN pg_depend d ON d.classid = 'pg_rewrite'::regclass AND d.objid = r.oid \
+                         AND d.refclassid = 'pg_class'::regclass \
+         WHERE d.refobjid <> r.ev_class \
+         UNION \
+         SELECT deps.matview, d.refobjid \
+         FROM deps \
+         JOIN pg_class v ON v.oid = deps.ref AND v.relkind = 'v' \
+         JOIN pg_rewrite r ON r.ev_class = v.oid \
+         JOIN pg_depend d ON d.classid = 'pg_rewrite'::regclass AND d.objid = r.oid \
+                         AND d.refclassid = 'pg_class'::regclass \
+         WHERE d.refobjid <> v.oid \
+     ) \
+     SELECT n.nspname::text, c.relname::text, c.relispopulated, \
+         EXISTS (SELECT 1 FROM pg_index i WHERE i.indrelid = c.oid AND i.indisunique \
+                 AND i.indisvalid AND i.indpred IS NULL AND i.indexprs IS NULL), \
+         ARRAY(SELECT DISTINCT dn.nspname || '.' || dc.relname \
+               FROM deps \
+               JOIN pg_class dc ON dc.oid = deps.ref AND dc.relkind = 'm' \
+               JOIN pg_namespace dn ON dn.oid = dc.relnamespace \
+               WHERE deps.matview = c.oid AND dc.oid <> c.oid) \
+     FROM pg_class c JOIN pg_namespace n ON n.oid = c.relnamespace \
+     WHERE c.relkind = 'm' \
+       AND n.nspname NOT IN ('pg_catalog', 'information_schema') \
+     ORDER BY 1, 2";
+
+/// Materialized views in the connected database, each after those it reads
+pub async fn list_materialized_views(client: &Client) -> Result<Vec<MaterializedView>> {
+    let rows = client
+        .query(MATVIEW_QUERY, &[])
+        .await
+        .context("Failed to list materialized views")?;
+    let views = rows
+        .iter()
+        .map(|row| MaterializedView {
+            schema: row.get(0),
+            name: row.get(1),
+            populated: row.get(2),
+            has_unique_index: row.get(3),
+            depends_on: row.get(4),
+        })
+        .collect();
+    Ok(dependency_order(views))
+}
+
+/// Order views so each comes after the views it depends on
+///
+/// Views are otherwise kept in their given order. PostgreSQL does not allow
+/// cycles, but any would be appended at the end rather than dropped.
+fn dependency_order(views: Vec<MaterializedView>) -> Vec<MaterializedView> {
+    let names: HashSet<String> = views.iter().map(MaterializedView::qualified_name).collect();
+    let mut placed: HashSet<String> = HashSet::new();
+    let mut ordered = Vec::with_capacity(views.len());
+    let mut remaining = views;
+    loop {
+        let (ready, waiting): (Vec<_>, Vec<_>) = remaining.into_iter().partition(|view| {
+            view.depends_on
+                .iter()
+                .all(|dep| placed.contains(dep) || !names.contains(dep))
+        });
+        if ready.is_empty() {
+            ordered.extend(waiting);
+            return ordered;
+        }
+        placed.extend(ready.iter().map(MaterializedView::qualified_name));
+        ordered.extend(ready);
+        remaining = waiting;
+    }
+}
+
+/// Create the source's materialized views that the target lacks, with their
+/// indexes, `WITH NO DATA`
+///
+/// A schema copy limited to selected tables leaves materialized views out.
+/// Views whose query reads objects missing on the target are skipped with a
+/// warning. Returns the views created, as `schema.name`.
+pub async fn create_missing_views(source: &Client, target: &Client) -> Result<Vec<String>> {
+    let existing: HashSet<String> = list_materialized_views(target)
+        .await?
+        .iter()
+        .map(MaterializedView::qualified_name)
+        .collect();
+
+    // Qualify every name in the definitions, whatever the target's search_path
+    source
+        .batch_execute("SET search_path = pg_catalog")
+        .await
+        .context("Failed to set search_path on source")?;
+    let views = read_definitions(source).await;
+    source
+        .batch_execute("RESET search_path")
+        .await
+        .context("Failed to reset search_path on source")?;
+
+    let mut created = Vec::new();
+    for (view, definition, indexes) in views? {
+        let name = view.qualified_name();
+        if existing.contains(&name) {
+            continue;
+        }
+        let create = format!(
+            "CREATE MATERIALIZED VIEW {} AS {} WITH NO DATA",
+            view.quoted_name(),
+            definition.trim().trim_end_matches(';')
+        );
+        if let Err(e) = target.batch_execute(&create).await {
+            tracing::warn!(
+                "  ⚠ Skipping materialized view {}: {}",
+                name,
+                error_reason(&e)
+            );
+            continue;
+        }
+        for index in indexes {
+            if let Err(e) = target.batch_execute(&index).await {
+                tracing::warn!(
+                    "  ⚠ Failed to create index on materialized view {}: {}",
+                    name,
+                    error_reason(&e)
+                );
+            }
+        }
+        created.push(name);
+    }
+    Ok(created)
+}
+
+/// Each view with its defining query and `CREATE INDEX` statements
+async fn read_definitions(client: &Client) -> Result<Vec<(MaterializedView, String, Vec<String>)>> {
+    let mut definitions = Vec::new();
+    for view in list_materialized_views(client).await? {
+        let row = client
+            .query_one(
+                "SELECT pg_get_viewdef(c.oid), \
+                     ARRAY(SELECT pg_get_indexdef(i.indexrelid) FROM pg_index i \
+                           WHERE i.indrelid = c.oid ORDER BY i.indexrelid) \
+                 FROM pg_class c JOIN pg_namespace n ON n.oid = c.relnamespace \
+                 WHERE n.nspname = $1 AND c.relname = $2 AND c.relkind = 'm'",
+                &[&view.schema, &view.name],
+            )
+            .await
+            .with_context(|| {
+                format!(
+                    "Failed to read definition of materialized view {}",
+                    view.qualified_name()
+                )
+            })?;
+        definitions.push((view, row.get(0), row.get(1)));
+    }
+    Ok(definitions)
+}
+
+/// Refresh every materialized view in the connected database, each after
+/// the views it reads
+///
+/// A view that fails to refresh is reported and the rest still run. Returns
+/// how many were refreshed and how many failed.
+pub async fn refresh_materialized_views(client: &Client) -> Result<(usize, usize)> {
+    let mut refreshed = 0;
+    let mut failed = 0;
+    for view in list_materialized_views(client).await? {
+        match client.batch_execute(&view.refresh_sql()).await {
+            Ok(()) => {
+                tracing::debug!("Refreshed materialized view {}", view.qualified_name());
+                refreshed += 1;
+            }
+            Err(e) => {
+                tracing::warn!(
+                    "⚠ Failed to refresh materialized view {}: {}",
+                    view.qualified_name(),
+                    error_reason(&e)
+                );
+                failed += 1;
+            }
+        }
+    }
+    Ok((refreshed, failed))
+}
+
+/// Refresh each target database's materialized views every `interval` until
+/// `shutdown` fires
+///
+/// A database that cannot be reached is retried on the next pass.
+pub async fn refresh_periodically(
+    target_urls: &[String],
+    interval: Duration,
+    mut shutdown: tokio::sync::broadcast::Receiver<()>,
+) -> Result<()> {
+    tracing::info!(
+        "Refreshing materialized views every {}s",
+        interval.as_secs()
+    );
+    let mut ticker = tokio::time::interval(interval);
+    // The first tick fires at once; views were just loaded or synced
+    ticker.tick().await;
+    loop {
+        tokio::select! {
+            biased;
+            _ = shutdown.recv() => {
+                tracing::info!("Shutdown signal received, no longer refreshing materialized views");
+                return Ok(());
+            }
+            _ = ticker.tick() => {}
+        }
+        for target_url in target_urls {
+            refresh_database(target_url).await;
+        }
+    }
+}
+
+/// Refresh one target database's materialized views, logging the outcome
+pub async fn refresh_database(target_url: &str) {
+    let db_name = crate::utils::parse_postgres_url(target_url)
+        .map(|parts| parts.database)
+        .unwrap_or_default();
+    let result = async {
+        let client = crate::postgres::connect(target_url).await?;
+        refresh_materialized_views(&client).await
+    }
+    .await;
+    match result {
+        Ok((0, 0)) => {}
+        Ok((refreshed, 0)) => tracing::info!(
+            "Refreshed {} materialized view(s) in '{}'",
+            refreshed,
+            db_name
+        ),
+        Ok((refreshed, failed)) => tracing::warn!(
+            "⚠ Refreshed {} materialized view(s) in '{}'; {} failed",
+            refreshed,
+            db_name,
+            failed
+        ),
+        Err(e) => tracing::warn!(
+            "⚠ Could not refresh materialized views in '{}': {:#}",
+            db_name,
+            e
+        ),
+    }
+}
+
+/// Server message for a failed statement, without the driver's "db error:" prefix
+fn error_reason(e: &tokio_postgres::Error) -> String {
+    e.as_db_error()
+        .map(|db| db.message().to_string())
+        .unwrap_or_else(|| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn view(name: &str, depends_on: &[&str]) -> MaterializedView {
+        MaterializedView {
+            schema: "public".to_string(),
+            name: name.to_string(),
+            populated: false,
+            has_unique_index: false,
+            depends_on: depends_on.iter().map(|dep| dep.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_dependency_order() {
+        let views = vec![
+            view("a_totals", &["public.c_daily"]),
+            view("b_report", &["public.a_totals", "public.c_daily"]),
+            view("c_daily", &[]),
+            view("d_external", &["other.gone"]),
+        ];
+        let names: Vec<String> = dependency_order(views)
+            .into_iter()
+            .map(|view| view.name)
+            .collect();
+        assert_eq!(names, vec!["c_daily", "d_external", "a_totals", "b_report"]);
+    }
+
+    #[test]
+    fn test_refresh_sql() {
+        let mut daily = view("daily", &[]);
+        assert_eq!(
+            daily.refresh_sql(),
+            r#"REFRESH MATERIALIZED VIEW "public"."daily""#
+        );
+        daily.has_unique_index = true;
+        assert_eq!(
+            daily.refresh_sql(),
+            r#"REFRESH MATERIALIZED VIEW "public"."daily""#
+        );
+        daily.populated = true;
+        assert_eq!(
+            daily.refresh_sql(),
+            r#"REFRESH MATERIALIZED VIEW CONCURRENTLY "public"."daily""#
+        );
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_create_and_refresh_views() {
+        let source_url = std::env::var("TEST_SOURCE_URL").unwrap();
+        let target_url = std::env::var("TEST_TARGET_URL").unwrap();
+        let source = crate::postgres::connect(&source_url).await.unwrap();
+        let target = crate::postgres::connect(&target_url).await.unwrap();
+        let setup = "DROP MATERIALIZED VIEW IF EXISTS mv_total, mv_daily;
+             DROP TABLE IF EXISTS mv_events;
+             CREATE TABLE mv_events (id int PRIMARY KEY, day date);
+             INSERT INTO mv_events VALUES (1, '2024-01-01'), (2, '2024-01-01');";
+        source.batch_execute(setup).await.unwrap();
+        target.batch_execute(setup).await.unwrap();
+        source
+            .batch_execute(
+                "CREATE MATERIALIZED VIEW mv_daily AS
+                     SELECT day, count(*) AS n FROM mv_events GROUP BY day;
+                 CREATE UNIQUE INDEX mv_daily_day ON mv_daily (day);
+                 CREATE MATERIALIZED VIEW mv_total AS SELECT sum(n) AS n FROM mv_daily;",
+            )
+            .await
+            .unwrap();
+
+        let created = create_missing_views(&source, &target).await.unwrap();
+        assert!(created.contains(&"public.mv_daily".to_string()));
+        assert!(created.contains(&"public.mv_total".to_string()));
+        refresh_materialized_views(&target).await.unwrap();
+        let total: i64 = target
+            .query_one("SELECT n::bigint FROM mv_total", &[])
+            .await
+            .unwrap()
+            .get(0);
+        assert_eq!(total, 2);
+
+        let cleanup = "DROP MATERIALIZED VIEW mv_total, mv_daily; DROP TABLE mv_events;";
+        source.batch_execute(cleanup).await.unwrap();
+        target.batch_execute(cleanup).await.unwrap();
+    }
+}
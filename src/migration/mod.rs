@@ -1,21 +1,37 @@
 // ABOUTME: Migration utilities module
 // ABOUTME: Handles schema introspection, dump/restore, and data migration
 
+pub mod analyze;
+pub mod artifacts;
 pub mod checksum;
+pub mod columns;
+pub mod dependencies;
+pub mod differential;
 pub mod dump;
 pub mod estimation;
 pub mod filtered;
+pub mod indexes;
+pub mod matviews;
+pub mod native;
+pub mod parity;
+pub mod rename;
 pub mod restore;
+pub mod roles;
 pub mod schema;
+pub mod transfer;
 
-pub use checksum::{compare_tables, compute_table_checksum, ChecksumResult};
+pub use checksum::{compare_renamed_table, compare_tables, compute_table_checksum, ChecksumResult};
 pub use dump::{
-    dump_data, dump_globals, dump_schema, remove_restricted_guc_settings,
-    remove_superuser_from_globals, remove_tablespace_statements, sanitize_globals_dump,
+    dump_data, dump_data_excluding, dump_globals, dump_schema, dump_schema_with_tablespaces,
+    remove_restricted_guc_settings, remove_superuser_from_globals, remove_tablespace_statements,
+    sanitize_globals_dump, TablespaceMap,
 };
-pub use estimation::{estimate_database_sizes, format_bytes, format_duration, DatabaseSizeInfo};
-pub use filtered::copy_filtered_tables;
+pub use estimation::{
+    estimate_database_sizes, estimate_dump_space, format_bytes, format_duration, DatabaseSizeInfo,
+};
+pub use filtered::{copy_filtered_tables, copy_filtered_tables_at_snapshot};
 pub use restore::{restore_data, restore_globals, restore_schema};
 pub use schema::{
-    get_table_columns, list_databases, list_tables, ColumnInfo, DatabaseInfo, TableInfo,
+    get_table_columns, list_databases, list_tables, selected_unlogged_tables, ColumnInfo,
+    DatabaseInfo, TableInfo,
 };
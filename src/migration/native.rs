@@ -0,0 +1,1403 @@
+// ABOUTME: Copies a database's schema and data over plain connections when pg_dump and psql are missing
+// ABOUTME: Builds DDL from the source catalog and streams rows with COPY at a shared snapshot
+
+use super::transfer::{pipe_copy, TransferBytes};
+use crate::filters::ReplicationFilter;
+use crate::postgres;
+use crate::table_rules::prioritized_waves;
+use crate::utils::quote_ident;
+use anyhow::{Context, Result};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+use tokio_postgres::Client;
+
+/// Parts of the schema to leave out, matching the edits made to a dumped schema
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SchemaOptions {
+    /// Create row-level security policies and `FORCE ROW LEVEL SECURITY`
+    pub include_policies: bool,
+    /// Leave triggers out
+    pub skip_triggers: bool,
+}
+
+// The audit schema stays with the replica that holds it
+const USER_SCHEMAS: &str = "n.nspname NOT IN ('pg_catalog', 'information_schema', '_replicator')
+  AND n.nspname NOT LIKE 'pg_toast%'
+  AND n.nspname NOT LIKE 'pg_temp%'";
+
+/// One DDL statement and the object it creates, for messages
+struct Statement {
+    object: String,
+    sql: String,
+}
+
+impl Statement {
+    fn new(object: impl Into<String>, sql: impl Into<String>) -> Self {
+        Self {
+            object: object.into(),
+            sql: sql.into(),
+        }
+    }
+}
+
+/// A table to create, with the names it is referred to by
+struct Table {
+    oid: u32,
+    schema: String,
+    name: String,
+    kind: String,
+    unlogged: bool,
+    /// `PARTITION OF` parent and bound, for partitions
+    partition_of: Option<(String, String)>,
+    partition_key: Option<String>,
+    inherits: Vec<String>,
+    comment: Option<String>,
+}
+
+impl Table {
+    fn quoted(&self) -> String {
+        format!("{}.{}", quote_ident(&self.schema), quote_ident(&self.name))
+    }
+}
+
+fn error_reason(e: &tokio_postgres::Error) -> String {
+    e.as_db_error()
+        .map(|db| db.message().to_string())
+        .unwrap_or_else(|| e.to_string())
+}
+
+fn quote_literal(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+fn comment_sql(kind: &str, object: &str, comment: &Option<String>) -> Option<String> {
+    comment.as_ref().map(|comment| {
+        format!(
+            "COMMENT ON {} {} IS {}",
+            kind,
+            object,
+            quote_literal(comment)
+        )
+    })
+}
+
+/// Run statements that must all succeed
+async fn apply(target: &Client, statements: &[Statement]) -> Result<()> {
+    for statement in statements {
+        target
+            .batch_execute(&statement.sql)
+            .await
+            .map_err(|e| anyhow::anyhow!("{}", error_reason(&e)))
+            .with_context(|| format!("Failed to create {}", statement.object))?;
+    }
+    Ok(())
+}
+
+/// Run statements that may fail, and return the ones that did
+async fn try_apply(target: &Client, statements: Vec<Statement>) -> Vec<(Statement, String)> {
+    let mut failed = Vec::new();
+    for statement in statements {
+        if let Err(e) = target.batch_execute(&statement.sql).await {
+            let reason = error_reason(&e);
+            failed.push((statement, reason));
+        }
+    }
+    failed
+}
+
+/// Create the source's user schemas, types, sequences, functions, tables,
+/// constraints, indexes, views, triggers, and policies on the target
+///
+/// This stands in for `pg_dump --schema-only` and its restore when the client
+/// tools are not installed. `filter` selects schemas and tables the same way;
+/// with an include list, functions and views are left out, as with
+/// `pg_dump --table`. Ownership, privileges, tablespaces, aggregates, and
+/// event triggers are not copied. Functions, views, and triggers the target
+/// refuses are skipped with a warning.
+pub async fn copy_schema(
+    source: &Client,
+    target: &Client,
+    database: &str,
+    filter: &ReplicationFilter,
+    options: SchemaOptions,
+) -> Result<()> {
+    // Qualify every name in generated definitions, whatever the target's search_path
+    source
+        .batch_execute("SET search_path = pg_catalog")
+        .await
+        .context("Failed to set search_path on source")?;
+    let result = create_objects(source, target, database, filter, options).await;
+    source
+        .batch_execute("RESET search_path")
+        .await
+        .context("Failed to reset search_path on source")?;
+    result
+}
+
+async fn create_objects(
+    source: &Client,
+    target: &Client,
+    database: &str,
+    filter: &ReplicationFilter,
+    options: SchemaOptions,
+) -> Result<()> {
+    target
+        .batch_execute("SET check_function_bodies = false")
+        .await
+        .context("Failed to prepare target session for schema copy")?;
+    let tables_only = filter.tables_for_database(database).is_some();
+
+    let schemas = schema_statements(source, database, filter).await?;
+    apply(target, &schemas).await?;
+    for (statement, reason) in try_apply(target, extension_statements(source).await?).await {
+        tracing::warn!("  ⚠ Skipping {}: {}", statement.object, reason);
+    }
+    apply(target, &type_statements(source, database, filter).await?).await?;
+    apply(
+        target,
+        &sequence_statements(source, database, filter).await?,
+    )
+    .await?;
+
+    // Functions can be needed by column defaults and can take table row types,
+    // so ones that fail before the tables exist are tried again after them
+    let functions = if tables_only {
+        Vec::new()
+    } else {
+        function_statements(source, database, filter).await?
+    };
+    let failed_functions = try_apply(target, functions).await;
+
+    let tables = selected_tables(source, database, filter).await?;
+    apply(target, &table_statements(source, &tables).await?).await?;
+    let oids: Vec<u32> = tables.iter().map(|table| table.oid).collect();
+    apply(target, &sequence_owner_statements(source, &oids).await?).await?;
+
+    let retried = try_apply(
+        target,
+        failed_functions
+            .into_iter()
+            .map(|(statement, _)| statement)
+            .collect(),
+    )
+    .await;
+    for (statement, reason) in retried {
+        tracing::warn!("  ⚠ Skipping {}: {}", statement.object, reason);
+    }
+
+    apply(target, &constraint_statements(source, &oids).await?).await?;
+    apply(target, &index_statements(source, &oids).await?).await?;
+    apply(target, &replica_identity_statements(source, &oids).await?).await?;
+
+    let mut trigger_oids = oids.clone();
+    if !tables_only {
+        let views = create_views(source, target, database, filter).await?;
+        for (statement, reason) in try_apply(target, index_statements(source, &views).await?).await
+        {
+            tracing::warn!("  ⚠ Skipping {}: {}", statement.object, reason);
+        }
+        trigger_oids.extend(views);
+    }
+
+    if !options.skip_triggers {
+        for (statement, reason) in
+            try_apply(target, trigger_statements(source, &trigger_oids).await?).await
+        {
+            tracing::warn!("  ⚠ Skipping {}: {}", statement.object, reason);
+        }
+    }
+    let security = security_statements(source, &oids, options.include_policies).await?;
+    for (statement, reason) in try_apply(target, security).await {
+        tracing::warn!("  ⚠ Skipping {}: {}", statement.object, reason);
+    }
+    Ok(())
+}
+
+async fn schema_statements(
+    source: &Client,
+    database: &str,
+    filter: &ReplicationFilter,
+) -> Result<Vec<Statement>> {
+    let rows = source
+        .query(
+            &format!(
+                "SELECT n.nspname, obj_description(n.oid, 'pg_namespace')
+                 FROM pg_namespace n
+                 WHERE {USER_SCHEMAS}
+                   AND NOT EXISTS (
+                       SELECT 1 FROM pg_depend d
+                       WHERE d.classid = 'pg_namespace'::regclass AND d.objid = n.oid
+                         AND d.deptype = 'e')
+                 ORDER BY 1"
+            ),
+            &[],
+        )
+        .await
+        .context("Failed to list schemas")?;
+    let mut statements = Vec::new();
+    for row in rows {
+        let schema: String = row.get(0);
+        if !filter.should_replicate_schema(database, &schema) {
+            continue;
+        }
+        let object = format!("schema {}", schema);
+        statements.push(Statement::new(
+            &object,
+            format!("CREATE SCHEMA IF NOT EXISTS {}", quote_ident(&schema)),
+        ));
+        if let Some(sql) = comment_sql("SCHEMA", &quote_ident(&schema), &row.get(1)) {
+            statements.push(Statement::new(&object, sql));
+        }
+    }
+    Ok(statements)
+}
+
+async fn extension_statements(source: &Client) -> Result<Vec<Statement>> {
+    let rows = source
+        .query(
+            "SELECT e.extname, n.nspname
+             FROM pg_extension e
+             JOIN pg_namespace n ON n.oid = e.extnamespace
+             WHERE e.extname <> 'plpgsql'
+             ORDER BY e.oid",
+            &[],
+        )
+        .await
+        .context("Failed to list extensions")?;
+    Ok(rows
+        .iter()
+        .map(|row| {
+            let name: &str = row.get(0);
+            let schema: &str = row.get(1);
+            Statement::new(
+                format!("extension {}", name),
+                format!(
+                    "CREATE SCHEMA IF NOT EXISTS {schema}; CREATE EXTENSION IF NOT EXISTS {} WITH SCHEMA {schema}",
+                    quote_ident(name),
+                    schema = quote_ident(schema)
+                ),
+            )
+        })
+        .collect())
+}
+
+async fn type_statements(
+    source: &Client,
+    database: &str,
+    filter: &ReplicationFilter,
+) -> Result<Vec<Statement>> {
+    let rows = source
+        .query(
+            &format!(
+                "SELECT n.nspname, t.typname, t.typtype::text,
+                        ARRAY(SELECT quote_literal(e.enumlabel) FROM pg_enum e
+                              WHERE e.enumtypid = t.oid ORDER BY e.enumsortorder),
+                        format_type(t.typbasetype, t.typtypmod),
+                        t.typnotnull,
+                        t.typdefault,
+                        CASE WHEN t.typcollation <> 0 AND t.typcollation <> bt.typcollation
+                             THEN format('%I.%I', cn.nspname, co.collname) END,
+                        ARRAY(SELECT format('CONSTRAINT %I %s', con.conname, pg_get_constraintdef(con.oid))
+                              FROM pg_constraint con
+                              WHERE con.contypid = t.oid AND con.contype = 'c'
+                              ORDER BY con.conname),
+                        ARRAY(SELECT format('%I %s', a.attname, format_type(a.atttypid, a.atttypmod))
+                              FROM pg_attribute a
+                              WHERE a.attrelid = t.typrelid AND a.attnum > 0 AND NOT a.attisdropped
+                              ORDER BY a.attnum),
+                        obj_description(t.oid, 'pg_type')
+                 FROM pg_type t
+                 JOIN pg_namespace n ON n.oid = t.typnamespace
+                 LEFT JOIN pg_type bt ON bt.oid = t.typbasetype
+                 LEFT JOIN pg_collation co ON co.oid = t.typcollation
+                 LEFT JOIN pg_namespace cn ON cn.oid = co.collnamespace
+                 LEFT JOIN pg_class c ON c.oid = t.typrelid
+                 WHERE {USER_SCHEMAS}
+                   AND (t.typtype IN ('e', 'd') OR (t.typtype = 'c' AND c.relkind = 'c'))
+                   AND NOT EXISTS (
+                       SELECT 1 FROM pg_depend d
+                       WHERE d.classid = 'pg_type'::regclass AND d.objid = t.oid
+                         AND d.deptype = 'e')
+                 ORDER BY CASE t.typtype WHEN 'e' THEN 0 WHEN 'd' THEN 1 ELSE 2 END, t.oid"
+            ),
+            &[],
+        )
+        .await
+        .context("Failed to list types")?;
+
+    let mut statements = Vec::new();
+    for row in rows {
+        let schema: String = row.get(0);
+        if !filter.should_replicate_schema(database, &schema) {
+            continue;
+        }
+        let name = format!("{}.{}", quote_ident(&schema), quote_ident(row.get(1)));
+        let object = format!("type {}.{}", schema, row.get::<_, &str>(1));
+        let sql = match row.get::<_, &str>(2) {
+            "e" => format!(
+                "CREATE TYPE {} AS ENUM ({})",
+                name,
+                row.get::<_, Vec<String>>(3).join(", ")
+            ),
+            "d" => {
+                let mut sql = format!("CREATE DOMAIN {} AS {}", name, row.get::<_, &str>(4));
+                if let Some(collation) = row.get::<_, Option<&str>>(7) {
+                    sql.push_str(&format!(" COLLATE {}", collation));
+                }
+                if let Some(default) = row.get::<_, Option<&str>>(6) {
+                    sql.push_str(&format!(" DEFAULT {}", default));
+                }
+                if row.get(5) {
+                    sql.push_str(" NOT NULL");
+                }
+                for constraint in row.get::<_, Vec<String>>(8) {
+                    sql.push(' ');
+                    sql.push_str(&constraint);
+                }
+                sql
+            }
+            _ => format!(
+                "CREATE TYPE {} AS ({})",
+                name,
+                row.get::<_, Vec<String>>(9).join(", ")
+            ),
+        };
+        statements.push(Statement::new(&object, sql));
+        if let Some(sql) = comment_sql("TYPE", &name, &row.get(10)) {
+            statements.push(Statement::new(&object, sql));
+        }
+    }
+    Ok(statements)
+}
+
+// Identity sequences are created with their column
+async fn sequence_statements(
+    source: &Client,
+    database: &str,
+    filter: &ReplicationFilter,
+) -> Result<Vec<Statement>> {
+    let rows = source
+        .query(
+            &format!(
+                "SELECT n.nspname, c.relname, format_type(s.seqtypid, NULL),
+                        s.seqstart, s.seqincrement, s.seqmin, s.seqmax, s.seqcache, s.seqcycle
+                 FROM pg_sequence s
+                 JOIN pg_class c ON c.oid = s.seqrelid
+                 JOIN pg_namespace n ON n.oid = c.relnamespace
+                 WHERE {USER_SCHEMAS}
+                   AND NOT EXISTS (
+                       SELECT 1 FROM pg_depend d
+                       WHERE d.classid = 'pg_class'::regclass AND d.objid = c.oid
+                         AND d.deptype IN ('e', 'i'))
+                 ORDER BY 1, 2"
+            ),
+            &[],
+        )
+        .await
+        .context("Failed to list sequences")?;
+    Ok(rows
+        .iter()
+        .filter(|row| filter.should_replicate_schema(database, row.get(0)))
+        .map(|row| {
+            let schema: &str = row.get(0);
+            let name: &str = row.get(1);
+            Statement::new(
+                format!("sequence {}.{}", schema, name),
+                format!(
+                    "CREATE SEQUENCE {}.{} AS {} START WITH {} INCREMENT BY {} MINVALUE {} MAXVALUE {} CACHE {}{}",
+                    quote_ident(schema),
+                    quote_ident(name),
+                    row.get::<_, &str>(2),
+                    row.get::<_, i64>(3),
+                    row.get::<_, i64>(4),
+                    row.get::<_, i64>(5),
+                    row.get::<_, i64>(6),
+                    row.get::<_, i64>(7),
+                    if row.get(8) { " CYCLE" } else { "" }
+                ),
+            )
+        })
+        .collect())
+}
+
+async fn function_statements(
+    source: &Client,
+    database: &str,
+    filter: &ReplicationFilter,
+) -> Result<Vec<Statement>> {
+    let rows = source
+        .query(
+            &format!(
+                "SELECT n.nspname, p.oid::regprocedure::text, pg_get_functiondef(p.oid)
+                 FROM pg_proc p
+                 JOIN pg_namespace n ON n.oid = p.pronamespace
+                 WHERE {USER_SCHEMAS}
+                   AND p.prokind IN ('f', 'p')
+                   AND NOT EXISTS (
+                       SELECT 1 FROM pg_depend d
+                       WHERE d.classid = 'pg_proc'::regclass AND d.objid = p.oid
+                         AND d.deptype = 'e')
+                 ORDER BY p.oid"
+            ),
+            &[],
+        )
+        .await
+        .context("Failed to list functions")?;
+    Ok(rows
+        .iter()
+        .filter(|row| filter.should_replicate_schema(database, row.get(0)))
+        .map(|row| {
+            Statement::new(
+                format!("function {}", row.get::<_, &str>(1)),
+                row.get::<_, String>(2),
+            )
+        })
+        .collect())
+}
+
+/// The source's tables that `filter` replicates, parents before partitions and
+/// inheritance children
+///
+/// A partition is kept when its parent is, whatever its own name.
+async fn selected_tables(
+    source: &Client,
+    database: &str,
+    filter: &ReplicationFilter,
+) -> Result<Vec<Table>> {
+    let metadata = crate::topology::metadata_table_condition("n.nspname", "c.relname");
+    let rows = source
+        .query(
+            &format!(
+                "SELECT c.oid, n.nspname, c.relname, c.relkind::text, c.relpersistence = 'u',
+                        (SELECT i.inhparent FROM pg_inherits i
+                         WHERE i.inhrelid = c.oid AND c.relispartition),
+                        CASE WHEN c.relispartition THEN pg_get_expr(c.relpartbound, c.oid) END,
+                        CASE WHEN c.relkind = 'p' THEN pg_get_partkeydef(c.oid) END,
+                        ARRAY(SELECT i.inhparent FROM pg_inherits i
+                              WHERE i.inhrelid = c.oid AND NOT c.relispartition
+                              ORDER BY i.inhseqno),
+                        obj_description(c.oid, 'pg_class')
+                 FROM pg_class c
+                 JOIN pg_namespace n ON n.oid = c.relnamespace
+                 WHERE {USER_SCHEMAS}
+                   AND c.relkind IN ('r', 'p')
+                   AND NOT {metadata}
+                   AND NOT EXISTS (
+                       SELECT 1 FROM pg_depend d
+                       WHERE d.classid = 'pg_class'::regclass AND d.objid = c.oid
+                         AND d.deptype = 'e')
+                 ORDER BY c.oid"
+            ),
+            &[],
+        )
+        .await
+        .context("Failed to list tables")?;
+
+    struct Row {
+        table: Table,
+        partition_parent: Option<u32>,
+        inherits: Vec<u32>,
+    }
+    let mut pending: Vec<Row> = rows
+        .iter()
+        .map(|row| Row {
+            table: Table {
+                oid: row.get(0),
+                schema: row.get(1),
+                name: row.get(2),
+                kind: row.get(3),
+                unlogged: row.get(4),
+                partition_of: row
+                    .get::<_, Option<String>>(6)
+                    .map(|bound| (String::new(), bound)),
+                partition_key: row.get(7),
+                inherits: Vec::new(),
+                comment: row.get(9),
+            },
+            partition_parent: row.get(5),
+            inherits: row.get(8),
+        })
+        .collect();
+    let names: HashMap<u32, String> = pending
+        .iter()
+        .map(|row| (row.table.oid, row.table.quoted()))
+        .collect();
+
+    // Each pass creates the tables whose parents are already placed
+    let mut placed: HashSet<u32> = HashSet::new();
+    let mut kept: HashSet<u32> = HashSet::new();
+    let mut ordered = Vec::new();
+    while !pending.is_empty() {
+        let (ready, waiting): (Vec<Row>, Vec<Row>) = pending.into_iter().partition(|row| {
+            row.partition_parent
+                .iter()
+                .chain(&row.inherits)
+                .all(|parent| placed.contains(parent) || !names.contains_key(parent))
+        });
+        if ready.is_empty() {
+            anyhow::bail!("Tables inherit from each other in a cycle");
+        }
+        for mut row in ready {
+            placed.insert(row.table.oid);
+            let filter_name = if row.table.schema == "public" {
+                row.table.name.clone()
+            } else {
+                format!("{}.{}", row.table.schema, row.table.name)
+            };
+            let keep = match row.partition_parent {
+                Some(parent) => kept.contains(&parent),
+                None => filter.should_replicate_table(database, &filter_name),
+            };
+            if !keep {
+                continue;
+            }
+            if let (Some(parent), Some((name, _))) =
+                (row.partition_parent, row.table.partition_of.as_mut())
+            {
+                *name = names[&parent].clone();
+            }
+            row.table.inherits = row
+                .inherits
+                .iter()
+                .filter(|parent| kept.contains(parent))
+                .map(|parent| names[parent].clone())
+                .collect();
+            kept.insert(row.table.oid);
+            ordered.push(row.table);
+        }
+        pending = waiting;
+    }
+    Ok(ordered)
+}
+
+async fn table_statements(source: &Client, tables: &[Table]) -> Result<Vec<Statement>> {
+    let oids: Vec<u32> = tables.iter().map(|table| table.oid).collect();
+    let rows = source
+        .query(
+            "SELECT a.attrelid, a.attname, format_type(a.atttypid, a.atttypmod), a.attnotnull,
+                    pg_get_expr(ad.adbin, ad.adrelid), a.attidentity::text, a.attgenerated::text,
+                    CASE WHEN a.attcollation <> 0 AND a.attcollation <> t.typcollation
+                         THEN format('%I.%I', cn.nspname, co.collname) END,
+                    a.attislocal,
+                    col_description(a.attrelid, a.attnum)
+             FROM pg_attribute a
+             JOIN pg_type t ON t.oid = a.atttypid
+             LEFT JOIN pg_attrdef ad ON ad.adrelid = a.attrelid AND ad.adnum = a.attnum
+             LEFT JOIN pg_collation co ON co.oid = a.attcollation
+             LEFT JOIN pg_namespace cn ON cn.oid = co.collnamespace
+             WHERE a.attrelid = ANY($1) AND a.attnum > 0 AND NOT a.attisdropped
+             ORDER BY a.attrelid, a.attnum",
+            &[&oids],
+        )
+        .await
+        .context("Failed to list table columns")?;
+
+    let mut columns: HashMap<u32, Vec<String>> = HashMap::new();
+    let mut comments: HashMap<u32, Vec<String>> = HashMap::new();
+    for row in &rows {
+        let oid: u32 = row.get(0);
+        let name = quote_ident(row.get(1));
+        if let Some(comment) = row.get::<_, Option<&str>>(9) {
+            comments.entry(oid).or_default().push(format!(
+                "{} IS {}",
+                name,
+                quote_literal(comment)
+            ));
+        }
+        // Inherited columns come from the parent
+        if !row.get::<_, bool>(8) {
+            continue;
+        }
+        let mut column = format!("{} {}", name, row.get::<_, &str>(2));
+        if let Some(collation) = row.get::<_, Option<&str>>(7) {
+            column.push_str(&format!(" COLLATE {}", collation));
+        }
+        let default: Option<&str> = row.get(4);
+        match (row.get::<_, &str>(5), row.get::<_, &str>(6), default) {
+            ("a", _, _) => column.push_str(" GENERATED ALWAYS AS IDENTITY"),
+            ("d", _, _) => column.push_str(" GENERATED BY DEFAULT AS IDENTITY"),
+            (_, "s", Some(expression)) => {
+                column.push_str(&format!(" GENERATED ALWAYS AS ({}) STORED", expression))
+            }
+            (_, _, Some(default)) => column.push_str(&format!(" DEFAULT {}", default)),
+            _ => {}
+        }
+        if row.get(3) {
+            column.push_str(" NOT NULL");
+        }
+        columns.entry(oid).or_default().push(column);
+    }
+
+    let mut statements = Vec::new();
+    for table in tables {
+        let object = format!("table {}.{}", table.schema, table.name);
+        let name = table.quoted();
+        let mut sql = match &table.partition_of {
+            Some((parent, bound)) => {
+                format!("CREATE TABLE {} PARTITION OF {} {}", name, parent, bound)
+            }
+            None => format!(
+                "CREATE {}TABLE {} (\n    {}\n)",
+                if table.unlogged { "UNLOGGED " } else { "" },
+                name,
+                columns
+                    .get(&table.oid)
+                    .map(|columns| columns.join(",\n    "))
+                    .unwrap_or_default()
+            ),
+        };
+        if !table.inherits.is_empty() {
+            sql.push_str(&format!(" INHERITS ({})", table.inherits.join(", ")));
+        }
+        if let Some(key) = &table.partition_key {
+            sql.push_str(&format!(" PARTITION BY {}", key));
+        }
+        statements.push(Statement::new(&object, sql));
+        if let Some(sql) = comment_sql("TABLE", &name, &table.comment) {
+            statements.push(Statement::new(&object, sql));
+        }
+        for comment in comments.get(&table.oid).into_iter().flatten() {
+            statements.push(Statement::new(
+                &object,
+                format!("COMMENT ON COLUMN {}.{}", name, comment),
+            ));
+        }
+    }
+    Ok(statements)
+}
+
+/// `OWNED BY` for serial columns, so the sequence goes with its table
+async fn sequence_owner_statements(source: &Client, oids: &[u32]) -> Result<Vec<Statement>> {
+    let rows = source
+        .query(
+            "SELECT sn.nspname, s.relname, tn.nspname, t.relname, a.attname
+             FROM pg_depend d
+             JOIN pg_class s ON s.oid = d.objid AND s.relkind = 'S'
+             JOIN pg_namespace sn ON sn.oid = s.relnamespace
+             JOIN pg_class t ON t.oid = d.refobjid
+             JOIN pg_namespace tn ON tn.oid = t.relnamespace
+             JOIN pg_attribute a ON a.attrelid = d.refobjid AND a.attnum = d.refobjsubid
+             WHERE d.classid = 'pg_class'::regclass
+               AND d.refclassid = 'pg_class'::regclass
+               AND d.deptype = 'a'
+               AND d.refobjid = ANY($1)
+             ORDER BY 1, 2",
+            &[&oids],
+        )
+        .await
+        .context("Failed to list sequence owners")?;
+    Ok(rows
+        .iter()
+        .map(|row| {
+            Statement::new(
+                format!(
+                    "sequence {}.{}",
+                    row.get::<_, &str>(0),
+                    row.get::<_, &str>(1)
+                ),
+                format!(
+                    "ALTER SEQUENCE {}.{} OWNED BY {}.{}.{}",
+                    quote_ident(row.get(0)),
+                    quote_ident(row.get(1)),
+                    quote_ident(row.get(2)),
+                    quote_ident(row.get(3)),
+                    quote_ident(row.get(4))
+                ),
+            )
+        })
+        .collect())
+}
+
+/// Check, key, exclusion, and foreign key constraints, foreign keys last
+///
+/// Constraints a partition or child table inherits come with its parent's.
+/// Foreign keys to tables that are not copied are left out with a warning.
+async fn constraint_statements(source: &Client, oids: &[u32]) -> Result<Vec<Statement>> {
+    let rows = source
+        .query(
+            "SELECT n.nspname, c.relname, con.conname, con.contype::text,
+                    pg_get_constraintdef(con.oid), con.confrelid,
+                    obj_description(con.oid, 'pg_constraint')
+             FROM pg_constraint con
+             JOIN pg_class c ON c.oid = con.conrelid
+             JOIN pg_namespace n ON n.oid = c.relnamespace
+             WHERE con.conrelid = ANY($1)
+               AND con.contype IN ('c', 'p', 'u', 'x', 'f')
+               AND con.conislocal
+               AND con.conparentid = 0
+             ORDER BY CASE con.contype WHEN 'c' THEN 0 WHEN 'f' THEN 2 ELSE 1 END,
+                      n.nspname, c.relname, con.conname",
+            &[&oids],
+        )
+        .await
+        .context("Failed to list constraints")?;
+    let copied: HashSet<u32> = oids.iter().copied().collect();
+
+    let mut statements = Vec::new();
+    for row in rows {
+        let table = format!("{}.{}", quote_ident(row.get(0)), quote_ident(row.get(1)));
+        let constraint: &str = row.get(2);
+        let object = format!(
+            "constraint {} on {}.{}",
+            constraint,
+            row.get::<_, &str>(0),
+            row.get::<_, &str>(1)
+        );
+        if row.get::<_, &str>(3) == "f" && !copied.contains(&row.get::<_, u32>(5)) {
+            tracing::warn!(
+                "  ⚠ Skipping foreign key {}: it references a table that is not copied",
+                object
+            );
+            continue;
+        }
+        statements.push(Statement::new(
+            &object,
+            format!(
+                "ALTER TABLE {} ADD CONSTRAINT {} {}",
+                table,
+                quote_ident(constraint),
+                row.get::<_, &str>(4)
+            ),
+        ));
+        if let Some(comment) = row.get::<_, Option<String>>(6) {
+            statements.push(Statement::new(
+                &object,
+                format!(
+                    "COMMENT ON CONSTRAINT {} ON {} IS {}",
+                    quote_ident(constraint),
+                    table,
+                    quote_literal(&comment)
+                ),
+            ));
+        }
+    }
+    Ok(statements)
+}
+
+/// Indexes that no constraint creates, on the given tables or materialized views
+///
+/// Indexes on partitioned tables are created on every partition at once.
+async fn index_statements(source: &Client, oids: &[u32]) -> Result<Vec<Statement>> {
+    let rows = source
+        .query(
+            "SELECT n.nspname, ic.relname, pg_get_indexdef(i.indexrelid),
+                    obj_description(i.indexrelid, 'pg_class')
+             FROM pg_index i
+             JOIN pg_class ic ON ic.oid = i.indexrelid
+             JOIN pg_namespace n ON n.oid = ic.relnamespace
+             WHERE i.indrelid = ANY($1)
+               AND NOT ic.relispartition
+               AND NOT EXISTS (
+                   SELECT 1 FROM pg_constraint con
+                   WHERE con.conindid = i.indexrelid AND con.conrelid = i.indrelid
+                     AND con.contype IN ('p', 'u', 'x'))
+             ORDER BY 1, 2",
+            &[&oids],
+        )
+        .await
+        .context("Failed to list indexes")?;
+    let mut statements = Vec::new();
+    for row in rows {
+        let object = format!("index {}.{}", row.get::<_, &str>(0), row.get::<_, &str>(1));
+        let definition: &str = row.get(2);
+        statements.push(Statement::new(
+            &object,
+            definition.replacen(" ON ONLY ", " ON ", 1),
+        ));
+        let name = format!("{}.{}", quote_ident(row.get(0)), quote_ident(row.get(1)));
+        if let Some(sql) = comment_sql("INDEX", &name, &row.get(3)) {
+            statements.push(Statement::new(&object, sql));
+        }
+    }
+    Ok(statements)
+}
+
+/// Replica identity settings other than the default, which sync relies on
+async fn replica_identity_statements(source: &Client, oids: &[u32]) -> Result<Vec<Statement>> {
+    let rows = source
+        .query(
+            "SELECT n.nspname, c.relname, c.relreplident::text,
+                    (SELECT ic.relname FROM pg_index i
+                     JOIN pg_class ic ON ic.oid = i.indexrelid
+                     WHERE i.indrelid = c.oid AND i.indisreplident)
+             FROM pg_class c
+             JOIN pg_namespace n ON n.oid = c.relnamespace
+             WHERE c.oid = ANY($1) AND c.relreplident <> 'd'
+             ORDER BY 1, 2",
+            &[&oids],
+        )
+        .await
+        .context("Failed to list replica identities")?;
+    Ok(rows
+        .iter()
+        .filter_map(|row| {
+            let identity = match (row.get::<_, &str>(2), row.get::<_, Option<&str>>(3)) {
+                ("f", _) => "FULL".to_string(),
+                ("n", _) => "NOTHING".to_string(),
+                ("i", Some(index)) => format!("USING INDEX {}", quote_ident(index)),
+                _ => return None,
+            };
+            Some(Statement::new(
+                format!(
+                    "replica identity of {}.{}",
+                    row.get::<_, &str>(0),
+                    row.get::<_, &str>(1)
+                ),
+                format!(
+                    "ALTER TABLE {}.{} REPLICA IDENTITY {}",
+                    quote_ident(row.get(0)),
+                    quote_ident(row.get(1)),
+                    identity
+                ),
+            ))
+        })
+        .collect())
+}
+
+/// Create views and materialized views (`WITH NO DATA`), retrying until every
+/// view whose dependencies exist has been created
+///
+/// Returns the oids of the views created on the source, for their indexes and
+/// triggers.
+async fn create_views(
+    source: &Client,
+    target: &Client,
+    database: &str,
+    filter: &ReplicationFilter,
+) -> Result<Vec<u32>> {
+    let rows = source
+        .query(
+            &format!(
+                "SELECT c.oid, n.nspname, c.relname, c.relkind::text, pg_get_viewdef(c.oid),
+                        array_to_string(c.reloptions, ', '),
+                        obj_description(c.oid, 'pg_class')
+                 FROM pg_class c
+                 JOIN pg_namespace n ON n.oid = c.relnamespace
+                 WHERE {USER_SCHEMAS}
+                   AND c.relkind IN ('v', 'm')
+                   AND NOT EXISTS (
+                       SELECT 1 FROM pg_depend d
+                       WHERE d.classid = 'pg_class'::regclass AND d.objid = c.oid
+                         AND d.deptype = 'e')
+                 ORDER BY c.oid"
+            ),
+            &[],
+        )
+        .await
+        .context("Failed to list views")?;
+
+    let mut pending: Vec<(u32, Statement)> = Vec::new();
+    for row in rows {
+        let schema: &str = row.get(1);
+        if !filter.should_replicate_schema(database, schema) {
+            continue;
+        }
+        let name = format!("{}.{}", quote_ident(schema), quote_ident(row.get(2)));
+        let materialized = row.get::<_, &str>(3) == "m";
+        let definition = row.get::<_, &str>(4).trim().trim_end_matches(';');
+        let options = row
+            .get::<_, Option<&str>>(5)
+            .filter(|options| !options.is_empty() && !materialized)
+            .map(|options| format!(" WITH ({})", options))
+            .unwrap_or_default();
+        let mut sql = if materialized {
+            format!(
+                "CREATE MATERIALIZED VIEW {} AS {} WITH NO DATA",
+                name, definition
+            )
+        } else {
+            format!("CREATE VIEW {}{} AS {}", name, options, definition)
+        };
+        if let Some(comment) = comment_sql(
+            if materialized {
+                "MATERIALIZED VIEW"
+            } else {
+                "VIEW"
+            },
+            &name,
+            &row.get(6),
+        ) {
+            sql.push_str(";\n");
+            sql.push_str(&comment);
+        }
+        pending.push((
+            row.get(0),
+            Statement::new(format!("view {}.{}", schema, row.get::<_, &str>(2)), sql),
+        ));
+    }
+
+    // A view can depend on one created after it, so passes repeat while any succeed
+    let mut created = Vec::new();
+    loop {
+        let before = created.len();
+        let mut waiting = Vec::new();
+        for (oid, statement) in pending {
+            match target.batch_execute(&statement.sql).await {
+                Ok(()) => created.push(oid),
+                Err(e) => waiting.push((oid, statement, error_reason(&e))),
+            }
+        }
+        if waiting.is_empty() || created.len() == before {
+            for (_, statement, reason) in &waiting {
+                tracing::warn!("  ⚠ Skipping {}: {}", statement.object, reason);
+            }
+            break;
+        }
+        pending = waiting
+            .into_iter()
+            .map(|(oid, statement, _)| (oid, statement))
+            .collect();
+    }
+    Ok(created)
+}
+
+async fn trigger_statements(source: &Client, oids: &[u32]) -> Result<Vec<Statement>> {
+    let rows = source
+        .query(
+            "SELECT n.nspname, c.relname, t.tgname, pg_get_triggerdef(t.oid), t.tgenabled::text
+             FROM pg_trigger t
+             JOIN pg_class c ON c.oid = t.tgrelid
+             JOIN pg_namespace n ON n.oid = c.relnamespace
+             WHERE t.tgrelid = ANY($1)
+               AND NOT t.tgisinternal
+               AND COALESCE((to_jsonb(t) ->> 'tgparentid')::oid, 0) = 0
+             ORDER BY 1, 2, 3",
+            &[&oids],
+        )
+        .await
+        .context("Failed to list triggers")?;
+    Ok(rows
+        .iter()
+        .map(|row| {
+            let table = format!("{}.{}", quote_ident(row.get(0)), quote_ident(row.get(1)));
+            let trigger = quote_ident(row.get(2));
+            let mut sql = row.get::<_, String>(3);
+            let state = match row.get::<_, &str>(4) {
+                "D" => Some("DISABLE TRIGGER"),
+                "R" => Some("ENABLE REPLICA TRIGGER"),
+                "A" => Some("ENABLE ALWAYS TRIGGER"),
+                _ => None,
+            };
+            if let Some(state) = state {
+                sql.push_str(&format!(";\nALTER TABLE {} {} {}", table, state, trigger));
+            }
+            Statement::new(
+                format!(
+                    "trigger {} on {}.{}",
+                    row.get::<_, &str>(2),
+                    row.get::<_, &str>(0),
+                    row.get::<_, &str>(1)
+                ),
+                sql,
+            )
+        })
+        .collect())
+}
+
+/// Row-level security settings, and with `include_policies` the policies
+///
+/// A dumped schema keeps `ENABLE ROW LEVEL SECURITY` even when its policies
+/// are left out, so this does the same.
+async fn security_statements(
+    source: &Client,
+    oids: &[u32],
+    include_policies: bool,
+) -> Result<Vec<Statement>> {
+    let mut statements = Vec::new();
+    let rows = source
+        .query(
+            "SELECT n.nspname, c.relname, c.relforcerowsecurity
+             FROM pg_class c
+             JOIN pg_namespace n ON n.oid = c.relnamespace
+             WHERE c.oid = ANY($1) AND c.relrowsecurity
+             ORDER BY 1, 2",
+            &[&oids],
+        )
+        .await
+        .context("Failed to list row-level security settings")?;
+    for row in &rows {
+        let table = format!("{}.{}", quote_ident(row.get(0)), quote_ident(row.get(1)));
+        let object = format!(
+            "row-level security on {}.{}",
+            row.get::<_, &str>(0),
+            row.get::<_, &str>(1)
+        );
+        statements.push(Statement::new(
+            &object,
+            format!("ALTER TABLE {} ENABLE ROW LEVEL SECURITY", table),
+        ));
+        if !include_policies {
+            crate::migration::dump::warn_row_security_without_policies(&table);
+        }
+        if include_policies && row.get(2) {
+            statements.push(Statement::new(
+                &object,
+                format!("ALTER TABLE {} FORCE ROW LEVEL SECURITY", table),
+            ));
+        }
+    }
+    if !include_policies {
+        return Ok(statements);
+    }
+
+    let rows = source
+        .query(
+            "SELECT n.nspname, c.relname, p.polname, p.polpermissive, p.polcmd::text,
+                    ARRAY(SELECT CASE WHEN r = 0 THEN 'PUBLIC'
+                                      ELSE quote_ident(pg_get_userbyid(r)) END
+                          FROM unnest(p.polroles) AS r),
+                    pg_get_expr(p.polqual, p.polrelid),
+                    pg_get_expr(p.polwithcheck, p.polrelid)
+             FROM pg_policy p
+             JOIN pg_class c ON c.oid = p.polrelid
+             JOIN pg_namespace n ON n.oid = c.relnamespace
+             WHERE p.polrelid = ANY($1)
+             ORDER BY 1, 2, 3",
+            &[&oids],
+        )
+        .await
+        .context("Failed to list row-level security policies")?;
+    for row in &rows {
+        let command = match row.get::<_, &str>(4) {
+            "r" => "SELECT",
+            "a" => "INSERT",
+            "w" => "UPDATE",
+            "d" => "DELETE",
+            _ => "ALL",
+        };
+        let mut sql = format!(
+            "CREATE POLICY {} ON {}.{} AS {} FOR {} TO {}",
+            quote_ident(row.get(2)),
+            quote_ident(row.get(0)),
+            quote_ident(row.get(1)),
+            if row.get(3) {
+                "PERMISSIVE"
+            } else {
+                "RESTRICTIVE"
+            },
+            command,
+            row.get::<_, Vec<String>>(5).join(", ")
+        );
+        if let Some(using) = row.get::<_, Option<&str>>(6) {
+            sql.push_str(&format!(" USING ({})", using));
+        }
+        if let Some(check) = row.get::<_, Option<&str>>(7) {
+            sql.push_str(&format!(" WITH CHECK ({})", check));
+        }
+        statements.push(Statement::new(
+            format!(
+                "policy {} on {}.{}",
+                row.get::<_, &str>(2),
+                row.get::<_, &str>(0),
+                row.get::<_, &str>(1)
+            ),
+            sql,
+        ));
+    }
+    Ok(statements)
+}
+
+/// Tables whose rows the native copy transfers, as `"schema"."table"`
+///
+/// These are the tables [`copy_schema`] creates, less partitioned parents
+/// (their rows live in the partitions), schema-only tables, and the tables in
+/// `excluded`.
+pub async fn data_tables(
+    source: &Client,
+    database: &str,
+    filter: &ReplicationFilter,
+    excluded: &[String],
+) -> Result<Vec<String>> {
+    let schema_only = filter.schema_only_tables(database);
+    let excluded: BTreeSet<&str> = excluded
+        .iter()
+        .chain(&schema_only)
+        .map(String::as_str)
+        .collect();
+    Ok(selected_tables(source, database, filter)
+        .await?
+        .iter()
+        .filter(|table| table.kind == "r")
+        .map(Table::quoted)
+        .filter(|table| !excluded.contains(table.as_str()))
+        .collect())
+}
+
+/// Stream rows from the source to the target with `COPY`, `jobs` tables at once
+///
+/// Every source connection reads at `snapshot`. Tables are copied in foreign
+/// key waves, so the target's constraints hold as rows arrive, with the
+/// `priority` tables and the tables they reference first. Nothing is
+/// written to disk: each table's rows go straight from one connection to the
+/// other through a small compressed buffer. Generated columns are left for
+/// the target to compute. Returns the bytes moved.
+pub async fn copy_tables(
+    source_url: &str,
+    target_url: &str,
+    tables: &[String],
+    snapshot: &str,
+    jobs: usize,
+    priority: &[String],
+) -> Result<TransferBytes> {
+    if tables.is_empty() {
+        return Ok(TransferBytes::default());
+    }
+    let target_client = postgres::connect_with_retry(target_url)
+        .await
+        .context("Failed to connect to target database to copy data")?;
+    let foreign_keys = crate::migration::filtered::list_foreign_keys(&target_client).await?;
+    // Foreign keys on a partitioned table apply to its partitions
+    let roots: Vec<String> = target_client
+        .query(
+            "SELECT n.nspname, c.relname
+             FROM unnest($1::text[]) WITH ORDINALITY AS t(name, ord)
+             JOIN pg_class c
+               ON c.oid = COALESCE(pg_partition_root(t.name::regclass), t.name::regclass)
+             JOIN pg_namespace n ON n.oid = c.relnamespace
+             ORDER BY t.ord",
+            &[&tables],
+        )
+        .await
+        .context("Failed to look up partitioned tables")?
+        .iter()
+        .map(|row| format!("{}.{}", quote_ident(row.get(0)), quote_ident(row.get(1))))
+        .collect();
+    let waves = prioritized_waves(&roots, &foreign_keys, priority);
+
+    let jobs = jobs.clamp(1, tables.len());
+    let mut workers = Vec::with_capacity(jobs);
+    for _ in 0..jobs {
+        let source_client = postgres::connect_with_retry(source_url)
+            .await
+            .context("Failed to connect to source database to copy data")?;
+        postgres::snapshot::begin_at_snapshot(&source_client, snapshot).await?;
+        let target_client = postgres::connect_with_retry(target_url)
+            .await
+            .context("Failed to connect to target database to copy data")?;
+        workers.push((source_client, target_client));
+    }
+    tracing::info!(
+        "  Copying {} table(s) in {} wave(s) with {} connection(s)...",
+        tables.len(),
+        waves.len(),
+        jobs
+    );
+
+    let database = crate::utils::parse_postgres_url(target_url)
+        .map(|parts| parts.database)
+        .unwrap_or_default();
+    let started = Instant::now();
+    let finished = AtomicUsize::new(0);
+    let mut transferred = TransferBytes::default();
+    for wave in &waves {
+        let next = AtomicUsize::new(0);
+        let moved =
+            futures::future::try_join_all(workers.iter().map(|(source_client, target_client)| {
+                let (database, next, finished) = (&database, &next, &finished);
+                async move {
+                    let mut moved = TransferBytes::default();
+                    while let Some(&i) = wave.get(next.fetch_add(1, Ordering::Relaxed)) {
+                        let start = Instant::now();
+                        let (bytes, rows) =
+                            copy_table(source_client, target_client, database, &tables[i])
+                                .await
+                                .with_context(|| format!("Failed to copy rows of {}", tables[i]))?;
+                        tracing::info!(
+                            "    [{}/{}] Copied {} row(s) of {} in {:.1}s",
+                            finished.fetch_add(1, Ordering::Relaxed) + 1,
+                            tables.len(),
+                            rows,
+                            tables[i],
+                            start.elapsed().as_secs_f64()
+                        );
+                        moved += bytes;
+                    }
+                    Ok::<_, anyhow::Error>(moved)
+                }
+            }))
+            .await?;
+        for bytes in moved {
+            transferred += bytes;
+        }
+    }
+    tracing::info!(
+        "  ✓ Copied {} table(s) in {:.1}s",
+        tables.len(),
+        started.elapsed().as_secs_f64()
+    );
+    Ok(transferred)
+}
+
+async fn copy_table(
+    source_client: &Client,
+    target_client: &Client,
+    database: &str,
+    table: &str,
+) -> Result<(TransferBytes, u64)> {
+    let columns: Vec<String> = source_client
+        .query(
+            "SELECT quote_ident(attname) FROM pg_attribute
+             WHERE attrelid = $1::text::regclass AND attnum > 0
+               AND NOT attisdropped AND attgenerated = ''
+             ORDER BY attnum",
+            &[&table],
+        )
+        .await
+        .context("Failed to list columns")?
+        .iter()
+        .map(|row| row.get(0))
+        .collect();
+    let event_table = table.replace('"', "");
+    crate::events::emit(|sink| sink.on_table_start(database, &event_table));
+
+    // Text format, because binary arrays and records carry type oids that
+    // differ between databases
+    let column_list = columns.join(", ");
+    let reader = source_client
+        .copy_out(&format!(
+            "COPY (SELECT {} FROM ONLY {}) TO STDOUT",
+            column_list, table
+        ))
+        .await?;
+    let writer = target_client
+        .copy_in(&format!("COPY {} ({}) FROM STDIN", table, column_list))
+        .await?;
+    let (bytes, rows) = pipe_copy(reader, writer).await?;
+    tracing::debug!(
+        "Streamed {} bytes of {} ({} compressed in flight)",
+        bytes.read_bytes,
+        table,
+        bytes.compressed_bytes
+    );
+    crate::events::emit(|sink| sink.on_rows_copied(database, &event_table, rows));
+    Ok((bytes, rows))
+}
+
+/// Set the target's sequences to the source's current values
+///
+/// Sequences owned by a column are matched through the column, since an
+/// identity sequence can be named differently on the target. Returns how many
+/// sequences were set.
+pub async fn copy_sequence_values(source: &Client, target: &Client) -> Result<usize> {
+    let rows = source
+        .query(
+            &format!(
+                "SELECT n.nspname, c.relname, tn.nspname, t.relname, a.attname
+                 FROM pg_class c
+                 JOIN pg_namespace n ON n.oid = c.relnamespace
+                 LEFT JOIN pg_depend d
+                        ON d.classid = 'pg_class'::regclass AND d.objid = c.oid
+                       AND d.refclassid = 'pg_class'::regclass AND d.deptype IN ('a', 'i')
+                 LEFT JOIN pg_class t ON t.oid = d.refobjid
+                 LEFT JOIN pg_namespace tn ON tn.oid = t.relnamespace
+                 LEFT JOIN pg_attribute a ON a.attrelid = d.refobjid AND a.attnum = d.refobjsubid
+                 WHERE c.relkind = 'S' AND {USER_SCHEMAS}
+                 ORDER BY 1, 2"
+            ),
+            &[],
+        )
+        .await
+        .context("Failed to list sequences")?;
+
+    let mut set = 0;
+    for row in rows {
+        let sequence = format!("{}.{}", quote_ident(row.get(0)), quote_ident(row.get(1)));
+        let target_sequence: Option<String> = match row.get::<_, Option<&str>>(4) {
+            Some(column) => {
+                let table = format!("{}.{}", quote_ident(row.get(2)), quote_ident(row.get(3)));
+                target
+                    .query_one(
+                        "SELECT CASE WHEN to_regclass($1) IS NOT NULL
+                                     THEN pg_get_serial_sequence($1, $2) END",
+                        &[&table, &column],
+                    )
+                    .await
+                    .with_context(|| format!("Failed to find the sequence for {}", table))?
+                    .get(0)
+            }
+            None => target
+                .query_one("SELECT to_regclass($1)::text", &[&sequence])
+                .await
+                .with_context(|| format!("Failed to look up sequence {}", sequence))?
+                .get::<_, Option<String>>(0)
+                .map(|_| sequence.clone()),
+        };
+        let Some(target_sequence) = target_sequence else {
+            continue;
+        };
+        let value = match source
+            .query_one(
+                &format!("SELECT last_value, is_called FROM {}", sequence),
+                &[],
+            )
+            .await
+        {
+            Ok(value) => value,
+            Err(e) => {
+                tracing::warn!(
+                    "  ⚠ Could not read sequence {}: {}",
+                    sequence,
+                    error_reason(&e)
+                );
+                continue;
+            }
+        };
+        let last_value: i64 = value.get(0);
+        let is_called: bool = value.get(1);
+        target
+            .query_one(
+                "SELECT setval($1::text::regclass, $2, $3)",
+                &[&target_sequence, &last_value, &is_called],
+            )
+            .await
+            .with_context(|| format!("Failed to set sequence {}", target_sequence))?;
+        set += 1;
+    }
+    Ok(set)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_comment_sql() {
+        assert_eq!(
+            comment_sql("TABLE", "\"public\".\"t\"", &Some("it's".to_string())),
+            Some("COMMENT ON TABLE \"public\".\"t\" IS 'it''s'".to_string())
+        );
+        assert_eq!(comment_sql("TABLE", "\"public\".\"t\"", &None), None);
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_copy_schema_and_data() {
+        let source_url = std::env::var("TEST_SOURCE_URL").unwrap();
+        let target_url = std::env::var("TEST_TARGET_URL").unwrap();
+        let source = postgres::connect_with_retry(&source_url).await.unwrap();
+        let target = postgres::connect_with_retry(&target_url).await.unwrap();
+        let database = crate::utils::parse_postgres_url(&source_url)
+            .unwrap()
+            .database;
+        let filter = ReplicationFilter::empty();
+
+        copy_schema(
+            &source,
+            &target,
+            &database,
+            &filter,
+            SchemaOptions::default(),
+        )
+        .await
+        .unwrap();
+        let tables = data_tables(&source, &database, &filter, &[]).await.unwrap();
+        let snapshot = postgres::snapshot::ExportedSnapshot::export(&source_url)
+            .await
+            .unwrap();
+        copy_tables(&source_url, &target_url, &tables, snapshot.id(), 2, &[])
+            .await
+            .unwrap();
+        copy_sequence_values(&source, &target).await.unwrap();
+    }
+}
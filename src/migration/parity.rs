@@ -0,0 +1,667 @@
+// ABOUTME: Structural parity checks between source and target databases
+// ABOUTME: Compares indexes, constraints, triggers, and sequence values with severity levels
+
+use crate::filters::NamePattern;
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use tokio_postgres::Client;
+
+/// Kind of catalog object compared by [`compare_structure`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ObjectKind {
+    /// Indexes not backing a constraint
+    Index,
+    /// Primary key, unique, foreign key, check, and exclusion constraints
+    Constraint,
+    /// User-defined triggers
+    Trigger,
+    /// Sequences and their current values
+    Sequence,
+}
+
+impl ObjectKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ObjectKind::Index => "index",
+            ObjectKind::Constraint => "constraint",
+            ObjectKind::Trigger => "trigger",
+            ObjectKind::Sequence => "sequence",
+        }
+    }
+
+    pub fn parse(value: &str) -> Result<Self> {
+        match value {
+            "index" => Ok(ObjectKind::Index),
+            "constraint" => Ok(ObjectKind::Constraint),
+            "trigger" => Ok(ObjectKind::Trigger),
+            "sequence" => Ok(ObjectKind::Sequence),
+            other => bail!(
+                "Unknown object kind '{}'. Use index, constraint, trigger, or sequence",
+                other
+            ),
+        }
+    }
+}
+
+/// How much a structural difference matters
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Worth knowing, e.g. an index only the target has
+    Info,
+    /// Likely to hurt, e.g. a missing index or trigger
+    Warning,
+    /// The target is not equivalent, e.g. a missing constraint
+    Error,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+}
+
+/// How an object on the target differs from the source
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Difference {
+    /// On the source but not the target
+    Missing,
+    /// On both, with different definitions
+    Changed,
+    /// On the target but not the source
+    Extra,
+    /// A sequence whose target value is below the source's
+    Behind,
+}
+
+/// One structural difference between source and target
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StructureFinding {
+    pub database: String,
+    pub kind: ObjectKind,
+    pub schema: String,
+    /// Table the object belongs to; sequences not owned by a column have none
+    pub table: Option<String>,
+    pub name: String,
+    pub difference: Difference,
+    pub severity: Severity,
+    /// Definitions (or sequence values) on each side, where the object exists
+    pub source: Option<String>,
+    pub target: Option<String>,
+}
+
+impl StructureFinding {
+    /// `schema.table.name`, or `schema.name` for objects without a table
+    pub fn object_name(&self) -> String {
+        match &self.table {
+            Some(table) => format!("{}.{}.{}", self.schema, table, self.name),
+            None => format!("{}.{}", self.schema, self.name),
+        }
+    }
+
+    /// What differs, in a few words
+    pub fn describe(&self) -> String {
+        let or_none = |value: &Option<String>| value.clone().unwrap_or_default();
+        match self.difference {
+            Difference::Missing => "missing on target".to_string(),
+            Difference::Extra => "only on target".to_string(),
+            Difference::Changed => format!(
+                "differs: source `{}`, target `{}`",
+                or_none(&self.source),
+                or_none(&self.target)
+            ),
+            Difference::Behind => format!(
+                "behind source: source {}, target {}",
+                or_none(&self.source),
+                self.target.as_deref().unwrap_or("never used")
+            ),
+        }
+    }
+}
+
+/// Severity overrides and suppressions for structural checks
+///
+/// Set from the `[verify]` section of the replication config file (see
+/// [`crate::config::load_parity_rules_from_file`]).
+#[derive(Debug, Clone, Default)]
+pub struct ParityRules {
+    ignore: Vec<(Option<ObjectKind>, NamePattern)>,
+    severities: HashMap<ObjectKind, Severity>,
+    fail_on: Option<Severity>,
+}
+
+impl ParityRules {
+    /// Suppress findings matching `kind:pattern`, or `pattern` for every kind
+    ///
+    /// The pattern is a [`NamePattern`] matched against the object's
+    /// [`object_name`](StructureFinding::object_name), with or without the
+    /// database in front (e.g. `trigger:public.orders.audit_*` or
+    /// `index:shop.*.tmp_*`).
+    pub fn ignore(&mut self, spec: &str) -> Result<()> {
+        let (kind, pattern) = match spec.split_once(':') {
+            Some((kind, pattern)) if kind != "re" => (Some(ObjectKind::parse(kind)?), pattern),
+            _ => (None, spec),
+        };
+        let pattern = NamePattern::parse(pattern)
+            .with_context(|| format!("Invalid verify ignore entry '{}'", spec))?;
+        self.ignore.push((kind, pattern));
+        Ok(())
+    }
+
+    /// Report every finding of `kind` at `severity`
+    pub fn set_severity(&mut self, kind: ObjectKind, severity: Severity) {
+        self.severities.insert(kind, severity);
+    }
+
+    /// Lowest severity that fails `verify` (error unless set)
+    pub fn set_fail_on(&mut self, severity: Severity) {
+        self.fail_on = Some(severity);
+    }
+
+    pub fn fail_on(&self) -> Severity {
+        self.fail_on.unwrap_or(Severity::Error)
+    }
+
+    fn is_ignored(&self, finding: &StructureFinding) -> bool {
+        let name = finding.object_name();
+        let with_database = format!("{}.{}", finding.database, name);
+        self.ignore.iter().any(|(kind, pattern)| {
+            kind.is_none_or(|kind| kind == finding.kind)
+                && (pattern.matches(&name) || pattern.matches(&with_database))
+        })
+    }
+}
+
+/// A catalog object as compared between the two sides
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct CatalogObject {
+    /// Definition as PostgreSQL prints it; a sequence's last value
+    definition: Option<String>,
+    /// Unique index, whose absence lets duplicates in
+    unique: bool,
+}
+
+type ObjectKey = (ObjectKind, String, Option<String>, String);
+
+/// Indexes, constraints, triggers, and sequences of a database's user schemas
+#[derive(Debug, Clone, Default)]
+pub struct Catalog {
+    objects: BTreeMap<ObjectKey, CatalogObject>,
+}
+
+impl Catalog {
+    fn insert(
+        &mut self,
+        kind: ObjectKind,
+        schema: String,
+        table: Option<String>,
+        name: String,
+        object: CatalogObject,
+    ) {
+        self.objects.insert((kind, schema, table, name), object);
+    }
+}
+
+const USER_SCHEMAS: &str = "n.nspname NOT IN ('pg_catalog', 'information_schema')
+    AND n.nspname NOT LIKE 'pg_toast%'
+    AND n.nspname NOT LIKE 'pg_temp%'";
+
+/// Read the structural objects of every user schema
+pub async fn read_catalog(client: &Client) -> Result<Catalog> {
+    let mut catalog = Catalog::default();
+
+    // Indexes behind a constraint are compared as the constraint
+    let indexes = client
+        .query(
+            &format!(
+                "SELECT n.nspname, t.relname, i.relname, pg_get_indexdef(i.oid), ix.indisunique
+                 FROM pg_index ix
+                 JOIN pg_class i ON i.oid = ix.indexrelid
+                 JOIN pg_class t ON t.oid = ix.indrelid
+                 JOIN pg_namespace n ON n.oid = t.relnamespace
+                 WHERE {} AND NOT EXISTS (
+                     SELECT 1 FROM pg_constraint c WHERE c.conindid = ix.indexrelid
+                 )",
+                USER_SCHEMAS
+            ),
+            &[],
+        )
+        .await
+        .context("Failed to list indexes")?;
+    for row in indexes {
+        catalog.insert(
+            ObjectKind::Index,
+            row.get(0),
+            Some(row.get(1)),
+            row.get(2),
+            CatalogObject {
+                definition: Some(row.get(3)),
+                unique: row.get(4),
+            },
+        );
+    }
+
+    let constraints = client
+        .query(
+            &format!(
+                "SELECT n.nspname, t.relname, c.conname, pg_get_constraintdef(c.oid)
+                 FROM pg_constraint c
+                 JOIN pg_class t ON t.oid = c.conrelid
+                 JOIN pg_namespace n ON n.oid = t.relnamespace
+                 WHERE {} AND c.contype IN ('p', 'u', 'f', 'c', 'x')",
+                USER_SCHEMAS
+            ),
+            &[],
+        )
+        .await
+        .context("Failed to list constraints")?;
+    for row in constraints {
+        catalog.insert(
+            ObjectKind::Constraint,
+            row.get(0),
+            Some(row.get(1)),
+            row.get(2),
+            CatalogObject {
+                definition: Some(row.get(3)),
+                unique: false,
+            },
+        );
+    }
+
+    let triggers = client
+        .query(
+            &format!(
+                "SELECT n.nspname, t.relname, tg.tgname,
+                        pg_get_triggerdef(tg.oid)
+                        || CASE tg.tgenabled WHEN 'D' THEN ' (disabled)' ELSE '' END
+                 FROM pg_trigger tg
+                 JOIN pg_class t ON t.oid = tg.tgrelid
+                 JOIN pg_namespace n ON n.oid = t.relnamespace
+                 WHERE {} AND NOT tg.tgisinternal",
+                USER_SCHEMAS
+            ),
+            &[],
+        )
+        .await
+        .context("Failed to list triggers")?;
+    for row in triggers {
+        catalog.insert(
+            ObjectKind::Trigger,
+            row.get(0),
+            Some(row.get(1)),
+            row.get(2),
+            CatalogObject {
+                definition: Some(row.get(3)),
+                unique: false,
+            },
+        );
+    }
+
+    // last_value is NULL until the sequence is first used, and when the
+    // user may not read it
+    let sequences = client
+        .query(
+            &format!(
+                "SELECT n.nspname, t.relname, s.relname, ps.last_value
+                 FROM pg_class s
+                 JOIN pg_namespace n ON n.oid = s.relnamespace
+                 JOIN pg_sequences ps ON ps.schemaname = n.nspname AND ps.sequencename = s.relname
+                 LEFT JOIN pg_depend d ON d.classid = 'pg_class'::regclass
+                     AND d.objid = s.oid
+                     AND d.refclassid = 'pg_class'::regclass
+                     AND d.deptype IN ('a', 'i')
+                 LEFT JOIN pg_class t ON t.oid = d.refobjid
+                 WHERE s.relkind = 'S' AND {}",
+                USER_SCHEMAS
+            ),
+            &[],
+        )
+        .await
+        .context("Failed to list sequences")?;
+    for row in sequences {
+        catalog.insert(
+            ObjectKind::Sequence,
+            row.get(0),
+            row.get(1),
+            row.get(2),
+            CatalogObject {
+                definition: row.get::<_, Option<i64>>(3).map(|value| value.to_string()),
+                unique: false,
+            },
+        );
+    }
+
+    Ok(catalog)
+}
+
+/// Default severity of a difference before [`ParityRules`] overrides
+fn default_severity(kind: ObjectKind, difference: Difference, unique: bool) -> Severity {
+    match (kind, difference) {
+        (ObjectKind::Constraint, Difference::Extra) => Severity::Warning,
+        (_, Difference::Extra) => Severity::Info,
+        (ObjectKind::Constraint | ObjectKind::Sequence, _) => Severity::Error,
+        (ObjectKind::Index, _) if unique => Severity::Error,
+        (ObjectKind::Index | ObjectKind::Trigger, _) => Severity::Warning,
+    }
+}
+
+/// Differences between two catalogs for the tables `selected` accepts
+///
+/// Objects are matched by kind, schema, table, and name. Sequences not
+/// owned by a column are always compared; a sequence is only reported when
+/// the target is missing it or has a lower value, since values ahead of the
+/// source are harmless.
+pub fn compare_catalogs(
+    database: &str,
+    source: &Catalog,
+    target: &Catalog,
+    selected: impl Fn(&str, &str) -> bool,
+    rules: &ParityRules,
+) -> Vec<StructureFinding> {
+    let in_scope = |(kind, schema, table, _): &ObjectKey| match table {
+        Some(table) => selected(schema, table),
+        None => *kind == ObjectKind::Sequence,
+    };
+
+    let mut findings = Vec::new();
+    let mut report = |key: &ObjectKey,
+                      difference,
+                      unique,
+                      source: Option<&CatalogObject>,
+                      target: Option<&CatalogObject>| {
+        let (kind, schema, table, name) = key.clone();
+        let finding = StructureFinding {
+            database: database.to_string(),
+            kind,
+            schema,
+            table,
+            name,
+            difference,
+            severity: rules
+                .severities
+                .get(&kind)
+                .copied()
+                .unwrap_or_else(|| default_severity(kind, difference, unique)),
+            source: source.and_then(|object| object.definition.clone()),
+            target: target.and_then(|object| object.definition.clone()),
+        };
+        if !rules.is_ignored(&finding) {
+            findings.push(finding);
+        }
+    };
+
+    for (key, source_object) in source.objects.iter().filter(|(key, _)| in_scope(key)) {
+        let target_object = target.objects.get(key);
+        let difference = match (key.0, target_object) {
+            (_, None) => Difference::Missing,
+            (ObjectKind::Sequence, Some(target_object)) => {
+                let value = |object: &CatalogObject| {
+                    object
+                        .definition
+                        .as_deref()
+                        .and_then(|value| value.parse::<i64>().ok())
+                };
+                match (value(source_object), value(target_object)) {
+                    (Some(source), Some(target)) if target >= source => continue,
+                    (None, _) => continue,
+                    _ => Difference::Behind,
+                }
+            }
+            (_, Some(target_object)) if target_object.definition == source_object.definition => {
+                continue
+            }
+            (_, Some(_)) => Difference::Changed,
+        };
+        report(
+            key,
+            difference,
+            source_object.unique,
+            Some(source_object),
+            target_object,
+        );
+    }
+
+    for (key, target_object) in target.objects.iter().filter(|(key, _)| in_scope(key)) {
+        if key.0 != ObjectKind::Sequence && !source.objects.contains_key(key) {
+            report(
+                key,
+                Difference::Extra,
+                target_object.unique,
+                None,
+                Some(target_object),
+            );
+        }
+    }
+
+    findings
+}
+
+/// Read both catalogs and compare them for the tables `selected` accepts
+pub async fn compare_structure(
+    database: &str,
+    source_client: &Client,
+    target_client: &Client,
+    selected: impl Fn(&str, &str) -> bool,
+    rules: &ParityRules,
+) -> Result<Vec<StructureFinding>> {
+    let (source, target) = tokio::try_join!(
+        async {
+            read_catalog(source_client)
+                .await
+                .context("Failed to read source catalog")
+        },
+        async {
+            read_catalog(target_client)
+                .await
+                .context("Failed to read target catalog")
+        }
+    )?;
+    Ok(compare_catalogs(
+        database, &source, &target, selected, rules,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn object(definition: &str) -> CatalogObject {
+        CatalogObject {
+            definition: Some(definition.to_string()),
+            unique: false,
+        }
+    }
+
+    fn catalog(objects: &[(ObjectKind, &str, Option<&str>, &str, CatalogObject)]) -> Catalog {
+        let mut catalog = Catalog::default();
+        for (kind, schema, table, name, object) in objects {
+            catalog.insert(
+                *kind,
+                schema.to_string(),
+                table.map(str::to_string),
+                name.to_string(),
+                object.clone(),
+            );
+        }
+        catalog
+    }
+
+    fn source() -> Catalog {
+        catalog(&[
+            (
+                ObjectKind::Constraint,
+                "public",
+                Some("orders"),
+                "orders_customer_fk",
+                object("FOREIGN KEY (customer_id) REFERENCES customers(id)"),
+            ),
+            (
+                ObjectKind::Index,
+                "public",
+                Some("orders"),
+                "orders_created_idx",
+                object("CREATE INDEX orders_created_idx ON public.orders USING btree (created_at)"),
+            ),
+            (
+                ObjectKind::Index,
+                "public",
+                Some("orders"),
+                "orders_ref_key",
+                CatalogObject {
+                    unique: true,
+                    ..object(
+                        "CREATE UNIQUE INDEX orders_ref_key ON public.orders USING btree (ref)",
+                    )
+                },
+            ),
+            (
+                ObjectKind::Trigger,
+                "public",
+                Some("audit_log"),
+                "audit_insert",
+                object("CREATE TRIGGER audit_insert ..."),
+            ),
+            (
+                ObjectKind::Sequence,
+                "public",
+                Some("orders"),
+                "orders_id_seq",
+                object("500"),
+            ),
+            (
+                ObjectKind::Sequence,
+                "public",
+                None,
+                "invoice_numbers",
+                object("10"),
+            ),
+        ])
+    }
+
+    #[test]
+    fn test_identical_catalogs_have_no_findings() {
+        let findings = compare_catalogs(
+            "shop",
+            &source(),
+            &source(),
+            |_, _| true,
+            &ParityRules::default(),
+        );
+        assert!(findings.is_empty());
+    }
+
+    #[test]
+    fn test_compare_catalogs_severities() {
+        let target = catalog(&[
+            (
+                ObjectKind::Index,
+                "public",
+                Some("orders"),
+                "orders_created_idx",
+                object("CREATE INDEX orders_created_idx ON public.orders USING hash (created_at)"),
+            ),
+            (
+                ObjectKind::Index,
+                "public",
+                Some("orders"),
+                "orders_tmp_idx",
+                object("CREATE INDEX orders_tmp_idx ON public.orders USING btree (id)"),
+            ),
+            (
+                ObjectKind::Sequence,
+                "public",
+                Some("orders"),
+                "orders_id_seq",
+                object("120"),
+            ),
+            (
+                ObjectKind::Sequence,
+                "public",
+                None,
+                "invoice_numbers",
+                object("11"),
+            ),
+        ]);
+
+        let findings = compare_catalogs(
+            "shop",
+            &source(),
+            &target,
+            |_, table| table != "audit_log",
+            &ParityRules::default(),
+        );
+        let summary: Vec<_> = findings
+            .iter()
+            .map(|f| (f.object_name(), f.difference, f.severity))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (
+                    "public.orders.orders_created_idx".to_string(),
+                    Difference::Changed,
+                    Severity::Warning
+                ),
+                (
+                    "public.orders.orders_ref_key".to_string(),
+                    Difference::Missing,
+                    Severity::Error
+                ),
+                (
+                    "public.orders.orders_customer_fk".to_string(),
+                    Difference::Missing,
+                    Severity::Error
+                ),
+                (
+                    "public.orders.orders_id_seq".to_string(),
+                    Difference::Behind,
+                    Severity::Error
+                ),
+                (
+                    "public.orders.orders_tmp_idx".to_string(),
+                    Difference::Extra,
+                    Severity::Info
+                ),
+            ]
+        );
+        assert_eq!(
+            findings[3].describe(),
+            "behind source: source 500, target 120"
+        );
+    }
+
+    #[test]
+    fn test_rules_override_and_ignore() {
+        let mut rules = ParityRules::default();
+        rules.ignore("constraint:public.orders.*_fk").unwrap();
+        rules.ignore("shop.public.orders.orders_id_seq").unwrap();
+        rules
+            .ignore("re:public\\.orders\\.orders_\\w+_key")
+            .unwrap();
+        rules.set_severity(ObjectKind::Trigger, Severity::Error);
+        assert!(rules.ignore("view:public.*").is_err());
+
+        let findings =
+            compare_catalogs("shop", &source(), &Catalog::default(), |_, _| true, &rules);
+        let summary: Vec<_> = findings
+            .iter()
+            .map(|f| (f.object_name(), f.severity))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (
+                    "public.orders.orders_created_idx".to_string(),
+                    Severity::Warning
+                ),
+                ("public.audit_log.audit_insert".to_string(), Severity::Error),
+                ("public.invoice_numbers".to_string(), Severity::Error),
+            ]
+        );
+    }
+}
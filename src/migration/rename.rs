@@ -0,0 +1,180 @@
+// ABOUTME: Moves restored tables to the schema and name the table rules give them
+// ABOUTME: Runs after the load, so dumps and copies can keep using source names
+
+use crate::table_rules::TargetNames;
+use crate::utils::quote_ident;
+use anyhow::{bail, Context, Result};
+use std::collections::BTreeMap;
+use tokio_postgres::Client;
+
+/// A table written under another schema or name on the target
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableRename {
+    pub schema: String,
+    pub table: String,
+    pub target_schema: String,
+    pub target_table: String,
+}
+
+impl TableRename {
+    fn source_name(&self) -> String {
+        format!("{}.{}", quote_ident(&self.schema), quote_ident(&self.table))
+    }
+
+    fn target_name(&self) -> String {
+        format!(
+            "{}.{}",
+            quote_ident(&self.target_schema),
+            quote_ident(&self.target_table)
+        )
+    }
+
+    /// Statements moving the table, renaming it in its schema first
+    fn statements(&self) -> Vec<String> {
+        let mut statements = Vec::new();
+        if self.table != self.target_table {
+            statements.push(format!(
+                "ALTER TABLE {} RENAME TO {}",
+                self.source_name(),
+                quote_ident(&self.target_table)
+            ));
+        }
+        if self.schema != self.target_schema {
+            statements.push(format!(
+                "CREATE SCHEMA IF NOT EXISTS {}",
+                quote_ident(&self.target_schema)
+            ));
+            statements.push(format!(
+                "ALTER TABLE {}.{} SET SCHEMA {}",
+                quote_ident(&self.schema),
+                quote_ident(&self.target_table),
+                quote_ident(&self.target_schema)
+            ));
+        }
+        statements
+    }
+}
+
+/// The renames `names` makes among a database's `(schema, table)` pairs
+///
+/// Fails when two tables would end up with the same target name.
+pub fn plan(names: &TargetNames, tables: &[(String, String)]) -> Result<Vec<TableRename>> {
+    let mut targets: BTreeMap<(String, String), &(String, String)> = BTreeMap::new();
+    let mut renames = Vec::new();
+    for source in tables {
+        let (target_schema, target_table) = names.resolve(&source.0, &source.1);
+        let target = (target_schema.clone(), target_table.clone());
+        if let Some(other) = targets.insert(target, source) {
+            bail!(
+                "Tables {}.{} and {}.{} would both be written to {}.{} on the target",
+                other.0,
+                other.1,
+                source.0,
+                source.1,
+                target_schema,
+                target_table
+            );
+        }
+        if (&target_schema, &target_table) != (&source.0, &source.1) {
+            renames.push(TableRename {
+                schema: source.0.clone(),
+                table: source.1.clone(),
+                target_schema,
+                target_table,
+            });
+        }
+    }
+    Ok(renames)
+}
+
+async fn table_exists(client: &Client, name: &str) -> Result<bool> {
+    let row = client
+        .query_one("SELECT to_regclass($1) IS NOT NULL", &[&name])
+        .await
+        .with_context(|| format!("Failed to look up table {}", name))?;
+    Ok(row.get(0))
+}
+
+/// Move tables on the target to their target names, returning how many moved
+///
+/// Tables already under their target name, or not on the target, are left
+/// alone, so this can run again after an interrupted load. Each table moves
+/// in one transaction; views and foreign keys follow it.
+pub async fn apply(client: &Client, renames: &[TableRename]) -> Result<usize> {
+    let mut moved = 0;
+    for rename in renames {
+        let source_name = rename.source_name();
+        let target_name = rename.target_name();
+        match (
+            table_exists(client, &source_name).await?,
+            table_exists(client, &target_name).await?,
+        ) {
+            (false, _) => continue,
+            (true, true) => bail!(
+                "Cannot rename {} to {}: the target already has a table named {}",
+                source_name,
+                target_name,
+                target_name
+            ),
+            (true, false) => {}
+        }
+        let batch = format!("BEGIN; {}; COMMIT", rename.statements().join("; "));
+        client
+            .batch_execute(&batch)
+            .await
+            .with_context(|| format!("Failed to rename {} to {}", source_name, target_name))?;
+        tracing::info!("  Renamed {} to {}", source_name, target_name);
+        moved += 1;
+    }
+    Ok(moved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::table_rules::{IdentifierCase, QualifiedTable, TableRules};
+
+    fn tables(names: &[(&str, &str)]) -> Vec<(String, String)> {
+        names
+            .iter()
+            .map(|(schema, table)| (schema.to_string(), table.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_plan_skips_unchanged_tables() {
+        let mut rules = TableRules::default();
+        rules
+            .add_rename(
+                QualifiedTable::parse("app.public.events").unwrap(),
+                QualifiedTable::parse("analytics.events_v2").unwrap(),
+            )
+            .unwrap();
+        let renames = plan(
+            &rules.target_names("app"),
+            &tables(&[("public", "events"), ("public", "users")]),
+        )
+        .unwrap();
+        assert_eq!(renames.len(), 1);
+        assert_eq!(
+            renames[0].statements(),
+            vec![
+                "ALTER TABLE \"public\".\"events\" RENAME TO \"events_v2\"",
+                "CREATE SCHEMA IF NOT EXISTS \"analytics\"",
+                "ALTER TABLE \"public\".\"events_v2\" SET SCHEMA \"analytics\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_plan_rejects_colliding_targets() {
+        let mut rules = TableRules::default();
+        rules.set_identifier_case(None, IdentifierCase::Lower);
+        let names = rules.target_names("app");
+        assert_eq!(
+            plan(&names, &tables(&[("public", "Users")])).unwrap()[0].target_table,
+            "users"
+        );
+        assert!(plan(&names, &tables(&[("public", "Users"), ("public", "users")])).is_err());
+    }
+}
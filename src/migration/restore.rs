@@ -11,8 +11,12 @@ pub async fn restore_globals(target_url: &str, input_path: &str) -> Result<()> {
     tracing::info!("Restoring global objects from {}", input_path);
 
     // Parse URL and create .pgpass file for secure authentication
-    let parts = crate::utils::parse_postgres_url(target_url)
-        .with_context(|| format!("Failed to parse target URL: {}", target_url))?;
+    let parts = crate::utils::parse_postgres_url(target_url).with_context(|| {
+        format!(
+            "Failed to parse target URL: {}",
+            crate::redact::redact(target_url)
+        )
+    })?;
     let pgpass = crate::utils::PgPassFile::new(&parts)
         .context("Failed to create .pgpass file for authentication")?;
 
@@ -98,8 +102,12 @@ pub async fn restore_schema(target_url: &str, input_path: &str) -> Result<()> {
     tracing::info!("Restoring schema from {}", input_path);
 
     // Parse URL and create .pgpass file for secure authentication
-    let parts = crate::utils::parse_postgres_url(target_url)
-        .with_context(|| format!("Failed to parse target URL: {}", target_url))?;
+    let parts = crate::utils::parse_postgres_url(target_url).with_context(|| {
+        format!(
+            "Failed to parse target URL: {}",
+            crate::redact::redact(target_url)
+        )
+    })?;
     let pgpass = crate::utils::PgPassFile::new(&parts)
         .context("Failed to create .pgpass file for authentication")?;
 
@@ -122,7 +130,7 @@ pub async fn restore_schema(target_url: &str, input_path: &str) -> Result<()> {
                 .arg("ON_ERROR_STOP=1") // Stop on first error for better visibility
                 .env("PGPASSFILE", pgpass.path())
                 .stdout(Stdio::inherit())
-                .stderr(Stdio::inherit());
+                .stderr(Stdio::piped());
 
             // Add username if specified
             if let Some(user) = &parts.user {
@@ -142,7 +150,7 @@ pub async fn restore_schema(target_url: &str, input_path: &str) -> Result<()> {
             // Mitigate hangs on serverless DBs with strict connection limits
             cmd.env("PGCONNECT_TIMEOUT", "30");
 
-            cmd.status().context(
+            cmd.spawn().context(
                 "Failed to execute psql. Is PostgreSQL client installed?\n\
                  Install with:\n\
                  - Ubuntu/Debian: sudo apt-get install postgresql-client\n\
@@ -200,8 +208,12 @@ pub async fn restore_data(target_url: &str, input_path: &str) -> Result<()> {
     tracing::info!("Restoring data from {} (format=directory)", input_path);
 
     // Parse URL and create .pgpass file for secure authentication
-    let parts = crate::utils::parse_postgres_url(target_url)
-        .with_context(|| format!("Failed to parse target URL: {}", target_url))?;
+    let parts = crate::utils::parse_postgres_url(target_url).with_context(|| {
+        format!(
+            "Failed to parse target URL: {}",
+            crate::redact::redact(target_url)
+        )
+    })?;
     let pgpass = crate::utils::PgPassFile::new(&parts)
         .context("Failed to create .pgpass file for authentication")?;
 
@@ -0,0 +1,724 @@
+// ABOUTME: Explicit role, ownership, and GRANT replication for init
+// ABOUTME: Applies statements one by one and reports what the target refused
+
+use anyhow::{Context, Result};
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use std::path::{Path, PathBuf};
+use tokio_postgres::error::SqlState;
+use tokio_postgres::Client;
+
+use crate::utils::{quote_ident, quote_literal};
+
+/// Which access-control objects `init` replicates explicitly
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AccessOptions {
+    /// Create roles and memberships, and reassign object ownership to match the source
+    pub include_roles: bool,
+    /// Apply the source's GRANTs on schemas, tables, sequences, and functions
+    pub include_grants: bool,
+    /// Give login roles a generated password instead of leaving them without one
+    pub generate_passwords: bool,
+}
+
+impl AccessOptions {
+    pub fn is_enabled(&self) -> bool {
+        self.include_roles || self.include_grants
+    }
+}
+
+/// A cluster role read from the source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceRole {
+    pub name: String,
+    pub superuser: bool,
+    pub inherit: bool,
+    pub create_role: bool,
+    pub create_db: bool,
+    pub login: bool,
+    pub replication: bool,
+    pub bypass_rls: bool,
+    pub connection_limit: i32,
+    pub valid_until: Option<String>,
+}
+
+/// A statement the target refused, with the server's reason
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkippedStatement {
+    pub statement: String,
+    pub reason: String,
+}
+
+/// Outcome of role, ownership, and GRANT replication
+#[derive(Debug, Clone, Default)]
+pub struct AccessReport {
+    pub roles_created: Vec<String>,
+    pub roles_existing: Vec<String>,
+    pub applied: usize,
+    pub skipped: Vec<SkippedStatement>,
+    /// `(role, password)` pairs for roles created with a generated password
+    pub generated_passwords: Vec<(String, String)>,
+}
+
+impl AccessReport {
+    /// Fold another report (e.g. from the next database) into this one
+    pub fn merge(&mut self, other: AccessReport) {
+        self.roles_created.extend(other.roles_created);
+        self.roles_existing.extend(other.roles_existing);
+        self.applied += other.applied;
+        self.skipped.extend(other.skipped);
+        self.generated_passwords.extend(other.generated_passwords);
+    }
+
+    /// Print the report to stdout
+    pub fn print(&self, passwords_path: Option<&Path>) {
+        println!();
+        println!("Roles and Grants Report");
+        println!("{}", "═".repeat(61));
+        println!(
+            "Roles created: {}  Already present: {}  Statements applied: {}",
+            self.roles_created.len(),
+            self.roles_existing.len(),
+            self.applied
+        );
+        if let Some(path) = passwords_path {
+            println!(
+                "Generated passwords for {} role(s) written to {}",
+                self.generated_passwords.len(),
+                path.display()
+            );
+        }
+        println!();
+
+        if self.skipped.is_empty() {
+            println!("  ✓ Nothing was skipped");
+        } else {
+            println!("  Skipped (insufficient privileges or unsupported on target):");
+            for skipped in &self.skipped {
+                println!("  ⚠ {}", skipped.statement);
+                println!("      Reason: {}", skipped.reason);
+            }
+        }
+
+        println!("{}", "═".repeat(61));
+        println!();
+    }
+}
+
+/// List the source's user-defined roles (built-in `pg_*` roles are excluded)
+pub async fn list_source_roles(client: &Client) -> Result<Vec<SourceRole>> {
+    let rows = client
+        .query(
+            "SELECT rolname, rolsuper, rolinherit, rolcreaterole, rolcreatedb, rolcanlogin,
+                    rolreplication, rolbypassrls, rolconnlimit, rolvaliduntil::text
+             FROM pg_roles
+             WHERE oid >= 16384 AND rolname !~ '^pg_'
+             ORDER BY rolname",
+            &[],
+        )
+        .await
+        .context("Failed to list roles on source")?;
+
+    Ok(rows
+        .iter()
+        .map(|row| SourceRole {
+            name: row.get(0),
+            superuser: row.get(1),
+            inherit: row.get(2),
+            create_role: row.get(3),
+            create_db: row.get(4),
+            login: row.get(5),
+            replication: row.get(6),
+            bypass_rls: row.get(7),
+            connection_limit: row.get(8),
+            valid_until: row.get(9),
+        })
+        .collect())
+}
+
+/// Build the CREATE ROLE statement for a source role
+///
+/// SUPERUSER is never replicated. REPLICATION and BYPASSRLS are only included
+/// when `privileged` is set, since they usually need a superuser to grant.
+/// Source passwords cannot be read, so a role only gets `password` if given.
+pub fn build_create_role_sql(
+    role: &SourceRole,
+    password: Option<&str>,
+    privileged: bool,
+) -> String {
+    fn flag(enabled: bool, name: &str) -> String {
+        if enabled {
+            name.to_string()
+        } else {
+            format!("NO{}", name)
+        }
+    }
+
+    let mut sql = format!(
+        "CREATE ROLE {} WITH {} {} {} {}",
+        quote_ident(&role.name),
+        flag(role.login, "LOGIN"),
+        flag(role.inherit, "INHERIT"),
+        flag(role.create_role, "CREATEROLE"),
+        flag(role.create_db, "CREATEDB"),
+    );
+    if privileged {
+        sql.push_str(&format!(
+            " {} {}",
+            flag(role.replication, "REPLICATION"),
+            flag(role.bypass_rls, "BYPASSRLS")
+        ));
+    }
+    if role.connection_limit >= 0 {
+        sql.push_str(&format!(" CONNECTION LIMIT {}", role.connection_limit));
+    }
+    if let Some(password) = password {
+        sql.push_str(&format!(" PASSWORD {}", quote_literal(password)));
+    }
+    if let Some(valid_until) = &role.valid_until {
+        sql.push_str(&format!(" VALID UNTIL {}", quote_literal(valid_until)));
+    }
+    sql
+}
+
+/// Generate a random 24-character alphanumeric password
+pub fn generate_password() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(24)
+        .map(char::from)
+        .collect()
+}
+
+/// Kind of database object whose owner or privileges are replicated
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ObjectKind {
+    Schema,
+    Table,
+    View,
+    MaterializedView,
+    ForeignTable,
+    Sequence,
+    Function,
+    Procedure,
+}
+
+impl ObjectKind {
+    fn from_relkind(relkind: i8) -> Option<Self> {
+        match relkind as u8 {
+            b'r' | b'p' => Some(ObjectKind::Table),
+            b'v' => Some(ObjectKind::View),
+            b'm' => Some(ObjectKind::MaterializedView),
+            b'f' => Some(ObjectKind::ForeignTable),
+            b'S' => Some(ObjectKind::Sequence),
+            _ => None,
+        }
+    }
+
+    /// Keyword used by `ALTER <kind> ... OWNER TO`
+    fn alter_keyword(&self) -> &'static str {
+        match self {
+            ObjectKind::Schema => "SCHEMA",
+            ObjectKind::Table => "TABLE",
+            ObjectKind::View => "VIEW",
+            ObjectKind::MaterializedView => "MATERIALIZED VIEW",
+            ObjectKind::ForeignTable => "FOREIGN TABLE",
+            ObjectKind::Sequence => "SEQUENCE",
+            ObjectKind::Function => "FUNCTION",
+            ObjectKind::Procedure => "PROCEDURE",
+        }
+    }
+
+    /// Keyword used by `GRANT ... ON <kind>`
+    fn grant_keyword(&self) -> &'static str {
+        match self {
+            ObjectKind::Schema => "SCHEMA",
+            ObjectKind::Table
+            | ObjectKind::View
+            | ObjectKind::MaterializedView
+            | ObjectKind::ForeignTable => "TABLE",
+            ObjectKind::Sequence => "SEQUENCE",
+            ObjectKind::Function => "FUNCTION",
+            ObjectKind::Procedure => "PROCEDURE",
+        }
+    }
+}
+
+/// One entry of an object's ACL
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AclEntry {
+    /// None means PUBLIC
+    pub grantee: Option<String>,
+    pub privilege: String,
+    pub grantable: bool,
+}
+
+/// An object in one database with its owner and explicit ACL
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DatabaseObject {
+    pub kind: ObjectKind,
+    /// Quoted, schema-qualified name (with argument types for routines)
+    pub identity: String,
+    pub owner: String,
+    /// Whether the owner can be changed directly (false for sequences owned by a column)
+    pub reassignable: bool,
+    pub acl: Vec<AclEntry>,
+}
+
+/// Build `ALTER <kind> <object> OWNER TO <role>`
+pub fn build_owner_sql(object: &DatabaseObject) -> String {
+    format!(
+        "ALTER {} {} OWNER TO {}",
+        object.kind.alter_keyword(),
+        object.identity,
+        quote_ident(&object.owner)
+    )
+}
+
+/// Build `GRANT <privilege> ON <kind> <object> TO <grantee>`
+pub fn build_grant_sql(object: &DatabaseObject, entry: &AclEntry) -> String {
+    let grantee = entry
+        .grantee
+        .as_deref()
+        .map(quote_ident)
+        .unwrap_or_else(|| "PUBLIC".to_string());
+    format!(
+        "GRANT {} ON {} {} TO {}{}",
+        entry.privilege,
+        object.kind.grant_keyword(),
+        object.identity,
+        grantee,
+        if entry.grantable {
+            " WITH GRANT OPTION"
+        } else {
+            ""
+        }
+    )
+}
+
+/// List user objects in a database with owners and explicit ACLs
+///
+/// System schemas and objects that belong to an extension are skipped; the
+/// extension recreates those with its own owner and privileges.
+pub async fn list_database_objects(client: &Client) -> Result<Vec<DatabaseObject>> {
+    let mut objects = Vec::new();
+
+    let acl_of = |rows: &[tokio_postgres::Row], oid: u32| -> Vec<AclEntry> {
+        rows.iter()
+            .filter(|row| row.get::<_, u32>(0) == oid)
+            .map(|row| AclEntry {
+                grantee: row.get(1),
+                privilege: row.get(2),
+                grantable: row.get(3),
+            })
+            .collect()
+    };
+
+    let schemas = client
+        .query(
+            "SELECT n.oid, format('%I', n.nspname), pg_get_userbyid(n.nspowner)
+             FROM pg_namespace n
+             WHERE n.nspname !~ '^pg_' AND n.nspname <> 'information_schema'
+               AND NOT EXISTS (SELECT 1 FROM pg_depend d
+                               WHERE d.classid = 'pg_namespace'::regclass
+                                 AND d.objid = n.oid AND d.deptype = 'e')
+             ORDER BY n.nspname",
+            &[],
+        )
+        .await
+        .context("Failed to list schemas")?;
+    let schema_acls = client
+        .query(
+            "SELECT n.oid, CASE WHEN a.grantee = 0 THEN NULL ELSE pg_get_userbyid(a.grantee) END,
+                    a.privilege_type, a.is_grantable
+             FROM pg_namespace n, aclexplode(n.nspacl) a
+             WHERE a.grantee <> n.nspowner",
+            &[],
+        )
+        .await
+        .context("Failed to read schema privileges")?;
+    for row in &schemas {
+        let oid: u32 = row.get(0);
+        objects.push(DatabaseObject {
+            kind: ObjectKind::Schema,
+            identity: row.get(1),
+            owner: row.get(2),
+            reassignable: true,
+            acl: acl_of(&schema_acls, oid),
+        });
+    }
+
+    let relations = client
+        .query(
+            "SELECT c.oid, c.relkind, format('%I.%I', n.nspname, c.relname),
+                    pg_get_userbyid(c.relowner),
+                    NOT EXISTS (SELECT 1 FROM pg_depend d
+                                WHERE d.classid = 'pg_class'::regclass AND d.objid = c.oid
+                                  AND d.refclassid = 'pg_class'::regclass
+                                  AND d.deptype IN ('a', 'i'))
+             FROM pg_class c
+             JOIN pg_namespace n ON n.oid = c.relnamespace
+             WHERE c.relkind IN ('r', 'p', 'v', 'm', 'f', 'S')
+               AND n.nspname !~ '^pg_' AND n.nspname <> 'information_schema'
+               AND NOT EXISTS (SELECT 1 FROM pg_depend d
+                               WHERE d.classid = 'pg_class'::regclass
+                                 AND d.objid = c.oid AND d.deptype = 'e')
+             ORDER BY n.nspname, c.relname",
+            &[],
+        )
+        .await
+        .context("Failed to list relations")?;
+    let relation_acls = client
+        .query(
+            "SELECT c.oid, CASE WHEN a.grantee = 0 THEN NULL ELSE pg_get_userbyid(a.grantee) END,
+                    a.privilege_type, a.is_grantable
+             FROM pg_class c, aclexplode(c.relacl) a
+             WHERE a.grantee <> c.relowner",
+            &[],
+        )
+        .await
+        .context("Failed to read table privileges")?;
+    for row in &relations {
+        let oid: u32 = row.get(0);
+        let Some(kind) = ObjectKind::from_relkind(row.get(1)) else {
+            continue;
+        };
+        objects.push(DatabaseObject {
+            kind,
+            identity: row.get(2),
+            owner: row.get(3),
+            reassignable: row.get(4),
+            acl: acl_of(&relation_acls, oid),
+        });
+    }
+
+    let routines = client
+        .query(
+            "SELECT p.oid, p.prokind = 'p',
+                    format('%I.%I(%s)', n.nspname, p.proname,
+                           pg_get_function_identity_arguments(p.oid)),
+                    pg_get_userbyid(p.proowner)
+             FROM pg_proc p
+             JOIN pg_namespace n ON n.oid = p.pronamespace
+             WHERE p.prokind IN ('f', 'p', 'w')
+               AND n.nspname !~ '^pg_' AND n.nspname <> 'information_schema'
+               AND NOT EXISTS (SELECT 1 FROM pg_depend d
+                               WHERE d.classid = 'pg_proc'::regclass
+                                 AND d.objid = p.oid AND d.deptype = 'e')
+             ORDER BY 3",
+            &[],
+        )
+        .await
+        .context("Failed to list functions")?;
+    let routine_acls = client
+        .query(
+            "SELECT p.oid, CASE WHEN a.grantee = 0 THEN NULL ELSE pg_get_userbyid(a.grantee) END,
+                    a.privilege_type, a.is_grantable
+             FROM pg_proc p, aclexplode(p.proacl) a
+             WHERE a.grantee <> p.proowner",
+            &[],
+        )
+        .await
+        .context("Failed to read function privileges")?;
+    for row in &routines {
+        let oid: u32 = row.get(0);
+        let is_procedure: bool = row.get(1);
+        objects.push(DatabaseObject {
+            kind: if is_procedure {
+                ObjectKind::Procedure
+            } else {
+                ObjectKind::Function
+            },
+            identity: row.get(2),
+            owner: row.get(3),
+            reassignable: true,
+            acl: acl_of(&routine_acls, oid),
+        });
+    }
+
+    Ok(objects)
+}
+
+/// Server message for a failed statement, without the driver's "db error:" prefix
+fn error_reason(e: &tokio_postgres::Error) -> String {
+    e.as_db_error()
+        .map(|db| db.message().to_string())
+        .unwrap_or_else(|| e.to_string())
+}
+
+/// Run one statement on the target and record the outcome
+///
+/// Objects that do not exist on the target (excluded by filters) are ignored;
+/// any other failure is recorded as skipped rather than aborting init.
+async fn apply_statement(target: &Client, statement: String, report: &mut AccessReport) -> bool {
+    match target.batch_execute(&statement).await {
+        Ok(()) => {
+            report.applied += 1;
+            true
+        }
+        Err(e) => {
+            let missing_object = matches!(
+                e.code(),
+                Some(&SqlState::UNDEFINED_TABLE)
+                    | Some(&SqlState::UNDEFINED_FUNCTION)
+                    | Some(&SqlState::INVALID_SCHEMA_NAME)
+            );
+            if missing_object {
+                tracing::debug!("Skipping statement for object not on target: {}", statement);
+            } else {
+                report.skipped.push(SkippedStatement {
+                    statement,
+                    reason: error_reason(&e),
+                });
+            }
+            false
+        }
+    }
+}
+
+/// Create the source's roles and role memberships on the target
+pub async fn replicate_roles(
+    source: &Client,
+    target: &Client,
+    generate_passwords: bool,
+) -> Result<AccessReport> {
+    let mut report = AccessReport::default();
+
+    let existing: Vec<String> = target
+        .query("SELECT rolname FROM pg_roles", &[])
+        .await
+        .context("Failed to list roles on target")?
+        .iter()
+        .map(|row| row.get(0))
+        .collect();
+
+    for role in list_source_roles(source).await? {
+        if existing.contains(&role.name) {
+            report.roles_existing.push(role.name.clone());
+            continue;
+        }
+
+        let password = (generate_passwords && role.login).then(generate_password);
+        if role.superuser {
+            report.skipped.push(SkippedStatement {
+                statement: format!("ALTER ROLE {} SUPERUSER", quote_ident(&role.name)),
+                reason: "SUPERUSER is never replicated".to_string(),
+            });
+        }
+
+        // Reported statements never include the generated password
+        let created = match target
+            .batch_execute(&build_create_role_sql(&role, password.as_deref(), true))
+            .await
+        {
+            Ok(()) => {
+                report.applied += 1;
+                true
+            }
+            Err(e)
+                if (role.replication || role.bypass_rls)
+                    && e.code() == Some(&SqlState::INSUFFICIENT_PRIVILEGE) =>
+            {
+                // Managed services rarely let non-superusers grant these attributes
+                let created = apply_statement(
+                    target,
+                    build_create_role_sql(&role, password.as_deref(), false),
+                    &mut report,
+                )
+                .await;
+                if created {
+                    report.skipped.push(SkippedStatement {
+                        statement: build_create_role_sql(&role, None, true),
+                        reason: format!(
+                            "{} (role created without these attributes)",
+                            error_reason(&e)
+                        ),
+                    });
+                }
+                created
+            }
+            Err(e) => {
+                report.skipped.push(SkippedStatement {
+                    statement: build_create_role_sql(&role, None, true),
+                    reason: error_reason(&e),
+                });
+                false
+            }
+        };
+
+        if created {
+            report.roles_created.push(role.name.clone());
+            if let Some(password) = password {
+                report
+                    .generated_passwords
+                    .push((role.name.clone(), password));
+            }
+        }
+    }
+
+    let memberships = source
+        .query(
+            "SELECT r.rolname, m.rolname, am.admin_option
+             FROM pg_auth_members am
+             JOIN pg_roles r ON r.oid = am.roleid
+             JOIN pg_roles m ON m.oid = am.member
+             WHERE m.oid >= 16384 AND m.rolname !~ '^pg_'
+             ORDER BY 1, 2",
+            &[],
+        )
+        .await
+        .context("Failed to list role memberships on source")?;
+    for row in &memberships {
+        let role: String = row.get(0);
+        let member: String = row.get(1);
+        let admin: bool = row.get(2);
+        let statement = format!(
+            "GRANT {} TO {}{}",
+            quote_ident(&role),
+            quote_ident(&member),
+            if admin { " WITH ADMIN OPTION" } else { "" }
+        );
+        apply_statement(target, statement, &mut report).await;
+    }
+
+    Ok(report)
+}
+
+/// Reassign ownership and/or apply GRANTs in one database
+///
+/// `source` and `target` must be connected to the same database on each side.
+pub async fn replicate_database_access(
+    source: &Client,
+    target: &Client,
+    options: &AccessOptions,
+) -> Result<AccessReport> {
+    let mut report = AccessReport::default();
+
+    for object in list_database_objects(source).await? {
+        if options.include_roles && object.reassignable {
+            apply_statement(target, build_owner_sql(&object), &mut report).await;
+        }
+        if options.include_grants {
+            for entry in &object.acl {
+                apply_statement(target, build_grant_sql(&object, entry), &mut report).await;
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+/// Where generated role passwords are written
+pub fn generated_passwords_path() -> PathBuf {
+    PathBuf::from(".seren-replicator").join("generated-role-passwords.txt")
+}
+
+/// Write generated passwords as `role:password` lines, readable only by the owner
+pub fn write_generated_passwords(path: &Path, passwords: &[(String, String)]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    let content: String = passwords
+        .iter()
+        .map(|(role, password)| format!("{}:{}\n", role, password))
+        .collect();
+    std::fs::write(path, content)
+        .with_context(|| format!("Failed to write generated passwords to {}", path.display()))?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))
+            .with_context(|| format!("Failed to restrict permissions on {}", path.display()))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn role(name: &str) -> SourceRole {
+        SourceRole {
+            name: name.to_string(),
+            superuser: false,
+            inherit: true,
+            create_role: false,
+            create_db: true,
+            login: true,
+            replication: true,
+            bypass_rls: false,
+            connection_limit: -1,
+            valid_until: None,
+        }
+    }
+
+    #[test]
+    fn test_build_create_role_sql() {
+        let app = role("app_user");
+        assert_eq!(
+            build_create_role_sql(&app, None, true),
+            "CREATE ROLE \"app_user\" WITH LOGIN INHERIT NOCREATEROLE CREATEDB REPLICATION NOBYPASSRLS"
+        );
+
+        let mut limited = role("it's");
+        limited.connection_limit = 5;
+        limited.valid_until = Some("2030-01-01 00:00:00+00".to_string());
+        assert_eq!(
+            build_create_role_sql(&limited, Some("s3cr'et"), false),
+            "CREATE ROLE \"it's\" WITH LOGIN INHERIT NOCREATEROLE CREATEDB \
+             CONNECTION LIMIT 5 PASSWORD 's3cr''et' VALID UNTIL '2030-01-01 00:00:00+00'"
+        );
+    }
+
+    #[test]
+    fn test_generate_password() {
+        let password = generate_password();
+        assert_eq!(password.len(), 24);
+        assert!(password.chars().all(|c| c.is_ascii_alphanumeric()));
+        assert_ne!(password, generate_password());
+    }
+
+    #[test]
+    fn test_build_owner_and_grant_sql() {
+        let view = DatabaseObject {
+            kind: ObjectKind::MaterializedView,
+            identity: "public.daily_totals".to_string(),
+            owner: "reporting".to_string(),
+            reassignable: true,
+            acl: vec![],
+        };
+        assert_eq!(
+            build_owner_sql(&view),
+            "ALTER MATERIALIZED VIEW public.daily_totals OWNER TO \"reporting\""
+        );
+
+        let select = AclEntry {
+            grantee: Some("analyst".to_string()),
+            privilege: "SELECT".to_string(),
+            grantable: true,
+        };
+        assert_eq!(
+            build_grant_sql(&view, &select),
+            "GRANT SELECT ON TABLE public.daily_totals TO \"analyst\" WITH GRANT OPTION"
+        );
+
+        let function = DatabaseObject {
+            kind: ObjectKind::Function,
+            identity: "app.total(integer)".to_string(),
+            owner: "app".to_string(),
+            reassignable: true,
+            acl: vec![],
+        };
+        let execute = AclEntry {
+            grantee: None,
+            privilege: "EXECUTE".to_string(),
+            grantable: false,
+        };
+        assert_eq!(
+            build_grant_sql(&function, &execute),
+            "GRANT EXECUTE ON FUNCTION app.total(integer) TO PUBLIC"
+        );
+    }
+}
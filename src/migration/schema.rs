@@ -15,6 +15,10 @@ pub struct TableInfo {
     pub schema: String,
     pub name: String,
     pub row_count_estimate: i64,
+    /// Total on-disk size including indexes and TOAST
+    pub size_bytes: i64,
+    /// `UNLOGGED`: not written to the WAL, so logical replication cannot publish it
+    pub unlogged: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -55,19 +59,39 @@ pub async fn list_databases(client: &Client) -> Result<Vec<DatabaseInfo>> {
 }
 
 /// List all tables in the current database
+///
+/// Other sessions' temporary tables are left out; they cannot be read from here.
+/// So are this tool's own metadata tables, which a replica used as a source
+/// keeps to itself.
 pub async fn list_tables(client: &Client) -> Result<Vec<TableInfo>> {
     let rows = client
         .query(
-            "SELECT
+            &format!(
+                "SELECT
                 pg_tables.schemaname,
                 pg_tables.tablename,
-                COALESCE(n_live_tup, 0) as row_count
+                COALESCE(n_live_tup, 0) as row_count,
+                COALESCE(pg_total_relation_size(pg_stat_user_tables.relid), 0) as size_bytes,
+                EXISTS (
+                    SELECT 1 FROM pg_catalog.pg_class c
+                    JOIN pg_catalog.pg_namespace n ON n.oid = c.relnamespace
+                    WHERE n.nspname = pg_tables.schemaname
+                      AND c.relname = pg_tables.tablename
+                      AND c.relpersistence = 'u'
+                ) as unlogged
              FROM pg_catalog.pg_tables
              LEFT JOIN pg_catalog.pg_stat_user_tables
                 ON pg_tables.schemaname = pg_stat_user_tables.schemaname
                 AND pg_tables.tablename = pg_stat_user_tables.relname
              WHERE pg_tables.schemaname NOT IN ('pg_catalog', 'information_schema')
+               AND pg_tables.schemaname NOT LIKE 'pg\\_temp\\_%'
+               AND NOT {}
              ORDER BY pg_tables.schemaname, pg_tables.tablename",
+                crate::topology::metadata_table_condition(
+                    "pg_tables.schemaname",
+                    "pg_tables.tablename"
+                )
+            ),
             &[],
         )
         .await
@@ -79,12 +103,52 @@ pub async fn list_tables(client: &Client) -> Result<Vec<TableInfo>> {
             schema: row.get(0),
             name: row.get(1),
             row_count_estimate: row.get(2),
+            size_bytes: row.get(3),
+            unlogged: row.get(4),
         })
         .collect();
 
     Ok(tables)
 }
 
+/// `UNLOGGED` tables in the current database that `filter` replicates with
+/// data, as (schema, table)
+///
+/// Logical replication cannot publish them, so init only copies them and sync
+/// only polls them by xmin with `--include-unlogged`.
+pub async fn selected_unlogged_tables(
+    client: &Client,
+    db_name: &str,
+    filter: &crate::filters::ReplicationFilter,
+) -> Result<Vec<(String, String)>> {
+    use crate::table_rules::TableRuleKind;
+
+    let mut selected = Vec::new();
+    for table in list_tables(client).await? {
+        if !table.unlogged {
+            continue;
+        }
+        let identifier = if table.schema == "public" {
+            table.name.clone()
+        } else {
+            format!("{}.{}", table.schema, table.name)
+        };
+        if !filter.should_replicate_table(db_name, &identifier) {
+            continue;
+        }
+        if matches!(
+            filter
+                .table_rules()
+                .rule_for_table(db_name, &table.schema, &table.name),
+            Some(TableRuleKind::SchemaOnly) | Some(TableRuleKind::Sample(_))
+        ) {
+            continue;
+        }
+        selected.push((table.schema, table.name));
+    }
+    Ok(selected)
+}
+
 /// Get columns for a specific table with their types
 ///
 /// Returns all columns with a flag indicating if they are timestamp-like types.
@@ -167,4 +231,32 @@ mod tests {
             );
         }
     }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_selected_unlogged_tables() {
+        let url = std::env::var("TEST_SOURCE_URL").unwrap();
+        let client = connect(&url).await.unwrap();
+        let db_name = crate::utils::parse_postgres_url(&url).unwrap().database;
+        client
+            .batch_execute(
+                "DROP TABLE IF EXISTS unlogged_cache, logged_orders;
+                 CREATE UNLOGGED TABLE unlogged_cache (id int PRIMARY KEY);
+                 CREATE TABLE logged_orders (id int PRIMARY KEY);",
+            )
+            .await
+            .unwrap();
+
+        let filter = crate::filters::ReplicationFilter::empty();
+        let unlogged = selected_unlogged_tables(&client, &db_name, &filter)
+            .await
+            .unwrap();
+        client
+            .batch_execute("DROP TABLE unlogged_cache, logged_orders")
+            .await
+            .unwrap();
+
+        assert!(unlogged.contains(&("public".to_string(), "unlogged_cache".to_string())));
+        assert!(!unlogged.iter().any(|(_, table)| table == "logged_orders"));
+    }
 }
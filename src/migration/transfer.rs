@@ -0,0 +1,365 @@
+// ABOUTME: Compression and byte accounting for data moved from source to target
+// ABOUTME: Pipes COPY streams through compressed frames and totals bytes per replication phase
+
+use anyhow::{Context, Result};
+use bytes::Bytes;
+use clap::ValueEnum;
+use futures::{pin_mut, SinkExt, StreamExt};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// Bytes of `COPY` data compressed together while streaming a table
+const FRAME_BYTES: usize = 1024 * 1024;
+
+/// Compressed frames the source can read ahead of the target
+const READ_AHEAD_FRAMES: usize = 16;
+
+/// zstd level for frames in flight; the fastest, since they live for milliseconds
+const ZSTD_LEVEL: i32 = 1;
+
+/// How streamed rows are compressed while they wait for the target
+///
+/// Frames are compressed in this process, between the source's `COPY` and the
+/// target's, so compression shrinks the rows buffered in memory but not the
+/// bytes sent over either connection.
+#[derive(Debug, Clone, Copy, Default, ValueEnum, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Compression {
+    /// zstd at its fastest level
+    Zstd,
+    /// lz4: less CPU than zstd, larger frames
+    Lz4,
+    /// Rows are passed on as they arrive
+    #[default]
+    None,
+}
+
+static COMPRESSION: OnceLock<Compression> = OnceLock::new();
+
+/// Set the compression for streamed data (call once at startup)
+pub fn init_compression(compression: Compression) {
+    let _ = COMPRESSION.set(compression);
+}
+
+/// Compression for streamed data, none unless set at startup
+pub fn compression() -> Compression {
+    COMPRESSION.get().copied().unwrap_or_default()
+}
+
+/// Step of a replication run that bytes are counted against
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Phase {
+    /// Roles and tablespaces
+    Globals,
+    /// Table, type, and function definitions
+    Schema,
+    /// Table rows
+    Data,
+}
+
+impl Phase {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Phase::Globals => "globals",
+            Phase::Schema => "schema",
+            Phase::Data => "data",
+        }
+    }
+}
+
+/// Bytes moved from source to target
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct TransferBytes {
+    /// Bytes read from the source
+    pub read_bytes: u64,
+    /// Bytes written to the target
+    pub written_bytes: u64,
+    /// Size of the bytes read once compressed; equal to `read_bytes` when
+    /// nothing was compressed
+    pub compressed_bytes: u64,
+}
+
+impl TransferBytes {
+    /// Bytes of a file a client tool wrote from the source and another
+    /// applied to the target
+    ///
+    /// Counts every byte once, at its size on disk, so dumps that the tool
+    /// compressed are counted compressed. Missing files count as empty.
+    pub fn of_path(path: &Path) -> Self {
+        let bytes = disk_usage(path);
+        Self {
+            read_bytes: bytes,
+            written_bytes: bytes,
+            compressed_bytes: bytes,
+        }
+    }
+}
+
+impl std::ops::AddAssign for TransferBytes {
+    fn add_assign(&mut self, other: Self) {
+        self.read_bytes += other.read_bytes;
+        self.written_bytes += other.written_bytes;
+        self.compressed_bytes += other.compressed_bytes;
+    }
+}
+
+/// Bytes moved per phase over a whole run
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TransferReport {
+    phases: BTreeMap<Phase, TransferBytes>,
+}
+
+/// The report as printed by `init --json`
+#[derive(Serialize)]
+struct TransferJson<'a> {
+    compression: Compression,
+    phases: &'a BTreeMap<Phase, TransferBytes>,
+    total: TransferBytes,
+}
+
+impl TransferReport {
+    pub fn add(&mut self, phase: Phase, bytes: TransferBytes) {
+        *self.phases.entry(phase).or_default() += bytes;
+    }
+
+    pub fn phase(&self, phase: Phase) -> TransferBytes {
+        self.phases.get(&phase).copied().unwrap_or_default()
+    }
+
+    pub fn total(&self) -> TransferBytes {
+        let mut total = TransferBytes::default();
+        for bytes in self.phases.values() {
+            total += *bytes;
+        }
+        total
+    }
+
+    /// Log the bytes of each phase and the total
+    pub fn log_summary(&self) {
+        tracing::info!("Data transferred:");
+        for (phase, bytes) in &self.phases {
+            tracing::info!("  {:<8} {}", phase.as_str(), describe(bytes));
+        }
+        tracing::info!("  {:<8} {}", "total", describe(&self.total()));
+    }
+
+    /// One line of JSON with the bytes of each phase and the total
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string(&TransferJson {
+            compression: compression(),
+            phases: &self.phases,
+            total: self.total(),
+        })
+        .context("Failed to serialize transfer report")
+    }
+}
+
+fn describe(bytes: &TransferBytes) -> String {
+    let mut line = format!(
+        "{} read from source, {} written to target",
+        super::format_bytes(bytes.read_bytes as i64),
+        super::format_bytes(bytes.written_bytes as i64)
+    );
+    if bytes.compressed_bytes < bytes.read_bytes {
+        line.push_str(&format!(
+            " ({} compressed)",
+            super::format_bytes(bytes.compressed_bytes as i64)
+        ));
+    }
+    line
+}
+
+/// Size of a file, or of every file under a directory
+fn disk_usage(path: &Path) -> u64 {
+    let Ok(metadata) = std::fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if !metadata.is_dir() {
+        return metadata.len();
+    }
+    std::fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| disk_usage(&entry.path()))
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+/// Move `COPY` data from a source stream to a target sink
+///
+/// The source reads ahead of the target by up to [`READ_AHEAD_FRAMES`]
+/// frames, compressed with [`compression`], so neither side waits on the
+/// other's round trips while the buffered rows stay small. The target's copy
+/// is only finished once the source has sent its last row; if the source
+/// fails, the sink is dropped, which aborts the copy. Returns the bytes moved
+/// and the rows the target wrote.
+pub async fn pipe_copy(
+    reader: tokio_postgres::CopyOutStream,
+    writer: tokio_postgres::CopyInSink<Bytes>,
+) -> Result<(TransferBytes, u64)> {
+    let compression = compression();
+    let (sender, mut receiver) = tokio::sync::mpsc::channel::<Option<Frame>>(READ_AHEAD_FRAMES);
+    let read = async move {
+        pin_mut!(reader);
+        let mut buffer = Vec::with_capacity(FRAME_BYTES);
+        let (mut read_bytes, mut compressed_bytes) = (0, 0);
+        loop {
+            let chunk = reader.next().await.transpose()?;
+            let done = chunk.is_none();
+            if let Some(chunk) = chunk {
+                buffer.extend_from_slice(&chunk);
+            }
+            if buffer.len() >= FRAME_BYTES || (done && !buffer.is_empty()) {
+                let frame = Frame::compress(compression, &buffer)?;
+                read_bytes += buffer.len() as u64;
+                compressed_bytes += frame.data.len() as u64;
+                buffer.clear();
+                sender
+                    .send(Some(frame))
+                    .await
+                    .map_err(|_| anyhow::anyhow!("Target stopped accepting rows"))?;
+            }
+            if done {
+                break;
+            }
+        }
+        sender
+            .send(None)
+            .await
+            .map_err(|_| anyhow::anyhow!("Target stopped accepting rows"))?;
+        Ok::<_, anyhow::Error>((read_bytes, compressed_bytes))
+    };
+    let write = async move {
+        pin_mut!(writer);
+        let mut written_bytes = 0;
+        loop {
+            match receiver.recv().await {
+                Some(Some(frame)) => {
+                    let data = frame.decompress()?;
+                    written_bytes += data.len() as u64;
+                    writer.as_mut().send(data).await?;
+                }
+                Some(None) => break,
+                None => anyhow::bail!("Source stopped sending rows"),
+            }
+        }
+        Ok((writer.finish().await?, written_bytes))
+    };
+    let ((read_bytes, compressed_bytes), (rows, written_bytes)) =
+        futures::future::try_join(read, write).await?;
+    let bytes = TransferBytes {
+        read_bytes,
+        written_bytes,
+        compressed_bytes,
+    };
+    Ok((bytes, rows))
+}
+
+/// A block of `COPY` data held, maybe compressed, while it waits for the target
+struct Frame {
+    compression: Compression,
+    len: usize,
+    data: Vec<u8>,
+}
+
+impl Frame {
+    fn compress(compression: Compression, data: &[u8]) -> Result<Self> {
+        let compressed = match compression {
+            Compression::Zstd => {
+                zstd::bulk::compress(data, ZSTD_LEVEL).context("Failed to compress rows")?
+            }
+            Compression::Lz4 => lz4_flex::block::compress(data),
+            Compression::None => data.to_vec(),
+        };
+        Ok(Self {
+            compression,
+            len: data.len(),
+            data: compressed,
+        })
+    }
+
+    fn decompress(self) -> Result<Bytes> {
+        let data = match self.compression {
+            Compression::Zstd => {
+                zstd::bulk::decompress(&self.data, self.len).context("Failed to decompress rows")?
+            }
+            Compression::Lz4 => lz4_flex::block::decompress(&self.data, self.len)
+                .context("Failed to decompress rows")?,
+            Compression::None => self.data,
+        };
+        Ok(Bytes::from(data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_round_trip() {
+        let rows = "1\tcustomer\t{a,b}\n".repeat(10_000);
+        for compression in [Compression::Zstd, Compression::Lz4, Compression::None] {
+            let frame = Frame::compress(compression, rows.as_bytes()).unwrap();
+            if compression == Compression::None {
+                assert_eq!(frame.data.len(), rows.len());
+            } else {
+                assert!(frame.data.len() < rows.len());
+            }
+            assert_eq!(frame.decompress().unwrap(), rows.as_bytes());
+        }
+    }
+
+    #[test]
+    fn test_report_totals_and_json() {
+        let mut report = TransferReport::default();
+        let streamed = TransferBytes {
+            read_bytes: 1000,
+            written_bytes: 1000,
+            compressed_bytes: 100,
+        };
+        report.add(Phase::Data, streamed);
+        report.add(Phase::Data, streamed);
+        report.add(
+            Phase::Schema,
+            TransferBytes {
+                read_bytes: 50,
+                written_bytes: 50,
+                compressed_bytes: 50,
+            },
+        );
+
+        assert_eq!(report.phase(Phase::Data).read_bytes, 2000);
+        assert_eq!(report.phase(Phase::Globals), TransferBytes::default());
+        assert_eq!(report.total().compressed_bytes, 250);
+
+        let json: serde_json::Value = serde_json::from_str(&report.to_json().unwrap()).unwrap();
+        assert_eq!(json["compression"], "none");
+        assert_eq!(json["phases"]["data"]["written_bytes"], 2000);
+        assert_eq!(json["phases"]["schema"]["read_bytes"], 50);
+        assert_eq!(json["total"]["read_bytes"], 2050);
+        assert!(json["phases"].get("globals").is_none());
+    }
+
+    #[test]
+    fn test_of_path_sums_directories() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a"), vec![0u8; 10]).unwrap();
+        std::fs::create_dir(dir.path().join("sub")).unwrap();
+        std::fs::write(dir.path().join("sub").join("b"), vec![0u8; 5]).unwrap();
+
+        assert_eq!(TransferBytes::of_path(dir.path()).read_bytes, 15);
+        assert_eq!(
+            TransferBytes::of_path(&dir.path().join("a")).written_bytes,
+            10
+        );
+        assert_eq!(
+            TransferBytes::of_path(&dir.path().join("missing")),
+            TransferBytes::default()
+        );
+    }
+}
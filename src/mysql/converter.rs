@@ -146,6 +146,36 @@ pub fn mysql_row_to_json(row: &Row, column_names: &[String]) -> Result<JsonValue
     Ok(JsonValue::Object(obj))
 }
 
+/// Decode JSON documents stored as text into structured JSON values
+///
+/// MariaDB returns `JSON` columns as LONGTEXT strings. For each listed column,
+/// string values that parse as JSON are replaced with the parsed document so
+/// the JSONB output matches what a MySQL source would produce. Values that
+/// fail to parse are left as strings.
+///
+/// # Examples
+///
+/// ```
+/// # use database_replicator::mysql::converter::decode_json_text_columns;
+/// let mut row = serde_json::json!({"id": 1, "payload": "{\"a\": 1}"});
+/// decode_json_text_columns(&mut row, &["payload".to_string()]);
+/// assert_eq!(row["payload"]["a"], 1);
+/// ```
+pub fn decode_json_text_columns(row: &mut JsonValue, columns: &[String]) {
+    let Some(obj) = row.as_object_mut() else {
+        return;
+    };
+    for column in columns {
+        if let Some(value) = obj.get_mut(column) {
+            if let JsonValue::String(text) = value {
+                if let Ok(parsed) = serde_json::from_str::<JsonValue>(text) {
+                    *value = parsed;
+                }
+            }
+        }
+    }
+}
+
 /// Get column names for a MySQL table
 ///
 /// Queries INFORMATION_SCHEMA to get all column names for a table.
@@ -199,6 +229,40 @@ pub async fn get_column_names(
     Ok(columns)
 }
 
+/// Column names used as the JSONB document ID, in order of preference
+pub const ID_COLUMNS: [&str; 3] = ["id", "Id", "ID"];
+
+/// Find the column whose value becomes the JSONB document ID
+///
+/// Tables without one get generated IDs, which later changes cannot address.
+pub fn id_column(column_names: &[String]) -> Option<&str> {
+    ID_COLUMNS
+        .iter()
+        .find(|candidate| column_names.iter().any(|name| name == *candidate))
+        .copied()
+}
+
+/// Extract the JSONB document ID from a converted row
+///
+/// # Examples
+///
+/// ```
+/// # use database_replicator::mysql::converter::document_id;
+/// assert_eq!(document_id(&serde_json::json!({"id": "42"})), Some("42".to_string()));
+/// assert_eq!(document_id(&serde_json::json!({"name": "x"})), None);
+/// ```
+pub fn document_id(data: &JsonValue) -> Option<String> {
+    ID_COLUMNS
+        .iter()
+        .find_map(|column| data.get(column))
+        .map(id_text)
+}
+
+/// Render an ID column value as a JSONB document ID
+pub fn id_text(value: &JsonValue) -> String {
+    value.to_string().trim_matches('"').to_string()
+}
+
 /// Convert an entire MySQL table to JSONB format
 ///
 /// Reads all rows from the table and converts them to (id, jsonb_data) tuples.
@@ -260,19 +324,14 @@ pub async fn convert_table_to_jsonb(
             .with_context(|| format!("Failed to convert row in table '{}'", table_name))?;
 
         // Try to extract ID from common ID column names
-        let id = if let Some(id_val) = json_data.get("id") {
-            // Use 'id' column if exists
-            id_val.to_string().trim_matches('"').to_string()
-        } else if let Some(id_val) = json_data.get("Id") {
-            // Case insensitive check
-            id_val.to_string().trim_matches('"').to_string()
-        } else if let Some(id_val) = json_data.get("ID") {
-            id_val.to_string().trim_matches('"').to_string()
-        } else {
-            // Generate sequential ID
-            let generated_id = format!("generated_{}", id_counter);
-            id_counter += 1;
-            generated_id
+        let id = match document_id(&json_data) {
+            Some(id) => id,
+            None => {
+                // Generate sequential ID
+                let generated_id = format!("generated_{}", id_counter);
+                id_counter += 1;
+                generated_id
+            }
         };
 
         result.push((id, json_data));
@@ -353,6 +412,21 @@ mod tests {
         assert!(json["value"].as_str().unwrap().contains("1d 10:30:45"));
     }
 
+    #[test]
+    fn test_decode_json_text_columns() {
+        let mut row = serde_json::json!({
+            "id": 1,
+            "doc": "{\"tags\": [\"a\", \"b\"]}",
+            "broken": "{not json",
+            "name": "[1,2]"
+        });
+        decode_json_text_columns(&mut row, &["doc".to_string(), "broken".to_string()]);
+        assert_eq!(row["doc"]["tags"][1], "b");
+        assert_eq!(row["broken"], "{not json");
+        // Columns not listed are left untouched
+        assert_eq!(row["name"], "[1,2]");
+    }
+
     #[test]
     fn test_convert_non_finite_double() {
         let value = Value::Double(f64::NAN);
@@ -0,0 +1,827 @@
+// ABOUTME: MySQL vs MariaDB server detection and flavor-specific behavior
+// ABOUTME: Parses version strings, GTID positions, binlog settings, and Galera status
+
+use anyhow::{bail, Context, Result};
+use mysql_async::{prelude::*, Conn};
+
+/// Minimum MariaDB release that has been validated with the MySQL source path
+pub const MIN_MARIADB_VERSION: (u32, u32) = (10, 6);
+
+/// Server implementation behind a `mysql://` connection string
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServerFlavor {
+    /// Oracle MySQL (and compatible forks such as Percona Server)
+    MySql,
+    /// MariaDB Server (including MariaDB Galera Cluster)
+    MariaDb,
+}
+
+impl std::fmt::Display for ServerFlavor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ServerFlavor::MySql => write!(f, "MySQL"),
+            ServerFlavor::MariaDb => write!(f, "MariaDB"),
+        }
+    }
+}
+
+/// Detected server details used to pick divergent code paths
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerInfo {
+    pub flavor: ServerFlavor,
+    /// Parsed (major, minor, patch) version
+    pub version: (u32, u32, u32),
+    /// Raw `SELECT VERSION()` output
+    pub version_string: String,
+    /// True when the node is a member of a Galera cluster (wsrep_on=ON)
+    pub galera: bool,
+}
+
+impl ServerInfo {
+    /// Build server info from a `SELECT VERSION()` string and Galera status
+    pub fn from_version_string(version_string: &str, galera: bool) -> Result<Self> {
+        let (flavor, version) = parse_version_string(version_string)?;
+        Ok(Self {
+            flavor,
+            version,
+            version_string: version_string.to_string(),
+            galera,
+        })
+    }
+
+    pub fn is_mariadb(&self) -> bool {
+        self.flavor == ServerFlavor::MariaDb
+    }
+
+    /// Check that the server is a release we support
+    ///
+    /// MariaDB releases older than 10.6 are rejected because their GTID and
+    /// JSON handling differs from what the replication path expects.
+    pub fn ensure_supported(&self) -> Result<()> {
+        if self.is_mariadb() {
+            let (major, minor, _) = self.version;
+            if (major, minor) < MIN_MARIADB_VERSION {
+                bail!(
+                    "MariaDB {}.{} is not supported. Upgrade to MariaDB {}.{} or newer.",
+                    major,
+                    minor,
+                    MIN_MARIADB_VERSION.0,
+                    MIN_MARIADB_VERSION.1
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Human-readable description for log output
+    pub fn describe(&self) -> String {
+        let (major, minor, patch) = self.version;
+        let mut desc = format!("{} {}.{}.{}", self.flavor, major, minor, patch);
+        if self.galera {
+            desc.push_str(" (Galera cluster node)");
+        }
+        desc
+    }
+
+    /// Apply session settings required for consistent reads on this flavor
+    ///
+    /// Galera nodes apply writesets asynchronously, so a read on one node may not
+    /// see commits acknowledged by another. `wsrep_sync_wait = 1` makes each
+    /// SELECT wait until the node has caught up with the cluster.
+    pub async fn prepare_session(&self, conn: &mut Conn) -> Result<()> {
+        if self.galera {
+            conn.query_drop("SET SESSION wsrep_sync_wait = 1")
+                .await
+                .context("Failed to enable wsrep_sync_wait for Galera causal reads")?;
+            tracing::debug!("Enabled wsrep_sync_wait=1 for Galera session");
+        }
+        Ok(())
+    }
+
+    /// Query that returns the server's current executed GTID position
+    ///
+    /// MariaDB's `gtid_binlog_pos` is used rather than `gtid_current_pos`
+    /// because binlog sync reads this server's binary log, which only holds
+    /// transactions the server has logged itself.
+    pub fn gtid_position_query(&self) -> &'static str {
+        match self.flavor {
+            ServerFlavor::MySql => "SELECT @@GLOBAL.gtid_executed",
+            ServerFlavor::MariaDb => "SELECT @@GLOBAL.gtid_binlog_pos",
+        }
+    }
+
+    /// Read the server settings that binlog sync depends on
+    pub async fn binlog_settings(&self, conn: &mut Conn) -> Result<BinlogSettings> {
+        let row: Option<(String, String, String, String)> = conn
+            .query_first(
+                "SELECT @@GLOBAL.log_bin, @@GLOBAL.binlog_format, \
+                 @@GLOBAL.binlog_row_image, @@GLOBAL.log_slave_updates",
+            )
+            .await
+            .context("Failed to read binary log settings")?;
+        let (log_bin, binlog_format, binlog_row_image, log_slave_updates) =
+            row.context("Server returned no binary log settings")?;
+
+        // MariaDB always assigns GTIDs; MySQL only does with gtid_mode=ON
+        let gtid_mode = match self.flavor {
+            ServerFlavor::MySql => conn
+                .query_first("SELECT @@GLOBAL.gtid_mode")
+                .await
+                .context("Failed to read gtid_mode")?,
+            ServerFlavor::MariaDb => None,
+        };
+
+        Ok(BinlogSettings {
+            log_bin: is_enabled(&log_bin),
+            binlog_format,
+            binlog_row_image,
+            log_slave_updates: is_enabled(&log_slave_updates),
+            gtid_mode,
+        })
+    }
+
+    /// Check that the server's binary log can drive binlog sync
+    pub fn check_binlog_settings(&self, settings: &BinlogSettings) -> Result<()> {
+        let mut problems = Vec::new();
+        if !settings.log_bin {
+            problems.push("binary logging is disabled (enable log_bin)".to_string());
+        }
+        if !settings.binlog_format.eq_ignore_ascii_case("ROW") {
+            problems.push(format!(
+                "binlog_format is {} (set binlog_format=ROW)",
+                settings.binlog_format
+            ));
+        }
+        // Row images must carry every column so each change can be matched by its ID
+        if !settings.binlog_row_image.eq_ignore_ascii_case("FULL") {
+            problems.push(format!(
+                "binlog_row_image is {} (set binlog_row_image=FULL)",
+                settings.binlog_row_image
+            ));
+        }
+        if let Some(gtid_mode) = &settings.gtid_mode {
+            if !gtid_mode.eq_ignore_ascii_case("ON") {
+                problems.push(format!(
+                    "gtid_mode is {} (set gtid_mode=ON and enforce_gtid_consistency=ON)",
+                    gtid_mode
+                ));
+            }
+        }
+        // Writes made on other Galera nodes only reach this node's binlog as replicated events
+        if self.galera && !settings.log_slave_updates {
+            problems.push(
+                "log_slave_updates is OFF, so writes from other Galera nodes are missing \
+                 from this node's binlog (set log_slave_updates=ON)"
+                    .to_string(),
+            );
+        }
+
+        if !problems.is_empty() {
+            bail!(
+                "{} is not configured for binlog sync:\n  - {}",
+                self.flavor,
+                problems.join("\n  - ")
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Server variables that binlog sync depends on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BinlogSettings {
+    pub log_bin: bool,
+    pub binlog_format: String,
+    pub binlog_row_image: String,
+    pub log_slave_updates: bool,
+    /// `gtid_mode` on MySQL; MariaDB has no such switch
+    pub gtid_mode: Option<String>,
+}
+
+fn is_enabled(value: &str) -> bool {
+    value == "1" || value.eq_ignore_ascii_case("ON")
+}
+
+/// Parse the output of `SELECT VERSION()` into a flavor and numeric version
+///
+/// # Examples
+///
+/// ```
+/// # use database_replicator::mysql::flavor::{parse_version_string, ServerFlavor};
+/// let (flavor, version) = parse_version_string("10.6.12-MariaDB-1:10.6.12+maria~ubu2004-log").unwrap();
+/// assert_eq!(flavor, ServerFlavor::MariaDb);
+/// assert_eq!(version, (10, 6, 12));
+///
+/// let (flavor, version) = parse_version_string("8.0.35").unwrap();
+/// assert_eq!(flavor, ServerFlavor::MySql);
+/// assert_eq!(version, (8, 0, 35));
+/// ```
+pub fn parse_version_string(version_string: &str) -> Result<(ServerFlavor, (u32, u32, u32))> {
+    let trimmed = version_string.trim();
+    let flavor = if trimmed.to_ascii_lowercase().contains("mariadb") {
+        ServerFlavor::MariaDb
+    } else {
+        ServerFlavor::MySql
+    };
+
+    // MariaDB 10.x behind MySQL-replication-compatible builds report "5.5.5-10.6.12-MariaDB"
+    let numeric = trimmed
+        .strip_prefix("5.5.5-")
+        .filter(|_| flavor == ServerFlavor::MariaDb)
+        .unwrap_or(trimmed);
+
+    let version_part: String = numeric
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || *c == '.')
+        .collect();
+    let mut parts = version_part.split('.').filter(|p| !p.is_empty());
+
+    let major = parts
+        .next()
+        .and_then(|p| p.parse::<u32>().ok())
+        .with_context(|| format!("Could not parse server version from '{}'", version_string))?;
+    let minor = parts.next().and_then(|p| p.parse().ok()).unwrap_or(0);
+    let patch = parts.next().and_then(|p| p.parse().ok()).unwrap_or(0);
+
+    Ok((flavor, (major, minor, patch)))
+}
+
+/// The GTID of a single transaction, as announced at its start in the binlog
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Gtid {
+    MySql {
+        source_uuid: String,
+        transaction_id: u64,
+    },
+    MariaDb(MariaDbGtid),
+}
+
+/// A GTID position in either MySQL or MariaDB format
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GtidPosition {
+    /// MySQL GTID set: `source_uuid:1-100[:105-110],...`
+    MySql(Vec<MySqlGtidSet>),
+    /// MariaDB GTID list: `domain-server-sequence[,...]` (one entry per domain)
+    MariaDb(Vec<MariaDbGtid>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MySqlGtidSet {
+    pub source_uuid: String,
+    /// Inclusive transaction intervals
+    pub intervals: Vec<(u64, u64)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MariaDbGtid {
+    pub domain_id: u32,
+    pub server_id: u32,
+    pub sequence: u64,
+}
+
+impl GtidPosition {
+    /// Parse a GTID position string using the format of the given flavor
+    pub fn parse(flavor: ServerFlavor, value: &str) -> Result<Self> {
+        let entries = value
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty());
+        match flavor {
+            ServerFlavor::MySql => entries
+                .map(parse_mysql_gtid_set)
+                .collect::<Result<Vec<_>>>()
+                .map(GtidPosition::MySql),
+            ServerFlavor::MariaDb => entries
+                .map(parse_mariadb_gtid)
+                .collect::<Result<Vec<_>>>()
+                .map(GtidPosition::MariaDb),
+        }
+    }
+
+    /// A position that has applied nothing yet
+    pub fn empty(flavor: ServerFlavor) -> Self {
+        match flavor {
+            ServerFlavor::MySql => GtidPosition::MySql(Vec::new()),
+            ServerFlavor::MariaDb => GtidPosition::MariaDb(Vec::new()),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        match self {
+            GtidPosition::MySql(sets) => sets.is_empty(),
+            GtidPosition::MariaDb(gtids) => gtids.is_empty(),
+        }
+    }
+
+    /// Add an applied transaction to the position
+    ///
+    /// MySQL positions are sets, so the transaction ID joins its source's
+    /// intervals. MariaDB positions keep the latest GTID of each replication
+    /// domain, since sequence numbers only grow within a domain.
+    pub fn advance(&mut self, gtid: &Gtid) -> Result<()> {
+        match (self, gtid) {
+            (
+                GtidPosition::MySql(sets),
+                Gtid::MySql {
+                    source_uuid,
+                    transaction_id,
+                },
+            ) => {
+                add_mysql_interval(sets, source_uuid, (*transaction_id, *transaction_id));
+                Ok(())
+            }
+            (GtidPosition::MariaDb(gtids), Gtid::MariaDb(gtid)) => {
+                advance_mariadb_domain(gtids, gtid);
+                Ok(())
+            }
+            _ => bail!(
+                "GTID '{:?}' does not match the format of the saved position",
+                gtid
+            ),
+        }
+    }
+
+    /// Combine two positions into one that covers both
+    ///
+    /// Progress is saved per table, so the resume point of a source is the
+    /// merge of the positions recorded for each of its tables.
+    pub fn merge(&mut self, other: &GtidPosition) -> Result<()> {
+        match (self, other) {
+            (GtidPosition::MySql(sets), GtidPosition::MySql(others)) => {
+                for set in others {
+                    for interval in &set.intervals {
+                        add_mysql_interval(sets, &set.source_uuid, *interval);
+                    }
+                }
+                Ok(())
+            }
+            (GtidPosition::MariaDb(gtids), GtidPosition::MariaDb(others)) => {
+                for gtid in others {
+                    advance_mariadb_domain(gtids, gtid);
+                }
+                Ok(())
+            }
+            _ => bail!("Cannot combine MySQL and MariaDB GTID positions"),
+        }
+    }
+}
+
+fn add_mysql_interval(sets: &mut Vec<MySqlGtidSet>, source_uuid: &str, interval: (u64, u64)) {
+    let set = match sets
+        .iter_mut()
+        .position(|set| set.source_uuid.eq_ignore_ascii_case(source_uuid))
+    {
+        Some(idx) => &mut sets[idx],
+        None => {
+            sets.push(MySqlGtidSet {
+                source_uuid: source_uuid.to_ascii_lowercase(),
+                intervals: Vec::new(),
+            });
+            sets.last_mut().expect("set was just pushed")
+        }
+    };
+
+    set.intervals.push(interval);
+    set.intervals.sort_unstable();
+    let mut merged: Vec<(u64, u64)> = Vec::with_capacity(set.intervals.len());
+    for &(start, end) in &set.intervals {
+        match merged.last_mut() {
+            Some(last) if start <= last.1.saturating_add(1) => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    set.intervals = merged;
+}
+
+fn advance_mariadb_domain(gtids: &mut Vec<MariaDbGtid>, gtid: &MariaDbGtid) {
+    match gtids.iter_mut().find(|g| g.domain_id == gtid.domain_id) {
+        Some(existing) if existing.sequence < gtid.sequence => *existing = gtid.clone(),
+        Some(_) => {}
+        None => gtids.push(gtid.clone()),
+    }
+}
+
+impl std::fmt::Display for GtidPosition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let rendered: Vec<String> = match self {
+            GtidPosition::MySql(sets) => sets
+                .iter()
+                .map(|set| {
+                    let intervals: Vec<String> = set
+                        .intervals
+                        .iter()
+                        .map(|(start, end)| {
+                            if start == end {
+                                start.to_string()
+                            } else {
+                                format!("{}-{}", start, end)
+                            }
+                        })
+                        .collect();
+                    format!("{}:{}", set.source_uuid, intervals.join(":"))
+                })
+                .collect(),
+            GtidPosition::MariaDb(gtids) => gtids
+                .iter()
+                .map(|g| format!("{}-{}-{}", g.domain_id, g.server_id, g.sequence))
+                .collect(),
+        };
+        write!(f, "{}", rendered.join(","))
+    }
+}
+
+fn parse_mysql_gtid_set(entry: &str) -> Result<MySqlGtidSet> {
+    let mut parts = entry.split(':');
+    let source_uuid = parts
+        .next()
+        .filter(|uuid| uuid.len() == 36)
+        .with_context(|| format!("Invalid MySQL GTID set '{}': missing source UUID", entry))?
+        .to_string();
+
+    let mut intervals = Vec::new();
+    for interval in parts {
+        let (start, end) = match interval.split_once('-') {
+            Some((start, end)) => (start, end),
+            None => (interval, interval),
+        };
+        let start: u64 = start
+            .parse()
+            .with_context(|| format!("Invalid GTID interval '{}' in '{}'", interval, entry))?;
+        let end: u64 = end
+            .parse()
+            .with_context(|| format!("Invalid GTID interval '{}' in '{}'", interval, entry))?;
+        if end < start {
+            bail!("Invalid GTID interval '{}' in '{}'", interval, entry);
+        }
+        intervals.push((start, end));
+    }
+
+    if intervals.is_empty() {
+        bail!(
+            "Invalid MySQL GTID set '{}': no transaction intervals",
+            entry
+        );
+    }
+
+    Ok(MySqlGtidSet {
+        source_uuid,
+        intervals,
+    })
+}
+
+fn parse_mariadb_gtid(entry: &str) -> Result<MariaDbGtid> {
+    let parts: Vec<&str> = entry.split('-').collect();
+    if parts.len() != 3 {
+        bail!(
+            "Invalid MariaDB GTID '{}': expected domain-server-sequence",
+            entry
+        );
+    }
+    Ok(MariaDbGtid {
+        domain_id: parts[0]
+            .parse()
+            .with_context(|| format!("Invalid GTID domain id in '{}'", entry))?,
+        server_id: parts[1]
+            .parse()
+            .with_context(|| format!("Invalid GTID server id in '{}'", entry))?,
+        sequence: parts[2]
+            .parse()
+            .with_context(|| format!("Invalid GTID sequence in '{}'", entry))?,
+    })
+}
+
+/// Detect whether the connected server is MySQL or MariaDB, and whether it is a Galera node
+pub async fn detect_server_info(conn: &mut Conn) -> Result<ServerInfo> {
+    let version: Option<String> = conn
+        .query_first("SELECT VERSION()")
+        .await
+        .context("Failed to query server version")?;
+    let version = version.context("Server returned no version")?;
+
+    // wsrep_on only exists on Galera-enabled builds; absence means standalone
+    let wsrep: Option<(String, String)> = conn
+        .query_first("SHOW VARIABLES LIKE 'wsrep_on'")
+        .await
+        .unwrap_or(None);
+    let galera = wsrep
+        .map(|(_, value)| value.eq_ignore_ascii_case("ON"))
+        .unwrap_or(false);
+
+    ServerInfo::from_version_string(&version, galera)
+}
+
+/// Get the server's current GTID position, if GTIDs are enabled
+pub async fn current_gtid_position(
+    conn: &mut Conn,
+    info: &ServerInfo,
+) -> Result<Option<GtidPosition>> {
+    let raw: Option<String> = conn
+        .query_first(info.gtid_position_query())
+        .await
+        .context("Failed to query current GTID position")?;
+
+    match raw {
+        Some(value) if !value.trim().is_empty() => {
+            // MySQL wraps long gtid_executed values across lines
+            let normalized: String = value.split_whitespace().collect();
+            Ok(Some(GtidPosition::parse(info.flavor, &normalized)?))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// List columns that MariaDB stores as LONGTEXT but declares as JSON
+///
+/// MariaDB implements `JSON` as an alias for `LONGTEXT` with a
+/// `CHECK (json_valid(col))` constraint, so values arrive as plain strings.
+/// Returns an empty list for MySQL, where JSON is a native type.
+pub async fn json_alias_columns(
+    conn: &mut Conn,
+    info: &ServerInfo,
+    db_name: &str,
+    table_name: &str,
+) -> Result<Vec<String>> {
+    if !info.is_mariadb() {
+        return Ok(Vec::new());
+    }
+
+    let query = r#"
+        SELECT CHECK_CLAUSE
+        FROM INFORMATION_SCHEMA.CHECK_CONSTRAINTS
+        WHERE CONSTRAINT_SCHEMA = ?
+        AND TABLE_NAME = ?
+    "#;
+    let clauses: Vec<String> =
+        conn.exec(query, (db_name, table_name))
+            .await
+            .with_context(|| {
+                format!(
+                    "Failed to read check constraints for '{}.{}'",
+                    db_name, table_name
+                )
+            })?;
+
+    Ok(clauses
+        .iter()
+        .filter_map(|clause| json_valid_column(clause))
+        .collect())
+}
+
+/// Extract the column name from a `json_valid(`col`)` check clause
+fn json_valid_column(clause: &str) -> Option<String> {
+    let lower = clause.trim().to_ascii_lowercase();
+    let inner = lower.strip_prefix("json_valid(")?.strip_suffix(')')?;
+    let start = "json_valid(".len();
+    let original = &clause.trim()[start..start + inner.len()];
+    let column = original.trim().trim_matches('`');
+    if column.is_empty() {
+        None
+    } else {
+        Some(column.to_string())
+    }
+}
+
+/// Render a MariaDB `UUID` value from a binlog row image as text
+///
+/// Row images carry the storage format. MariaDB stores time-based UUIDs
+/// (versions 1-5 with the RFC 4122 variant) with their five segments in
+/// reverse order so they sort by time; any other UUID is stored as-is.
+///
+/// # Examples
+///
+/// ```
+/// # use database_replicator::mysql::flavor::mariadb_uuid_from_record;
+/// // Stored form of 6ccd780c-baba-1026-9564-5b8c656024db
+/// let stored = [
+///     0x5b, 0x8c, 0x65, 0x60, 0x24, 0xdb, 0x95, 0x64,
+///     0x10, 0x26, 0xba, 0xba, 0x6c, 0xcd, 0x78, 0x0c,
+/// ];
+/// assert_eq!(
+///     mariadb_uuid_from_record(&stored).unwrap(),
+///     "6ccd780c-baba-1026-9564-5b8c656024db"
+/// );
+/// ```
+pub fn mariadb_uuid_from_record(bytes: &[u8]) -> Option<String> {
+    let stored: [u8; 16] = bytes.try_into().ok()?;
+    let variant_rfc4122 = stored[6] >> 6 == 0b10;
+    let version = stored[8] >> 4;
+    let uuid = if variant_rfc4122 && (1..=5).contains(&version) {
+        let mut swapped = [0u8; 16];
+        swapped[0..4].copy_from_slice(&stored[12..16]);
+        swapped[4..6].copy_from_slice(&stored[10..12]);
+        swapped[6..8].copy_from_slice(&stored[8..10]);
+        swapped[8..10].copy_from_slice(&stored[6..8]);
+        swapped[10..16].copy_from_slice(&stored[0..6]);
+        swapped
+    } else {
+        stored
+    };
+    Some(uuid::Uuid::from_bytes(uuid).hyphenated().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_mysql_version() {
+        let (flavor, version) = parse_version_string("8.0.35-0ubuntu0.22.04.1").unwrap();
+        assert_eq!(flavor, ServerFlavor::MySql);
+        assert_eq!(version, (8, 0, 35));
+    }
+
+    #[test]
+    fn test_parse_mariadb_version() {
+        let (flavor, version) = parse_version_string("10.11.6-MariaDB-log").unwrap();
+        assert_eq!(flavor, ServerFlavor::MariaDb);
+        assert_eq!(version, (10, 11, 6));
+    }
+
+    #[test]
+    fn test_parse_mariadb_compat_prefix() {
+        let (flavor, version) = parse_version_string("5.5.5-10.6.12-MariaDB").unwrap();
+        assert_eq!(flavor, ServerFlavor::MariaDb);
+        assert_eq!(version, (10, 6, 12));
+    }
+
+    #[test]
+    fn test_parse_invalid_version() {
+        assert!(parse_version_string("unknown").is_err());
+    }
+
+    #[test]
+    fn test_ensure_supported_rejects_old_mariadb() {
+        let info = ServerInfo::from_version_string("10.4.31-MariaDB", false).unwrap();
+        assert!(info.ensure_supported().is_err());
+
+        let info = ServerInfo::from_version_string("10.6.0-MariaDB", true).unwrap();
+        assert!(info.ensure_supported().is_ok());
+        assert!(info.describe().contains("Galera"));
+
+        let info = ServerInfo::from_version_string("5.7.44", false).unwrap();
+        assert!(info.ensure_supported().is_ok());
+    }
+
+    #[test]
+    fn test_parse_mysql_gtid() {
+        let value = "3e11fa47-71ca-11e1-9e33-c80aa9429562:1-5:11-18,\
+                     4f22fb58-71ca-11e1-9e33-c80aa9429562:7";
+        let position = GtidPosition::parse(ServerFlavor::MySql, value).unwrap();
+        match &position {
+            GtidPosition::MySql(sets) => {
+                assert_eq!(sets.len(), 2);
+                assert_eq!(sets[0].intervals, vec![(1, 5), (11, 18)]);
+                assert_eq!(sets[1].intervals, vec![(7, 7)]);
+            }
+            _ => panic!("expected MySQL GTID"),
+        }
+        assert_eq!(position.to_string(), value);
+    }
+
+    #[test]
+    fn test_parse_mariadb_gtid() {
+        let position = GtidPosition::parse(ServerFlavor::MariaDb, "0-1-100,1-2-5").unwrap();
+        assert_eq!(
+            position,
+            GtidPosition::MariaDb(vec![
+                MariaDbGtid {
+                    domain_id: 0,
+                    server_id: 1,
+                    sequence: 100
+                },
+                MariaDbGtid {
+                    domain_id: 1,
+                    server_id: 2,
+                    sequence: 5
+                },
+            ])
+        );
+        assert_eq!(position.to_string(), "0-1-100,1-2-5");
+    }
+
+    #[test]
+    fn test_gtid_format_mismatch_is_rejected() {
+        assert!(GtidPosition::parse(ServerFlavor::MariaDb, "3e11fa47:1-5").is_err());
+        assert!(GtidPosition::parse(ServerFlavor::MySql, "0-1-100").is_err());
+    }
+
+    #[test]
+    fn test_advance_mysql_gtid_joins_intervals() {
+        let uuid = "3e11fa47-71ca-11e1-9e33-c80aa9429562";
+        let mut position =
+            GtidPosition::parse(ServerFlavor::MySql, &format!("{}:1-5:7", uuid)).unwrap();
+        let gtid = |transaction_id| Gtid::MySql {
+            source_uuid: uuid.to_string(),
+            transaction_id,
+        };
+        position.advance(&gtid(6)).unwrap();
+        assert_eq!(position.to_string(), format!("{}:1-7", uuid));
+
+        position
+            .advance(&Gtid::MySql {
+                source_uuid: "4f22fb58-71ca-11e1-9e33-c80aa9429562".to_string(),
+                transaction_id: 1,
+            })
+            .unwrap();
+        assert_eq!(
+            position.to_string(),
+            format!("{}:1-7,4f22fb58-71ca-11e1-9e33-c80aa9429562:1", uuid)
+        );
+    }
+
+    #[test]
+    fn test_advance_mariadb_gtid_keeps_latest_per_domain() {
+        let mut position = GtidPosition::parse(ServerFlavor::MariaDb, "0-1-100").unwrap();
+        let gtid = |domain_id, server_id, sequence| {
+            Gtid::MariaDb(MariaDbGtid {
+                domain_id,
+                server_id,
+                sequence,
+            })
+        };
+        position.advance(&gtid(0, 2, 101)).unwrap();
+        position.advance(&gtid(0, 1, 99)).unwrap();
+        position.advance(&gtid(1, 1, 5)).unwrap();
+        assert_eq!(position.to_string(), "0-2-101,1-1-5");
+
+        let mysql = Gtid::MySql {
+            source_uuid: "3e11fa47-71ca-11e1-9e33-c80aa9429562".to_string(),
+            transaction_id: 1,
+        };
+        assert!(position.advance(&mysql).is_err());
+    }
+
+    #[test]
+    fn test_merge_positions() {
+        let uuid = "3e11fa47-71ca-11e1-9e33-c80aa9429562";
+        let mut position =
+            GtidPosition::parse(ServerFlavor::MySql, &format!("{}:1-5", uuid)).unwrap();
+        let later = GtidPosition::parse(ServerFlavor::MySql, &format!("{}:1-9", uuid)).unwrap();
+        position.merge(&later).unwrap();
+        assert_eq!(position, later);
+
+        let mut position = GtidPosition::parse(ServerFlavor::MariaDb, "0-1-10,1-1-3").unwrap();
+        let other = GtidPosition::parse(ServerFlavor::MariaDb, "0-1-8,2-1-1").unwrap();
+        position.merge(&other).unwrap();
+        assert_eq!(position.to_string(), "0-1-10,1-1-3,2-1-1");
+        assert!(position.merge(&later).is_err());
+    }
+
+    #[test]
+    fn test_check_binlog_settings() {
+        let settings = BinlogSettings {
+            log_bin: true,
+            binlog_format: "ROW".to_string(),
+            binlog_row_image: "FULL".to_string(),
+            log_slave_updates: false,
+            gtid_mode: Some("ON".to_string()),
+        };
+        let mysql = ServerInfo::from_version_string("8.0.35", false).unwrap();
+        assert!(mysql.check_binlog_settings(&settings).is_ok());
+
+        let no_gtids = BinlogSettings {
+            gtid_mode: Some("OFF".to_string()),
+            ..settings.clone()
+        };
+        let err = mysql.check_binlog_settings(&no_gtids).unwrap_err();
+        assert!(err.to_string().contains("gtid_mode"));
+
+        // MariaDB defaults to MIXED, and Galera nodes need log_slave_updates
+        let galera = ServerInfo::from_version_string("10.11.6-MariaDB", true).unwrap();
+        let mariadb_defaults = BinlogSettings {
+            binlog_format: "MIXED".to_string(),
+            gtid_mode: None,
+            ..settings
+        };
+        let err = galera
+            .check_binlog_settings(&mariadb_defaults)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("binlog_format=ROW"));
+        assert!(err.contains("log_slave_updates"));
+    }
+
+    #[test]
+    fn test_mariadb_uuid_from_record_keeps_other_versions() {
+        // Version 4 UUIDs are stored unchanged
+        let uuid = uuid::Uuid::parse_str("f47ac10b-58cc-4372-a567-0e02b2c3d479").unwrap();
+        assert_eq!(
+            mariadb_uuid_from_record(uuid.as_bytes()).unwrap(),
+            "f47ac10b-58cc-4372-a567-0e02b2c3d479"
+        );
+        assert!(mariadb_uuid_from_record(b"short").is_none());
+    }
+
+    #[test]
+    fn test_json_valid_column() {
+        assert_eq!(
+            json_valid_column("json_valid(`payload`)"),
+            Some("payload".to_string())
+        );
+        assert_eq!(
+            json_valid_column("JSON_VALID(`Data`)"),
+            Some("Data".to_string())
+        );
+        assert_eq!(json_valid_column("`age` > 0"), None);
+    }
+}
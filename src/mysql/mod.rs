@@ -1,8 +1,11 @@
 // ABOUTME: MySQL database reading utilities for replication to PostgreSQL
 // ABOUTME: Provides secure connection validation and read-only database access
 
+pub mod binlog;
 pub mod converter;
+pub mod flavor;
 pub mod reader;
+pub mod writer;
 
 use anyhow::{bail, Context, Result};
 use mysql_async::{Conn, Opts};
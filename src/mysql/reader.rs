@@ -50,6 +50,57 @@ pub async fn list_tables(conn: &mut Conn, db_name: &str) -> Result<Vec<String>>
     Ok(tables)
 }
 
+/// List user tables in a MySQL database with estimated row counts and sizes
+///
+/// Uses the statistics in INFORMATION_SCHEMA.TABLES, which are estimates for
+/// InnoDB, so no table is scanned. Size is data plus indexes. The returned
+/// `schema` is the database name.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use database_replicator::mysql::{connect_mysql, reader::list_table_stats};
+/// # async fn example() -> anyhow::Result<()> {
+/// let mut conn = connect_mysql("mysql://localhost:3306/mydb").await?;
+/// for table in list_table_stats(&mut conn, "mydb").await? {
+///     println!("{}: ~{} rows", table.name, table.row_count_estimate);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub async fn list_table_stats(
+    conn: &mut Conn,
+    db_name: &str,
+) -> Result<Vec<crate::migration::TableInfo>> {
+    let query = r#"
+        SELECT TABLE_NAME,
+               CAST(COALESCE(TABLE_ROWS, 0) AS SIGNED),
+               CAST(COALESCE(DATA_LENGTH, 0) + COALESCE(INDEX_LENGTH, 0) AS SIGNED)
+        FROM INFORMATION_SCHEMA.TABLES
+        WHERE TABLE_SCHEMA = ?
+        AND TABLE_TYPE = 'BASE TABLE'
+        ORDER BY TABLE_NAME
+    "#;
+
+    let rows: Vec<(String, i64, i64)> = conn
+        .exec(query, (db_name,))
+        .await
+        .with_context(|| format!("Failed to list tables from database '{}'", db_name))?;
+
+    Ok(rows
+        .into_iter()
+        .map(
+            |(name, row_count_estimate, size_bytes)| crate::migration::TableInfo {
+                schema: db_name.to_string(),
+                name,
+                row_count_estimate,
+                size_bytes,
+                unlogged: false,
+            },
+        )
+        .collect())
+}
+
 /// Get row count for a MySQL table
 ///
 /// Executes COUNT(*) query to determine table size.
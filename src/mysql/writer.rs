@@ -0,0 +1,489 @@
+// ABOUTME: MySQL target writer for reverse (PostgreSQL → MySQL) replication
+// ABOUTME: Maps PostgreSQL types to MySQL and applies rows with ON DUPLICATE KEY UPDATE
+
+use anyhow::{bail, Context, Result};
+use chrono::{Datelike, Timelike};
+use mysql_async::{prelude::*, Conn, Params, Value};
+use rust_decimal::Decimal;
+use tokio_postgres::Row;
+
+use crate::postgres::postgis::{self, SpatialEncoding, SpatialType};
+use crate::utils::{quote_ident, quote_mysql_ident};
+
+/// Maximum rows per multi-row upsert statement
+///
+/// Keeps statements within the default `max_allowed_packet` for typical row
+/// widths. Wide tables get fewer rows, see [`rows_per_statement`].
+const MAX_ROWS_PER_STATEMENT: usize = 500;
+
+/// Most placeholders MySQL accepts in one prepared statement
+const MAX_PLACEHOLDERS: usize = 65_535;
+
+/// Rows per upsert for a table of `column_count` columns
+fn rows_per_statement(column_count: usize) -> usize {
+    (MAX_PLACEHOLDERS / column_count.max(1)).clamp(1, MAX_ROWS_PER_STATEMENT)
+}
+
+/// Map a PostgreSQL `udt_name` to a MySQL column type
+///
+/// Primary key columns get bounded string types because MySQL cannot index
+/// unbounded TEXT columns without a prefix length.
+///
+/// # Examples
+///
+/// ```
+/// # use database_replicator::mysql::writer::pg_type_to_mysql;
+/// assert_eq!(pg_type_to_mysql("int8", false), "BIGINT");
+/// assert_eq!(pg_type_to_mysql("text", false), "LONGTEXT");
+/// assert_eq!(pg_type_to_mysql("text", true), "VARCHAR(255)");
+/// assert_eq!(pg_type_to_mysql("jsonb", false), "JSON");
+/// ```
+pub fn pg_type_to_mysql(udt_name: &str, is_key: bool) -> &'static str {
+    match udt_name {
+        "int2" | "smallint" => "SMALLINT",
+        "int4" | "integer" => "INT",
+        "int8" | "bigint" => "BIGINT",
+        "float4" | "real" => "FLOAT",
+        "float8" | "double precision" => "DOUBLE",
+        "numeric" | "decimal" => "DECIMAL(65,30)",
+        "bool" | "boolean" => "TINYINT(1)",
+        "uuid" => "CHAR(36)",
+        "date" => "DATE",
+        "time" => "TIME(6)",
+        // MySQL DATETIME has no zone; timestamptz values are written in UTC
+        "timestamp" | "timestamptz" => "DATETIME(6)",
+        "json" | "jsonb" => "JSON",
+        "bytea" => {
+            if is_key {
+                "VARBINARY(255)"
+            } else {
+                "LONGBLOB"
+            }
+        }
+        // Arrays have no MySQL equivalent and are stored as JSON arrays
+        t if t.starts_with('_') => "JSON",
+        _ => {
+            if is_key {
+                "VARCHAR(255)"
+            } else {
+                "LONGTEXT"
+            }
+        }
+    }
+}
+
+/// Build a CREATE TABLE IF NOT EXISTS statement for the MySQL target
+pub fn build_create_table_sql(
+    table: &str,
+    columns: &[(String, String)],
+    primary_key_columns: &[String],
+) -> Result<String> {
+    if columns.is_empty() {
+        bail!("Table '{}' has no columns", table);
+    }
+    if primary_key_columns.is_empty() {
+        bail!(
+            "Table '{}' has no primary key; MySQL targets require one for upserts",
+            table
+        );
+    }
+
+    let mut definitions: Vec<String> = columns
+        .iter()
+        .map(|(name, udt)| {
+            let is_key = primary_key_columns.contains(name);
+            let null = if is_key { " NOT NULL" } else { "" };
+            format!(
+                "{} {}{}",
+                quote_mysql_ident(name),
+                pg_type_to_mysql(udt, is_key),
+                null
+            )
+        })
+        .collect();
+
+    let pk: Vec<String> = primary_key_columns
+        .iter()
+        .map(|c| quote_mysql_ident(c))
+        .collect();
+    definitions.push(format!("PRIMARY KEY ({})", pk.join(", ")));
+
+    Ok(format!(
+        "CREATE TABLE IF NOT EXISTS {} ({}) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4",
+        quote_mysql_ident(table),
+        definitions.join(", ")
+    ))
+}
+
+/// Build a multi-row INSERT ... ON DUPLICATE KEY UPDATE statement
+///
+/// Generates a query like:
+/// ```sql
+/// INSERT INTO `users` (`id`, `name`) VALUES (?, ?), (?, ?)
+/// ON DUPLICATE KEY UPDATE `name` = VALUES(`name`)
+/// ```
+pub fn build_upsert_query(
+    table: &str,
+    primary_key_columns: &[String],
+    all_columns: &[String],
+    num_rows: usize,
+) -> String {
+    let quoted_columns: Vec<String> = all_columns.iter().map(|c| quote_mysql_ident(c)).collect();
+    let placeholders = format!("({})", vec!["?"; all_columns.len()].join(", "));
+    let values = vec![placeholders; num_rows].join(", ");
+
+    let updates: Vec<String> = all_columns
+        .iter()
+        .filter(|c| !primary_key_columns.contains(c))
+        .map(|c| {
+            let quoted = quote_mysql_ident(c);
+            format!("{} = VALUES({})", quoted, quoted)
+        })
+        .collect();
+
+    // With only key columns there is nothing to update; re-assign a key to make the insert a no-op
+    let update_clause = if updates.is_empty() {
+        let quoted = quote_mysql_ident(&primary_key_columns[0]);
+        format!("{} = {}", quoted, quoted)
+    } else {
+        updates.join(", ")
+    };
+
+    format!(
+        "INSERT INTO {} ({}) VALUES {} ON DUPLICATE KEY UPDATE {}",
+        quote_mysql_ident(table),
+        quoted_columns.join(", "),
+        values,
+        update_clause
+    )
+}
+
+/// Column types [`pg_row_to_mysql_values`] decodes from their binary form
+///
+/// Every other column is read through [`select_expression`] as text.
+const NATIVE_TYPES: &[&str] = &[
+    "int2",
+    "int4",
+    "int8",
+    "float4",
+    "float8",
+    "bool",
+    "numeric",
+    "uuid",
+    "date",
+    "time",
+    "timestamp",
+    "timestamptz",
+    "json",
+    "jsonb",
+    "bytea",
+    "text",
+    "varchar",
+    "bpchar",
+    "name",
+    "citext",
+    "_text",
+    "_varchar",
+    "_bpchar",
+    "_citext",
+    "_int2",
+    "_int4",
+    "_int8",
+    "_float4",
+    "_float8",
+    "_bool",
+    "_uuid",
+    "_jsonb",
+    "_json",
+];
+
+/// Build the SELECT expression that reads a column for a MySQL target
+///
+/// Types without a binary decoding here (interval, inet, timetz, enums, and
+/// so on) are read as their PostgreSQL text form, arrays of them as JSON
+/// text, and PostGIS values as EWKT.
+///
+/// # Examples
+///
+/// ```
+/// # use database_replicator::mysql::writer::select_expression;
+/// assert_eq!(select_expression("id", "int8"), "\"id\"");
+/// assert_eq!(select_expression("span", "interval"), "\"span\"::text AS \"span\"");
+/// assert_eq!(
+///     select_expression("hosts", "_inet"),
+///     "to_json(\"hosts\")::text AS \"hosts\""
+/// );
+/// ```
+pub fn select_expression(column: &str, udt_name: &str) -> String {
+    let quoted = quote_ident(column);
+    if NATIVE_TYPES.contains(&udt_name) {
+        quoted
+    } else if SpatialType::from_udt(udt_name).is_some() {
+        postgis::select_expression(column, udt_name, SpatialEncoding::Ewkt)
+    } else if udt_name.starts_with('_') {
+        format!("to_json({})::text AS {}", quoted, quoted)
+    } else {
+        format!("{}::text AS {}", quoted, quoted)
+    }
+}
+
+/// Convert a PostgreSQL row into MySQL parameter values
+///
+/// `column_types` holds (column_name, udt_name) pairs in row order, as returned
+/// by [`crate::xmin::get_table_columns`]. The row must have been read with
+/// [`select_expression`] for each column.
+///
+/// # Errors
+///
+/// Fails when a value cannot be represented, such as a `numeric` NaN or one
+/// with more than 28 significant digits, or when a column was not read in the
+/// form its type needs. Values are never silently written as NULL.
+pub fn pg_row_to_mysql_values(row: &Row, column_types: &[(String, String)]) -> Result<Vec<Value>> {
+    column_types
+        .iter()
+        .enumerate()
+        .map(|(idx, (name, udt))| {
+            pg_value_to_mysql(row, idx, udt)
+                .with_context(|| format!("Cannot convert column '{}' ({}) for MySQL", name, udt))
+        })
+        .collect()
+}
+
+fn pg_value_to_mysql(row: &Row, idx: usize, udt: &str) -> Result<Value> {
+    fn or_null<T>(value: Option<T>, f: impl FnOnce(T) -> Value) -> Result<Value> {
+        Ok(value.map(f).unwrap_or(Value::NULL))
+    }
+    fn json_array<T: serde::Serialize>(value: Option<Vec<T>>) -> Result<Value> {
+        match value {
+            Some(v) => Ok(Value::Bytes(serde_json::to_vec(&v)?)),
+            None => Ok(Value::NULL),
+        }
+    }
+
+    match udt {
+        "int2" => or_null(row.try_get::<_, Option<i16>>(idx)?, |v| {
+            Value::Int(v as i64)
+        }),
+        "int4" => or_null(row.try_get::<_, Option<i32>>(idx)?, |v| {
+            Value::Int(v as i64)
+        }),
+        "int8" => or_null(row.try_get::<_, Option<i64>>(idx)?, Value::Int),
+        "float4" => or_null(row.try_get::<_, Option<f32>>(idx)?, Value::Float),
+        "float8" => or_null(row.try_get::<_, Option<f64>>(idx)?, Value::Double),
+        "bool" => or_null(row.try_get::<_, Option<bool>>(idx)?, |v| {
+            Value::Int(v as i64)
+        }),
+        "numeric" => or_null(row.try_get::<_, Option<Decimal>>(idx)?, |v| {
+            Value::Bytes(v.to_string().into_bytes())
+        }),
+        "uuid" => or_null(row.try_get::<_, Option<uuid::Uuid>>(idx)?, |v| {
+            Value::Bytes(v.to_string().into_bytes())
+        }),
+        "date" => or_null(row.try_get::<_, Option<chrono::NaiveDate>>(idx)?, |d| {
+            Value::Date(d.year() as u16, d.month() as u8, d.day() as u8, 0, 0, 0, 0)
+        }),
+        "time" => or_null(row.try_get::<_, Option<chrono::NaiveTime>>(idx)?, |t| {
+            Value::Time(
+                false,
+                0,
+                t.hour() as u8,
+                t.minute() as u8,
+                t.second() as u8,
+                // A leap second's extra nanoseconds have no MySQL equivalent
+                (t.nanosecond() / 1_000).min(999_999),
+            )
+        }),
+        "timestamp" => or_null(
+            row.try_get::<_, Option<chrono::NaiveDateTime>>(idx)?,
+            naive_to_mysql,
+        ),
+        "timestamptz" => or_null(
+            row.try_get::<_, Option<chrono::DateTime<chrono::Utc>>>(idx)?,
+            |v| naive_to_mysql(v.naive_utc()),
+        ),
+        "json" | "jsonb" => or_null(row.try_get::<_, Option<serde_json::Value>>(idx)?, |v| {
+            Value::Bytes(v.to_string().into_bytes())
+        }),
+        "bytea" => or_null(row.try_get::<_, Option<Vec<u8>>>(idx)?, Value::Bytes),
+        "_text" | "_varchar" | "_bpchar" | "_citext" => {
+            json_array(row.try_get::<_, Option<Vec<String>>>(idx)?)
+        }
+        "_int2" => json_array(row.try_get::<_, Option<Vec<i16>>>(idx)?),
+        "_int4" => json_array(row.try_get::<_, Option<Vec<i32>>>(idx)?),
+        "_int8" => json_array(row.try_get::<_, Option<Vec<i64>>>(idx)?),
+        "_float4" => json_array(row.try_get::<_, Option<Vec<f32>>>(idx)?),
+        "_float8" => json_array(row.try_get::<_, Option<Vec<f64>>>(idx)?),
+        "_bool" => json_array(row.try_get::<_, Option<Vec<bool>>>(idx)?),
+        "_uuid" => json_array(
+            row.try_get::<_, Option<Vec<uuid::Uuid>>>(idx)?
+                .map(|v| v.iter().map(|u| u.to_string()).collect::<Vec<_>>()),
+        ),
+        "_jsonb" | "_json" => json_array(row.try_get::<_, Option<Vec<serde_json::Value>>>(idx)?),
+        // Text types, and everything select_expression reads as text
+        _ => or_null(row.try_get::<_, Option<String>>(idx)?, |v| {
+            Value::Bytes(v.into_bytes())
+        }),
+    }
+}
+
+fn naive_to_mysql(v: chrono::NaiveDateTime) -> Value {
+    Value::Date(
+        v.year() as u16,
+        v.month() as u8,
+        v.day() as u8,
+        v.hour() as u8,
+        v.minute() as u8,
+        v.second() as u8,
+        v.and_utc().timestamp_subsec_micros(),
+    )
+}
+
+/// Applies replicated rows to a MySQL target database
+pub struct MySqlWriter {
+    conn: Conn,
+}
+
+impl MySqlWriter {
+    /// Create a writer that owns the given MySQL connection
+    pub fn new(conn: Conn) -> Self {
+        Self { conn }
+    }
+
+    /// Create the target table if it does not exist
+    pub async fn ensure_table(
+        &mut self,
+        table: &str,
+        columns: &[(String, String)],
+        primary_key_columns: &[String],
+    ) -> Result<()> {
+        let sql = build_create_table_sql(table, columns, primary_key_columns)?;
+        self.conn
+            .query_drop(&sql)
+            .await
+            .with_context(|| format!("Failed to create MySQL table '{}'", table))?;
+        Ok(())
+    }
+
+    /// Apply a batch of rows using INSERT ... ON DUPLICATE KEY UPDATE
+    ///
+    /// Returns the number of rows written.
+    pub async fn apply_batch(
+        &mut self,
+        table: &str,
+        primary_key_columns: &[String],
+        all_columns: &[String],
+        rows: Vec<Vec<Value>>,
+    ) -> Result<u64> {
+        let mut written = 0u64;
+        for chunk in rows.chunks(rows_per_statement(all_columns.len())) {
+            let query = build_upsert_query(table, primary_key_columns, all_columns, chunk.len());
+            let params: Vec<Value> = chunk.iter().flatten().cloned().collect();
+            self.conn
+                .exec_drop(&query, Params::Positional(params))
+                .await
+                .with_context(|| format!("Failed to upsert batch into MySQL table '{}'", table))?;
+            written += chunk.len() as u64;
+        }
+        Ok(written)
+    }
+
+    /// Close the underlying connection cleanly
+    pub async fn disconnect(self) -> Result<()> {
+        self.conn
+            .disconnect()
+            .await
+            .context("Failed to close MySQL connection")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pg_type_mapping() {
+        assert_eq!(pg_type_to_mysql("int4", false), "INT");
+        assert_eq!(pg_type_to_mysql("bool", false), "TINYINT(1)");
+        assert_eq!(pg_type_to_mysql("timestamptz", false), "DATETIME(6)");
+        assert_eq!(pg_type_to_mysql("_int4", false), "JSON");
+        assert_eq!(pg_type_to_mysql("uuid", true), "CHAR(36)");
+        assert_eq!(pg_type_to_mysql("varchar", true), "VARCHAR(255)");
+        assert_eq!(pg_type_to_mysql("bytea", true), "VARBINARY(255)");
+        assert_eq!(pg_type_to_mysql("geometry", false), "LONGTEXT");
+    }
+
+    #[test]
+    fn test_select_expression_reads_unsupported_types_as_text() {
+        assert_eq!(select_expression("starts", "time"), "\"starts\"");
+        assert_eq!(select_expression("tags", "_text"), "\"tags\"");
+        assert_eq!(
+            select_expression("opens", "timetz"),
+            "\"opens\"::text AS \"opens\""
+        );
+        assert_eq!(
+            select_expression("mood", "mood_enum"),
+            "\"mood\"::text AS \"mood\""
+        );
+        assert_eq!(
+            select_expression("prices", "_numeric"),
+            "to_json(\"prices\")::text AS \"prices\""
+        );
+        assert_eq!(
+            select_expression("geom", "geometry"),
+            "ST_AsEWKT(\"geom\") AS \"geom\""
+        );
+    }
+
+    #[test]
+    fn test_build_create_table_sql() {
+        let columns = vec![
+            ("id".to_string(), "int8".to_string()),
+            ("email".to_string(), "text".to_string()),
+        ];
+        let sql = build_create_table_sql("users", &columns, &["id".to_string()]).unwrap();
+        assert!(sql.starts_with("CREATE TABLE IF NOT EXISTS `users`"));
+        assert!(sql.contains("`id` BIGINT NOT NULL"));
+        assert!(sql.contains("`email` LONGTEXT"));
+        assert!(sql.contains("PRIMARY KEY (`id`)"));
+    }
+
+    #[test]
+    fn test_build_create_table_requires_primary_key() {
+        let columns = vec![("id".to_string(), "int8".to_string())];
+        assert!(build_create_table_sql("events", &columns, &[]).is_err());
+    }
+
+    #[test]
+    fn test_rows_per_statement_stays_under_placeholder_limit() {
+        assert_eq!(rows_per_statement(10), MAX_ROWS_PER_STATEMENT);
+        assert_eq!(rows_per_statement(131), MAX_ROWS_PER_STATEMENT);
+        assert_eq!(rows_per_statement(200), 327);
+        assert!(rows_per_statement(200) * 200 <= MAX_PLACEHOLDERS);
+    }
+
+    #[test]
+    fn test_build_upsert_query() {
+        let query = build_upsert_query(
+            "users",
+            &["id".to_string()],
+            &["id".to_string(), "name".to_string()],
+            2,
+        );
+        assert_eq!(
+            query,
+            "INSERT INTO `users` (`id`, `name`) VALUES (?, ?), (?, ?) \
+             ON DUPLICATE KEY UPDATE `name` = VALUES(`name`)"
+        );
+    }
+
+    #[test]
+    fn test_build_upsert_query_all_key_columns() {
+        let query = build_upsert_query(
+            "tags",
+            &["a".to_string(), "b".to_string()],
+            &["a".to_string(), "b".to_string()],
+            1,
+        );
+        assert!(query.ends_with("ON DUPLICATE KEY UPDATE `a` = `a`"));
+    }
+}
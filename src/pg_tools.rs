@@ -0,0 +1,330 @@
+// ABOUTME: Downloads pinned builds of the PostgreSQL client tools into ~/.seren/tools
+// ABOUTME: Puts them first in PATH when the installed tools are missing or older than the source
+
+use anyhow::{bail, Context, Result};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+/// Environment variable that overrides where the tool archives are downloaded from
+pub const TOOLS_URL_ENV: &str = "SEREN_PG_TOOLS_URL";
+
+const DEFAULT_TOOLS_URL: &str =
+    "https://github.com/theseus-rs/postgresql-binaries/releases/download";
+
+/// Release downloaded for each major version, oldest first
+///
+/// Each archive holds a self-contained build of PostgreSQL: the client tools
+/// and the libraries they load, with no dependency on a system libpq.
+const RELEASES: &[(u32, &str)] = &[
+    (13, "13.21.0"),
+    (14, "14.18.0"),
+    (15, "15.13.0"),
+    (16, "16.9.0"),
+    (17, "17.5.0"),
+    (18, "18.0.0"),
+];
+
+/// Tools the download has to provide before it is used
+const REQUIRED_TOOLS: [&str; 4] = ["pg_dump", "pg_dumpall", "pg_restore", "psql"];
+
+/// Release to download for a source server, or `None` when the server is newer
+/// than every pinned release
+///
+/// Any pg_dump can dump older servers, so sources older than the oldest pinned
+/// release get that release. Without a known source the newest one is used.
+pub fn release_for(server_major: Option<u32>) -> Option<(u32, &'static str)> {
+    let (oldest, newest) = (RELEASES[0], RELEASES[RELEASES.len() - 1]);
+    match server_major {
+        None => Some(newest),
+        Some(major) if major <= oldest.0 => Some(oldest),
+        Some(major) => RELEASES.iter().copied().find(|(m, _)| *m == major),
+    }
+}
+
+/// Platform name the archives are published under
+pub fn platform_target() -> Option<&'static str> {
+    match (std::env::consts::OS, std::env::consts::ARCH) {
+        ("linux", "x86_64") => Some("x86_64-unknown-linux-gnu"),
+        ("linux", "aarch64") => Some("aarch64-unknown-linux-gnu"),
+        ("macos", "x86_64") => Some("x86_64-apple-darwin"),
+        ("macos", "aarch64") => Some("aarch64-apple-darwin"),
+        _ => None,
+    }
+}
+
+fn archive_name(version: &str, target: &str) -> String {
+    format!("postgresql-{}-{}.tar.gz", version, target)
+}
+
+fn archive_url(base: &str, version: &str, target: &str) -> String {
+    format!(
+        "{}/{}/{}",
+        base.trim_end_matches('/'),
+        version,
+        archive_name(version, target)
+    )
+}
+
+/// Directory the downloaded releases are kept in: `~/.seren/tools`
+pub fn tools_dir() -> Result<PathBuf> {
+    let home = dirs::home_dir().context("Failed to determine home directory")?;
+    Ok(home.join(".seren").join("tools"))
+}
+
+/// Why the installed client tools can't be used for a source, if they can't
+pub fn download_reason(server_major: Option<u32>) -> Option<String> {
+    let missing = crate::utils::missing_client_tools();
+    if !missing.is_empty() {
+        return Some(format!("{} not found", missing.join(", ")));
+    }
+    let server = server_major?;
+    match crate::utils::get_pg_tool_version("pg_dump") {
+        Ok(local) if local < server => Some(format!(
+            "pg_dump {} is older than the source server ({})",
+            local, server
+        )),
+        Ok(_) => None,
+        Err(e) => Some(format!("{:#}", e)),
+    }
+}
+
+/// Make sure client tools that can dump `source_url` are first in PATH
+///
+/// Keeps the installed tools when they are recent enough; otherwise downloads
+/// the pinned release for the source's major version into [`tools_dir`], or
+/// reuses an earlier download, and prepends its `bin` directory to PATH so
+/// every later pg_dump, pg_restore, and psql call runs it.
+pub async fn ensure(source_url: Option<&str>) -> Result<()> {
+    let server_major = match source_url {
+        Some(url)
+            if matches!(
+                crate::detect_source_type(url),
+                Ok(crate::SourceType::PostgreSQL)
+            ) =>
+        {
+            source_major(url).await
+        }
+        _ => None,
+    };
+    let Some(reason) = download_reason(server_major) else {
+        tracing::info!("✓ Installed PostgreSQL client tools are recent enough; not downloading");
+        return Ok(());
+    };
+    let Some((major, version)) = release_for(server_major) else {
+        bail!(
+            "No pinned PostgreSQL client tools for source server {} ({}); install them from https://www.postgresql.org/download/",
+            server_major.unwrap_or_default(),
+            reason
+        );
+    };
+    tracing::info!(
+        "{}; using PostgreSQL {} client tools from {}",
+        reason,
+        version,
+        tools_dir()?.display()
+    );
+
+    let bin = install(version).await?;
+    prepend_to_path(&bin)?;
+    let installed = crate::utils::get_pg_tool_version("pg_dump")
+        .context("Downloaded pg_dump does not run on this machine")?;
+    if installed != major {
+        bail!(
+            "pg_dump in {} reports version {}, expected {}",
+            bin.display(),
+            installed,
+            major
+        );
+    }
+    tracing::info!("✓ Using pg_dump {} from {}", installed, bin.display());
+    Ok(())
+}
+
+/// Major version of the source, or `None` when it can't be reached yet
+async fn source_major(url: &str) -> Option<u32> {
+    let client = match crate::postgres::connect(url).await {
+        Ok(client) => client,
+        Err(e) => {
+            tracing::warn!(
+                "⚠ Could not connect to the source to pick client tools: {:#}",
+                e
+            );
+            return None;
+        }
+    };
+    crate::postgres::versions::server_major(&client).await.ok()
+}
+
+/// `bin` directory of `version`, downloading and unpacking it first if needed
+pub async fn install(version: &str) -> Result<PathBuf> {
+    let Some(target) = platform_target() else {
+        bail!(
+            "No PostgreSQL client tool downloads for {}-{}; install them from https://www.postgresql.org/download/",
+            std::env::consts::OS,
+            std::env::consts::ARCH
+        );
+    };
+    let root = tools_dir()?;
+    let release_dir = root.join(format!("postgresql-{}", version));
+    let bin = release_dir.join("bin");
+    if has_tools(&bin) {
+        tracing::debug!(
+            "Reusing PostgreSQL {} client tools in {}",
+            version,
+            bin.display()
+        );
+        return Ok(bin);
+    }
+
+    let base = std::env::var(TOOLS_URL_ENV).unwrap_or_else(|_| DEFAULT_TOOLS_URL.to_string());
+    let url = archive_url(&base, version, target);
+    let http = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(600))
+        .build()
+        .context("Failed to create HTTP client")?;
+    tracing::info!("Downloading {}...", url);
+    let archive = fetch(&http, &url).await?;
+    let checksum = fetch(&http, &format!("{}.sha256", url)).await?;
+    verify_checksum(&archive, &String::from_utf8_lossy(&checksum))
+        .with_context(|| format!("Download of {} is corrupt", url))?;
+
+    std::fs::create_dir_all(&root)
+        .with_context(|| format!("Failed to create {}", root.display()))?;
+    // Unpack next to the final directory and move it into place, so an
+    // interrupted run never leaves a partial release behind
+    let staging = tempfile::tempdir_in(&root).with_context(|| {
+        format!(
+            "Failed to create a temporary directory in {}",
+            root.display()
+        )
+    })?;
+    tar::Archive::new(flate2::read::GzDecoder::new(archive.as_slice()))
+        .unpack(staging.path())
+        .with_context(|| format!("Failed to unpack {}", archive_name(version, target)))?;
+    let unpacked = find_release_root(staging.path())?;
+    if release_dir.exists() {
+        std::fs::remove_dir_all(&release_dir)
+            .with_context(|| format!("Failed to replace {}", release_dir.display()))?;
+    }
+    std::fs::rename(&unpacked, &release_dir)
+        .with_context(|| format!("Failed to move tools into {}", release_dir.display()))?;
+    if !has_tools(&bin) {
+        bail!(
+            "{} does not contain {}",
+            archive_name(version, target),
+            REQUIRED_TOOLS.join(", ")
+        );
+    }
+    Ok(bin)
+}
+
+async fn fetch(http: &reqwest::Client, url: &str) -> Result<Vec<u8>> {
+    let response = http
+        .get(url)
+        .send()
+        .await
+        .with_context(|| format!("Failed to download {}", url))?;
+    if !response.status().is_success() {
+        bail!("Failed to download {}: HTTP {}", url, response.status());
+    }
+    let body = response
+        .bytes()
+        .await
+        .with_context(|| format!("Failed to download {}", url))?;
+    Ok(body.to_vec())
+}
+
+/// Check `contents` against a `.sha256` file: the hex digest, optionally
+/// followed by the file name
+fn verify_checksum(contents: &[u8], checksum_file: &str) -> Result<()> {
+    let Some(expected) = checksum_file.split_whitespace().next() else {
+        bail!("Checksum file is empty");
+    };
+    let actual: String = Sha256::digest(contents)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    if !actual.eq_ignore_ascii_case(expected) {
+        bail!("SHA-256 is {}, expected {}", actual, expected);
+    }
+    Ok(())
+}
+
+/// The unpacked directory holding `bin/pg_dump`: the staging directory itself
+/// or the single directory the archive wraps everything in
+fn find_release_root(staging: &Path) -> Result<PathBuf> {
+    if staging.join("bin").is_dir() {
+        return Ok(staging.to_path_buf());
+    }
+    let entries = std::fs::read_dir(staging)
+        .with_context(|| format!("Failed to read {}", staging.display()))?;
+    for entry in entries {
+        let path = entry?.path();
+        if path.join("bin").is_dir() {
+            return Ok(path);
+        }
+    }
+    bail!("Archive has no bin directory")
+}
+
+fn has_tools(bin: &Path) -> bool {
+    REQUIRED_TOOLS.iter().all(|tool| bin.join(tool).is_file())
+}
+
+/// Put `bin` ahead of every other directory in PATH
+fn prepend_to_path(bin: &Path) -> Result<()> {
+    let mut paths = vec![bin.to_path_buf()];
+    if let Some(path) = std::env::var_os("PATH") {
+        paths.extend(std::env::split_paths(&path).filter(|dir| dir != bin));
+    }
+    let joined = std::env::join_paths(paths).context("Failed to build PATH")?;
+    std::env::set_var("PATH", joined);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_release_for() {
+        assert_eq!(release_for(Some(16)), Some((16, "16.9.0")));
+        // Older sources get the oldest pinned release
+        assert_eq!(release_for(Some(9)), Some((13, "13.21.0")));
+        assert_eq!(release_for(Some(12)), Some((13, "13.21.0")));
+        assert_eq!(release_for(None), Some((18, "18.0.0")));
+        assert_eq!(release_for(Some(19)), None);
+    }
+
+    #[test]
+    fn test_archive_url() {
+        assert_eq!(
+            archive_url(
+                "https://example.com/releases/",
+                "17.5.0",
+                "x86_64-unknown-linux-gnu"
+            ),
+            "https://example.com/releases/17.5.0/postgresql-17.5.0-x86_64-unknown-linux-gnu.tar.gz"
+        );
+    }
+
+    #[test]
+    fn test_verify_checksum() {
+        let digest = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+        assert!(verify_checksum(b"hello", digest).is_ok());
+        assert!(verify_checksum(b"hello", &format!("{}  hello.tar.gz\n", digest)).is_ok());
+        assert!(verify_checksum(b"hello!", digest).is_err());
+        assert!(verify_checksum(b"hello", "").is_err());
+    }
+
+    #[test]
+    fn test_find_release_root() {
+        let staging = tempfile::tempdir().unwrap();
+        assert!(find_release_root(staging.path()).is_err());
+        let wrapped = staging
+            .path()
+            .join("postgresql-17.5.0-x86_64-unknown-linux-gnu");
+        std::fs::create_dir_all(wrapped.join("bin")).unwrap();
+        assert_eq!(find_release_root(staging.path()).unwrap(), wrapped);
+    }
+}
@@ -0,0 +1,345 @@
+// ABOUTME: Google Cloud SQL connectivity by instance connection name
+// ABOUTME: Resolves cloudsql:// URLs to the instance address and pins TLS to its server CA
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Scheme of a source named by its Cloud SQL instance instead of a hostname
+pub const CLOUDSQL_SCHEME: &str = "cloudsql://";
+
+/// Which of an instance's addresses to connect to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IpType {
+    Public,
+    Private,
+}
+
+/// A parsed `cloudsql://[user[:password]@]project:region:instance/database[?params]` URL
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CloudSqlUrl {
+    pub project: String,
+    pub region: String,
+    pub instance: String,
+    pub user: Option<String>,
+    pub password: Option<String>,
+    pub database: String,
+    pub ip_type: IpType,
+    /// Query parameters other than `ip_type`, passed through unchanged
+    pub params: Vec<(String, String)>,
+}
+
+impl CloudSqlUrl {
+    /// Parse a `cloudsql://` URL; returns `Ok(None)` for any other string
+    pub fn parse(url: &str) -> Result<Option<Self>> {
+        let Some(rest) = url.strip_prefix(CLOUDSQL_SCHEME) else {
+            return Ok(None);
+        };
+        let (rest, query) = match rest.split_once('?') {
+            Some((rest, query)) => (rest, Some(query)),
+            None => (rest, None),
+        };
+        let (auth, rest) = match rest.rsplit_once('@') {
+            Some((auth, rest)) => (Some(auth), rest),
+            None => (None, rest),
+        };
+        let (user, password) = match auth.map(|auth| auth.split_once(':').unwrap_or((auth, ""))) {
+            Some((user, password)) => (
+                Some(user.to_string()).filter(|user| !user.is_empty()),
+                Some(password.to_string()).filter(|password| !password.is_empty()),
+            ),
+            None => (None, None),
+        };
+        let (instance_name, database) = rest
+            .split_once('/')
+            .context("Cloud SQL URL is missing a database name")?;
+        let [project, region, instance] = instance_name.split(':').collect::<Vec<_>>()[..] else {
+            bail!(
+                "Invalid Cloud SQL instance connection name '{}': expected <project>:<region>:<instance>",
+                instance_name
+            );
+        };
+        if project.is_empty() || region.is_empty() || instance.is_empty() || database.is_empty() {
+            bail!(
+                "Invalid Cloud SQL URL: expected cloudsql://user@<project>:<region>:<instance>/<database>"
+            );
+        }
+
+        let mut ip_type = IpType::Public;
+        let mut params = Vec::new();
+        for param in query.into_iter().flat_map(|query| query.split('&')) {
+            let (key, value) = param.split_once('=').unwrap_or((param, ""));
+            match (key, value) {
+                ("ip_type", "public") => ip_type = IpType::Public,
+                ("ip_type", "private") => ip_type = IpType::Private,
+                ("ip_type", other) => {
+                    bail!("Invalid ip_type '{}': expected public or private", other)
+                }
+                _ => params.push((key.to_string(), value.to_string())),
+            }
+        }
+
+        Ok(Some(Self {
+            project: project.to_string(),
+            region: region.to_string(),
+            instance: instance.to_string(),
+            user,
+            password,
+            database: database.to_string(),
+            ip_type,
+            params,
+        }))
+    }
+
+    /// The `project:region:instance` name used by Cloud SQL tools
+    pub fn connection_name(&self) -> String {
+        format!("{}:{}:{}", self.project, self.region, self.instance)
+    }
+
+    /// The equivalent PostgreSQL URL for an instance at `address`
+    ///
+    /// TLS is always required. Users with `@`, such as IAM service accounts,
+    /// are percent-encoded.
+    fn to_postgres_url(&self, address: &str) -> String {
+        let mut url = String::from("postgresql://");
+        if let Some(user) = &self.user {
+            url.push_str(&user.replace('@', "%40"));
+            if let Some(password) = &self.password {
+                url.push(':');
+                url.push_str(password);
+            }
+            url.push('@');
+        }
+        url.push_str(&format!("{}:5432/{}", address, self.database));
+        let mut params = vec![("sslmode".to_string(), "require".to_string())];
+        params.extend(
+            self.params
+                .iter()
+                .filter(|(key, _)| key != "sslmode")
+                .cloned(),
+        );
+        let query: Vec<String> = params
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect();
+        url.push('?');
+        url.push_str(&query.join("&"));
+        url
+    }
+}
+
+/// What `gcloud sql instances describe` reports about an instance
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct InstanceInfo {
+    region: String,
+    database_version: String,
+    #[serde(default)]
+    ip_addresses: Vec<IpAddress>,
+    server_ca_cert: Option<ServerCaCert>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct IpAddress {
+    #[serde(rename = "type")]
+    kind: String,
+    ip_address: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ServerCaCert {
+    cert: String,
+}
+
+/// A looked-up instance address and the CA that signs its server certificate
+#[derive(Debug, Clone)]
+struct ResolvedInstance {
+    address: String,
+    server_ca: Option<String>,
+}
+
+/// Instances looked up so far, keyed by connection name and address type
+static INSTANCES: Mutex<Option<HashMap<(String, IpType), ResolvedInstance>>> = Mutex::new(None);
+
+impl InstanceInfo {
+    fn resolve(&self, url: &CloudSqlUrl) -> Result<ResolvedInstance> {
+        if !self.database_version.starts_with("POSTGRES") {
+            bail!(
+                "Cloud SQL instance {} runs {}, not PostgreSQL",
+                url.connection_name(),
+                self.database_version
+            );
+        }
+        if self.region != url.region {
+            bail!(
+                "Cloud SQL instance {} is in region {}, not {}",
+                url.connection_name(),
+                self.region,
+                url.region
+            );
+        }
+        let (kind, name, other) = match url.ip_type {
+            IpType::Public => ("PRIMARY", "public", "private"),
+            IpType::Private => ("PRIVATE", "private", "public"),
+        };
+        let address = self
+            .ip_addresses
+            .iter()
+            .find(|ip| ip.kind == kind)
+            .with_context(|| {
+                format!(
+                    "Cloud SQL instance {} has no {} IP address; try ip_type={}",
+                    url.connection_name(),
+                    name,
+                    other
+                )
+            })?
+            .ip_address
+            .clone();
+        Ok(ResolvedInstance {
+            address,
+            server_ca: self.server_ca_cert.as_ref().map(|ca| ca.cert.clone()),
+        })
+    }
+}
+
+/// Turn a `cloudsql://` URL into a PostgreSQL URL; other strings are returned unchanged
+///
+/// The instance's address and server CA are looked up once per process with
+/// the `gcloud` CLI, using its configured account.
+pub async fn resolve(connection_string: &str) -> Result<String> {
+    let Some(url) = CloudSqlUrl::parse(connection_string)? else {
+        return Ok(connection_string.to_string());
+    };
+    let key = (url.connection_name(), url.ip_type);
+    let cached = INSTANCES
+        .lock()
+        .unwrap()
+        .as_ref()
+        .and_then(|instances| instances.get(&key).cloned());
+    let instance = match cached {
+        Some(instance) => instance,
+        None => {
+            let project = format!("--project={}", url.project);
+            let output = crate::secrets::run_cli(
+                "gcloud",
+                &[
+                    "sql",
+                    "instances",
+                    "describe",
+                    &url.instance,
+                    &project,
+                    "--format=json",
+                ],
+            )
+            .await
+            .with_context(|| {
+                format!(
+                    "Failed to look up Cloud SQL instance {}",
+                    url.connection_name()
+                )
+            })?;
+            let info: InstanceInfo = serde_json::from_str(&output)
+                .context("gcloud returned an unexpected instance description")?;
+            let instance = info.resolve(&url)?;
+            tracing::info!(
+                "Cloud SQL instance {} is at {}",
+                url.connection_name(),
+                instance.address
+            );
+            INSTANCES
+                .lock()
+                .unwrap()
+                .get_or_insert_with(HashMap::new)
+                .insert(key, instance.clone());
+            instance
+        }
+    };
+    Ok(url.to_postgres_url(&instance.address))
+}
+
+/// The server CA of a resolved Cloud SQL instance at `host`, as PEM
+///
+/// Cloud SQL server certificates name the instance rather than its address,
+/// so connections verify the chain against this CA instead of the hostname.
+pub fn server_ca_for_host(host: &str) -> Option<String> {
+    INSTANCES
+        .lock()
+        .unwrap()
+        .as_ref()?
+        .values()
+        .find(|instance| instance.address == host)
+        .and_then(|instance| instance.server_ca.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cloudsql_url() {
+        assert_eq!(CloudSqlUrl::parse("postgresql://h/db").unwrap(), None);
+
+        let url = CloudSqlUrl::parse(
+            "cloudsql://replicator@example.iam:pw@my-project:us-central1:prod-pg/app?ip_type=private&application_name=x",
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(url.connection_name(), "my-project:us-central1:prod-pg");
+        assert_eq!(url.user.as_deref(), Some("replicator@example.iam"));
+        assert_eq!(url.password.as_deref(), Some("pw"));
+        assert_eq!(url.database, "app");
+        assert_eq!(url.ip_type, IpType::Private);
+        assert_eq!(
+            url.to_postgres_url("10.1.2.3"),
+            "postgresql://replicator%40example.iam:pw@10.1.2.3:5432/app?sslmode=require&application_name=x"
+        );
+
+        let url = CloudSqlUrl::parse("cloudsql://my-project:us-central1:prod-pg/app")
+            .unwrap()
+            .unwrap();
+        assert_eq!(url.user, None);
+        assert_eq!(url.ip_type, IpType::Public);
+        assert_eq!(
+            url.to_postgres_url("34.1.2.3"),
+            "postgresql://34.1.2.3:5432/app?sslmode=require"
+        );
+
+        assert!(CloudSqlUrl::parse("cloudsql://u@prod-pg/app").is_err());
+        assert!(CloudSqlUrl::parse("cloudsql://u@p:r:i").is_err());
+        assert!(CloudSqlUrl::parse("cloudsql://u@p:r:i/app?ip_type=psc").is_err());
+    }
+
+    #[test]
+    fn test_instance_info_resolves_address() {
+        let info: InstanceInfo = serde_json::from_str(
+            r#"{
+                "region": "us-central1",
+                "databaseVersion": "POSTGRES_16",
+                "ipAddresses": [
+                    {"type": "PRIMARY", "ipAddress": "34.1.2.3"},
+                    {"type": "OUTGOING", "ipAddress": "34.9.9.9"}
+                ],
+                "serverCaCert": {"cert": "-----BEGIN CERTIFICATE-----"}
+            }"#,
+        )
+        .unwrap();
+        let public = CloudSqlUrl::parse("cloudsql://u@p:us-central1:i/app")
+            .unwrap()
+            .unwrap();
+        let resolved = info.resolve(&public).unwrap();
+        assert_eq!(resolved.address, "34.1.2.3");
+        assert!(resolved.server_ca.is_some());
+
+        let private = CloudSqlUrl::parse("cloudsql://u@p:us-central1:i/app?ip_type=private")
+            .unwrap()
+            .unwrap();
+        assert!(info.resolve(&private).is_err());
+        let other_region = CloudSqlUrl::parse("cloudsql://u@p:europe-west1:i/app")
+            .unwrap()
+            .unwrap();
+        assert!(info.resolve(&other_region).is_err());
+    }
+}
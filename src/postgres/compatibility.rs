@@ -0,0 +1,477 @@
+// ABOUTME: Source/target compatibility report for extensions, types, collations, locales, and versions
+// ABOUTME: Finds objects the target cannot recreate and suggests how to fix each one
+
+use anyhow::{Context, Result};
+use tokio_postgres::Client;
+
+use super::extensions::{
+    get_available_extensions, get_installed_extensions, get_preloaded_libraries, requires_preload,
+    AvailableExtension,
+};
+
+/// How serious a compatibility finding is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// Restore will fail unless this is fixed
+    Error,
+    /// Restore may succeed but behavior can differ on the target
+    Warning,
+}
+
+/// Kind of database object a finding is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ObjectKind {
+    Extension,
+    Type,
+    Collation,
+    Locale,
+    /// An object using a feature newer than the target
+    Feature,
+    /// A parameter a function sets
+    Setting,
+    /// A name that is a keyword on the target
+    Keyword,
+}
+
+impl std::fmt::Display for ObjectKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ObjectKind::Extension => write!(f, "Extension"),
+            ObjectKind::Type => write!(f, "Type"),
+            ObjectKind::Collation => write!(f, "Collation"),
+            ObjectKind::Locale => write!(f, "Locale"),
+            ObjectKind::Feature => write!(f, "Feature"),
+            ObjectKind::Setting => write!(f, "Setting"),
+            ObjectKind::Keyword => write!(f, "Keyword"),
+        }
+    }
+}
+
+/// A single compatibility finding with a remediation hint
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompatibilityIssue {
+    pub severity: Severity,
+    pub kind: ObjectKind,
+    /// Source database the object was found in
+    pub database: String,
+    /// Object name (extension, qualified type, collation, or `encoding`)
+    pub object: String,
+    pub message: String,
+    pub remediation: String,
+}
+
+/// Compatibility findings for all checked source databases
+#[derive(Debug, Clone, Default)]
+pub struct CompatibilityReport {
+    pub databases_checked: Vec<String>,
+    pub issues: Vec<CompatibilityIssue>,
+}
+
+impl CompatibilityReport {
+    pub fn has_errors(&self) -> bool {
+        self.error_count() > 0
+    }
+
+    pub fn error_count(&self) -> usize {
+        self.issues
+            .iter()
+            .filter(|i| i.severity == Severity::Error)
+            .count()
+    }
+
+    pub fn warning_count(&self) -> usize {
+        self.issues
+            .iter()
+            .filter(|i| i.severity == Severity::Warning)
+            .count()
+    }
+
+    /// Print the report with remediation hints
+    pub fn print(&self) {
+        println!();
+        println!("Compatibility Report");
+        println!("{}", "═".repeat(61));
+        println!("Databases checked: {}", self.databases_checked.join(", "));
+        println!();
+
+        if self.issues.is_empty() {
+            println!("  ✓ No extension, type, collation, locale, or version issues found");
+        }
+
+        for issue in &self.issues {
+            let icon = match issue.severity {
+                Severity::Error => "✗",
+                Severity::Warning => "⚠",
+            };
+            println!(
+                "  {} [{}] {} ({}): {}",
+                icon, issue.database, issue.kind, issue.object, issue.message
+            );
+            println!("      Fix: {}", issue.remediation);
+        }
+
+        println!("{}", "═".repeat(61));
+        println!(
+            "{} error(s), {} warning(s)",
+            self.error_count(),
+            self.warning_count()
+        );
+        println!();
+    }
+}
+
+/// Installation hint for an extension missing on the target
+///
+/// # Examples
+///
+/// ```
+/// # use database_replicator::postgres::compatibility::extension_remediation;
+/// assert!(extension_remediation("postgis").contains("postgis"));
+/// assert!(extension_remediation("some_custom_ext").contains("some_custom_ext"));
+/// ```
+pub fn extension_remediation(extension: &str) -> String {
+    match extension {
+        "postgis" | "postgis_topology" | "postgis_raster" | "postgis_tiger_geocoder" => {
+            "Install PostGIS on the target (e.g. the postgresql-<version>-postgis-3 package) \
+             or choose a managed plan that includes postgis"
+                .to_string()
+        }
+        "timescaledb" => "Install TimescaleDB on the target and add 'timescaledb' to \
+                          shared_preload_libraries, then restart PostgreSQL"
+            .to_string(),
+        "vector" => "Install pgvector on the target (e.g. the postgresql-<version>-pgvector \
+                     package) or choose a managed plan that includes the 'vector' extension"
+            .to_string(),
+        "hstore" | "pg_trgm" | "citext" | "ltree" | "btree_gist" | "btree_gin" | "pgcrypto"
+        | "uuid-ossp" | "tablefunc" | "intarray" | "cube" | "earthdistance" | "fuzzystrmatch" => {
+            format!(
+                "'{}' ships with PostgreSQL contrib; install the postgresql-contrib package on the target",
+                extension
+            )
+        }
+        _ => format!(
+            "Install the '{}' extension on the target, or drop it from the source if unused",
+            extension
+        ),
+    }
+}
+
+/// Check one source database against the target
+///
+/// `target_available` and `target_preloaded` are cluster-wide and can be fetched
+/// once and reused for every source database.
+pub async fn check_database(
+    database: &str,
+    source_client: &Client,
+    target_client: &Client,
+    target_available: &[AvailableExtension],
+    target_preloaded: &[String],
+) -> Result<Vec<CompatibilityIssue>> {
+    let mut issues = Vec::new();
+    let issue =
+        |severity, kind, object: &str, message: String, remediation: String| CompatibilityIssue {
+            severity,
+            kind,
+            database: database.to_string(),
+            object: object.to_string(),
+            message,
+            remediation,
+        };
+
+    // Extensions
+    let source_extensions = get_installed_extensions(source_client)
+        .await
+        .with_context(|| format!("Failed to get extensions for database '{}'", database))?;
+    for ext in &source_extensions {
+        match target_available.iter().find(|e| e.name == ext.name) {
+            None => issues.push(issue(
+                Severity::Error,
+                ObjectKind::Extension,
+                &ext.name,
+                format!(
+                    "version {} is installed on source but not available on target",
+                    ext.version
+                ),
+                extension_remediation(&ext.name),
+            )),
+            Some(target) => {
+                if requires_preload(&ext.name) && !target_preloaded.iter().any(|l| l == &ext.name) {
+                    issues.push(issue(
+                        Severity::Error,
+                        ObjectKind::Extension,
+                        &ext.name,
+                        "requires shared_preload_libraries on target".to_string(),
+                        format!(
+                            "Add to postgresql.conf: shared_preload_libraries = '{}' and restart PostgreSQL",
+                            ext.name
+                        ),
+                    ));
+                }
+                if let Some(target_version) = &target.default_version {
+                    if major_version(&ext.version) != major_version(target_version) {
+                        issues.push(issue(
+                            Severity::Warning,
+                            ObjectKind::Extension,
+                            &ext.name,
+                            format!(
+                                "version mismatch: source={}, target default={}",
+                                ext.version, target_version
+                            ),
+                            format!(
+                                "Install version {} on the target or run ALTER EXTENSION {} UPDATE after init",
+                                ext.version, ext.name
+                            ),
+                        ));
+                    }
+                }
+            }
+        }
+    }
+
+    // Base types not owned by an extension need a C library the target won't have
+    let rows = source_client
+        .query(
+            "SELECT n.nspname || '.' || t.typname
+             FROM pg_type t
+             JOIN pg_namespace n ON n.oid = t.typnamespace
+             WHERE t.typtype = 'b'
+               AND n.nspname NOT IN ('pg_catalog', 'information_schema')
+               AND n.nspname NOT LIKE 'pg_toast%'
+               AND NOT EXISTS (
+                   SELECT 1 FROM pg_depend d
+                   WHERE d.objid = t.oid AND d.classid = 'pg_type'::regclass AND d.deptype = 'e'
+               )
+               AND t.typelem = 0
+             ORDER BY 1",
+            &[],
+        )
+        .await
+        .with_context(|| format!("Failed to list custom types for database '{}'", database))?;
+    for row in &rows {
+        let name: String = row.get(0);
+        issues.push(issue(
+            Severity::Warning,
+            ObjectKind::Type,
+            &name,
+            "base type is defined outside an extension and depends on C functions".to_string(),
+            "Install the shared library that implements this type on the target, \
+             or package it as an extension"
+                .to_string(),
+        ));
+    }
+
+    // Collations used by user columns
+    let rows = source_client
+        .query(
+            "SELECT DISTINCT c.collname, cn.nspname, c.collprovider::text,
+                    COALESCE(c.collcollate::text, '')
+             FROM pg_attribute a
+             JOIN pg_class r ON r.oid = a.attrelid
+             JOIN pg_namespace rn ON rn.oid = r.relnamespace
+             JOIN pg_collation c ON c.oid = a.attcollation
+             JOIN pg_namespace cn ON cn.oid = c.collnamespace
+             WHERE a.attnum > 0 AND NOT a.attisdropped
+               AND rn.nspname NOT IN ('pg_catalog', 'information_schema')
+               AND rn.nspname NOT LIKE 'pg_toast%'
+               AND c.collname NOT IN ('default', 'C', 'POSIX', 'ucs_basic')
+             ORDER BY 1",
+            &[],
+        )
+        .await
+        .with_context(|| format!("Failed to list collations for database '{}'", database))?;
+    for row in &rows {
+        let name: String = row.get(0);
+        let namespace: String = row.get(1);
+        let provider: String = row.get(2);
+        let locale: String = row.get(3);
+
+        if namespace == "pg_catalog" {
+            // Built-in collations must exist on the target under the same name
+            let exists = target_client
+                .query_opt(
+                    "SELECT 1 FROM pg_collation WHERE collname = $1 LIMIT 1",
+                    &[&name],
+                )
+                .await
+                .context("Failed to query target collations")?
+                .is_some();
+            if !exists {
+                issues.push(issue(
+                    Severity::Error,
+                    ObjectKind::Collation,
+                    &name,
+                    "is used by source columns but does not exist on target".to_string(),
+                    collation_remediation(&provider),
+                ));
+            }
+        } else if provider == "i" {
+            // User-defined ICU collations are recreated by restore if the target has ICU
+            let has_icu = target_client
+                .query_opt(
+                    "SELECT 1 FROM pg_collation WHERE collprovider = 'i' LIMIT 1",
+                    &[],
+                )
+                .await
+                .context("Failed to query target collations")?
+                .is_some();
+            if !has_icu {
+                issues.push(issue(
+                    Severity::Error,
+                    ObjectKind::Collation,
+                    &format!("{}.{}", namespace, name),
+                    "is an ICU collation but the target was built without ICU".to_string(),
+                    collation_remediation(&provider),
+                ));
+            }
+        } else if !locale.is_empty() {
+            let exists = target_client
+                .query_opt(
+                    "SELECT 1 FROM pg_collation WHERE collcollate = $1 LIMIT 1",
+                    &[&locale],
+                )
+                .await
+                .context("Failed to query target collations")?
+                .is_some();
+            if !exists {
+                issues.push(issue(
+                    Severity::Warning,
+                    ObjectKind::Collation,
+                    &format!("{}.{}", namespace, name),
+                    format!(
+                        "uses OS locale '{}', which the target does not list",
+                        locale
+                    ),
+                    collation_remediation(&provider),
+                ));
+            }
+        }
+    }
+
+    // Encoding, default collation, and collation library versions
+    for mismatch in super::locale::locale_mismatches(database, source_client, target_client).await?
+    {
+        let (object, remediation) = match &mismatch.collation {
+            None => (
+                "encoding".to_string(),
+                "Create the target database with the source's ENCODING (from template0) \
+                 before running init"
+                    .to_string(),
+            ),
+            Some(collation) => (
+                collation.to_string(),
+                "Run init with --reindex-after-restore to rebuild indexes on affected columns, \
+                 or create the target database with the source's locale"
+                    .to_string(),
+            ),
+        };
+        issues.push(issue(
+            Severity::Warning,
+            ObjectKind::Locale,
+            &object,
+            mismatch.message,
+            remediation,
+        ));
+    }
+
+    Ok(issues)
+}
+
+/// Build a compatibility report for the given source databases
+///
+/// `source_url_for` maps a database name to a connection string for it.
+/// `versions` are the server and client tool versions the copy runs with.
+pub async fn build_report(
+    databases: &[String],
+    source_url_for: impl Fn(&str) -> Result<String>,
+    target_client: &Client,
+    versions: &super::versions::VersionMatrix,
+) -> Result<CompatibilityReport> {
+    let target_available = get_available_extensions(target_client)
+        .await
+        .context("Failed to get target available extensions")?;
+    let target_preloaded = get_preloaded_libraries(target_client)
+        .await
+        .context("Failed to get target preloaded libraries")?;
+    let target_catalog = super::versions::TargetCatalog::load(target_client).await?;
+
+    let mut report = CompatibilityReport::default();
+    for database in databases {
+        let url = source_url_for(database)?;
+        let source_client = super::connect(&url)
+            .await
+            .with_context(|| format!("Failed to connect to source database '{}'", database))?;
+        let issues = check_database(
+            database,
+            &source_client,
+            target_client,
+            &target_available,
+            &target_preloaded,
+        )
+        .await?;
+        report.databases_checked.push(database.clone());
+        report.issues.extend(issues);
+        report.issues.extend(
+            super::versions::check_database(database, &source_client, versions, &target_catalog)
+                .await?,
+        );
+    }
+    Ok(report)
+}
+
+fn major_version(version: &str) -> &str {
+    version.split('.').next().unwrap_or("0")
+}
+
+fn collation_remediation(provider: &str) -> String {
+    if provider == "i" {
+        "Use a target built with ICU support (most PostgreSQL packages and managed services are)"
+            .to_string()
+    } else {
+        "Generate the locale on the target host (e.g. locale-gen) and run \
+         pg_import_system_collations, or change the column collation on the source"
+            .to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_issue(severity: Severity) -> CompatibilityIssue {
+        CompatibilityIssue {
+            severity,
+            kind: ObjectKind::Extension,
+            database: "app".to_string(),
+            object: "postgis".to_string(),
+            message: "missing".to_string(),
+            remediation: extension_remediation("postgis"),
+        }
+    }
+
+    #[test]
+    fn test_report_counts() {
+        let mut report = CompatibilityReport::default();
+        assert!(!report.has_errors());
+
+        report.issues.push(sample_issue(Severity::Warning));
+        assert!(!report.has_errors());
+        assert_eq!(report.warning_count(), 1);
+
+        report.issues.push(sample_issue(Severity::Error));
+        assert!(report.has_errors());
+        assert_eq!(report.error_count(), 1);
+    }
+
+    #[test]
+    fn test_extension_remediation_hints() {
+        assert!(extension_remediation("timescaledb").contains("shared_preload_libraries"));
+        assert!(extension_remediation("vector").contains("pgvector"));
+        assert!(extension_remediation("hstore").contains("contrib"));
+    }
+
+    #[test]
+    fn test_major_version() {
+        assert_eq!(major_version("3.4.2"), "3");
+        assert_eq!(major_version("1.5"), "1");
+    }
+}
@@ -79,6 +79,59 @@ pub async fn get_preloaded_libraries(client: &Client) -> Result<Vec<String>> {
     Ok(libraries)
 }
 
+/// Get the installed version of one extension, or None if it is not installed
+pub async fn installed_extension_version(client: &Client, name: &str) -> Result<Option<String>> {
+    let row = client
+        .query_opt(
+            "SELECT extversion FROM pg_extension WHERE extname = $1",
+            &[&name],
+        )
+        .await
+        .with_context(|| format!("Failed to check for extension {}", name))?;
+    Ok(row.map(|row| row.get(0)))
+}
+
+/// Create an extension on the target when the source has it installed
+///
+/// Returns the target's version when the source uses the extension, or None
+/// when there is nothing to do. Column types from extensions such as PostGIS
+/// and pgvector need this before any table using them can be restored.
+pub async fn mirror_extension(
+    source: &Client,
+    target: &Client,
+    name: &str,
+) -> Result<Option<String>> {
+    let Some(source_version) = installed_extension_version(source, name).await? else {
+        return Ok(None);
+    };
+
+    if let Some(target_version) = installed_extension_version(target, name).await? {
+        return Ok(Some(target_version));
+    }
+
+    tracing::info!(
+        "Source uses {} {}, creating extension on target",
+        name,
+        source_version
+    );
+    target
+        .batch_execute(&format!(
+            "CREATE EXTENSION IF NOT EXISTS {}",
+            crate::utils::quote_ident(name)
+        ))
+        .await
+        .with_context(|| {
+            format!(
+                "Failed to create extension {} on the target (source has version {}).\n\
+                 Install the extension packages on the target server or ask your provider \
+                 to enable it, then retry.",
+                name, source_version
+            )
+        })?;
+
+    installed_extension_version(target, name).await
+}
+
 /// Extensions that require preloading via shared_preload_libraries
 const PRELOAD_REQUIRED_EXTENSIONS: &[&str] = &[
     "timescaledb",
@@ -0,0 +1,634 @@
+// ABOUTME: Works out the privileges a planned replication needs on source and target
+// ABOUTME: Probes them without side effects and writes GRANT statements for the missing ones
+
+use crate::filters::ReplicationFilter;
+use crate::utils::{quote_ident, replace_database_in_connection_string};
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use std::collections::BTreeSet;
+use std::fmt;
+use tokio_postgres::Client;
+
+/// How the data will be replicated, which decides the privileges needed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ReplicationMode {
+    /// One-time copy of schema and data (init --no-sync)
+    Snapshot,
+    /// Copy followed by logical replication through a publication and subscription
+    Logical,
+    /// Copy followed by xmin-based polling of the source
+    Xmin,
+}
+
+impl ReplicationMode {
+    /// The mode sync picks for a source with this `wal_level`
+    pub fn for_wal_level(wal_level: &str) -> Self {
+        if wal_level == "logical" {
+            ReplicationMode::Logical
+        } else {
+            ReplicationMode::Xmin
+        }
+    }
+}
+
+impl fmt::Display for ReplicationMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ReplicationMode::Snapshot => "snapshot",
+            ReplicationMode::Logical => "logical",
+            ReplicationMode::Xmin => "xmin",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Source,
+    Target,
+}
+
+impl fmt::Display for Side {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Side::Source => "source",
+            Side::Target => "target",
+        })
+    }
+}
+
+/// A privilege one step of the replication relies on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Privilege {
+    /// `REPLICATION` role attribute, for publications and replication slots
+    Replication,
+    /// Membership in AWS RDS's `rds_replication`, which stands in for `REPLICATION`
+    RdsReplication,
+    /// `CREATEDB` role attribute, for creating the target databases
+    CreateDb,
+    /// Before PostgreSQL 16 only superusers can create subscriptions
+    Superuser,
+    /// Membership in `pg_create_subscription` (PostgreSQL 16+)
+    CreateSubscription,
+    /// `CREATE` on a database, for schemas, publications, and subscriptions
+    DatabaseCreate {
+        database: String,
+    },
+    SchemaUsage {
+        schema: String,
+    },
+    SchemaCreate {
+        schema: String,
+    },
+    TableSelect {
+        schema: String,
+        table: String,
+    },
+    /// Only a table's owner can add it to a publication
+    TableOwner {
+        schema: String,
+        table: String,
+        owner: String,
+    },
+}
+
+impl Privilege {
+    pub fn describe(&self) -> String {
+        match self {
+            Privilege::Replication => "REPLICATION".to_string(),
+            Privilege::RdsReplication => "membership in rds_replication".to_string(),
+            Privilege::CreateDb => "CREATEDB".to_string(),
+            Privilege::Superuser => "SUPERUSER (to create subscriptions)".to_string(),
+            Privilege::CreateSubscription => "membership in pg_create_subscription".to_string(),
+            Privilege::DatabaseCreate { database } => format!("CREATE on database {}", database),
+            Privilege::SchemaUsage { schema } => format!("USAGE on schema {}", schema),
+            Privilege::SchemaCreate { schema } => format!("CREATE on schema {}", schema),
+            Privilege::TableSelect { schema, table } => {
+                format!("SELECT on {}.{}", schema, table)
+            }
+            Privilege::TableOwner {
+                schema,
+                table,
+                owner,
+            } => format!("ownership of {}.{} (owned by {})", schema, table, owner),
+        }
+    }
+
+    /// Statement that gives `user` this privilege
+    pub fn grant_statement(&self, user: &str) -> String {
+        let user = quote_ident(user);
+        match self {
+            Privilege::Replication => format!("ALTER ROLE {} WITH REPLICATION;", user),
+            Privilege::RdsReplication => format!("GRANT rds_replication TO {};", user),
+            Privilege::CreateDb => format!("ALTER ROLE {} WITH CREATEDB;", user),
+            Privilege::Superuser => format!("ALTER ROLE {} WITH SUPERUSER;", user),
+            Privilege::CreateSubscription => format!("GRANT pg_create_subscription TO {};", user),
+            Privilege::DatabaseCreate { database } => {
+                format!(
+                    "GRANT CREATE ON DATABASE {} TO {};",
+                    quote_ident(database),
+                    user
+                )
+            }
+            Privilege::SchemaUsage { schema } => {
+                format!("GRANT USAGE ON SCHEMA {} TO {};", quote_ident(schema), user)
+            }
+            Privilege::SchemaCreate { schema } => {
+                format!(
+                    "GRANT CREATE ON SCHEMA {} TO {};",
+                    quote_ident(schema),
+                    user
+                )
+            }
+            Privilege::TableSelect { schema, table } => format!(
+                "GRANT SELECT ON TABLE {}.{} TO {};",
+                quote_ident(schema),
+                quote_ident(table),
+                user
+            ),
+            Privilege::TableOwner { owner, .. } => {
+                format!("GRANT {} TO {};", quote_ident(owner), user)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Requirement {
+    pub side: Side,
+    /// Database the grant must run in; `None` for role-wide privileges
+    pub database: Option<String>,
+    pub privilege: Privilege,
+    pub granted: bool,
+}
+
+/// Every privilege a planned replication needs, and whether the users have it
+#[derive(Debug, Clone)]
+pub struct GrantReport {
+    pub mode: ReplicationMode,
+    pub source_user: String,
+    pub target_user: String,
+    pub requirements: Vec<Requirement>,
+}
+
+impl GrantReport {
+    pub fn missing(&self) -> impl Iterator<Item = &Requirement> {
+        self.requirements.iter().filter(|r| !r.granted)
+    }
+
+    pub fn missing_count(&self) -> usize {
+        self.missing().count()
+    }
+
+    /// psql script that grants what `side`'s user is missing
+    ///
+    /// Role-wide statements come first; the rest follow a `\connect` to the
+    /// database they apply in. Empty when nothing is missing.
+    pub fn grant_script(&self, side: Side) -> String {
+        let user = match side {
+            Side::Source => &self.source_user,
+            Side::Target => &self.target_user,
+        };
+        let mut script = String::new();
+        let mut current_database = None;
+        let mut written = BTreeSet::new();
+        let mut missing: Vec<_> = self.missing().filter(|r| r.side == side).collect();
+        missing.sort_by_key(|r| r.database.is_some());
+        for requirement in missing {
+            if requirement.database != current_database {
+                if let Some(database) = &requirement.database {
+                    script.push_str(&format!("\\connect {}\n", quote_ident(database)));
+                }
+                current_database = requirement.database.clone();
+                written.clear();
+            }
+            let statement = requirement.privilege.grant_statement(user);
+            if written.insert(statement.clone()) {
+                script.push_str(&statement);
+                script.push('\n');
+            }
+        }
+        script
+    }
+
+    pub fn print(&self) {
+        println!();
+        println!("Privileges for {} replication", self.mode);
+        println!("{}", "═".repeat(61));
+        for side in [Side::Source, Side::Target] {
+            let (total, missing) = self
+                .requirements
+                .iter()
+                .filter(|r| r.side == side)
+                .fold((0, 0), |(total, missing), r| {
+                    (total + 1, missing + usize::from(!r.granted))
+                });
+            if missing == 0 {
+                println!("  ✓ {}: all {} required privilege(s) granted", side, total);
+                continue;
+            }
+            println!(
+                "  ✗ {}: missing {} of {} privilege(s)",
+                side, missing, total
+            );
+            for requirement in self.missing().filter(|r| r.side == side) {
+                match &requirement.database {
+                    Some(database) => {
+                        println!("      [{}] {}", database, requirement.privilege.describe())
+                    }
+                    None => println!("      {}", requirement.privilege.describe()),
+                }
+            }
+            println!(
+                "    Run as a superuser or the objects' owner on the {}:",
+                side
+            );
+            for line in self.grant_script(side).lines() {
+                println!("      {}", line);
+            }
+        }
+        println!("{}", "═".repeat(61));
+        println!();
+    }
+}
+
+/// Work out and probe the privileges `mode` needs for `databases`
+///
+/// The source user needs USAGE on each replicated schema and SELECT on each
+/// table the filter keeps; logical replication adds REPLICATION (or
+/// `rds_replication` on RDS), CREATE on the database for the publication, and
+/// ownership of the published tables. The target user needs CREATEDB, CREATE
+/// on databases and schemas that already exist, and for logical replication
+/// the right to create subscriptions. Probes only call `has_*_privilege` and
+/// `pg_has_role`, so they change nothing.
+pub async fn analyze(
+    source_url: &str,
+    target_url: &str,
+    databases: &[String],
+    filter: &ReplicationFilter,
+    mode: ReplicationMode,
+) -> Result<GrantReport> {
+    let source = crate::postgres::connect(source_url)
+        .await
+        .context("Failed to connect to source database")?;
+    let target = crate::postgres::connect(target_url)
+        .await
+        .context("Failed to connect to target database")?;
+    let source_role = RoleAttributes::load(&source).await?;
+    let target_role = RoleAttributes::load(&target).await?;
+    let mut requirements = Vec::new();
+
+    if mode == ReplicationMode::Logical {
+        let on_rds = source
+            .query_one(
+                "SELECT EXISTS (SELECT 1 FROM pg_roles WHERE rolname = 'rds_replication')",
+                &[],
+            )
+            .await
+            .context("Failed to look for the rds_replication role")?
+            .get::<_, bool>(0);
+        let privilege = if on_rds {
+            Privilege::RdsReplication
+        } else {
+            Privilege::Replication
+        };
+        requirements.push(Requirement {
+            side: Side::Source,
+            database: None,
+            privilege,
+            granted: source_role.can_replicate,
+        });
+    }
+
+    for database in databases {
+        let url = replace_database_in_connection_string(source_url, database)?;
+        let client = crate::postgres::connect(&url)
+            .await
+            .with_context(|| format!("Failed to connect to source database '{}'", database))?;
+        requirements.extend(source_requirements(&client, database, filter, mode).await?);
+    }
+
+    requirements.push(Requirement {
+        side: Side::Target,
+        database: None,
+        privilege: Privilege::CreateDb,
+        granted: target_role.has_create_db || target_role.is_superuser,
+    });
+    if mode == ReplicationMode::Logical {
+        let major = crate::postgres::versions::server_major(&target).await?;
+        let (privilege, granted) = if major >= 16 {
+            let member: bool = target
+                .query_one(
+                    "SELECT pg_has_role(current_user, 'pg_create_subscription', 'MEMBER')",
+                    &[],
+                )
+                .await
+                .context("Failed to check membership in pg_create_subscription")?
+                .get(0);
+            (Privilege::CreateSubscription, member)
+        } else {
+            (Privilege::Superuser, false)
+        };
+        requirements.push(Requirement {
+            side: Side::Target,
+            database: None,
+            privilege,
+            granted: granted || target_role.is_superuser,
+        });
+    }
+
+    let existing: Vec<String> = target
+        .query(
+            "SELECT datname FROM pg_database WHERE datname = ANY($1)",
+            &[&databases],
+        )
+        .await
+        .context("Failed to list databases on target")?
+        .iter()
+        .map(|row| row.get(0))
+        .collect();
+    for database in databases.iter().filter(|db| existing.contains(db)) {
+        let url = replace_database_in_connection_string(target_url, database)?;
+        let client = crate::postgres::connect(&url)
+            .await
+            .with_context(|| format!("Failed to connect to target database '{}'", database))?;
+        let schemas: BTreeSet<String> = requirements
+            .iter()
+            .filter(|r| r.database.as_deref() == Some(database.as_str()))
+            .filter_map(|r| match &r.privilege {
+                Privilege::SchemaUsage { schema } => Some(schema.clone()),
+                _ => None,
+            })
+            .collect();
+        requirements.extend(target_requirements(&client, database, &schemas).await?);
+    }
+
+    Ok(GrantReport {
+        mode,
+        source_user: source_role.name,
+        target_user: target_role.name,
+        requirements,
+    })
+}
+
+struct RoleAttributes {
+    name: String,
+    has_create_db: bool,
+    is_superuser: bool,
+    can_replicate: bool,
+}
+
+impl RoleAttributes {
+    async fn load(client: &Client) -> Result<Self> {
+        let privs = crate::postgres::check_source_privileges(client).await?;
+        let name: String = client
+            .query_one("SELECT current_user::text", &[])
+            .await
+            .context("Failed to query the current user")?
+            .get(0);
+        Ok(RoleAttributes {
+            name,
+            has_create_db: privs.has_create_db,
+            is_superuser: privs.is_superuser,
+            can_replicate: privs.can_replicate(),
+        })
+    }
+}
+
+async fn source_requirements(
+    client: &Client,
+    database: &str,
+    filter: &ReplicationFilter,
+    mode: ReplicationMode,
+) -> Result<Vec<Requirement>> {
+    let rows = client
+        .query(
+            "SELECT n.nspname::text, c.relname::text, pg_get_userbyid(c.relowner)::text,
+                    has_schema_privilege(n.oid, 'USAGE'),
+                    has_table_privilege(c.oid, 'SELECT'),
+                    pg_has_role(c.relowner, 'USAGE')
+             FROM pg_catalog.pg_class c
+             JOIN pg_catalog.pg_namespace n ON n.oid = c.relnamespace
+             WHERE c.relkind IN ('r', 'p')
+               AND n.nspname NOT IN ('pg_catalog', 'information_schema')
+               AND n.nspname NOT LIKE 'pg_toast%'
+               AND n.nspname NOT LIKE 'pg_temp%'
+             ORDER BY 1, 2",
+            &[],
+        )
+        .await
+        .with_context(|| format!("Failed to check table privileges in '{}'", database))?;
+
+    let requirement = |privilege, granted| Requirement {
+        side: Side::Source,
+        database: Some(database.to_string()),
+        privilege,
+        granted,
+    };
+    let mut requirements = Vec::new();
+    if mode == ReplicationMode::Logical {
+        let granted: bool = client
+            .query_one(
+                "SELECT has_database_privilege(current_database(), 'CREATE')",
+                &[],
+            )
+            .await
+            .with_context(|| format!("Failed to check CREATE on database '{}'", database))?
+            .get(0);
+        requirements.push(requirement(
+            Privilege::DatabaseCreate {
+                database: database.to_string(),
+            },
+            granted,
+        ));
+    }
+
+    let mut schemas = BTreeSet::new();
+    for row in rows {
+        let schema: String = row.get(0);
+        let table: String = row.get(1);
+        if !filter.should_replicate_table(database, &format!("{}.{}", schema, table)) {
+            continue;
+        }
+        if schemas.insert(schema.clone()) {
+            requirements.push(requirement(
+                Privilege::SchemaUsage {
+                    schema: schema.clone(),
+                },
+                row.get(3),
+            ));
+        }
+        requirements.push(requirement(
+            Privilege::TableSelect {
+                schema: schema.clone(),
+                table: table.clone(),
+            },
+            row.get(4),
+        ));
+        if mode == ReplicationMode::Logical {
+            requirements.push(requirement(
+                Privilege::TableOwner {
+                    schema,
+                    table,
+                    owner: row.get(2),
+                },
+                row.get(5),
+            ));
+        }
+    }
+    Ok(requirements)
+}
+
+/// Requirements in a target database that already exists, so init restores
+/// into it instead of creating it (and owning everything in it)
+async fn target_requirements(
+    client: &Client,
+    database: &str,
+    schemas: &BTreeSet<String>,
+) -> Result<Vec<Requirement>> {
+    let requirement = |privilege, granted| Requirement {
+        side: Side::Target,
+        database: Some(database.to_string()),
+        privilege,
+        granted,
+    };
+    let granted: bool = client
+        .query_one(
+            "SELECT has_database_privilege(current_database(), 'CREATE')",
+            &[],
+        )
+        .await
+        .with_context(|| format!("Failed to check CREATE on target database '{}'", database))?
+        .get(0);
+    let mut requirements = vec![requirement(
+        Privilege::DatabaseCreate {
+            database: database.to_string(),
+        },
+        granted,
+    )];
+
+    let names: Vec<&String> = schemas.iter().collect();
+    let rows = client
+        .query(
+            "SELECT nspname::text, has_schema_privilege(oid, 'CREATE')
+             FROM pg_catalog.pg_namespace
+             WHERE nspname = ANY($1)
+             ORDER BY 1",
+            &[&names],
+        )
+        .await
+        .with_context(|| format!("Failed to check schema privileges in target '{}'", database))?;
+    for row in rows {
+        requirements.push(requirement(
+            Privilege::SchemaCreate { schema: row.get(0) },
+            row.get(1),
+        ));
+    }
+    Ok(requirements)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn requirement(side: Side, database: Option<&str>, privilege: Privilege) -> Requirement {
+        Requirement {
+            side,
+            database: database.map(str::to_string),
+            privilege,
+            granted: false,
+        }
+    }
+
+    #[test]
+    fn test_grant_statement() {
+        let table = Privilege::TableSelect {
+            schema: "sales".to_string(),
+            table: "Orders".to_string(),
+        };
+        assert_eq!(
+            table.grant_statement("app"),
+            "GRANT SELECT ON TABLE \"sales\".\"Orders\" TO \"app\";"
+        );
+        assert_eq!(
+            Privilege::Replication.grant_statement("repl\"user"),
+            "ALTER ROLE \"repl\"\"user\" WITH REPLICATION;"
+        );
+        assert_eq!(
+            Privilege::TableOwner {
+                schema: "public".to_string(),
+                table: "users".to_string(),
+                owner: "owner".to_string(),
+            }
+            .grant_statement("app"),
+            "GRANT \"owner\" TO \"app\";"
+        );
+    }
+
+    #[test]
+    fn test_grant_script() {
+        let mut report = GrantReport {
+            mode: ReplicationMode::Logical,
+            source_user: "app".to_string(),
+            target_user: "writer".to_string(),
+            requirements: vec![
+                requirement(
+                    Side::Source,
+                    Some("shop"),
+                    Privilege::SchemaUsage {
+                        schema: "public".to_string(),
+                    },
+                ),
+                requirement(
+                    Side::Source,
+                    Some("shop"),
+                    Privilege::TableOwner {
+                        schema: "public".to_string(),
+                        table: "a".to_string(),
+                        owner: "owner".to_string(),
+                    },
+                ),
+                requirement(
+                    Side::Source,
+                    Some("shop"),
+                    Privilege::TableOwner {
+                        schema: "public".to_string(),
+                        table: "b".to_string(),
+                        owner: "owner".to_string(),
+                    },
+                ),
+                requirement(Side::Source, None, Privilege::Replication),
+                requirement(Side::Target, None, Privilege::CreateDb),
+            ],
+        };
+        report.requirements[0].granted = true;
+
+        assert_eq!(report.missing_count(), 4);
+        assert_eq!(
+            report.grant_script(Side::Source),
+            "ALTER ROLE \"app\" WITH REPLICATION;\n\
+             \\connect \"shop\"\n\
+             GRANT \"owner\" TO \"app\";\n"
+        );
+        assert_eq!(
+            report.grant_script(Side::Target),
+            "ALTER ROLE \"writer\" WITH CREATEDB;\n"
+        );
+
+        for r in &mut report.requirements {
+            r.granted = true;
+        }
+        assert_eq!(report.grant_script(Side::Source), "");
+    }
+
+    #[test]
+    fn test_mode_for_wal_level() {
+        assert_eq!(
+            ReplicationMode::for_wal_level("logical"),
+            ReplicationMode::Logical
+        );
+        assert_eq!(
+            ReplicationMode::for_wal_level("replica"),
+            ReplicationMode::Xmin
+        );
+    }
+}
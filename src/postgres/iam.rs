@@ -0,0 +1,245 @@
+// ABOUTME: IAM database authentication for AWS RDS/Aurora and Google Cloud SQL sources
+// ABOUTME: Generates short-lived auth tokens and substitutes them for the source password
+
+use crate::utils::PostgresUrlParts;
+use anyhow::{bail, Context, Result};
+use clap::ValueEnum;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// RDS auth tokens are valid for 15 minutes and Google access tokens for an
+/// hour; new connections get a fresh one well before either expires
+const TOKEN_REFRESH_AFTER: Duration = Duration::from_secs(10 * 60);
+
+/// How the tool authenticates to the source database
+#[derive(Debug, Clone, Copy, Default, ValueEnum, PartialEq, Eq)]
+pub enum SourceAuth {
+    /// Use the password in the connection string
+    #[default]
+    Password,
+    /// Generate RDS IAM auth tokens with the `aws` CLI
+    AwsIam,
+    /// Use Google access tokens from the `gcloud` CLI (Cloud SQL IAM auth)
+    GcpIam,
+}
+
+/// Where tokens for the IAM source come from
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TokenIssuer {
+    Aws { region: String },
+    Gcp,
+}
+
+/// The source that authenticates with IAM, set once at startup
+static IAM_SOURCE: OnceLock<IamSource> = OnceLock::new();
+
+struct IamSource {
+    host: String,
+    port: u16,
+    user: String,
+    issuer: TokenIssuer,
+    token: Mutex<Option<(String, Instant)>>,
+}
+
+impl IamSource {
+    fn matches(&self, host: &str, port: u16, user: Option<&str>) -> bool {
+        self.host == host && self.port == port && user == Some(self.user.as_str())
+    }
+
+    /// The cached token, or a new one if it is close to expiring
+    async fn token(&self) -> Result<String> {
+        let mut cached = self.token.lock().await;
+        if let Some((token, created)) = cached.as_ref() {
+            if created.elapsed() < TOKEN_REFRESH_AFTER {
+                return Ok(token.clone());
+            }
+        }
+        let token = match &self.issuer {
+            TokenIssuer::Aws { region } => {
+                let port = self.port.to_string();
+                crate::secrets::run_cli(
+                    "aws",
+                    &[
+                        "rds",
+                        "generate-db-auth-token",
+                        "--hostname",
+                        &self.host,
+                        "--port",
+                        &port,
+                        "--username",
+                        &self.user,
+                        "--region",
+                        region,
+                    ],
+                )
+                .await
+                .context("Failed to generate RDS IAM auth token")?
+            }
+            TokenIssuer::Gcp => crate::secrets::run_cli("gcloud", &["auth", "print-access-token"])
+                .await
+                .context("Failed to get a Google access token for Cloud SQL IAM auth")?,
+        }
+        .trim()
+        .to_string();
+        tracing::debug!(
+            "Generated IAM auth token for {}@{}:{}",
+            self.user,
+            self.host,
+            self.port
+        );
+        *cached = Some((token.clone(), Instant::now()));
+        Ok(token)
+    }
+}
+
+/// Authenticate connections to `source_url` with IAM tokens (call once at startup)
+///
+/// Applies to every later connection with the same host, port, and user, so
+/// URLs that only swap the database name are covered too. For `aws-iam` the
+/// region comes from `AWS_REGION`, `AWS_DEFAULT_REGION`, or the RDS endpoint
+/// hostname. `password` leaves connections unchanged.
+pub fn init(auth: SourceAuth, source_url: &str) -> Result<()> {
+    if auth == SourceAuth::Password {
+        return Ok(());
+    }
+    let parts = crate::utils::parse_postgres_url(source_url)
+        .context("IAM source authentication needs a PostgreSQL source URL")?;
+    let user = parts
+        .user
+        .clone()
+        .context("IAM source authentication needs a user in the source URL")?;
+    if parts.password.is_some() {
+        tracing::warn!("Ignoring the source password; connecting with IAM auth tokens");
+    }
+    let issuer = match auth {
+        SourceAuth::AwsIam => TokenIssuer::Aws {
+            region: std::env::var("AWS_REGION")
+                .or_else(|_| std::env::var("AWS_DEFAULT_REGION"))
+                .ok()
+                .filter(|region| !region.is_empty())
+                .or_else(|| region_from_host(&parts.host))
+                .context(
+                    "Could not determine the AWS region for IAM auth; set AWS_REGION or use the RDS endpoint hostname",
+                )?,
+        },
+        SourceAuth::GcpIam => TokenIssuer::Gcp,
+        SourceAuth::Password => return Ok(()),
+    };
+
+    let source = IamSource {
+        host: parts.host,
+        port: parts.port,
+        user,
+        issuer,
+        token: Mutex::new(None),
+    };
+    if IAM_SOURCE.set(source).is_err() {
+        bail!("IAM source authentication is already configured");
+    }
+    match auth {
+        SourceAuth::AwsIam => tracing::info!("Source authentication: AWS RDS IAM"),
+        _ => tracing::info!("Source authentication: Google Cloud SQL IAM"),
+    }
+    Ok(())
+}
+
+/// The region of an RDS endpoint such as `db.abc123.us-east-1.rds.amazonaws.com`
+fn region_from_host(host: &str) -> Option<String> {
+    let labels: Vec<&str> = host.split('.').collect();
+    labels
+        .windows(3)
+        .find(|window| window[1] == "rds" && window[2] == "amazonaws")
+        .map(|window| window[0].to_string())
+}
+
+/// Replace the password in a connection URL with an IAM token when it names
+/// the IAM source; other URLs are returned unchanged
+pub async fn apply_to_url(connection_string: &str) -> Result<String> {
+    let Some(source) = IAM_SOURCE.get() else {
+        return Ok(connection_string.to_string());
+    };
+    let Ok(mut url) = url::Url::parse(connection_string) else {
+        return Ok(connection_string.to_string());
+    };
+    let host = url.host_str().unwrap_or_default().to_lowercase();
+    let port = url.port().unwrap_or(5432);
+    let user = percent_encoding::percent_decode_str(url.username()).decode_utf8_lossy();
+    if !source.matches(
+        &host,
+        port,
+        Some(user.as_ref()).filter(|user| !user.is_empty()),
+    ) {
+        return Ok(connection_string.to_string());
+    }
+
+    let token = source.token().await?;
+    let encoded: String = url::form_urlencoded::byte_serialize(token.as_bytes()).collect();
+    url.set_password(Some(&encoded))
+        .map_err(|_| anyhow::anyhow!("Cannot set a password on the source URL"))?;
+    Ok(url.to_string())
+}
+
+/// Put an IAM token in parsed URL parts used for pg_dump and other subprocesses
+pub async fn apply_to_parts(parts: &mut PostgresUrlParts) -> Result<()> {
+    let Some(source) = IAM_SOURCE.get() else {
+        return Ok(());
+    };
+    if source.matches(&parts.host, parts.port, parts.user.as_deref()) {
+        parts.password = Some(source.token().await?);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_region_from_host() {
+        assert_eq!(
+            region_from_host("prod.abc123xyz.us-east-1.rds.amazonaws.com").as_deref(),
+            Some("us-east-1")
+        );
+        assert_eq!(
+            region_from_host("prod.cluster-abc123.eu-west-2.rds.amazonaws.com").as_deref(),
+            Some("eu-west-2")
+        );
+        assert_eq!(region_from_host("db.internal"), None);
+    }
+
+    #[test]
+    fn test_source_matches_host_port_and_user() {
+        let source = IamSource {
+            host: "prod.abc.us-east-1.rds.amazonaws.com".to_string(),
+            port: 5432,
+            user: "replicator".to_string(),
+            issuer: TokenIssuer::Gcp,
+            token: Mutex::new(None),
+        };
+        assert!(source.matches(
+            "prod.abc.us-east-1.rds.amazonaws.com",
+            5432,
+            Some("replicator")
+        ));
+        assert!(!source.matches(
+            "prod.abc.us-east-1.rds.amazonaws.com",
+            5433,
+            Some("replicator")
+        ));
+        assert!(!source.matches("prod.abc.us-east-1.rds.amazonaws.com", 5432, None));
+        assert!(!source.matches("target.example.com", 5432, Some("replicator")));
+    }
+
+    #[tokio::test]
+    async fn test_cached_token_is_reused() {
+        let source = IamSource {
+            host: "h".to_string(),
+            port: 5432,
+            user: "u".to_string(),
+            issuer: TokenIssuer::Gcp,
+            token: Mutex::new(Some(("cached-token".to_string(), Instant::now()))),
+        };
+        assert_eq!(source.token().await.unwrap(), "cached-token");
+    }
+}
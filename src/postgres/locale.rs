@@ -0,0 +1,418 @@
+// ABOUTME: Compares encoding, locale, and collation versions between source and target
+// ABOUTME: Finds and rebuilds target indexes whose ordering depends on a mismatched collation
+
+use anyhow::{Context, Result};
+use tokio_postgres::Client;
+
+/// Collation whose ordering differs between source and target
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CollationRef {
+    /// The database default collation (`lc_collate`/`lc_ctype` or ICU locale)
+    DatabaseDefault,
+    /// A collation named explicitly by a column
+    Named { schema: String, name: String },
+}
+
+impl std::fmt::Display for CollationRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CollationRef::DatabaseDefault => write!(f, "database default"),
+            CollationRef::Named { schema, name } => write!(f, "{}.{}", schema, name),
+        }
+    }
+}
+
+/// An encoding or collation difference between a source database and the target
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocaleMismatch {
+    /// Affected collation, or `None` for an encoding difference
+    pub collation: Option<CollationRef>,
+    pub message: String,
+}
+
+impl LocaleMismatch {
+    /// Whether indexes need rebuilding for this difference
+    pub fn affects_indexes(&self) -> bool {
+        self.collation.is_some()
+    }
+}
+
+/// Encoding and default collation of one database
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct DatabaseLocale {
+    encoding: String,
+    collate: String,
+    ctype: String,
+    /// `c` for libc, `i` for ICU, `b` for the builtin provider
+    provider: String,
+    icu_locale: Option<String>,
+}
+
+impl DatabaseLocale {
+    /// Locale the collation version is looked up by
+    fn version_locale(&self) -> &str {
+        match self.provider.as_str() {
+            "i" => self.icu_locale.as_deref().unwrap_or_default(),
+            _ => &self.collate,
+        }
+    }
+}
+
+// Falls back to template1, which CREATE DATABASE copies, when the target
+// database does not exist yet. Locale columns are read through to_jsonb so the
+// query works before and after they were added or renamed.
+const DATABASE_LOCALE_QUERY: &str = "
+SELECT pg_encoding_to_char(d.encoding)::text,
+       d.datcollate::text,
+       d.datctype::text,
+       COALESCE(to_jsonb(d)->>'datlocprovider', 'c'),
+       COALESCE(to_jsonb(d)->>'datlocale', to_jsonb(d)->>'daticulocale')
+FROM pg_database d
+WHERE d.datname IN ($1, 'template1')
+ORDER BY d.datname = $1 DESC
+LIMIT 1";
+
+// libc collations all share the C library's version, so any one will do
+const COLLATION_VERSION_QUERY: &str = "
+SELECT pg_collation_actual_version(c.oid)
+FROM pg_collation c
+WHERE c.collprovider::text = $1
+  AND ($1 = 'c'
+       OR COALESCE(to_jsonb(c)->>'colllocale', to_jsonb(c)->>'colliculocale',
+                   c.collcollate::text) = $2)
+  AND pg_collation_actual_version(c.oid) IS NOT NULL
+LIMIT 1";
+
+const COLUMN_COLLATIONS_QUERY: &str = "
+SELECT DISTINCT cn.nspname, c.collname, c.collprovider::text,
+       COALESCE(to_jsonb(c)->>'colllocale', to_jsonb(c)->>'colliculocale',
+                c.collcollate::text, '')
+FROM pg_attribute a
+JOIN pg_class r ON r.oid = a.attrelid
+JOIN pg_namespace rn ON rn.oid = r.relnamespace
+JOIN pg_collation c ON c.oid = a.attcollation
+JOIN pg_namespace cn ON cn.oid = c.collnamespace
+WHERE a.attnum > 0 AND NOT a.attisdropped
+  AND rn.nspname NOT IN ('pg_catalog', 'information_schema')
+  AND rn.nspname NOT LIKE 'pg_toast%'
+  AND c.collname NOT IN ('default', 'C', 'POSIX', 'ucs_basic')
+ORDER BY 1, 2";
+
+async fn database_locale(client: &Client, database: &str) -> Result<DatabaseLocale> {
+    let row = client
+        .query_one(DATABASE_LOCALE_QUERY, &[&database])
+        .await
+        .with_context(|| format!("Failed to read locale settings for database '{}'", database))?;
+    Ok(DatabaseLocale {
+        encoding: row.get(0),
+        collate: row.get(1),
+        ctype: row.get(2),
+        provider: row.get(3),
+        icu_locale: row.get(4),
+    })
+}
+
+/// Version of the collation library behind `provider` and `locale`, if it reports one
+async fn collation_version(
+    client: &Client,
+    provider: &str,
+    locale: &str,
+) -> Result<Option<String>> {
+    if !matches!(provider, "c" | "i") || locale.is_empty() || is_c_locale(locale) {
+        return Ok(None);
+    }
+    let row = client
+        .query_opt(COLLATION_VERSION_QUERY, &[&provider, &locale])
+        .await
+        .context("Failed to read collation version")?;
+    Ok(row.and_then(|r| r.get(0)))
+}
+
+fn is_c_locale(locale: &str) -> bool {
+    matches!(locale, "C" | "POSIX") || locale.starts_with("C.")
+}
+
+/// Differences between the source and target database defaults, not counting versions
+fn compare_defaults(source: &DatabaseLocale, target: &DatabaseLocale) -> Vec<LocaleMismatch> {
+    let mut mismatches = Vec::new();
+    if source.encoding != target.encoding {
+        mismatches.push(LocaleMismatch {
+            collation: None,
+            message: format!(
+                "encoding differs (source={}, target={}); characters the target encoding \
+                 cannot represent fail to restore",
+                source.encoding, target.encoding
+            ),
+        });
+    }
+
+    let mut differences = Vec::new();
+    if source.provider != target.provider {
+        differences.push(format!(
+            "provider: source={}, target={}",
+            provider_name(&source.provider),
+            provider_name(&target.provider)
+        ));
+    }
+    if source.collate != target.collate {
+        differences.push(format!(
+            "lc_collate: source={}, target={}",
+            source.collate, target.collate
+        ));
+    }
+    if source.ctype != target.ctype {
+        differences.push(format!(
+            "lc_ctype: source={}, target={}",
+            source.ctype, target.ctype
+        ));
+    }
+    if source.provider == "i" && target.provider == "i" && source.icu_locale != target.icu_locale {
+        differences.push(format!(
+            "ICU locale: source={}, target={}",
+            source.icu_locale.as_deref().unwrap_or_default(),
+            target.icu_locale.as_deref().unwrap_or_default()
+        ));
+    }
+    if !differences.is_empty() {
+        mismatches.push(LocaleMismatch {
+            collation: Some(CollationRef::DatabaseDefault),
+            message: format!(
+                "default collation differs ({}); text sorts and compares differently on the target",
+                differences.join("; ")
+            ),
+        });
+    }
+    mismatches
+}
+
+fn version_mismatch(collation: CollationRef, source: &str, target: &str) -> LocaleMismatch {
+    LocaleMismatch {
+        message: format!(
+            "{} collation version differs (source={}, target={}); sort order can differ and \
+             indexes built under the other version may be corrupt",
+            collation, source, target
+        ),
+        collation: Some(collation),
+    }
+}
+
+fn provider_name(provider: &str) -> &str {
+    match provider {
+        "c" => "libc",
+        "i" => "icu",
+        "b" => "builtin",
+        other => other,
+    }
+}
+
+/// Encoding, locale, and collation version differences for one source database
+///
+/// `target_client` may be connected to any database on the target. The target
+/// database's settings are used when it exists, and template1's otherwise, since
+/// init creates databases from it.
+pub async fn locale_mismatches(
+    database: &str,
+    source_client: &Client,
+    target_client: &Client,
+) -> Result<Vec<LocaleMismatch>> {
+    let source = database_locale(source_client, database).await?;
+    let target = database_locale(target_client, database).await?;
+
+    let mut mismatches = compare_defaults(&source, &target);
+    if !mismatches.iter().any(LocaleMismatch::affects_indexes) && source.provider == target.provider
+    {
+        let source_version =
+            collation_version(source_client, &source.provider, source.version_locale()).await?;
+        let target_version =
+            collation_version(target_client, &target.provider, target.version_locale()).await?;
+        if let (Some(source_version), Some(target_version)) = (source_version, target_version) {
+            if source_version != target_version {
+                mismatches.push(version_mismatch(
+                    CollationRef::DatabaseDefault,
+                    &source_version,
+                    &target_version,
+                ));
+            }
+        }
+    }
+
+    let rows = source_client
+        .query(COLUMN_COLLATIONS_QUERY, &[])
+        .await
+        .with_context(|| format!("Failed to list collations for database '{}'", database))?;
+    for row in &rows {
+        let schema: String = row.get(0);
+        let name: String = row.get(1);
+        let provider: String = row.get(2);
+        let locale: String = row.get(3);
+
+        // Collations missing on the target are reported by the compatibility check
+        let source_version = collation_version(source_client, &provider, &locale).await?;
+        let target_version = collation_version(target_client, &provider, &locale).await?;
+        if let (Some(source_version), Some(target_version)) = (source_version, target_version) {
+            if source_version != target_version {
+                mismatches.push(version_mismatch(
+                    CollationRef::Named { schema, name },
+                    &source_version,
+                    &target_version,
+                ));
+            }
+        }
+    }
+
+    Ok(mismatches)
+}
+
+/// Indexes on the target that sort by one of the mismatched collations
+///
+/// Returns quoted, schema-qualified index names.
+pub async fn affected_indexes(
+    client: &Client,
+    mismatches: &[LocaleMismatch],
+) -> Result<Vec<String>> {
+    let include_default = mismatches
+        .iter()
+        .any(|m| m.collation == Some(CollationRef::DatabaseDefault));
+    let named: Vec<String> = mismatches
+        .iter()
+        .filter_map(|m| match &m.collation {
+            Some(collation @ CollationRef::Named { .. }) => Some(collation.to_string()),
+            _ => None,
+        })
+        .collect();
+    if !include_default && named.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let rows = client
+        .query(
+            "SELECT DISTINCT format('%I.%I', n.nspname, ic.relname)
+             FROM pg_index i
+             JOIN pg_class ic ON ic.oid = i.indexrelid
+             JOIN pg_namespace n ON n.oid = ic.relnamespace
+             JOIN pg_collation c ON c.oid = ANY (i.indcollation::oid[])
+             JOIN pg_namespace cn ON cn.oid = c.collnamespace
+             WHERE n.nspname NOT IN ('pg_catalog', 'information_schema')
+               AND n.nspname NOT LIKE 'pg_toast%'
+               AND (($1 AND cn.nspname = 'pg_catalog' AND c.collname = 'default')
+                    OR cn.nspname || '.' || c.collname = ANY ($2))
+             ORDER BY 1",
+            &[&include_default, &named],
+        )
+        .await
+        .context("Failed to list indexes using mismatched collations")?;
+    Ok(rows.iter().map(|row| row.get(0)).collect())
+}
+
+/// Rebuild each index, logging failures instead of stopping
+///
+/// Returns how many indexes were rebuilt and how many failed.
+pub async fn reindex(client: &Client, indexes: &[String]) -> Result<(usize, usize)> {
+    let mut rebuilt = 0;
+    let mut failed = 0;
+    for index in indexes {
+        match client
+            .batch_execute(&format!("REINDEX INDEX {}", index))
+            .await
+        {
+            Ok(()) => {
+                tracing::debug!("Rebuilt index {}", index);
+                rebuilt += 1;
+            }
+            Err(e) => {
+                tracing::warn!(
+                    "  ⚠ Failed to rebuild index {}: {}",
+                    index,
+                    error_reason(&e)
+                );
+                failed += 1;
+            }
+        }
+    }
+    Ok((rebuilt, failed))
+}
+
+fn error_reason(e: &tokio_postgres::Error) -> String {
+    e.as_db_error()
+        .map(|db| db.message().to_string())
+        .unwrap_or_else(|| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn locale(encoding: &str, collate: &str, provider: &str) -> DatabaseLocale {
+        DatabaseLocale {
+            encoding: encoding.to_string(),
+            collate: collate.to_string(),
+            ctype: collate.to_string(),
+            provider: provider.to_string(),
+            icu_locale: (provider == "i").then(|| "en-US".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_matching_defaults() {
+        let source = locale("UTF8", "en_US.UTF-8", "c");
+        assert!(compare_defaults(&source, &source.clone()).is_empty());
+    }
+
+    #[test]
+    fn test_collate_and_encoding_differences() {
+        let source = locale("UTF8", "en_US.UTF-8", "c");
+        let target = locale("LATIN1", "C", "c");
+        let mismatches = compare_defaults(&source, &target);
+
+        assert_eq!(mismatches.len(), 2);
+        assert!(!mismatches[0].affects_indexes());
+        assert!(mismatches[0].message.contains("source=UTF8, target=LATIN1"));
+        assert_eq!(mismatches[1].collation, Some(CollationRef::DatabaseDefault));
+        assert!(mismatches[1]
+            .message
+            .contains("lc_collate: source=en_US.UTF-8, target=C"));
+        assert!(mismatches[1].message.contains("lc_ctype"));
+    }
+
+    #[test]
+    fn test_provider_and_icu_locale_differences() {
+        let source = locale("UTF8", "en_US.UTF-8", "i");
+        let target = locale("UTF8", "en_US.UTF-8", "c");
+        let mismatches = compare_defaults(&source, &target);
+        assert_eq!(mismatches.len(), 1);
+        assert!(mismatches[0]
+            .message
+            .contains("provider: source=icu, target=libc"));
+
+        let mut target = source.clone();
+        target.icu_locale = Some("de-DE".to_string());
+        let mismatches = compare_defaults(&source, &target);
+        assert!(mismatches[0]
+            .message
+            .contains("ICU locale: source=en-US, target=de-DE"));
+    }
+
+    #[test]
+    fn test_version_mismatch_message() {
+        let mismatch = version_mismatch(
+            CollationRef::Named {
+                schema: "public".to_string(),
+                name: "german".to_string(),
+            },
+            "153.112",
+            "153.14",
+        );
+        assert!(mismatch.affects_indexes());
+        assert!(mismatch.message.starts_with(
+            "public.german collation version differs (source=153.112, target=153.14)"
+        ));
+    }
+
+    #[test]
+    fn test_c_locales_have_no_version() {
+        assert!(is_c_locale("C"));
+        assert!(is_c_locale("C.UTF-8"));
+        assert!(is_c_locale("POSIX"));
+        assert!(!is_c_locale("en_US.UTF-8"));
+    }
+}
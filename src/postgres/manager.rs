@@ -0,0 +1,238 @@
+// ABOUTME: Long-lived PostgreSQL connections that reconnect after they drop
+// ABOUTME: Used by sync loops to re-establish sessions and replay the in-flight batch
+
+use anyhow::{Context, Result};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio_postgres::Client;
+
+/// How a [`ConnectionManager`] retries after a dropped connection
+#[derive(Debug, Clone)]
+pub struct ReconnectPolicy {
+    /// Connection attempts per reconnect before giving up
+    pub max_attempts: u32,
+    /// Delay before the second attempt; doubles after each failure
+    pub initial_delay: Duration,
+    /// Upper bound for the delay between attempts
+    pub max_delay: Duration,
+    /// Times one operation is replayed on a new connection before its error is returned
+    pub max_replays: u32,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 8,
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(60),
+            max_replays: 3,
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// Delay after the `attempt`th failed connection attempt (1-based)
+    pub fn delay(&self, attempt: u32) -> Duration {
+        self.initial_delay
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+            .min(self.max_delay)
+    }
+}
+
+/// A PostgreSQL connection that is re-established when it drops
+///
+/// Callers take a fresh [`client`](Self::client) for each unit of work. When
+/// that work fails, [`recover`](Self::recover) decides whether the error was a
+/// lost connection and, if so, reconnects so the caller can replay it. Work
+/// should be idempotent or transactional, since a batch that failed mid-commit
+/// may or may not have been applied.
+pub struct ConnectionManager {
+    url: String,
+    /// Names the connection in logs, e.g. "source"
+    role: &'static str,
+    client: RwLock<Arc<Client>>,
+    policy: ReconnectPolicy,
+}
+
+impl ConnectionManager {
+    /// Connect to `url` with the default [`ReconnectPolicy`]
+    pub async fn connect(url: &str, role: &'static str) -> Result<Self> {
+        let client = super::connect_with_retry(url)
+            .await
+            .with_context(|| format!("Failed to connect to {} database", role))?;
+        Ok(Self {
+            url: url.to_string(),
+            role,
+            client: RwLock::new(Arc::new(client)),
+            policy: ReconnectPolicy::default(),
+        })
+    }
+
+    pub fn with_policy(mut self, policy: ReconnectPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// The current client; hold it only for one unit of work
+    pub fn client(&self) -> Arc<Client> {
+        self.client.read().unwrap().clone()
+    }
+
+    /// Reconnect first if the connection has closed since the last use
+    pub async fn ensure_connected(&self) -> Result<()> {
+        if self.client().is_closed() {
+            tracing::warn!("{} connection closed; reconnecting", self.role);
+            self.reconnect().await?;
+        }
+        Ok(())
+    }
+
+    /// Handle an error from work done with [`client`](Self::client)
+    ///
+    /// Returns `Ok` after reconnecting when `err` was a lost connection and the
+    /// work may be replayed; `replays` counts replays of the same work and is
+    /// incremented here. Any other error, or one that keeps recurring past
+    /// `max_replays`, is returned unchanged.
+    pub async fn recover(&self, err: anyhow::Error, replays: &mut u32) -> Result<()> {
+        if !is_connection_error(&err) || *replays >= self.policy.max_replays {
+            return Err(err);
+        }
+        *replays += 1;
+        tracing::warn!(
+            "Lost {} connection ({:#}); reconnecting to replay the batch ({}/{})",
+            self.role,
+            err,
+            replays,
+            self.policy.max_replays
+        );
+        self.reconnect().await
+    }
+
+    /// Open a new connection, backing off exponentially between attempts
+    pub async fn reconnect(&self) -> Result<()> {
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            match super::connect(&self.url).await {
+                Ok(client) => {
+                    *self.client.write().unwrap() = Arc::new(client);
+                    tracing::info!("Reconnected to {} database", self.role);
+                    return Ok(());
+                }
+                Err(e) if attempt >= self.policy.max_attempts => {
+                    return Err(e).with_context(|| {
+                        format!(
+                            "Failed to reconnect to {} database after {} attempts",
+                            self.role, attempt
+                        )
+                    });
+                }
+                Err(e) => {
+                    let delay = self.policy.delay(attempt);
+                    tracing::warn!(
+                        "Reconnect to {} database failed (attempt {}/{}), retrying in {:?}: {:#}",
+                        self.role,
+                        attempt,
+                        self.policy.max_attempts,
+                        delay,
+                        e
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+            }
+        }
+    }
+}
+
+/// Whether an error means the connection itself was lost
+///
+/// Covers closed connections, socket errors, and server errors in SQLSTATE
+/// class 08 (connection exception) or 57P01-57P03 (server shutting down or
+/// restarting). Query errors such as constraint violations are not.
+pub fn is_connection_error(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| {
+        if cause.is::<std::io::Error>() {
+            return true;
+        }
+        let Some(pg) = cause.downcast_ref::<tokio_postgres::Error>() else {
+            return false;
+        };
+        if pg.is_closed() {
+            return true;
+        }
+        match pg.as_db_error() {
+            Some(db) => {
+                let code = db.code().code();
+                code.starts_with("08") || matches!(code, "57P01" | "57P02" | "57P03")
+            }
+            None => {
+                std::error::Error::source(pg).is_some_and(|source| source.is::<std::io::Error>())
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reconnect_delay_backs_off_to_max() {
+        let policy = ReconnectPolicy::default();
+        assert_eq!(policy.delay(1), Duration::from_secs(1));
+        assert_eq!(policy.delay(2), Duration::from_secs(2));
+        assert_eq!(policy.delay(4), Duration::from_secs(8));
+        assert_eq!(policy.delay(7), Duration::from_secs(60));
+        assert_eq!(policy.delay(40), Duration::from_secs(60));
+    }
+
+    #[test]
+    fn test_is_connection_error() {
+        let reset = anyhow::Error::new(std::io::Error::new(
+            std::io::ErrorKind::ConnectionReset,
+            "connection reset by peer",
+        ))
+        .context("Failed to apply batch");
+        assert!(is_connection_error(&reset));
+
+        assert!(!is_connection_error(&anyhow::anyhow!(
+            "duplicate key value violates unique constraint"
+        )));
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_recover_reconnects_after_terminated_backend() {
+        let url = std::env::var("TEST_TARGET_URL").unwrap();
+        let manager = ConnectionManager::connect(&url, "target").await.unwrap();
+        let pid: i32 = manager
+            .client()
+            .query_one("SELECT pg_backend_pid()", &[])
+            .await
+            .unwrap()
+            .get(0);
+
+        let killer = crate::postgres::connect(&url).await.unwrap();
+        killer
+            .execute("SELECT pg_terminate_backend($1)", &[&pid])
+            .await
+            .unwrap();
+
+        let mut replays = 0;
+        let err = manager
+            .client()
+            .query_one("SELECT 1", &[])
+            .await
+            .map_err(anyhow::Error::from)
+            .unwrap_err();
+        manager.recover(err, &mut replays).await.unwrap();
+        assert_eq!(replays, 1);
+        let one: i32 = manager
+            .client()
+            .query_one("SELECT 1", &[])
+            .await
+            .unwrap()
+            .get(0);
+        assert_eq!(one, 1);
+    }
+}
@@ -1,15 +1,30 @@
 // ABOUTME: PostgreSQL utilities module
 // ABOUTME: Exports connection management and common database operations
 
+pub mod cloudsql;
+pub mod compatibility;
 pub mod connection;
 pub mod extensions;
+pub mod grants;
+pub mod iam;
+pub mod locale;
+pub mod manager;
+pub mod pgvector;
+pub mod pool;
+pub mod pooler;
+pub mod postgis;
 pub mod privileges;
+pub mod replica;
+pub mod snapshot;
+pub mod versions;
 
 pub use connection::{add_keepalive_params, connect, connect_with_retry};
 pub use extensions::{
     get_available_extensions, get_installed_extensions, get_preloaded_libraries, requires_preload,
     AvailableExtension, Extension,
 };
+pub use manager::{is_connection_error, ConnectionManager, ReconnectPolicy};
+pub use pool::{PgPool, PooledClient, PooledConnection};
 pub use privileges::{
     check_source_privileges, check_table_select_permissions, check_target_privileges,
     check_wal_level, PrivilegeCheck, TablePermissionCheck,
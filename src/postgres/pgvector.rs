@@ -0,0 +1,193 @@
+// ABOUTME: pgvector detection, embedding column handling, and ANN index deferral
+// ABOUTME: Moves vectors as text and rebuilds ivfflat/hnsw indexes after bulk loads
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use tokio_postgres::types::Type;
+use tokio_postgres::Client;
+
+use super::extensions::mirror_extension;
+use super::pooler;
+use crate::utils::quote_ident;
+
+/// Column types provided by the pgvector extension
+pub const VECTOR_TYPES: &[&str] = &["vector", "halfvec", "sparsevec"];
+
+/// Index access methods that build approximate nearest neighbor indexes
+pub const VECTOR_INDEX_METHODS: &[&str] = &["ivfflat", "hnsw"];
+
+/// Check whether an `information_schema.columns.udt_name` is a pgvector type
+pub fn is_vector_udt(udt_name: &str) -> bool {
+    VECTOR_TYPES.contains(&udt_name)
+}
+
+/// Find the pgvector columns in a `(column_name, udt_name)` list
+pub fn vector_columns(columns: &[(String, String)]) -> Vec<(String, String)> {
+    columns
+        .iter()
+        .filter(|(_, udt)| is_vector_udt(udt))
+        .cloned()
+        .collect()
+}
+
+/// Build the SELECT expression for a vector column
+///
+/// The driver has no decoder for pgvector types, so embeddings are read in
+/// their text form (`[0.1,0.2,0.3]`), which round-trips without precision loss.
+pub fn select_expression(column: &str) -> String {
+    let quoted = quote_ident(column);
+    format!("{}::text AS {}", quoted, quoted)
+}
+
+/// Wrap a text parameter placeholder so it is stored as the given vector type
+///
+/// # Examples
+///
+/// ```
+/// # use database_replicator::postgres::pgvector::insert_expression;
+/// assert_eq!(insert_expression("$3", "vector"), "$3::text::vector");
+/// assert_eq!(insert_expression("$3", "halfvec"), "$3::text::halfvec");
+/// ```
+pub fn insert_expression(placeholder: &str, type_name: &str) -> String {
+    format!("{}::text::{}", placeholder, type_name)
+}
+
+/// Create pgvector on the target when the source uses it
+///
+/// Returns the target's pgvector version when the source has it installed,
+/// or None when there is nothing to do.
+pub async fn ensure_pgvector(source: &Client, target: &Client) -> Result<Option<String>> {
+    mirror_extension(source, target, "vector").await
+}
+
+/// An ivfflat or hnsw index, kept so it can be rebuilt after a bulk load
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VectorIndex {
+    pub schema: String,
+    pub name: String,
+    pub method: String,
+    /// Full `CREATE INDEX` statement from `pg_get_indexdef`
+    pub definition: String,
+}
+
+/// List the ivfflat/hnsw indexes in a database
+pub async fn list_vector_indexes(client: &Client) -> Result<Vec<VectorIndex>> {
+    let methods: Vec<&str> = VECTOR_INDEX_METHODS.to_vec();
+    let rows = pooler::query(
+        client,
+        "SELECT n.nspname, c.relname, am.amname, pg_get_indexdef(c.oid)
+         FROM pg_class c
+         JOIN pg_namespace n ON n.oid = c.relnamespace
+         JOIN pg_am am ON am.oid = c.relam
+         WHERE c.relkind = 'i' AND am.amname = ANY($1)
+         ORDER BY n.nspname, c.relname",
+        &[(&methods, Type::TEXT_ARRAY)],
+    )
+    .await
+    .context("Failed to list vector indexes")?;
+
+    Ok(rows
+        .iter()
+        .map(|row| VectorIndex {
+            schema: row.get(0),
+            name: row.get(1),
+            method: row.get(2),
+            definition: row.get(3),
+        })
+        .collect())
+}
+
+/// Drop the ivfflat/hnsw indexes in a database before a bulk load
+///
+/// Building these indexes row by row during a load is far slower than building
+/// them once afterwards, and ivfflat picks its list centroids from the rows that
+/// exist at build time. Returns the dropped indexes for [`recreate_vector_indexes`].
+pub async fn defer_vector_indexes(client: &Client) -> Result<Vec<VectorIndex>> {
+    let indexes = list_vector_indexes(client).await?;
+    for index in &indexes {
+        client
+            .batch_execute(&format!(
+                "DROP INDEX IF EXISTS {}.{}",
+                quote_ident(&index.schema),
+                quote_ident(&index.name)
+            ))
+            .await
+            .with_context(|| {
+                format!(
+                    "Failed to drop {} index {}.{} before bulk load",
+                    index.method, index.schema, index.name
+                )
+            })?;
+        tracing::debug!("Deferred {} index: {}", index.method, index.definition);
+    }
+    Ok(indexes)
+}
+
+/// Rebuild indexes dropped by [`defer_vector_indexes`]
+///
+/// Every index is attempted even if an earlier one fails; the error lists the
+/// statements that still need to be run by hand.
+pub async fn recreate_vector_indexes(client: &Client, indexes: &[VectorIndex]) -> Result<()> {
+    let mut failed = Vec::new();
+    for index in indexes {
+        let start = std::time::Instant::now();
+        match client.batch_execute(&index.definition).await {
+            Ok(()) => tracing::info!(
+                "  Rebuilt {} index {}.{} in {:.1}s",
+                index.method,
+                index.schema,
+                index.name,
+                start.elapsed().as_secs_f64()
+            ),
+            Err(e) => {
+                tracing::error!(
+                    "  Failed to rebuild {} index {}.{}: {}",
+                    index.method,
+                    index.schema,
+                    index.name,
+                    e
+                );
+                failed.push(format!("{};", index.definition));
+            }
+        }
+    }
+
+    if !failed.is_empty() {
+        anyhow::bail!(
+            "Failed to rebuild {} vector index(es). Run these on the target:\n{}",
+            failed.len(),
+            failed.join("\n")
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vector_columns() {
+        let columns = vec![
+            ("id".to_string(), "int8".to_string()),
+            ("embedding".to_string(), "vector".to_string()),
+            ("sparse".to_string(), "sparsevec".to_string()),
+            ("body".to_string(), "text".to_string()),
+        ];
+        assert_eq!(
+            vector_columns(&columns),
+            vec![
+                ("embedding".to_string(), "vector".to_string()),
+                ("sparse".to_string(), "sparsevec".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_select_expression() {
+        assert_eq!(
+            select_expression("embedding"),
+            "\"embedding\"::text AS \"embedding\""
+        );
+    }
+}
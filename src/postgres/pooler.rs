@@ -0,0 +1,246 @@
+// ABOUTME: Detection of transaction-mode connection poolers such as PgBouncer
+// ABOUTME: Switches query handling to survive server connections changing between statements
+
+use anyhow::{bail, Context, Result};
+use clap::ValueEnum;
+use futures::{pin_mut, TryStreamExt};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use tokio_postgres::types::{ToSql, Type};
+use tokio_postgres::{Client, Row, SimpleQueryMessage};
+
+/// Whether connections go through a transaction-mode pooler
+#[derive(Debug, Clone, Copy, Default, ValueEnum, PartialEq, Eq)]
+pub enum PoolerMode {
+    /// Detect from the connection URL and by probing each server once
+    #[default]
+    Auto,
+    /// Connections reach PostgreSQL directly or through a session-mode pooler
+    Session,
+    /// Connections go through a transaction-mode pooler
+    Transaction,
+}
+
+static POOLER_MODE: OnceLock<PoolerMode> = OnceLock::new();
+
+/// Set once any connection is found to be transaction-pooled
+static TRANSACTION_POOLING: AtomicBool = AtomicBool::new(false);
+
+/// Probe results per `host:port`, so each server is checked and reported once
+static PROBED: Mutex<Option<HashMap<String, bool>>> = Mutex::new(None);
+
+/// Set the pooler mode (call once at startup)
+pub fn init_pooler_mode(mode: PoolerMode) {
+    let _ = POOLER_MODE.set(mode);
+    if mode == PoolerMode::Transaction {
+        TRANSACTION_POOLING.store(true, Ordering::Relaxed);
+        warn_transaction_pooling("all servers");
+    }
+}
+
+fn pooler_mode() -> PoolerMode {
+    POOLER_MODE.get().copied().unwrap_or_default()
+}
+
+/// Whether this process talks to any server through a transaction-mode pooler
+///
+/// Statements are then sent through [`query`] and its siblings, which need no
+/// prepared statement to survive from one round trip to the next.
+pub fn transaction_pooling() -> bool {
+    TRANSACTION_POOLING.load(Ordering::Relaxed) || test_hooks::pooled_on_this_thread()
+}
+
+/// A statement parameter and the type it is sent as
+pub type Param<'a> = (&'a (dyn ToSql + Sync), Type);
+
+/// Run a statement and return its rows
+///
+/// `Client::query` prepares a named statement and runs it in a second round
+/// trip. A transaction-mode pooler may send the two to different server
+/// connections, and a named statement left behind on a server connection
+/// clashes with those of the next client. Behind such a pooler the statement
+/// is sent unnamed in one round trip, with each parameter's given type;
+/// otherwise the server infers the types as usual.
+pub async fn query(client: &Client, sql: &str, params: &[Param<'_>]) -> Result<Vec<Row>> {
+    if transaction_pooling() {
+        test_hooks::count_unnamed_statement();
+        return Ok(client.query_typed(sql, params).await?);
+    }
+    let values: Vec<&(dyn ToSql + Sync)> = params.iter().map(|(value, _)| *value).collect();
+    Ok(client.query(sql, &values).await?)
+}
+
+/// Run a statement that returns exactly one row, see [`query`]
+pub async fn query_one(client: &Client, sql: &str, params: &[Param<'_>]) -> Result<Row> {
+    match query_opt(client, sql, params).await? {
+        Some(row) => Ok(row),
+        None => bail!("Query returned no rows"),
+    }
+}
+
+/// Run a statement that returns at most one row, see [`query`]
+pub async fn query_opt(client: &Client, sql: &str, params: &[Param<'_>]) -> Result<Option<Row>> {
+    let mut rows = query(client, sql, params).await?;
+    if rows.len() > 1 {
+        bail!("Query returned {} rows, expected at most one", rows.len());
+    }
+    Ok(rows.pop())
+}
+
+/// Run a statement and return the number of rows it changed, see [`query`]
+pub async fn execute(client: &Client, sql: &str, params: &[Param<'_>]) -> Result<u64> {
+    if transaction_pooling() {
+        test_hooks::count_unnamed_statement();
+        let stream = client
+            .query_typed_raw(sql, params.iter().map(|(value, ty)| (*value, ty.clone())))
+            .await?;
+        pin_mut!(stream);
+        while stream.try_next().await?.is_some() {}
+        return Ok(stream.rows_affected().unwrap_or(0));
+    }
+    let values: Vec<&(dyn ToSql + Sync)> = params.iter().map(|(value, _)| *value).collect();
+    Ok(client.execute(sql, &values).await?)
+}
+
+/// Whether a connection URL looks like it points at a pooler
+///
+/// Matches the PgBouncer and Supabase transaction-mode ports (6432, 6543)
+/// and hostnames containing `pooler` or `pgbouncer` (Neon, Supabase).
+pub fn url_suggests_pooler(url: &str) -> bool {
+    match crate::utils::parse_postgres_url(url) {
+        Ok(parts) => {
+            let host = parts.host.to_lowercase();
+            matches!(parts.port, 6432 | 6543)
+                || host.contains("pooler")
+                || host.contains("pgbouncer")
+        }
+        Err(_) => false,
+    }
+}
+
+/// Check a new connection for transaction pooling, once per server
+///
+/// In auto mode a URL that looks pooled is confirmed by asking for the
+/// backend PID several times: a transaction-mode pooler may answer each
+/// statement from a different server connection.
+pub(crate) async fn check_connection(url: &str, client: &Client) -> Result<()> {
+    if pooler_mode() != PoolerMode::Auto {
+        return Ok(());
+    }
+    let Ok(parts) = crate::utils::parse_postgres_url(url) else {
+        return Ok(());
+    };
+    let key = format!("{}:{}", parts.host.to_lowercase(), parts.port);
+    if let Some(probed) = PROBED.lock().unwrap().as_ref() {
+        if probed.contains_key(&key) {
+            return Ok(());
+        }
+    }
+
+    let pooled = url_suggests_pooler(url) && backend_changes(client).await?;
+    PROBED
+        .lock()
+        .unwrap()
+        .get_or_insert_with(HashMap::new)
+        .insert(key.clone(), pooled);
+    if pooled {
+        TRANSACTION_POOLING.store(true, Ordering::Relaxed);
+        warn_transaction_pooling(&key);
+    } else if url_suggests_pooler(url) {
+        tracing::info!(
+            "{} looks like a connection pooler but kept one server connection; \
+             pass --pooler-mode transaction if it runs in transaction mode",
+            key
+        );
+    }
+    Ok(())
+}
+
+/// Whether consecutive statements outside a transaction ran on different backends
+async fn backend_changes(client: &Client) -> Result<bool> {
+    let mut pids = Vec::new();
+    for _ in 0..3 {
+        let messages = client
+            .simple_query("SELECT pg_backend_pid()")
+            .await
+            .context("Failed to probe for a connection pooler")?;
+        pids.extend(messages.into_iter().filter_map(|message| match message {
+            SimpleQueryMessage::Row(row) => row.get(0).map(str::to_string),
+            _ => None,
+        }));
+    }
+    pids.dedup();
+    Ok(pids.len() > 1)
+}
+
+fn warn_transaction_pooling(server: &str) {
+    tracing::warn!(
+        "Transaction-mode connection pooler in use ({}): statements are sent unnamed, \
+         session settings are not used, and prepared statements are not cached",
+        server
+    );
+    tracing::warn!(
+        "xmin-based sync cannot keep one snapshot across statements through the pooler, \
+         so rows changed mid-cycle may be picked up a cycle later; prefer a direct connection"
+    );
+}
+
+#[cfg(not(test))]
+mod test_hooks {
+    pub(super) fn pooled_on_this_thread() -> bool {
+        false
+    }
+
+    pub(super) fn count_unnamed_statement() {}
+}
+
+#[cfg(test)]
+pub(crate) mod test_hooks {
+    use std::cell::Cell;
+
+    thread_local! {
+        static POOLED: Cell<bool> = const { Cell::new(false) };
+        static UNNAMED_STATEMENTS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    /// Behave as if behind a transaction-mode pooler on this test's thread
+    pub(crate) fn pool_this_thread() {
+        POOLED.with(|pooled| pooled.set(true));
+    }
+
+    /// Statements sent unnamed on this test's thread so far
+    pub(crate) fn unnamed_statements() -> usize {
+        UNNAMED_STATEMENTS.with(Cell::get)
+    }
+
+    pub(super) fn pooled_on_this_thread() -> bool {
+        POOLED.with(Cell::get)
+    }
+
+    pub(super) fn count_unnamed_statement() {
+        UNNAMED_STATEMENTS.with(|count| count.set(count.get() + 1));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_url_suggests_pooler() {
+        assert!(url_suggests_pooler(
+            "postgresql://u:p@db.example.com:6432/app"
+        ));
+        assert!(url_suggests_pooler(
+            "postgresql://u:p@aws-0-us-east-1.pooler.supabase.com:6543/postgres"
+        ));
+        assert!(url_suggests_pooler(
+            "postgresql://u:p@ep-cool-123-pooler.us-east-2.aws.neon.tech/db"
+        ));
+        assert!(!url_suggests_pooler(
+            "postgresql://u:p@db.example.com:5432/app"
+        ));
+        assert!(!url_suggests_pooler("not a url"));
+    }
+}
@@ -0,0 +1,143 @@
+// ABOUTME: PostGIS detection and geometry/geography handling for replication
+// ABOUTME: Ensures the extension exists on the target and moves spatial values as EWKB/EWKT
+
+use anyhow::Result;
+use tokio_postgres::Client;
+
+use super::extensions::{installed_extension_version, mirror_extension};
+use crate::utils::quote_ident;
+
+/// PostGIS column types that need special handling when read row-by-row
+///
+/// The driver has no native decoder for these types, so values are selected as
+/// EWKB (binary) or EWKT (text) which both carry the SRID.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpatialType {
+    Geometry,
+    Geography,
+}
+
+impl SpatialType {
+    /// Map an `information_schema.columns.udt_name` to a spatial type
+    pub fn from_udt(udt_name: &str) -> Option<Self> {
+        match udt_name {
+            "geometry" => Some(SpatialType::Geometry),
+            "geography" => Some(SpatialType::Geography),
+            _ => None,
+        }
+    }
+}
+
+/// How spatial values are encoded when read from the source
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpatialEncoding {
+    /// Extended WKB (`bytea`), used between PostgreSQL databases
+    Ewkb,
+    /// Extended WKT (`text`, e.g. `SRID=4326;POINT(1 2)`), used for non-PostGIS targets
+    Ewkt,
+}
+
+/// Find the spatial columns in a `(column_name, udt_name)` list
+pub fn spatial_columns(columns: &[(String, String)]) -> Vec<(String, SpatialType)> {
+    columns
+        .iter()
+        .filter_map(|(name, udt)| SpatialType::from_udt(udt).map(|t| (name.clone(), t)))
+        .collect()
+}
+
+/// Build the SELECT expression for a column, encoding spatial values
+///
+/// # Examples
+///
+/// ```
+/// # use database_replicator::postgres::postgis::{select_expression, SpatialEncoding};
+/// assert_eq!(select_expression("id", "int4", SpatialEncoding::Ewkb), "\"id\"");
+/// assert_eq!(
+///     select_expression("geom", "geometry", SpatialEncoding::Ewkb),
+///     "ST_AsEWKB(\"geom\") AS \"geom\""
+/// );
+/// ```
+pub fn select_expression(column: &str, udt_name: &str, encoding: SpatialEncoding) -> String {
+    let quoted = quote_ident(column);
+    match (SpatialType::from_udt(udt_name), encoding) {
+        (None, _) => quoted,
+        (Some(SpatialType::Geometry), SpatialEncoding::Ewkb) => {
+            format!("ST_AsEWKB({}) AS {}", quoted, quoted)
+        }
+        // Geography has no EWKB output function; the cast to geometry keeps the SRID
+        (Some(SpatialType::Geography), SpatialEncoding::Ewkb) => {
+            format!("ST_AsEWKB({}::geometry) AS {}", quoted, quoted)
+        }
+        (Some(_), SpatialEncoding::Ewkt) => format!("ST_AsEWKT({}) AS {}", quoted, quoted),
+    }
+}
+
+/// Wrap an EWKB parameter placeholder so it is stored as the column's spatial type
+///
+/// # Examples
+///
+/// ```
+/// # use database_replicator::postgres::postgis::{insert_expression, SpatialType};
+/// assert_eq!(insert_expression("$2", SpatialType::Geometry), "ST_GeomFromEWKB($2)");
+/// assert_eq!(
+///     insert_expression("$2", SpatialType::Geography),
+///     "ST_GeomFromEWKB($2)::geography"
+/// );
+/// ```
+pub fn insert_expression(placeholder: &str, spatial_type: SpatialType) -> String {
+    match spatial_type {
+        SpatialType::Geometry => format!("ST_GeomFromEWKB({})", placeholder),
+        SpatialType::Geography => format!("ST_GeomFromEWKB({})::geography", placeholder),
+    }
+}
+
+/// Get the installed PostGIS version, or None if PostGIS is not installed
+pub async fn postgis_version(client: &Client) -> Result<Option<String>> {
+    installed_extension_version(client, "postgis").await
+}
+
+/// Create PostGIS on the target when the source uses it
+///
+/// Returns the target's PostGIS version when the source has PostGIS installed,
+/// or None when there is nothing to do.
+pub async fn ensure_postgis(source: &Client, target: &Client) -> Result<Option<String>> {
+    mirror_extension(source, target, "postgis").await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spatial_columns() {
+        let columns = vec![
+            ("id".to_string(), "int4".to_string()),
+            ("geom".to_string(), "geometry".to_string()),
+            ("area".to_string(), "geography".to_string()),
+            ("tags".to_string(), "_text".to_string()),
+        ];
+        assert_eq!(
+            spatial_columns(&columns),
+            vec![
+                ("geom".to_string(), SpatialType::Geometry),
+                ("area".to_string(), SpatialType::Geography),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_select_expression_encodings() {
+        assert_eq!(
+            select_expression("area", "geography", SpatialEncoding::Ewkb),
+            "ST_AsEWKB(\"area\"::geometry) AS \"area\""
+        );
+        assert_eq!(
+            select_expression("geom", "geometry", SpatialEncoding::Ewkt),
+            "ST_AsEWKT(\"geom\") AS \"geom\""
+        );
+        assert_eq!(
+            select_expression("name", "text", SpatialEncoding::Ewkt),
+            "\"name\""
+        );
+    }
+}
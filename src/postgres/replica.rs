@@ -0,0 +1,96 @@
+// ABOUTME: Read-replica selection for bulk snapshot reads with fallback to the primary
+// ABOUTME: Waits for a replica to replay the primary's WAL position before reading from it
+
+use anyhow::{bail, Context, Result};
+use std::time::{Duration, Instant};
+use tokio_postgres::Client;
+
+/// How long to wait for a replica to replay the primary's current WAL position
+const CATCH_UP_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Pick where to read bulk snapshot data from
+///
+/// Returns the first replica that is in recovery and has replayed everything
+/// the primary had written when this was called, so the snapshot includes all
+/// changes committed before the copy started. Replicas that are unreachable,
+/// not in recovery, or still behind after five minutes are skipped with a
+/// warning; the primary is returned when none qualify.
+pub async fn choose_read_source(primary_url: &str, replica_urls: &[String]) -> Result<String> {
+    if replica_urls.is_empty() {
+        return Ok(primary_url.to_string());
+    }
+    let primary = super::connect_with_retry(primary_url)
+        .await
+        .context("Failed to connect to the source primary")?;
+    let primary_lsn: String = primary
+        .query_one("SELECT pg_current_wal_lsn()::text", &[])
+        .await
+        .context("Failed to read the primary's WAL position")?
+        .get(0);
+
+    for replica_url in replica_urls {
+        let host = crate::redact::redact_url(replica_url);
+        match replica_ready(replica_url, &primary_lsn).await {
+            Ok(()) => {
+                tracing::info!("  Reading snapshot data from replica {}", host);
+                return Ok(replica_url.clone());
+            }
+            Err(e) => tracing::warn!("⚠ Skipping replica {}: {:#}", host, e),
+        }
+    }
+    tracing::warn!("⚠ No source replica is usable; reading snapshot data from the primary");
+    Ok(primary_url.to_string())
+}
+
+/// Connect to a replica and wait until it has replayed `primary_lsn`
+async fn replica_ready(replica_url: &str, primary_lsn: &str) -> Result<()> {
+    let client = super::connect(replica_url).await?;
+    let in_recovery: bool = client
+        .query_one("SELECT pg_is_in_recovery()", &[])
+        .await?
+        .get(0);
+    if !in_recovery {
+        bail!("server is not in recovery, so it is not a replica");
+    }
+
+    let started = Instant::now();
+    loop {
+        if caught_up(&client, primary_lsn).await? {
+            return Ok(());
+        }
+        if started.elapsed() >= CATCH_UP_TIMEOUT {
+            bail!(
+                "still behind primary WAL position {} after {:?}",
+                primary_lsn,
+                CATCH_UP_TIMEOUT
+            );
+        }
+        tracing::info!(
+            "  Waiting for replica to replay WAL up to {}...",
+            primary_lsn
+        );
+        tokio::time::sleep(Duration::from_secs(2)).await;
+    }
+}
+
+async fn caught_up(client: &Client, lsn: &str) -> Result<bool> {
+    let row = client
+        .query_one(
+            "SELECT COALESCE(pg_last_wal_replay_lsn() >= $1::text::pg_lsn, false)",
+            &[&lsn],
+        )
+        .await
+        .context("Failed to read the replica's replay position")?;
+    Ok(row.get(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_no_replicas_reads_from_primary() {
+        let url = "postgresql://user@primary.invalid/db";
+        assert_eq!(choose_read_source(url, &[]).await.unwrap(), url);
+    }
+}
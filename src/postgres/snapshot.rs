@@ -0,0 +1,224 @@
+// ABOUTME: Exported snapshots shared by connections that must read one point in time
+// ABOUTME: Lets pg_dump and parallel copy workers see identical, FK-consistent data
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio_postgres::Client;
+
+/// WAL position of the server, which on a replica is how far it has replayed
+const CURRENT_LSN: &str = "CASE WHEN pg_is_in_recovery() \
+     THEN pg_last_wal_replay_lsn() ELSE pg_current_wal_lsn() END::text";
+
+/// How the snapshot behind a [`ConsistencyPoint`] was taken
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SnapshotMethod {
+    /// Created with a replication slot, whose stream starts exactly at the LSN
+    Slot,
+    /// Exported from a transaction; the LSN is the WAL position at export
+    Exported,
+}
+
+impl SnapshotMethod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SnapshotMethod::Slot => "slot",
+            SnapshotMethod::Exported => "exported",
+        }
+    }
+
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "slot" => SnapshotMethod::Slot,
+            _ => SnapshotMethod::Exported,
+        }
+    }
+}
+
+/// The point in a source's history that a copy of it reflects
+///
+/// Every transaction the snapshot sees committed at or before `lsn`, and
+/// `txid_snapshot` (`xmin:xmax:xip,...`) tells exactly which ones it saw.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConsistencyPoint {
+    pub lsn: String,
+    pub txid_snapshot: String,
+    pub taken_at: DateTime<Utc>,
+    pub method: SnapshotMethod,
+}
+
+impl ConsistencyPoint {
+    /// The consistency point of a replication slot's snapshot
+    ///
+    /// The slot reports its LSN; the transaction IDs are read by importing
+    /// the snapshot on a new connection to `url`.
+    pub async fn of_slot(url: &str, snapshot_id: &str, lsn: &str) -> Result<Self> {
+        let client = super::connect_with_retry(url).await?;
+        begin_at_snapshot(&client, snapshot_id).await?;
+        let txid_snapshot: String = client
+            .query_one("SELECT txid_current_snapshot()::text", &[])
+            .await
+            .context("Failed to read the snapshot's transaction IDs")?
+            .get(0);
+        client
+            .batch_execute("COMMIT")
+            .await
+            .context("Failed to end snapshot transaction")?;
+        Ok(Self {
+            lsn: lsn.to_string(),
+            txid_snapshot,
+            taken_at: Utc::now(),
+            method: SnapshotMethod::Slot,
+        })
+    }
+
+    /// First transaction ID the snapshot does not know about
+    pub fn xmax(&self) -> Option<u64> {
+        self.txid_snapshot.split(':').nth(1)?.parse().ok()
+    }
+}
+
+impl std::fmt::Display for ConsistencyPoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "LSN {} (txid snapshot {}, {} at {})",
+            self.lsn,
+            self.txid_snapshot,
+            self.method.as_str(),
+            self.taken_at.to_rfc3339()
+        )
+    }
+}
+
+/// A snapshot exported from a source database, kept alive until dropped
+///
+/// `pg_dump --snapshot` and [`begin_at_snapshot`] readers on the same server
+/// then all see exactly the same data, whether it is a replica or the primary.
+pub struct ExportedSnapshot {
+    // The exporting transaction must stay open for the snapshot to be importable
+    _client: Client,
+    id: String,
+    point: ConsistencyPoint,
+}
+
+impl ExportedSnapshot {
+    pub async fn export(url: &str) -> Result<Self> {
+        let client = super::connect_with_retry(url).await?;
+        client
+            .batch_execute("BEGIN ISOLATION LEVEL REPEATABLE READ READ ONLY")
+            .await
+            .context("Failed to start snapshot transaction")?;
+        // The snapshot is taken by this statement, so the other columns
+        // describe exactly what it sees
+        let row = client
+            .query_one(
+                &format!(
+                    "SELECT pg_export_snapshot(), txid_current_snapshot()::text, {}",
+                    CURRENT_LSN
+                ),
+                &[],
+            )
+            .await
+            .context("Failed to export snapshot")?;
+        let id: String = row.get(0);
+        tracing::debug!("Exported snapshot {}", id);
+        Ok(Self {
+            _client: client,
+            id,
+            point: ConsistencyPoint {
+                lsn: row.get(2),
+                txid_snapshot: row.get(1),
+                taken_at: Utc::now(),
+                method: SnapshotMethod::Exported,
+            },
+        })
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn consistency_point(&self) -> &ConsistencyPoint {
+        &self.point
+    }
+}
+
+/// Start a read-only transaction on `client` that sees an exported snapshot
+pub async fn begin_at_snapshot(client: &Client, snapshot_id: &str) -> Result<()> {
+    if !is_valid_snapshot_id(snapshot_id) {
+        bail!("Invalid snapshot id: {}", snapshot_id);
+    }
+    client
+        .batch_execute(&format!(
+            "BEGIN ISOLATION LEVEL REPEATABLE READ READ ONLY; SET TRANSACTION SNAPSHOT '{}'",
+            snapshot_id
+        ))
+        .await
+        .context("Failed to import exported snapshot")
+}
+
+/// Snapshot ids look like `00000003-0000001B-1`
+fn is_valid_snapshot_id(id: &str) -> bool {
+    !id.is_empty() && id.chars().all(|c| c.is_ascii_hexdigit() || c == '-')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_valid_snapshot_id() {
+        assert!(is_valid_snapshot_id("00000003-0000001B-1"));
+        assert!(!is_valid_snapshot_id(""));
+        assert!(!is_valid_snapshot_id("1'; DROP TABLE users; --"));
+    }
+
+    #[test]
+    fn test_consistency_point_xmax() {
+        let mut point = ConsistencyPoint {
+            lsn: "0/16B3748".to_string(),
+            txid_snapshot: "748:752:748,750".to_string(),
+            taken_at: Utc::now(),
+            method: SnapshotMethod::Exported,
+        };
+        assert_eq!(point.xmax(), Some(752));
+        point.txid_snapshot = "garbage".to_string();
+        assert_eq!(point.xmax(), None);
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_importers_do_not_see_later_writes() {
+        let url = std::env::var("TEST_SOURCE_URL").unwrap();
+        let writer = crate::postgres::connect(&url).await.unwrap();
+        writer
+            .batch_execute(
+                "DROP TABLE IF EXISTS snapshot_test; CREATE TABLE snapshot_test (id int)",
+            )
+            .await
+            .unwrap();
+
+        let snapshot = ExportedSnapshot::export(&url).await.unwrap();
+        writer
+            .batch_execute("INSERT INTO snapshot_test VALUES (1)")
+            .await
+            .unwrap();
+
+        let reader = crate::postgres::connect(&url).await.unwrap();
+        begin_at_snapshot(&reader, snapshot.id()).await.unwrap();
+        let count: i64 = reader
+            .query_one("SELECT count(*) FROM snapshot_test", &[])
+            .await
+            .unwrap()
+            .get(0);
+        assert_eq!(count, 0);
+
+        reader.batch_execute("COMMIT").await.unwrap();
+        writer
+            .batch_execute("DROP TABLE snapshot_test")
+            .await
+            .unwrap();
+    }
+}
@@ -0,0 +1,543 @@
+// ABOUTME: Compatibility matrix of source and target server versions and the client tools
+// ABOUTME: Flags known cross-version problems and picks pg_dump flags the target understands
+
+use anyhow::{Context, Result};
+use std::collections::{HashMap, HashSet};
+use tokio_postgres::Client;
+
+use super::compatibility::{CompatibilityIssue, ObjectKind, Severity};
+
+/// Session settings that plain pg_dump output sets, with the first major
+/// version that has each; pg_dump of that version or later writes them
+const DUMP_SETTINGS: &[(&str, u32)] = &[
+    ("default_table_access_method", 12),
+    ("transaction_timeout", 17),
+];
+
+/// Objects using a feature the target may be too old to create
+struct Feature {
+    since: u32,
+    description: &'static str,
+    severity: Severity,
+    remediation: &'static str,
+    /// Returns the qualified name of each object using the feature
+    query: &'static str,
+}
+
+const USER_NAMESPACE: &str = "n.nspname NOT IN ('pg_catalog', 'information_schema') \
+     AND n.nspname NOT LIKE 'pg\\_%'";
+
+const FEATURES: &[Feature] = &[
+    Feature {
+        since: 12,
+        description: "generated columns",
+        severity: Severity::Error,
+        remediation: "Upgrade the target, or drop the generation expression on the source",
+        query: "SELECT format('%I.%I.%I', n.nspname, c.relname, a.attname)
+                FROM pg_attribute a
+                JOIN pg_class c ON c.oid = a.attrelid
+                JOIN pg_namespace n ON n.oid = c.relnamespace
+                WHERE a.attgenerated <> '' AND a.attnum > 0 AND NOT a.attisdropped
+                  AND {user}",
+    },
+    Feature {
+        since: 14,
+        description: "SQL-standard function bodies (BEGIN ATOMIC)",
+        severity: Severity::Error,
+        remediation: "Upgrade the target, or rewrite the functions with AS $$ ... $$ bodies",
+        query: "SELECT format('%I.%I', n.nspname, p.proname)
+                FROM pg_proc p
+                JOIN pg_namespace n ON n.oid = p.pronamespace
+                WHERE p.prosqlbody IS NOT NULL AND {user}
+                  AND NOT EXISTS (
+                      SELECT 1 FROM pg_depend d
+                      WHERE d.classid = 'pg_proc'::regclass AND d.objid = p.oid
+                        AND d.deptype = 'e')",
+    },
+    Feature {
+        since: 14,
+        description: "multirange columns",
+        severity: Severity::Error,
+        remediation: "Upgrade the target, or store the ranges in an array column on the source",
+        query: "SELECT format('%I.%I.%I', n.nspname, c.relname, a.attname)
+                FROM pg_attribute a
+                JOIN pg_type t ON t.oid = a.atttypid
+                JOIN pg_class c ON c.oid = a.attrelid
+                JOIN pg_namespace n ON n.oid = c.relnamespace
+                WHERE t.typtype = 'm' AND c.relkind IN ('r', 'p')
+                  AND a.attnum > 0 AND NOT a.attisdropped AND {user}",
+    },
+    Feature {
+        since: 14,
+        description: "columns compressed with lz4",
+        severity: Severity::Warning,
+        remediation: "None needed: they use the target's default compression",
+        query: "SELECT format('%I.%I.%I', n.nspname, c.relname, a.attname)
+                FROM pg_attribute a
+                JOIN pg_class c ON c.oid = a.attrelid
+                JOIN pg_namespace n ON n.oid = c.relnamespace
+                WHERE a.attcompression = 'l' AND a.attnum > 0 AND NOT a.attisdropped
+                  AND {user}",
+    },
+    Feature {
+        since: 15,
+        description: "unique indexes with NULLS NOT DISTINCT",
+        severity: Severity::Error,
+        remediation: "Upgrade the target, or recreate the indexes without NULLS NOT DISTINCT",
+        query: "SELECT format('%I.%I', n.nspname, c.relname)
+                FROM pg_index i
+                JOIN pg_class c ON c.oid = i.indexrelid
+                JOIN pg_namespace n ON n.oid = c.relnamespace
+                WHERE i.indnullsnotdistinct AND {user}",
+    },
+];
+
+/// Major versions of the servers and of the client tools that copy between them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VersionMatrix {
+    pub source: u32,
+    pub target: u32,
+    /// `None` when the tool is not installed
+    pub pg_dump: Option<u32>,
+    pub pg_restore: Option<u32>,
+    pub psql: Option<u32>,
+}
+
+/// A problem that follows from the versions alone
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionIssue {
+    pub severity: Severity,
+    pub message: String,
+    pub remediation: String,
+}
+
+impl VersionMatrix {
+    pub async fn detect(source_client: &Client, target_client: &Client) -> Result<Self> {
+        let tool = |name| crate::utils::get_pg_tool_version(name).ok();
+        Ok(Self {
+            source: server_major(source_client)
+                .await
+                .context("Failed to read the source server version")?,
+            target: server_major(target_client)
+                .await
+                .context("Failed to read the target server version")?,
+            pg_dump: tool("pg_dump"),
+            pg_restore: tool("pg_restore"),
+            psql: tool("psql"),
+        })
+    }
+
+    pub fn issues(&self) -> Vec<VersionIssue> {
+        let mut issues = Vec::new();
+        let mut issue = |severity, message: String, remediation: String| {
+            issues.push(VersionIssue {
+                severity,
+                message,
+                remediation,
+            })
+        };
+
+        if let Some(pg_dump) = self.pg_dump.filter(|pg_dump| *pg_dump < self.source) {
+            issue(
+                Severity::Error,
+                format!(
+                    "pg_dump {} cannot dump a PostgreSQL {} server",
+                    pg_dump, self.source
+                ),
+                client_install_hint(self.source),
+            );
+        }
+        if let (Some(pg_dump), Some(pg_restore)) = (self.pg_dump, self.pg_restore) {
+            if pg_restore < pg_dump {
+                issue(
+                    Severity::Error,
+                    format!(
+                        "pg_restore {} cannot read archives written by pg_dump {}",
+                        pg_restore, pg_dump
+                    ),
+                    client_install_hint(pg_dump),
+                );
+            }
+        }
+        if let (Some(pg_dump), Some(psql)) = (self.pg_dump, self.psql) {
+            if psql < pg_dump {
+                issue(
+                    Severity::Warning,
+                    format!(
+                        "psql {} may not parse the schema pg_dump {} writes",
+                        psql, pg_dump
+                    ),
+                    client_install_hint(pg_dump),
+                );
+            }
+        }
+        if self.source > self.target {
+            issue(
+                Severity::Warning,
+                format!(
+                    "Copying PostgreSQL {} into an older PostgreSQL {} server",
+                    self.source, self.target
+                ),
+                format!(
+                    "Objects using features added after PostgreSQL {} are listed in the compatibility report",
+                    self.target
+                ),
+            );
+        }
+        if self.source.min(self.target) < 10 {
+            issue(
+                Severity::Warning,
+                "Logical replication needs PostgreSQL 10 or later on both servers".to_string(),
+                "sync keeps the target current by xmin polling instead".to_string(),
+            );
+        }
+        issues
+    }
+
+    /// Extra `pg_dump --schema-only` flags that keep options the target lacks out of the dump
+    pub fn schema_dump_flags(&self) -> Vec<&'static str> {
+        let Some(pg_dump) = self.pg_dump else {
+            return Vec::new();
+        };
+        let mut flags = Vec::new();
+        if pg_dump >= 14 && self.target < 14 {
+            flags.push("--no-toast-compression");
+        }
+        if pg_dump >= 15 && self.target < 12 {
+            flags.push("--no-table-access-method");
+        }
+        flags
+    }
+
+    /// Session settings in a plain dump that the target does not have
+    pub fn unsupported_dump_settings(&self) -> Vec<&'static str> {
+        let Some(pg_dump) = self.pg_dump else {
+            return Vec::new();
+        };
+        DUMP_SETTINGS
+            .iter()
+            .filter(|(_, since)| pg_dump >= *since && self.target < *since)
+            .map(|(name, _)| *name)
+            .collect()
+    }
+
+    /// Whether pg_dump predates the target and may leave its keywords unquoted
+    fn pg_dump_predates_target(&self) -> bool {
+        self.pg_dump.is_some_and(|pg_dump| pg_dump < self.target)
+    }
+}
+
+impl std::fmt::Display for VersionMatrix {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let tool = |version: Option<u32>| {
+            version.map_or_else(|| "not installed".to_string(), |v| v.to_string())
+        };
+        write!(
+            f,
+            "source {}, target {}, pg_dump {}, pg_restore {}, psql {}",
+            self.source,
+            self.target,
+            tool(self.pg_dump),
+            tool(self.pg_restore),
+            tool(self.psql)
+        )
+    }
+}
+
+fn client_install_hint(major: u32) -> String {
+    format!(
+        "Install the PostgreSQL {} client tools (Ubuntu: sudo apt install postgresql-client-{}; macOS: brew install postgresql@{}), or rerun with --download-pg-tools",
+        major, major, major
+    )
+}
+
+/// Major version of the server `client` is connected to; 9 for any 9.x release
+pub async fn server_major(client: &Client) -> Result<u32> {
+    let version: String = client
+        .query_one("SHOW server_version_num", &[])
+        .await
+        .context("Failed to get PostgreSQL version")?
+        .get(0);
+    let version: u32 = version
+        .parse()
+        .with_context(|| format!("Invalid server_version_num '{}'", version))?;
+    Ok(version / 10000)
+}
+
+/// Parameters and keywords of the target server, read once for every source database
+pub struct TargetCatalog {
+    settings: HashSet<String>,
+    /// Words that the target's SQL parser does not accept as plain identifiers
+    keywords: HashSet<String>,
+}
+
+impl TargetCatalog {
+    pub async fn load(target_client: &Client) -> Result<Self> {
+        let settings = target_client
+            .query("SELECT name FROM pg_settings", &[])
+            .await
+            .context("Failed to list target parameters")?
+            .iter()
+            .map(|row| row.get(0))
+            .collect();
+        let keywords = non_identifier_keywords(target_client)
+            .await
+            .context("Failed to list target keywords")?;
+        Ok(Self { settings, keywords })
+    }
+}
+
+async fn non_identifier_keywords(client: &Client) -> Result<HashSet<String>> {
+    Ok(client
+        .query(
+            "SELECT word FROM pg_get_keywords() WHERE catcode <> 'U'",
+            &[],
+        )
+        .await?
+        .iter()
+        .map(|row| row.get(0))
+        .collect())
+}
+
+/// Check one source database for objects the target's version cannot recreate
+///
+/// Reports objects using features newer than the target, functions that set
+/// parameters the target does not have, and names that became keywords in a
+/// target newer than pg_dump.
+pub async fn check_database(
+    database: &str,
+    source_client: &Client,
+    versions: &VersionMatrix,
+    target: &TargetCatalog,
+) -> Result<Vec<CompatibilityIssue>> {
+    let mut issues = Vec::new();
+    let issue =
+        |severity, kind, object: String, message: String, remediation: String| CompatibilityIssue {
+            severity,
+            kind,
+            database: database.to_string(),
+            object,
+            message,
+            remediation,
+        };
+
+    for feature in FEATURES
+        .iter()
+        .filter(|feature| versions.source >= feature.since && versions.target < feature.since)
+    {
+        let objects: Vec<String> = source_client
+            .query(&feature.query.replace("{user}", USER_NAMESPACE), &[])
+            .await
+            .with_context(|| {
+                format!(
+                    "Failed to look for {} in database '{}'",
+                    feature.description, database
+                )
+            })?
+            .iter()
+            .map(|row| row.get(0))
+            .collect();
+        if objects.is_empty() {
+            continue;
+        }
+        issues.push(issue(
+            feature.severity,
+            ObjectKind::Feature,
+            summarize(&objects),
+            format!(
+                "{} need PostgreSQL {}; the target runs {}",
+                feature.description, feature.since, versions.target
+            ),
+            feature.remediation.to_string(),
+        ));
+    }
+
+    // Parameters removed or renamed by a major release fail CREATE FUNCTION
+    let rows = source_client
+        .query(
+            &format!(
+                "SELECT format('%I.%I', n.nspname, p.proname), split_part(setting, '=', 1)
+                 FROM pg_proc p
+                 JOIN pg_namespace n ON n.oid = p.pronamespace
+                 CROSS JOIN LATERAL unnest(p.proconfig) AS setting
+                 WHERE {}",
+                USER_NAMESPACE
+            ),
+            &[],
+        )
+        .await
+        .with_context(|| {
+            format!(
+                "Failed to read function settings in database '{}'",
+                database
+            )
+        })?;
+    let mut unknown: HashMap<String, Vec<String>> = HashMap::new();
+    for row in &rows {
+        let function: String = row.get(0);
+        let setting: String = row.get(1);
+        let setting = setting.to_lowercase();
+        // Names with a dot are extension placeholders any server accepts
+        if !setting.contains('.') && !target.settings.contains(&setting) {
+            unknown.entry(setting).or_default().push(function);
+        }
+    }
+    let mut unknown: Vec<_> = unknown.into_iter().collect();
+    unknown.sort();
+    for (setting, functions) in unknown {
+        issues.push(issue(
+            Severity::Error,
+            ObjectKind::Setting,
+            summarize(&functions),
+            format!(
+                "sets '{}', which PostgreSQL {} does not have",
+                setting, versions.target
+            ),
+            format!(
+                "Remove the setting on the source: ALTER FUNCTION <name> RESET {}",
+                setting
+            ),
+        ));
+    }
+
+    if versions.pg_dump_predates_target() {
+        let quoted = non_identifier_keywords(source_client)
+            .await
+            .with_context(|| format!("Failed to list keywords of database '{}'", database))?;
+        let new_keywords: Vec<&String> = target.keywords.difference(&quoted).collect();
+        if !new_keywords.is_empty() {
+            let rows = source_client
+                .query(
+                    &format!(
+                        "SELECT format('%I.%I', n.nspname, c.relname), c.relname::text
+                         FROM pg_class c JOIN pg_namespace n ON n.oid = c.relnamespace
+                         WHERE c.relname::text = ANY($1) AND {user}
+                         UNION ALL
+                         SELECT format('%I.%I.%I', n.nspname, c.relname, a.attname), a.attname::text
+                         FROM pg_attribute a
+                         JOIN pg_class c ON c.oid = a.attrelid
+                         JOIN pg_namespace n ON n.oid = c.relnamespace
+                         WHERE a.attname::text = ANY($1) AND c.relkind IN ('r', 'p', 'v', 'm', 'f')
+                           AND a.attnum > 0 AND NOT a.attisdropped AND {user}
+                         UNION ALL
+                         SELECT n.nspname::text, n.nspname::text
+                         FROM pg_namespace n
+                         WHERE n.nspname::text = ANY($1) AND {user}",
+                        user = USER_NAMESPACE
+                    ),
+                    &[&new_keywords],
+                )
+                .await
+                .with_context(|| {
+                    format!("Failed to look for keywords in database '{}'", database)
+                })?;
+            for row in &rows {
+                let word: String = row.get(1);
+                issues.push(issue(
+                    Severity::Error,
+                    ObjectKind::Keyword,
+                    row.get(0),
+                    format!(
+                        "'{}' is a keyword in PostgreSQL {}, which pg_dump {} writes unquoted",
+                        word,
+                        versions.target,
+                        versions.pg_dump.unwrap_or_default()
+                    ),
+                    client_install_hint(versions.target),
+                ));
+            }
+        }
+    }
+
+    Ok(issues)
+}
+
+/// Up to five names, then how many more there are
+fn summarize(names: &[String]) -> String {
+    const SHOWN: usize = 5;
+    let mut summary = names
+        .iter()
+        .take(SHOWN)
+        .cloned()
+        .collect::<Vec<_>>()
+        .join(", ");
+    if names.len() > SHOWN {
+        summary.push_str(&format!(" and {} more", names.len() - SHOWN));
+    }
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matrix(source: u32, target: u32, tools: u32) -> VersionMatrix {
+        VersionMatrix {
+            source,
+            target,
+            pg_dump: Some(tools),
+            pg_restore: Some(tools),
+            psql: Some(tools),
+        }
+    }
+
+    #[test]
+    fn test_version_issues() {
+        assert!(matrix(16, 16, 16).issues().is_empty());
+        // Newer targets are fine; logical replication works across versions
+        assert!(matrix(13, 16, 16).issues().is_empty());
+
+        let issues = matrix(16, 13, 15).issues();
+        assert_eq!(issues.len(), 2);
+        assert_eq!(issues[0].severity, Severity::Error);
+        assert_eq!(
+            issues[0].message,
+            "pg_dump 15 cannot dump a PostgreSQL 16 server"
+        );
+        assert_eq!(issues[1].severity, Severity::Warning);
+
+        let mut old_restore = matrix(16, 16, 17);
+        old_restore.pg_restore = Some(16);
+        assert_eq!(old_restore.issues()[0].severity, Severity::Error);
+        assert!(old_restore.issues()[0].message.contains("pg_restore 16"));
+    }
+
+    #[test]
+    fn test_dump_adjustments_follow_target() {
+        let downgrade = matrix(17, 13, 17);
+        assert_eq!(
+            downgrade.schema_dump_flags(),
+            vec!["--no-toast-compression"]
+        );
+        assert_eq!(
+            downgrade.unsupported_dump_settings(),
+            vec!["transaction_timeout"]
+        );
+
+        let ancient = matrix(16, 11, 16);
+        assert_eq!(
+            ancient.schema_dump_flags(),
+            vec!["--no-toast-compression", "--no-table-access-method"]
+        );
+        assert_eq!(
+            ancient.unsupported_dump_settings(),
+            vec!["default_table_access_method"]
+        );
+
+        assert!(matrix(17, 17, 17).schema_dump_flags().is_empty());
+        assert!(matrix(17, 17, 17).unsupported_dump_settings().is_empty());
+        // An older pg_dump does not write what it does not know
+        assert_eq!(
+            matrix(13, 11, 13).unsupported_dump_settings(),
+            vec!["default_table_access_method"]
+        );
+        assert!(matrix(11, 11, 11).unsupported_dump_settings().is_empty());
+    }
+
+    #[test]
+    fn test_summarize() {
+        let names: Vec<String> = (1..=7).map(|i| format!("t{}", i)).collect();
+        assert_eq!(summarize(&names[..2]), "t1, t2");
+        assert_eq!(summarize(&names), "t1, t2, t3, t4, t5 and 2 more");
+    }
+}
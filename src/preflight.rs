@@ -1,6 +1,7 @@
 // ABOUTME: Pre-flight validation checks for replication prerequisites
 // ABOUTME: Validates local environment, network connectivity, and database permissions
 
+use crate::failure::{Failure, FailureKind};
 use anyhow::Result;
 use tokio_postgres::Client;
 
@@ -42,6 +43,8 @@ impl CheckResult {
 #[derive(Debug, Clone)]
 pub struct PreflightIssue {
     pub title: String,
+    /// Whether fixing the issue takes a change or just another attempt
+    pub kind: FailureKind,
     pub explanation: String,
     pub fixes: Vec<String>,
 }
@@ -73,6 +76,21 @@ impl PreflightResult {
         self.issues.len()
     }
 
+    /// The issues as one error, of the first kind retrying can't fix
+    ///
+    /// Only when every issue is a connection problem is the failure retryable.
+    pub fn failure(&self) -> Failure {
+        let kind = self
+            .issues
+            .iter()
+            .map(|issue| issue.kind)
+            .find(|kind| !kind.is_retryable())
+            .or_else(|| self.issues.first().map(|issue| issue.kind))
+            .unwrap_or(FailureKind::Other);
+        let titles: Vec<&str> = self.issues.iter().map(|i| i.title.as_str()).collect();
+        Failure::new(kind, titles.join("; "))
+    }
+
     /// Print formatted output
     pub fn print(&self) {
         println!();
@@ -196,6 +214,7 @@ pub async fn run_preflight_checks(
                 ));
                 result.issues.push(PreflightIssue {
                     title: "Source connection for permissions failed".to_string(),
+                    kind: crate::failure::classify(&e),
                     explanation: e.to_string(),
                     fixes: vec!["Ensure source database is accessible".to_string()],
                 });
@@ -220,6 +239,7 @@ pub async fn run_preflight_checks(
                 ));
                 result.issues.push(PreflightIssue {
                     title: "Target connection for permissions failed".to_string(),
+                    kind: crate::failure::classify(&e),
                     explanation: e.to_string(),
                     fixes: vec!["Ensure target database is accessible".to_string()],
                 });
@@ -266,16 +286,20 @@ fn check_local_environment(result: &mut PreflightResult) {
         }
     }
 
+    // Init falls back to copying over plain connections, so this is not an issue
     if !missing.is_empty() {
-        result.issues.push(PreflightIssue {
-            title: "Missing PostgreSQL client tools".to_string(),
-            explanation: format!("Required tools not found: {}", missing.join(", ")),
-            fixes: vec![
-                "Ubuntu: sudo apt install postgresql-client-17".to_string(),
-                "macOS: brew install postgresql@17".to_string(),
-                "RHEL: sudo dnf install postgresql17".to_string(),
-            ],
-        });
+        result.local_env.push(
+            CheckResult::fail(
+                "native copy",
+                format!(
+                    "Missing {}: schema and data will be copied without the client tools",
+                    missing.join(", ")
+                ),
+            )
+            .with_details(
+                "Install postgresql-client for a full dump (ownership, privileges, aggregates)",
+            ),
+        );
     }
 }
 
@@ -308,6 +332,7 @@ async fn check_network_connectivity(
             ));
             result.issues.push(PreflightIssue {
                 title: format!("{} database unreachable", db_type),
+                kind: crate::failure::classify(&e),
                 explanation: e.to_string(),
                 fixes: vec![
                     "Verify connection string is correct".to_string(),
@@ -332,6 +357,7 @@ fn check_version_compatibility(result: &mut PreflightResult) {
         ));
         result.issues.push(PreflightIssue {
             title: "PostgreSQL version mismatch".to_string(),
+            kind: FailureKind::Other,
             explanation: format!(
                 "Local pg_dump ({}) cannot dump from server ({})",
                 local, server
@@ -340,6 +366,7 @@ fn check_version_compatibility(result: &mut PreflightResult) {
                 format!("Install PostgreSQL {} client tools:", server),
                 format!("  Ubuntu: sudo apt install postgresql-client-{}", server),
                 format!("  macOS: brew install postgresql@{}", server),
+                "Or rerun with --download-pg-tools to fetch them into ~/.seren/tools".to_string(),
                 "Or use SerenAI cloud execution (recommended for SerenDB targets)".to_string(),
             ],
         });
@@ -377,6 +404,7 @@ async fn check_source_permissions(
                 ));
                 result.issues.push(PreflightIssue {
                     title: "Missing REPLICATION privilege".to_string(),
+                    kind: FailureKind::Auth,
                     explanation: "Required for continuous sync".to_string(),
                     fixes: vec![
                         "Standard PostgreSQL: ALTER USER <username> WITH REPLICATION;".to_string(),
@@ -417,6 +445,7 @@ async fn check_source_permissions(
                 );
                 result.issues.push(PreflightIssue {
                     title: "Missing table permissions".to_string(),
+                    kind: FailureKind::Auth,
                     explanation: format!("User needs SELECT on {} tables", count),
                     fixes: vec![
                         "Run: GRANT SELECT ON ALL TABLES IN SCHEMA public TO <username>;"
@@ -447,6 +476,7 @@ async fn check_target_permissions(result: &mut PreflightResult, client: &Client)
                     .push(CheckResult::fail("createdb", "Cannot create databases"));
                 result.issues.push(PreflightIssue {
                     title: "Missing CREATEDB privilege".to_string(),
+                    kind: FailureKind::Auth,
                     explanation: "Cannot create databases on target".to_string(),
                     fixes: vec!["Run: ALTER USER <username> CREATEDB;".to_string()],
                 });
@@ -508,6 +538,7 @@ mod tests {
         let mut result = PreflightResult::new();
         result.issues.push(PreflightIssue {
             title: "Test issue".to_string(),
+            kind: FailureKind::Other,
             explanation: "Test".to_string(),
             fixes: vec![],
         });
@@ -515,10 +546,38 @@ mod tests {
         assert_eq!(result.failed_count(), 1);
     }
 
+    #[test]
+    fn test_preflight_failure_kind() {
+        let issue = |title: &str, kind| PreflightIssue {
+            title: title.to_string(),
+            kind,
+            explanation: String::new(),
+            fixes: vec![],
+        };
+        let mut result = PreflightResult::new();
+        result.issues.push(issue(
+            "target database unreachable",
+            FailureKind::Connection,
+        ));
+        assert_eq!(result.failure().kind, FailureKind::Connection);
+
+        // Retrying can't fix a missing privilege, whatever else failed
+        result
+            .issues
+            .push(issue("Missing CREATEDB privilege", FailureKind::Auth));
+        let failure = result.failure();
+        assert_eq!(failure.kind, FailureKind::Auth);
+        assert_eq!(
+            failure.to_string(),
+            "target database unreachable; Missing CREATEDB privilege"
+        );
+    }
+
     #[test]
     fn test_preflight_issue_multiple_fixes() {
         let issue = PreflightIssue {
             title: "Test".to_string(),
+            kind: FailureKind::Other,
             explanation: "Details".to_string(),
             fixes: vec!["Fix 1".to_string(), "Fix 2".to_string()],
         };
@@ -5,7 +5,9 @@ use anyhow::{Context, Result};
 use reqwest::Client;
 use std::time::Duration;
 
-use super::models::{JobResponse, JobSpec, JobStatus};
+use super::estimate::JobEstimate;
+use super::models::{JobResponse, JobSpec, JobStatus, Schedule};
+use super::sealing::SealingKey;
 
 #[derive(Clone)]
 pub struct RemoteClient {
@@ -28,10 +30,101 @@ impl RemoteClient {
         })
     }
 
+    /// The key the orchestrator's workers open sealed credentials with
+    pub async fn get_sealing_key(&self) -> Result<SealingKey> {
+        let url = format!("{}/public-key", self.api_base_url);
+
+        let mut request = self.client.get(&url);
+
+        // Add API key header if provided
+        if let Some(ref key) = self.api_key {
+            request = request.header("x-api-key", key);
+        }
+
+        let response = request
+            .send()
+            .await
+            .context("Failed to fetch the remote service's public key. If the service is unavailable, you can use --local to run replication on your machine instead")?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+
+            if status == 401 {
+                anyhow::bail!(
+                    "Authentication failed. Your API key may be invalid or expired.\n\
+                    Generate a new key at: https://console.serendb.com/api-keys"
+                );
+            }
+
+            anyhow::bail!("Failed to fetch public key {}: {}", status, body);
+        }
+
+        let key: SealingKey = response
+            .json()
+            .await
+            .context("Failed to parse public key response")?;
+
+        Ok(key)
+    }
+
+    /// A copy of `spec` with its credentials sealed to the orchestrator's key
+    async fn sealed(&self, spec: &JobSpec) -> Result<JobSpec> {
+        let key = self.get_sealing_key().await?;
+        let mut sealed = spec.clone();
+        sealed.seal(&key)?;
+        Ok(sealed)
+    }
+
+    /// The orchestrator's instance class, duration, and price for `spec`, or
+    /// None when the service has no estimate endpoint
+    pub async fn estimate_job(&self, spec: &JobSpec) -> Result<Option<JobEstimate>> {
+        let url = format!("{}/jobs/estimate", self.api_base_url);
+        let spec = self.sealed(spec).await?;
+
+        let mut request = self.client.post(&url).json(&spec);
+
+        // Add API key header if provided
+        if let Some(ref key) = self.api_key {
+            request = request.header("x-api-key", key);
+        }
+
+        let response = request
+            .send()
+            .await
+            .context("Failed to estimate the job. The remote service may be unavailable")?;
+
+        if response.status() == 404 {
+            return Ok(None);
+        }
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = crate::redact::redact(&response.text().await.unwrap_or_default());
+
+            if status == 401 {
+                anyhow::bail!(
+                    "Authentication failed. Your API key may be invalid or expired.\n\
+                    Generate a new key at: https://console.serendb.com/api-keys"
+                );
+            }
+
+            anyhow::bail!("Job estimate failed with status {}: {}", status, body);
+        }
+
+        let estimate: JobEstimate = response
+            .json()
+            .await
+            .context("Failed to parse job estimate")?;
+
+        Ok(Some(estimate))
+    }
+
+    /// Submit a job; its credentials are sealed before they leave this machine
     pub async fn submit_job(&self, spec: &JobSpec) -> Result<JobResponse> {
         let url = format!("{}/jobs", self.api_base_url);
+        let spec = self.sealed(spec).await?;
 
-        let mut request = self.client.post(&url).json(spec);
+        let mut request = self.client.post(&url).json(&spec);
 
         // Add API key header if provided
         if let Some(ref key) = self.api_key {
@@ -45,7 +138,8 @@ impl RemoteClient {
 
         if !response.status().is_success() {
             let status = response.status();
-            let body = response.text().await.unwrap_or_default();
+            // Validation errors can echo the submitted spec back
+            let body = crate::redact::redact(&response.text().await.unwrap_or_default());
 
             // Special handling for authentication errors
             if status == 401 {
@@ -108,6 +202,130 @@ impl RemoteClient {
         Ok(job_status)
     }
 
+    /// Ask the orchestrator to re-run `spec` on `spec.schedule`
+    pub async fn create_schedule(&self, spec: &JobSpec) -> Result<Schedule> {
+        let url = format!("{}/schedules", self.api_base_url);
+        let spec = self.sealed(spec).await?;
+
+        let mut request = self.client.post(&url).json(&spec);
+
+        // Add API key header if provided
+        if let Some(ref key) = self.api_key {
+            request = request.header("x-api-key", key);
+        }
+
+        let response = request
+            .send()
+            .await
+            .context("Failed to create schedule. The remote service may be unavailable")?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            // Validation errors can echo the submitted spec back
+            let body = crate::redact::redact(&response.text().await.unwrap_or_default());
+
+            if status == 401 {
+                anyhow::bail!(
+                    "Authentication failed. Your API key may be invalid or expired.\n\
+                    Generate a new key at: https://console.serendb.com/api-keys"
+                );
+            }
+
+            anyhow::bail!(
+                "Creating the schedule failed with status {}: {}",
+                status,
+                body
+            );
+        }
+
+        let schedule: Schedule = response
+            .json()
+            .await
+            .context("Failed to parse schedule response")?;
+
+        Ok(schedule)
+    }
+
+    /// Schedules owned by the API key, with the last job each one started
+    pub async fn list_schedules(&self) -> Result<Vec<Schedule>> {
+        let url = format!("{}/schedules", self.api_base_url);
+
+        let mut request = self.client.get(&url);
+
+        // Add API key header if provided
+        if let Some(ref key) = self.api_key {
+            request = request.header("x-api-key", key);
+        }
+
+        let response = request
+            .send()
+            .await
+            .context("Failed to list schedules. The remote service may be unavailable")?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+
+            if status == 401 {
+                anyhow::bail!(
+                    "Authentication failed. Your API key may be invalid or expired.\n\
+                    Generate a new key at: https://console.serendb.com/api-keys"
+                );
+            }
+
+            anyhow::bail!("Failed to list schedules {}: {}", status, body);
+        }
+
+        let schedules: Vec<Schedule> =
+            response.json().await.context("Failed to parse schedules")?;
+
+        Ok(schedules)
+    }
+
+    /// Stop re-running a schedule; jobs it already started keep running
+    pub async fn delete_schedule(&self, schedule_id: &str) -> Result<()> {
+        let url = format!("{}/schedules/{}", self.api_base_url, schedule_id);
+
+        let mut request = self.client.delete(&url);
+
+        // Add API key header if provided
+        if let Some(ref key) = self.api_key {
+            request = request.header("x-api-key", key);
+        }
+
+        let response = request
+            .send()
+            .await
+            .context("Failed to delete schedule. The remote service may be unavailable")?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+
+            if status == 401 {
+                anyhow::bail!(
+                    "Authentication failed. Your API key may be invalid or expired.\n\
+                    Generate a new key at: https://console.serendb.com/api-keys"
+                );
+            }
+            if status == 404 {
+                anyhow::bail!(
+                    "Schedule {} not found. Run `database-replicator jobs schedule list` to see your schedules",
+                    schedule_id
+                );
+            }
+
+            anyhow::bail!(
+                "Failed to delete schedule {}: {} {}",
+                schedule_id,
+                status,
+                body
+            );
+        }
+
+        Ok(())
+    }
+
     pub async fn poll_until_complete(
         &self,
         job_id: &str,
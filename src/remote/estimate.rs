@@ -0,0 +1,130 @@
+// ABOUTME: Instance class, duration, and price preview for SerenAI cloud jobs
+// ABOUTME: Uses the orchestrator's estimate endpoint, or a local size table without a price
+
+use anyhow::{bail, Result};
+use dialoguer::theme::ColorfulTheme;
+use dialoguer::Confirm;
+use serde::Deserialize;
+use std::time::Duration;
+
+use crate::migration::{format_bytes, format_duration};
+
+/// What a cloud job is expected to run on, take, and cost
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct JobEstimate {
+    pub instance_class: String,
+    pub estimated_duration_seconds: u64,
+    /// What the job will be billed; only the orchestrator knows the price
+    pub estimated_cost_usd: Option<f64>,
+}
+
+/// Worker sizes, smallest first: (largest data size in bytes, class, copy
+/// throughput in bytes per second)
+const INSTANCE_CLASSES: [(i64, &str, i64); 4] = [
+    (10 * GB, "c6i.large", 20 * MB),
+    (100 * GB, "c6i.xlarge", 40 * MB),
+    (500 * GB, "c6i.2xlarge", 80 * MB),
+    (i64::MAX, "c6i.4xlarge", 160 * MB),
+];
+
+const MB: i64 = 1024 * 1024;
+const GB: i64 = 1024 * MB;
+
+/// Provisioning and schema copy, on top of the data copy
+const SETUP_SECONDS: u64 = 300;
+
+impl JobEstimate {
+    /// Estimate from the data size alone, as the orchestrator picks the class
+    ///
+    /// There is no price: the job is billed at SerenAI's rates, not the
+    /// instance's list price.
+    pub fn from_size(estimated_size_bytes: i64) -> Self {
+        let size = estimated_size_bytes.max(0);
+        let (_, class, throughput) = INSTANCE_CLASSES
+            .into_iter()
+            .find(|(max_size, ..)| size <= *max_size)
+            .expect("the last class takes any size");
+        let seconds = SETUP_SECONDS + (size / throughput) as u64;
+        JobEstimate {
+            instance_class: class.to_string(),
+            estimated_duration_seconds: seconds,
+            estimated_cost_usd: None,
+        }
+    }
+
+    pub fn estimated_duration(&self) -> Duration {
+        Duration::from_secs(self.estimated_duration_seconds)
+    }
+}
+
+/// Print `estimate` and ask whether to submit the job, unless `yes`
+///
+/// # Errors
+///
+/// Returns an error if the user declines, or if confirmation is needed but
+/// stdin is not a terminal.
+pub fn confirm_estimate(
+    estimate: &JobEstimate,
+    estimated_size_bytes: i64,
+    yes: bool,
+) -> Result<()> {
+    println!();
+    println!("Cloud job estimate:");
+    println!("  Data size:      {}", format_bytes(estimated_size_bytes));
+    println!("  Instance class: {}", estimate.instance_class);
+    println!(
+        "  Duration:       {}",
+        format_duration(estimate.estimated_duration())
+    );
+    match estimate.estimated_cost_usd {
+        Some(cost) => println!("  Price:          ${:.2}", cost),
+        None => println!("  Price:          not available from the remote service"),
+    }
+    println!();
+    if yes {
+        return Ok(());
+    }
+    crate::interactive::ensure_interactive(
+        "confirmation to submit the cloud job",
+        "Pass -y/--yes (or set DATABASE_REPLICATOR_YES=1) to submit without confirming.",
+    )?;
+    let proceed = Confirm::with_theme(&ColorfulTheme::default())
+        .with_prompt("Submit the job?")
+        .default(true)
+        .interact()?;
+    if !proceed {
+        bail!("Replication cancelled before submitting the cloud job");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_from_size() {
+        let empty = JobEstimate::from_size(0);
+        assert_eq!(empty.instance_class, "c6i.large");
+        assert_eq!(empty.estimated_duration_seconds, SETUP_SECONDS);
+        assert_eq!(empty.estimated_cost_usd, None);
+
+        // 200 GB at 80 MB/s is 2560s, plus setup
+        let medium = JobEstimate::from_size(200 * GB);
+        assert_eq!(medium.instance_class, "c6i.2xlarge");
+        assert_eq!(medium.estimated_duration_seconds, 2560 + SETUP_SECONDS);
+
+        let large = JobEstimate::from_size(2048 * GB);
+        assert_eq!(large.instance_class, "c6i.4xlarge");
+        assert_eq!(large.estimated_cost_usd, None);
+    }
+
+    #[test]
+    fn test_estimate_from_service() {
+        let estimate: JobEstimate = serde_json::from_str(
+            r#"{"instance_class":"c6i.xlarge","estimated_duration_seconds":900,"estimated_cost_usd":1.25}"#,
+        )
+        .unwrap();
+        assert_eq!(estimate.estimated_cost_usd, Some(1.25));
+    }
+}
@@ -0,0 +1,30 @@
+// ABOUTME: Keeps the spec of the last cloud job this machine ran to completion
+// ABOUTME: `jobs schedule` sends it back to the orchestrator to re-run on a cron schedule
+
+use anyhow::{Context, Result};
+use std::path::PathBuf;
+
+use super::models::JobSpec;
+
+fn path() -> Result<PathBuf> {
+    Ok(crate::state::state_dir()?.join("last-job.json"))
+}
+
+/// Save the spec of a job that completed, replacing the one saved before
+///
+/// The spec holds the source and target credentials, so the file is
+/// encrypted like the other state files.
+pub fn save(spec: &JobSpec) -> Result<()> {
+    let contents = serde_json::to_string_pretty(spec)?;
+    crate::encryption::write_state_file(&path()?, &contents)
+}
+
+/// The spec of the last job that completed, if any
+pub fn load() -> Result<Option<JobSpec>> {
+    match crate::encryption::read_state_file(&path()?)? {
+        Some(contents) => Ok(Some(
+            serde_json::from_str(&contents).context("Failed to parse the saved job spec")?,
+        )),
+        None => Ok(None),
+    }
+}
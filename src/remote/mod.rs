@@ -2,7 +2,15 @@
 // ABOUTME: Handles job submission, status polling, and log retrieval
 
 pub mod client;
+pub mod estimate;
+pub mod last_job;
 pub mod models;
+pub mod sealing;
 
 pub use client::RemoteClient;
-pub use models::{FilterSpec, JobResponse, JobSpec, JobStatus};
+pub use estimate::JobEstimate;
+pub use models::{
+    FilterSpec, JobResponse, JobSpec, JobStatus, MaskingSpec, Schedule, TableFilterSpec,
+    TableRulesSpec, TimeFilterSpec, SCHEMA_VERSION,
+};
+pub use sealing::{SealedSecrets, SealingKey};
@@ -0,0 +1,237 @@
+// ABOUTME: Replica identity checks for tables published through logical replication
+// ABOUTME: Flags tables whose UPDATEs and DELETEs the source cannot publish faithfully
+
+use anyhow::{Context, Result};
+use tokio_postgres::{Client, Row};
+
+/// How the source identifies a table's changed rows in the WAL
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplicaIdentity {
+    /// The primary key, if there is one
+    Default,
+    /// A chosen unique index
+    Index,
+    /// Every column of the old row
+    Full,
+    /// Nothing; UPDATEs and DELETEs cannot be published
+    Nothing,
+}
+
+/// A table's replica identity and what backs it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableIdentity {
+    pub schema: String,
+    pub name: String,
+    pub identity: ReplicaIdentity,
+    /// Whether the key the identity relies on exists: the chosen index for
+    /// `Index`, otherwise the primary key
+    pub has_key: bool,
+    /// A unique index that could serve as the replica identity: not partial,
+    /// not deferrable, and on NOT NULL columns only
+    pub unique_index: Option<String>,
+}
+
+/// A published table whose changes would not replicate faithfully
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IdentityProblem {
+    pub table: TableIdentity,
+    /// What breaks, and how to fix it
+    pub message: String,
+}
+
+const IDENTITY_QUERY: &str = "SELECT n.nspname::text, c.relname::text, c.relreplident::text, \
+         CASE c.relreplident \
+             WHEN 'i' THEN EXISTS (SELECT 1 FROM pg_index i WHERE i.indrelid = c.oid AND i.indisreplident) \
+             ELSE EXISTS (SELECT 1 FROM pg_index i WHERE i.indrelid = c.oid AND i.indisprimary) \
+         END, \
+         (SELECT ic.relname::text FROM pg_index i JOIN pg_class ic ON ic.oid = i.indexrelid \
+          WHERE i.indrelid = c.oid AND i.indisunique AND i.indisvalid AND i.indimmediate \
+            AND i.indpred IS NULL AND i.indexprs IS NULL \
+            AND NOT EXISTS (SELECT 1 FROM pg_attribute a \
+                            WHERE a.attrelid = c.oid AND a.attnum = ANY (i.indkey) \
+                              AND NOT a.attnotnull) \
+          ORDER BY ic.relname LIMIT 1) \
+     FROM pg_class c JOIN pg_namespace n ON n.oid = c.relnamespace \
+     WHERE c.relkind IN ('r', 'p') \
+       AND n.nspname NOT IN ('pg_catalog', 'information_schema') \
+       AND n.nspname NOT LIKE 'pg\\_toast%'";
+
+impl TableIdentity {
+    fn from_row(row: &Row) -> Self {
+        let identity = match row.get::<_, String>(2).as_str() {
+            "i" => ReplicaIdentity::Index,
+            "f" => ReplicaIdentity::Full,
+            "n" => ReplicaIdentity::Nothing,
+            _ => ReplicaIdentity::Default,
+        };
+        Self {
+            schema: row.get(0),
+            name: row.get(1),
+            identity,
+            has_key: row.get(3),
+            unique_index: row.get(4),
+        }
+    }
+
+    /// Whether the source rejects UPDATEs and DELETEs once the table is published
+    pub fn blocks_changes(&self) -> bool {
+        match self.identity {
+            ReplicaIdentity::Nothing => true,
+            ReplicaIdentity::Full => false,
+            ReplicaIdentity::Default | ReplicaIdentity::Index => !self.has_key,
+        }
+    }
+
+    /// What goes wrong when this table is published, with how to fix it
+    ///
+    /// A `row_filtered` table's filter may only use replica identity columns,
+    /// and with anything short of `FULL` a row that an UPDATE moves into the
+    /// filter arrives without the unchanged large (TOASTed) values the old row
+    /// would supply; `FULL` logs the whole old row, so both work.
+    pub fn problem(&self, row_filtered: bool) -> Option<String> {
+        let table = format!("{}.{}", self.schema, self.name);
+        let quoted = format!(
+            "{}.{}",
+            crate::utils::quote_ident(&self.schema),
+            crate::utils::quote_ident(&self.name)
+        );
+        let full = format!("ALTER TABLE {} REPLICA IDENTITY FULL", quoted);
+        if self.blocks_changes() {
+            let alter = match &self.unique_index {
+                Some(index) => format!(
+                    "ALTER TABLE {} REPLICA IDENTITY USING INDEX {} (or {})",
+                    quoted,
+                    crate::utils::quote_ident(index),
+                    full
+                ),
+                None => full,
+            };
+            let (reason, fix) = match self.identity {
+                ReplicaIdentity::Nothing if self.has_key => (
+                    "has REPLICA IDENTITY NOTHING",
+                    format!("Run: ALTER TABLE {} REPLICA IDENTITY DEFAULT", quoted),
+                ),
+                ReplicaIdentity::Nothing => {
+                    ("has REPLICA IDENTITY NOTHING", format!("Run: {}", alter))
+                }
+                ReplicaIdentity::Index => (
+                    "uses a replica identity index that no longer exists",
+                    format!("Choose another unique index or run: {}", alter),
+                ),
+                _ => (
+                    "has no primary key",
+                    format!("Add a primary key or run: {}", alter),
+                ),
+            };
+            return Some(format!(
+                "{} {}, so the source rejects its UPDATEs and DELETEs once it is published. {}",
+                table, reason, fix
+            ));
+        }
+        if row_filtered && self.identity != ReplicaIdentity::Full {
+            return Some(format!(
+                "{} has a row filter but not REPLICA IDENTITY FULL, so the source rejects \
+                 UPDATEs and DELETEs if the filter uses non-key columns, and rows updated into \
+                 the filter can arrive without their unchanged large values. Run: {}",
+                table, full
+            ));
+        }
+        None
+    }
+}
+
+/// Set a table's replica identity to FULL, so every column of the old row is logged
+///
+/// Needs ownership of the table. UPDATEs and DELETEs then write more WAL, and
+/// a target without a matching key scans the table to apply each one.
+pub async fn set_replica_identity_full(client: &Client, table: &TableIdentity) -> Result<()> {
+    client
+        .batch_execute(&format!(
+            "ALTER TABLE {}.{} REPLICA IDENTITY FULL",
+            crate::utils::quote_ident(&table.schema),
+            crate::utils::quote_ident(&table.name)
+        ))
+        .await
+        .with_context(|| {
+            format!(
+                "Failed to set REPLICA IDENTITY FULL on {}.{} (table owner required)",
+                table.schema, table.name
+            )
+        })
+}
+
+/// Replica identity of every user table in the connected database
+pub async fn table_identities(client: &Client) -> Result<Vec<TableIdentity>> {
+    let rows = client
+        .query(IDENTITY_QUERY, &[])
+        .await
+        .context("Failed to read table replica identities")?;
+    Ok(rows.iter().map(TableIdentity::from_row).collect())
+}
+
+/// Replica identity of one table, or `None` if it does not exist
+pub async fn table_identity(
+    client: &Client,
+    schema: &str,
+    name: &str,
+) -> Result<Option<TableIdentity>> {
+    let row = client
+        .query_opt(
+            &format!("{} AND n.nspname = $1 AND c.relname = $2", IDENTITY_QUERY),
+            &[&schema, &name],
+        )
+        .await
+        .with_context(|| format!("Failed to read replica identity of {}.{}", schema, name))?;
+    Ok(row.as_ref().map(TableIdentity::from_row))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(identity: ReplicaIdentity, has_key: bool) -> TableIdentity {
+        TableIdentity {
+            schema: "public".to_string(),
+            name: "orders".to_string(),
+            identity,
+            has_key,
+            unique_index: None,
+        }
+    }
+
+    #[test]
+    fn test_problem() {
+        assert_eq!(table(ReplicaIdentity::Default, true).problem(false), None);
+        assert_eq!(table(ReplicaIdentity::Full, true).problem(true), None);
+
+        let no_key = table(ReplicaIdentity::Default, false)
+            .problem(false)
+            .unwrap();
+        assert!(no_key.starts_with("public.orders has no primary key"));
+        assert!(no_key.ends_with(r#"ALTER TABLE "public"."orders" REPLICA IDENTITY FULL"#));
+        let nothing = table(ReplicaIdentity::Nothing, true)
+            .problem(false)
+            .unwrap();
+        assert!(nothing.contains("has REPLICA IDENTITY NOTHING"));
+        assert!(nothing.ends_with(r#"Run: ALTER TABLE "public"."orders" REPLICA IDENTITY DEFAULT"#));
+        let nothing = table(ReplicaIdentity::Nothing, false)
+            .problem(false)
+            .unwrap();
+        assert!(nothing.ends_with(r#"Run: ALTER TABLE "public"."orders" REPLICA IDENTITY FULL"#));
+        assert_eq!(table(ReplicaIdentity::Full, false).problem(false), None);
+        let dropped_index = table(ReplicaIdentity::Index, false).problem(false).unwrap();
+        assert!(dropped_index.contains("index that no longer exists"));
+
+        let filtered = table(ReplicaIdentity::Default, true).problem(true).unwrap();
+        assert!(filtered.contains("has a row filter but not REPLICA IDENTITY FULL"));
+        assert_eq!(table(ReplicaIdentity::Index, true).problem(false), None);
+
+        let with_index = TableIdentity {
+            unique_index: Some("orders_code_key".to_string()),
+            ..table(ReplicaIdentity::Default, false)
+        };
+        assert!(with_index.problem(false).unwrap().ends_with(
+            r#"Add a primary key or run: ALTER TABLE "public"."orders" REPLICA IDENTITY USING INDEX "orders_code_key" (or ALTER TABLE "public"."orders" REPLICA IDENTITY FULL)"#
+        ));
+    }
+}
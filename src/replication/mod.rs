@@ -1,16 +1,23 @@
 // ABOUTME: Replication utilities module
 // ABOUTME: Handles PostgreSQL logical replication setup and monitoring
 
+pub mod identity;
 pub mod monitor;
 pub mod publication;
+pub mod slot;
 pub mod subscription;
 
 pub use monitor::{
-    get_replication_lag, get_subscription_status, is_replication_caught_up, SourceReplicationStats,
-    SubscriptionStats,
+    current_wal_lsn, get_replication_lag, get_subscription_status, get_subscription_tables,
+    has_replayed_lsn, is_replication_caught_up, SourceReplicationStats, SubscriptionStats,
+    SubscriptionTableStats,
 };
-pub use publication::{create_publication, drop_publication, list_publications};
+pub use publication::{
+    add_to_publication, create_publication, drop_publication, list_publications, publication_diff,
+    remove_from_publication, update_publication, PublicationDiff,
+};
+pub use slot::SnapshotSlot;
 pub use subscription::{
-    create_subscription, detect_subscription_state, drop_subscription, list_subscriptions,
-    wait_for_sync, SubscriptionState,
+    create_subscription, create_subscription_for_slot, detect_subscription_state,
+    drop_subscription, list_subscriptions, refresh_subscription, wait_for_sync, SubscriptionState,
 };
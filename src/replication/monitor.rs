@@ -48,9 +48,9 @@ pub async fn get_replication_lag(
                     write_lsn::text,
                     flush_lsn::text,
                     replay_lsn::text,
-                    EXTRACT(EPOCH FROM write_lag) * 1000 as write_lag_ms,
-                    EXTRACT(EPOCH FROM flush_lag) * 1000 as flush_lag_ms,
-                    EXTRACT(EPOCH FROM replay_lag) * 1000 as replay_lag_ms
+                    (EXTRACT(EPOCH FROM write_lag) * 1000)::bigint as write_lag_ms,
+                    (EXTRACT(EPOCH FROM flush_lag) * 1000)::bigint as flush_lag_ms,
+                    (EXTRACT(EPOCH FROM replay_lag) * 1000)::bigint as replay_lag_ms
                 FROM pg_stat_replication
                 WHERE application_name = $1",
                 &[&sub_name],
@@ -66,9 +66,9 @@ pub async fn get_replication_lag(
                     write_lsn::text,
                     flush_lsn::text,
                     replay_lsn::text,
-                    EXTRACT(EPOCH FROM write_lag) * 1000 as write_lag_ms,
-                    EXTRACT(EPOCH FROM flush_lag) * 1000 as flush_lag_ms,
-                    EXTRACT(EPOCH FROM replay_lag) * 1000 as replay_lag_ms
+                    (EXTRACT(EPOCH FROM write_lag) * 1000)::bigint as write_lag_ms,
+                    (EXTRACT(EPOCH FROM flush_lag) * 1000)::bigint as flush_lag_ms,
+                    (EXTRACT(EPOCH FROM replay_lag) * 1000)::bigint as replay_lag_ms
                 FROM pg_stat_replication",
                 &[],
             )
@@ -104,35 +104,31 @@ pub async fn get_subscription_status(
         crate::utils::validate_postgres_identifier(name).context("Invalid subscription name")?;
     }
 
-    let rows = if let Some(sub_name) = subscription_name {
-        client
-            .query(
-                "SELECT
-                    subname,
-                    pid,
-                    received_lsn::text,
-                    latest_end_lsn::text,
-                    srsubstate
-                FROM pg_stat_subscription
-                WHERE subname = $1",
-                &[&sub_name],
-            )
-            .await
-    } else {
-        client
-            .query(
-                "SELECT
-                    subname,
-                    pid,
-                    received_lsn::text,
-                    latest_end_lsn::text,
-                    srsubstate
-                FROM pg_stat_subscription",
-                &[],
-            )
-            .await
-    }
-    .context("Failed to query subscription statistics")?;
+    // pg_stat_subscription has no state column; summarize the per-table sync
+    // states from pg_subscription_rel, reporting the least advanced one
+    let query = "SELECT
+            s.subname,
+            s.pid,
+            s.received_lsn::text,
+            s.latest_end_lsn::text,
+            COALESCE((
+                SELECT CASE
+                    WHEN bool_or(r.srsubstate = 'i') THEN 'i'
+                    WHEN bool_or(r.srsubstate = 'd') THEN 'd'
+                    WHEN bool_or(r.srsubstate <> 'r') THEN 's'
+                    ELSE 'r'
+                END
+                FROM pg_subscription_rel r
+                WHERE r.srsubid = s.subid
+            ), 'r')
+        FROM pg_stat_subscription s
+        WHERE s.relid IS NULL
+          AND ($1::text IS NULL OR s.subname = $1)";
+
+    let rows = client
+        .query(query, &[&subscription_name])
+        .await
+        .context("Failed to query subscription statistics")?;
 
     let mut stats = Vec::new();
     for row in rows {
@@ -148,6 +144,96 @@ pub async fn get_subscription_status(
     Ok(stats)
 }
 
+/// Per-table progress of a subscription on the target database
+#[derive(Debug, Clone)]
+pub struct SubscriptionTableStats {
+    pub schema: String,
+    pub table: String,
+    /// Table sync state: i (initialize), d (copying data), f (finished copy), s (synchronized), r (ready)
+    pub state: String,
+    /// LSN the table's initial sync finished at; `None` until it does
+    pub sync_lsn: Option<String>,
+}
+
+/// Get the tables a subscription replicates and how far each has synced
+/// Queries pg_subscription_rel on the target database
+pub async fn get_subscription_tables(
+    client: &Client,
+    subscription_name: &str,
+) -> Result<Vec<SubscriptionTableStats>> {
+    crate::utils::validate_postgres_identifier(subscription_name)
+        .context("Invalid subscription name")?;
+
+    let rows = client
+        .query(
+            "SELECT n.nspname::text, c.relname::text, r.srsubstate::text, r.srsublsn::text
+             FROM pg_subscription_rel r
+             JOIN pg_subscription s ON s.oid = r.srsubid
+             JOIN pg_class c ON c.oid = r.srrelid
+             JOIN pg_namespace n ON n.oid = c.relnamespace
+             WHERE s.subname = $1
+             ORDER BY 1, 2",
+            &[&subscription_name],
+        )
+        .await
+        .context("Failed to query subscription tables")?;
+
+    Ok(rows
+        .iter()
+        .map(|row| SubscriptionTableStats {
+            schema: row.get(0),
+            table: row.get(1),
+            state: row.get(2),
+            sync_lsn: row.get(3),
+        })
+        .collect())
+}
+
+/// Record how far a subscription has applied each of its tables
+///
+/// PostgreSQL's apply worker commits the rows itself, so unlike xmin and
+/// binlog sync the position cannot share their transaction. Ready tables get
+/// the last WAL location the subscription reported back to the source;
+/// tables still finishing their initial copy get the LSN that copy ended at.
+/// Tables with neither yet are left out. Returns the number of tables recorded.
+pub async fn record_subscription_progress(
+    client: &Client,
+    source_url: &str,
+    subscription_name: &str,
+) -> Result<usize> {
+    let source_id = crate::xmin::progress::source_identifier(source_url)?;
+    let applied_lsn = get_subscription_status(client, Some(subscription_name))
+        .await?
+        .into_iter()
+        .next()
+        .and_then(|stats| stats.latest_end_lsn);
+    let tables = get_subscription_tables(client, subscription_name).await?;
+
+    crate::xmin::progress::ensure_progress_table(client).await?;
+    let mut recorded = 0;
+    for table in &tables {
+        let position = match table.state.as_str() {
+            "r" => applied_lsn.as_deref().or(table.sync_lsn.as_deref()),
+            _ => table.sync_lsn.as_deref(),
+        };
+        let Some(position) = position else {
+            continue;
+        };
+        crate::xmin::progress::record_progress(
+            client,
+            &source_id,
+            &table.schema,
+            &table.table,
+            crate::xmin::progress::PositionKind::Lsn,
+            position,
+            0,
+        )
+        .await?;
+        recorded += 1;
+    }
+    Ok(recorded)
+}
+
 /// Check if replication is caught up (no lag)
 /// Returns true if all replication slots have < 1 second of replay lag
 pub async fn is_replication_caught_up(
@@ -176,6 +262,33 @@ pub async fn is_replication_caught_up(
     Ok(true)
 }
 
+/// Current WAL write position of the source, as text (e.g. `0/16B3748`)
+pub async fn current_wal_lsn(client: &Client) -> Result<String> {
+    let row = client
+        .query_one("SELECT pg_current_wal_lsn()::text", &[])
+        .await
+        .context("Failed to query current WAL position")?;
+    Ok(row.get(0))
+}
+
+/// Check whether a subscriber has replayed the source WAL up to `lsn`
+/// Returns false when the subscription has no active walsender on the source
+pub async fn has_replayed_lsn(client: &Client, subscription_name: &str, lsn: &str) -> Result<bool> {
+    crate::utils::validate_postgres_identifier(subscription_name)
+        .context("Invalid subscription name")?;
+
+    let row = client
+        .query_one(
+            "SELECT COALESCE(bool_and(replay_lsn >= $1::text::pg_lsn), false)
+            FROM pg_stat_replication
+            WHERE application_name = $2",
+            &[&lsn, &subscription_name],
+        )
+        .await
+        .context("Failed to compare replay position")?;
+    Ok(row.get(0))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -4,13 +4,16 @@
 use anyhow::{bail, Context, Result};
 use tokio_postgres::Client;
 
+use super::identity::{table_identities, table_identity, IdentityProblem};
 use crate::filters::ReplicationFilter;
 use crate::table_rules::TableRuleKind;
 
-/// Create a publication for tables with optional filtering
+/// Create a publication for the tables the filter selects
 ///
-/// When table filters are specified, creates a publication for only the filtered tables.
-/// Without filters, creates a publication for all tables.
+/// The publication lists its tables explicitly, even without filters, so it
+/// never starts carrying a table the target has no schema for. Tables added
+/// later are picked up with [`update_publication`]. A database without any
+/// tables gets an empty publication.
 ///
 /// # Arguments
 ///
@@ -38,20 +41,65 @@ pub async fn create_publication(
 
     tracing::info!("Creating publication '{}'...", publication_name);
 
-    if filter.is_empty() {
+    let tables = tables_for_publication(client, db_name, filter).await?;
+    if tables.is_empty() {
+        if !filter.is_empty() {
+            bail!(
+                "No tables available for publication '{}' after applying filters and schema-only rules",
+                publication_name
+            );
+        }
+        tracing::warn!(
+            "⚠ Database '{}' has no tables yet; publication '{}' starts empty",
+            db_name,
+            publication_name
+        );
         let query = format!(
-            "CREATE PUBLICATION {} FOR ALL TABLES",
+            "CREATE PUBLICATION {}",
             crate::utils::quote_ident(publication_name)
         );
         return execute_publication_query(client, publication_name, &query).await;
     }
+    check_predicate_support(client, &tables).await?;
+
+    let query = format!(
+        "CREATE PUBLICATION {} FOR TABLE {}",
+        crate::utils::quote_ident(publication_name),
+        table_clauses(&tables)
+    );
+
+    execute_publication_query(client, publication_name, &query).await?;
+    for problem in identity_problems(client, &tables).await? {
+        tracing::warn!("⚠ {}", problem.message);
+    }
+    Ok(())
+}
 
-    let tables = crate::migration::list_tables(client).await?;
+/// A table a publication carries, with its row filter if any
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PublicationTable {
+    schema: String,
+    name: String,
+    predicate: Option<String>,
+}
 
-    let mut plain_tables = Vec::new();
-    let mut predicate_tables = Vec::new();
+impl PublicationTable {
+    fn qualified_name(&self) -> String {
+        format!("{}.{}", self.schema, self.name)
+    }
+}
 
-    for table in tables {
+/// Tables the filter replicates through logical replication
+///
+/// Schema-only, sampled, and `UNLOGGED` tables are left out; predicate tables
+/// carry their predicate as a row filter.
+async fn tables_for_publication(
+    client: &Client,
+    db_name: &str,
+    filter: &ReplicationFilter,
+) -> Result<Vec<PublicationTable>> {
+    let mut selected = Vec::new();
+    for table in crate::migration::list_tables(client).await? {
         // Build "schema.table" identifier for include/exclude logic
         let table_identifier = if table.schema == "public" {
             table.name.clone()
@@ -62,74 +110,379 @@ pub async fn create_publication(
         if !filter.should_replicate_table(db_name, &table_identifier) {
             continue;
         }
-
-        // Validate schema/table names
-        crate::utils::validate_postgres_identifier(&table.schema).with_context(|| {
-            format!(
-                "Invalid schema name '{}' for table '{}': must be a valid PostgreSQL identifier",
-                table.schema, table.name
-            )
-        })?;
-        crate::utils::validate_postgres_identifier(&table.name).with_context(|| {
-            format!(
-                "Invalid table name '{}' in schema '{}': must be a valid PostgreSQL identifier",
-                table.name, table.schema
-            )
-        })?;
-
-        let fq_table = format!("\"{}\".\"{}\"", table.schema, table.name);
-
-        match filter
-            .table_rules()
-            .rule_for_table(db_name, &table.schema, &table.name)
-        {
-            Some(TableRuleKind::SchemaOnly) => {
-                tracing::debug!(
-                    "Excluding table '{}' from publication (schema-only)",
-                    table_identifier
-                );
-            }
-            Some(TableRuleKind::Predicate(pred)) => {
-                predicate_tables.push((fq_table, pred));
-            }
-            None => {
-                plain_tables.push(fq_table);
-            }
+        if table.unlogged {
+            // PostgreSQL refuses to publish tables that bypass the WAL
+            tracing::debug!(
+                "Excluding table '{}' from publication (unlogged)",
+                table_identifier
+            );
+            continue;
         }
-    }
 
-    if plain_tables.is_empty() && predicate_tables.is_empty() {
-        bail!(
-            "No tables available for publication '{}' after applying filters and schema-only rules",
-            publication_name
-        );
+        let predicate =
+            match filter
+                .table_rules()
+                .rule_for_table(db_name, &table.schema, &table.name)
+            {
+                Some(TableRuleKind::SchemaOnly) => {
+                    tracing::debug!(
+                        "Excluding table '{}' from publication (schema-only)",
+                        table_identifier
+                    );
+                    continue;
+                }
+                Some(TableRuleKind::Sample(_)) => {
+                    tracing::debug!(
+                        "Excluding table '{}' from publication (sampled snapshot)",
+                        table_identifier
+                    );
+                    continue;
+                }
+                Some(TableRuleKind::Predicate(pred)) => Some(pred),
+                None => None,
+            };
+        selected.push(PublicationTable {
+            schema: table.schema,
+            name: table.name,
+            predicate,
+        });
     }
+    Ok(selected)
+}
+
+/// The `FOR TABLE` list: plain tables first, then tables with row filters
+fn table_clauses(tables: &[PublicationTable]) -> String {
+    let quoted = |table: &PublicationTable| {
+        format!(
+            "{}.{}",
+            crate::utils::quote_ident(&table.schema),
+            crate::utils::quote_ident(&table.name)
+        )
+    };
+    let plain = tables
+        .iter()
+        .filter(|table| table.predicate.is_none())
+        .map(quoted);
+    let filtered = tables.iter().filter_map(|table| {
+        table
+            .predicate
+            .as_ref()
+            .map(|predicate| format!("{} WHERE ({})", quoted(table), predicate))
+    });
+    plain.chain(filtered).collect::<Vec<_>>().join(", ")
+}
 
-    let has_predicates = !predicate_tables.is_empty();
+async fn check_predicate_support(client: &Client, tables: &[PublicationTable]) -> Result<()> {
+    if tables.iter().all(|table| table.predicate.is_none()) {
+        return Ok(());
+    }
     let server_version = get_server_version(client).await?;
-    if has_predicates && server_version < 150000 {
+    if server_version < 150000 {
         bail!(
             "Table-level predicates require PostgreSQL 15+. Detected server version {}.\n\
              Upgrade the source database or remove --table-filter/--time-filter for logical replication.",
             server_version
         );
     }
+    Ok(())
+}
+
+/// How a publication's tables differ from the tables the filter selects
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PublicationDiff {
+    /// `schema.table` names the filter selects but the publication lacks
+    pub added: Vec<String>,
+    /// `schema.table` names in the publication the filter no longer selects
+    pub removed: Vec<String>,
+}
+
+impl PublicationDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+fn diff_tables(current: &[String], desired: &[String]) -> PublicationDiff {
+    PublicationDiff {
+        added: desired
+            .iter()
+            .filter(|table| !current.contains(table))
+            .cloned()
+            .collect(),
+        removed: current
+            .iter()
+            .filter(|table| !desired.contains(table))
+            .cloned()
+            .collect(),
+    }
+}
+
+/// Whether a publication was created `FOR ALL TABLES`
+///
+/// Such publications already carry every table, including new ones, and
+/// cannot be narrowed to a table list in place.
+pub async fn is_all_tables_publication(client: &Client, publication_name: &str) -> Result<bool> {
+    let row = client
+        .query_opt(
+            "SELECT puballtables FROM pg_publication WHERE pubname = $1",
+            &[&publication_name],
+        )
+        .await
+        .context("Failed to look up publication")?;
+    Ok(row.is_some_and(|row| row.get(0)))
+}
+
+/// `schema.table` names a publication currently carries
+pub async fn published_tables(client: &Client, publication_name: &str) -> Result<Vec<String>> {
+    Ok(published_table_names(client, publication_name)
+        .await?
+        .iter()
+        .map(|(schema, table)| format!("{}.{}", schema, table))
+        .collect())
+}
+
+async fn published_table_names(
+    client: &Client,
+    publication_name: &str,
+) -> Result<Vec<(String, String)>> {
+    let rows = client
+        .query(
+            "SELECT schemaname::text, tablename::text \
+             FROM pg_publication_tables WHERE pubname = $1 ORDER BY 1, 2",
+            &[&publication_name],
+        )
+        .await
+        .context("Failed to list publication tables")?;
+    Ok(rows.iter().map(|row| (row.get(0), row.get(1))).collect())
+}
+
+/// `schema.table` names the filter publishes in this database
+pub async fn selected_tables(
+    client: &Client,
+    db_name: &str,
+    filter: &ReplicationFilter,
+) -> Result<Vec<String>> {
+    Ok(tables_for_publication(client, db_name, filter)
+        .await?
+        .iter()
+        .map(PublicationTable::qualified_name)
+        .collect())
+}
+
+/// Compare an existing publication with the tables the filter selects
+///
+/// Returns `None` for `FOR ALL TABLES` publications, whose membership
+/// follows the database rather than the filter.
+pub async fn publication_diff(
+    client: &Client,
+    db_name: &str,
+    publication_name: &str,
+    filter: &ReplicationFilter,
+) -> Result<Option<PublicationDiff>> {
+    if is_all_tables_publication(client, publication_name).await? {
+        return Ok(None);
+    }
+    let current = published_tables(client, publication_name).await?;
+    let desired = selected_tables(client, db_name, filter).await?;
+    Ok(Some(diff_tables(&current, &desired)))
+}
+
+/// Set a publication's tables, and their row filters, to what the filter selects
+///
+/// `FOR ALL TABLES` publications are left unchanged.
+pub async fn update_publication(
+    client: &Client,
+    db_name: &str,
+    publication_name: &str,
+    filter: &ReplicationFilter,
+) -> Result<PublicationDiff> {
+    crate::utils::validate_postgres_identifier(publication_name).with_context(|| {
+        format!(
+            "Invalid publication name '{}': must be a valid PostgreSQL identifier",
+            publication_name
+        )
+    })?;
+    if is_all_tables_publication(client, publication_name).await? {
+        tracing::info!(
+            "Publication '{}' is FOR ALL TABLES; it already carries every table",
+            publication_name
+        );
+        return Ok(PublicationDiff::default());
+    }
 
-    let mut clauses = Vec::new();
-    clauses.extend(plain_tables);
-    clauses.extend(
-        predicate_tables
+    let tables = tables_for_publication(client, db_name, filter).await?;
+    let current = published_tables(client, publication_name).await?;
+    let desired: Vec<String> = tables
+        .iter()
+        .map(PublicationTable::qualified_name)
+        .collect();
+    let diff = diff_tables(&current, &desired);
+
+    let query = if tables.is_empty() {
+        if current.is_empty() {
+            return Ok(diff);
+        }
+        // SET TABLE needs at least one table
+        let quoted: Vec<String> = published_table_names(client, publication_name)
+            .await?
             .iter()
-            .map(|(table, predicate)| format!("{} WHERE ({})", table, predicate)),
+            .map(|(schema, name)| {
+                format!(
+                    "{}.{}",
+                    crate::utils::quote_ident(schema),
+                    crate::utils::quote_ident(name)
+                )
+            })
+            .collect();
+        format!(
+            "ALTER PUBLICATION {} DROP TABLE {}",
+            crate::utils::quote_ident(publication_name),
+            quoted.join(", ")
+        )
+    } else {
+        check_predicate_support(client, &tables).await?;
+        format!(
+            "ALTER PUBLICATION {} SET TABLE {}",
+            crate::utils::quote_ident(publication_name),
+            table_clauses(&tables)
+        )
+    };
+    client.execute(&query, &[]).await.with_context(|| {
+        format!(
+            "Failed to update tables of publication '{}'",
+            publication_name
+        )
+    })?;
+    tracing::info!(
+        "✓ Publication '{}' updated: {} table(s) added, {} removed",
+        publication_name,
+        diff.added.len(),
+        diff.removed.len()
     );
+    Ok(diff)
+}
 
+/// Add `tables` (`schema.table`) to a publication, with the filter's row filters
+///
+/// Unlike [`update_publication`], tables already published stay, whether or
+/// not the filter still selects them.
+pub async fn add_to_publication(
+    client: &Client,
+    db_name: &str,
+    publication_name: &str,
+    filter: &ReplicationFilter,
+    tables: &[String],
+) -> Result<()> {
+    let tables: Vec<PublicationTable> = tables_for_publication(client, db_name, filter)
+        .await?
+        .into_iter()
+        .filter(|table| tables.contains(&table.qualified_name()))
+        .collect();
+    if tables.is_empty() {
+        return Ok(());
+    }
+    check_predicate_support(client, &tables).await?;
     let query = format!(
-        "CREATE PUBLICATION {} FOR TABLE {}",
+        "ALTER PUBLICATION {} ADD TABLE {}",
+        crate::utils::quote_ident(publication_name),
+        table_clauses(&tables)
+    );
+    client
+        .execute(&query, &[])
+        .await
+        .with_context(|| format!("Failed to add tables to publication '{}'", publication_name))?;
+    tracing::info!(
+        "✓ Publication '{}' updated: {} table(s) added",
+        publication_name,
+        tables.len()
+    );
+    Ok(())
+}
+
+/// Remove `tables` (`schema.table`) from a publication
+///
+/// Tables the publication does not carry are ignored. Their changes stop
+/// being decoded from the slot from this point on.
+pub async fn remove_from_publication(
+    client: &Client,
+    publication_name: &str,
+    tables: &[String],
+) -> Result<()> {
+    let quoted: Vec<String> = published_table_names(client, publication_name)
+        .await?
+        .iter()
+        .filter(|(schema, name)| tables.contains(&format!("{}.{}", schema, name)))
+        .map(|(schema, name)| {
+            format!(
+                "{}.{}",
+                crate::utils::quote_ident(schema),
+                crate::utils::quote_ident(name)
+            )
+        })
+        .collect();
+    if quoted.is_empty() {
+        return Ok(());
+    }
+    let query = format!(
+        "ALTER PUBLICATION {} DROP TABLE {}",
         crate::utils::quote_ident(publication_name),
-        clauses.join(", ")
+        quoted.join(", ")
+    );
+    client.execute(&query, &[]).await.with_context(|| {
+        format!(
+            "Failed to remove tables from publication '{}'",
+            publication_name
+        )
+    })?;
+    tracing::info!(
+        "✓ Publication '{}' updated: {} table(s) removed",
+        publication_name,
+        quoted.len()
     );
+    Ok(())
+}
+
+/// Whether a `schema.table` has no primary key or other replica identity
+///
+/// Once such a table is published, the source rejects its UPDATEs and
+/// DELETEs, since there is nothing to identify the changed rows by.
+pub async fn lacks_replica_identity(client: &Client, table: &str) -> Result<bool> {
+    let (schema, name) = table.split_once('.').unwrap_or(("public", table));
+    Ok(table_identity(client, schema, name)
+        .await?
+        .is_some_and(|table| table.blocks_changes()))
+}
 
-    execute_publication_query(client, publication_name, &query).await
+/// Replica identity problems of the tables the filter publishes in this database
+///
+/// See [`super::identity::TableIdentity::problem`] for what counts as a problem.
+pub async fn replica_identity_problems(
+    client: &Client,
+    db_name: &str,
+    filter: &ReplicationFilter,
+) -> Result<Vec<IdentityProblem>> {
+    let tables = tables_for_publication(client, db_name, filter).await?;
+    identity_problems(client, &tables).await
+}
+
+async fn identity_problems(
+    client: &Client,
+    tables: &[PublicationTable],
+) -> Result<Vec<IdentityProblem>> {
+    let identities = table_identities(client).await?;
+    Ok(tables
+        .iter()
+        .filter_map(|table| {
+            let identity = identities
+                .iter()
+                .find(|identity| identity.schema == table.schema && identity.name == table.name)?;
+            let message = identity.problem(table.predicate.is_some())?;
+            Some(IdentityProblem {
+                table: identity.clone(),
+                message,
+            })
+        })
+        .collect())
 }
 
 /// Extract detailed error message from tokio-postgres error
@@ -271,6 +624,40 @@ mod tests {
     use super::*;
     use crate::postgres::connect;
 
+    #[test]
+    fn test_table_clauses_quote_names_and_put_row_filters_last() {
+        let tables = vec![
+            PublicationTable {
+                schema: "public".to_string(),
+                name: "orders".to_string(),
+                predicate: Some("created_at > now() - interval '90 days'".to_string()),
+            },
+            PublicationTable {
+                schema: "sales".to_string(),
+                name: "Line Items".to_string(),
+                predicate: None,
+            },
+        ];
+        assert_eq!(
+            table_clauses(&tables),
+            "\"sales\".\"Line Items\", \"public\".\"orders\" WHERE (created_at > now() - interval '90 days')"
+        );
+    }
+
+    #[test]
+    fn test_diff_tables() {
+        let current = vec!["public.users".to_string(), "public.audit".to_string()];
+        let desired = vec!["public.users".to_string(), "public.orders".to_string()];
+        assert_eq!(
+            diff_tables(&current, &desired),
+            PublicationDiff {
+                added: vec!["public.orders".to_string()],
+                removed: vec!["public.audit".to_string()],
+            }
+        );
+        assert!(diff_tables(&desired, &desired).is_empty());
+    }
+
     #[tokio::test]
     #[ignore]
     async fn test_create_and_list_publications() {
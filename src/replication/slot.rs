@@ -0,0 +1,650 @@
+// ABOUTME: Logical replication slots created together with an exported snapshot
+// ABOUTME: Lets init copy data at the slot's start point so streaming continues with no gap
+
+use anyhow::{bail, Context, Result};
+use std::process::Stdio;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdout, Command};
+use tokio_postgres::types::Type;
+use tokio_postgres::Client;
+
+use crate::postgres::pooler;
+use crate::state::TrackedSlot;
+
+/// A logical slot whose creating session still holds the exported snapshot
+///
+/// The snapshot sees exactly the data committed before the slot's consistent
+/// point, and the slot streams every change committed after it. Copying data
+/// at [`snapshot_id`](Self::snapshot_id) and then subscribing to the slot
+/// therefore neither misses nor repeats a change.
+///
+/// Replication commands need a walsender connection, which tokio-postgres
+/// cannot open, so the slot is created from a `psql` session in replication
+/// mode. The snapshot lives until [`release`](Self::release) ends that session.
+pub struct SnapshotSlot {
+    name: String,
+    consistent_lsn: String,
+    snapshot_id: String,
+    session: Child,
+    _pgpass: crate::utils::PgPassFile,
+}
+
+impl SnapshotSlot {
+    /// Create a `pgoutput` slot named `slot_name` in the database of `source_db_url`
+    ///
+    /// Slot creation waits for transactions already running on the source to
+    /// finish, so a long-running transaction delays it.
+    pub async fn create(source_db_url: &str, slot_name: &str) -> Result<Self> {
+        crate::utils::validate_postgres_identifier(slot_name)
+            .with_context(|| format!("Invalid replication slot name '{}'", slot_name))?;
+
+        let mut parts = crate::utils::parse_postgres_url(source_db_url).with_context(|| {
+            format!(
+                "Failed to parse source URL: {}",
+                crate::redact::redact(source_db_url)
+            )
+        })?;
+        crate::postgres::iam::apply_to_parts(&mut parts).await?;
+        let pgpass = crate::utils::PgPassFile::new(&parts)
+            .context("Failed to create .pgpass file for authentication")?;
+
+        let mut cmd = Command::new("psql");
+        cmd.arg("-X")
+            .arg("--no-align")
+            .arg("--tuples-only")
+            .arg("--quiet")
+            .arg("-v")
+            .arg("ON_ERROR_STOP=1")
+            .arg("--host")
+            .arg(&parts.host)
+            .arg("--port")
+            .arg(parts.port.to_string())
+            .arg("--dbname")
+            .arg(replication_conninfo(&parts.database))
+            .env("PGPASSFILE", pgpass.path())
+            .env("PGCONNECT_TIMEOUT", "30")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        if let Some(user) = &parts.user {
+            cmd.arg("--username").arg(user);
+        }
+        for (env_var, value) in parts.to_pg_env_vars() {
+            cmd.env(env_var, value);
+        }
+        for (env_var, value) in crate::utils::get_keepalive_env_vars() {
+            cmd.env(env_var, value);
+        }
+
+        let mut session = cmd.spawn().context(
+            "Failed to execute psql. Is PostgreSQL client installed?\n\
+             Install with:\n\
+             - Ubuntu/Debian: sudo apt-get install postgresql-client\n\
+             - macOS: brew install postgresql\n\
+             - RHEL/CentOS: sudo yum install postgresql",
+        )?;
+
+        tracing::info!(
+            "  Creating replication slot '{}' (waits for open transactions on the source)...",
+            slot_name
+        );
+        let stdin = session.stdin.as_mut().expect("stdin is piped");
+        stdin
+            .write_all(
+                format!(
+                    "CREATE_REPLICATION_SLOT {} LOGICAL pgoutput EXPORT_SNAPSHOT;\n",
+                    crate::utils::quote_ident(slot_name)
+                )
+                .as_bytes(),
+            )
+            .await
+            .context("Failed to send CREATE_REPLICATION_SLOT to psql")?;
+        stdin.flush().await?;
+
+        let stdout = session.stdout.take().expect("stdout is piped");
+        let line = match read_first_line(stdout).await? {
+            Some(line) => line,
+            None => {
+                let mut stderr = String::new();
+                if let Some(mut pipe) = session.stderr.take() {
+                    let _ = pipe.read_to_string(&mut stderr).await;
+                }
+                let _ = session.wait().await;
+                bail!(
+                    "Failed to create replication slot '{}': {}\n\
+                     The source user needs the REPLICATION attribute, wal_level must be 'logical', \
+                     and max_replication_slots must have a free slot.",
+                    slot_name,
+                    stderr.trim()
+                );
+            }
+        };
+        let (name, consistent_lsn, snapshot_id) = parse_slot_row(&line)?;
+        tracing::info!(
+            "  ✓ Replication slot '{}' starts at {} (snapshot {})",
+            name,
+            consistent_lsn,
+            snapshot_id
+        );
+
+        Ok(Self {
+            name,
+            consistent_lsn,
+            snapshot_id,
+            session,
+            _pgpass: pgpass,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The LSN streaming starts from; everything before it is in the snapshot
+    pub fn consistent_lsn(&self) -> &str {
+        &self.consistent_lsn
+    }
+
+    /// Snapshot id for `pg_dump --snapshot` and `SET TRANSACTION SNAPSHOT`
+    pub fn snapshot_id(&self) -> &str {
+        &self.snapshot_id
+    }
+
+    /// End the creating session, keeping the slot for a subscription to use
+    pub async fn release(mut self) -> Result<()> {
+        drop(self.session.stdin.take());
+        let status = self
+            .session
+            .wait()
+            .await
+            .context("Failed to wait for psql to exit")?;
+        if !status.success() {
+            tracing::warn!(
+                "psql session for replication slot '{}' exited with {}",
+                self.name,
+                status
+            );
+        }
+        Ok(())
+    }
+
+    /// End the creating session and drop the slot, so the source stops retaining WAL for it
+    pub async fn abandon(self, source_db_url: &str) -> Result<()> {
+        let name = self.name.clone();
+        self.release().await?;
+        let client = crate::postgres::connect_with_retry(source_db_url).await?;
+        drop_inactive_slot(&client, &name).await?;
+        untrack(&name, source_db_url);
+        Ok(())
+    }
+}
+
+/// Make `slot_name` available for a new snapshot slot
+///
+/// An inactive slot of that name is left over from an interrupted run and is
+/// dropped; an active one belongs to a running subscription and is an error.
+pub async fn clear_stale_slot(client: &Client, source_db_url: &str, slot_name: &str) -> Result<()> {
+    let row = pooler::query_opt(
+        client,
+        "SELECT active FROM pg_replication_slots WHERE slot_name = $1",
+        &[(&slot_name, Type::TEXT)],
+    )
+    .await
+    .context("Failed to look up replication slots")?;
+    match row.map(|row| row.get::<_, bool>(0)) {
+        None => Ok(()),
+        Some(true) => bail!(
+            "Replication slot '{}' is in use by a running subscription.\n\
+             Drop that subscription on the target before re-running init with sync.",
+            slot_name
+        ),
+        Some(false) => {
+            tracing::warn!(
+                "  Dropping replication slot '{}' left by an earlier run",
+                slot_name
+            );
+            drop_inactive_slot(client, slot_name).await?;
+            untrack(slot_name, source_db_url);
+            Ok(())
+        }
+    }
+}
+
+/// Whether a replication slot exists on the source
+pub async fn slot_exists(client: &Client, slot_name: &str) -> Result<bool> {
+    let row = pooler::query_opt(
+        client,
+        "SELECT 1 FROM pg_replication_slots WHERE slot_name = $1",
+        &[(&slot_name, Type::TEXT)],
+    )
+    .await
+    .context("Failed to look up replication slots")?;
+    Ok(row.is_some())
+}
+
+async fn drop_inactive_slot(client: &Client, slot_name: &str) -> Result<()> {
+    pooler::execute(
+        client,
+        "SELECT pg_drop_replication_slot(slot_name) FROM pg_replication_slots \
+         WHERE slot_name = $1 AND NOT active",
+        &[(&slot_name, Type::TEXT)],
+    )
+    .await
+    .with_context(|| format!("Failed to drop replication slot '{}'", slot_name))?;
+    Ok(())
+}
+
+/// Inactive slots holding more WAL than this get a warning, repeated each time
+/// they hold another multiple of it
+pub const ORPHAN_WAL_WARNING_BYTES: i64 = 1 << 30;
+
+/// A replication slot on a source server
+#[derive(Debug, Clone)]
+pub struct SlotInfo {
+    pub name: String,
+    /// Database of a logical slot; `None` for physical slots
+    pub database: Option<String>,
+    pub slot_type: String,
+    pub active: bool,
+    /// WAL the server keeps for the slot, from its restart LSN to the current position
+    pub retained_wal_bytes: Option<i64>,
+}
+
+/// Every replication slot on the server `client` is connected to
+pub async fn list_slots(client: &Client) -> Result<Vec<SlotInfo>> {
+    let rows = pooler::query(
+        client,
+        "SELECT slot_name::text, database::text, slot_type, active, \
+                pg_wal_lsn_diff(pg_current_wal_lsn(), restart_lsn)::bigint \
+         FROM pg_replication_slots ORDER BY slot_name",
+        &[],
+    )
+    .await
+    .context("Failed to list replication slots")?;
+    Ok(rows
+        .iter()
+        .map(|row| SlotInfo {
+            name: row.get(0),
+            database: row.get(1),
+            slot_type: row.get(2),
+            active: row.get(3),
+            retained_wal_bytes: row.get(4),
+        })
+        .collect())
+}
+
+/// Drop a replication slot by name
+///
+/// A slot in use by a walsender is only dropped with `force`, which first
+/// disconnects the subscriber using it.
+pub async fn drop_slot(client: &Client, slot_name: &str, force: bool) -> Result<()> {
+    let row = pooler::query_opt(
+        client,
+        "SELECT active_pid FROM pg_replication_slots WHERE slot_name = $1",
+        &[(&slot_name, Type::TEXT)],
+    )
+    .await
+    .context("Failed to look up replication slots")?;
+    let Some(row) = row else {
+        bail!("Replication slot '{}' does not exist", slot_name);
+    };
+    if let Some(pid) = row.get::<_, Option<i32>>(0) {
+        if !force {
+            bail!(
+                "Replication slot '{}' is in use by process {}.\n\
+                 Drop the subscription that uses it, or pass --force to disconnect it.",
+                slot_name,
+                pid
+            );
+        }
+        tracing::warn!(
+            "Disconnecting process {} that is using replication slot '{}'",
+            pid,
+            slot_name
+        );
+        pooler::execute(
+            client,
+            "SELECT pg_terminate_backend($1)",
+            &[(&pid, Type::INT4)],
+        )
+        .await
+        .context("Failed to disconnect the slot's walsender")?;
+        // The slot is released once the walsender has exited
+        for _ in 0..50 {
+            if !slot_is_active(client, slot_name).await? {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+        }
+    }
+    pooler::execute(
+        client,
+        "SELECT pg_drop_replication_slot($1)",
+        &[(&slot_name, Type::TEXT)],
+    )
+    .await
+    .with_context(|| format!("Failed to drop replication slot '{}'", slot_name))?;
+    Ok(())
+}
+
+async fn slot_is_active(client: &Client, slot_name: &str) -> Result<bool> {
+    let row = pooler::query_opt(
+        client,
+        "SELECT active FROM pg_replication_slots WHERE slot_name = $1",
+        &[(&slot_name, Type::TEXT)],
+    )
+    .await
+    .context("Failed to look up replication slots")?;
+    Ok(row.is_some_and(|row| row.get(0)))
+}
+
+/// Remember a slot created for the subscription on `target_db_url`
+///
+/// Tracking is best effort: a state file that cannot be written only costs
+/// automatic cleanup, so failures are logged rather than returned.
+pub fn track(slot_name: &str, source_db_url: &str, target_db_url: &str) {
+    let result = update_tracked(|slots| {
+        if !slots
+            .iter()
+            .any(|slot| is_same_slot(slot, slot_name, source_db_url))
+        {
+            slots.push(TrackedSlot {
+                name: slot_name.to_string(),
+                source_url: source_db_url.to_string(),
+                target_url: target_db_url.to_string(),
+                created_at: chrono::Utc::now(),
+            });
+        }
+    });
+    if let Err(e) = result {
+        tracing::warn!(
+            "⚠ Failed to record replication slot '{}': {:#}",
+            slot_name,
+            e
+        );
+    }
+}
+
+/// Forget a slot that was dropped
+pub fn untrack(slot_name: &str, source_db_url: &str) {
+    let result = update_tracked(|slots| {
+        slots.retain(|slot| !is_same_slot(slot, slot_name, source_db_url));
+    });
+    if let Err(e) = result {
+        tracing::warn!("⚠ Failed to update tracked replication slots: {:#}", e);
+    }
+}
+
+/// Slots this tool created, as recorded in the local state file
+pub fn tracked_slots() -> Result<Vec<TrackedSlot>> {
+    Ok(crate::state::load()?.replication_slots)
+}
+
+fn update_tracked(change: impl FnOnce(&mut Vec<TrackedSlot>)) -> Result<()> {
+    let mut state = crate::state::load()?;
+    let before = state.replication_slots.clone();
+    change(&mut state.replication_slots);
+    if state.replication_slots != before {
+        crate::state::save(&state)?;
+    }
+    Ok(())
+}
+
+/// Slot names are unique per server, whatever database or user a URL names
+fn is_same_slot(slot: &TrackedSlot, slot_name: &str, source_db_url: &str) -> bool {
+    slot.name == slot_name && source_key(&slot.source_url) == source_key(source_db_url)
+}
+
+fn source_key(url: &str) -> String {
+    crate::postgres::pool::host_key(url)
+}
+
+/// Drop tracked slots that nothing streams from any more
+///
+/// A tracked slot is orphaned when it is inactive and the target no longer
+/// has the subscription it was created for. Slots that are gone are
+/// forgotten; slots whose source or target cannot be reached are kept.
+/// Returns the dropped slots as `name (database)`.
+pub async fn drop_orphaned_slots() -> Result<Vec<String>> {
+    let mut dropped = Vec::new();
+    for slot in tracked_slots()? {
+        match drop_if_orphaned(&slot).await {
+            Ok(true) => {
+                let database = crate::utils::parse_postgres_url(&slot.source_url)
+                    .map(|parts| parts.database)
+                    .unwrap_or_default();
+                tracing::info!(
+                    "  ✓ Dropped orphaned replication slot '{}' in '{}'",
+                    slot.name,
+                    database
+                );
+                dropped.push(format!("{} ({})", slot.name, database));
+            }
+            Ok(false) => {}
+            Err(e) => tracing::warn!(
+                "⚠ Could not check replication slot '{}': {:#}",
+                slot.name,
+                e
+            ),
+        }
+    }
+    Ok(dropped)
+}
+
+async fn drop_if_orphaned(slot: &TrackedSlot) -> Result<bool> {
+    let source = crate::postgres::connect_with_retry(&slot.source_url).await?;
+    let active = pooler::query_opt(
+        &source,
+        "SELECT active FROM pg_replication_slots WHERE slot_name = $1",
+        &[(&slot.name, Type::TEXT)],
+    )
+    .await
+    .context("Failed to look up replication slots")?
+    .map(|row| row.get::<_, bool>(0));
+    match active {
+        None => {
+            untrack(&slot.name, &slot.source_url);
+            Ok(false)
+        }
+        Some(true) => Ok(false),
+        Some(false) => {
+            let target = crate::postgres::connect_with_retry(&slot.target_url).await?;
+            if super::list_subscriptions(&target)
+                .await?
+                .contains(&slot.name)
+            {
+                return Ok(false);
+            }
+            drop_inactive_slot(&source, &slot.name).await?;
+            untrack(&slot.name, &slot.source_url);
+            Ok(true)
+        }
+    }
+}
+
+/// Warns about inactive slots whose retained WAL keeps growing
+///
+/// An inactive slot pins WAL on the source until it is dropped, which can
+/// eventually fill the disk. Each slot is reported when its retained WAL
+/// passes [`ORPHAN_WAL_WARNING_BYTES`] and again at every further multiple.
+#[derive(Default)]
+pub struct SlotWatchdog {
+    /// Highest multiple of the threshold reported per `host:port/slot`
+    reported: std::sync::Mutex<std::collections::HashMap<String, i64>>,
+}
+
+impl SlotWatchdog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Check the slots on the server `client` is connected to (`url`)
+    pub async fn check(&self, url: &str, client: &Client) -> Result<()> {
+        let server = source_key(url);
+        for slot in list_slots(client).await? {
+            let key = format!("{}/{}", server, slot.name);
+            let mut reported = self.reported.lock().unwrap();
+            if slot.active {
+                reported.remove(&key);
+                continue;
+            }
+            let previous = reported.get(&key).copied().unwrap_or(0);
+            if let Some(level) = warning_level(previous, slot.retained_wal_bytes) {
+                reported.insert(key, level);
+                tracing::warn!(
+                    "⚠ Inactive replication slot '{}' on {} is holding {} of WAL and growing",
+                    slot.name,
+                    server,
+                    crate::migration::estimation::format_bytes(
+                        slot.retained_wal_bytes.unwrap_or_default()
+                    )
+                );
+                tracing::warn!(
+                    "  If nothing uses it, drop it: database-replicator slots drop --source <url> {}",
+                    slot.name
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The new threshold multiple to report, if `retained` passed one above `previous`
+fn warning_level(previous: i64, retained: Option<i64>) -> Option<i64> {
+    let level = retained? / ORPHAN_WAL_WARNING_BYTES;
+    (level > previous).then_some(level)
+}
+
+/// A libpq conninfo string for a logical walsender on `database`
+fn replication_conninfo(database: &str) -> String {
+    let escaped = database.replace('\\', "\\\\").replace('\'', "\\'");
+    format!("dbname='{}' replication=database", escaped)
+}
+
+async fn read_first_line(stdout: ChildStdout) -> Result<Option<String>> {
+    let mut lines = BufReader::new(stdout).lines();
+    while let Some(line) = lines
+        .next_line()
+        .await
+        .context("Failed to read psql output")?
+    {
+        if !line.trim().is_empty() {
+            return Ok(Some(line));
+        }
+    }
+    Ok(None)
+}
+
+/// Parse `slot_name|consistent_point|snapshot_name|output_plugin` from psql
+fn parse_slot_row(line: &str) -> Result<(String, String, String)> {
+    let fields: Vec<&str> = line.trim().split('|').collect();
+    match fields.as_slice() {
+        [name, lsn, snapshot, _plugin] if !snapshot.is_empty() => {
+            Ok((name.to_string(), lsn.to_string(), snapshot.to_string()))
+        }
+        _ => bail!("Unexpected CREATE_REPLICATION_SLOT output: {}", line),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_slot_row() {
+        let (name, lsn, snapshot) =
+            parse_slot_row("seren_migration_sub|0/15007F0|00000003-00000004-1|pgoutput\n").unwrap();
+        assert_eq!(name, "seren_migration_sub");
+        assert_eq!(lsn, "0/15007F0");
+        assert_eq!(snapshot, "00000003-00000004-1");
+
+        assert!(parse_slot_row("ERROR:  permission denied").is_err());
+        assert!(parse_slot_row("slot|0/15007F0||pgoutput").is_err());
+    }
+
+    #[test]
+    fn test_warning_level_repeats_at_each_multiple() {
+        const GB: i64 = ORPHAN_WAL_WARNING_BYTES;
+        assert_eq!(warning_level(0, None), None);
+        assert_eq!(warning_level(0, Some(GB / 2)), None);
+        assert_eq!(warning_level(0, Some(GB + 1)), Some(1));
+        assert_eq!(warning_level(1, Some(GB + GB / 2)), None);
+        assert_eq!(warning_level(1, Some(3 * GB)), Some(3));
+    }
+
+    #[test]
+    fn test_is_same_slot_compares_server_and_name() {
+        let slot = TrackedSlot {
+            name: "seren_migration_sub".to_string(),
+            source_url: "postgresql://user:a@db.example.com:5432/app".to_string(),
+            target_url: "postgresql://user:b@target.example.com/app".to_string(),
+            created_at: chrono::Utc::now(),
+        };
+        assert!(is_same_slot(
+            &slot,
+            "seren_migration_sub",
+            "postgresql://other@DB.example.com/app"
+        ));
+        assert!(!is_same_slot(
+            &slot,
+            "seren_migration_sub",
+            "postgresql://user@replica.example.com/app"
+        ));
+        assert!(!is_same_slot(
+            &slot,
+            "seren_migration_sub_app",
+            "postgresql://user@db.example.com/app"
+        ));
+    }
+
+    #[test]
+    fn test_replication_conninfo_quotes_database() {
+        assert_eq!(
+            replication_conninfo("app"),
+            "dbname='app' replication=database"
+        );
+        assert_eq!(
+            replication_conninfo("it's"),
+            "dbname='it\\'s' replication=database"
+        );
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_snapshot_slot_sees_data_before_consistent_point() {
+        let url = std::env::var("TEST_SOURCE_URL").unwrap();
+        let client = crate::postgres::connect(&url).await.unwrap();
+        client
+            .batch_execute(
+                "DROP TABLE IF EXISTS slot_probe; CREATE TABLE slot_probe (id int); \
+                 INSERT INTO slot_probe VALUES (1)",
+            )
+            .await
+            .unwrap();
+        clear_stale_slot(&client, &url, "slot_probe_test")
+            .await
+            .unwrap();
+
+        let slot = SnapshotSlot::create(&url, "slot_probe_test").await.unwrap();
+        client
+            .execute("INSERT INTO slot_probe VALUES (2)", &[])
+            .await
+            .unwrap();
+        let reader = crate::postgres::connect(&url).await.unwrap();
+        crate::postgres::snapshot::begin_at_snapshot(&reader, slot.snapshot_id())
+            .await
+            .unwrap();
+        let count: i64 = reader
+            .query_one("SELECT count(*) FROM slot_probe", &[])
+            .await
+            .unwrap()
+            .get(0);
+        assert_eq!(count, 1);
+        reader.batch_execute("COMMIT").await.unwrap();
+
+        slot.abandon(&url).await.unwrap();
+        assert!(!slot_exists(&client, "slot_probe_test").await.unwrap());
+        client.execute("DROP TABLE slot_probe", &[]).await.unwrap();
+    }
+}
@@ -42,6 +42,51 @@ pub async fn create_subscription(
     subscription_name: &str,
     source_connection_string: &str,
     publication_name: &str,
+) -> Result<()> {
+    create_subscription_with(
+        client,
+        subscription_name,
+        source_connection_string,
+        publication_name,
+        None,
+    )
+    .await
+}
+
+/// Create a subscription that streams from an existing slot without copying data
+///
+/// Used after the target was loaded from the slot's exported snapshot (see
+/// [`super::SnapshotSlot`]): the slot already holds every change since then,
+/// so the initial table copy is skipped.
+pub async fn create_subscription_for_slot(
+    client: &Client,
+    subscription_name: &str,
+    source_connection_string: &str,
+    publication_name: &str,
+    slot_name: &str,
+) -> Result<()> {
+    crate::utils::validate_postgres_identifier(slot_name)
+        .with_context(|| format!("Invalid replication slot name '{}'", slot_name))?;
+    let options = format!(
+        "create_slot = false, slot_name = {}, copy_data = false",
+        crate::utils::quote_literal(slot_name)
+    );
+    create_subscription_with(
+        client,
+        subscription_name,
+        source_connection_string,
+        publication_name,
+        Some(&options),
+    )
+    .await
+}
+
+async fn create_subscription_with(
+    client: &Client,
+    subscription_name: &str,
+    source_connection_string: &str,
+    publication_name: &str,
+    options: Option<&str>,
 ) -> Result<()> {
     // Validate subscription name to prevent SQL injection
     crate::utils::validate_postgres_identifier(subscription_name).with_context(|| {
@@ -81,12 +126,15 @@ pub async fn create_subscription(
         "  To avoid storing passwords, configure .pgpass on the target PostgreSQL server"
     );
 
-    let query = format!(
+    let mut query = format!(
         "CREATE SUBSCRIPTION {} CONNECTION {} PUBLICATION {}",
         crate::utils::quote_ident(subscription_name),
         crate::utils::quote_literal(source_connection_string),
         crate::utils::quote_ident(publication_name)
     );
+    if let Some(options) = options {
+        query.push_str(&format!(" WITH ({})", options));
+    }
 
     match client.execute(&query, &[]).await {
         Ok(_) => {
@@ -164,6 +212,38 @@ pub async fn create_subscription(
     }
 }
 
+/// Pick up tables added to or removed from a subscription's publication
+///
+/// With `copy_data`, tables new to the subscription are copied before their
+/// changes are streamed. Tables no longer published stop receiving changes
+/// but keep their rows on the target.
+pub async fn refresh_subscription(
+    client: &Client,
+    subscription_name: &str,
+    copy_data: bool,
+) -> Result<()> {
+    crate::utils::validate_postgres_identifier(subscription_name).with_context(|| {
+        format!(
+            "Invalid subscription name '{}': must be a valid PostgreSQL identifier",
+            subscription_name
+        )
+    })?;
+    let query = format!(
+        "ALTER SUBSCRIPTION {} REFRESH PUBLICATION WITH (copy_data = {})",
+        crate::utils::quote_ident(subscription_name),
+        copy_data
+    );
+    client.execute(&query, &[]).await.map_err(|e| {
+        anyhow::anyhow!(
+            "Failed to refresh subscription '{}': {}",
+            subscription_name,
+            extract_pg_error_details(&e)
+        )
+    })?;
+    tracing::info!("✓ Subscription '{}' refreshed", subscription_name);
+    Ok(())
+}
+
 /// List all subscriptions in the database
 pub async fn list_subscriptions(client: &Client) -> Result<Vec<String>> {
     let rows = client
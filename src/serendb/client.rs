@@ -1,10 +1,12 @@
 // ABOUTME: HTTP client for SerenDB Console API
-// ABOUTME: Manages project settings like logical replication
+// ABOUTME: Manages projects, branches, endpoints, and settings like logical replication
 
 use anyhow::{Context, Result};
-use reqwest::Client;
+use reqwest::{Client, Method};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 
+use super::error::{is_retryable, parse_retry_after, retry_delay, ConsoleError, MAX_RETRIES};
 use crate::utils::replace_database_in_connection_string;
 
 /// Default SerenDB Console API base URL
@@ -27,6 +29,17 @@ pub struct Project {
     pub organization_id: Option<String>,
 }
 
+/// Storage limits of a project's plan from SerenDB Console API
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProjectLimits {
+    /// Storage the plan allows the project; unlimited when unset
+    #[serde(default)]
+    pub storage_limit_bytes: Option<i64>,
+    /// Storage the project's branches use now
+    #[serde(default)]
+    pub storage_used_bytes: i64,
+}
+
 /// Branch information from SerenDB Console API
 #[allow(dead_code)]
 #[derive(Debug, Clone, Deserialize)]
@@ -47,6 +60,35 @@ pub struct Database {
     pub branch_id: String,
 }
 
+/// Compute endpoint information from SerenDB Console API
+#[derive(Debug, Clone, Deserialize)]
+pub struct Endpoint {
+    pub id: String,
+    pub branch_id: String,
+    /// `init` while starting, then `active`, or `idle` once suspended
+    #[serde(default)]
+    pub current_state: Option<String>,
+    /// Host name clients connect to
+    #[serde(default)]
+    pub host: Option<String>,
+}
+
+impl Endpoint {
+    /// Whether the endpoint takes connections; an idle one wakes on the first
+    pub fn is_ready(&self) -> bool {
+        matches!(self.current_state.as_deref(), Some("active") | Some("idle"))
+    }
+}
+
+/// How long one Console API request may take before it counts as failed
+const REQUEST_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// How long to wait for the endpoint of a newly created project or branch
+pub const ENDPOINT_START_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(300);
+
+/// How often [`ConsoleClient::wait_for_endpoint`] checks a starting endpoint
+const ENDPOINT_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
 /// Connection string response payload
 #[allow(dead_code)]
 #[derive(Debug, Deserialize)]
@@ -61,6 +103,21 @@ pub struct CreateDatabaseRequest {
     pub name: String,
 }
 
+/// Request payload to create a project
+#[derive(Debug, Serialize)]
+pub struct CreateProjectRequest {
+    pub name: String,
+}
+
+/// Request payload to create a branch
+#[derive(Debug, Serialize)]
+pub struct CreateBranchRequest {
+    pub name: String,
+    /// Branch to copy; the project's default branch when unset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<String>,
+}
+
 /// Paginated response wrapper from the Console API
 #[allow(dead_code)]
 #[derive(Debug, Deserialize)]
@@ -130,27 +187,8 @@ impl ConsoleClient {
     pub async fn list_projects(&self) -> Result<Vec<Project>> {
         let url = format!("{}/api/projects", self.api_base_url);
 
-        let response = self
-            .client
-            .get(&url)
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .header("Content-Type", "application/json")
-            .send()
-            .await
-            .context("Failed to send request to SerenDB Console API")?;
-
-        self.handle_common_errors(&response).await?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let body = response.text().await.unwrap_or_default();
-            anyhow::bail!("SerenDB Console API returned error {}: {}", status, body);
-        }
-
-        let data: PaginatedResponse<Project> = response
-            .json()
-            .await
-            .context("Failed to parse projects response from SerenDB Console API")?;
+        let response = self.send(Method::GET, &url, None, None).await?;
+        let data: PaginatedResponse<Project> = parse(response, "projects").await?;
 
         Ok(data.data)
     }
@@ -159,27 +197,8 @@ impl ConsoleClient {
     pub async fn list_branches(&self, project_id: &str) -> Result<Vec<Branch>> {
         let url = format!("{}/api/projects/{}/branches", self.api_base_url, project_id);
 
-        let response = self
-            .client
-            .get(&url)
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .header("Content-Type", "application/json")
-            .send()
-            .await
-            .context("Failed to send request to SerenDB Console API")?;
-
-        self.handle_common_errors(&response).await?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let body = response.text().await.unwrap_or_default();
-            anyhow::bail!("SerenDB Console API returned error {}: {}", status, body);
-        }
-
-        let data: PaginatedResponse<Branch> = response
-            .json()
-            .await
-            .context("Failed to parse branches response from SerenDB Console API")?;
+        let response = self.send(Method::GET, &url, None, None).await?;
+        let data: PaginatedResponse<Branch> = parse(response, "branches").await?;
 
         Ok(data.data)
     }
@@ -199,27 +218,8 @@ impl ConsoleClient {
             self.api_base_url, project_id, branch_id
         );
 
-        let response = self
-            .client
-            .get(&url)
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .header("Content-Type", "application/json")
-            .send()
-            .await
-            .context("Failed to send request to SerenDB Console API")?;
-
-        self.handle_common_errors(&response).await?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let body = response.text().await.unwrap_or_default();
-            anyhow::bail!("SerenDB Console API returned error {}: {}", status, body);
-        }
-
-        let data: PaginatedResponse<Database> = response
-            .json()
-            .await
-            .context("Failed to parse databases response from SerenDB Console API")?;
+        let response = self.send(Method::GET, &url, None, None).await?;
+        let data: PaginatedResponse<Database> = parse(response, "databases").await?;
 
         Ok(data.data)
     }
@@ -241,36 +241,209 @@ impl ConsoleClient {
         };
 
         let response = self
-            .client
-            .post(&url)
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .header("Content-Type", "application/json")
-            .json(&request)
-            .send()
+            .send(
+                Method::POST,
+                &url,
+                Some(serde_json::to_value(&request)?),
+                None,
+            )
             .await
-            .context("Failed to send request to SerenDB Console API")?;
+            .with_context(|| format!("Failed to create database '{}'", name))?;
+        let data: DataResponse<Database> = parse(response, "create database").await?;
 
-        self.handle_common_errors(&response).await?;
+        Ok(data.data)
+    }
 
-        if !response.status().is_success() {
-            let status = response.status();
-            let body = response.text().await.unwrap_or_default();
-            anyhow::bail!(
-                "Failed to create database '{}': {} - {}",
-                name,
-                status,
-                body
-            );
-        }
+    /// Create a new SerenDB project, which comes with a default branch
+    pub async fn create_project(&self, name: &str) -> Result<Project> {
+        let url = format!("{}/api/projects", self.api_base_url);
+
+        let request = CreateProjectRequest {
+            name: name.to_string(),
+        };
+
+        let response = self
+            .send(
+                Method::POST,
+                &url,
+                Some(serde_json::to_value(&request)?),
+                None,
+            )
+            .await
+            .with_context(|| format!("Failed to create project '{}'", name))?;
+        let data: DataResponse<Project> = parse(response, "create project").await?;
+
+        Ok(data.data)
+    }
+
+    /// Create a new branch in a project, copied from `parent_id` or the default branch
+    pub async fn create_branch(
+        &self,
+        project_id: &str,
+        name: &str,
+        parent_id: Option<&str>,
+    ) -> Result<Branch> {
+        let url = format!("{}/api/projects/{}/branches", self.api_base_url, project_id);
+
+        let request = CreateBranchRequest {
+            name: name.to_string(),
+            parent_id: parent_id.map(str::to_string),
+        };
 
-        let data: DataResponse<Database> = response
-            .json()
+        let response = self
+            .send(
+                Method::POST,
+                &url,
+                Some(serde_json::to_value(&request)?),
+                Some(format!(
+                    "Project {} not found.\n\
+                     Verify the project ID is correct and you have access to it.",
+                    project_id
+                )),
+            )
+            .await
+            .with_context(|| format!("Failed to create branch '{}'", name))?;
+        let data: DataResponse<Branch> = parse(response, "create branch").await?;
+
+        Ok(data.data)
+    }
+
+    /// Make a branch the project's default, which its other tools connect to
+    pub async fn set_default_branch(&self, project_id: &str, branch_id: &str) -> Result<Branch> {
+        let url = format!(
+            "{}/api/projects/{}/branches/{}/set_default",
+            self.api_base_url, project_id, branch_id
+        );
+
+        let response = self
+            .send(
+                Method::POST,
+                &url,
+                None,
+                Some(format!(
+                    "Branch {} not found in project {}",
+                    branch_id, project_id
+                )),
+            )
             .await
-            .context("Failed to parse create database response from SerenDB Console API")?;
+            .with_context(|| format!("Failed to make branch {} the default", branch_id))?;
+        let data: DataResponse<Branch> = parse(response, "set default branch").await?;
 
         Ok(data.data)
     }
 
+    /// List the compute endpoints of a branch
+    pub async fn list_endpoints(&self, project_id: &str, branch_id: &str) -> Result<Vec<Endpoint>> {
+        let url = format!(
+            "{}/api/projects/{}/branches/{}/endpoints",
+            self.api_base_url, project_id, branch_id
+        );
+
+        let response = self.send(Method::GET, &url, None, None).await?;
+        let data: PaginatedResponse<Endpoint> = parse(response, "endpoints").await?;
+
+        Ok(data.data)
+    }
+
+    /// Create a read-write compute endpoint for a branch
+    pub async fn create_endpoint(&self, project_id: &str, branch_id: &str) -> Result<Endpoint> {
+        let url = format!(
+            "{}/api/projects/{}/branches/{}/endpoints",
+            self.api_base_url, project_id, branch_id
+        );
+
+        let response = self
+            .send(Method::POST, &url, Some(serde_json::json!({})), None)
+            .await
+            .with_context(|| format!("Failed to create an endpoint for branch {}", branch_id))?;
+        let data: DataResponse<Endpoint> = parse(response, "create endpoint").await?;
+
+        Ok(data.data)
+    }
+
+    /// Restart a compute endpoint so it picks up changed project settings
+    ///
+    /// Connections to the endpoint drop while it restarts.
+    pub async fn restart_endpoint(&self, project_id: &str, endpoint_id: &str) -> Result<Endpoint> {
+        let url = format!(
+            "{}/api/projects/{}/endpoints/{}/restart",
+            self.api_base_url, project_id, endpoint_id
+        );
+
+        let response = self
+            .send(
+                Method::POST,
+                &url,
+                None,
+                Some(format!(
+                    "Endpoint {} not found in project {}",
+                    endpoint_id, project_id
+                )),
+            )
+            .await
+            .with_context(|| format!("Failed to restart endpoint {}", endpoint_id))?;
+        let data: DataResponse<Endpoint> = parse(response, "restart endpoint").await?;
+
+        Ok(data.data)
+    }
+
+    /// Find the endpoint of a project that serves `hostname`, on any branch
+    pub async fn find_endpoint_by_hostname(
+        &self,
+        project_id: &str,
+        hostname: &str,
+    ) -> Result<Option<Endpoint>> {
+        for branch in self.list_branches(project_id).await? {
+            let endpoints = self.list_endpoints(project_id, &branch.id).await?;
+            if let Some(endpoint) = endpoint_for_host(&endpoints, hostname) {
+                return Ok(Some(endpoint.clone()));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Wait until a branch has an endpoint that takes connections
+    ///
+    /// A branch without an endpoint gets one. Fails once `timeout` passes with
+    /// the endpoint still starting.
+    pub async fn wait_for_endpoint(
+        &self,
+        project_id: &str,
+        branch_id: &str,
+        timeout: std::time::Duration,
+    ) -> Result<Endpoint> {
+        if self.list_endpoints(project_id, branch_id).await?.is_empty() {
+            tracing::info!("Creating a compute endpoint for branch {}...", branch_id);
+            self.create_endpoint(project_id, branch_id).await?;
+        }
+
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            let endpoints = self.list_endpoints(project_id, branch_id).await?;
+            if let Some(endpoint) = ready_endpoint(&endpoints) {
+                return Ok(endpoint.clone());
+            }
+            if tokio::time::Instant::now() >= deadline {
+                anyhow::bail!(
+                    "The endpoint for branch {} did not start within {}s.\n\
+                     Check its status at console.serendb.com, then run init again.",
+                    branch_id,
+                    timeout.as_secs()
+                );
+            }
+            tracing::debug!(
+                "Endpoint for branch {} is {}; checking again in {}s",
+                branch_id,
+                endpoints
+                    .first()
+                    .and_then(|endpoint| endpoint.current_state.as_deref())
+                    .unwrap_or("starting"),
+                ENDPOINT_POLL_INTERVAL.as_secs()
+            );
+            tokio::time::sleep(ENDPOINT_POLL_INTERVAL).await;
+        }
+    }
+
     /// Get a connection string for a branch/database combination
     pub async fn get_connection_string(
         &self,
@@ -285,30 +458,19 @@ impl ConsoleClient {
         );
 
         let response = self
-            .client
-            .get(&url)
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .header("Content-Type", "application/json")
-            .send()
-            .await
-            .context("Failed to send request to SerenDB Console API")?;
-
-        self.handle_common_errors_with_context(
-            &response,
-            Some("Branch has no compute endpoint. Select a different branch or create an endpoint at console.serendb.com.".to_string()),
-        )
-        .await?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let body = response.text().await.unwrap_or_default();
-            anyhow::bail!("SerenDB Console API returned error {}: {}", status, body);
-        }
-
-        let data: DataResponse<ConnectionStringResponse> = response
-            .json()
-            .await
-            .context("Failed to parse connection string response from SerenDB Console API")?;
+            .send(
+                Method::GET,
+                &url,
+                None,
+                Some(
+                    "Branch has no compute endpoint. Select a different branch or create an \
+                     endpoint at console.serendb.com."
+                        .to_string(),
+                ),
+            )
+            .await?;
+        let data: DataResponse<ConnectionStringResponse> =
+            parse(response, "connection string").await?;
 
         replace_database_in_connection_string(&data.data.connection_string, database)
     }
@@ -326,34 +488,35 @@ impl ConsoleClient {
         let url = format!("{}/api/projects/{}", self.api_base_url, project_id);
 
         let response = self
-            .client
-            .get(&url)
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .header("Content-Type", "application/json")
-            .send()
-            .await
-            .context("Failed to send request to SerenDB Console API")?;
-
-        self.handle_common_errors_with_context(
-            &response,
-            Some(format!(
-                "Project {} not found.\n\
-                 Verify the project ID is correct and you have access to it.",
-                project_id
-            )),
-        )
-        .await?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let body = response.text().await.unwrap_or_default();
-            anyhow::bail!("SerenDB Console API returned error {}: {}", status, body);
-        }
+            .send(
+                Method::GET,
+                &url,
+                None,
+                Some(format!(
+                    "Project {} not found.\n\
+                     Verify the project ID is correct and you have access to it.",
+                    project_id
+                )),
+            )
+            .await?;
+        let data: DataResponse<Project> = parse(response, "project").await?;
 
-        let data: DataResponse<Project> = response
-            .json()
-            .await
-            .context("Failed to parse project response from SerenDB Console API")?;
+        Ok(data.data)
+    }
+
+    /// Get the storage limit of a project's plan and how much of it is used
+    pub async fn get_project_limits(&self, project_id: &str) -> Result<ProjectLimits> {
+        let url = format!("{}/api/projects/{}/limits", self.api_base_url, project_id);
+
+        let response = self
+            .send(
+                Method::GET,
+                &url,
+                None,
+                Some(format!("No limits found for project {}", project_id)),
+            )
+            .await?;
+        let data: DataResponse<ProjectLimits> = parse(response, "project limits").await?;
 
         Ok(data.data)
     }
@@ -378,39 +541,19 @@ impl ConsoleClient {
         };
 
         let response = self
-            .client
-            .patch(&url)
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .header("Content-Type", "application/json")
-            .json(&request)
-            .send()
+            .send(
+                Method::PATCH,
+                &url,
+                Some(serde_json::to_value(&request)?),
+                Some(format!(
+                    "Project {} not found.\n\
+                     Verify the project ID is correct and you have access to it.",
+                    project_id
+                )),
+            )
             .await
-            .context("Failed to send request to SerenDB Console API")?;
-
-        self.handle_common_errors_with_context(
-            &response,
-            Some(format!(
-                "Project {} not found.\n\
-                 Verify the project ID is correct and you have access to it.",
-                project_id
-            )),
-        )
-        .await?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let body = response.text().await.unwrap_or_default();
-            anyhow::bail!(
-                "Failed to enable logical replication. SerenDB Console API returned {}: {}",
-                status,
-                body
-            );
-        }
-
-        let data: DataResponse<Project> = response
-            .json()
-            .await
-            .context("Failed to parse project response from SerenDB Console API")?;
+            .context("Failed to enable logical replication")?;
+        let data: DataResponse<Project> = parse(response, "project").await?;
 
         Ok(data.data)
     }
@@ -451,6 +594,7 @@ impl ConsoleClient {
             // Get the default branch for each project
             let branch = match self.get_default_branch(&project.id).await {
                 Ok(b) => b,
+                Err(e) if is_fatal(&e) => return Err(e),
                 Err(_) => continue, // Skip projects without branches
             };
 
@@ -460,6 +604,7 @@ impl ConsoleClient {
                 .await
             {
                 Ok(s) => s,
+                Err(e) if is_fatal(&e) => return Err(e),
                 Err(_) => continue, // Skip branches without endpoints
             };
 
@@ -481,34 +626,124 @@ impl ConsoleClient {
         Ok(None)
     }
 
-    async fn handle_common_errors(&self, response: &reqwest::Response) -> Result<()> {
-        self.handle_common_errors_with_context(response, None).await
-    }
-
-    async fn handle_common_errors_with_context(
+    /// Send a request, retrying while the API is rate limiting or failing
+    ///
+    /// Server errors and dropped connections are retried only for requests
+    /// other than POST, which may have gone through. Unsuccessful responses
+    /// become a [`ConsoleError`]; `not_found` names the resource for a 404.
+    async fn send(
         &self,
-        response: &reqwest::Response,
-        not_found_message: Option<String>,
-    ) -> Result<()> {
-        if response.status() == reqwest::StatusCode::UNAUTHORIZED {
-            anyhow::bail!(
-                "SerenDB API key is invalid or expired.\n\
-                 Generate a new key at: https://console.serendb.com/api-keys"
-            );
-        }
+        method: Method,
+        url: &str,
+        body: Option<serde_json::Value>,
+        not_found: Option<String>,
+    ) -> Result<reqwest::Response> {
+        let idempotent = method != Method::POST;
+        let mut attempt = 0;
+        loop {
+            let started = std::time::Instant::now();
+            let mut request = self
+                .client
+                .request(method.clone(), url)
+                .header("Authorization", format!("Bearer {}", self.api_key))
+                .header("Content-Type", "application/json")
+                .timeout(REQUEST_TIMEOUT);
+            if let Some(body) = &body {
+                request = request.json(body);
+            }
+
+            let response = match request.send().await {
+                Ok(response) => response,
+                Err(e) if idempotent && attempt < MAX_RETRIES => {
+                    let delay = retry_delay(attempt, None);
+                    tracing::debug!(
+                        "Console API {} {} failed: {}; retrying in {:?}",
+                        method,
+                        url,
+                        e,
+                        delay
+                    );
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                    continue;
+                }
+                Err(e) => return Err(e).context("Failed to send request to SerenDB Console API"),
+            };
 
-        if response.status() == reqwest::StatusCode::NOT_FOUND {
-            if let Some(message) = not_found_message {
-                anyhow::bail!(message);
-            } else {
-                anyhow::bail!("Resource not found. Verify the ID is correct and you have access.");
+            let status = response.status();
+            tracing::debug!(
+                "Console API {} {} -> {} in {}ms",
+                method,
+                url,
+                status.as_u16(),
+                started.elapsed().as_millis()
+            );
+            if status.is_success() {
+                return Ok(response);
+            }
+            if is_retryable(status, idempotent) && attempt < MAX_RETRIES {
+                let retry_after = parse_retry_after(
+                    response
+                        .headers()
+                        .get(reqwest::header::RETRY_AFTER)
+                        .and_then(|value| value.to_str().ok()),
+                );
+                let delay = retry_delay(attempt, retry_after);
+                tracing::warn!(
+                    "SerenDB Console API returned {} (attempt {}/{}), retrying in {:?}...",
+                    status.as_u16(),
+                    attempt + 1,
+                    MAX_RETRIES + 1,
+                    delay
+                );
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+                continue;
             }
-        }
 
-        Ok(())
+            let body = response.text().await.unwrap_or_default();
+            tracing::debug!("Console API error body: {}", crate::redact::redact(&body));
+            return Err(ConsoleError::from_response(status, &body, not_found).into());
+        }
     }
 }
 
+/// Parse the JSON body of a successful response; `what` names it in errors
+async fn parse<T: DeserializeOwned>(response: reqwest::Response, what: &str) -> Result<T> {
+    response
+        .json()
+        .await
+        .with_context(|| format!("Failed to parse {} response from SerenDB Console API", what))
+}
+
+/// Whether `error` is a [`ConsoleError`] that ends a search across projects
+fn is_fatal(error: &anyhow::Error) -> bool {
+    error
+        .downcast_ref::<ConsoleError>()
+        .is_some_and(ConsoleError::is_fatal)
+}
+
+fn ready_endpoint(endpoints: &[Endpoint]) -> Option<&Endpoint> {
+    endpoints.iter().find(|endpoint| endpoint.is_ready())
+}
+
+/// The endpoint serving `hostname`
+///
+/// Pooled host names add `-pooler` to the endpoint ID in their first label, so
+/// an endpoint also matches when that label starts with its ID.
+fn endpoint_for_host<'a>(endpoints: &'a [Endpoint], hostname: &str) -> Option<&'a Endpoint> {
+    let hostname = hostname.to_lowercase();
+    let first_label = hostname.split('.').next().unwrap_or_default();
+    endpoints.iter().find(|endpoint| {
+        endpoint
+            .host
+            .as_deref()
+            .is_some_and(|host| host.eq_ignore_ascii_case(&hostname))
+            || first_label == endpoint.id.to_lowercase()
+            || first_label == format!("{}-pooler", endpoint.id.to_lowercase())
+    })
+}
+
 fn select_default_branch(project_id: &str, branches: Vec<Branch>) -> Result<Branch> {
     if branches.is_empty() {
         anyhow::bail!("Project {} has no branches", project_id);
@@ -566,6 +801,60 @@ mod tests {
         assert_eq!(db.branch_id, "br1");
     }
 
+    #[test]
+    fn test_create_branch_request_serialization() {
+        let request = CreateBranchRequest {
+            name: "replica".to_string(),
+            parent_id: None,
+        };
+        assert_eq!(
+            serde_json::to_string(&request).unwrap(),
+            r#"{"name":"replica"}"#
+        );
+    }
+
+    #[test]
+    fn test_ready_endpoint() {
+        let endpoint = |id: &str, state: Option<&str>| Endpoint {
+            id: id.into(),
+            branch_id: "br1".into(),
+            current_state: state.map(str::to_string),
+            host: None,
+        };
+        assert!(ready_endpoint(&[endpoint("ep1", Some("init")), endpoint("ep2", None)]).is_none());
+        let endpoints = [endpoint("ep1", Some("init")), endpoint("ep2", Some("idle"))];
+        assert_eq!(ready_endpoint(&endpoints).unwrap().id, "ep2");
+
+        let json = r#"{"id": "ep3", "branch_id": "br1", "current_state": "active"}"#;
+        let endpoint: Endpoint = serde_json::from_str(json).unwrap();
+        assert!(endpoint.is_ready());
+    }
+
+    #[test]
+    fn test_endpoint_for_host() {
+        let endpoint = |id: &str, host: Option<&str>| Endpoint {
+            id: id.into(),
+            branch_id: "br1".into(),
+            current_state: Some("active".into()),
+            host: host.map(str::to_string),
+        };
+        let endpoints = [
+            endpoint("ep-quiet-sun-123", None),
+            endpoint("ep-bold-moon-456", Some("db.example.serendb.com")),
+        ];
+        let found = |host: &str| endpoint_for_host(&endpoints, host).map(|e| e.id.as_str());
+        assert_eq!(
+            found("ep-quiet-sun-123.us-east-1.serendb.com"),
+            Some("ep-quiet-sun-123")
+        );
+        assert_eq!(
+            found("EP-QUIET-SUN-123-pooler.us-east-1.serendb.com"),
+            Some("ep-quiet-sun-123")
+        );
+        assert_eq!(found("db.example.serendb.com"), Some("ep-bold-moon-456"));
+        assert_eq!(found("ep-quiet-sun-12.us-east-1.serendb.com"), None);
+    }
+
     #[test]
     fn test_select_default_branch_prefers_flagged_branch() {
         let branches = vec![
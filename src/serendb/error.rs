@@ -0,0 +1,212 @@
+// ABOUTME: Typed errors for SerenDB Console API responses and the retry policy for them
+// ABOUTME: Turns status codes into messages that say what to do next
+
+use reqwest::StatusCode;
+use std::time::Duration;
+
+/// Retries after the first attempt for rate-limited or failing requests
+pub const MAX_RETRIES: u32 = 4;
+
+/// Delay before the first retry; doubles after each one
+const INITIAL_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// Longest wait between attempts, including one asked for by `Retry-After`
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// Why the SerenDB Console API refused a request
+///
+/// Returned inside `anyhow::Error`; callers that react to one kind use
+/// `downcast_ref::<ConsoleError>()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConsoleError {
+    /// The API key is missing, invalid, or expired
+    Auth,
+    /// The project, branch, or endpoint does not exist or the key cannot see it
+    NotFound(String),
+    /// The account has reached a limit of its plan
+    QuotaExceeded(String),
+    /// Still rate limited after every retry
+    RateLimited,
+    /// Any other unsuccessful response
+    Api { status: u16, message: String },
+}
+
+impl ConsoleError {
+    /// Whether every other request would fail the same way, so a search across
+    /// projects or branches should stop instead of skipping this one
+    pub fn is_fatal(&self) -> bool {
+        matches!(
+            self,
+            ConsoleError::Auth | ConsoleError::QuotaExceeded(_) | ConsoleError::RateLimited
+        )
+    }
+
+    /// Classify an unsuccessful response
+    ///
+    /// `not_found` replaces the generic message for a 404 with one naming the
+    /// missing resource.
+    pub fn from_response(status: StatusCode, body: &str, not_found: Option<String>) -> Self {
+        let message = error_message(body);
+        let lower = message.to_lowercase();
+        match status {
+            StatusCode::UNAUTHORIZED => ConsoleError::Auth,
+            StatusCode::NOT_FOUND => ConsoleError::NotFound(not_found.unwrap_or_else(|| {
+                "Resource not found. Verify the ID is correct and you have access.".to_string()
+            })),
+            StatusCode::PAYMENT_REQUIRED => ConsoleError::QuotaExceeded(message),
+            StatusCode::FORBIDDEN | StatusCode::CONFLICT | StatusCode::UNPROCESSABLE_ENTITY
+                if lower.contains("quota") || lower.contains("limit") =>
+            {
+                ConsoleError::QuotaExceeded(message)
+            }
+            StatusCode::TOO_MANY_REQUESTS => ConsoleError::RateLimited,
+            _ => ConsoleError::Api {
+                status: status.as_u16(),
+                message,
+            },
+        }
+    }
+}
+
+impl std::fmt::Display for ConsoleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConsoleError::Auth => write!(
+                f,
+                "SerenDB API key is invalid or expired.\n\
+                 Generate a new key at: https://console.serendb.com/api-keys\n\
+                 Then set SEREN_API_KEY or pass --api-key."
+            ),
+            ConsoleError::NotFound(message) => write!(f, "{}", message),
+            ConsoleError::QuotaExceeded(message) => write!(
+                f,
+                "SerenDB plan limit reached: {}\n\
+                 Delete projects or branches you no longer need at console.serendb.com, \
+                 or upgrade your plan.",
+                message
+            ),
+            ConsoleError::RateLimited => write!(
+                f,
+                "SerenDB Console API is still rate limiting requests after {} retries.\n\
+                 Wait a minute and run the command again.",
+                MAX_RETRIES
+            ),
+            ConsoleError::Api { status, message } => {
+                write!(
+                    f,
+                    "SerenDB Console API returned error {}: {}",
+                    status, message
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConsoleError {}
+
+/// Whether a response with this status is worth sending again
+///
+/// A rate-limited request was never processed, so it is always retried.
+/// Server errors are retried only for `idempotent` requests, since a create
+/// that failed late may have gone through.
+pub fn is_retryable(status: StatusCode, idempotent: bool) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || (idempotent && status.is_server_error())
+}
+
+/// How long to wait before retry number `attempt` (counting from 0)
+///
+/// `retry_after` is the server's `Retry-After` delay, which wins when given.
+pub fn retry_delay(attempt: u32, retry_after: Option<Duration>) -> Duration {
+    retry_after
+        .unwrap_or_else(|| INITIAL_RETRY_DELAY.saturating_mul(2u32.saturating_pow(attempt)))
+        .min(MAX_RETRY_DELAY)
+}
+
+/// Parse a `Retry-After` header given in seconds
+pub fn parse_retry_after(value: Option<&str>) -> Option<Duration> {
+    value?.trim().parse().ok().map(Duration::from_secs)
+}
+
+/// The `error` or `message` field of a JSON error body, or the body itself
+fn error_message(body: &str) -> String {
+    let body = crate::redact::redact(body.trim());
+    serde_json::from_str::<serde_json::Value>(&body)
+        .ok()
+        .and_then(|value| {
+            ["error", "message"]
+                .iter()
+                .find_map(|key| match &value[*key] {
+                    serde_json::Value::String(message) => Some(message.clone()),
+                    serde_json::Value::Object(inner) => inner
+                        .get("message")
+                        .and_then(|m| m.as_str())
+                        .map(str::to_string),
+                    _ => None,
+                })
+        })
+        .unwrap_or(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_response() {
+        assert_eq!(
+            ConsoleError::from_response(StatusCode::UNAUTHORIZED, "", None),
+            ConsoleError::Auth
+        );
+        assert_eq!(
+            ConsoleError::from_response(
+                StatusCode::NOT_FOUND,
+                "",
+                Some("Project p1 not found".into())
+            ),
+            ConsoleError::NotFound("Project p1 not found".into())
+        );
+        assert_eq!(
+            ConsoleError::from_response(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                r#"{"error": "Branch limit of 10 reached"}"#,
+                None
+            ),
+            ConsoleError::QuotaExceeded("Branch limit of 10 reached".into())
+        );
+        assert_eq!(
+            ConsoleError::from_response(
+                StatusCode::BAD_REQUEST,
+                r#"{"error": {"message": "name is required"}}"#,
+                None
+            ),
+            ConsoleError::Api {
+                status: 400,
+                message: "name is required".into()
+            }
+        );
+        assert_eq!(
+            ConsoleError::from_response(StatusCode::BAD_GATEWAY, "upstream down", None).to_string(),
+            "SerenDB Console API returned error 502: upstream down"
+        );
+    }
+
+    #[test]
+    fn test_retry_policy() {
+        assert!(is_retryable(StatusCode::TOO_MANY_REQUESTS, false));
+        assert!(is_retryable(StatusCode::SERVICE_UNAVAILABLE, true));
+        assert!(!is_retryable(StatusCode::SERVICE_UNAVAILABLE, false));
+        assert!(!is_retryable(StatusCode::BAD_REQUEST, true));
+
+        assert_eq!(retry_delay(0, None), Duration::from_millis(500));
+        assert_eq!(retry_delay(3, None), Duration::from_secs(4));
+        assert_eq!(retry_delay(10, None), MAX_RETRY_DELAY);
+        assert_eq!(
+            retry_delay(0, parse_retry_after(Some(" 7 "))),
+            Duration::from_secs(7)
+        );
+        assert_eq!(
+            parse_retry_after(Some("Wed, 21 Oct 2026 07:28:00 GMT")),
+            None
+        );
+    }
+}
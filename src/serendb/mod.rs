@@ -2,11 +2,19 @@
 // ABOUTME: Enables checking and enabling logical replication on SerenDB projects
 
 mod client;
+mod error;
 mod picker;
+mod quota;
+mod run_branch;
 mod target;
 
-pub use client::{Branch, ConsoleClient, Database, Project};
+pub use client::{
+    Branch, ConsoleClient, Database, Endpoint, Project, ProjectLimits, ENDPOINT_START_TIMEOUT,
+};
+pub use error::ConsoleError;
 pub use picker::{create_missing_databases, select_target, TargetSelection};
+pub use quota::check_storage_quota;
+pub use run_branch::{create_run_branch, promote_run_branch, RunBranch};
 pub use target::{clear_target_state, load_target_state, save_target_state, TargetState};
 
 use anyhow::Result;
@@ -67,7 +75,9 @@ mod tests {
         let dir = tempdir().expect("tempdir");
         let path = dir.path().join("target.json");
         std::env::set_var("SEREN_TARGET_STATE_PATH", &path);
+        std::env::set_var(crate::encryption::STATE_KEY_ENV, "test-key");
         func();
+        std::env::remove_var(crate::encryption::STATE_KEY_ENV);
         std::env::remove_var("SEREN_TARGET_STATE_PATH");
     }
 
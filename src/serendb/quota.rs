@@ -0,0 +1,200 @@
+// ABOUTME: Compares the data init is about to copy with a SerenDB project's storage quota
+// ABOUTME: Stops before the copy starts instead of when the target runs out of space
+
+use crate::migration::format_bytes;
+use crate::serendb::{ConsoleClient, ProjectLimits};
+use anyhow::{bail, Context, Result};
+use dialoguer::{theme::ColorfulTheme, Confirm};
+
+/// Estimated data that does not fit in a project's free storage
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct StorageShortfall {
+    needed: i64,
+    available: i64,
+    limit: i64,
+}
+
+/// The shortfall when `needed` bytes exceed the project's free storage
+///
+/// `reclaimable` is what the copy frees by replacing databases already on the
+/// target. Projects without a storage limit never fall short.
+fn storage_shortfall(
+    limits: &ProjectLimits,
+    needed: i64,
+    reclaimable: i64,
+) -> Option<StorageShortfall> {
+    let limit = limits.storage_limit_bytes?;
+    let available = (limit - limits.storage_used_bytes + reclaimable).clamp(0, limit);
+    (needed > available).then_some(StorageShortfall {
+        needed,
+        available,
+        limit,
+    })
+}
+
+/// Bytes used by the target databases among `databases`, which init replaces
+async fn target_database_bytes(target_url: &str, databases: &[String]) -> Result<i64> {
+    let client = crate::postgres::connect_with_retry(target_url).await?;
+    let row = client
+        .query_one(
+            "SELECT COALESCE(sum(pg_database_size(datname)), 0)::bigint
+             FROM pg_database WHERE datname = ANY($1)",
+            &[&databases],
+        )
+        .await
+        .context("Failed to measure existing target databases")?;
+    Ok(row.get(0))
+}
+
+/// Check that `needed_bytes` of source data, going into `databases`, fits in
+/// the SerenDB project behind `target_url`
+///
+/// The project is `project_id`, the one named in the target's host, or the
+/// one whose endpoint serves it. Space used by `databases` already on the
+/// target counts as free. The check is skipped, with a warning where something
+/// failed, when no API key is set or the project or its limits cannot be
+/// found. When the data does not fit, asks whether to copy anyway, or fails
+/// with `skip_confirmation`.
+pub async fn check_storage_quota(
+    target_url: &str,
+    project_id: Option<&str>,
+    needed_bytes: i64,
+    databases: &[String],
+    skip_confirmation: bool,
+) -> Result<()> {
+    let Some(api_key) = std::env::var("SEREN_API_KEY")
+        .ok()
+        .filter(|key| !key.trim().is_empty())
+    else {
+        tracing::debug!("No SerenDB API key set; skipping the storage quota check");
+        return Ok(());
+    };
+    let client = ConsoleClient::new(None, api_key.trim().to_string());
+
+    let project_id = match project_id.map(str::to_string).or_else(|| {
+        crate::utils::parse_serendb_url_for_ids(target_url).map(|(project_id, _, _)| project_id)
+    }) {
+        Some(project_id) => project_id,
+        None => {
+            let host = crate::utils::parse_postgres_url(target_url)?.host;
+            match client.find_project_by_hostname(&host).await {
+                Ok(Some(project_id)) => project_id,
+                Ok(None) => {
+                    tracing::debug!(
+                        "No SerenDB project serves {}; skipping the storage quota check",
+                        host
+                    );
+                    return Ok(());
+                }
+                Err(e) => {
+                    tracing::warn!("⚠ Could not find the SerenDB project for {}: {}", host, e);
+                    return Ok(());
+                }
+            }
+        }
+    };
+    let limits = match client.get_project_limits(&project_id).await {
+        Ok(limits) => limits,
+        Err(e) => {
+            tracing::warn!(
+                "⚠ Could not check the storage quota of project {}: {}",
+                project_id,
+                e
+            );
+            return Ok(());
+        }
+    };
+
+    let reclaimable_bytes = if limits.storage_limit_bytes.is_some() {
+        target_database_bytes(target_url, databases)
+            .await
+            .unwrap_or_else(|e| {
+                tracing::warn!("⚠ Could not measure existing target databases: {}", e);
+                0
+            })
+    } else {
+        0
+    };
+    let Some(shortfall) = storage_shortfall(&limits, needed_bytes, reclaimable_bytes) else {
+        if let Some(limit) = limits.storage_limit_bytes {
+            tracing::info!(
+                "✓ Estimated {} fits in the project's storage quota ({} used of {})",
+                format_bytes(needed_bytes),
+                format_bytes(limits.storage_used_bytes),
+                format_bytes(limit)
+            );
+        }
+        return Ok(());
+    };
+
+    println!();
+    println!(
+        "⚠ The data to copy is about {}, but project {} has {} of its {} storage quota free.",
+        format_bytes(shortfall.needed),
+        project_id,
+        format_bytes(shortfall.available),
+        format_bytes(shortfall.limit)
+    );
+    println!("  The restore would likely fail once the quota is used up.");
+    println!("  Free space in the project, filter out tables you don't need, or upgrade the plan at console.serendb.com.");
+    println!();
+    if skip_confirmation {
+        bail!(
+            "Estimated data ({}) exceeds the free storage quota of project {} ({}).\n\
+             Pass --skip-quota-check to copy anyway.",
+            format_bytes(shortfall.needed),
+            project_id,
+            format_bytes(shortfall.available)
+        );
+    }
+    crate::interactive::ensure_interactive(
+        "confirmation to exceed the storage quota",
+        "Pass --skip-quota-check to copy anyway.",
+    )?;
+    let proceed = Confirm::with_theme(&ColorfulTheme::default())
+        .with_prompt("Copy anyway?")
+        .default(false)
+        .interact()?;
+    if !proceed {
+        bail!("Replication cancelled: estimated data exceeds the project's storage quota");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GB: i64 = 1024 * 1024 * 1024;
+
+    #[test]
+    fn test_storage_shortfall() {
+        let limits = ProjectLimits {
+            storage_limit_bytes: Some(10 * GB),
+            storage_used_bytes: 7 * GB,
+        };
+        assert_eq!(storage_shortfall(&limits, 3 * GB, 0), None);
+        assert_eq!(
+            storage_shortfall(&limits, 4 * GB, 0),
+            Some(StorageShortfall {
+                needed: 4 * GB,
+                available: 3 * GB,
+                limit: 10 * GB,
+            })
+        );
+        // Replacing a 2 GB database on the target frees its space first
+        assert_eq!(storage_shortfall(&limits, 4 * GB, 2 * GB), None);
+        // Over quota already
+        let full = ProjectLimits {
+            storage_limit_bytes: Some(GB),
+            storage_used_bytes: 2 * GB,
+        };
+        assert_eq!(storage_shortfall(&full, 1, 0).unwrap().available, 0);
+
+        let unlimited = ProjectLimits {
+            storage_limit_bytes: None,
+            storage_used_bytes: 100 * GB,
+        };
+        assert_eq!(storage_shortfall(&unlimited, 100 * GB, 0), None);
+    }
+}
@@ -0,0 +1,163 @@
+// ABOUTME: Creates a fresh SerenDB branch for one init run and promotes it afterwards
+// ABOUTME: Lets a staging refresh replace the default branch in one step once it verifies
+
+use crate::serendb::{Branch, ConsoleClient, TargetState, ENDPOINT_START_TIMEOUT};
+use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
+
+/// A branch created to replicate into for one run
+#[derive(Debug, Clone)]
+pub struct RunBranch {
+    /// Target state pointing at the new branch
+    pub state: TargetState,
+    /// Connection string for the first database on the new branch
+    pub connection_string: String,
+    /// Name of the branch the new one was copied from
+    pub parent_name: String,
+}
+
+/// `name`, or `name-<timestamp>` when a branch already has it
+fn branch_name(name: &str, existing: &[Branch], now: DateTime<Utc>) -> String {
+    if existing.iter().any(|branch| branch.name == name) {
+        format!("{}-{}", name, now.format("%Y%m%d-%H%M%S"))
+    } else {
+        name.to_string()
+    }
+}
+
+/// Create a branch named `name` to replicate into and wait for its endpoint
+///
+/// The project, the branch to copy, and the databases come from `base` when
+/// set, such as a target picked interactively. Otherwise they come from
+/// `target_url`: the project named in its host, or the one with an endpoint
+/// serving it, and the branch of that endpoint. When a branch named `name`
+/// already exists, a timestamp is appended so each run gets its own branch.
+pub async fn create_run_branch(
+    client: &ConsoleClient,
+    name: &str,
+    target_url: &str,
+    base: Option<&TargetState>,
+    source_url: &str,
+) -> Result<RunBranch> {
+    let (project_id, parent_id, databases) = match base {
+        Some(state) => (
+            state.project_id.clone(),
+            Some(state.branch_id.clone()),
+            state.databases.clone(),
+        ),
+        None => {
+            let parts = crate::utils::parse_postgres_url(target_url)?;
+            let project_id = match crate::utils::parse_serendb_url_for_ids(target_url) {
+                Some((project_id, _, _)) => project_id,
+                None => client
+                    .find_project_by_hostname(&parts.host)
+                    .await?
+                    .ok_or_else(|| {
+                        anyhow::anyhow!(
+                            "--new-branch needs a SerenDB target, but no SerenDB project \
+                             this API key can see serves {}",
+                            parts.host
+                        )
+                    })?,
+            };
+            let parent_id = client
+                .find_endpoint_by_hostname(&project_id, &parts.host)
+                .await?
+                .map(|endpoint| endpoint.branch_id);
+            (project_id, parent_id, vec![parts.database])
+        }
+    };
+    let Some(database) = databases.first() else {
+        bail!(
+            "The SerenDB target has no databases recorded. Re-run init without --new-branch first."
+        );
+    };
+
+    let project = client.get_project(&project_id).await?;
+    let branches = client.list_branches(&project_id).await?;
+    let parent = match parent_id {
+        Some(id) => branches.iter().find(|branch| branch.id == id),
+        None => branches
+            .iter()
+            .find(|branch| branch.is_default)
+            .or(branches.first()),
+    };
+    let Some(parent) = parent.cloned() else {
+        bail!(
+            "Could not find the branch to copy in project '{}'",
+            project.name
+        );
+    };
+
+    let name = branch_name(name, &branches, Utc::now());
+    println!(
+        "Creating branch '{}' from '{}' in project '{}'...",
+        name, parent.name, project.name
+    );
+    let branch = client
+        .create_branch(&project_id, &name, Some(&parent.id))
+        .await?;
+    println!("  Waiting for the branch's endpoint to start...");
+    client
+        .wait_for_endpoint(&project_id, &branch.id, ENDPOINT_START_TIMEOUT)
+        .await?;
+    println!("✓ Branch '{}' is ready", branch.name);
+
+    let connection_string = client
+        .get_connection_string(&project_id, &branch.id, database, false)
+        .await?;
+    Ok(RunBranch {
+        state: TargetState::new(
+            project.id,
+            project.name,
+            branch.id,
+            branch.name,
+            databases,
+            source_url,
+        ),
+        connection_string,
+        parent_name: parent.name,
+    })
+}
+
+/// Make the run's branch the project's default branch
+///
+/// The branch the run copied from keeps the data from before the run, so it
+/// can be promoted back or deleted in the console.
+pub async fn promote_run_branch(client: &ConsoleClient, run: &RunBranch) -> Result<()> {
+    client
+        .set_default_branch(&run.state.project_id, &run.state.branch_id)
+        .await?;
+    println!(
+        "✓ Branch '{}' is now the default branch of project '{}'",
+        run.state.branch_name, run.state.project_name
+    );
+    println!(
+        "  Branch '{}' keeps the data from before this run; delete it at console.serendb.com once it is no longer needed.",
+        run.parent_name
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_branch_name_adds_timestamp_when_taken() {
+        let now = DateTime::parse_from_rfc3339("2026-10-17T02:30:05Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let branches = vec![Branch {
+            id: "br1".into(),
+            name: "nightly-refresh".into(),
+            project_id: "proj".into(),
+            is_default: false,
+        }];
+        assert_eq!(branch_name("staging", &branches, now), "staging");
+        assert_eq!(
+            branch_name("nightly-refresh", &branches, now),
+            "nightly-refresh-20261017-023005"
+        );
+    }
+}
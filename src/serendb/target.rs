@@ -1,5 +1,5 @@
 // ABOUTME: Persists SerenDB target selection for reuse across commands
-// ABOUTME: Stores project/branch/database selection in .seren-replicator/target.json
+// ABOUTME: Stores project/branch/database selection in .seren-replicator/target.json (encrypted)
 
 use anyhow::{Context, Result};
 use chrono::Utc;
@@ -80,12 +80,9 @@ fn target_file_path() -> PathBuf {
 pub fn load_target_state() -> Result<Option<TargetState>> {
     let path = target_file_path();
 
-    if !path.exists() {
+    let Some(content) = crate::encryption::read_state_file(&path)? else {
         return Ok(None);
-    }
-
-    let content = std::fs::read_to_string(&path)
-        .with_context(|| format!("Failed to read {}", path.display()))?;
+    };
 
     let state: TargetState = serde_json::from_str(&content).with_context(|| {
         format!(
@@ -109,16 +106,9 @@ pub fn load_target_state() -> Result<Option<TargetState>> {
 pub fn save_target_state(state: &TargetState) -> Result<()> {
     let path = target_file_path();
 
-    if let Some(parent) = path.parent() {
-        std::fs::create_dir_all(parent)
-            .with_context(|| format!("Failed to create directory {}", parent.display()))?;
-    }
-
     let content =
         serde_json::to_string_pretty(state).context("Failed to serialize target state")?;
-
-    std::fs::write(&path, content)
-        .with_context(|| format!("Failed to write {}", path.display()))?;
+    crate::encryption::write_state_file(&path, &content)?;
 
     tracing::info!("Saved SerenDB target configuration to {}", path.display());
     Ok(())
@@ -144,7 +134,9 @@ mod tests {
         let dir = tempdir().expect("tempdir");
         let file_path = dir.path().join("target.json");
         std::env::set_var(TARGET_FILE_ENV, &file_path);
+        std::env::set_var(crate::encryption::STATE_KEY_ENV, "test-key");
         func();
+        std::env::remove_var(crate::encryption::STATE_KEY_ENV);
         std::env::remove_var(TARGET_FILE_ENV);
     }
 
@@ -168,6 +160,9 @@ mod tests {
             assert_eq!(loaded.project_id, "proj-123");
             assert_eq!(loaded.databases.len(), 2);
             assert!(loaded.source_matches("postgresql://localhost/source"));
+
+            let on_disk = std::fs::read_to_string(target_file_path()).unwrap();
+            assert!(!on_disk.contains("proj-123"));
         });
     }
 
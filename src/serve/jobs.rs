@@ -0,0 +1,736 @@
+// ABOUTME: Job registry for the serve command - runs submitted JobSpecs in the background
+// ABOUTME: Tracks status, pause/resume, cancellation, and a bounded log buffer per job
+
+use anyhow::{bail, Context, Result};
+use crypto_box::SecretKey;
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{broadcast, oneshot, watch};
+
+use crate::commands::InitOptions;
+use crate::events::EventSink;
+use crate::filters::ReplicationFilter;
+use crate::redact::ConnectionString;
+use crate::remote::JobSpec;
+use crate::replicator::Replicator;
+use crate::xmin::SyncStats;
+use crate::SourceType;
+
+/// Log lines kept per job for `StreamLogs` callers that connect late
+pub const LOG_BUFFER_LINES: usize = 1000;
+
+/// Finished jobs kept for `GetJob`, `ListJobs`, and `StreamLogs` by default
+pub const FINISHED_JOBS_KEPT: usize = 100;
+
+/// Lifecycle of a submitted job
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobState {
+    Pending,
+    Running,
+    Paused,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl JobState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobState::Pending => "pending",
+            JobState::Running => "running",
+            JobState::Paused => "paused",
+            JobState::Completed => "completed",
+            JobState::Failed => "failed",
+            JobState::Cancelled => "cancelled",
+        }
+    }
+
+    /// Whether the job has stopped for good
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            JobState::Completed | JobState::Failed | JobState::Cancelled
+        )
+    }
+}
+
+/// Point-in-time view of a job
+#[derive(Debug, Clone)]
+pub struct JobSummary {
+    pub id: String,
+    pub command: String,
+    pub state: JobState,
+    pub created_at: String,
+    pub started_at: Option<String>,
+    pub completed_at: Option<String>,
+    pub error: Option<String>,
+    pub sync_cycles: u64,
+    pub rows_synced: u64,
+}
+
+/// One line of a job's log
+#[derive(Debug, Clone)]
+pub struct LogLine {
+    pub timestamp: String,
+    pub message: String,
+}
+
+struct JobLogs {
+    lines: VecDeque<LogLine>,
+    /// Dropped when the job finishes, which ends every follower's stream
+    sender: Option<broadcast::Sender<LogLine>>,
+}
+
+/// A submitted job and its live state
+pub struct Job {
+    summary: Mutex<JobSummary>,
+    logs: Mutex<JobLogs>,
+    paused: watch::Sender<bool>,
+    cancel: Mutex<Option<oneshot::Sender<()>>>,
+}
+
+impl Job {
+    fn new(id: String, command: String) -> Self {
+        let (sender, _) = broadcast::channel(256);
+        Self {
+            summary: Mutex::new(JobSummary {
+                id,
+                command,
+                state: JobState::Pending,
+                created_at: now(),
+                started_at: None,
+                completed_at: None,
+                error: None,
+                sync_cycles: 0,
+                rows_synced: 0,
+            }),
+            logs: Mutex::new(JobLogs {
+                lines: VecDeque::new(),
+                sender: Some(sender),
+            }),
+            paused: watch::Sender::new(false),
+            cancel: Mutex::new(None),
+        }
+    }
+
+    pub fn id(&self) -> String {
+        self.summary.lock().unwrap().id.clone()
+    }
+
+    pub fn summary(&self) -> JobSummary {
+        self.summary.lock().unwrap().clone()
+    }
+
+    /// Append a line to the job's log, with credentials masked, and send it to followers
+    pub fn log(&self, message: impl Into<String>) {
+        let line = LogLine {
+            timestamp: now(),
+            message: crate::redact::redact(&message.into()),
+        };
+        let mut logs = self.logs.lock().unwrap();
+        if logs.lines.len() == LOG_BUFFER_LINES {
+            logs.lines.pop_front();
+        }
+        logs.lines.push_back(line.clone());
+        if let Some(ref sender) = logs.sender {
+            let _ = sender.send(line);
+        }
+    }
+
+    /// Buffered lines, plus a receiver for new ones while the job is still going
+    pub fn subscribe(&self) -> (Vec<LogLine>, Option<broadcast::Receiver<LogLine>>) {
+        let logs = self.logs.lock().unwrap();
+        let receiver = logs.sender.as_ref().map(|sender| sender.subscribe());
+        (logs.lines.iter().cloned().collect(), receiver)
+    }
+
+    /// Stop starting new sync cycles until [`Job::resume`]
+    ///
+    /// A snapshot or sync cycle already under way runs to completion first.
+    pub fn pause(&self) -> Result<JobSummary> {
+        {
+            let mut summary = self.summary.lock().unwrap();
+            if summary.state.is_finished() {
+                bail!("Job {} is already {}", summary.id, summary.state.as_str());
+            }
+            summary.state = JobState::Paused;
+        }
+        self.paused.send_replace(true);
+        self.log("Paused; the current snapshot or sync cycle finishes first");
+        Ok(self.summary())
+    }
+
+    /// Let a paused job continue
+    pub fn resume(&self) -> Result<JobSummary> {
+        {
+            let mut summary = self.summary.lock().unwrap();
+            if summary.state != JobState::Paused {
+                bail!(
+                    "Job {} is {}, not paused",
+                    summary.id,
+                    summary.state.as_str()
+                );
+            }
+            summary.state = if summary.started_at.is_some() {
+                JobState::Running
+            } else {
+                JobState::Pending
+            };
+        }
+        self.paused.send_replace(false);
+        self.log("Resumed");
+        Ok(self.summary())
+    }
+
+    /// Stop the job wherever it is
+    pub fn cancel(&self) -> Result<JobSummary> {
+        if let Some(cancel) = self.cancel.lock().unwrap().take() {
+            let _ = cancel.send(());
+        }
+        if !self.finish(JobState::Cancelled, None) {
+            let summary = self.summary();
+            bail!("Job {} is already {}", summary.id, summary.state.as_str());
+        }
+        Ok(self.summary())
+    }
+
+    fn start(&self) {
+        let mut summary = self.summary.lock().unwrap();
+        summary.started_at = Some(now());
+        if summary.state == JobState::Pending {
+            summary.state = JobState::Running;
+        }
+    }
+
+    /// Move to a final state; returns false if the job had already finished
+    fn finish(&self, state: JobState, error: Option<String>) -> bool {
+        {
+            let mut summary = self.summary.lock().unwrap();
+            if summary.state.is_finished() {
+                return false;
+            }
+            summary.state = state;
+            summary.completed_at = Some(now());
+            summary.error = error.as_deref().map(crate::redact::redact);
+        }
+        match error {
+            Some(error) => self.log(format!("Job {}: {}", state.as_str(), error)),
+            None => self.log(format!("Job {}", state.as_str())),
+        }
+        self.logs.lock().unwrap().sender = None;
+        true
+    }
+
+    async fn wait_while_paused(&self) {
+        let mut paused = self.paused.subscribe();
+        let _ = paused.wait_for(|paused| !*paused).await;
+    }
+
+    fn record_cycle(&self, stats: &SyncStats) {
+        let cycle = {
+            let mut summary = self.summary.lock().unwrap();
+            summary.sync_cycles += 1;
+            summary.rows_synced += stats.rows_synced;
+            summary.sync_cycles
+        };
+        self.log(format!(
+            "Sync cycle {} completed: {} tables, {} rows in {}ms",
+            cycle, stats.tables_synced, stats.rows_synced, stats.duration_ms
+        ));
+        for error in &stats.errors {
+            self.log(format!("Sync cycle {} error: {}", cycle, error));
+        }
+    }
+}
+
+impl EventSink for Job {
+    fn on_table_start(&self, database: &str, table: &str) {
+        self.log(format!("Starting {}/{}", database, table));
+    }
+
+    fn on_rows_copied(&self, database: &str, table: &str, rows: u64) {
+        self.log(format!("Copied {} rows into {}/{}", rows, database, table));
+    }
+
+    fn on_error(&self, database: &str, table: Option<&str>, message: &str) {
+        match table {
+            Some(table) => self.log(format!("Error in {}/{}: {}", database, table, message)),
+            None => self.log(format!("Error in {}: {}", database, message)),
+        }
+    }
+}
+
+/// What a job does once it starts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct JobPlan {
+    snapshot: bool,
+    sync: bool,
+}
+
+impl JobPlan {
+    /// Read the command and options of a JobSpec
+    ///
+    /// `init` takes a snapshot and then keeps syncing unless `enable_sync` is
+    /// false; `sync` only runs sync cycles. Continuous sync needs a PostgreSQL
+    /// source.
+    fn from_spec(spec: &JobSpec) -> Result<Self> {
+        let postgres_source =
+            crate::detect_source_type(spec.source_url.expose())? == SourceType::PostgreSQL;
+        let plan = match spec.command.as_str() {
+            "init" => JobPlan {
+                snapshot: true,
+                sync: option_bool(spec, "enable_sync")?.unwrap_or(postgres_source),
+            },
+            "sync" => JobPlan {
+                snapshot: false,
+                sync: true,
+            },
+            other => bail!(
+                "Unsupported job command '{}': expected \"init\" or \"sync\"",
+                other
+            ),
+        };
+        if plan.sync && !postgres_source {
+            bail!("Continuous sync is only supported for PostgreSQL sources");
+        }
+        if spec.schedule.is_some() {
+            bail!("Scheduled jobs are run by the SerenAI orchestrator; submit them with `jobs schedule`");
+        }
+        Ok(plan)
+    }
+}
+
+fn option_bool(spec: &JobSpec, name: &str) -> Result<Option<bool>> {
+    match spec.options.get(name) {
+        None | Some(serde_json::Value::Null) => Ok(None),
+        Some(serde_json::Value::Bool(value)) => Ok(Some(*value)),
+        Some(other) => bail!("Job option '{}' must be a boolean, got {}", name, other),
+    }
+}
+
+fn option_usize(spec: &JobSpec, name: &str) -> Result<Option<usize>> {
+    match spec.options.get(name) {
+        None | Some(serde_json::Value::Null) => Ok(None),
+        Some(value) => match value.as_u64() {
+            Some(count) if count > 0 => Ok(Some(count as usize)),
+            _ => bail!(
+                "Job option '{}' must be a positive integer, got {}",
+                name,
+                value
+            ),
+        },
+    }
+}
+
+/// Every job submitted to this server
+pub struct JobManager {
+    jobs: Mutex<Vec<Arc<Job>>>,
+    finished_jobs_kept: usize,
+    state_dir: PathBuf,
+    sync_interval: Duration,
+    sealing_key: Option<SecretKey>,
+}
+
+impl JobManager {
+    /// Jobs keep their sync state under `state_dir/<job id>/` and sleep
+    /// `sync_interval` between sync cycles
+    pub fn new(state_dir: impl Into<PathBuf>, sync_interval: Duration) -> Self {
+        Self {
+            jobs: Mutex::new(Vec::new()),
+            finished_jobs_kept: FINISHED_JOBS_KEPT,
+            state_dir: state_dir.into(),
+            sync_interval,
+            sealing_key: None,
+        }
+    }
+
+    /// Open sealed credentials in submitted specs with `key` (see
+    /// [`JobSpec::seal`]); sealed specs are refused without one
+    pub fn with_sealing_key(mut self, key: SecretKey) -> Self {
+        self.sealing_key = Some(key);
+        self
+    }
+
+    /// Keep at most `limit` finished jobs; older ones are forgotten, with
+    /// their logs, as new jobs are submitted
+    pub fn with_finished_job_limit(mut self, limit: usize) -> Self {
+        self.finished_jobs_kept = limit;
+        self
+    }
+
+    /// Check a JobSpec and start running it in the background
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the spec cannot run here: an unknown schema version
+    /// or command, sealed credentials this server has no key for, a SerenDB
+    /// project target without `target_url`, or an invalid filter, table rule,
+    /// or option. `max_connections_per_host` is
+    /// left to the server's own `--max-connections-per-host`.
+    pub fn submit(&self, mut spec: JobSpec) -> Result<Arc<Job>> {
+        if !matches!(spec.version.split('.').next(), Some("1" | "2")) {
+            bail!("Unsupported job spec schema_version '{}'", spec.version);
+        }
+        if spec.is_sealed() {
+            let key = self.sealing_key.as_ref().context(
+                "Job spec has sealed credentials but this server was started without --sealing-key",
+            )?;
+            spec.unseal(key)?;
+        }
+        let plan = JobPlan::from_spec(&spec)?;
+        let target = spec.target_url.clone().map(ConnectionString::into_inner).context(
+            "Job spec needs a target_url; SerenDB project targets are only resolved by the SerenAI cloud",
+        )?;
+        let filter = match spec.filter {
+            Some(ref filter) => ReplicationFilter::new(
+                filter.include_databases.clone(),
+                filter.exclude_databases.clone(),
+                filter.include_tables.clone(),
+                filter.exclude_tables.clone(),
+            )?,
+            None => ReplicationFilter::empty(),
+        };
+        let filter = match spec.table_rules {
+            Some(ref rules) => filter.with_table_rules(rules.to_table_rules()?),
+            None => filter,
+        };
+        let init_options = InitOptions {
+            defer_indexes: option_bool(&spec, "defer_indexes")?.unwrap_or(false),
+            index_build_jobs: option_usize(&spec, "index_build_jobs")?,
+            analyze_jobs: option_usize(&spec, "analyze_jobs")?,
+            restore_jobs: option_usize(&spec, "restore_jobs")?,
+            ..InitOptions::default()
+        };
+
+        let id = uuid::Uuid::new_v4().to_string();
+        let job = Arc::new(Job::new(id.clone(), spec.command.clone()));
+        let replicator = Replicator::builder()
+            .source(spec.source_url.expose())
+            .target(target)
+            .filter(filter)
+            .drop_existing(option_bool(&spec, "drop_existing")?.unwrap_or(false))
+            .init_options(init_options)
+            .state_file(self.state_dir.join(&id).join("xmin-sync-state.json"))
+            .event_sink(job.clone())
+            .build()?;
+
+        job.log(format!("Job {} submitted ({})", id, spec.command));
+        let (cancel_tx, cancel_rx) = oneshot::channel();
+        *job.cancel.lock().unwrap() = Some(cancel_tx);
+        let runner = job.clone();
+        let sync_interval = self.sync_interval;
+        // Snapshots hold SQLite connections across awaits, so their futures are
+        // not Send; each job gets a thread with its own runtime instead
+        std::thread::Builder::new()
+            .name(format!("job-{}", id))
+            .spawn(move || {
+                let runtime = match tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                {
+                    Ok(runtime) => runtime,
+                    Err(e) => {
+                        runner.finish(
+                            JobState::Failed,
+                            Some(format!("Failed to start job runtime: {}", e)),
+                        );
+                        return;
+                    }
+                };
+                runtime.block_on(async move {
+                    tokio::select! {
+                        _ = run_job(runner, replicator, plan, sync_interval) => {}
+                        _ = cancel_rx => {}
+                    }
+                });
+            })
+            .context("Failed to start job thread")?;
+
+        self.add(job.clone());
+        Ok(job)
+    }
+
+    /// Register a job, dropping the oldest finished jobs beyond the limit
+    fn add(&self, job: Arc<Job>) {
+        let mut jobs = self.jobs.lock().unwrap();
+        jobs.push(job);
+        let finished = jobs
+            .iter()
+            .filter(|job| job.summary().state.is_finished())
+            .count();
+        let mut excess = finished.saturating_sub(self.finished_jobs_kept);
+        jobs.retain(|job| {
+            if excess > 0 && job.summary().state.is_finished() {
+                excess -= 1;
+                false
+            } else {
+                true
+            }
+        });
+    }
+
+    pub fn get(&self, id: &str) -> Option<Arc<Job>> {
+        self.jobs
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|job| job.id() == id)
+            .cloned()
+    }
+
+    /// Summaries of every job, oldest first
+    pub fn list(&self) -> Vec<JobSummary> {
+        self.jobs
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|job| job.summary())
+            .collect()
+    }
+}
+
+async fn run_job(job: Arc<Job>, replicator: Replicator, plan: JobPlan, sync_interval: Duration) {
+    // A job paused before it started waits here
+    job.wait_while_paused().await;
+    job.start();
+    match execute(&job, &replicator, plan, sync_interval).await {
+        Ok(()) => {
+            job.finish(JobState::Completed, None);
+        }
+        Err(e) => {
+            job.finish(JobState::Failed, Some(format!("{:#}", e)));
+        }
+    }
+}
+
+async fn execute(
+    job: &Job,
+    replicator: &Replicator,
+    plan: JobPlan,
+    sync_interval: Duration,
+) -> Result<()> {
+    if plan.snapshot {
+        job.log("Starting snapshot");
+        let report = replicator.snapshot().await?;
+        job.log(format!(
+            "Snapshot copied {} database(s) in {:.1}s",
+            report.databases.len(),
+            report.duration.as_secs_f64()
+        ));
+    }
+    if !plan.sync {
+        return Ok(());
+    }
+
+    loop {
+        job.wait_while_paused().await;
+        match replicator.sync_once().await {
+            Ok(stats) => job.record_cycle(&stats),
+            // Like the sync daemon, a failed cycle is retried on the next interval
+            Err(e) => job.log(format!("Sync cycle failed: {:#}", e)),
+        }
+        tokio::time::sleep(sync_interval).await;
+    }
+}
+
+fn now() -> String {
+    chrono::Utc::now().to_rfc3339()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::remote::{TableFilterSpec, TableRulesSpec};
+    use std::collections::HashMap;
+
+    fn spec(command: &str, source: &str) -> JobSpec {
+        JobSpec {
+            version: "1.0".to_string(),
+            command: command.to_string(),
+            source_url: source.into(),
+            target_url: Some("postgresql://user@target.example.com/app".into()),
+            target_project_id: None,
+            target_branch_id: None,
+            target_databases: None,
+            seren_api_key: None,
+            filter: None,
+            table_rules: None,
+            options: HashMap::new(),
+            schedule: None,
+            sealed: None,
+            artifact_checksums: None,
+        }
+    }
+
+    #[test]
+    fn test_job_plan_from_spec() {
+        let pg = "postgresql://user@source.example.com/app";
+        assert_eq!(
+            JobPlan::from_spec(&spec("init", pg)).unwrap(),
+            JobPlan {
+                snapshot: true,
+                sync: true
+            }
+        );
+        assert_eq!(
+            JobPlan::from_spec(&spec("sync", pg)).unwrap(),
+            JobPlan {
+                snapshot: false,
+                sync: true
+            }
+        );
+
+        let mut no_sync = spec("init", pg);
+        no_sync
+            .options
+            .insert("enable_sync".to_string(), serde_json::Value::Bool(false));
+        assert!(!JobPlan::from_spec(&no_sync).unwrap().sync);
+
+        // Non-PostgreSQL sources snapshot only, and cannot be asked to sync
+        let mysql = "mysql://user@source.example.com/app";
+        assert!(!JobPlan::from_spec(&spec("init", mysql)).unwrap().sync);
+        assert!(JobPlan::from_spec(&spec("sync", mysql)).is_err());
+
+        assert!(JobPlan::from_spec(&spec("verify", pg)).is_err());
+    }
+
+    #[test]
+    fn test_option_bool_rejects_non_booleans() {
+        let mut job = spec("init", "postgresql://user@source.example.com/app");
+        job.options
+            .insert("drop_existing".to_string(), serde_json::json!("yes"));
+        assert!(option_bool(&job, "drop_existing").is_err());
+        assert_eq!(option_bool(&job, "enable_sync").unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_submit_rejects_unrunnable_specs() {
+        let manager = JobManager::new("/tmp/unused", Duration::from_secs(60));
+        let pg = "postgresql://user@source.example.com/app";
+
+        let mut project_target = spec("init", pg);
+        project_target.target_url = None;
+        project_target.target_project_id = Some("project".to_string());
+        assert!(manager.submit(project_target).is_err());
+
+        let mut future_version = spec("init", pg);
+        future_version.version = "3.0".to_string();
+        assert!(manager.submit(future_version).is_err());
+
+        let mut filtered_schema_only = spec("init", pg);
+        filtered_schema_only.table_rules = Some(TableRulesSpec {
+            schema_only_tables: vec!["app.public.events".to_string()],
+            table_filters: vec![TableFilterSpec {
+                table: "app.public.events".to_string(),
+                predicate: "id > 10".to_string(),
+            }],
+            ..TableRulesSpec::default()
+        });
+        assert!(manager.submit(filtered_schema_only).is_err());
+
+        // Sealed credentials need the server's key
+        let mut sealed = spec("init", pg);
+        sealed.sealed = Some(crate::remote::SealedSecrets {
+            key_id: "k1".to_string(),
+            sealed_key: String::new(),
+            ciphertext: String::new(),
+        });
+        assert!(manager.submit(sealed).is_err());
+
+        let mut zero_jobs = spec("init", pg);
+        zero_jobs
+            .options
+            .insert("restore_jobs".to_string(), serde_json::json!(0));
+        assert!(manager.submit(zero_jobs).is_err());
+
+        assert!(manager.list().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_job_pause_resume_and_logs() {
+        let job = Job::new("job-1".to_string(), "sync".to_string());
+        job.log("first");
+        let (backlog, receiver) = job.subscribe();
+        assert_eq!(backlog.len(), 1);
+        let mut receiver = receiver.unwrap();
+
+        assert_eq!(job.pause().unwrap().state, JobState::Paused);
+        assert!(receiver.recv().await.unwrap().message.starts_with("Paused"));
+        // Never started, so resuming goes back to pending
+        assert_eq!(job.resume().unwrap().state, JobState::Pending);
+        assert!(job.resume().is_err());
+
+        assert_eq!(job.cancel().unwrap().state, JobState::Cancelled);
+        assert!(job.cancel().is_err());
+        assert!(job.pause().is_err());
+
+        // Finishing closes the stream for followers
+        let (_, receiver) = job.subscribe();
+        assert!(receiver.is_none());
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_sqlite_init_job_completes() {
+        let target_url = std::env::var("TEST_TARGET_URL").unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("serve_job.db");
+        {
+            let conn = rusqlite::Connection::open(&db_path).unwrap();
+            conn.execute_batch(
+                "CREATE TABLE serve_job_items (id INTEGER PRIMARY KEY, name TEXT);
+                 INSERT INTO serve_job_items (name) VALUES ('a'), ('b'), ('c');",
+            )
+            .unwrap();
+        }
+
+        let mut job_spec = spec("init", db_path.to_str().unwrap());
+        job_spec.target_url = Some(target_url.into());
+        let manager = JobManager::new(dir.path().join("state"), Duration::from_secs(60));
+        let job = manager.submit(job_spec).unwrap();
+
+        for _ in 0..300 {
+            if job.summary().state.is_finished() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+
+        let summary = job.summary();
+        assert_eq!(summary.state, JobState::Completed, "{:?}", summary.error);
+        let (logs, _) = job.subscribe();
+        assert!(logs
+            .iter()
+            .any(|line| line.message == "Copied 3 rows into main/serve_job_items"));
+    }
+
+    #[test]
+    fn test_finished_jobs_are_evicted_oldest_first() {
+        let manager =
+            JobManager::new("/tmp/unused", Duration::from_secs(60)).with_finished_job_limit(1);
+        let running = Arc::new(Job::new("running".to_string(), "sync".to_string()));
+        manager.add(running);
+        for id in ["done-1", "done-2", "done-3"] {
+            let job = Arc::new(Job::new(id.to_string(), "init".to_string()));
+            job.finish(JobState::Completed, None);
+            manager.add(job);
+        }
+
+        let ids: Vec<String> = manager.list().into_iter().map(|job| job.id).collect();
+        assert_eq!(ids, vec!["running", "done-3"]);
+        assert!(manager.get("done-1").is_none());
+    }
+
+    #[test]
+    fn test_log_buffer_is_bounded() {
+        let job = Job::new("job-1".to_string(), "init".to_string());
+        for i in 0..LOG_BUFFER_LINES + 5 {
+            job.log(format!("line {}", i));
+        }
+        let (backlog, _) = job.subscribe();
+        assert_eq!(backlog.len(), LOG_BUFFER_LINES);
+        assert_eq!(backlog[0].message, "line 5");
+    }
+}
@@ -0,0 +1,12 @@
+// ABOUTME: Long-running replicator service for the serve command
+// ABOUTME: Accepts JobSpecs over gRPC and runs them with the library Replicator
+
+pub mod jobs;
+pub mod server;
+
+pub mod proto {
+    tonic::include_proto!("replicator");
+}
+
+pub use jobs::{Job, JobManager, JobState, JobSummary};
+pub use server::serve;
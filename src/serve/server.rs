@@ -0,0 +1,403 @@
+// ABOUTME: gRPC front end for the serve command's JobManager
+// ABOUTME: Maps Replicator service calls onto jobs and streams job logs
+
+use anyhow::{Context, Result};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use subtle::ConstantTimeEq;
+use tokio::net::TcpListener;
+use tokio::sync::{broadcast, mpsc};
+use tokio_stream::wrappers::{ReceiverStream, TcpListenerStream};
+use tokio_stream::Stream;
+use tonic::service::Interceptor;
+use tonic::transport::{Identity, Server, ServerTlsConfig};
+use tonic::{Request, Response, Status};
+
+use super::jobs::{JobManager, JobSummary, LogLine};
+use super::proto::replicator_server::{Replicator as ReplicatorApi, ReplicatorServer};
+use super::proto::{
+    Job as JobMessage, JobRequest, ListJobsRequest, ListJobsResponse, LogLine as LogLineMessage,
+    StreamLogsRequest, SubmitJobRequest,
+};
+use crate::remote::JobSpec;
+use crate::secrets::SecretResolver;
+
+/// Serve the Replicator gRPC API on `listener` until `shutdown` resolves
+///
+/// When `token` is set, every call must send `authorization: Bearer <token>`.
+/// With a `tls` identity the API is served over TLS; otherwise it is plaintext
+/// HTTP/2, which is only safe on a loopback address.
+pub async fn serve(
+    listener: TcpListener,
+    token: Option<String>,
+    tls: Option<Identity>,
+    jobs: Arc<JobManager>,
+    shutdown: impl Future<Output = ()>,
+) -> Result<()> {
+    let service = ReplicatorService { jobs };
+    let interceptor = AuthInterceptor {
+        expected: token.map(|token| Arc::new(format!("Bearer {}", token).into_bytes())),
+    };
+    let mut builder = Server::builder();
+    if let Some(identity) = tls {
+        builder = builder
+            .tls_config(ServerTlsConfig::new().identity(identity))
+            .context("Invalid TLS certificate or key")?;
+    }
+    builder
+        .add_service(ReplicatorServer::with_interceptor(service, interceptor))
+        .serve_with_incoming_shutdown(TcpListenerStream::new(listener), shutdown)
+        .await
+        .context("gRPC server exited")
+}
+
+#[derive(Clone)]
+struct AuthInterceptor {
+    /// The full `authorization` header value callers must send
+    expected: Option<Arc<Vec<u8>>>,
+}
+
+impl Interceptor for AuthInterceptor {
+    fn call(&mut self, request: Request<()>) -> Result<Request<()>, Status> {
+        let Some(ref expected) = self.expected else {
+            return Ok(request);
+        };
+        let provided = request
+            .metadata()
+            .get("authorization")
+            .ok_or_else(|| Status::unauthenticated("missing authorization header"))?;
+        // Constant-time, so response timing does not reveal how much of the
+        // token a guess got right
+        if bool::from(provided.as_bytes().ct_eq(expected.as_slice())) {
+            Ok(request)
+        } else {
+            Err(Status::unauthenticated("invalid authorization header"))
+        }
+    }
+}
+
+struct ReplicatorService {
+    jobs: Arc<JobManager>,
+}
+
+type LogStream = Pin<Box<dyn Stream<Item = Result<LogLineMessage, Status>> + Send>>;
+
+#[tonic::async_trait]
+impl ReplicatorApi for ReplicatorService {
+    async fn submit_job(
+        &self,
+        request: Request<SubmitJobRequest>,
+    ) -> Result<Response<JobMessage>, Status> {
+        let mut spec: JobSpec = serde_json::from_str(&request.get_ref().spec_json)
+            .map_err(|e| Status::invalid_argument(format!("invalid job spec: {}", e)))?;
+        let mut resolved = Vec::new();
+        resolve_spec_secrets(&mut spec, &mut resolved)
+            .await
+            .map_err(|e| Status::failed_precondition(client_message(&e, &resolved)))?;
+        let job = self
+            .jobs
+            .submit(spec)
+            .map_err(|e| Status::invalid_argument(client_message(&e, &resolved)))?;
+        Ok(Response::new(job_to_proto(job.summary())))
+    }
+
+    async fn get_job(&self, request: Request<JobRequest>) -> Result<Response<JobMessage>, Status> {
+        let id = &request.get_ref().job_id;
+        let job = self.jobs.get(id).ok_or_else(|| not_found(id))?;
+        Ok(Response::new(job_to_proto(job.summary())))
+    }
+
+    async fn list_jobs(
+        &self,
+        _: Request<ListJobsRequest>,
+    ) -> Result<Response<ListJobsResponse>, Status> {
+        let jobs = self.jobs.list().into_iter().map(job_to_proto).collect();
+        Ok(Response::new(ListJobsResponse { jobs }))
+    }
+
+    async fn pause_job(
+        &self,
+        request: Request<JobRequest>,
+    ) -> Result<Response<JobMessage>, Status> {
+        let id = &request.get_ref().job_id;
+        let job = self.jobs.get(id).ok_or_else(|| not_found(id))?;
+        let summary = job.pause().map_err(precondition_err)?;
+        Ok(Response::new(job_to_proto(summary)))
+    }
+
+    async fn resume_job(
+        &self,
+        request: Request<JobRequest>,
+    ) -> Result<Response<JobMessage>, Status> {
+        let id = &request.get_ref().job_id;
+        let job = self.jobs.get(id).ok_or_else(|| not_found(id))?;
+        let summary = job.resume().map_err(precondition_err)?;
+        Ok(Response::new(job_to_proto(summary)))
+    }
+
+    async fn cancel_job(
+        &self,
+        request: Request<JobRequest>,
+    ) -> Result<Response<JobMessage>, Status> {
+        let id = &request.get_ref().job_id;
+        let job = self.jobs.get(id).ok_or_else(|| not_found(id))?;
+        let summary = job.cancel().map_err(precondition_err)?;
+        Ok(Response::new(job_to_proto(summary)))
+    }
+
+    type StreamLogsStream = LogStream;
+
+    async fn stream_logs(
+        &self,
+        request: Request<StreamLogsRequest>,
+    ) -> Result<Response<Self::StreamLogsStream>, Status> {
+        let request = request.into_inner();
+        let job = self
+            .jobs
+            .get(&request.job_id)
+            .ok_or_else(|| not_found(&request.job_id))?;
+        let (backlog, live) = job.subscribe();
+        let live = if request.follow { live } else { None };
+
+        let (tx, rx) = mpsc::channel(64);
+        tokio::spawn(async move {
+            for line in backlog {
+                if tx.send(Ok(log_to_proto(line))).await.is_err() {
+                    return;
+                }
+            }
+            let Some(mut live) = live else {
+                return;
+            };
+            loop {
+                match live.recv().await {
+                    Ok(line) => {
+                        if tx.send(Ok(log_to_proto(line))).await.is_err() {
+                            return;
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        let notice = LogLineMessage {
+                            timestamp: chrono::Utc::now().to_rfc3339(),
+                            message: format!("({} log lines skipped)", skipped),
+                        };
+                        if tx.send(Ok(notice)).await.is_err() {
+                            return;
+                        }
+                    }
+                    // The job finished
+                    Err(broadcast::error::RecvError::Closed) => return,
+                }
+            }
+        });
+
+        Ok(Response::new(Box::pin(ReceiverStream::new(rx))))
+    }
+}
+
+/// Resolve `secret://` references and a `cloudsql://` source in a spec with
+/// this server's credentials
+///
+/// Every value read from a secret is pushed to `resolved` (even when a later
+/// step fails) so errors can be scrubbed before they go back to the client.
+async fn resolve_spec_secrets(spec: &mut JobSpec, resolved: &mut Vec<String>) -> Result<()> {
+    let resolver = SecretResolver::for_remote_specs();
+    let source_url = resolve_field(&resolver, spec.source_url.expose(), resolved)
+        .await
+        .context("Failed to resolve source_url")?;
+    let source_url = crate::postgres::cloudsql::resolve(&source_url)
+        .await
+        .context("Failed to resolve source_url")?;
+    if source_url != spec.source_url.expose() {
+        resolved.push(source_url.clone());
+    }
+    spec.source_url = source_url.into();
+    if let Some(target_url) = spec.target_url.as_mut() {
+        *target_url = resolve_field(&resolver, target_url.expose(), resolved)
+            .await
+            .context("Failed to resolve target_url")?
+            .into();
+    }
+    Ok(())
+}
+
+async fn resolve_field(
+    resolver: &SecretResolver,
+    value: &str,
+    resolved: &mut Vec<String>,
+) -> Result<String> {
+    let secret = resolver.resolve(value).await?;
+    if crate::secrets::is_secret_ref(value) {
+        resolved.push(secret.clone());
+    }
+    Ok(secret)
+}
+
+/// Format an error for a client: credentials in URLs are masked and any value
+/// resolved from a secret is removed entirely
+fn client_message(err: &anyhow::Error, resolved: &[String]) -> String {
+    let mut message = format!("{:#}", err);
+    for value in resolved.iter().filter(|value| !value.is_empty()) {
+        message = message.replace(value.as_str(), crate::redact::MASK);
+    }
+    crate::redact::redact(&message)
+}
+
+fn job_to_proto(summary: JobSummary) -> JobMessage {
+    JobMessage {
+        job_id: summary.id,
+        command: summary.command,
+        status: summary.state.as_str().to_string(),
+        created_at: summary.created_at,
+        started_at: summary.started_at.unwrap_or_default(),
+        completed_at: summary.completed_at.unwrap_or_default(),
+        error: summary.error.unwrap_or_default(),
+        sync_cycles: summary.sync_cycles,
+        rows_synced: summary.rows_synced,
+    }
+}
+
+fn log_to_proto(line: LogLine) -> LogLineMessage {
+    LogLineMessage {
+        timestamp: line.timestamp,
+        message: line.message,
+    }
+}
+
+fn not_found(id: &str) -> Status {
+    Status::not_found(format!("no job with id {}", id))
+}
+
+fn precondition_err(err: anyhow::Error) -> Status {
+    Status::failed_precondition(format!("{:#}", err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serve::proto::replicator_client::ReplicatorClient;
+    use std::time::Duration;
+    use tokio::sync::oneshot;
+
+    async fn start_server(token: Option<&str>) -> (String, oneshot::Sender<()>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = format!("http://{}", listener.local_addr().unwrap());
+        let jobs = Arc::new(JobManager::new("/tmp/unused", Duration::from_secs(60)));
+        let (tx, rx) = oneshot::channel();
+        let token = token.map(str::to_string);
+        tokio::spawn(serve(listener, token, None, jobs, async move {
+            let _ = rx.await;
+        }));
+        (addr, tx)
+    }
+
+    #[tokio::test]
+    async fn test_submit_and_lookup_errors() {
+        let (addr, _shutdown) = start_server(None).await;
+        let mut client = ReplicatorClient::connect(addr).await.unwrap();
+
+        let status = client
+            .submit_job(SubmitJobRequest {
+                spec_json: "not json".to_string(),
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+
+        let spec = serde_json::json!({
+            "schema_version": "1.0",
+            "command": "verify",
+            "source_url": "postgresql://user@source.example.com/app",
+            "target_url": "postgresql://user@target.example.com/app",
+            "options": {}
+        });
+        let status = client
+            .submit_job(SubmitJobRequest {
+                spec_json: spec.to_string(),
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+        assert!(status.message().contains("Unsupported job command"));
+
+        let status = client
+            .get_job(JobRequest {
+                job_id: "missing".to_string(),
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::NotFound);
+
+        let jobs = client.list_jobs(ListJobsRequest {}).await.unwrap();
+        assert!(jobs.into_inner().jobs.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_invalid_tls_identity_is_rejected() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let jobs = Arc::new(JobManager::new("/tmp/unused", Duration::from_secs(60)));
+        let identity = Identity::from_pem("not a certificate", "not a key");
+        let err = serve(listener, None, Some(identity), jobs, async {})
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Invalid TLS certificate or key"));
+    }
+
+    #[tokio::test]
+    async fn test_env_secrets_are_not_resolved_for_clients() {
+        std::env::set_var("SERVE_TEST_SERVER_SECRET", "server-only-value");
+        let (addr, _shutdown) = start_server(None).await;
+        let mut client = ReplicatorClient::connect(addr).await.unwrap();
+
+        let spec = serde_json::json!({
+            "schema_version": "1.0",
+            "command": "init",
+            "source_url": "secret://env/SERVE_TEST_SERVER_SECRET",
+            "target_url": "postgresql://user@target.example.com/app",
+            "options": {}
+        });
+        let status = client
+            .submit_job(SubmitJobRequest {
+                spec_json: spec.to_string(),
+            })
+            .await
+            .unwrap_err();
+        std::env::remove_var("SERVE_TEST_SERVER_SECRET");
+        assert_eq!(status.code(), tonic::Code::FailedPrecondition);
+        assert!(status.message().contains("Unknown secret provider 'env'"));
+        assert!(!status.message().contains("server-only-value"));
+    }
+
+    #[test]
+    fn test_client_message_removes_resolved_values() {
+        let err = anyhow::anyhow!("Could not detect source database type from 'opaque-token'");
+        let message = client_message(&err, &["opaque-token".to_string()]);
+        assert!(!message.contains("opaque-token"));
+
+        let err = anyhow::anyhow!("connect to postgresql://app:hunter2@db/app failed");
+        assert!(!client_message(&err, &[]).contains("hunter2"));
+    }
+
+    #[tokio::test]
+    async fn test_token_is_required_when_configured() {
+        let (addr, _shutdown) = start_server(Some("secret")).await;
+        let mut client = ReplicatorClient::connect(addr).await.unwrap();
+
+        let status = client.list_jobs(ListJobsRequest {}).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::Unauthenticated);
+
+        let mut request = Request::new(ListJobsRequest {});
+        request
+            .metadata_mut()
+            .insert("authorization", "Bearer secreT".parse().unwrap());
+        let status = client.list_jobs(request).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::Unauthenticated);
+
+        let mut request = Request::new(ListJobsRequest {});
+        request
+            .metadata_mut()
+            .insert("authorization", "Bearer secret".parse().unwrap());
+        assert!(client.list_jobs(request).await.is_ok());
+    }
+}
@@ -406,6 +406,7 @@ pub async fn convert_table_batched(
 
     let mut total_rows = 0usize;
     let mut batch_num = 0usize;
+    let mut schema = crate::jsonb::schema::DocumentSchema::default();
 
     // Process batches until exhausted
     while let Some(rows) = read_table_batch(sqlite_conn, &mut reader)? {
@@ -421,6 +422,9 @@ pub async fn convert_table_batched(
 
         // Convert batch to JSONB
         let jsonb_rows = convert_batch_to_jsonb(rows, &id_column, total_rows, table)?;
+        for (_, data) in &jsonb_rows {
+            schema.observe(data);
+        }
 
         // COPY batch to PostgreSQL for maximum throughput
         if !jsonb_rows.is_empty() {
@@ -432,6 +436,9 @@ pub async fn convert_table_batched(
                         batch_num, table
                     )
                 })?;
+            crate::events::emit(|sink| {
+                sink.on_rows_copied(super::SQLITE_DATABASE_NAME, table, batch_row_count as u64)
+            });
         }
 
         total_rows += batch_row_count;
@@ -446,6 +453,10 @@ pub async fn convert_table_batched(
         }
     }
 
+    crate::jsonb::schema::SchemaRegistry::default()
+        .record(pg_client, table, source_type, &schema)
+        .await?;
+
     tracing::info!(
         "Completed batched conversion of table '{}': {} total rows in {} batches",
         table,
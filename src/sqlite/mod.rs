@@ -3,10 +3,15 @@
 
 pub mod converter;
 pub mod reader;
+pub mod writer;
 
 use anyhow::{bail, Context, Result};
 use std::path::PathBuf;
 
+/// Database name SQLite sources are filtered under, matching SQLite's own
+/// name for the primary database (e.g. `--include-tables main.users`)
+pub const SQLITE_DATABASE_NAME: &str = "main";
+
 /// Validate a SQLite file path to prevent path traversal attacks
 ///
 /// Security checks:
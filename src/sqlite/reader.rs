@@ -104,6 +104,49 @@ pub fn get_table_row_count(conn: &Connection, table: &str) -> Result<usize> {
     Ok(count as usize)
 }
 
+/// List user tables with row counts and on-disk sizes
+///
+/// Row counts are exact. Sizes come from the `dbstat` virtual table and
+/// include the table's indexes; they are reported as 0 when SQLite was built
+/// without `dbstat`. The returned `schema` is always
+/// [`SQLITE_DATABASE_NAME`](crate::sqlite::SQLITE_DATABASE_NAME).
+///
+/// # Examples
+///
+/// ```no_run
+/// # use database_replicator::sqlite::{open_sqlite, reader::list_table_stats};
+/// # fn example() -> anyhow::Result<()> {
+/// let conn = open_sqlite("database.db")?;
+/// for table in list_table_stats(&conn)? {
+///     println!("{}: {} rows", table.name, table.row_count_estimate);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn list_table_stats(conn: &Connection) -> Result<Vec<crate::migration::TableInfo>> {
+    let mut tables = Vec::new();
+    for name in list_tables(conn)? {
+        let row_count_estimate = get_table_row_count(conn, &name)? as i64;
+        let size_bytes = conn
+            .query_row(
+                "SELECT COALESCE(SUM(d.pgsize), 0) FROM dbstat d \
+                 JOIN sqlite_master m ON m.name = d.name \
+                 WHERE m.tbl_name = ?1",
+                [&name],
+                |row| row.get::<_, i64>(0),
+            )
+            .unwrap_or(0);
+        tables.push(crate::migration::TableInfo {
+            schema: crate::sqlite::SQLITE_DATABASE_NAME.to_string(),
+            name,
+            row_count_estimate,
+            size_bytes,
+            unlogged: false,
+        });
+    }
+    Ok(tables)
+}
+
 /// Batch reader state for iterating over large SQLite tables.
 ///
 /// Uses rowid-based pagination to efficiently read tables in chunks
@@ -413,6 +456,21 @@ mod tests {
         assert!(!tables.iter().any(|t| t.starts_with("sqlite_")));
     }
 
+    #[test]
+    fn test_list_table_stats() {
+        let (_temp_dir, db_path) = create_test_db();
+        let conn = Connection::open(db_path).unwrap();
+
+        let tables = list_table_stats(&conn).unwrap();
+
+        let names: Vec<&str> = tables.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, vec!["posts", "users"]);
+        assert!(tables.iter().all(|t| t.schema == "main"));
+        assert_eq!(tables[0].row_count_estimate, 1);
+        assert_eq!(tables[1].row_count_estimate, 3);
+        assert!(tables.iter().all(|t| t.size_bytes >= 0));
+    }
+
     #[test]
     fn test_get_table_row_count() {
         let (_temp_dir, db_path) = create_test_db();
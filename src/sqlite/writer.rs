@@ -0,0 +1,388 @@
+// ABOUTME: SQLite file target writer for PostgreSQL → SQLite edge replicas
+// ABOUTME: Maps PostgreSQL types to SQLite affinities and upserts rows with ON CONFLICT
+
+use anyhow::{bail, Context, Result};
+use rusqlite::types::Value;
+use rusqlite::Connection;
+use rust_decimal::Decimal;
+use std::path::{Path, PathBuf};
+use tokio_postgres::Row;
+
+use crate::utils::quote_ident;
+
+/// Parse a `sqlite:` target URL into a file path
+///
+/// Accepts `sqlite:/path/db.sqlite`, `sqlite://relative.db`, and
+/// `sqlite:///absolute/path.db`. Unlike [`super::validate_sqlite_path`], the
+/// file does not need to exist yet because it will be created.
+///
+/// # Examples
+///
+/// ```
+/// # use database_replicator::sqlite::writer::parse_sqlite_target;
+/// # use std::path::PathBuf;
+/// assert_eq!(
+///     parse_sqlite_target("sqlite:/var/lib/app/replica.sqlite").unwrap(),
+///     PathBuf::from("/var/lib/app/replica.sqlite")
+/// );
+/// assert_eq!(
+///     parse_sqlite_target("sqlite:///tmp/replica.db").unwrap(),
+///     PathBuf::from("/tmp/replica.db")
+/// );
+/// assert!(parse_sqlite_target("sqlite:/tmp/replica.txt").is_err());
+/// ```
+pub fn parse_sqlite_target(target: &str) -> Result<PathBuf> {
+    let rest = target
+        .strip_prefix("sqlite:")
+        .with_context(|| "SQLite targets must start with 'sqlite:'")?;
+    // sqlite://relative.db and sqlite:///abs.db follow the usual URL convention
+    let path = rest.strip_prefix("//").unwrap_or(rest);
+    if path.is_empty() {
+        bail!("SQLite target path cannot be empty");
+    }
+
+    let path_buf = PathBuf::from(path);
+    match path_buf.extension().and_then(|e| e.to_str()) {
+        Some("db" | "sqlite" | "sqlite3") => Ok(path_buf),
+        _ => bail!(
+            "Invalid SQLite target '{}'. File must end in .db, .sqlite, or .sqlite3",
+            path
+        ),
+    }
+}
+
+/// Map a PostgreSQL `udt_name` to a SQLite column type
+///
+/// Types without an exact SQLite equivalent are stored as TEXT so no precision
+/// is lost: numerics keep their decimal representation, timestamps use
+/// ISO-8601, and arrays are serialized as JSON.
+///
+/// # Examples
+///
+/// ```
+/// # use database_replicator::sqlite::writer::pg_type_to_sqlite;
+/// assert_eq!(pg_type_to_sqlite("int8"), "INTEGER");
+/// assert_eq!(pg_type_to_sqlite("float8"), "REAL");
+/// assert_eq!(pg_type_to_sqlite("numeric"), "TEXT");
+/// assert_eq!(pg_type_to_sqlite("bytea"), "BLOB");
+/// ```
+pub fn pg_type_to_sqlite(udt_name: &str) -> &'static str {
+    match udt_name {
+        "int2" | "int4" | "int8" | "bool" => "INTEGER",
+        "float4" | "float8" => "REAL",
+        "bytea" => "BLOB",
+        _ => "TEXT",
+    }
+}
+
+/// Build a CREATE TABLE IF NOT EXISTS statement for the SQLite target
+pub fn build_create_table_sql(
+    table: &str,
+    columns: &[(String, String)],
+    primary_key_columns: &[String],
+) -> Result<String> {
+    if columns.is_empty() {
+        bail!("Table '{}' has no columns", table);
+    }
+    if primary_key_columns.is_empty() {
+        bail!(
+            "Table '{}' has no primary key; SQLite targets require one for upserts",
+            table
+        );
+    }
+
+    let mut definitions: Vec<String> = columns
+        .iter()
+        .map(|(name, udt)| {
+            let null = if primary_key_columns.contains(name) {
+                " NOT NULL"
+            } else {
+                ""
+            };
+            format!("{} {}{}", quote_ident(name), pg_type_to_sqlite(udt), null)
+        })
+        .collect();
+
+    let pk: Vec<String> = primary_key_columns.iter().map(|c| quote_ident(c)).collect();
+    definitions.push(format!("PRIMARY KEY ({})", pk.join(", ")));
+
+    Ok(format!(
+        "CREATE TABLE IF NOT EXISTS {} ({})",
+        quote_ident(table),
+        definitions.join(", ")
+    ))
+}
+
+/// Build a single-row INSERT ... ON CONFLICT DO UPDATE statement
+///
+/// Generates a query like:
+/// ```sql
+/// INSERT INTO "users" ("id", "name") VALUES (?1, ?2)
+/// ON CONFLICT ("id") DO UPDATE SET "name" = excluded."name"
+/// ```
+pub fn build_upsert_query(
+    table: &str,
+    primary_key_columns: &[String],
+    all_columns: &[String],
+) -> String {
+    let quoted_columns: Vec<String> = all_columns.iter().map(|c| quote_ident(c)).collect();
+    let placeholders: Vec<String> = (1..=all_columns.len()).map(|i| format!("?{}", i)).collect();
+    let pk: Vec<String> = primary_key_columns.iter().map(|c| quote_ident(c)).collect();
+
+    let updates: Vec<String> = all_columns
+        .iter()
+        .filter(|c| !primary_key_columns.contains(c))
+        .map(|c| {
+            let quoted = quote_ident(c);
+            format!("{} = excluded.{}", quoted, quoted)
+        })
+        .collect();
+
+    let action = if updates.is_empty() {
+        "DO NOTHING".to_string()
+    } else {
+        format!("DO UPDATE SET {}", updates.join(", "))
+    };
+
+    format!(
+        "INSERT INTO {} ({}) VALUES ({}) ON CONFLICT ({}) {}",
+        quote_ident(table),
+        quoted_columns.join(", "),
+        placeholders.join(", "),
+        pk.join(", "),
+        action
+    )
+}
+
+/// Convert a PostgreSQL row into SQLite values
+///
+/// `column_types` holds (column_name, udt_name) pairs in row order, as returned
+/// by [`crate::xmin::get_table_columns`].
+pub fn pg_row_to_sqlite_values(row: &Row, column_types: &[(String, String)]) -> Vec<Value> {
+    column_types
+        .iter()
+        .enumerate()
+        .map(|(idx, (_name, udt))| pg_value_to_sqlite(row, idx, udt))
+        .collect()
+}
+
+fn pg_value_to_sqlite(row: &Row, idx: usize, udt: &str) -> Value {
+    fn or_null<T>(value: Option<T>, f: impl FnOnce(T) -> Value) -> Value {
+        value.map(f).unwrap_or(Value::Null)
+    }
+    fn json_array<T: serde::Serialize>(value: Option<Vec<T>>) -> Value {
+        or_null(value, |v| {
+            Value::Text(serde_json::to_string(&v).unwrap_or_else(|_| "[]".to_string()))
+        })
+    }
+
+    match udt {
+        "int2" => or_null(row.get::<_, Option<i16>>(idx), |v| Value::Integer(v as i64)),
+        "int4" => or_null(row.get::<_, Option<i32>>(idx), |v| Value::Integer(v as i64)),
+        "int8" => or_null(row.get::<_, Option<i64>>(idx), Value::Integer),
+        "float4" => or_null(row.get::<_, Option<f32>>(idx), |v| Value::Real(v as f64)),
+        "float8" => or_null(row.get::<_, Option<f64>>(idx), Value::Real),
+        "bool" => or_null(row.get::<_, Option<bool>>(idx), |v| {
+            Value::Integer(v as i64)
+        }),
+        "numeric" => or_null(row.get::<_, Option<Decimal>>(idx), |v| {
+            Value::Text(v.to_string())
+        }),
+        "uuid" => or_null(row.get::<_, Option<uuid::Uuid>>(idx), |v| {
+            Value::Text(v.to_string())
+        }),
+        "date" => or_null(row.get::<_, Option<chrono::NaiveDate>>(idx), |v| {
+            Value::Text(v.format("%Y-%m-%d").to_string())
+        }),
+        "timestamp" => or_null(row.get::<_, Option<chrono::NaiveDateTime>>(idx), |v| {
+            Value::Text(v.format("%Y-%m-%d %H:%M:%S%.f").to_string())
+        }),
+        "timestamptz" => or_null(
+            row.get::<_, Option<chrono::DateTime<chrono::Utc>>>(idx),
+            |v| Value::Text(v.to_rfc3339()),
+        ),
+        "json" | "jsonb" => or_null(row.get::<_, Option<serde_json::Value>>(idx), |v| {
+            Value::Text(v.to_string())
+        }),
+        "bytea" => or_null(row.get::<_, Option<Vec<u8>>>(idx), Value::Blob),
+        "_text" | "_varchar" | "_bpchar" | "_citext" => {
+            json_array(row.get::<_, Option<Vec<String>>>(idx))
+        }
+        "_int2" => json_array(row.get::<_, Option<Vec<i16>>>(idx)),
+        "_int4" => json_array(row.get::<_, Option<Vec<i32>>>(idx)),
+        "_int8" => json_array(row.get::<_, Option<Vec<i64>>>(idx)),
+        "_float4" => json_array(row.get::<_, Option<Vec<f32>>>(idx)),
+        "_float8" => json_array(row.get::<_, Option<Vec<f64>>>(idx)),
+        "_bool" => json_array(row.get::<_, Option<Vec<bool>>>(idx)),
+        "_uuid" => json_array(
+            row.get::<_, Option<Vec<uuid::Uuid>>>(idx)
+                .map(|v| v.iter().map(|u| u.to_string()).collect::<Vec<_>>()),
+        ),
+        "_jsonb" | "_json" => json_array(row.get::<_, Option<Vec<serde_json::Value>>>(idx)),
+        _ => or_null(
+            row.try_get::<_, Option<String>>(idx).ok().flatten(),
+            Value::Text,
+        ),
+    }
+}
+
+/// Applies replicated rows to a local SQLite file
+pub struct SqliteWriter {
+    conn: Connection,
+}
+
+impl SqliteWriter {
+    /// Open (or create) the SQLite target file for writing
+    ///
+    /// Missing parent directories are created. The database is switched to WAL
+    /// mode so readers of the replica are not blocked while a sync is running.
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent).with_context(|| {
+                format!(
+                    "Failed to create directory for SQLite target: {}",
+                    parent.display()
+                )
+            })?;
+        }
+
+        let conn = Connection::open(path)
+            .with_context(|| format!("Failed to open SQLite target: {}", path.display()))?;
+        conn.pragma_update(None, "journal_mode", "WAL")
+            .context("Failed to enable WAL mode on SQLite target")?;
+
+        tracing::info!("Opened SQLite target: {}", path.display());
+        Ok(Self { conn })
+    }
+
+    /// Create the target table if it does not exist
+    pub fn ensure_table(
+        &self,
+        table: &str,
+        columns: &[(String, String)],
+        primary_key_columns: &[String],
+    ) -> Result<()> {
+        let sql = build_create_table_sql(table, columns, primary_key_columns)?;
+        self.conn
+            .execute(&sql, [])
+            .with_context(|| format!("Failed to create SQLite table '{}'", table))?;
+        Ok(())
+    }
+
+    /// Apply a batch of rows in a single transaction
+    ///
+    /// Returns the number of rows written.
+    pub fn apply_batch(
+        &mut self,
+        table: &str,
+        primary_key_columns: &[String],
+        all_columns: &[String],
+        rows: Vec<Vec<Value>>,
+    ) -> Result<u64> {
+        let query = build_upsert_query(table, primary_key_columns, all_columns);
+        let tx = self
+            .conn
+            .transaction()
+            .context("Failed to begin SQLite transaction")?;
+        {
+            let mut stmt = tx.prepare_cached(&query).with_context(|| {
+                format!("Failed to prepare upsert for SQLite table '{}'", table)
+            })?;
+            for row in &rows {
+                stmt.execute(rusqlite::params_from_iter(row.iter()))
+                    .with_context(|| {
+                        format!("Failed to upsert row into SQLite table '{}'", table)
+                    })?;
+            }
+        }
+        tx.commit()
+            .with_context(|| format!("Failed to commit batch to SQLite table '{}'", table))?;
+        Ok(rows.len() as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sqlite_target() {
+        assert_eq!(
+            parse_sqlite_target("sqlite:replica.db").unwrap(),
+            PathBuf::from("replica.db")
+        );
+        assert_eq!(
+            parse_sqlite_target("sqlite://data/replica.sqlite3").unwrap(),
+            PathBuf::from("data/replica.sqlite3")
+        );
+        assert!(parse_sqlite_target("sqlite:").is_err());
+        assert!(parse_sqlite_target("sqlite:/tmp/replica").is_err());
+        assert!(parse_sqlite_target("/tmp/replica.db").is_err());
+    }
+
+    #[test]
+    fn test_build_create_table_sql() {
+        let columns = vec![
+            ("id".to_string(), "int8".to_string()),
+            ("price".to_string(), "numeric".to_string()),
+        ];
+        let sql = build_create_table_sql("items", &columns, &["id".to_string()]).unwrap();
+        assert_eq!(
+            sql,
+            "CREATE TABLE IF NOT EXISTS \"items\" (\"id\" INTEGER NOT NULL, \"price\" TEXT, PRIMARY KEY (\"id\"))"
+        );
+        assert!(build_create_table_sql("items", &columns, &[]).is_err());
+    }
+
+    #[test]
+    fn test_build_upsert_query() {
+        let query = build_upsert_query(
+            "users",
+            &["id".to_string()],
+            &["id".to_string(), "name".to_string()],
+        );
+        assert_eq!(
+            query,
+            "INSERT INTO \"users\" (\"id\", \"name\") VALUES (?1, ?2) \
+             ON CONFLICT (\"id\") DO UPDATE SET \"name\" = excluded.\"name\""
+        );
+
+        let query = build_upsert_query("tags", &["a".to_string()], &["a".to_string()]);
+        assert!(query.ends_with("ON CONFLICT (\"a\") DO NOTHING"));
+    }
+
+    #[test]
+    fn test_apply_batch_upserts_rows() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join("replica.db");
+        let mut writer = SqliteWriter::open(&path).unwrap();
+
+        let columns = vec![
+            ("id".to_string(), "int4".to_string()),
+            ("name".to_string(), "text".to_string()),
+        ];
+        let pk = vec!["id".to_string()];
+        let names = vec!["id".to_string(), "name".to_string()];
+        writer.ensure_table("users", &columns, &pk).unwrap();
+
+        let rows = vec![
+            vec![Value::Integer(1), Value::Text("alice".to_string())],
+            vec![Value::Integer(2), Value::Text("bob".to_string())],
+        ];
+        assert_eq!(writer.apply_batch("users", &pk, &names, rows).unwrap(), 2);
+
+        let update = vec![vec![Value::Integer(2), Value::Text("robert".to_string())]];
+        writer.apply_batch("users", &pk, &names, update).unwrap();
+
+        let (count, name): (i64, String) = writer
+            .conn
+            .query_row(
+                "SELECT COUNT(*), (SELECT name FROM users WHERE id = 2) FROM users",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(count, 2);
+        assert_eq!(name, "robert");
+    }
+}
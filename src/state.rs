@@ -6,31 +6,47 @@ use std::path::PathBuf;
 #[derive(Serialize, Deserialize, Default)]
 pub struct AppState {
     pub target_url: Option<String>,
+    /// Replication slots this tool created on source databases
+    #[serde(default)]
+    pub replication_slots: Vec<TrackedSlot>,
 }
 
-fn get_state_path() -> Result<PathBuf> {
+/// A replication slot created by `init` or `sync`, kept so it can be cleaned up
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct TrackedSlot {
+    pub name: String,
+    /// Source database the slot lives in
+    pub source_url: String,
+    /// Target database whose subscription streams from the slot
+    pub target_url: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Directory for the CLI's state files (`~/.database-replicator`), created if missing
+pub fn state_dir() -> Result<PathBuf> {
     let home_dir =
         dirs::home_dir().ok_or_else(|| anyhow::anyhow!("Could not find home directory"))?;
     let state_dir = home_dir.join(".database-replicator");
     if !state_dir.exists() {
         fs::create_dir_all(&state_dir)?;
     }
-    Ok(state_dir.join("state.json"))
+    Ok(state_dir)
+}
+
+fn get_state_path() -> Result<PathBuf> {
+    Ok(state_dir()?.join("state.json"))
 }
 
 pub fn load() -> Result<AppState> {
     let state_path = get_state_path()?;
-    if !state_path.exists() {
-        return Ok(AppState::default());
+    match crate::encryption::read_state_file(&state_path)? {
+        Some(contents) => Ok(serde_json::from_str(&contents)?),
+        None => Ok(AppState::default()),
     }
-    let state_file = fs::File::open(state_path)?;
-    let state = serde_json::from_reader(state_file)?;
-    Ok(state)
 }
 
 pub fn save(state: &AppState) -> Result<()> {
     let state_path = get_state_path()?;
-    let state_file = fs::File::create(state_path)?;
-    serde_json::to_writer_pretty(state_file, state)?;
-    Ok(())
+    let contents = serde_json::to_string_pretty(state)?;
+    crate::encryption::write_state_file(&state_path, &contents)
 }
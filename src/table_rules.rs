@@ -1,11 +1,14 @@
 // ABOUTME: Table-level replication rules for schema-only and filtered copies
 // ABOUTME: Supports CLI/config inputs and deterministic fingerprints
 
+use crate::jsonb::promote::PromotedKey;
+use crate::remote::models::{MaskingSpec, TableFilterSpec, TableRulesSpec, TimeFilterSpec};
+use crate::transform::{ColumnTransform, TableTransforms};
 use crate::utils;
 use crate::utils::quote_ident;
 use anyhow::{anyhow, bail, Context, Result};
 use sha2::{Digest, Sha256};
-use std::collections::{BTreeMap, BTreeSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 
 /// Represents a fully-qualified table identifier with optional database and schema
 /// Supports parsing from: `database.schema.table`, `schema.table`, or `table`
@@ -156,26 +159,356 @@ pub struct TimeFilterRule {
 }
 
 impl TimeFilterRule {
-    fn predicate(&self) -> String {
+    /// Condition that keeps the rows inside the window
+    pub fn predicate(&self) -> String {
         format!(
             "{} >= NOW() - INTERVAL '{}'",
             quote_ident(&self.column),
             self.interval
         )
     }
+
+    /// Condition that matches rows older than the window, on a table whose
+    /// time column is named `column` (the target may rename it)
+    pub fn aged_out_predicate(&self, column: &str) -> String {
+        format!(
+            "{} < NOW() - INTERVAL '{}'",
+            quote_ident(column),
+            self.interval
+        )
+    }
+}
+
+/// How much of a table a sample rule copies
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SampleSize {
+    /// Hundredths of a percent of the table's rows (1000 = 10%)
+    Percent(u32),
+    /// A fixed number of rows
+    Rows(u64),
+}
+
+/// Deterministic row sample for non-production targets
+///
+/// Rows are picked by a seeded hash of the primary key (or the whole row when the
+/// table has no primary key), so the same seed selects the same rows every run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SampleRule {
+    pub size: SampleSize,
+    pub seed: i64,
+}
+
+impl SampleRule {
+    /// Parse a percentage such as `10%`, `0.5%`, or `25`
+    pub fn percent(spec: &str, seed: i64) -> Result<Self> {
+        let trimmed = spec.trim();
+        let number = trimmed.strip_suffix('%').unwrap_or(trimmed).trim();
+        let percent: f64 = number.parse().with_context(|| {
+            format!("Invalid sample '{}': expected a percentage like 10%", spec)
+        })?;
+        if !(percent > 0.0 && percent <= 100.0) {
+            bail!(
+                "Sample percentage must be greater than 0 and at most 100, got '{}'",
+                spec
+            );
+        }
+        let hundredths = (percent * 100.0).round() as u32;
+        if hundredths == 0 {
+            bail!("Sample percentage '{}' is below the 0.01% resolution", spec);
+        }
+        Ok(SampleRule {
+            size: SampleSize::Percent(hundredths),
+            seed,
+        })
+    }
+
+    /// Sample a fixed number of rows
+    pub fn rows(count: u64, seed: i64) -> Result<Self> {
+        if count == 0 {
+            bail!("Sample row count must be positive");
+        }
+        Ok(SampleRule {
+            size: SampleSize::Rows(count),
+            seed,
+        })
+    }
+
+    /// Build a WHERE predicate that keeps only the sampled rows
+    ///
+    /// `table` is the schema-qualified source table and `key` is the text
+    /// expression hashed for each row (primary key columns, or the whole row).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use database_replicator::table_rules::SampleRule;
+    /// let rule = SampleRule::percent("10%", 0).unwrap();
+    /// assert_eq!(
+    ///     rule.predicate("\"public\".\"events\"", "\"id\"::text"),
+    ///     "abs(hashtextextended(\"id\"::text, 0) % 10000) < 1000"
+    /// );
+    /// ```
+    pub fn predicate(&self, table: &str, key: &str) -> String {
+        let hash = format!("hashtextextended({}, {})", key, self.seed);
+        match self.size {
+            SampleSize::Percent(hundredths) => {
+                format!("abs({} % 10000) < {}", hash, hundredths)
+            }
+            // Keep the N rows with the lowest hashes
+            SampleSize::Rows(count) => format!(
+                "{hash} <= (SELECT max(sample_hash) FROM (SELECT {hash} AS sample_hash FROM {table} ORDER BY 1 LIMIT {count}) AS sample_keys)"
+            ),
+        }
+    }
+}
+
+impl std::fmt::Display for SampleRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.size {
+            SampleSize::Percent(hundredths) => {
+                write!(f, "{}%", hundredths as f64 / 100.0)?;
+            }
+            SampleSize::Rows(count) => write!(f, "{} rows", count)?,
+        }
+        if self.seed != 0 {
+            write!(f, " (seed {})", self.seed)?;
+        }
+        Ok(())
+    }
+}
+
+/// Where a table's rows are read from instead of `SELECT * FROM table`
+///
+/// Rows must have the target table's column names; each column is cast to the
+/// target's type, and extra columns are ignored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TableSource {
+    /// A SELECT list evaluated over each of the table's rows,
+    /// e.g. `id, lower(email) AS email, price * 100 AS price_cents`
+    Select(String),
+    /// A source-side view, named `"schema"."view"`, whose rows replace the table's
+    View(String),
+}
+
+impl TableSource {
+    pub fn select(list: &str) -> Result<Self> {
+        let list = list.trim();
+        if list.is_empty() {
+            bail!("Table source select list cannot be empty");
+        }
+        Ok(TableSource::Select(list.to_string()))
+    }
+
+    /// A view named `view` or `schema.view`
+    pub fn view(name: &str) -> Result<Self> {
+        let qualified = QualifiedTable::parse(name)?;
+        if qualified.database.is_some() {
+            bail!(
+                "Table source view '{}' must be 'view' or 'schema.view'",
+                name
+            );
+        }
+        Ok(TableSource::View(qualified.schema_qualified()))
+    }
+
+    /// A FROM item reading `table` (`"schema"."table"`) through this source
+    ///
+    /// The relation is aliased to the unquoted table name `alias`, so
+    /// predicates written against the table still apply.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use database_replicator::table_rules::TableSource;
+    /// let source = TableSource::select("id, lower(email) AS email").unwrap();
+    /// assert_eq!(
+    ///     source.relation("\"public\".\"users\"", "users"),
+    ///     "(SELECT id, lower(email) AS email FROM \"public\".\"users\") AS \"users\""
+    /// );
+    /// ```
+    pub fn relation(&self, table: &str, alias: &str) -> String {
+        match self {
+            TableSource::Select(list) => {
+                format!("(SELECT {} FROM {}) AS {}", list, table, quote_ident(alias))
+            }
+            TableSource::View(view) => format!("{} AS {}", view, quote_ident(alias)),
+        }
+    }
+}
+
+impl std::fmt::Display for TableSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TableSource::Select(list) => write!(f, "SELECT {}", list),
+            TableSource::View(view) => write!(f, "view {}", view),
+        }
+    }
+}
+
+/// How schema and table names are cased on the target
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IdentifierCase {
+    /// Names are kept as they are on the source
+    #[default]
+    Preserve,
+    Lower,
+    Upper,
+}
+
+impl IdentifierCase {
+    pub fn parse(value: &str) -> Result<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "preserve" => Ok(IdentifierCase::Preserve),
+            "lower" => Ok(IdentifierCase::Lower),
+            "upper" => Ok(IdentifierCase::Upper),
+            _ => bail!(
+                "Invalid identifier case '{}': expected 'preserve', 'lower', or 'upper'",
+                value
+            ),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IdentifierCase::Preserve => "preserve",
+            IdentifierCase::Lower => "lower",
+            IdentifierCase::Upper => "upper",
+        }
+    }
+
+    pub fn apply(&self, name: &str) -> String {
+        match self {
+            IdentifierCase::Preserve => name.to_string(),
+            IdentifierCase::Lower => name.to_lowercase(),
+            IdentifierCase::Upper => name.to_uppercase(),
+        }
+    }
+}
+
+/// Target name, type, or nullability of one column
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ColumnOverride {
+    /// Column name on the target
+    pub rename_to: Option<String>,
+    /// SQL type on the target, e.g. `text` or `bigint`
+    pub target_type: Option<String>,
+    /// Whether the target column accepts NULL
+    pub nullable: Option<bool>,
+}
+
+impl std::fmt::Display for ColumnOverride {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(name) = &self.rename_to {
+            parts.push(format!("renamed to {}", name));
+        }
+        if let Some(target_type) = &self.target_type {
+            parts.push(format!("type {}", target_type));
+        }
+        match self.nullable {
+            Some(true) => parts.push("nullable".to_string()),
+            Some(false) => parts.push("not null".to_string()),
+            None => {}
+        }
+        write!(f, "{}", parts.join(", "))
+    }
+}
+
+/// Overrides for the columns of one table, keyed by source column name
+pub type ColumnOverrides = BTreeMap<String, ColumnOverride>;
+
+/// Where the tables of one database are written on the target
+///
+/// A table named by a rename goes exactly where the rename says; any other
+/// table keeps its name, cased by the database's identifier case. Columns keep
+/// their names unless a column override renames them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TargetNames {
+    /// Target `(schema, table)` keyed by the source table's `"schema"."table"`
+    renames: BTreeMap<String, (String, String)>,
+    case: IdentifierCase,
+    /// Target column names keyed like `renames`, then by source column name
+    columns: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+impl TargetNames {
+    /// Target `(schema, table)` of a source table
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use database_replicator::table_rules::{QualifiedTable, TableRules};
+    /// let mut rules = TableRules::default();
+    /// rules
+    ///     .add_rename(
+    ///         QualifiedTable::parse("app.public.events").unwrap(),
+    ///         QualifiedTable::parse("analytics.events_v2").unwrap(),
+    ///     )
+    ///     .unwrap();
+    /// let names = rules.target_names("app");
+    /// assert_eq!(
+    ///     names.resolve("public", "events"),
+    ///     ("analytics".to_string(), "events_v2".to_string())
+    /// );
+    /// assert_eq!(
+    ///     names.resolve("public", "users"),
+    ///     ("public".to_string(), "users".to_string())
+    /// );
+    /// ```
+    pub fn resolve(&self, schema: &str, table: &str) -> (String, String) {
+        match self
+            .renames
+            .get(&format!("{}.{}", quote_ident(schema), quote_ident(table)))
+        {
+            Some(target) => target.clone(),
+            None => (self.case.apply(schema), self.case.apply(table)),
+        }
+    }
+
+    /// Target name of a source table's column
+    pub fn resolve_column(&self, schema: &str, table: &str, column: &str) -> String {
+        self.columns
+            .get(&format!("{}.{}", quote_ident(schema), quote_ident(table)))
+            .and_then(|columns| columns.get(column))
+            .cloned()
+            .unwrap_or_else(|| column.to_string())
+    }
+
+    /// Whether some of a table's columns are renamed on the target
+    pub fn renames_columns(&self, schema: &str, table: &str) -> bool {
+        self.columns
+            .contains_key(&format!("{}.{}", quote_ident(schema), quote_ident(table)))
+    }
+
+    /// Whether every table and column keeps its source name
+    pub fn is_identity(&self) -> bool {
+        self.renames.is_empty() && self.case == IdentifierCase::Preserve && self.columns.is_empty()
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum TableRuleKind {
     SchemaOnly,
     Predicate(String),
+    Sample(SampleRule),
 }
 
 #[derive(Debug, Clone, Default)]
 pub struct TableRules {
     schema_only: ScopedTableSet,
+    /// Tables `init` copies before the rest, and hands to sync first
+    priority: ScopedTableSet,
     table_filters: ScopedTableMap<String>,
     time_filters: ScopedTableMap<TimeFilterRule>,
+    /// How long the target keeps a partitioned table's partitions, as an interval
+    retention: ScopedTableMap<String>,
+    samples: ScopedTableMap<SampleRule>,
+    transforms: ScopedTableMap<TableTransforms>,
+    sources: ScopedTableMap<TableSource>,
+    renames: ScopedTableMap<QualifiedTable>,
+    identifier_cases: BTreeMap<ScopeKey, IdentifierCase>,
+    column_overrides: ScopedTableMap<ColumnOverrides>,
+    promotions: ScopedTableMap<Vec<PromotedKey>>,
 }
 
 type ScopedTableSet = BTreeMap<ScopeKey, BTreeSet<SchemaTableKey>>;
@@ -208,6 +541,14 @@ impl TableRules {
         Ok(())
     }
 
+    pub fn add_priority_table(&mut self, qualified: QualifiedTable) -> Result<()> {
+        let scope = ScopeKey::from_option(qualified.database.clone());
+        let key = SchemaTableKey::from_qualified(&qualified);
+        ensure_schema_only_free(&self.schema_only, &qualified, "priority")?;
+        self.priority.entry(scope).or_default().insert(key);
+        Ok(())
+    }
+
     pub fn add_table_filter(&mut self, qualified: QualifiedTable, predicate: String) -> Result<()> {
         if predicate.trim().is_empty() {
             bail!(
@@ -254,6 +595,62 @@ impl TableRules {
         Ok(())
     }
 
+    /// Drop target partitions of a partitioned table once their range is
+    /// older than `window`, however long the source keeps them
+    pub fn add_retention(&mut self, qualified: QualifiedTable, window: String) -> Result<()> {
+        let interval = normalize_time_window(&window)?;
+        let scope = ScopeKey::from_option(qualified.database.clone());
+        let key = SchemaTableKey::from_qualified(&qualified);
+        ensure_schema_only_free(&self.schema_only, &qualified, "retention")?;
+        self.retention
+            .entry(scope)
+            .or_default()
+            .insert(key, interval);
+        Ok(())
+    }
+
+    pub fn add_sample(&mut self, qualified: QualifiedTable, rule: SampleRule) -> Result<()> {
+        let scope = ScopeKey::from_option(qualified.database.clone());
+        let key = SchemaTableKey::from_qualified(&qualified);
+        ensure_schema_only_free(&self.schema_only, &qualified, "sample")?;
+        let filtered = self
+            .table_filters
+            .get(&scope)
+            .is_some_and(|inner| inner.contains_key(&key))
+            || self
+                .time_filters
+                .get(&scope)
+                .is_some_and(|inner| inner.contains_key(&key));
+        if filtered {
+            bail!(
+                "Cannot sample table '{}' because a table or time filter already exists",
+                qualified.schema_qualified()
+            );
+        }
+        self.samples.entry(scope).or_default().insert(key, rule);
+        Ok(())
+    }
+
+    /// Rewrite a column's values on their way to the target (see [`crate::transform`])
+    pub fn add_transform(
+        &mut self,
+        qualified: QualifiedTable,
+        column: String,
+        transform: ColumnTransform,
+    ) -> Result<()> {
+        utils::validate_postgres_identifier(&column)?;
+        let scope = ScopeKey::from_option(qualified.database.clone());
+        let key = SchemaTableKey::from_qualified(&qualified);
+        ensure_schema_only_free(&self.schema_only, &qualified, "transform")?;
+        self.transforms
+            .entry(scope)
+            .or_default()
+            .entry(key)
+            .or_default()
+            .insert(column, transform)
+            .with_context(|| format!("Invalid transforms for '{}'", qualified.schema_qualified()))
+    }
+
     pub fn apply_schema_only_cli(&mut self, specs: &[String]) -> Result<()> {
         for spec in specs {
             let qualified = QualifiedTable::parse(spec)?;
@@ -262,6 +659,14 @@ impl TableRules {
         Ok(())
     }
 
+    pub fn apply_priority_cli(&mut self, specs: &[String]) -> Result<()> {
+        for spec in specs {
+            let qualified = QualifiedTable::parse(spec)?;
+            self.add_priority_table(qualified)?;
+        }
+        Ok(())
+    }
+
     pub fn apply_table_filter_cli(&mut self, specs: &[String]) -> Result<()> {
         for spec in specs {
             let (table_part, predicate) = spec
@@ -300,10 +705,29 @@ impl TableRules {
         Ok(())
     }
 
+    pub fn apply_retention_cli(&mut self, specs: &[String]) -> Result<()> {
+        for spec in specs {
+            let (table_part, window) = spec
+                .split_once(':')
+                .with_context(|| format!("Retention '{}' must be table:window", spec))?;
+            if window.trim().is_empty() {
+                bail!("Retention '{}' must include a non-empty window", spec);
+            }
+            let qualified = QualifiedTable::parse(table_part)?;
+            self.add_retention(qualified, window.trim().to_string())?;
+        }
+        Ok(())
+    }
+
     pub fn schema_only_tables(&self, database: &str) -> Vec<String> {
         collect_tables(&self.schema_only, database)
     }
 
+    /// High-priority tables of a database, as `"schema"."table"`
+    pub fn priority_tables(&self, database: &str) -> Vec<String> {
+        collect_tables(&self.priority, database)
+    }
+
     pub fn table_filter(&self, database: &str, schema: &str, table: &str) -> Option<&String> {
         lookup_scoped(&self.table_filters, database, schema, table)
     }
@@ -317,6 +741,296 @@ impl TableRules {
         lookup_scoped(&self.time_filters, database, schema, table)
     }
 
+    /// Retention intervals of a database's partitioned tables, as unquoted
+    /// (schema, table, interval); database-specific rules win over global ones
+    pub fn retention_tables(&self, database: &str) -> Vec<(String, String, String)> {
+        let mut tables = BTreeMap::new();
+        for scope in [ScopeKey::Global, ScopeKey::database(database)] {
+            if let Some(rules) = self.retention.get(&scope) {
+                for (key, interval) in rules {
+                    tables.insert((key.schema.clone(), key.table.clone()), interval.clone());
+                }
+            }
+        }
+        tables
+            .into_iter()
+            .map(|((schema, table), interval)| (schema, table, interval))
+            .collect()
+    }
+
+    /// Time filters of a database's tables, keyed by `"schema"."table"`
+    pub fn time_filter_tables(&self, database: &str) -> BTreeMap<String, TimeFilterRule> {
+        let schema_only: BTreeSet<String> = self.schema_only_tables(database).into_iter().collect();
+        scoped_map_values(&self.time_filters, database)
+            .into_iter()
+            .filter(|(table, _)| !schema_only.contains(table))
+            .collect()
+    }
+
+    pub fn sample(&self, database: &str, schema: &str, table: &str) -> Option<&SampleRule> {
+        lookup_scoped(&self.samples, database, schema, table)
+    }
+
+    /// Sampled tables for a database as `("schema"."table", rule)` pairs
+    pub fn sample_tables(&self, database: &str) -> Vec<(String, SampleRule)> {
+        let schema_only: BTreeSet<String> = self.schema_only_tables(database).into_iter().collect();
+        scoped_map_values(&self.samples, database)
+            .into_iter()
+            .filter(|(table, _)| !schema_only.contains(table))
+            .collect()
+    }
+
+    pub fn transforms(
+        &self,
+        database: &str,
+        schema: &str,
+        table: &str,
+    ) -> Option<&TableTransforms> {
+        lookup_scoped(&self.transforms, database, schema, table)
+    }
+
+    /// Transformed tables for a database, keyed by `"schema"."table"`
+    pub fn transformed_tables(&self, database: &str) -> BTreeMap<String, TableTransforms> {
+        let schema_only: BTreeSet<String> = self.schema_only_tables(database).into_iter().collect();
+        scoped_map_values(&self.transforms, database)
+            .into_iter()
+            .filter(|(table, _)| !schema_only.contains(table))
+            .collect()
+    }
+
+    /// Read a table's rows through a custom SELECT list or view
+    pub fn add_source(&mut self, qualified: QualifiedTable, source: TableSource) -> Result<()> {
+        let scope = ScopeKey::from_option(qualified.database.clone());
+        let key = SchemaTableKey::from_qualified(&qualified);
+        ensure_schema_only_free(&self.schema_only, &qualified, "table source")?;
+        self.sources.entry(scope).or_default().insert(key, source);
+        Ok(())
+    }
+
+    pub fn source(&self, database: &str, schema: &str, table: &str) -> Option<&TableSource> {
+        lookup_scoped(&self.sources, database, schema, table)
+    }
+
+    /// Tables read through a custom source in a database, keyed by `"schema"."table"`
+    pub fn source_tables(&self, database: &str) -> BTreeMap<String, TableSource> {
+        let schema_only: BTreeSet<String> = self.schema_only_tables(database).into_iter().collect();
+        scoped_map_values(&self.sources, database)
+            .into_iter()
+            .filter(|(table, _)| !schema_only.contains(table))
+            .collect()
+    }
+
+    /// Whether rows are rewritten on their way to the target, by column
+    /// transforms or custom table sources
+    pub fn rewrites_rows(&self) -> bool {
+        !self.transforms.is_empty() || !self.sources.is_empty()
+    }
+
+    /// Write a table to another schema or under another name on the target
+    ///
+    /// `target` may only name the database the table is replicated into.
+    pub fn add_rename(&mut self, qualified: QualifiedTable, target: QualifiedTable) -> Result<()> {
+        if let Some(database) = &target.database {
+            if qualified.database.as_ref() != Some(database) {
+                bail!(
+                    "Cannot rename table '{}' to '{}': tables stay in the database they are replicated into",
+                    qualified.schema_qualified(),
+                    target.fully_qualified()
+                );
+            }
+        }
+        let scope = ScopeKey::from_option(qualified.database.clone());
+        let key = SchemaTableKey::from_qualified(&qualified);
+        let target = QualifiedTable::new(None, target.schema, target.table);
+        self.renames.entry(scope).or_default().insert(key, target);
+        Ok(())
+    }
+
+    /// Case target schema and table names of one database, or of every
+    /// database when `database` is None
+    pub fn set_identifier_case(&mut self, database: Option<String>, case: IdentifierCase) {
+        self.identifier_cases
+            .insert(ScopeKey::from_option(database), case);
+    }
+
+    /// Where a database's tables are written on the target
+    pub fn target_names(&self, database: &str) -> TargetNames {
+        let case = self
+            .identifier_cases
+            .get(&ScopeKey::database(database))
+            .or_else(|| self.identifier_cases.get(&ScopeKey::Global))
+            .copied()
+            .unwrap_or_default();
+        let renames = scoped_map_values(&self.renames, database)
+            .into_iter()
+            .map(|(table, target)| (table, (target.schema, target.table)))
+            .collect();
+        let columns = self
+            .column_override_tables(database)
+            .into_iter()
+            .map(|(table, overrides)| {
+                let renamed: BTreeMap<String, String> = overrides
+                    .into_iter()
+                    .filter_map(|(column, o)| o.rename_to.map(|name| (column, name)))
+                    .collect();
+                (table, renamed)
+            })
+            .filter(|(_, renamed)| !renamed.is_empty())
+            .collect();
+        TargetNames {
+            renames,
+            case,
+            columns,
+        }
+    }
+
+    /// Override a column's target name, type, or nullability
+    pub fn add_column_override(
+        &mut self,
+        qualified: QualifiedTable,
+        column: String,
+        column_override: ColumnOverride,
+    ) -> Result<()> {
+        let column = non_empty(&column, "column")?;
+        if column_override == ColumnOverride::default() {
+            bail!(
+                "Column override for {}.{} must set 'rename_to', 'type', or 'nullable'",
+                qualified.schema_qualified(),
+                column
+            );
+        }
+        if let Some(name) = &column_override.rename_to {
+            utils::validate_postgres_identifier(name)?;
+        }
+        if let Some(target_type) = &column_override.target_type {
+            non_empty(target_type, "type")?;
+        }
+        let scope = ScopeKey::from_option(qualified.database.clone());
+        let key = SchemaTableKey::from_qualified(&qualified);
+        let overrides = self
+            .column_overrides
+            .entry(scope)
+            .or_default()
+            .entry(key)
+            .or_default();
+        if overrides.contains_key(&column) {
+            bail!(
+                "Column {}.{} has more than one override",
+                qualified.schema_qualified(),
+                column
+            );
+        }
+        overrides.insert(column, column_override);
+        Ok(())
+    }
+
+    pub fn column_overrides(
+        &self,
+        database: &str,
+        schema: &str,
+        table: &str,
+    ) -> Option<&ColumnOverrides> {
+        lookup_scoped(&self.column_overrides, database, schema, table)
+    }
+
+    /// Tables with column overrides in a database, keyed by `"schema"."table"`
+    pub fn column_override_tables(&self, database: &str) -> BTreeMap<String, ColumnOverrides> {
+        scoped_map_values(&self.column_overrides, database)
+    }
+
+    /// Keep a JSON key of a JSONB target table in a typed, indexed column
+    pub fn add_promotion(
+        &mut self,
+        qualified: QualifiedTable,
+        promoted: PromotedKey,
+    ) -> Result<()> {
+        let scope = ScopeKey::from_option(qualified.database.clone());
+        let key = SchemaTableKey::from_qualified(&qualified);
+        let keys = self
+            .promotions
+            .entry(scope)
+            .or_default()
+            .entry(key)
+            .or_default();
+        if keys
+            .iter()
+            .any(|existing| existing.column == promoted.column)
+        {
+            bail!(
+                "Promoted column '{}' of {} is defined more than once",
+                promoted.column,
+                qualified.schema_qualified()
+            );
+        }
+        keys.push(promoted);
+        Ok(())
+    }
+
+    /// Keys promoted to columns in a JSONB target table
+    pub fn promotions(&self, database: &str, schema: &str, table: &str) -> &[PromotedKey] {
+        lookup_scoped(&self.promotions, database, schema, table)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Whether some table or column is written under another name than on the source
+    pub fn renames_tables(&self) -> bool {
+        !self.renames.is_empty()
+            || self
+                .identifier_cases
+                .values()
+                .any(|case| *case != IdentifierCase::Preserve)
+            || self
+                .column_overrides
+                .values()
+                .flat_map(|tables| tables.values())
+                .flat_map(|columns| columns.values())
+                .any(|o| o.rename_to.is_some())
+    }
+
+    /// Whether some table only keeps rows within a sliding time window
+    pub fn has_time_filters(&self) -> bool {
+        self.time_filters.values().any(|tables| !tables.is_empty())
+    }
+
+    /// Whether logical replication cannot keep the target current, since
+    /// it streams rows untouched into same-named tables, its row filters
+    /// cannot use `NOW()` for a time window, and it would fail on rows for
+    /// partitions retention dropped
+    pub fn requires_xmin_sync(&self) -> bool {
+        self.rewrites_rows()
+            || self.renames_tables()
+            || self.has_time_filters()
+            || self.retention.values().any(|tables| !tables.is_empty())
+    }
+
+    /// Whether the target holds other rows, or other names, than a plain
+    /// copy of the source tables would
+    pub fn alters_copied_rows(&self) -> bool {
+        self.requires_xmin_sync()
+            || self.table_filters.values().any(|tables| !tables.is_empty())
+            || self.samples.values().any(|tables| !tables.is_empty())
+    }
+
+    /// Unquoted names of the sampled tables in one schema of a database
+    pub fn sampled_table_names(&self, database: &str, schema: &str) -> Vec<String> {
+        let mut names = BTreeSet::new();
+        for scope in [ScopeKey::Global, ScopeKey::database(database)] {
+            if let Some(tables) = self.samples.get(&scope) {
+                names.extend(
+                    tables
+                        .keys()
+                        .filter(|key| key.schema == schema)
+                        .filter(|key| {
+                            !has_schema_only_rule(&self.schema_only, database, schema, &key.table)
+                        })
+                        .map(|key| key.table.clone()),
+                );
+            }
+        }
+        names.into_iter().collect()
+    }
+
     pub fn predicate_tables(&self, database: &str) -> Vec<(String, String)> {
         let schema_only: BTreeSet<String> = self.schema_only_tables(database).into_iter().collect();
         let mut combined = BTreeMap::new();
@@ -353,13 +1067,25 @@ impl TableRules {
         if let Some(rule) = self.time_filter(database, schema, table) {
             return Some(TableRuleKind::Predicate(rule.predicate()));
         }
+        if let Some(rule) = self.sample(database, schema, table) {
+            return Some(TableRuleKind::Sample(rule.clone()));
+        }
         None
     }
 
     pub fn merge(&mut self, other: TableRules) {
         merge_sets(&mut self.schema_only, other.schema_only);
+        merge_sets(&mut self.priority, other.priority);
         merge_maps(&mut self.table_filters, other.table_filters);
         merge_maps(&mut self.time_filters, other.time_filters);
+        merge_maps(&mut self.retention, other.retention);
+        merge_maps(&mut self.samples, other.samples);
+        merge_maps(&mut self.transforms, other.transforms);
+        merge_maps(&mut self.sources, other.sources);
+        merge_maps(&mut self.renames, other.renames);
+        self.identifier_cases.extend(other.identifier_cases);
+        merge_maps(&mut self.column_overrides, other.column_overrides);
+        merge_maps(&mut self.promotions, other.promotions);
     }
 
     pub fn fingerprint(&self) -> String {
@@ -369,11 +1095,355 @@ impl TableRules {
         hash_scoped_map(&mut hasher, &self.time_filters, |value| {
             format!("{}|{}", value.column, value.interval)
         });
+        hash_scoped_map(&mut hasher, &self.samples, |value| {
+            format!("{:?}|{}", value.size, value.seed)
+        });
+        hash_scoped_map(&mut hasher, &self.transforms, |value| value.to_string());
+        hash_scoped_map(&mut hasher, &self.sources, |value| value.to_string());
+        hash_scoped_map(&mut hasher, &self.renames, |value| value.schema_qualified());
+        for (scope, case) in &self.identifier_cases {
+            hash_scope_label(&mut hasher, scope);
+            hasher.update(case.as_str().as_bytes());
+            hasher.update(b"|");
+        }
+        hash_scoped_map(&mut hasher, &self.column_overrides, |columns| {
+            columns
+                .iter()
+                .map(|(column, o)| format!("{}:{}", column, o))
+                .collect::<Vec<_>>()
+                .join(";")
+        });
+        hash_scoped_map(&mut hasher, &self.promotions, |keys| {
+            keys.iter()
+                .map(|key| key.to_string())
+                .collect::<Vec<_>>()
+                .join(";")
+        });
         format!("{:x}", hasher.finalize())
     }
 
     pub fn is_empty(&self) -> bool {
-        self.schema_only.is_empty() && self.table_filters.is_empty() && self.time_filters.is_empty()
+        self.schema_only.is_empty()
+            && self.priority.is_empty()
+            && self.table_filters.is_empty()
+            && self.time_filters.is_empty()
+            && self.retention.is_empty()
+            && self.samples.is_empty()
+            && self.transforms.is_empty()
+            && self.sources.is_empty()
+            && self.renames.is_empty()
+            && self.identifier_cases.is_empty()
+            && self.column_overrides.is_empty()
+            && self.promotions.is_empty()
+    }
+
+    /// The rules as sent in a remote [`JobSpec`](crate::remote::JobSpec)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the rules sample tables, read them through custom
+    /// sources, or rename tables or columns, which only local runs support.
+    pub fn to_spec(&self) -> Result<TableRulesSpec> {
+        if !self.samples.is_empty()
+            || !self.sources.is_empty()
+            || self.renames_tables()
+            || !self.column_overrides.is_empty()
+            || !self.promotions.is_empty()
+        {
+            bail!(
+                "Samples, table sources, renames, column overrides, and promoted keys are only \
+                 supported with local execution. Add --local to run init on this machine."
+            );
+        }
+        let mut spec = TableRulesSpec::default();
+        for (scope, tables) in &self.schema_only {
+            for key in tables {
+                spec.schema_only_tables.push(spec_table_name(scope, key));
+            }
+        }
+        for (scope, tables) in &self.table_filters {
+            for (key, predicate) in tables {
+                spec.table_filters.push(TableFilterSpec {
+                    table: spec_table_name(scope, key),
+                    predicate: predicate.clone(),
+                });
+            }
+        }
+        for (scope, tables) in &self.time_filters {
+            for (key, rule) in tables {
+                spec.time_filters.push(TimeFilterSpec {
+                    table: spec_table_name(scope, key),
+                    column: rule.column.clone(),
+                    last: rule.interval.clone(),
+                });
+            }
+        }
+        for (scope, tables) in &self.transforms {
+            for (key, transforms) in tables {
+                for column in transforms.columns() {
+                    let transform = transforms
+                        .get(column)
+                        .expect("transformed columns have a transform");
+                    spec.masking.push(MaskingSpec {
+                        table: spec_table_name(scope, key),
+                        column: column.to_string(),
+                        transform: transform.transform.to_string(),
+                        seed: transform.seed,
+                    });
+                }
+            }
+        }
+        Ok(spec)
+    }
+}
+
+/// Unquoted `[database.]schema.table`, as [`QualifiedTable::parse`] reads it
+fn spec_table_name(scope: &ScopeKey, key: &SchemaTableKey) -> String {
+    match scope {
+        ScopeKey::Global => format!("{}.{}", key.schema, key.table),
+        ScopeKey::Database(database) => format!("{}.{}.{}", database, key.schema, key.table),
+    }
+}
+
+/// A foreign key between two tables named as `"schema"."table"`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForeignKey {
+    pub child: String,
+    pub child_columns: Vec<String>,
+    pub parent: String,
+    pub parent_columns: Vec<String>,
+}
+
+/// Expand filtered tables into a referentially consistent copy plan
+///
+/// `filtered` holds `("schema"."table", predicate)` pairs for tables that are only
+/// partly copied. The plan keeps every parent row that a kept child row
+/// references, following foreign keys transitively:
+///
+/// - Tables that reference a filtered table are pulled into the plan with all of
+///   their rows, since they would otherwise be restored before their parents and
+///   emptied by `TRUNCATE ... CASCADE`. Tables in `data_less` (schema-only) stay out.
+/// - A filtered parent's predicate is widened with
+///   `(key) IN (SELECT fk FROM child WHERE <child predicate>)` for each child.
+/// - A self-referencing table is copied in full rather than filtered.
+///
+/// Returns the plan ordered parents first, with `TRUE` for tables copied in full.
+/// Fails if the planned tables form a foreign key cycle.
+pub fn fk_consistent_subset(
+    filtered: &[(String, String)],
+    foreign_keys: &[ForeignKey],
+    data_less: &BTreeSet<String>,
+) -> Result<Vec<(String, String)>> {
+    // None means every row of the table is copied
+    let mut plan: BTreeMap<String, Option<String>> = filtered
+        .iter()
+        .map(|(table, predicate)| (table.clone(), Some(predicate.clone())))
+        .collect();
+
+    // Pull in tables that reference planned tables until nothing changes
+    loop {
+        let pulled: BTreeSet<String> = foreign_keys
+            .iter()
+            .filter(|fk| plan.contains_key(&fk.parent))
+            .filter(|fk| !plan.contains_key(&fk.child) && !data_less.contains(&fk.child))
+            .map(|fk| fk.child.clone())
+            .collect();
+        if pulled.is_empty() {
+            break;
+        }
+        for table in pulled {
+            tracing::info!(
+                "  Copying {} in full: it references a filtered table",
+                table
+            );
+            plan.insert(table, None);
+        }
+    }
+
+    for fk in foreign_keys.iter().filter(|fk| fk.child == fk.parent) {
+        if let Some(predicate) = plan.get_mut(&fk.child) {
+            if predicate.take().is_some() {
+                tracing::warn!(
+                    "  Copying {} in full: its filter cannot follow the self-referencing foreign key",
+                    fk.child
+                );
+            }
+        }
+    }
+
+    let edges: Vec<&ForeignKey> = foreign_keys
+        .iter()
+        .filter(|fk| fk.child != fk.parent)
+        .filter(|fk| plan.contains_key(&fk.child) && plan.contains_key(&fk.parent))
+        .collect();
+
+    // Kahn's algorithm over parent -> child edges; BTree ordering keeps it deterministic
+    let mut pending_parents: BTreeMap<&str, usize> =
+        plan.keys().map(|table| (table.as_str(), 0)).collect();
+    for fk in &edges {
+        *pending_parents.get_mut(fk.child.as_str()).unwrap() += 1;
+    }
+    let mut ready: BTreeSet<&str> = pending_parents
+        .iter()
+        .filter(|(_, count)| **count == 0)
+        .map(|(table, _)| *table)
+        .collect();
+    let mut order = Vec::with_capacity(plan.len());
+    while let Some(table) = ready.pop_first() {
+        order.push(table.to_string());
+        for fk in edges.iter().filter(|fk| fk.parent == table) {
+            let count = pending_parents.get_mut(fk.child.as_str()).unwrap();
+            *count -= 1;
+            if *count == 0 {
+                ready.insert(fk.child.as_str());
+            }
+        }
+    }
+    if order.len() < plan.len() {
+        let cyclic: Vec<&str> = pending_parents
+            .iter()
+            .filter(|(_, count)| **count > 0)
+            .map(|(table, _)| *table)
+            .collect();
+        bail!(
+            "Cannot build a consistent subset: foreign keys form a cycle between {}. \
+             Remove the filters on these tables or copy them in full.",
+            cyclic.join(", ")
+        );
+    }
+
+    // Widen predicates children first, so each child's final predicate is known
+    let mut effective: BTreeMap<String, Option<String>> = BTreeMap::new();
+    for table in order.iter().rev() {
+        let widened = plan[table].as_ref().map(|predicate| {
+            let mut clauses = vec![format!("({})", predicate)];
+            for fk in edges.iter().filter(|fk| &fk.parent == table) {
+                let child_filter = match &effective[&fk.child] {
+                    Some(child_predicate) => format!(" WHERE ({})", child_predicate),
+                    None => String::new(),
+                };
+                clauses.push(format!(
+                    "{} IN (SELECT {} FROM {}{})",
+                    column_tuple(&fk.parent_columns),
+                    column_list(&fk.child_columns),
+                    fk.child,
+                    child_filter
+                ));
+            }
+            clauses.join(" OR ")
+        });
+        effective.insert(table.clone(), widened);
+    }
+
+    Ok(order
+        .into_iter()
+        .map(|table| {
+            let predicate = effective
+                .remove(&table)
+                .flatten()
+                .unwrap_or_else(|| "TRUE".to_string());
+            (table, predicate)
+        })
+        .collect())
+}
+
+/// Tables each table references, leaving out self-references
+pub fn foreign_key_parents(foreign_keys: &[ForeignKey]) -> HashMap<&str, Vec<&str>> {
+    let mut parents: HashMap<&str, Vec<&str>> = HashMap::new();
+    for fk in foreign_keys.iter().filter(|fk| fk.child != fk.parent) {
+        parents
+            .entry(fk.child.as_str())
+            .or_default()
+            .push(fk.parent.as_str());
+    }
+    parents
+}
+
+/// Group tables into waves that only reference tables in earlier waves
+///
+/// `tables` are named as `"schema"."table"`, and only foreign keys between them
+/// are followed. Tables in a foreign key cycle share the last wave. Returns
+/// indexes into `tables`.
+pub fn foreign_key_waves(tables: &[String], foreign_keys: &[ForeignKey]) -> Vec<Vec<usize>> {
+    let parents = foreign_key_parents(foreign_keys);
+    let mut remaining: Vec<usize> = (0..tables.len()).collect();
+    let mut waves = Vec::new();
+    while !remaining.is_empty() {
+        let waiting: BTreeSet<&str> = remaining.iter().map(|&i| tables[i].as_str()).collect();
+        let (ready, blocked): (Vec<usize>, Vec<usize>) = remaining.iter().partition(|&&i| {
+            !parents
+                .get(tables[i].as_str())
+                .is_some_and(|parents| parents.iter().any(|parent| waiting.contains(parent)))
+        });
+        if ready.is_empty() {
+            waves.push(blocked);
+            break;
+        }
+        waves.push(ready);
+        remaining = blocked;
+    }
+    waves
+}
+
+/// `priority` and every table it references, directly or through other tables
+///
+/// Copying these first lets the priority tables be used, and their changes
+/// applied, before the rest of the database has arrived.
+pub fn with_referenced_tables(
+    priority: &[String],
+    foreign_keys: &[ForeignKey],
+) -> BTreeSet<String> {
+    let parents = foreign_key_parents(foreign_keys);
+    let mut group: BTreeSet<String> = BTreeSet::new();
+    let mut pending: Vec<&str> = priority.iter().map(String::as_str).collect();
+    while let Some(table) = pending.pop() {
+        if group.insert(table.to_string()) {
+            pending.extend(parents.get(table).into_iter().flatten());
+        }
+    }
+    group
+}
+
+/// [`foreign_key_waves`], with the waves of `priority` and the tables it
+/// references ahead of the waves of the other tables
+///
+/// Since the first group holds every table its tables reference, foreign key
+/// order still holds across the two.
+pub fn prioritized_waves(
+    tables: &[String],
+    foreign_keys: &[ForeignKey],
+    priority: &[String],
+) -> Vec<Vec<usize>> {
+    if priority.is_empty() {
+        return foreign_key_waves(tables, foreign_keys);
+    }
+    let group = with_referenced_tables(priority, foreign_keys);
+    let (first, rest): (Vec<usize>, Vec<usize>) =
+        (0..tables.len()).partition(|&i| group.contains(&tables[i]));
+    let mut waves = Vec::new();
+    for indexes in [first, rest] {
+        let names: Vec<String> = indexes.iter().map(|&i| tables[i].clone()).collect();
+        waves.extend(
+            foreign_key_waves(&names, foreign_keys)
+                .into_iter()
+                .map(|wave| wave.into_iter().map(|i| indexes[i]).collect::<Vec<_>>()),
+        );
+    }
+    waves
+}
+
+fn column_list(columns: &[String]) -> String {
+    columns
+        .iter()
+        .map(|column| quote_ident(column))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn column_tuple(columns: &[String]) -> String {
+    match columns {
+        [column] => quote_ident(column),
+        _ => format!("({})", column_list(columns)),
     }
 }
 
@@ -773,6 +1843,388 @@ mod tests {
         assert!(predicates[0].1.contains("INTERVAL '6 month'"));
     }
 
+    #[test]
+    fn time_filter_tables_give_sliding_windows() {
+        let mut rules = TableRules::default();
+        rules
+            .apply_time_filter_cli(&["db1.public.metrics:created_at:90 days".into()])
+            .unwrap();
+        let windows = rules.time_filter_tables("db1");
+        let window = &windows["\"public\".\"metrics\""];
+        assert_eq!(
+            window.predicate(),
+            "\"created_at\" >= NOW() - INTERVAL '90 day'"
+        );
+        assert_eq!(
+            window.aged_out_predicate("CreatedAt"),
+            "\"CreatedAt\" < NOW() - INTERVAL '90 day'"
+        );
+        assert!(rules.time_filter_tables("db2").is_empty());
+    }
+
+    #[test]
+    fn sample_percent_parsing() {
+        let rule = SampleRule::percent("10%", 0).unwrap();
+        assert_eq!(rule.size, SampleSize::Percent(1000));
+        assert_eq!(
+            SampleRule::percent("0.5", 7).unwrap().size,
+            SampleSize::Percent(50)
+        );
+        assert_eq!(rule.to_string(), "10%");
+        assert!(SampleRule::percent("0%", 0).is_err());
+        assert!(SampleRule::percent("150%", 0).is_err());
+        assert!(SampleRule::percent("ten", 0).is_err());
+        assert!(SampleRule::rows(0, 0).is_err());
+    }
+
+    #[test]
+    fn sample_rows_predicate() {
+        let rule = SampleRule::rows(100, 42).unwrap();
+        assert_eq!(rule.to_string(), "100 rows (seed 42)");
+        assert_eq!(
+            rule.predicate("\"public\".\"events\"", "\"id\"::text"),
+            "hashtextextended(\"id\"::text, 42) <= (SELECT max(sample_hash) FROM \
+             (SELECT hashtextextended(\"id\"::text, 42) AS sample_hash FROM \"public\".\"events\" \
+             ORDER BY 1 LIMIT 100) AS sample_keys)"
+        );
+    }
+
+    #[test]
+    fn samples_are_not_predicate_tables() {
+        let mut rules = TableRules::default();
+        rules
+            .add_sample(
+                QualifiedTable::parse("db1.public.events").unwrap(),
+                SampleRule::percent("10%", 0).unwrap(),
+            )
+            .unwrap();
+        assert!(rules.predicate_tables("db1").is_empty());
+        assert_eq!(rules.sample_tables("db1").len(), 1);
+        assert_eq!(rules.sampled_table_names("db1", "public"), vec!["events"]);
+        assert!(rules.sampled_table_names("db1", "sales").is_empty());
+        assert!(rules.sample_tables("db2").is_empty());
+        assert!(matches!(
+            rules.rule_for_table("db1", "public", "events"),
+            Some(TableRuleKind::Sample(_))
+        ));
+    }
+
+    #[test]
+    fn transforms_are_scoped_and_fingerprinted() {
+        use crate::transform::Transform;
+
+        let transform = |spec: &str| ColumnTransform {
+            transform: Transform::parse(spec).unwrap(),
+            seed: 0,
+        };
+        let mut rules = TableRules::default();
+        let before = rules.fingerprint();
+        rules
+            .add_transform(
+                QualifiedTable::parse("db1.public.users").unwrap(),
+                "email".to_string(),
+                transform("fake_email"),
+            )
+            .unwrap();
+        rules
+            .add_transform(
+                QualifiedTable::parse("db1.public.users").unwrap(),
+                "name".to_string(),
+                transform("const:REDACTED"),
+            )
+            .unwrap();
+        assert!(rules
+            .add_transform(
+                QualifiedTable::parse("db1.public.users").unwrap(),
+                "email".to_string(),
+                transform("shuffle"),
+            )
+            .is_err());
+        assert!(rules.rewrites_rows());
+        assert_ne!(rules.fingerprint(), before);
+
+        let users = rules.transforms("db1", "public", "users").unwrap();
+        assert_eq!(users.get("email").unwrap().transform, Transform::FakeEmail);
+        assert_eq!(users.columns().collect::<Vec<_>>(), vec!["email", "name"]);
+        assert!(rules.transforms("db2", "public", "users").is_none());
+        assert_eq!(
+            rules
+                .transformed_tables("db1")
+                .keys()
+                .cloned()
+                .collect::<Vec<_>>(),
+            vec!["\"public\".\"users\""]
+        );
+
+        rules
+            .add_schema_only_table(QualifiedTable::parse("db1.public.audit").unwrap())
+            .unwrap();
+        assert!(rules
+            .add_transform(
+                QualifiedTable::parse("db1.public.audit").unwrap(),
+                "who".to_string(),
+                transform("shuffle"),
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn renames_and_identifier_case_resolve_target_names() {
+        let mut rules = TableRules::default();
+        let before = rules.fingerprint();
+        rules
+            .add_rename(
+                QualifiedTable::parse("db1.public.Events").unwrap(),
+                QualifiedTable::parse("analytics.events_v2").unwrap(),
+            )
+            .unwrap();
+        rules.set_identifier_case(Some("db1".to_string()), IdentifierCase::Lower);
+        assert!(rules.renames_tables());
+        assert!(rules.requires_xmin_sync());
+        assert!(!rules.rewrites_rows());
+        assert_ne!(rules.fingerprint(), before);
+
+        let names = rules.target_names("db1");
+        assert!(!names.is_identity());
+        assert_eq!(
+            names.resolve("public", "Events"),
+            ("analytics".to_string(), "events_v2".to_string())
+        );
+        assert_eq!(
+            names.resolve("Sales", "Orders"),
+            ("sales".to_string(), "orders".to_string())
+        );
+        assert!(rules.target_names("db2").is_identity());
+
+        // Tables cannot move to another database
+        assert!(rules
+            .add_rename(
+                QualifiedTable::parse("db1.public.users").unwrap(),
+                QualifiedTable::parse("db2.public.users").unwrap(),
+            )
+            .is_err());
+        assert!(IdentifierCase::parse("camel").is_err());
+    }
+
+    #[test]
+    fn column_overrides_rename_target_columns() {
+        let mut rules = TableRules::default();
+        let before = rules.fingerprint();
+        rules
+            .add_column_override(
+                QualifiedTable::parse("db1.public.users").unwrap(),
+                "size".to_string(),
+                ColumnOverride {
+                    target_type: Some("bigint".to_string()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert!(!rules.renames_tables());
+        assert!(rules.target_names("db1").is_identity());
+        assert_ne!(rules.fingerprint(), before);
+
+        rules
+            .add_column_override(
+                QualifiedTable::parse("db1.public.users").unwrap(),
+                "UserName".to_string(),
+                ColumnOverride {
+                    rename_to: Some("user_name".to_string()),
+                    nullable: Some(false),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert!(rules.renames_tables());
+        let names = rules.target_names("db1");
+        assert_eq!(
+            names.resolve_column("public", "users", "UserName"),
+            "user_name"
+        );
+        assert_eq!(names.resolve_column("public", "users", "size"), "size");
+        assert!(names.renames_columns("public", "users"));
+        assert!(!names.renames_columns("public", "orders"));
+        assert_eq!(
+            rules.column_overrides("db1", "public", "users").unwrap()["UserName"].to_string(),
+            "renamed to user_name, not null"
+        );
+
+        // One override per column, and each must change something
+        assert!(rules
+            .add_column_override(
+                QualifiedTable::parse("db1.public.users").unwrap(),
+                "size".to_string(),
+                ColumnOverride {
+                    nullable: Some(true),
+                    ..Default::default()
+                },
+            )
+            .is_err());
+        assert!(rules
+            .add_column_override(
+                QualifiedTable::parse("db1.public.users").unwrap(),
+                "email".to_string(),
+                ColumnOverride::default(),
+            )
+            .is_err());
+    }
+
+    fn fk(child: &str, child_column: &str, parent: &str, parent_column: &str) -> ForeignKey {
+        ForeignKey {
+            child: format!("\"public\".\"{}\"", child),
+            child_columns: vec![child_column.to_string()],
+            parent: format!("\"public\".\"{}\"", parent),
+            parent_columns: vec![parent_column.to_string()],
+        }
+    }
+
+    #[test]
+    fn foreign_key_waves_put_parents_first() {
+        let tables: Vec<String> = ["order_items", "orders", "users", "audit"]
+            .iter()
+            .map(|table| format!("\"public\".\"{}\"", table))
+            .collect();
+        let fks = vec![
+            fk("order_items", "order_id", "orders", "id"),
+            fk("orders", "user_id", "users", "id"),
+            fk("users", "referrer_id", "users", "id"),
+            fk("audit", "user_id", "accounts", "id"),
+        ];
+        assert_eq!(
+            foreign_key_waves(&tables, &fks),
+            vec![vec![2, 3], vec![1], vec![0]]
+        );
+
+        let cycle = vec![
+            fk("orders", "user_id", "users", "id"),
+            fk("users", "last_order_id", "orders", "id"),
+        ];
+        assert_eq!(
+            foreign_key_waves(&tables, &cycle),
+            vec![vec![0, 3], vec![1, 2]]
+        );
+    }
+
+    #[test]
+    fn prioritized_waves_copy_priority_tables_and_their_parents_first() {
+        let tables: Vec<String> = ["order_items", "orders", "users", "audit", "events"]
+            .iter()
+            .map(|table| format!("\"public\".\"{}\"", table))
+            .collect();
+        let fks = vec![
+            fk("order_items", "order_id", "orders", "id"),
+            fk("orders", "user_id", "users", "id"),
+            fk("audit", "user_id", "accounts", "id"),
+        ];
+        let priority = vec!["\"public\".\"orders\"".to_string()];
+        assert_eq!(
+            with_referenced_tables(&priority, &fks),
+            BTreeSet::from([
+                "\"public\".\"orders\"".to_string(),
+                "\"public\".\"users\"".to_string()
+            ])
+        );
+        assert_eq!(
+            prioritized_waves(&tables, &fks, &priority),
+            vec![vec![2], vec![1], vec![0, 3, 4]]
+        );
+        assert_eq!(
+            prioritized_waves(&tables, &fks, &[]),
+            foreign_key_waves(&tables, &fks)
+        );
+    }
+
+    #[test]
+    fn subset_widens_parents_of_filtered_children() {
+        let filtered = vec![
+            (
+                "\"public\".\"orders\"".to_string(),
+                "amount > 10".to_string(),
+            ),
+            ("\"public\".\"users\"".to_string(), "active".to_string()),
+        ];
+        let fks = vec![fk("orders", "user_id", "users", "id")];
+
+        let plan = fk_consistent_subset(&filtered, &fks, &BTreeSet::new()).unwrap();
+        assert_eq!(
+            plan,
+            vec![
+                (
+                    "\"public\".\"users\"".to_string(),
+                    "(active) OR \"id\" IN (SELECT \"user_id\" FROM \"public\".\"orders\" WHERE ((amount > 10)))"
+                        .to_string()
+                ),
+                ("\"public\".\"orders\"".to_string(), "(amount > 10)".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn subset_pulls_in_referencing_tables_transitively() {
+        let filtered = vec![("\"public\".\"users\"".to_string(), "active".to_string())];
+        let fks = vec![
+            fk("orders", "user_id", "users", "id"),
+            fk("order_items", "order_id", "orders", "id"),
+            fk("audit", "user_id", "users", "id"),
+        ];
+        let data_less: BTreeSet<String> = ["\"public\".\"audit\"".to_string()].into();
+
+        let plan = fk_consistent_subset(&filtered, &fks, &data_less).unwrap();
+        let tables: Vec<&str> = plan.iter().map(|(t, _)| t.as_str()).collect();
+        assert_eq!(
+            tables,
+            vec![
+                "\"public\".\"users\"",
+                "\"public\".\"orders\"",
+                "\"public\".\"order_items\""
+            ]
+        );
+        assert_eq!(
+            plan[0].1,
+            "(active) OR \"id\" IN (SELECT \"user_id\" FROM \"public\".\"orders\")"
+        );
+        assert_eq!(plan[1].1, "TRUE");
+        assert_eq!(plan[2].1, "TRUE");
+    }
+
+    #[test]
+    fn subset_copies_self_referencing_tables_in_full() {
+        let filtered = vec![(
+            "\"public\".\"employees\"".to_string(),
+            "dept = 1".to_string(),
+        )];
+        let fks = vec![fk("employees", "manager_id", "employees", "id")];
+
+        let plan = fk_consistent_subset(&filtered, &fks, &BTreeSet::new()).unwrap();
+        assert_eq!(
+            plan,
+            vec![("\"public\".\"employees\"".to_string(), "TRUE".to_string())]
+        );
+    }
+
+    #[test]
+    fn subset_rejects_cycles() {
+        let filtered = vec![("\"public\".\"a\"".to_string(), "x = 1".to_string())];
+        let fks = vec![fk("a", "b_id", "b", "id"), fk("b", "a_id", "a", "id")];
+
+        let err = fk_consistent_subset(&filtered, &fks, &BTreeSet::new()).unwrap_err();
+        assert!(err.to_string().contains("cycle"));
+    }
+
+    #[test]
+    fn sample_conflicts_with_filters() {
+        let mut rules = TableRules::default();
+        rules
+            .apply_table_filter_cli(&["logs:level = 'error'".to_string()])
+            .unwrap();
+        assert!(rules
+            .add_sample(
+                QualifiedTable::parse("logs").unwrap(),
+                SampleRule::rows(10, 0).unwrap()
+            )
+            .is_err());
+    }
+
     #[test]
     fn test_fingerprint_changes_with_schema() {
         // Different schemas should produce different fingerprints
@@ -0,0 +1,424 @@
+// ABOUTME: Records which databases feed each target in _replicator_origins, hop by hop
+// ABOUTME: Lets a replica serve as the source of a further hop without creating loops
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use tokio_postgres::Client;
+
+use crate::postgres::snapshot::{ConsistencyPoint, SnapshotMethod};
+
+/// Name of the origins table created in each target's `public` schema
+pub const ORIGINS_TABLE: &str = "_replicator_origins";
+
+/// Name of the table recording the point in each source that a target's copy reflects
+pub const SNAPSHOTS_TABLE: &str = "_replicator_snapshots";
+
+/// `pg_dump` pattern for the tables this tool keeps in a target's `public` schema
+///
+/// A replica used as a source keeps them to itself; the next hop records its own.
+pub const METADATA_TABLE_PATTERN: &str = "public._replicator_*";
+
+/// SQL condition that is true for this tool's own metadata tables
+///
+/// `schema` and `table` are the SQL expressions holding a table's schema and
+/// name. The audit schema and the `_replicator_*` tables in `public` describe
+/// how a database was replicated, so they never travel to the next hop.
+pub fn metadata_table_condition(schema: &str, table: &str) -> String {
+    format!(
+        "({schema} = '{}' OR ({schema} = 'public' AND {table} LIKE '\\_replicator\\_%'))",
+        crate::audit::AUDIT_SCHEMA
+    )
+}
+
+/// How data moves along one hop
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HopMethod {
+    /// A one-off copy by `init --no-sync`
+    Snapshot,
+    /// A logical replication subscription
+    Logical,
+    /// Polling by xmin with `sync`
+    Xmin,
+}
+
+impl HopMethod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HopMethod::Snapshot => "snapshot",
+            HopMethod::Logical => "logical",
+            HopMethod::Xmin => "xmin",
+        }
+    }
+
+    fn parse(value: &str) -> Self {
+        match value {
+            "logical" => HopMethod::Logical,
+            "xmin" => HopMethod::Xmin,
+            _ => HopMethod::Snapshot,
+        }
+    }
+}
+
+/// A database taking part in replication
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Node {
+    /// `<system identifier>/<database>`, the same whichever host name reaches it
+    pub id: String,
+    /// `host:port/database` as given in the URL, never with credentials
+    pub label: String,
+}
+
+impl Node {
+    /// Identify the database named in `url` through `client`, connected to any
+    /// database on the same server
+    ///
+    /// Servers that hide `pg_control_system()` are identified by their label.
+    pub async fn identify(client: &Client, url: &str) -> Result<Self> {
+        let label = crate::xmin::source_identifier(url)?;
+        let database = crate::utils::parse_postgres_url(url)?.database;
+        let id = match client
+            .query_one(
+                "SELECT system_identifier::text FROM pg_control_system()",
+                &[],
+            )
+            .await
+        {
+            Ok(row) => format!("{}/{}", row.get::<_, String>(0), database),
+            Err(e) => {
+                tracing::debug!("Could not read the system identifier of {}: {}", label, e);
+                label.clone()
+            }
+        };
+        Ok(Self { id, label })
+    }
+}
+
+/// One hop recorded in a database: rows flow from `source` into `target`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hop {
+    pub source: Node,
+    pub target: Node,
+    pub method: HopMethod,
+    /// 1 for the hop into the database holding the record, 2 for the one before it, ...
+    pub distance: i32,
+    /// When the hop last copied or checked for changes
+    pub synced_at: DateTime<Utc>,
+}
+
+/// Create the origins table in the target if it does not exist
+pub async fn ensure_origins_table(client: &Client) -> Result<()> {
+    client
+        .batch_execute(&format!(
+            "CREATE TABLE IF NOT EXISTS public.\"{}\" (
+                source_id TEXT NOT NULL,
+                source_label TEXT NOT NULL,
+                target_id TEXT NOT NULL,
+                target_label TEXT NOT NULL,
+                method TEXT NOT NULL,
+                distance INTEGER NOT NULL,
+                synced_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+                PRIMARY KEY (source_id, target_id)
+            )",
+            ORIGINS_TABLE
+        ))
+        .await
+        .context("Failed to create _replicator_origins table in target")?;
+    Ok(())
+}
+
+/// The hops recorded in a database, nearest first; none when it was never a target
+pub async fn load_hops(client: &Client) -> Result<Vec<Hop>> {
+    let exists: bool = client
+        .query_one(
+            &format!(
+                "SELECT to_regclass('public.\"{}\"') IS NOT NULL",
+                ORIGINS_TABLE
+            ),
+            &[],
+        )
+        .await
+        .context("Failed to look up _replicator_origins")?
+        .get(0);
+    if !exists {
+        return Ok(Vec::new());
+    }
+    let rows = client
+        .query(
+            &format!(
+                "SELECT source_id, source_label, target_id, target_label, method, distance, synced_at
+                 FROM public.\"{}\"
+                 ORDER BY distance, source_label",
+                ORIGINS_TABLE
+            ),
+            &[],
+        )
+        .await
+        .context("Failed to read _replicator_origins")?;
+    Ok(rows
+        .iter()
+        .map(|row| Hop {
+            source: Node {
+                id: row.get(0),
+                label: row.get(1),
+            },
+            target: Node {
+                id: row.get(2),
+                label: row.get(3),
+            },
+            method: HopMethod::parse(row.get(4)),
+            distance: row.get(5),
+            synced_at: row.get(6),
+        })
+        .collect())
+}
+
+/// Fail when replicating `source` into `target` would feed `target` its own rows
+///
+/// `upstream` are the hops recorded in `source`.
+pub fn check_for_loop(source: &Node, target: &Node, upstream: &[Hop]) -> Result<()> {
+    if source.id == target.id {
+        bail!(
+            "{} and {} are the same database; replicating it into itself would loop",
+            source.label,
+            target.label
+        );
+    }
+    if let Some(hop) = upstream.iter().find(|hop| hop.source.id == target.id) {
+        bail!(
+            "{} is already replicated from {} ({} hop(s) upstream); replicating it back would create a loop.\n\
+             Stop the replication into {} first, or pick a target that is not upstream of it.",
+            source.label,
+            target.label,
+            hop.distance,
+            source.label
+        );
+    }
+    Ok(())
+}
+
+/// Check that the hop from `source_url` into `target_url` does not loop
+///
+/// `source_client` is connected to the source database, where its hops are
+/// recorded. `target_client` may be connected to any database on the target
+/// server, so this can run before the target database exists.
+pub async fn check_hop(
+    source_client: &Client,
+    source_url: &str,
+    target_client: &Client,
+    target_url: &str,
+) -> Result<()> {
+    let source = Node::identify(source_client, source_url).await?;
+    let target = Node::identify(target_client, target_url).await?;
+    let upstream = load_hops(source_client).await?;
+    check_for_loop(&source, &target, &upstream)
+}
+
+/// Record the hop from `source_url` into `target_url`, and the hops that fed
+/// the source, in the target's origins table
+///
+/// Both clients are connected to the databases named in their URLs. Fails
+/// without writing anything when the hop would loop.
+pub async fn record_hop(
+    source_client: &Client,
+    source_url: &str,
+    target_client: &Client,
+    target_url: &str,
+    method: HopMethod,
+) -> Result<()> {
+    let source = Node::identify(source_client, source_url).await?;
+    let target = Node::identify(target_client, target_url).await?;
+    let upstream = load_hops(source_client).await?;
+    check_for_loop(&source, &target, &upstream)?;
+
+    ensure_origins_table(target_client).await?;
+    let upsert = format!(
+        "INSERT INTO public.\"{}\"
+            (source_id, source_label, target_id, target_label, method, distance, synced_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7)
+         ON CONFLICT (source_id, target_id) DO UPDATE SET
+            source_label = EXCLUDED.source_label,
+            target_label = EXCLUDED.target_label,
+            method = EXCLUDED.method,
+            distance = EXCLUDED.distance,
+            synced_at = EXCLUDED.synced_at",
+        ORIGINS_TABLE
+    );
+    let direct = Hop {
+        source,
+        target,
+        method,
+        distance: 1,
+        synced_at: Utc::now(),
+    };
+    target_client
+        .batch_execute("BEGIN")
+        .await
+        .context("Failed to start transaction for _replicator_origins")?;
+    let result = async {
+        for hop in std::iter::once(direct).chain(upstream.into_iter().map(|hop| Hop {
+            distance: hop.distance + 1,
+            ..hop
+        })) {
+            target_client
+                .execute(
+                    &upsert,
+                    &[
+                        &hop.source.id,
+                        &hop.source.label,
+                        &hop.target.id,
+                        &hop.target.label,
+                        &hop.method.as_str(),
+                        &hop.distance,
+                        &hop.synced_at,
+                    ],
+                )
+                .await
+                .context("Failed to record hop in _replicator_origins")?;
+        }
+        Ok::<_, anyhow::Error>(())
+    }
+    .await;
+    match result {
+        Ok(()) => target_client
+            .batch_execute("COMMIT")
+            .await
+            .context("Failed to commit _replicator_origins"),
+        Err(e) => {
+            let _ = target_client.batch_execute("ROLLBACK").await;
+            Err(e)
+        }
+    }
+}
+
+/// Record the point in the source's history that `init` copied into the target
+///
+/// One row is kept per source, replaced by each new copy.
+pub async fn record_consistency_point(
+    source_client: &Client,
+    source_url: &str,
+    target_client: &Client,
+    point: &ConsistencyPoint,
+) -> Result<()> {
+    let source = Node::identify(source_client, source_url).await?;
+    target_client
+        .batch_execute(&format!(
+            "CREATE TABLE IF NOT EXISTS public.\"{}\" (
+                source_id TEXT PRIMARY KEY,
+                source_label TEXT NOT NULL,
+                lsn PG_LSN NOT NULL,
+                txid_snapshot TEXT NOT NULL,
+                method TEXT NOT NULL,
+                snapshot_at TIMESTAMPTZ NOT NULL,
+                recorded_at TIMESTAMPTZ NOT NULL DEFAULT now()
+            )",
+            SNAPSHOTS_TABLE
+        ))
+        .await
+        .context("Failed to create _replicator_snapshots table in target")?;
+    target_client
+        .execute(
+            &format!(
+                "INSERT INTO public.\"{}\"
+                    (source_id, source_label, lsn, txid_snapshot, method, snapshot_at)
+                 VALUES ($1, $2, $3::text::pg_lsn, $4, $5, $6)
+                 ON CONFLICT (source_id) DO UPDATE SET
+                    source_label = EXCLUDED.source_label,
+                    lsn = EXCLUDED.lsn,
+                    txid_snapshot = EXCLUDED.txid_snapshot,
+                    method = EXCLUDED.method,
+                    snapshot_at = EXCLUDED.snapshot_at,
+                    recorded_at = now()",
+                SNAPSHOTS_TABLE
+            ),
+            &[
+                &source.id,
+                &source.label,
+                &point.lsn,
+                &point.txid_snapshot,
+                &point.method.as_str(),
+                &point.taken_at,
+            ],
+        )
+        .await
+        .context("Failed to record snapshot in _replicator_snapshots")?;
+    Ok(())
+}
+
+/// The consistency point recorded in a target for the source `source_id`
+pub async fn load_consistency_point(
+    client: &Client,
+    source_id: &str,
+) -> Result<Option<ConsistencyPoint>> {
+    let exists: bool = client
+        .query_one(
+            &format!(
+                "SELECT to_regclass('public.\"{}\"') IS NOT NULL",
+                SNAPSHOTS_TABLE
+            ),
+            &[],
+        )
+        .await
+        .context("Failed to look up _replicator_snapshots")?
+        .get(0);
+    if !exists {
+        return Ok(None);
+    }
+    let row = client
+        .query_opt(
+            &format!(
+                "SELECT lsn::text, txid_snapshot, method, snapshot_at
+                 FROM public.\"{}\" WHERE source_id = $1",
+                SNAPSHOTS_TABLE
+            ),
+            &[&source_id],
+        )
+        .await
+        .context("Failed to read _replicator_snapshots")?;
+    Ok(row.map(|row| ConsistencyPoint {
+        lsn: row.get(0),
+        txid_snapshot: row.get(1),
+        method: SnapshotMethod::parse(row.get(2)),
+        taken_at: row.get(3),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(name: &str) -> Node {
+        Node {
+            id: format!("7000/{}", name),
+            label: format!("{}:5432/app", name),
+        }
+    }
+
+    fn hop(source: &str, target: &str, distance: i32) -> Hop {
+        Hop {
+            source: node(source),
+            target: node(target),
+            method: HopMethod::Logical,
+            distance,
+            synced_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_check_for_loop() {
+        // a -> b -> c is recorded in c
+        let upstream = vec![hop("b", "c", 1), hop("a", "b", 2)];
+        assert!(check_for_loop(&node("c"), &node("d"), &upstream).is_ok());
+        let err = check_for_loop(&node("c"), &node("a"), &upstream).unwrap_err();
+        assert!(err.to_string().contains("2 hop(s) upstream"));
+        assert!(check_for_loop(&node("c"), &node("b"), &upstream).is_err());
+        assert!(check_for_loop(&node("c"), &node("c"), &[]).is_err());
+    }
+
+    #[test]
+    fn test_metadata_table_condition() {
+        assert_eq!(
+            metadata_table_condition("n.nspname", "c.relname"),
+            "(n.nspname = '_replicator' OR (n.nspname = 'public' AND c.relname LIKE '\\_replicator\\_%'))"
+        );
+    }
+}
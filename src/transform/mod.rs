@@ -0,0 +1,274 @@
+// ABOUTME: Column transformations that anonymize data copied to staging targets
+// ABOUTME: Parses per-column transform specs and builds the SELECT lists that apply them
+
+pub mod sql;
+
+use anyhow::{bail, Context, Result};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Digest used by a `hash:` transform
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    Md5,
+    Sha224,
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+impl HashAlgorithm {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HashAlgorithm::Md5 => "md5",
+            HashAlgorithm::Sha224 => "sha224",
+            HashAlgorithm::Sha256 => "sha256",
+            HashAlgorithm::Sha384 => "sha384",
+            HashAlgorithm::Sha512 => "sha512",
+        }
+    }
+}
+
+/// How a column's values are rewritten on their way to the target
+///
+/// Every transform is deterministic: a value is always rewritten to the same
+/// output for the same seed, so a key transformed in a parent table still
+/// matches the same key transformed in a child table. NULLs stay NULL.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Transform {
+    /// A fake address derived from the value, e.g. `user_1a2b3c4d5e6f7a8b@example.com`
+    FakeEmail,
+    /// The value's characters in a seeded order
+    Shuffle,
+    /// Hex digest of the seed and the value
+    Hash(HashAlgorithm),
+    /// The same text for every non-NULL value
+    Const(String),
+}
+
+impl Transform {
+    /// Parse a transform such as `fake_email`, `shuffle`, `hash:sha256`, or `const:REDACTED`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use database_replicator::transform::{HashAlgorithm, Transform};
+    /// assert_eq!(Transform::parse("hash:sha256").unwrap(), Transform::Hash(HashAlgorithm::Sha256));
+    /// assert_eq!(Transform::parse("const:REDACTED").unwrap(), Transform::Const("REDACTED".into()));
+    /// assert!(Transform::parse("scramble").is_err());
+    /// ```
+    pub fn parse(spec: &str) -> Result<Self> {
+        let trimmed = spec.trim();
+        let (name, argument) = match trimmed.split_once(':') {
+            Some((name, argument)) => (name.trim(), Some(argument)),
+            None => (trimmed, None),
+        };
+        match (name.to_ascii_lowercase().as_str(), argument) {
+            ("fake_email", None) => Ok(Transform::FakeEmail),
+            ("shuffle", None) => Ok(Transform::Shuffle),
+            ("hash", Some(algorithm)) => {
+                let algorithm = match algorithm.trim().to_ascii_lowercase().as_str() {
+                    "md5" => HashAlgorithm::Md5,
+                    "sha224" => HashAlgorithm::Sha224,
+                    "sha256" => HashAlgorithm::Sha256,
+                    "sha384" => HashAlgorithm::Sha384,
+                    "sha512" => HashAlgorithm::Sha512,
+                    other => bail!(
+                        "Invalid transform '{}': unknown hash '{}' (use md5, sha224, sha256, sha384, or sha512)",
+                        spec,
+                        other
+                    ),
+                };
+                Ok(Transform::Hash(algorithm))
+            }
+            // The constant is kept as written, including surrounding spaces
+            ("const", Some(value)) => Ok(Transform::Const(value.to_string())),
+            ("hash", None) | ("const", None) => {
+                bail!("Invalid transform '{}': expected {}:<value>", spec, name)
+            }
+            _ => bail!(
+                "Invalid transform '{}': expected fake_email, shuffle, hash:<algorithm>, or const:<value>",
+                spec
+            ),
+        }
+    }
+}
+
+impl fmt::Display for Transform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Transform::FakeEmail => write!(f, "fake_email"),
+            Transform::Shuffle => write!(f, "shuffle"),
+            Transform::Hash(algorithm) => write!(f, "hash:{}", algorithm.as_str()),
+            Transform::Const(value) => write!(f, "const:{}", value),
+        }
+    }
+}
+
+/// A transform and the seed it mixes into each value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnTransform {
+    pub transform: Transform,
+    pub seed: i64,
+}
+
+impl fmt::Display for ColumnTransform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.transform)?;
+        if self.seed != 0 {
+            write!(f, " (seed {})", self.seed)?;
+        }
+        Ok(())
+    }
+}
+
+/// The transformed columns of one table
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TableTransforms {
+    columns: BTreeMap<String, ColumnTransform>,
+}
+
+impl TableTransforms {
+    /// Transform `column`, which must not already have a transform
+    pub fn insert(&mut self, column: String, transform: ColumnTransform) -> Result<()> {
+        if self.columns.contains_key(&column) {
+            bail!("Column '{}' has more than one transform", column);
+        }
+        self.columns.insert(column, transform);
+        Ok(())
+    }
+
+    pub fn get(&self, column: &str) -> Option<&ColumnTransform> {
+        self.columns.get(column)
+    }
+
+    /// Names of the transformed columns
+    pub fn columns(&self) -> impl Iterator<Item = &str> {
+        self.columns.keys().map(String::as_str)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.columns.is_empty()
+    }
+
+    /// Whether any of `columns` is transformed
+    pub fn touches(&self, columns: &[String]) -> bool {
+        columns
+            .iter()
+            .any(|column| self.columns.contains_key(column))
+    }
+
+    /// Replace the SELECT expressions of transformed columns
+    ///
+    /// `columns` are `(name, type)` pairs in SELECT order, where the type is
+    /// valid SQL for casting the transformed text back to the column's type.
+    /// `expressions` holds one entry per column and is updated in place. Fails
+    /// if a transform names a column that is not in `columns`.
+    pub fn apply(&self, columns: &[(String, String)], expressions: &mut [String]) -> Result<()> {
+        for (column, transform) in &self.columns {
+            let index = columns
+                .iter()
+                .position(|(name, _)| name == column)
+                .with_context(|| format!("Transformed column '{}' does not exist", column))?;
+            expressions[index] = sql::select_expression(column, &columns[index].1, transform);
+        }
+        Ok(())
+    }
+
+    /// A SELECT list of every column in `columns`, with transforms applied
+    pub fn select_list(&self, columns: &[(String, String)]) -> Result<String> {
+        let mut expressions: Vec<String> = columns
+            .iter()
+            .map(|(name, _)| crate::utils::quote_ident(name))
+            .collect();
+        self.apply(columns, &mut expressions)?;
+        Ok(expressions.join(", "))
+    }
+}
+
+impl fmt::Display for TableTransforms {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let described: Vec<String> = self
+            .columns
+            .iter()
+            .map(|(column, transform)| format!("{} = {}", column, transform))
+            .collect();
+        write!(f, "{}", described.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn column(transform: Transform) -> ColumnTransform {
+        ColumnTransform { transform, seed: 0 }
+    }
+
+    #[test]
+    fn test_parse_transforms() {
+        assert_eq!(
+            Transform::parse("fake_email").unwrap(),
+            Transform::FakeEmail
+        );
+        assert_eq!(Transform::parse(" Shuffle ").unwrap(), Transform::Shuffle);
+        assert_eq!(
+            Transform::parse("hash:SHA512").unwrap(),
+            Transform::Hash(HashAlgorithm::Sha512)
+        );
+        assert_eq!(
+            Transform::parse("const:a:b").unwrap(),
+            Transform::Const("a:b".to_string())
+        );
+        assert_eq!(
+            Transform::parse("const:").unwrap(),
+            Transform::Const(String::new())
+        );
+        assert!(Transform::parse("hash").is_err());
+        assert!(Transform::parse("hash:crc32").is_err());
+        assert!(Transform::parse("shuffle:3").is_err());
+        assert!(Transform::parse("").is_err());
+    }
+
+    #[test]
+    fn test_transform_round_trips_through_display() {
+        for spec in ["fake_email", "shuffle", "hash:md5", "const:REDACTED"] {
+            assert_eq!(Transform::parse(spec).unwrap().to_string(), spec);
+        }
+    }
+
+    #[test]
+    fn test_duplicate_column_is_rejected() {
+        let mut transforms = TableTransforms::default();
+        transforms
+            .insert("email".to_string(), column(Transform::FakeEmail))
+            .unwrap();
+        assert!(transforms
+            .insert("email".to_string(), column(Transform::Shuffle))
+            .is_err());
+    }
+
+    #[test]
+    fn test_select_list_replaces_transformed_columns() {
+        let mut transforms = TableTransforms::default();
+        transforms
+            .insert(
+                "note".to_string(),
+                column(Transform::Const("REDACTED".to_string())),
+            )
+            .unwrap();
+        let columns = vec![
+            ("id".to_string(), "integer".to_string()),
+            ("note".to_string(), "text".to_string()),
+        ];
+        assert_eq!(
+            transforms.select_list(&columns).unwrap(),
+            "\"id\", (CASE WHEN \"note\"::text IS NULL THEN NULL ELSE 'REDACTED' END)::text AS \"note\""
+        );
+        assert!(transforms.touches(&["note".to_string()]));
+        assert!(!transforms.touches(&["id".to_string()]));
+
+        let missing = vec![("id".to_string(), "integer".to_string())];
+        assert!(transforms.select_list(&missing).is_err());
+    }
+}
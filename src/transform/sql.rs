@@ -0,0 +1,124 @@
+// ABOUTME: SQL expressions that apply column transforms on the source while reading
+// ABOUTME: Keeps the work in PostgreSQL so COPY and xmin batch reads both see transformed values
+
+use super::{ColumnTransform, HashAlgorithm, Transform};
+use crate::utils::{quote_ident, quote_literal};
+use anyhow::{Context, Result};
+use tokio_postgres::Client;
+
+/// SELECT expression reading `column` through `transform`, cast back to `sql_type`
+///
+/// The value is read as text, rewritten, and cast back, so the result decodes
+/// like the original column. The expression is aliased to the column name.
+pub fn select_expression(column: &str, sql_type: &str, transform: &ColumnTransform) -> String {
+    format!(
+        "({})::{} AS {}",
+        text_expression(&format!("{}::text", quote_ident(column)), transform),
+        sql_type,
+        quote_ident(column)
+    )
+}
+
+/// Text expression rewriting the text expression `value`
+fn text_expression(value: &str, transform: &ColumnTransform) -> String {
+    // The seed prefixes every digest input, so outputs change with the seed
+    let salted = format!(
+        "{} || {}",
+        quote_literal(&format!("{}:", transform.seed)),
+        value
+    );
+    match &transform.transform {
+        Transform::FakeEmail => format!(
+            "'user_' || left({}, 16) || '@example.com'",
+            digest(HashAlgorithm::Md5, &salted)
+        ),
+        // Characters are ordered by a digest of the seed, the value, and their position
+        Transform::Shuffle => format!(
+            "CASE WHEN {value} IS NULL THEN NULL ELSE \
+             (SELECT coalesce(string_agg(c, '' ORDER BY md5({salted} || ':' || n)), '') \
+             FROM regexp_split_to_table({value}, '') WITH ORDINALITY AS chars(c, n)) END"
+        ),
+        Transform::Hash(algorithm) => digest(*algorithm, &salted),
+        Transform::Const(text) => format!(
+            "CASE WHEN {} IS NULL THEN NULL ELSE {} END",
+            value,
+            quote_literal(text)
+        ),
+    }
+}
+
+/// Lowercase hex digest of a text expression
+fn digest(algorithm: HashAlgorithm, input: &str) -> String {
+    match algorithm {
+        HashAlgorithm::Md5 => format!("md5({})", input),
+        other => format!(
+            "encode({}(convert_to({}, 'UTF8')), 'hex')",
+            other.as_str(),
+            input
+        ),
+    }
+}
+
+/// Columns a COPY of the table writes, as `(name, type)` pairs in column order
+///
+/// Types are spelled by `format_type`, so they can be cast to. Generated
+/// columns are left out, since the target computes them.
+pub async fn copy_columns(
+    client: &Client,
+    schema: &str,
+    table: &str,
+) -> Result<Vec<(String, String)>> {
+    let rows = client
+        .query(
+            "SELECT a.attname::text, format_type(a.atttypid, a.atttypmod)
+             FROM pg_attribute a
+             JOIN pg_class c ON c.oid = a.attrelid
+             JOIN pg_namespace n ON n.oid = c.relnamespace
+             WHERE n.nspname = $1 AND c.relname = $2
+               AND a.attnum > 0 AND NOT a.attisdropped AND a.attgenerated = ''
+             ORDER BY a.attnum",
+            &[&schema, &table],
+        )
+        .await
+        .with_context(|| format!("Failed to get columns for {}.{}", schema, table))?;
+    Ok(rows.iter().map(|row| (row.get(0), row.get(1))).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expression(transform: Transform, seed: i64) -> String {
+        select_expression("email", "text", &ColumnTransform { transform, seed })
+    }
+
+    #[test]
+    fn test_hash_expressions_are_salted_with_the_seed() {
+        assert_eq!(
+            expression(Transform::Hash(HashAlgorithm::Sha256), 7),
+            "(encode(sha256(convert_to('7:' || \"email\"::text, 'UTF8')), 'hex'))::text AS \"email\""
+        );
+        assert_eq!(
+            expression(Transform::Hash(HashAlgorithm::Md5), 0),
+            "(md5('0:' || \"email\"::text))::text AS \"email\""
+        );
+    }
+
+    #[test]
+    fn test_fake_email_expression() {
+        assert_eq!(
+            expression(Transform::FakeEmail, 0),
+            "('user_' || left(md5('0:' || \"email\"::text), 16) || '@example.com')::text AS \"email\""
+        );
+    }
+
+    #[test]
+    fn test_shuffle_and_const_keep_nulls() {
+        assert!(expression(Transform::Shuffle, 0)
+            .starts_with("(CASE WHEN \"email\"::text IS NULL THEN NULL ELSE (SELECT"));
+        assert_eq!(
+            expression(Transform::Const("it's".to_string()), 0),
+            "(CASE WHEN \"email\"::text IS NULL THEN NULL ELSE 'it''s' END)::text AS \"email\""
+        );
+    }
+}
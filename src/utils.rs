@@ -112,6 +112,17 @@ pub fn validate_connection_string(url: &str) -> Result<()> {
     Ok(())
 }
 
+/// PostgreSQL client tools that are not installed or not in PATH
+///
+/// Checks `pg_dump`, `pg_dumpall`, `pg_restore`, and `psql`. Without them, init
+/// copies schema and data over plain connections instead.
+pub fn missing_client_tools() -> Vec<&'static str> {
+    ["pg_dump", "pg_dumpall", "pg_restore", "psql"]
+        .into_iter()
+        .filter(|tool| which(tool).is_err())
+        .collect()
+}
+
 /// Check that required PostgreSQL client tools are available
 ///
 /// Verifies that the following tools are installed and in PATH:
@@ -231,43 +242,44 @@ where
 
 /// Retry a subprocess execution with exponential backoff on connection errors
 ///
-/// Executes a subprocess command with automatic retry on connection-related failures.
-/// Each retry doubles the delay (exponential backoff) to handle transient connection issues.
+/// Runs the child that `operation` spawns and retries it while it fails for
+/// a transient reason. Each retry doubles the delay (exponential backoff).
 ///
-/// Connection errors are detected by checking:
-/// - Non-zero exit codes
-/// - Stderr output containing connection-related error patterns:
-///   - "connection closed"
-///   - "connection refused"
-///   - "could not connect"
-///   - "server closed the connection"
-///   - "timeout"
-///   - "Connection timed out"
+/// The child's stderr must be piped. It is echoed to this process's stderr
+/// as it arrives, and its last lines are classified when the child fails
+/// (see [`ToolError`](crate::failure::ToolError)):
+/// - Connection refused, reset, or dropped, and servers starting up or out of
+///   connection slots are retried
+/// - Rejected credentials, missing roles, missing privileges, a full disk,
+///   and unrecognized errors fail at once
+///
+/// The final error names the cause, the stderr line it was read from, and a
+/// suggested fix.
 ///
 /// # Arguments
 ///
-/// * `operation` - Function that executes a Command and returns the exit status
+/// * `operation` - Function that spawns the command with stderr piped
 /// * `max_retries` - Maximum number of retry attempts (0 = no retries, just initial attempt)
 /// * `initial_delay` - Delay before first retry (doubles each subsequent retry)
 /// * `operation_name` - Name of the operation for logging (e.g., "pg_restore", "psql")
 ///
 /// # Returns
 ///
-/// Returns Ok(()) on success or the last error after all retries exhausted.
+/// Returns Ok(()) on success or the classified error of the last attempt.
 ///
 /// # Examples
 ///
 /// ```no_run
 /// # use anyhow::Result;
 /// # use std::time::Duration;
-/// # use std::process::Command;
+/// # use std::process::{Command, Stdio};
 /// # use database_replicator::utils::retry_subprocess_with_backoff;
 /// # async fn example() -> Result<()> {
 /// retry_subprocess_with_backoff(
 ///     || {
 ///         let mut cmd = Command::new("psql");
-///         cmd.arg("--version");
-///         cmd.status().map_err(anyhow::Error::from)
+///         cmd.arg("--version").stderr(Stdio::piped());
+///         cmd.spawn().map_err(anyhow::Error::from)
 ///     },
 ///     3,  // Try up to 3 times
 ///     Duration::from_secs(1),  // Start with 1s delay
@@ -283,62 +295,64 @@ pub async fn retry_subprocess_with_backoff<F>(
     operation_name: &str,
 ) -> Result<()>
 where
-    F: FnMut() -> Result<std::process::ExitStatus>,
+    F: FnMut() -> Result<std::process::Child>,
 {
     let mut delay = initial_delay;
-    let mut last_error = None;
-
-    for attempt in 0..=max_retries {
-        match operation() {
-            Ok(status) => {
-                if status.success() {
-                    return Ok(());
-                } else {
-                    // Non-zero exit code - check if it's a connection error
-                    // We can't easily capture stderr here, so we'll treat all non-zero
-                    // exit codes as potential connection errors for now
-                    let error = anyhow::anyhow!(
-                        "{} failed with exit code: {}",
-                        operation_name,
-                        status.code().unwrap_or(-1)
-                    );
-                    last_error = Some(error);
-
-                    if attempt < max_retries {
-                        tracing::warn!(
-                            "{} failed (attempt {}/{}), retrying in {:?}...",
-                            operation_name,
-                            attempt + 1,
-                            max_retries + 1,
-                            delay
-                        );
-                        tokio::time::sleep(delay).await;
-                        delay *= 2; // Exponential backoff
-                    }
-                }
-            }
-            Err(e) => {
-                last_error = Some(e);
-
-                if attempt < max_retries {
-                    tracing::warn!(
-                        "{} failed (attempt {}/{}): {}, retrying in {:?}...",
-                        operation_name,
-                        attempt + 1,
-                        max_retries + 1,
-                        last_error.as_ref().unwrap(),
-                        delay
-                    );
-                    tokio::time::sleep(delay).await;
-                    delay *= 2; // Exponential backoff
-                }
-            }
+    let mut attempt = 0;
+    loop {
+        let (status, stderr) = wait_echoing_stderr(operation()?)
+            .with_context(|| format!("Failed to wait for {}", operation_name))?;
+        if status.success() {
+            return Ok(());
+        }
+        let error = crate::failure::ToolError::from_stderr(&stderr);
+        if !error.class.is_transient() || attempt >= max_retries {
+            return Err(error.into_error(operation_name, status.code()));
         }
+        tracing::warn!(
+            "{} failed (attempt {}/{}, {}), retrying in {:?}...",
+            operation_name,
+            attempt + 1,
+            max_retries + 1,
+            error.class.describe(),
+            delay
+        );
+        tokio::time::sleep(delay).await;
+        delay *= 2; // Exponential backoff
+        attempt += 1;
     }
+}
 
-    Err(last_error.unwrap_or_else(|| {
-        anyhow::anyhow!("{} failed after {} retries", operation_name, max_retries)
-    }))
+/// Lines of a child's stderr kept for classifying its failure
+const STDERR_TAIL_LINES: usize = 50;
+
+/// Wait for a child, echoing its piped stderr, and return its last lines
+fn wait_echoing_stderr(
+    mut child: std::process::Child,
+) -> std::io::Result<(std::process::ExitStatus, String)> {
+    let reader = child.stderr.take().map(|stderr| {
+        std::thread::spawn(move || {
+            use std::io::BufRead;
+            let mut reader = std::io::BufReader::new(stderr);
+            let mut tail = std::collections::VecDeque::new();
+            let mut line = Vec::new();
+            while matches!(reader.read_until(b'\n', &mut line), Ok(n) if n > 0) {
+                let text = String::from_utf8_lossy(&line).trim_end().to_string();
+                eprintln!("{}", text);
+                if tail.len() == STDERR_TAIL_LINES {
+                    tail.pop_front();
+                }
+                tail.push_back(text);
+                line.clear();
+            }
+            Vec::from(tail).join("\n")
+        })
+    });
+    let status = child.wait()?;
+    let stderr = reader
+        .map(|reader| reader.join().unwrap_or_default())
+        .unwrap_or_default();
+    Ok((status, stderr))
 }
 
 /// Validate a PostgreSQL identifier (database name, schema name, etc.)
@@ -601,10 +615,18 @@ pub fn quote_mysql_ident(identifier: &str) -> String {
 /// ```
 pub fn validate_source_target_different(source_url: &str, target_url: &str) -> Result<()> {
     // Parse both URLs to extract components
-    let source_parts = parse_postgres_url(source_url)
-        .with_context(|| format!("Failed to parse source URL: {}", source_url))?;
-    let target_parts = parse_postgres_url(target_url)
-        .with_context(|| format!("Failed to parse target URL: {}", target_url))?;
+    let source_parts = parse_postgres_url(source_url).with_context(|| {
+        format!(
+            "Failed to parse source URL: {}",
+            crate::redact::redact(source_url)
+        )
+    })?;
+    let target_parts = parse_postgres_url(target_url).with_context(|| {
+        format!(
+            "Failed to parse target URL: {}",
+            crate::redact::redact(target_url)
+        )
+    })?;
 
     // Compare normalized components
     if source_parts.host == target_parts.host
@@ -685,10 +707,16 @@ pub fn parse_postgres_url(url: &str) -> Result<PostgresUrlParts> {
     // Use rsplit_once to split from the right, so passwords can contain '@'
     let (user, password, host_and_port) = if let Some((auth, hp)) = auth_and_host.rsplit_once('@') {
         // Has authentication
+        // Users are percent-decoded so IAM users such as `sa%40project.iam` work
+        let decode = |user: &str| {
+            percent_encoding::percent_decode_str(user)
+                .decode_utf8_lossy()
+                .into_owned()
+        };
         let (user, pass) = if let Some((u, p)) = auth.split_once(':') {
-            (Some(u.to_string()), Some(p.to_string()))
+            (Some(decode(u)), Some(p.to_string()))
         } else {
-            (Some(auth.to_string()), None)
+            (Some(decode(auth)), None)
         };
         (user, pass, hp)
     } else {
@@ -787,6 +815,11 @@ impl PostgresUrlParts {
     /// - `application_name` → `PGAPPNAME`
     /// - `client_encoding` → `PGCLIENTENCODING`
     ///
+    /// `PGSSLCOMPRESSION=1` is added when `--ssl-compression` was given.
+    /// It is only a hint: libpq negotiates compression with the server during
+    /// the TLS handshake, and most OpenSSL builds and servers since
+    /// PostgreSQL 14 decline it, in which case the connection is uncompressed.
+    ///
     /// # Returns
     ///
     /// Vec of (env_var_name, value) pairs to be set as environment variables
@@ -811,6 +844,10 @@ impl PostgresUrlParts {
             }
         }
 
+        if crate::postgres::connection::ssl_compression_requested() {
+            env_vars.push(("PGSSLCOMPRESSION", "1".to_string()));
+        }
+
         env_vars
     }
 }
@@ -878,8 +915,11 @@ impl PgPassFile {
 
         // Write .pgpass entry
         // Format: hostname:port:database:username:password
-        let username = parts.user.as_deref().unwrap_or("*");
-        let password = parts.password.as_deref().unwrap_or("");
+        // ':' and '\' must be escaped; RDS IAM tokens contain ':'
+        let escape = |field: &str| field.replace('\\', "\\\\").replace(':', "\\:");
+        let username = parts.user.as_deref().map(escape);
+        let username = username.as_deref().unwrap_or("*");
+        let password = escape(parts.password.as_deref().unwrap_or(""));
         let entry = format!(
             "{}:{}:{}:{}:{}\n",
             parts.host, parts.port, parts.database, username, password
@@ -959,10 +999,19 @@ impl Drop for PgPassFile {
 /// # }
 /// ```
 pub fn create_managed_temp_dir() -> Result<std::path::PathBuf> {
+    create_managed_temp_dir_in(&std::env::temp_dir())
+}
+
+/// Create a managed temporary directory inside `base` instead of the system temp directory
+///
+/// Used for `--work-dir`. Directories left in `base` by a killed process are
+/// not cleaned up on the next startup, which only looks in the system temp
+/// directory.
+pub fn create_managed_temp_dir_in(base: &std::path::Path) -> Result<std::path::PathBuf> {
     use std::fs;
     use std::time::SystemTime;
 
-    let system_temp = std::env::temp_dir();
+    let system_temp = base;
 
     // Generate timestamp for directory name
     let timestamp = SystemTime::now()
@@ -987,6 +1036,33 @@ pub fn create_managed_temp_dir() -> Result<std::path::PathBuf> {
     Ok(temp_path)
 }
 
+/// Bytes an unprivileged user can still write on the filesystem holding `path`
+///
+/// Returns `None` on platforms where this is not known.
+pub fn available_space(path: &std::path::Path) -> Result<Option<u64>> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+
+        let c_path = std::ffi::CString::new(path.as_os_str().as_bytes())
+            .with_context(|| format!("Invalid path: {}", path.display()))?;
+        let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+        if unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) } != 0 {
+            return Err(std::io::Error::last_os_error())
+                .with_context(|| format!("Failed to read free space of {}", path.display()));
+        }
+        // The field types differ between platforms
+        #[allow(clippy::useless_conversion)]
+        let bytes = u64::from(stat.f_bavail) * u64::from(stat.f_frsize);
+        Ok(Some(bytes))
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = path;
+        Ok(None)
+    }
+}
+
 /// Clean up stale temporary directories from previous runs
 ///
 /// Removes temporary directories created by `create_managed_temp_dir()` that are
@@ -1698,6 +1774,48 @@ mod tests {
         assert_eq!(attempts, 3); // Initial + 2 retries
     }
 
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_retry_subprocess_only_transient_errors() {
+        async fn run(stderr: &str) -> (Result<()>, u32) {
+            let mut attempts = 0;
+            let result = retry_subprocess_with_backoff(
+                || {
+                    attempts += 1;
+                    std::process::Command::new("sh")
+                        .arg("-c")
+                        .arg(format!("echo '{}' >&2; exit 1", stderr))
+                        .stderr(std::process::Stdio::piped())
+                        .spawn()
+                        .map_err(anyhow::Error::from)
+                },
+                2,
+                Duration::from_millis(10),
+                "pg_dump",
+            )
+            .await;
+            (result, attempts)
+        }
+
+        let (result, attempts) =
+            run("pg_dump: error: connection to server failed: Connection refused").await;
+        assert_eq!(attempts, 3);
+        let err = result.unwrap_err();
+        assert_eq!(
+            crate::failure::classify(&err),
+            crate::failure::FailureKind::Connection
+        );
+
+        let (result, attempts) = run(
+            "pg_dump: error: connection to server failed: FATAL:  password authentication failed for user \"app\"",
+        )
+        .await;
+        assert_eq!(attempts, 1);
+        let message = result.unwrap_err().to_string();
+        assert!(message.starts_with("pg_dump failed with exit code 1 (authentication failed)"));
+        assert!(message.contains("Suggestion:"));
+    }
+
     #[test]
     fn test_validate_source_target_different_valid() {
         // Different hosts
@@ -1902,6 +2020,25 @@ mod tests {
         assert_eq!(content, "localhost:5432:testdb:*:testpass\n");
     }
 
+    #[test]
+    fn test_pgpass_file_escapes_separators() {
+        let parts = PostgresUrlParts {
+            host: "db.rds.amazonaws.com".to_string(),
+            port: 5432,
+            database: "testdb".to_string(),
+            user: Some("testuser".to_string()),
+            password: Some(r"db.rds.amazonaws.com:5432/?Action=connect\x".to_string()),
+            query_params: std::collections::HashMap::new(),
+        };
+
+        let pgpass = PgPassFile::new(&parts).unwrap();
+        let content = std::fs::read_to_string(pgpass.path()).unwrap();
+        assert_eq!(
+            content,
+            "db.rds.amazonaws.com:5432:testdb:testuser:db.rds.amazonaws.com\\:5432/?Action=connect\\\\x\n"
+        );
+    }
+
     #[test]
     fn test_strip_password_from_url() {
         // With password
@@ -2,14 +2,35 @@
 // ABOUTME: Runs sync cycles at configurable intervals with reconciliation
 
 use anyhow::{Context, Result};
-use std::path::PathBuf;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
 use std::time::Duration;
-use tokio::time::interval;
+use tokio::sync::watch;
+use tokio::time::{interval, Interval};
 
+use crate::audit::{self, RunMethod, RunOutcome};
+use crate::events;
+use crate::migration::checksum::compute_table_checksum;
+use crate::postgres::pgvector::ensure_pgvector;
+use crate::postgres::pooler;
+use crate::postgres::postgis::{ensure_postgis, SpatialEncoding};
+use crate::postgres::ConnectionManager;
+use crate::replication::slot::SlotWatchdog;
+use crate::table_rules::{ColumnOverrides, TableSource, TargetNames, TimeFilterRule};
+use crate::transform::TableTransforms;
+use crate::utils::quote_ident;
+
+use super::progress::{ensure_progress_table, load_progress, source_identifier};
 use super::reader::{detect_wraparound, WraparoundCheck, XminReader};
 use super::reconciler::Reconciler;
-use super::state::SyncState;
-use super::writer::{get_primary_key_columns, get_table_columns, row_to_values, ChangeWriter};
+use super::state::{ensure_state_table, SyncState};
+use super::verifier::{pace, table_bytes, DriftTracker, Finding, TableCheck};
+use super::writer::{
+    column_conversions, get_generated_columns, get_primary_key_columns, get_table_columns,
+    row_to_values, ChangeWriter, ColumnConversion,
+};
 
 /// Configuration for the SyncDaemon.
 #[derive(Debug, Clone)]
@@ -21,12 +42,41 @@ pub struct DaemonConfig {
     pub reconcile_interval: Option<Duration>,
     /// Path to store sync state
     pub state_path: PathBuf,
+    /// Keep sync state in the target's `_replicator_sync_state` table instead
+    /// of `state_path`, so no local files survive between runs (see `sync --stateless`)
+    pub state_in_target: bool,
     /// Maximum rows to process per batch
     pub batch_size: usize,
-    /// Tables to sync (empty = all tables)
+    /// Tables to sync as `table` or `schema.table` (empty = all tables in `schemas`).
+    /// Unqualified names are looked up in the first schema.
     pub tables: Vec<String>,
-    /// Schema to sync from
-    pub schema: String,
+    /// Schemas to sync from
+    pub schemas: Vec<String>,
+    /// Write source values into `GENERATED ALWAYS AS IDENTITY` columns using
+    /// OVERRIDING SYSTEM VALUE. When false, non-key identity columns are left
+    /// for the target to generate.
+    pub overriding_system_value: bool,
+    /// Tables never synced, e.g. ones init loaded as a sample; named like `tables`
+    pub exclude_tables: Vec<String>,
+    /// While this file exists, no changes are applied: running cycles stop at
+    /// the next table and new cycles are skipped (see `sync --pause`)
+    pub pause_file: Option<PathBuf>,
+    /// Record runs and per-table watermarks in the target's `_replicator`
+    /// schema (see [`crate::audit`])
+    pub audit_schema: bool,
+    /// Filter to check again each cycle for tables that newly match it; their
+    /// schema is created on the target and they are copied in full before
+    /// syncing like the rest (see `sync --auto-add-tables`)
+    pub auto_add_tables: Option<crate::filters::ReplicationFilter>,
+    /// Interval between refreshes of each target database's materialized
+    /// views (see [`crate::migration::matviews`]); None disables them
+    pub matview_refresh_interval: Option<Duration>,
+    /// Bytes per second the background verifier reads from source and target
+    /// while comparing table checksums; None disables it (see `sync --verify-rate`)
+    pub verify_rate: Option<u64>,
+    /// Delete target rows that fall out of their table's time filter window
+    /// after each sync (see `sync --purge-aged-out`)
+    pub purge_aged_out: bool,
 }
 
 impl Default for DaemonConfig {
@@ -35,10 +85,261 @@ impl Default for DaemonConfig {
             sync_interval: Duration::from_secs(3600), // 1 hour
             reconcile_interval: Some(Duration::from_secs(86400)), // 1 day
             state_path: SyncState::default_path(),
+            state_in_target: false,
             batch_size: 10_000, // 10K rows per batch for good throughput while bounding memory
             tables: Vec::new(),
-            schema: "public".to_string(),
+            schemas: vec!["public".to_string()],
+            overriding_system_value: true,
+            exclude_tables: Vec::new(),
+            pause_file: None,
+            audit_schema: false,
+            auto_add_tables: None,
+            matview_refresh_interval: None,
+            verify_rate: None,
+            purge_aged_out: false,
+        }
+    }
+}
+
+/// One source/target database pair synced by a [`SyncDaemon`].
+#[derive(Debug, Clone)]
+pub struct SyncDatabase {
+    /// Source database URL
+    pub source_url: String,
+    /// Target database URL
+    pub target_url: String,
+    /// Tables to sync as `table` or `schema.table` (empty = all tables in `schemas`).
+    /// Unqualified names are looked up in the first schema.
+    pub tables: Vec<String>,
+    /// Schemas to sync from
+    pub schemas: Vec<String>,
+    /// Tables never synced; named like `tables`
+    pub exclude_tables: Vec<String>,
+    /// Column transforms applied as rows are read, keyed by `"schema"."table"`
+    /// (see [`crate::transform`])
+    pub transforms: BTreeMap<String, TableTransforms>,
+    /// Custom SELECT lists or views rows are read through, keyed like `transforms`
+    pub sources: BTreeMap<String, TableSource>,
+    /// Schema and name each table is written under on the target
+    pub target_names: TargetNames,
+    /// Target column names, types, and nullability, keyed like `transforms`
+    pub column_overrides: BTreeMap<String, ColumnOverrides>,
+    /// Time filters whose window rows must fall in to sync, keyed like `transforms`
+    pub time_windows: BTreeMap<String, TimeFilterRule>,
+    /// Retention of range-partitioned tables, keyed like `transforms`: target
+    /// partitions older than the window are dropped and their partition key is
+    /// the window's column (see [`super::retention`])
+    pub retention: BTreeMap<String, TimeFilterRule>,
+}
+
+impl SyncDatabase {
+    /// Split a `table` or `schema.table` entry into (schema, table).
+    pub fn qualify(&self, table: &str) -> (String, String) {
+        match table.split_once('.') {
+            Some((schema, table)) => (schema.to_string(), table.to_string()),
+            None => {
+                let schema = self.schemas.first().map_or("public", String::as_str);
+                (schema.to_string(), table.to_string())
+            }
+        }
+    }
+
+    /// Transforms for a table's columns, if it has any
+    pub fn transforms_for(&self, schema: &str, table: &str) -> Option<&TableTransforms> {
+        self.transforms
+            .get(&format!("{}.{}", quote_ident(schema), quote_ident(table)))
+    }
+
+    /// Custom source a table's rows are read through, if it has one
+    pub fn source_for(&self, schema: &str, table: &str) -> Option<&TableSource> {
+        self.sources
+            .get(&format!("{}.{}", quote_ident(schema), quote_ident(table)))
+    }
+
+    /// Overrides for a table's target columns, if it has any
+    pub fn column_overrides_for(&self, schema: &str, table: &str) -> Option<&ColumnOverrides> {
+        self.column_overrides
+            .get(&format!("{}.{}", quote_ident(schema), quote_ident(table)))
+    }
+
+    /// Time filter a table's rows are synced within, if it has one
+    pub fn time_window_for(&self, schema: &str, table: &str) -> Option<&TimeFilterRule> {
+        self.time_windows
+            .get(&format!("{}.{}", quote_ident(schema), quote_ident(table)))
+    }
+
+    /// Retention window of a partitioned table, if it has one
+    pub fn retention_for(&self, schema: &str, table: &str) -> Option<&TimeFilterRule> {
+        self.retention
+            .get(&format!("{}.{}", quote_ident(schema), quote_ident(table)))
+    }
+
+    /// Source database name, for logs and per-database state files
+    pub fn name(&self) -> String {
+        crate::utils::parse_postgres_url(&self.source_url)
+            .map(|parts| parts.database)
+            .unwrap_or_default()
+    }
+
+    /// Work out which databases, schemas, and tables a filter selects for sync
+    ///
+    /// Without database-level filters only the database in `source_url` is
+    /// synced into `target_url`. Otherwise every selected database is synced
+    /// into the same-named database on the target, as init creates them.
+    /// Databases with no schemas left after schema filters are skipped, and
+    /// sampled tables are excluded since syncing would fill them in.
+    pub async fn plan(
+        source_url: &str,
+        target_url: &str,
+        filter: &crate::filters::ReplicationFilter,
+    ) -> Result<Vec<SyncDatabase>> {
+        use crate::commands::sync::replace_database_in_url;
+
+        let source_parts = crate::utils::parse_postgres_url(source_url)?;
+        let selects_databases = filter.include_databases().is_some()
+            || filter.exclude_databases().is_some()
+            || filter.include_tables().is_some();
+        let database_names = if selects_databases {
+            let source_client = crate::postgres::connect(source_url)
+                .await
+                .context("Failed to connect to source database to list databases")?;
+            filter.get_databases_to_replicate(&source_client).await?
+        } else {
+            vec![source_parts.database.clone()]
+        };
+
+        let mut databases = Vec::new();
+        for db_name in &database_names {
+            let source_db_url = replace_database_in_url(source_url, db_name)?;
+            // init preserves database names, so each source database has a
+            // same-named target database
+            let target_db_url = if selects_databases {
+                replace_database_in_url(target_url, db_name)?
+            } else {
+                target_url.to_string()
+            };
+
+            // Filter stores "db.table" format, we need "table" or "schema.table"
+            let tables = filter.tables_for_database(db_name).unwrap_or_default();
+
+            // Every user schema the schema filters keep, not just public
+            let source_client = crate::postgres::connect(&source_db_url)
+                .await
+                .with_context(|| format!("Failed to connect to source database '{}'", db_name))?;
+            let schemas = filter.schemas_to_replicate(&source_client, db_name).await?;
+            let mut retention = BTreeMap::new();
+            for (schema, table, interval) in filter.retention_tables(db_name) {
+                let window =
+                    super::retention::window(&source_client, &schema, &table, &interval).await?;
+                tracing::info!(
+                    "Keeping partitions of {}.{} in '{}' on the target for {} (by {})",
+                    schema,
+                    table,
+                    db_name,
+                    window.interval,
+                    window.column
+                );
+                retention.insert(
+                    format!("{}.{}", quote_ident(&schema), quote_ident(&table)),
+                    window,
+                );
+            }
+            drop(source_client);
+            if schemas.is_empty() {
+                tracing::warn!(
+                    "Skipping database '{}': no schemas match --include-schemas/--exclude-schemas",
+                    db_name
+                );
+                continue;
+            }
+
+            // Sampled tables only hold a snapshot subset; syncing would fill them in
+            let sampled_tables: Vec<String> = schemas
+                .iter()
+                .flat_map(|schema| {
+                    filter
+                        .table_rules()
+                        .sampled_table_names(db_name, schema)
+                        .into_iter()
+                        .map(move |table| format!("{}.{}", schema, table))
+                })
+                .collect();
+            if !sampled_tables.is_empty() {
+                tracing::info!(
+                    "Skipping sampled table(s) in '{}': {}",
+                    db_name,
+                    sampled_tables.join(", ")
+                );
+            }
+
+            let transforms = filter.transformed_tables(db_name);
+            for (table, columns) in &transforms {
+                tracing::info!("Transforming {} in '{}': {}", table, db_name, columns);
+            }
+            let sources = filter.source_tables(db_name);
+            for (table, source) in &sources {
+                tracing::info!("Reading {} in '{}' through {}", table, db_name, source);
+            }
+            let time_windows = filter.time_filter_tables(db_name);
+            for (table, window) in &time_windows {
+                tracing::info!(
+                    "Syncing {} in '{}' where {} is within the last {}",
+                    table,
+                    db_name,
+                    window.column,
+                    window.interval
+                );
+            }
+
+            databases.push(SyncDatabase {
+                source_url: source_db_url,
+                target_url: target_db_url,
+                tables,
+                schemas,
+                exclude_tables: sampled_tables,
+                transforms,
+                sources,
+                target_names: filter.target_names(db_name),
+                column_overrides: filter.column_override_tables(db_name),
+                time_windows,
+                retention,
+            });
+        }
+        if databases.is_empty() {
+            anyhow::bail!("No source databases left to sync after applying filters");
+        }
+
+        Ok(databases)
+    }
+
+    /// Narrow a [`plan`](Self::plan) to the `UNLOGGED` tables `filter` selects,
+    /// which logical replication cannot publish
+    ///
+    /// Databases without any are dropped.
+    pub async fn unlogged_only(
+        databases: Vec<SyncDatabase>,
+        filter: &crate::filters::ReplicationFilter,
+    ) -> Result<Vec<SyncDatabase>> {
+        let mut narrowed = Vec::new();
+        for mut database in databases {
+            let db_name = database.name();
+            let source_client = crate::postgres::connect(&database.source_url)
+                .await
+                .with_context(|| format!("Failed to connect to source database '{}'", db_name))?;
+            let tables: Vec<String> =
+                crate::migration::selected_unlogged_tables(&source_client, &db_name, filter)
+                    .await?
+                    .into_iter()
+                    .filter(|(schema, _)| database.schemas.contains(schema))
+                    .map(|(schema, table)| format!("{}.{}", schema, table))
+                    .collect();
+            if tables.is_empty() {
+                continue;
+            }
+            database.tables = tables;
+            narrowed.push(database);
         }
+        Ok(narrowed)
     }
 }
 
@@ -59,6 +360,13 @@ impl SyncStats {
     }
 }
 
+/// How often a running daemon syncs and reconciles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Schedule {
+    sync_interval: Duration,
+    reconcile_interval: Option<Duration>,
+}
+
 /// SyncDaemon orchestrates continuous xmin-based replication.
 ///
 /// It runs periodic sync cycles that:
@@ -66,116 +374,531 @@ impl SyncStats {
 /// 2. Apply changes to target using upsert
 /// 3. Periodically run reconciliation to detect deletes
 /// 4. Persist sync state for resume capability
+///
+/// One daemon can serve several databases. Each cycle visits them in turn with
+/// their own reader/writer connections, so a failing database does not stop
+/// the others.
+///
+/// A running daemon can [`reload`](Self::reload) its databases and intervals;
+/// cycles in progress finish with the settings they started with.
 pub struct SyncDaemon {
     config: DaemonConfig,
-    source_url: String,
-    target_url: String,
+    databases: RwLock<Arc<Vec<SyncDatabase>>>,
+    /// Intervals [`run`](Self::run) ticks at, replaced by [`reload`](Self::reload)
+    schedule: watch::Sender<Schedule>,
+    /// Set once shutdown was requested during a cycle, which then stops
+    /// before its next table
+    stopping: AtomicBool,
+    /// `_replicator.runs` id per database name, when `audit_schema` is set
+    audit_runs: Mutex<HashMap<String, i64>>,
+    /// Warns when an inactive replication slot keeps WAL growing on a source
+    slot_watchdog: SlotWatchdog,
+    /// Sync cycles run so far, which tell the verifier whether a mismatch has
+    /// had a chance to sync
+    cycles_completed: AtomicU64,
+    /// Verifier results per database name, waiting to be saved with its sync state
+    verify_checks: Mutex<HashMap<String, Vec<TableCheck>>>,
 }
 
 impl SyncDaemon {
-    /// Create a new SyncDaemon with the given configuration.
+    /// Create a new SyncDaemon for one database pair, using the config's table settings.
     pub fn new(source_url: String, target_url: String, config: DaemonConfig) -> Self {
-        Self {
-            config,
+        let database = SyncDatabase {
             source_url,
             target_url,
+            tables: config.tables.clone(),
+            schemas: config.schemas.clone(),
+            exclude_tables: config.exclude_tables.clone(),
+            transforms: BTreeMap::new(),
+            sources: BTreeMap::new(),
+            target_names: TargetNames::default(),
+            column_overrides: BTreeMap::new(),
+            time_windows: BTreeMap::new(),
+            retention: BTreeMap::new(),
+        };
+        Self::with_databases(vec![database], config)
+    }
+
+    /// Create a SyncDaemon for several database pairs.
+    ///
+    /// `tables`, `schemas`, and `exclude_tables` in the config are ignored in
+    /// favour of each database's own settings. With more than one database,
+    /// each keeps its sync state next to `state_path` with the database name
+    /// added (e.g. `xmin-sync-state.orders.json`).
+    pub fn with_databases(databases: Vec<SyncDatabase>, config: DaemonConfig) -> Self {
+        let schedule = Schedule {
+            sync_interval: config.sync_interval,
+            reconcile_interval: config.reconcile_interval,
+        };
+        Self {
+            config,
+            databases: RwLock::new(Arc::new(databases)),
+            schedule: watch::channel(schedule).0,
+            stopping: AtomicBool::new(false),
+            audit_runs: Mutex::new(HashMap::new()),
+            slot_watchdog: SlotWatchdog::new(),
+            cycles_completed: AtomicU64::new(0),
+            verify_checks: Mutex::new(HashMap::new()),
         }
     }
 
-    /// Run a single sync cycle for all configured tables.
+    /// Databases this daemon syncs.
+    pub fn databases(&self) -> Arc<Vec<SyncDatabase>> {
+        self.databases.read().unwrap().clone()
+    }
+
+    /// Replace the databases and intervals of a running daemon, e.g. after its
+    /// config file changed.
     ///
-    /// This is the main entry point for synchronization. It:
+    /// New table rules apply from the next cycle. When the intervals change
+    /// the next sync cycle starts at once and reconciliation waits a full new
+    /// interval.
+    pub fn reload(
+        &self,
+        databases: Vec<SyncDatabase>,
+        sync_interval: Duration,
+        reconcile_interval: Option<Duration>,
+    ) {
+        let tables: usize = databases.iter().map(|db| db.tables.len()).sum();
+        tracing::info!(
+            "Reloaded {} database(s) ({} listed table(s)), sync_interval={:?}, reconcile_interval={:?}",
+            databases.len(),
+            tables,
+            sync_interval,
+            reconcile_interval
+        );
+        *self.databases.write().unwrap() = Arc::new(databases);
+        self.schedule.send_if_modified(|schedule| {
+            let reloaded = Schedule {
+                sync_interval,
+                reconcile_interval,
+            };
+            let changed = *schedule != reloaded;
+            *schedule = reloaded;
+            changed
+        });
+    }
+
+    /// Whether shutdown was requested while a cycle was running.
+    fn is_stopping(&self) -> bool {
+        self.stopping.load(Ordering::Relaxed)
+    }
+
+    /// Run a single sync cycle for all configured databases and tables.
+    ///
+    /// This is the main entry point for synchronization. For each database it:
     /// 1. Loads or creates sync state
     /// 2. Connects to source and target databases
     /// 3. Syncs each table
     /// 4. Saves updated state
+    ///
+    /// A database that cannot be synced at all (e.g. connection failure) is
+    /// recorded in the stats; the cycle only fails if every database did.
     pub async fn run_sync_cycle(&self) -> Result<SyncStats> {
         let start = std::time::Instant::now();
         let mut stats = SyncStats::default();
+        let mut failures = Vec::new();
+
+        let databases = self.databases();
+        for database in databases.iter() {
+            if self.is_stopping() {
+                break;
+            }
+            if let Err(e) = self.sync_database(database, &mut stats).await {
+                tracing::error!("Failed to sync database '{}': {:?}", database.name(), e);
+                events::emit(|sink| sink.on_error(&database.name(), None, &e.to_string()));
+                stats.errors.push(format!(
+                    "Failed to sync database '{}': {}",
+                    database.name(),
+                    e
+                ));
+                failures.push(e);
+            }
+        }
+        self.cycles_completed.fetch_add(1, Ordering::Relaxed);
+
+        if !failures.is_empty() && failures.len() == databases.len() {
+            return Err(Self::combine_failures(failures, &stats.errors));
+        }
+
+        stats.duration_ms = start.elapsed().as_millis() as u64;
+        events::emit(|sink| sink.on_cycle_complete(&stats));
+        Ok(stats)
+    }
+
+    /// Sync every table of one database pair into `stats`.
+    async fn sync_database(&self, database: &SyncDatabase, stats: &mut SyncStats) -> Result<()> {
+        // Connect to databases; dropped connections are re-established as
+        // tables and batches are synced
+        let source = ConnectionManager::connect(&database.source_url, "source").await?;
+        let target = ConnectionManager::connect(&database.target_url, "target").await?;
+        let source_client = source.client();
+        let target_client = target.client();
+
+        // Applied positions are committed alongside the data in the target
+        ensure_progress_table(&target_client).await?;
+        let source_id = source_identifier(&database.source_url)?;
 
         // Load or create sync state
-        let mut state = self.load_or_create_state().await?;
+        let state_path = self.state_path(database);
+        let mut state = if self.config.state_in_target {
+            ensure_state_table(&target_client).await?;
+            Self::load_or_create_target_state(database, &target_client, &source_id).await?
+        } else {
+            Self::load_or_create_state(database, &state_path).await?
+        };
+        let checks = self
+            .verify_checks
+            .lock()
+            .unwrap()
+            .remove(&database.name())
+            .unwrap_or_default();
+        for check in checks {
+            state
+                .get_or_create_table(&check.schema, &check.table)
+                .record_verification(check.checked_at, check.drift);
+        }
 
-        // Connect to databases
-        let source_client = crate::postgres::connect_with_retry(&self.source_url)
-            .await
-            .context("Failed to connect to source database")?;
-        let target_client = crate::postgres::connect_with_retry(&self.target_url)
+        if let Err(e) = self
+            .slot_watchdog
+            .check(&database.source_url, &source_client)
             .await
-            .context("Failed to connect to target database")?;
+        {
+            tracing::debug!("Could not check replication slots on source: {:#}", e);
+        }
 
-        let reader = XminReader::new(&source_client);
-        let writer = ChangeWriter::new(&target_client);
+        // A source fed by this target would send its own rows back
+        crate::topology::check_hop(
+            &source_client,
+            &database.source_url,
+            &target_client,
+            &database.target_url,
+        )
+        .await?;
 
-        // Get tables to sync
-        let tables = if self.config.tables.is_empty() {
-            reader.list_tables(&self.config.schema).await?
+        // Geometry and embedding columns need their extensions on the target
+        // before any row can be applied
+        ensure_postgis(&source_client, &target_client).await?;
+        ensure_pgvector(&source_client, &target_client).await?;
+
+        let audit_run = if self.config.audit_schema {
+            Some(self.audit_run(&target_client, database, &source_id).await?)
         } else {
-            self.config.tables.clone()
+            None
         };
 
+        // Tables that newly match the filter join this cycle
+        let refreshed;
+        let database = match &self.config.auto_add_tables {
+            Some(filter) => match Self::refresh_tables(database, filter, &source_client).await {
+                Ok(database) => {
+                    refreshed = database;
+                    &refreshed
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        "Could not check '{}' for new tables: {:#}",
+                        database.name(),
+                        e
+                    );
+                    database
+                }
+            },
+            None => database,
+        };
+
+        // Get tables to sync
+        let tables = Self::tables_to_sync(&XminReader::new(&source_client), database).await?;
+        if self.config.auto_add_tables.is_some() {
+            if let Err(e) = Self::create_new_tables(database, &tables, &target_client).await {
+                tracing::warn!(
+                    "Could not create new tables on the target for '{}': {:#}",
+                    database.name(),
+                    e
+                );
+            }
+        }
+
         // Sync each table
-        for table in &tables {
-            match self
-                .sync_table(&reader, &writer, &mut state, &self.config.schema, table)
+        let database_name = database.name();
+        let mut rows_applied = 0u64;
+        let mut first_error = None;
+        for (schema, table) in &tables {
+            if self.is_paused() {
+                tracing::info!("Sync paused; stopping cycle before {}.{}", schema, table);
+                break;
+            }
+            if self.is_stopping() {
+                tracing::info!("Shutting down; {}.{} syncs on the next run", schema, table);
+                break;
+            }
+            let qualified = format!("{}.{}", schema, table);
+            events::emit(|sink| sink.on_table_start(&database_name, &qualified));
+            // Changes committed after this point may not be picked up this cycle
+            let data_as_of = chrono::Utc::now();
+            let result = async {
+                source.ensure_connected().await?;
+                target.ensure_connected().await?;
+                self.sync_table(
+                    &source,
+                    &target,
+                    &mut state,
+                    &source_id,
+                    &database_name,
+                    schema,
+                    table,
+                    database.transforms_for(schema, table),
+                    database.source_for(schema, table),
+                    &database.target_names,
+                    database.column_overrides_for(schema, table),
+                    // Rows older than the retention window would land in dropped partitions
+                    database
+                        .time_window_for(schema, table)
+                        .or(database.retention_for(schema, table)),
+                )
                 .await
-            {
+            }
+            .await;
+            match result {
                 Ok(rows) => {
                     stats.tables_synced += 1;
                     stats.rows_synced += rows;
+                    rows_applied += rows;
+                    if let Some(window) = database
+                        .time_window_for(schema, table)
+                        .filter(|_| self.config.purge_aged_out)
+                    {
+                        match purge_aged_out(&target, &database.target_names, schema, table, window)
+                            .await
+                        {
+                            Ok(purged) => stats.rows_deleted += purged,
+                            Err(e) => {
+                                tracing::warn!("{:#}", e);
+                                stats.errors.push(format!("{:#}", e));
+                            }
+                        }
+                    }
+                    if let Some(window) = database.retention_for(schema, table) {
+                        let (target_schema, target_table) =
+                            database.target_names.resolve(schema, table);
+                        let dropped = super::retention::drop_expired_partitions(
+                            &target.client(),
+                            &target_schema,
+                            &target_table,
+                            window,
+                        )
+                        .await;
+                        match dropped {
+                            Ok(dropped) if !dropped.is_empty() => tracing::info!(
+                                "Dropped {} partition(s) of {}.{} older than {}: {}",
+                                dropped.len(),
+                                target_schema,
+                                target_table,
+                                window.interval,
+                                dropped.join(", ")
+                            ),
+                            Ok(_) => {}
+                            Err(e) => {
+                                tracing::warn!("{:#}", e);
+                                stats.errors.push(format!("{:#}", e));
+                            }
+                        }
+                    }
+                    if let Some(run_id) = audit_run {
+                        let xmin = state.get_or_create_table(schema, table).last_xmin;
+                        if let Err(e) = audit::record_watermark(
+                            &target.client(),
+                            run_id,
+                            &source_id,
+                            schema,
+                            table,
+                            RunMethod::Xmin,
+                            Some(&xmin.to_string()),
+                            data_as_of,
+                        )
+                        .await
+                        {
+                            tracing::warn!("{:#}", e);
+                        }
+                    }
                 }
                 Err(e) => {
                     // Log with :? to show full error chain including root cause
-                    tracing::error!("Failed to sync {}.{}: {:?}", self.config.schema, table, e);
-                    let error_msg =
-                        format!("Failed to sync {}.{}: {}", self.config.schema, table, e);
+                    tracing::error!("Failed to sync {}.{}: {:?}", schema, table, e);
+                    let error_msg = format!("Failed to sync {}.{}: {}", schema, table, e);
+                    events::emit(|sink| {
+                        sink.on_error(&database_name, Some(&qualified), &e.to_string())
+                    });
+                    first_error.get_or_insert_with(|| error_msg.clone());
                     stats.errors.push(error_msg);
                 }
             }
         }
 
+        if let Some(run_id) = audit_run {
+            if let Err(e) = audit::record_cycle(
+                &target.client(),
+                run_id,
+                rows_applied,
+                first_error.as_deref(),
+            )
+            .await
+            {
+                tracing::warn!("{:#}", e);
+            }
+        }
+
+        // A clean cycle stamps the hop, so `topology` can tell how recent it was
+        if first_error.is_none() {
+            if let Err(e) = crate::topology::record_hop(
+                &source.client(),
+                &database.source_url,
+                &target.client(),
+                &database.target_url,
+                crate::topology::HopMethod::Xmin,
+            )
+            .await
+            {
+                tracing::warn!("{:#}", e);
+            }
+        }
+
         // Save state
-        state.save(&self.config.state_path).await?;
+        if self.config.state_in_target {
+            target.ensure_connected().await?;
+            state.save_to_target(&target.client(), &source_id).await?;
+        } else {
+            state.save(&state_path).await?;
+        }
+        Ok(())
+    }
 
-        stats.duration_ms = start.elapsed().as_millis() as u64;
-        Ok(stats)
+    /// This daemon's `_replicator.runs` row for a database, created on first use.
+    async fn audit_run(
+        &self,
+        target_client: &tokio_postgres::Client,
+        database: &SyncDatabase,
+        source_id: &str,
+    ) -> Result<i64> {
+        if let Some(run_id) = self.audit_runs.lock().unwrap().get(&database.name()) {
+            return Ok(*run_id);
+        }
+        audit::ensure_audit_schema(target_client).await?;
+        let run_id = audit::start_run(
+            target_client,
+            "sync",
+            RunMethod::Xmin,
+            source_id,
+            chrono::Utc::now(),
+        )
+        .await?;
+        self.audit_runs
+            .lock()
+            .unwrap()
+            .insert(database.name(), run_id);
+        Ok(run_id)
+    }
+
+    /// Close the `_replicator.runs` rows this daemon opened.
+    ///
+    /// [`run`](Self::run) does this on shutdown; callers that drive cycles
+    /// themselves (e.g. `sync --once`) call it when they are done.
+    pub async fn finish_audit_runs(&self, outcome: &RunOutcome) {
+        let runs: Vec<(String, i64)> = self.audit_runs.lock().unwrap().drain().collect();
+        for (name, run_id) in runs {
+            let databases = self.databases();
+            let Some(database) = databases.iter().find(|db| db.name() == name) else {
+                continue;
+            };
+            let result = async {
+                let client = crate::postgres::connect_with_retry(&database.target_url).await?;
+                audit::finish_run(&client, run_id, outcome).await
+            }
+            .await;
+            if let Err(e) = result {
+                tracing::warn!("Failed to finish audit run for '{}': {:#}", name, e);
+            }
+        }
     }
 
     /// Run reconciliation to detect and delete orphaned rows.
     pub async fn run_reconciliation(&self) -> Result<SyncStats> {
         let start = std::time::Instant::now();
         let mut stats = SyncStats::default();
+        let mut failures = Vec::new();
 
+        let databases = self.databases();
+        for database in databases.iter() {
+            if self.is_stopping() {
+                break;
+            }
+            if let Err(e) = self.reconcile_database(database, &mut stats).await {
+                tracing::error!(
+                    "Failed to reconcile database '{}': {:?}",
+                    database.name(),
+                    e
+                );
+                events::emit(|sink| sink.on_error(&database.name(), None, &e.to_string()));
+                stats.errors.push(format!(
+                    "Failed to reconcile database '{}': {}",
+                    database.name(),
+                    e
+                ));
+                failures.push(e);
+            }
+        }
+
+        if !failures.is_empty() && failures.len() == databases.len() {
+            return Err(Self::combine_failures(failures, &stats.errors));
+        }
+
+        stats.duration_ms = start.elapsed().as_millis() as u64;
+        events::emit(|sink| sink.on_cycle_complete(&stats));
+        Ok(stats)
+    }
+
+    /// Reconcile every table of one database pair into `stats`.
+    async fn reconcile_database(
+        &self,
+        database: &SyncDatabase,
+        stats: &mut SyncStats,
+    ) -> Result<()> {
         // Connect to databases
-        let source_client = crate::postgres::connect_with_retry(&self.source_url)
+        let source_client = crate::postgres::connect_with_retry(&database.source_url)
             .await
             .context("Failed to connect to source database")?;
-        let target_client = crate::postgres::connect_with_retry(&self.target_url)
+        let target_client = crate::postgres::connect_with_retry(&database.target_url)
             .await
             .context("Failed to connect to target database")?;
 
-        let reconciler = Reconciler::new(&source_client, &target_client);
+        let reconciler = Reconciler::new(&source_client, &target_client)
+            .with_target_names(database.target_names.clone());
         let reader = XminReader::new(&source_client);
 
         // Get tables to reconcile
-        let tables = if self.config.tables.is_empty() {
-            reader.list_tables(&self.config.schema).await?
-        } else {
-            self.config.tables.clone()
-        };
-
+        let tables = Self::tables_to_sync(&reader, database).await?;
+        Self::check_transformed_keys(&reader, database, &tables).await?;
         // Reconcile each table
-        for table in &tables {
+        for (schema, table) in &tables {
+            if self.is_paused() {
+                tracing::info!(
+                    "Sync paused; stopping reconciliation before {}.{}",
+                    schema,
+                    table
+                );
+                break;
+            }
+            if self.is_stopping() {
+                break;
+            }
+
             // Check if table exists in target before reconciliation
-            match reconciler
-                .table_exists_in_target(&self.config.schema, table)
-                .await
-            {
+            match reconciler.table_exists_in_target(schema, table).await {
                 Ok(true) => {}
                 Ok(false) => {
                     tracing::warn!(
                         "Skipping reconciliation for {}.{}: table does not exist in target",
-                        self.config.schema,
+                        schema,
                         table
                     );
                     continue;
@@ -183,7 +906,7 @@ impl SyncDaemon {
                 Err(e) => {
                     tracing::warn!(
                         "Skipping reconciliation for {}.{}: failed to check table existence: {}",
-                        self.config.schema,
+                        schema,
                         table,
                         e
                     );
@@ -192,23 +915,18 @@ impl SyncDaemon {
             }
 
             // Get primary key columns
-            let pk_columns = reader.get_primary_key(&self.config.schema, table).await?;
+            let pk_columns = reader.get_primary_key(schema, table).await?;
             if pk_columns.is_empty() {
                 tracing::warn!(
                     "Skipping reconciliation for {}.{}: no primary key",
-                    self.config.schema,
+                    schema,
                     table
                 );
                 continue;
             }
 
             match reconciler
-                .reconcile_table_batched(
-                    &self.config.schema,
-                    table,
-                    &pk_columns,
-                    self.config.batch_size,
-                )
+                .reconcile_table_batched(schema, table, &pk_columns, self.config.batch_size)
                 .await
             {
                 Ok(deleted) => {
@@ -216,35 +934,115 @@ impl SyncDaemon {
                     stats.rows_deleted += deleted;
                 }
                 Err(e) => {
-                    let error_msg = format!(
-                        "Failed to reconcile {}.{}: {}",
-                        self.config.schema, table, e
-                    );
+                    let error_msg = format!("Failed to reconcile {}.{}: {}", schema, table, e);
                     tracing::error!("{}", error_msg);
+                    events::emit(|sink| {
+                        sink.on_error(
+                            &database.name(),
+                            Some(&format!("{}.{}", schema, table)),
+                            &e.to_string(),
+                        )
+                    });
                     stats.errors.push(error_msg);
                 }
             }
         }
 
-        stats.duration_ms = start.elapsed().as_millis() as u64;
-        Ok(stats)
+        Ok(())
+    }
+
+    /// A single database's error as-is, or all of them when several failed.
+    fn combine_failures(mut failures: Vec<anyhow::Error>, messages: &[String]) -> anyhow::Error {
+        if failures.len() == 1 {
+            failures.remove(0)
+        } else {
+            anyhow::anyhow!(
+                "All {} databases failed:\n{}",
+                failures.len(),
+                messages.join("\n")
+            )
+        }
+    }
+
+    /// Whether an operator has paused sync with the configured pause file.
+    pub fn is_paused(&self) -> bool {
+        self.config
+            .pause_file
+            .as_ref()
+            .is_some_and(|path| path.exists())
+    }
+
+    /// Log pause transitions and return whether this tick should be skipped.
+    fn skip_while_paused(&self, was_paused: &mut bool) -> bool {
+        let paused = self.is_paused();
+        if paused && !*was_paused {
+            tracing::info!("Sync paused; skipping cycles until resumed");
+        } else if !paused && *was_paused {
+            tracing::info!("Sync resumed");
+        }
+        *was_paused = paused;
+        paused
+    }
+
+    /// State file for a database; the configured path when there is only one.
+    fn state_path(&self, database: &SyncDatabase) -> PathBuf {
+        let path = &self.config.state_path;
+        if self.databases.read().unwrap().len() == 1 {
+            return path.clone();
+        }
+        SyncState::database_path(path, &database.name())
     }
 
     /// Run the daemon continuously until stopped.
     ///
     /// This starts the main loop that runs sync cycles at the configured interval.
-    /// Reconciliation runs at its own interval if configured.
+    /// Reconciliation runs at its own interval if configured, and the
+    /// background verifier alongside both when `verify_rate` is set.
     pub async fn run(&self, mut shutdown: tokio::sync::broadcast::Receiver<()>) -> Result<()> {
-        let mut sync_interval = interval(self.config.sync_interval);
-        let mut reconcile_interval = self.config.reconcile_interval.map(|d| interval(d));
+        if self.schedule.borrow().reconcile_interval.is_some() {
+            for database in self.databases().iter() {
+                let source_client = crate::postgres::connect_with_retry(&database.source_url)
+                    .await
+                    .context("Failed to connect to source database")?;
+                let reader = XminReader::new(&source_client);
+                let tables = Self::tables_to_sync(&reader, database).await?;
+                Self::check_transformed_keys(&reader, database, &tables).await?;
+            }
+        }
+
+        let verifier_shutdown = shutdown.resubscribe();
+        tokio::join!(
+            self.run_cycles(&mut shutdown),
+            self.run_verifier(verifier_shutdown)
+        );
+
+        self.finish_audit_runs(&RunOutcome::Stopped).await;
+        Ok(())
+    }
+
+    /// Run sync, reconciliation, and materialized view refresh cycles until shutdown.
+    ///
+    /// Shutdown during a sync or reconciliation cycle lets the table in
+    /// progress finish and saves sync state before returning.
+    async fn run_cycles(&self, shutdown: &mut tokio::sync::broadcast::Receiver<()>) {
+        let mut schedule = self.schedule.subscribe();
+        let current = *schedule.borrow_and_update();
+        let mut sync_interval = interval(current.sync_interval);
+        let mut reconcile_interval = current.reconcile_interval.map(|d| interval(d));
+        // The first tick would fire at once, before any rows are synced
+        let mut matview_interval = self
+            .config
+            .matview_refresh_interval
+            .map(Self::delayed_interval);
 
         let mut cycles = 0u64;
         let mut reconcile_cycles = 0u64;
+        let mut was_paused = false;
 
         tracing::info!(
             "Starting SyncDaemon with sync_interval={:?}, reconcile_interval={:?}",
-            self.config.sync_interval,
-            self.config.reconcile_interval
+            current.sync_interval,
+            current.reconcile_interval
         );
 
         loop {
@@ -255,36 +1053,49 @@ impl SyncDaemon {
                     tracing::info!("Shutdown signal received, stopping SyncDaemon");
                     break;
                 }
+                Ok(()) = schedule.changed() => {
+                    let reloaded = *schedule.borrow_and_update();
+                    sync_interval = interval(reloaded.sync_interval);
+                    reconcile_interval = reloaded.reconcile_interval.map(Self::delayed_interval);
+                }
                 _ = sync_interval.tick() => {
+                    if self.skip_while_paused(&mut was_paused) {
+                        continue;
+                    }
                     cycles += 1;
                     tracing::info!("Starting sync cycle {}", cycles);
 
-                    // Run sync cycle with shutdown check - abort if shutdown received
-                    tokio::select! {
-                        biased;
+                    let cycle = self.run_sync_cycle();
+                    tokio::pin!(cycle);
+                    let result = tokio::select! {
+                        result = &mut cycle => result,
                         _ = shutdown.recv() => {
-                            tracing::info!("Shutdown signal received during sync cycle, aborting");
-                            break;
+                            tracing::info!(
+                                "Shutdown signal received during sync cycle; finishing the table in progress"
+                            );
+                            self.stopping.store(true, Ordering::Relaxed);
+                            cycle.await
                         }
-                        result = self.run_sync_cycle() => {
-                            match result {
-                                Ok(stats) => {
-                                    tracing::info!(
-                                        "Sync cycle {} completed: {} tables, {} rows in {}ms",
-                                        cycles,
-                                        stats.tables_synced,
-                                        stats.rows_synced,
-                                        stats.duration_ms
-                                    );
-                                    if !stats.errors.is_empty() {
-                                        tracing::warn!("Sync cycle had {} errors", stats.errors.len());
-                                    }
-                                }
-                                Err(e) => {
-                                    tracing::error!("Sync cycle {} failed: {}", cycles, e);
-                                }
+                    };
+                    match result {
+                        Ok(stats) => {
+                            tracing::info!(
+                                "Sync cycle {} completed: {} tables, {} rows in {}ms",
+                                cycles,
+                                stats.tables_synced,
+                                stats.rows_synced,
+                                stats.duration_ms
+                            );
+                            if !stats.errors.is_empty() {
+                                tracing::warn!("Sync cycle had {} errors", stats.errors.len());
                             }
                         }
+                        Err(e) => {
+                            tracing::error!("Sync cycle {} failed: {}", cycles, e);
+                        }
+                    }
+                    if self.is_stopping() {
+                        break;
                     }
                 }
                 _ = async {
@@ -294,70 +1105,519 @@ impl SyncDaemon {
                         std::future::pending::<tokio::time::Instant>().await
                     }
                 } => {
+                    if self.skip_while_paused(&mut was_paused) {
+                        continue;
+                    }
                     reconcile_cycles += 1;
                     tracing::info!("Starting reconciliation cycle {}", reconcile_cycles);
 
-                    // Run reconciliation with shutdown check
+                    let cycle = self.run_reconciliation();
+                    tokio::pin!(cycle);
+                    let result = tokio::select! {
+                        result = &mut cycle => result,
+                        _ = shutdown.recv() => {
+                            tracing::info!(
+                                "Shutdown signal received during reconciliation; finishing the table in progress"
+                            );
+                            self.stopping.store(true, Ordering::Relaxed);
+                            cycle.await
+                        }
+                    };
+                    match result {
+                        Ok(stats) => {
+                            tracing::info!(
+                                "Reconciliation cycle {} completed: {} tables, {} rows deleted in {}ms",
+                                reconcile_cycles,
+                                stats.tables_synced,
+                                stats.rows_deleted,
+                                stats.duration_ms
+                            );
+                        }
+                        Err(e) => {
+                            tracing::error!("Reconciliation cycle {} failed: {}", reconcile_cycles, e);
+                        }
+                    }
+                    if self.is_stopping() {
+                        break;
+                    }
+                }
+                _ = async {
+                    if let Some(ref mut interval) = matview_interval {
+                        interval.tick().await
+                    } else {
+                        std::future::pending::<tokio::time::Instant>().await
+                    }
+                } => {
+                    if self.skip_while_paused(&mut was_paused) {
+                        continue;
+                    }
                     tokio::select! {
                         biased;
                         _ = shutdown.recv() => {
-                            tracing::info!("Shutdown signal received during reconciliation, aborting");
+                            tracing::info!("Shutdown signal received during materialized view refresh, aborting");
                             break;
                         }
-                        result = self.run_reconciliation() => {
-                            match result {
-                                Ok(stats) => {
-                                    tracing::info!(
-                                        "Reconciliation cycle {} completed: {} tables, {} rows deleted in {}ms",
-                                        reconcile_cycles,
-                                        stats.tables_synced,
-                                        stats.rows_deleted,
-                                        stats.duration_ms
-                                    );
-                                }
-                                Err(e) => {
-                                    tracing::error!("Reconciliation cycle {} failed: {}", reconcile_cycles, e);
-                                }
+                        _ = async {
+                            for database in self.databases().iter() {
+                                crate::migration::matviews::refresh_database(&database.target_url).await;
                             }
-                        }
+                        } => {}
                     }
                 }
             }
         }
+    }
+
+    /// An interval whose first tick is a full period away.
+    fn delayed_interval(period: Duration) -> Interval {
+        let mut ticker = interval(period);
+        ticker.reset();
+        ticker
+    }
+
+    /// Walk every table comparing checksums at `verify_rate` until shutdown.
+    ///
+    /// Each table is checked in one query per side, followed by a pause long
+    /// enough to hold reads to the configured rate on average; passes over all
+    /// databases are at least a sync interval apart. A table that still
+    /// differs after a sync cycle has run since its first mismatch has drifted:
+    /// orphaned target rows are deleted and the next cycle copies it in full.
+    async fn run_verifier(&self, mut shutdown: tokio::sync::broadcast::Receiver<()>) {
+        let Some(rate) = self.config.verify_rate else {
+            return;
+        };
+        tracing::info!(
+            "Background verification reading up to {}/s",
+            crate::migration::format_bytes(rate as i64)
+        );
+
+        let mut tracker = DriftTracker::default();
+        let mut passes = 0u64;
+        loop {
+            let pass_started = tokio::time::Instant::now();
+            let pass = async {
+                for database in self.databases().iter() {
+                    if let Err(e) = self.verify_database(database, rate, &mut tracker).await {
+                        tracing::warn!(
+                            "⚠ Could not verify database '{}': {:#}",
+                            database.name(),
+                            e
+                        );
+                    }
+                }
+            };
+            tokio::select! {
+                biased;
+                _ = shutdown.recv() => break,
+                _ = pass => {}
+            }
+            passes += 1;
+            tracing::debug!("Verification pass {} finished", passes);
+
+            tokio::select! {
+                biased;
+                _ = shutdown.recv() => break,
+                _ = tokio::time::sleep_until(pass_started + self.schedule.borrow().sync_interval) => {}
+            }
+        }
+    }
+
+    /// Compare each table of one database pair, repairing the ones that drifted.
+    async fn verify_database(
+        &self,
+        database: &SyncDatabase,
+        rate: u64,
+        tracker: &mut DriftTracker,
+    ) -> Result<()> {
+        let source_client = crate::postgres::connect_with_retry(&database.source_url)
+            .await
+            .context("Failed to connect to source database")?;
+        let target_client = crate::postgres::connect_with_retry(&database.target_url)
+            .await
+            .context("Failed to connect to target database")?;
+        let reader = XminReader::new(&source_client);
+        let reconciler = Reconciler::new(&source_client, &target_client)
+            .with_target_names(database.target_names.clone());
+        let database_name = database.name();
+
+        let tables = Self::tables_to_sync(&reader, database).await?;
+        for (schema, table) in &tables {
+            if self.is_paused() {
+                return Ok(());
+            }
+            // Rewritten rows, and rows outside a time window, differ from the
+            // source by design
+            if database.transforms_for(schema, table).is_some()
+                || database.source_for(schema, table).is_some()
+                || database.time_window_for(schema, table).is_some()
+                || database.retention_for(schema, table).is_some()
+            {
+                continue;
+            }
+            if !reconciler
+                .table_exists_in_target(schema, table)
+                .await
+                .unwrap_or(false)
+            {
+                continue;
+            }
+
+            let started = std::time::Instant::now();
+            let bytes = table_bytes(&source_client, schema, table).await?;
+            let cycles_completed = self.cycles_completed.load(Ordering::Relaxed);
+            let (target_schema, target_table) = database.target_names.resolve(schema, table);
+            let (source, target) = tokio::try_join!(
+                compute_table_checksum(&source_client, schema, table),
+                compute_table_checksum(&target_client, &target_schema, &target_table)
+            )?;
+            let key = format!("{}/{}.{}", database_name, schema, table);
+            let finding = tracker.observe(&key, source == target, cycles_completed);
+
+            match finding {
+                Finding::Match => {
+                    tracing::debug!("Verified {}.{}: {} rows match", schema, table, source.1);
+                }
+                Finding::Suspect => {
+                    tracing::info!(
+                        "{}.{} differs from the source ({} vs {} rows); checking again after the next sync cycle",
+                        schema,
+                        table,
+                        source.1,
+                        target.1
+                    );
+                }
+                Finding::Drift => {
+                    let message =
+                        format!(
+                        "Verification found drift: {} source rows, {} target rows, checksums {}",
+                        source.1,
+                        target.1,
+                        if source.0 == target.0 { "match" } else { "differ" }
+                    );
+                    tracing::warn!("⚠ {}.{}: {}", schema, table, message);
+                    events::emit(|sink| {
+                        sink.on_error(
+                            &database_name,
+                            Some(&format!("{}.{}", schema, table)),
+                            &message,
+                        )
+                    });
+                    self.repair_drift(&reader, &reconciler, schema, table)
+                        .await
+                        .with_context(|| format!("Failed to repair {}.{}", schema, table))?;
+                }
+            }
+            if finding != Finding::Suspect {
+                self.verify_checks
+                    .lock()
+                    .unwrap()
+                    .entry(database_name.clone())
+                    .or_default()
+                    .push(TableCheck {
+                        schema: schema.clone(),
+                        table: table.clone(),
+                        checked_at: chrono::Utc::now(),
+                        drift: finding == Finding::Drift,
+                    });
+            }
+
+            tokio::time::sleep(pace(rate, bytes, started.elapsed())).await;
+        }
+        Ok(())
+    }
+
+    /// Delete target rows missing from the source; the next cycle copies the rest.
+    async fn repair_drift(
+        &self,
+        reader: &XminReader<'_>,
+        reconciler: &Reconciler<'_>,
+        schema: &str,
+        table: &str,
+    ) -> Result<()> {
+        let pk_columns = reader.get_primary_key(schema, table).await?;
+        if pk_columns.is_empty() {
+            anyhow::bail!("Table {}.{} has no primary key", schema, table);
+        }
+        let deleted = reconciler
+            .reconcile_table_batched(schema, table, &pk_columns, self.config.batch_size)
+            .await?;
+        tracing::info!(
+            "Deleted {} orphaned row(s) from {}.{}; the next sync cycle copies it in full",
+            deleted,
+            schema,
+            table
+        );
+        Ok(())
+    }
+
+    /// A database's tables and schemas as the filter selects them now
+    ///
+    /// Name patterns are resolved again, so tables created since the last
+    /// cycle that match them are included, as [`SyncDatabase::plan`] would.
+    async fn refresh_tables(
+        database: &SyncDatabase,
+        filter: &crate::filters::ReplicationFilter,
+        source_client: &tokio_postgres::Client,
+    ) -> Result<SyncDatabase> {
+        let db_name = database.name();
+        let filter = filter.resolve_patterns(&database.source_url).await?;
+        Ok(SyncDatabase {
+            tables: filter.tables_for_database(&db_name).unwrap_or_default(),
+            schemas: filter.schemas_to_replicate(source_client, &db_name).await?,
+            ..database.clone()
+        })
+    }
+
+    /// Create tables on the target that are about to be synced but do not exist there
+    ///
+    /// Their first sync then starts from xmin 0 and copies them in full.
+    async fn create_new_tables(
+        database: &SyncDatabase,
+        tables: &[(String, String)],
+        target_client: &tokio_postgres::Client,
+    ) -> Result<()> {
+        let existing: Vec<(String, String)> = crate::migration::list_tables(target_client)
+            .await?
+            .into_iter()
+            .map(|table| (table.schema, table.name))
+            .collect();
+        let missing_tables: Vec<(String, String)> = tables
+            .iter()
+            .filter(|(schema, table)| {
+                !existing.contains(&database.target_names.resolve(schema, table))
+            })
+            .cloned()
+            .collect();
+        let missing: Vec<String> = missing_tables
+            .iter()
+            .map(|(schema, table)| format!("{}.{}", schema, table))
+            .collect();
+        if missing.is_empty() {
+            return Ok(());
+        }
+        tracing::info!(
+            "Adding {} new table(s) in '{}': {}",
+            missing.len(),
+            database.name(),
+            missing.join(", ")
+        );
+        crate::commands::sync::create_target_tables(
+            &database.source_url,
+            &database.target_url,
+            &database.name(),
+            &missing,
+        )
+        .await?;
 
+        // They are created with their source names and types, like init restores them
+        let column_overrides: BTreeMap<String, ColumnOverrides> = missing_tables
+            .iter()
+            .filter_map(|(schema, table)| {
+                database
+                    .column_overrides_for(schema, table)
+                    .map(|overrides| {
+                        (
+                            format!("{}.{}", quote_ident(schema), quote_ident(table)),
+                            overrides.clone(),
+                        )
+                    })
+            })
+            .collect();
+        crate::migration::columns::apply(target_client, &column_overrides).await?;
+        let renames = crate::migration::rename::plan(&database.target_names, &missing_tables)?;
+        crate::migration::rename::apply(target_client, &renames).await?;
         Ok(())
     }
 
+    /// Resolve a database's table list to (schema, table) pairs, minus excluded tables.
+    /// Fail if any of `tables` has transformed primary key columns.
+    ///
+    /// Reconciliation looks up target keys on the source, and a transformed
+    /// key is never found there, so deletes of these tables could not be
+    /// detected.
+    async fn check_transformed_keys(
+        reader: &XminReader<'_>,
+        database: &SyncDatabase,
+        tables: &[(String, String)],
+    ) -> Result<()> {
+        for (schema, table) in tables {
+            let Some(transforms) = database.transforms_for(schema, table) else {
+                continue;
+            };
+            let pk_columns = reader.get_primary_key(schema, table).await?;
+            if transforms.touches(&pk_columns) {
+                anyhow::bail!(
+                    "Primary key columns of {}.{} ({}) are transformed, so xmin sync \
+                     cannot detect deletes from it. Remove the transforms on those \
+                     columns, or run sync with --no-reconcile.",
+                    schema,
+                    table,
+                    pk_columns.join(", ")
+                );
+            }
+        }
+        Ok(())
+    }
+
+    async fn tables_to_sync(
+        reader: &XminReader<'_>,
+        database: &SyncDatabase,
+    ) -> Result<Vec<(String, String)>> {
+        let tables = if database.tables.is_empty() {
+            let mut tables = Vec::new();
+            for schema in &database.schemas {
+                for table in reader.list_tables(schema).await? {
+                    tables.push((schema.clone(), table));
+                }
+            }
+            tables
+        } else {
+            database
+                .tables
+                .iter()
+                .map(|table| database.qualify(table))
+                .collect()
+        };
+        let mut excluded: Vec<(String, String)> = database
+            .exclude_tables
+            .iter()
+            .map(|table| database.qualify(table))
+            .collect();
+        // A retained table is synced through its parent, so rows route to the
+        // target's remaining partitions and none are synced into dropped ones
+        for (schema, table) in &tables {
+            if database.retention_for(schema, table).is_some() {
+                excluded
+                    .extend(super::retention::partitions(reader.client(), schema, table).await?);
+            }
+        }
+        Ok(tables
+            .into_iter()
+            .filter(|table| !excluded.contains(table))
+            .collect())
+    }
+
     /// Sync a single table using batched processing.
     ///
     /// This method processes rows in batches to avoid loading entire tables into memory.
     /// This is critical for large tables (millions of rows) where loading everything
     /// at once would cause OOM or connection timeouts.
+    #[allow(clippy::too_many_arguments)]
     async fn sync_table(
         &self,
-        reader: &XminReader<'_>,
-        writer: &ChangeWriter<'_>,
+        source: &ConnectionManager,
+        target: &ConnectionManager,
         state: &mut SyncState,
+        source_id: &str,
+        database_name: &str,
         schema: &str,
         table: &str,
+        transforms: Option<&TableTransforms>,
+        table_source: Option<&TableSource>,
+        target_names: &TargetNames,
+        column_overrides: Option<&ColumnOverrides>,
+        time_window: Option<&TimeFilterRule>,
     ) -> Result<u64> {
+        let started = std::time::Instant::now();
+        let (target_schema, target_table) = target_names.resolve(schema, table);
+
         // Get table state
         let table_state = state.get_or_create_table(schema, table);
-        let stored_xmin = table_state.last_xmin;
+        let mut stored_xmin = table_state.last_xmin;
+        let resync = table_state.resync_pending;
+
+        // The target's progress row commits with the data, so it wins over the local
+        // state file (which may be stale after a crash or missing on another host)
+        if let Some(xmin) =
+            load_progress(&target.client(), source_id, &target_schema, &target_table)
+                .await?
+                .and_then(|p| p.xmin())
+        {
+            if xmin != stored_xmin {
+                tracing::info!(
+                    "Resuming {}.{} from target progress xmin {} (local state had {})",
+                    schema,
+                    table,
+                    xmin,
+                    stored_xmin
+                );
+                stored_xmin = xmin;
+            }
+        }
 
         // Get table metadata from SOURCE (not target - tables may not exist there yet)
-        let columns = get_table_columns(reader.client(), schema, table).await?;
-        let pk_columns = get_primary_key_columns(reader.client(), schema, table).await?;
+        let source_client = source.client();
+        let columns = get_table_columns(&source_client, schema, table).await?;
+        let pk_columns = get_primary_key_columns(&source_client, schema, table).await?;
 
         if pk_columns.is_empty() {
             anyhow::bail!("Table {}.{} has no primary key", schema, table);
         }
 
+        // Generated columns are checked on the TARGET, since that is where inserts
+        // happen; they are named by their source columns here
+        let target_column = |column: &str| target_names.resolve_column(schema, table, column);
+        let source_column = |target: &String| {
+            column_overrides
+                .and_then(|overrides| {
+                    overrides
+                        .iter()
+                        .find(|(_, o)| o.rename_to.as_ref() == Some(target))
+                        .map(|(column, _)| column.clone())
+                })
+                .unwrap_or_else(|| target.clone())
+        };
+        let mut generated =
+            get_generated_columns(&target.client(), &target_schema, &target_table).await?;
+        generated.stored = generated.stored.iter().map(source_column).collect();
+        generated.identity_always = generated
+            .identity_always
+            .iter()
+            .map(source_column)
+            .collect();
+        let columns = generated
+            .writable_columns(&columns, &pk_columns, self.config.overriding_system_value)
+            .with_context(|| format!("Cannot sync {}.{}", schema, table))?;
+        let identity_columns: Vec<String> = generated
+            .overridden_identity_columns(&columns)
+            .iter()
+            .map(|column| target_column(column))
+            .collect();
+
+        // Values keep their source types, so overridden columns are cast on insert
+        let mut conversions: Vec<(String, ColumnConversion)> = column_conversions(&columns)
+            .into_iter()
+            .map(|(column, conversion)| (target_column(&column), conversion))
+            .collect();
+        for (column, udt) in &columns {
+            let target_type = column_overrides
+                .and_then(|overrides| overrides.get(column))
+                .and_then(|o| o.target_type.clone());
+            let name = target_column(column);
+            if let Some(to) = target_type {
+                if !conversions.iter().any(|(converted, _)| *converted == name) {
+                    let from = udt.clone();
+                    conversions.push((name, ColumnConversion::Cast { from, to }));
+                }
+            }
+        }
+
         let column_names: Vec<String> = columns.iter().map(|(name, _)| name.clone()).collect();
+        let target_column_names: Vec<String> =
+            column_names.iter().map(|c| target_column(c)).collect();
+        let target_pk_columns: Vec<String> = pk_columns.iter().map(|c| target_column(c)).collect();
 
         // Check for xmin wraparound before starting
+        let reader = XminReader::new(&source_client);
         let current_xmin = reader.get_current_xmin().await?;
-        let (since_xmin, is_full_sync) = if detect_wraparound(stored_xmin, current_xmin)
+        let (since_xmin, is_full_sync) = if resync {
+            tracing::warn!(
+                "Verification found {}.{} out of sync - performing full table sync",
+                schema,
+                table
+            );
+            (0, true)
+        } else if detect_wraparound(stored_xmin, current_xmin)
             == WraparoundCheck::WraparoundDetected
         {
             tracing::warn!(
@@ -374,14 +1634,41 @@ impl SyncDaemon {
         let batch_size = self.config.batch_size;
         let mut batch_reader = reader
             .read_changes_batched(schema, table, &column_names, since_xmin, batch_size)
-            .await?;
+            .await?
+            .with_column_types(&columns, SpatialEncoding::Ewkb);
+        if let Some(table_source) = table_source {
+            batch_reader = batch_reader.with_source(table_source, &pk_columns, &columns);
+        }
+        if let Some(transforms) = transforms {
+            batch_reader = batch_reader.with_transforms(transforms, &columns)?;
+        }
+        if let Some(window) = time_window {
+            batch_reader = batch_reader.with_predicate(window.predicate());
+        }
 
         let mut total_rows = 0u64;
         let mut max_xmin = since_xmin;
         let mut batch_count = 0u64;
 
         // Process batches until exhausted
-        while let Some((rows, batch_max_xmin)) = reader.fetch_batch(&mut batch_reader).await? {
+        // A batch that fails because a connection dropped is replayed on a new
+        // one: the reader only advances after a successful fetch, and the writer
+        // applies each batch and its progress row in a single transaction
+        loop {
+            let mut replays = 0;
+            let fetched = loop {
+                let client = source.client();
+                match XminReader::new(&client)
+                    .fetch_batch(&mut batch_reader)
+                    .await
+                {
+                    Ok(fetched) => break fetched,
+                    Err(e) => source.recover(e, &mut replays).await?,
+                }
+            };
+            let Some((rows, batch_max_xmin)) = fetched else {
+                break;
+            };
             if rows.is_empty() {
                 break;
             }
@@ -410,20 +1697,51 @@ impl SyncDaemon {
             }
 
             // Convert and apply batch immediately (memory = O(batch_size))
-            let values: Vec<Vec<Box<dyn tokio_postgres::types::ToSql + Sync + Send>>> = rows
-                .iter()
-                .map(|row| row_to_values(row, &columns))
-                .collect();
-
-            let affected = writer
-                .apply_batch(schema, table, &pk_columns, &column_names, values)
-                .await?;
+            let mut replays = 0;
+            let affected = loop {
+                let values: Vec<Vec<Box<dyn tokio_postgres::types::ToSql + Sync + Send>>> = rows
+                    .iter()
+                    .map(|row| row_to_values(row, &columns))
+                    .collect();
+                let value_types = rows
+                    .first()
+                    .map(|row| {
+                        row.columns()[..columns.len()]
+                            .iter()
+                            .map(|c| c.type_().clone())
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                let client = target.client();
+                let writer = ChangeWriter::new(&client)
+                    .with_identity_columns(identity_columns.clone())
+                    .with_column_conversions(conversions.clone())
+                    .with_value_types(value_types);
+                match writer
+                    .apply_batch_with_progress(
+                        &target_schema,
+                        &target_table,
+                        &target_pk_columns,
+                        &target_column_names,
+                        values,
+                        source_id,
+                        &batch_max_xmin.to_string(),
+                    )
+                    .await
+                {
+                    Ok(affected) => break affected,
+                    Err(e) => target.recover(e, &mut replays).await?,
+                }
+            };
 
             total_rows += affected;
             max_xmin = batch_max_xmin;
 
             // Update state after each batch for resume capability
             state.update_table(schema, table, max_xmin, affected);
+            events::emit(|sink| {
+                sink.on_rows_copied(database_name, &format!("{}.{}", schema, table), affected)
+            });
 
             // Log progress every 10 batches or 100K rows
             if batch_count.is_multiple_of(10) || total_rows % 100_000 < batch_len as u64 {
@@ -457,24 +1775,24 @@ impl SyncDaemon {
             );
         }
 
+        let table_state = state.get_or_create_table(schema, table);
+        table_state.record_cycle(total_rows, started.elapsed());
+        table_state.resync_pending = false;
         Ok(total_rows)
     }
 
     /// Load existing state or create new state.
-    async fn load_or_create_state(&self) -> Result<SyncState> {
-        if self.config.state_path.exists() {
-            match SyncState::load(&self.config.state_path).await {
+    async fn load_or_create_state(database: &SyncDatabase, state_path: &Path) -> Result<SyncState> {
+        if state_path.exists() {
+            match SyncState::load(state_path).await {
                 Ok(state) => {
-                    tracing::info!(
-                        "Loaded existing sync state from {:?}",
-                        self.config.state_path
-                    );
+                    tracing::info!("Loaded existing sync state from {:?}", state_path);
                     return Ok(state);
                 }
                 Err(e) => {
                     tracing::warn!(
                         "Failed to load sync state from {:?}: {}. Creating new state.",
-                        self.config.state_path,
+                        state_path,
                         e
                     );
                 }
@@ -482,10 +1800,67 @@ impl SyncDaemon {
         }
 
         tracing::info!("Creating new sync state");
-        Ok(SyncState::new(&self.source_url, &self.target_url))
+        Ok(SyncState::new(&database.source_url, &database.target_url))
+    }
+
+    /// Load the state stored in the target for a database, or create new state.
+    async fn load_or_create_target_state(
+        database: &SyncDatabase,
+        target_client: &tokio_postgres::Client,
+        source_id: &str,
+    ) -> Result<SyncState> {
+        if let Some(state) = SyncState::load_from_target(target_client, source_id).await? {
+            tracing::info!("Loaded existing sync state for {} from target", source_id);
+            return Ok(state);
+        }
+        tracing::info!("Creating new sync state in target");
+        Ok(SyncState::new(&database.source_url, &database.target_url))
     }
 }
 
+/// Delete target rows of a time-filtered table that are now older than its window
+///
+/// Keeps targets that hold e.g. "the last 90 days" from growing without bound.
+/// The time column is looked up under its target name.
+async fn purge_aged_out(
+    target: &ConnectionManager,
+    target_names: &TargetNames,
+    schema: &str,
+    table: &str,
+    window: &TimeFilterRule,
+) -> Result<u64> {
+    let (target_schema, target_table) = target_names.resolve(schema, table);
+    let column = target_names.resolve_column(schema, table, &window.column);
+    target.ensure_connected().await?;
+    let purged = pooler::execute(
+        &target.client(),
+        &format!(
+            "DELETE FROM {}.{} WHERE {}",
+            quote_ident(&target_schema),
+            quote_ident(&target_table),
+            window.aged_out_predicate(&column)
+        ),
+        &[],
+    )
+    .await
+    .with_context(|| {
+        format!(
+            "Failed to purge rows older than {} from {}.{}",
+            window.interval, target_schema, target_table
+        )
+    })?;
+    if purged > 0 {
+        tracing::info!(
+            "Purged {} row(s) older than {} from {}.{}",
+            purged,
+            window.interval,
+            target_schema,
+            target_table
+        );
+    }
+    Ok(purged)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -496,7 +1871,131 @@ mod tests {
         assert_eq!(config.sync_interval, Duration::from_secs(3600));
         assert_eq!(config.reconcile_interval, Some(Duration::from_secs(86400)));
         assert_eq!(config.batch_size, 10_000);
-        assert_eq!(config.schema, "public");
+        assert_eq!(config.schemas, vec!["public".to_string()]);
+    }
+
+    #[test]
+    fn test_pause_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let pause_file = dir.path().join("sync.paused");
+        let config = DaemonConfig {
+            pause_file: Some(pause_file.clone()),
+            ..Default::default()
+        };
+        let daemon = SyncDaemon::new(
+            "postgresql://localhost/app".to_string(),
+            "postgresql://target/app".to_string(),
+            config,
+        );
+        let mut was_paused = false;
+
+        assert!(!daemon.is_paused());
+        assert!(!daemon.skip_while_paused(&mut was_paused));
+
+        std::fs::write(&pause_file, "").unwrap();
+        assert!(daemon.is_paused());
+        assert!(daemon.skip_while_paused(&mut was_paused));
+        assert!(was_paused);
+
+        std::fs::remove_file(&pause_file).unwrap();
+        assert!(!daemon.skip_while_paused(&mut was_paused));
+        assert!(!was_paused);
+
+        // Without a pause file configured, sync never pauses
+        let daemon = SyncDaemon::new(
+            "postgresql://localhost/app".to_string(),
+            "postgresql://target/app".to_string(),
+            DaemonConfig::default(),
+        );
+        assert!(!daemon.is_paused());
+    }
+
+    #[test]
+    fn test_sync_database_qualify() {
+        let database = SyncDatabase {
+            source_url: "postgresql://localhost/app".to_string(),
+            target_url: "postgresql://target/app".to_string(),
+            tables: Vec::new(),
+            schemas: vec!["sales".to_string(), "public".to_string()],
+            exclude_tables: Vec::new(),
+            transforms: BTreeMap::new(),
+            sources: BTreeMap::new(),
+            target_names: TargetNames::default(),
+            column_overrides: BTreeMap::new(),
+            time_windows: BTreeMap::new(),
+            retention: BTreeMap::new(),
+        };
+        assert_eq!(database.name(), "app");
+        assert_eq!(
+            database.qualify("orders"),
+            ("sales".to_string(), "orders".to_string())
+        );
+        assert_eq!(
+            database.qualify("public.users"),
+            ("public".to_string(), "users".to_string())
+        );
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_check_transformed_keys_rejects_primary_key_transforms() {
+        use crate::transform::{ColumnTransform, Transform};
+
+        let source_url = std::env::var("TEST_SOURCE_URL").unwrap();
+        let client = crate::postgres::connect(&source_url).await.unwrap();
+        client
+            .batch_execute(
+                "CREATE SCHEMA IF NOT EXISTS xmin_keys_test;
+                 DROP TABLE IF EXISTS xmin_keys_test.users;
+                 CREATE TABLE xmin_keys_test.users (email text PRIMARY KEY, name text);",
+            )
+            .await
+            .unwrap();
+
+        let database = |column: &str| {
+            let mut transforms = TableTransforms::default();
+            transforms
+                .insert(
+                    column.to_string(),
+                    ColumnTransform {
+                        transform: Transform::FakeEmail,
+                        seed: 0,
+                    },
+                )
+                .unwrap();
+            SyncDatabase {
+                source_url: source_url.clone(),
+                target_url: source_url.clone(),
+                tables: Vec::new(),
+                schemas: vec!["xmin_keys_test".to_string()],
+                exclude_tables: Vec::new(),
+                transforms: BTreeMap::from([(
+                    "\"xmin_keys_test\".\"users\"".to_string(),
+                    transforms,
+                )]),
+                sources: BTreeMap::new(),
+                target_names: TargetNames::default(),
+                column_overrides: BTreeMap::new(),
+                time_windows: BTreeMap::new(),
+                retention: BTreeMap::new(),
+            }
+        };
+        let reader = XminReader::new(&client);
+        let tables = vec![("xmin_keys_test".to_string(), "users".to_string())];
+
+        SyncDaemon::check_transformed_keys(&reader, &database("name"), &tables)
+            .await
+            .unwrap();
+        let err = SyncDaemon::check_transformed_keys(&reader, &database("email"), &tables)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("xmin_keys_test.users (email)"));
+        assert!(err.to_string().contains("--no-reconcile"));
+
+        client
+            .batch_execute("DROP SCHEMA xmin_keys_test CASCADE")
+            .await
+            .unwrap();
     }
 
     #[test]
@@ -0,0 +1,209 @@
+// ABOUTME: Dead-letter quarantine for rows that cannot be applied to the target
+// ABOUTME: Stores failed rows as JSONB with the error in _replicator_dead_letters
+
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use tokio_postgres::types::{ToSql, Type};
+use tokio_postgres::Client;
+
+use crate::postgres::pooler::{self, Param};
+use crate::utils::{quote_ident, quote_literal};
+
+/// Name of the dead-letter table created in the target's `public` schema
+pub const DEAD_LETTER_TABLE: &str = "_replicator_dead_letters";
+
+/// Create the dead-letter table in the target if it does not exist
+pub async fn ensure_dead_letter_table(client: &Client) -> Result<()> {
+    client
+        .batch_execute(&format!(
+            "CREATE TABLE IF NOT EXISTS public.\"{}\" (
+                id BIGSERIAL PRIMARY KEY,
+                schema_name TEXT NOT NULL,
+                table_name TEXT NOT NULL,
+                row_data JSONB,
+                error TEXT NOT NULL,
+                failed_at TIMESTAMPTZ NOT NULL DEFAULT now()
+            )",
+            DEAD_LETTER_TABLE
+        ))
+        .await
+        .context("Failed to create _replicator_dead_letters table in target")?;
+    Ok(())
+}
+
+/// Column/value pairs per `jsonb_build_object` call, which takes at most 100
+/// arguments
+const MAX_PAIRS_PER_OBJECT: usize = 50;
+
+/// Build the INSERT used to quarantine a row
+///
+/// Each value is cast to the target column's type so PostgreSQL can infer the
+/// parameter types, then folded into a single JSONB object:
+/// ```sql
+/// INSERT INTO public."_replicator_dead_letters" (schema_name, table_name, row_data, error)
+/// VALUES ($1, $2, jsonb_build_object('id', $3::integer, 'name', $4::text), $5)
+/// ```
+/// Wider rows are split across several objects joined with `||`.
+fn build_quarantine_query(columns: &[String], column_types: &HashMap<String, String>) -> String {
+    let pairs: Vec<String> = columns
+        .iter()
+        .enumerate()
+        .map(|(idx, col)| {
+            let cast = column_types
+                .get(col)
+                .map(|t| format!("::{}", t))
+                .unwrap_or_default();
+            format!("{}, ${}{}", quote_literal(col), idx + 3, cast)
+        })
+        .collect();
+
+    let objects: Vec<String> = if pairs.is_empty() {
+        vec!["jsonb_build_object()".to_string()]
+    } else {
+        pairs
+            .chunks(MAX_PAIRS_PER_OBJECT)
+            .map(|chunk| format!("jsonb_build_object({})", chunk.join(", ")))
+            .collect()
+    };
+
+    format!(
+        "INSERT INTO public.\"{}\" (schema_name, table_name, row_data, error) \
+         VALUES ($1, $2, {}, ${})",
+        DEAD_LETTER_TABLE,
+        objects.join(" || "),
+        columns.len() + 3
+    )
+}
+
+/// Get column types of a target table as `format_type` strings
+async fn get_target_column_types(
+    client: &Client,
+    schema: &str,
+    table: &str,
+) -> Result<HashMap<String, String>> {
+    let qualified = format!("{}.{}", quote_ident(schema), quote_ident(table));
+    let rows = pooler::query(
+        client,
+        "SELECT a.attname, format_type(a.atttypid, a.atttypmod)
+         FROM pg_attribute a
+         WHERE a.attrelid = $1::text::regclass AND a.attnum > 0 AND NOT a.attisdropped",
+        &[(&qualified, Type::TEXT)],
+    )
+    .await
+    .with_context(|| format!("Failed to get column types for {}.{}", schema, table))?;
+    Ok(rows.iter().map(|row| (row.get(0), row.get(1))).collect())
+}
+
+/// Quarantine a row that could not be applied
+///
+/// Must be called inside the apply transaction. The row is stored as JSONB when
+/// its values can be encoded against the target's column types; otherwise only
+/// the error is recorded so the failure is never silently dropped. `value_types`
+/// are the types the values are sent as (see
+/// [`ChangeWriter::with_value_types`](super::ChangeWriter::with_value_types)).
+pub async fn quarantine_row(
+    client: &Client,
+    schema: &str,
+    table: &str,
+    all_columns: &[String],
+    values: &[Box<dyn ToSql + Sync + Send>],
+    value_types: &[Type],
+    error: &str,
+) -> Result<()> {
+    ensure_dead_letter_table(client).await?;
+
+    client
+        .batch_execute("SAVEPOINT replicator_dead_letter")
+        .await
+        .context("Failed to create dead-letter savepoint")?;
+
+    let with_data = async {
+        let column_types = get_target_column_types(client, schema, table).await?;
+        let query = build_quarantine_query(all_columns, &column_types);
+        let mut params: Vec<Param> = vec![(&schema, Type::TEXT), (&table, Type::TEXT)];
+        params.extend(values.iter().enumerate().map(|(idx, value)| {
+            let ty = value_types.get(idx).cloned().unwrap_or(Type::UNKNOWN);
+            (value.as_ref() as &(dyn ToSql + Sync), ty)
+        }));
+        params.push((&error, Type::TEXT));
+        pooler::execute(client, &query, &params).await?;
+        Ok::<(), anyhow::Error>(())
+    }
+    .await;
+
+    if let Err(e) = with_data {
+        tracing::debug!(
+            "Could not encode dead-letter row for {}.{}: {:?}",
+            schema,
+            table,
+            e
+        );
+        client
+            .batch_execute("ROLLBACK TO SAVEPOINT replicator_dead_letter")
+            .await
+            .context("Failed to roll back dead-letter savepoint")?;
+        pooler::execute(
+            client,
+            &format!(
+                "INSERT INTO public.\"{}\" (schema_name, table_name, row_data, error) \
+                 VALUES ($1, $2, NULL, $3)",
+                DEAD_LETTER_TABLE
+            ),
+            &[
+                (&schema, Type::TEXT),
+                (&table, Type::TEXT),
+                (&error, Type::TEXT),
+            ],
+        )
+        .await
+        .with_context(|| format!("Failed to quarantine row for {}.{}", schema, table))?;
+    }
+
+    client
+        .batch_execute("RELEASE SAVEPOINT replicator_dead_letter")
+        .await
+        .context("Failed to release dead-letter savepoint")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_quarantine_query() {
+        let columns = vec!["id".to_string(), "name".to_string(), "extra".to_string()];
+        let types = HashMap::from([
+            ("id".to_string(), "integer".to_string()),
+            ("name".to_string(), "character varying(50)".to_string()),
+        ]);
+        let query = build_quarantine_query(&columns, &types);
+        assert_eq!(
+            query,
+            "INSERT INTO public.\"_replicator_dead_letters\" (schema_name, table_name, row_data, error) \
+             VALUES ($1, $2, jsonb_build_object('id', $3::integer, 'name', $4::character varying(50), 'extra', $5), $6)"
+        );
+    }
+
+    #[test]
+    fn test_build_quarantine_query_splits_wide_rows() {
+        let columns: Vec<String> = (0..120).map(|i| format!("c{}", i)).collect();
+        let query = build_quarantine_query(&columns, &HashMap::new());
+
+        // Columns start at $3; each object holds at most 50 pairs (100 arguments)
+        let object = |range: std::ops::Range<usize>| {
+            let pairs: Vec<String> = range.map(|i| format!("'c{}', ${}", i, i + 3)).collect();
+            format!("jsonb_build_object({})", pairs.join(", "))
+        };
+        assert_eq!(
+            query,
+            format!(
+                "INSERT INTO public.\"_replicator_dead_letters\" (schema_name, table_name, row_data, error) \
+                 VALUES ($1, $2, {} || {} || {}, $123)",
+                object(0..50),
+                object(50..100),
+                object(100..120)
+            )
+        );
+    }
+}
@@ -2,12 +2,18 @@
 // ABOUTME: Provides change detection using PostgreSQL's xmin system column
 
 pub mod daemon;
+pub mod dead_letter;
+pub mod progress;
 pub mod reader;
 pub mod reconciler;
+pub mod retention;
 pub mod state;
+pub mod target;
+pub mod verifier;
 pub mod writer;
 
-pub use daemon::{DaemonConfig, SyncDaemon, SyncStats};
+pub use daemon::{DaemonConfig, SyncDaemon, SyncDatabase, SyncStats};
+pub use progress::{source_identifier, PositionKind, TableProgress};
 pub use reader::{detect_wraparound, BatchReader, ColumnInfo, WraparoundCheck, XminReader};
 pub use reconciler::{ReconcileConfig, ReconcileResult, Reconciler};
 pub use state::{SyncState, TableSyncState};
@@ -2,8 +2,16 @@
 // ABOUTME: Uses xmin system column to detect rows modified since last sync
 
 use anyhow::{Context, Result};
+use tokio_postgres::types::Type;
 use tokio_postgres::{Client, Row};
 
+use crate::postgres::pgvector::{self, is_vector_udt, vector_columns};
+use crate::postgres::pooler;
+use crate::postgres::postgis::{self, spatial_columns, SpatialEncoding};
+use crate::table_rules::TableSource;
+use crate::transform::TableTransforms;
+use crate::utils::quote_ident;
+
 /// Threshold for detecting xmin wraparound.
 /// If old_xmin - new_xmin > this value, we assume wraparound occurred.
 /// PostgreSQL xmin is 32-bit (~4 billion max), so 2 billion is half.
@@ -95,13 +103,20 @@ impl<'a> XminReader<'a> {
     ///
     /// This should be called at the start of a sync to establish the high-water mark.
     pub async fn get_current_xmin(&self) -> Result<u32> {
-        let row = self
+        // Simple query protocol, so it also works through transaction poolers
+        let txid: i64 = self
             .client
-            .query_one("SELECT txid_current()::text::bigint", &[])
+            .simple_query("SELECT txid_current()::text::bigint")
             .await
-            .context("Failed to get current transaction ID")?;
-
-        let txid: i64 = row.get(0);
+            .context("Failed to get current transaction ID")?
+            .into_iter()
+            .find_map(|message| match message {
+                tokio_postgres::SimpleQueryMessage::Row(row) => {
+                    row.get(0).and_then(|txid| txid.parse().ok())
+                }
+                _ => None,
+            })
+            .context("txid_current() returned no transaction ID")?;
         // xmin is stored as u32, txid_current() returns i64
         // We mask to get the 32-bit xmin value
         Ok((txid & 0xFFFFFFFF) as u32)
@@ -143,9 +158,7 @@ impl<'a> XminReader<'a> {
             column_list, schema, table
         );
 
-        let rows = self
-            .client
-            .query(&query, &[&(since_xmin as i64)])
+        let rows = pooler::query(self.client, &query, &[(&(since_xmin as i64), Type::INT8)])
             .await
             .with_context(|| format!("Failed to read changes from {}.{}", schema, table))?;
 
@@ -191,6 +204,9 @@ impl<'a> XminReader<'a> {
             last_ctid: None,
             batch_size,
             exhausted: false,
+            select_list: None,
+            source: None,
+            predicate: None,
         })
     }
 
@@ -207,7 +223,9 @@ impl<'a> XminReader<'a> {
             return Ok(None);
         }
 
-        let column_list = if batch_reader.columns.is_empty() {
+        let column_list = if let Some(ref select_list) = batch_reader.select_list {
+            select_list.join(", ")
+        } else if batch_reader.columns.is_empty() {
             "*".to_string()
         } else {
             batch_reader
@@ -218,6 +236,21 @@ impl<'a> XminReader<'a> {
                 .join(", ")
         };
 
+        // A custom source exposes the table's xmin and ctid as columns
+        let (from, xmin, ctid) = match &batch_reader.source {
+            Some(source) => (source.clone(), "_xmin", "_tid"),
+            None => (
+                format!("\"{}\".\"{}\"", batch_reader.schema, batch_reader.table),
+                "xmin::text::bigint",
+                "ctid",
+            ),
+        };
+
+        let predicate = match &batch_reader.predicate {
+            Some(predicate) => format!(" AND ({})", predicate),
+            None => String::new(),
+        };
+
         // Use (xmin, ctid) as compound pagination key to handle duplicate xmin values.
         // ctid is the physical tuple location and provides a stable tie-breaker.
         let (query, rows) = if let Some(ref last_ctid) = batch_reader.last_ctid {
@@ -230,58 +263,54 @@ impl<'a> XminReader<'a> {
             // Subsequent batches: use compound (xmin, ctid) > ($1, 'ctid'::tid) filter
             // Note: ctid must be inlined because tokio-postgres can't serialize String to tid type
             let query = format!(
-                "SELECT {}, xmin::text::bigint as _xmin, ctid::text as _ctid \
-                 FROM \"{}\".\"{}\" \
-                 WHERE (xmin::text::bigint, ctid) > ($1, '{}'::tid) \
-                 ORDER BY xmin::text::bigint, ctid \
-                 LIMIT $2",
-                column_list, batch_reader.schema, batch_reader.table, last_ctid
+                "SELECT {column_list}, {xmin} as _xmin, {ctid}::text as _ctid \
+                 FROM {from} \
+                 WHERE ({xmin}, {ctid}) > ($1, '{last_ctid}'::tid){predicate} \
+                 ORDER BY {xmin}, {ctid} \
+                 LIMIT $2"
             );
 
-            let rows = self
-                .client
-                .query(
-                    &query,
-                    &[
-                        &(batch_reader.current_xmin as i64),
-                        &(batch_reader.batch_size as i64),
-                    ],
+            let rows = pooler::query(
+                self.client,
+                &query,
+                &[
+                    (&(batch_reader.current_xmin as i64), Type::INT8),
+                    (&(batch_reader.batch_size as i64), Type::INT8),
+                ],
+            )
+            .await
+            .with_context(|| {
+                format!(
+                    "Failed to read batch from {}.{}",
+                    batch_reader.schema, batch_reader.table
                 )
-                .await
-                .with_context(|| {
-                    format!(
-                        "Failed to read batch from {}.{}",
-                        batch_reader.schema, batch_reader.table
-                    )
-                })?;
+            })?;
             (query, rows)
         } else {
             // First batch: simple xmin > $1 filter
             let query = format!(
-                "SELECT {}, xmin::text::bigint as _xmin, ctid::text as _ctid \
-                 FROM \"{}\".\"{}\" \
-                 WHERE xmin::text::bigint > $1 \
-                 ORDER BY xmin::text::bigint, ctid \
-                 LIMIT $2",
-                column_list, batch_reader.schema, batch_reader.table
+                "SELECT {column_list}, {xmin} as _xmin, {ctid}::text as _ctid \
+                 FROM {from} \
+                 WHERE {xmin} > $1{predicate} \
+                 ORDER BY {xmin}, {ctid} \
+                 LIMIT $2"
             );
 
-            let rows = self
-                .client
-                .query(
-                    &query,
-                    &[
-                        &(batch_reader.current_xmin as i64),
-                        &(batch_reader.batch_size as i64),
-                    ],
+            let rows = pooler::query(
+                self.client,
+                &query,
+                &[
+                    (&(batch_reader.current_xmin as i64), Type::INT8),
+                    (&(batch_reader.batch_size as i64), Type::INT8),
+                ],
+            )
+            .await
+            .with_context(|| {
+                format!(
+                    "Failed to read batch from {}.{}",
+                    batch_reader.schema, batch_reader.table
                 )
-                .await
-                .with_context(|| {
-                    format!(
-                        "Failed to read batch from {}.{}",
-                        batch_reader.schema, batch_reader.table
-                    )
-                })?;
+            })?;
             (query, rows)
         };
 
@@ -325,9 +354,7 @@ impl<'a> XminReader<'a> {
             schema, table
         );
 
-        let row = self
-            .client
-            .query_one(&query, &[&(since_xmin as i64)])
+        let row = pooler::query_one(self.client, &query, &[(&(since_xmin as i64), Type::INT8)])
             .await
             .with_context(|| format!("Failed to count changes in {}.{}", schema, table))?;
 
@@ -335,13 +362,15 @@ impl<'a> XminReader<'a> {
         Ok(count)
     }
 
-    /// Get list of all tables in a schema.
+    /// Get list of all tables in a schema, leaving out this tool's metadata tables.
     pub async fn list_tables(&self, schema: &str) -> Result<Vec<String>> {
-        let rows = self
-            .client
-            .query(
-                "SELECT tablename FROM pg_tables WHERE schemaname = $1 ORDER BY tablename",
-                &[&schema],
+        let rows = pooler::query(
+            self.client,
+                &format!(
+                    "SELECT tablename FROM pg_tables WHERE schemaname = $1 AND NOT {} ORDER BY tablename",
+                    crate::topology::metadata_table_condition("schemaname", "tablename")
+                ),
+                &[(&schema, Type::TEXT)],
             )
             .await
             .with_context(|| format!("Failed to list tables in schema {}", schema))?;
@@ -351,17 +380,16 @@ impl<'a> XminReader<'a> {
 
     /// Get column information for a table.
     pub async fn get_columns(&self, schema: &str, table: &str) -> Result<Vec<ColumnInfo>> {
-        let rows = self
-            .client
-            .query(
-                "SELECT column_name, data_type, is_nullable, column_default
-                 FROM information_schema.columns
-                 WHERE table_schema = $1 AND table_name = $2
-                 ORDER BY ordinal_position",
-                &[&schema, &table],
-            )
-            .await
-            .with_context(|| format!("Failed to get columns for {}.{}", schema, table))?;
+        let rows = pooler::query(
+            self.client,
+            "SELECT column_name, data_type, is_nullable, column_default
+             FROM information_schema.columns
+             WHERE table_schema = $1 AND table_name = $2
+             ORDER BY ordinal_position",
+            &[(&schema, Type::TEXT), (&table, Type::TEXT)],
+        )
+        .await
+        .with_context(|| format!("Failed to get columns for {}.{}", schema, table))?;
 
         Ok(rows
             .iter()
@@ -376,22 +404,21 @@ impl<'a> XminReader<'a> {
 
     /// Get primary key columns for a table.
     pub async fn get_primary_key(&self, schema: &str, table: &str) -> Result<Vec<String>> {
-        let rows = self
-            .client
-            .query(
-                "SELECT a.attname
-                 FROM pg_index i
-                 JOIN pg_attribute a ON a.attrelid = i.indrelid AND a.attnum = ANY(i.indkey)
-                 JOIN pg_class c ON c.oid = i.indrelid
-                 JOIN pg_namespace n ON n.oid = c.relnamespace
-                 WHERE i.indisprimary
-                   AND n.nspname = $1
-                   AND c.relname = $2
-                 ORDER BY array_position(i.indkey, a.attnum)",
-                &[&schema, &table],
-            )
-            .await
-            .with_context(|| format!("Failed to get primary key for {}.{}", schema, table))?;
+        let rows = pooler::query(
+            self.client,
+            "SELECT a.attname
+             FROM pg_index i
+             JOIN pg_attribute a ON a.attrelid = i.indrelid AND a.attnum = ANY(i.indkey)
+             JOIN pg_class c ON c.oid = i.indrelid
+             JOIN pg_namespace n ON n.oid = c.relnamespace
+             WHERE i.indisprimary
+               AND n.nspname = $1
+               AND c.relname = $2
+             ORDER BY array_position(i.indkey, a.attnum)",
+            &[(&schema, Type::TEXT), (&table, Type::TEXT)],
+        )
+        .await
+        .with_context(|| format!("Failed to get primary key for {}.{}", schema, table))?;
 
         Ok(rows.iter().map(|row| row.get(0)).collect())
     }
@@ -439,9 +466,7 @@ impl<'a> XminReader<'a> {
             column_list, schema, table
         );
 
-        let rows = self
-            .client
-            .query(&query, &[])
+        let rows = pooler::query(self.client, &query, &[])
             .await
             .with_context(|| format!("Failed to read all rows from {}.{}", schema, table))?;
 
@@ -519,6 +544,152 @@ pub struct BatchReader {
     pub last_ctid: Option<String>,
     pub batch_size: usize,
     pub exhausted: bool,
+    /// SELECT expressions used instead of the quoted `columns`, one per column, when set.
+    /// Lets extension types be read in a decodable form (see [`Self::with_column_types`])
+    /// and transformed columns be rewritten (see [`Self::with_transforms`]).
+    pub select_list: Option<Vec<String>>,
+    /// FROM item read instead of the table, exposing each row's `_xmin` and
+    /// `_tid` (see [`Self::with_source`])
+    pub source: Option<String>,
+    /// Condition rows must also meet, such as a time filter's window (see
+    /// [`Self::with_predicate`])
+    pub predicate: Option<String>,
+}
+
+impl BatchReader {
+    /// Read only the changed rows that meet `predicate`
+    ///
+    /// Rows outside it are skipped even when they change. The predicate is
+    /// evaluated over the FROM item, so with a custom source it must name
+    /// columns the source exposes.
+    pub fn with_predicate(mut self, predicate: String) -> Self {
+        self.predicate = Some(predicate);
+        self
+    }
+
+    /// Read extension column types in a form the driver can decode.
+    ///
+    /// PostGIS geometry/geography columns are read in `spatial_encoding` and
+    /// pgvector columns as text. `column_types` are `(column_name, udt_name)`
+    /// pairs in the same order as `columns`. Tables without such columns are
+    /// left unchanged.
+    pub fn with_column_types(
+        mut self,
+        column_types: &[(String, String)],
+        spatial_encoding: SpatialEncoding,
+    ) -> Self {
+        if spatial_columns(column_types).is_empty() && vector_columns(column_types).is_empty() {
+            return self;
+        }
+        let expressions: Vec<String> = column_types
+            .iter()
+            .map(|(name, udt)| {
+                if is_vector_udt(udt) {
+                    pgvector::select_expression(name)
+                } else {
+                    postgis::select_expression(name, udt, spatial_encoding)
+                }
+            })
+            .collect();
+        self.select_list = Some(expressions);
+        self
+    }
+
+    /// Read each column through its own SELECT expression, one per column.
+    ///
+    /// For targets that decode values their own way, such as MySQL (see
+    /// [`crate::mysql::writer::select_expression`]).
+    pub fn with_select_list(mut self, expressions: Vec<String>) -> Self {
+        self.select_list = Some(expressions);
+        self
+    }
+
+    /// Read transformed columns through their transforms.
+    ///
+    /// `column_types` are `(column_name, udt_name)` pairs in the same order as
+    /// `columns`; each transformed value is cast back to its column's type.
+    pub fn with_transforms(
+        mut self,
+        transforms: &TableTransforms,
+        column_types: &[(String, String)],
+    ) -> Result<Self> {
+        let casts: Vec<(String, String)> = column_types
+            .iter()
+            .map(|(name, udt)| (name.clone(), quote_ident(udt)))
+            .collect();
+        let mut expressions = self.select_list.take().unwrap_or_else(|| {
+            self.columns
+                .iter()
+                .map(|column| quote_ident(column))
+                .collect()
+        });
+        transforms
+            .apply(&casts, &mut expressions)
+            .with_context(|| format!("Cannot transform {}.{}", self.schema, self.table))?;
+        self.select_list = Some(expressions);
+        Ok(self)
+    }
+
+    /// Read rows through a custom table source instead of the table.
+    ///
+    /// Changes are still found by the table's own xmin: a SELECT list is
+    /// evaluated over each changed row, and a view's rows are matched to the
+    /// changed rows by `pk_columns`. Each column is cast to its type in
+    /// `column_types` (`(column_name, udt_name)` pairs in the same order as
+    /// `columns`) unless extension types already set the SELECT list.
+    pub fn with_source(
+        mut self,
+        source: &TableSource,
+        pk_columns: &[String],
+        column_types: &[(String, String)],
+    ) -> Self {
+        let table = format!("{}.{}", quote_ident(&self.schema), quote_ident(&self.table));
+        let relation = match source {
+            TableSource::Select(list) => format!(
+                "(SELECT {}, xmin::text::bigint AS _xmin, ctid AS _tid FROM {}) AS {}",
+                list,
+                table,
+                quote_ident(&self.table)
+            ),
+            TableSource::View(view) => {
+                let join: Vec<String> = pk_columns
+                    .iter()
+                    .map(|column| {
+                        format!(
+                            "_view.{} = _base.{}",
+                            quote_ident(column),
+                            quote_ident(column)
+                        )
+                    })
+                    .collect();
+                format!(
+                    "(SELECT _view.*, _base.xmin::text::bigint AS _xmin, _base.ctid AS _tid \
+                     FROM {} AS _base JOIN {} AS _view ON {}) AS {}",
+                    table,
+                    view,
+                    join.join(" AND "),
+                    quote_ident(&self.table)
+                )
+            }
+        };
+        self.source = Some(relation);
+        if self.select_list.is_none() {
+            self.select_list = Some(
+                column_types
+                    .iter()
+                    .map(|(name, udt)| {
+                        format!(
+                            "{}::{} AS {}",
+                            quote_ident(name),
+                            quote_ident(udt),
+                            quote_ident(name)
+                        )
+                    })
+                    .collect(),
+            );
+        }
+        self
+    }
 }
 
 /// Information about a table column.
@@ -544,6 +715,9 @@ mod tests {
             last_ctid: None,
             batch_size: 1000,
             exhausted: false,
+            select_list: None,
+            source: None,
+            predicate: None,
         };
 
         assert_eq!(reader.schema, "public");
@@ -553,6 +727,61 @@ mod tests {
         assert!(!reader.exhausted);
     }
 
+    #[test]
+    fn test_batch_reader_with_source() {
+        let reader = || BatchReader {
+            schema: "public".to_string(),
+            table: "users".to_string(),
+            columns: vec!["id".to_string(), "email".to_string()],
+            current_xmin: 0,
+            last_ctid: None,
+            batch_size: 1000,
+            exhausted: false,
+            select_list: None,
+            source: None,
+            predicate: None,
+        };
+        let column_types = vec![
+            ("id".to_string(), "int4".to_string()),
+            ("email".to_string(), "text".to_string()),
+        ];
+        let pk = vec!["id".to_string()];
+
+        let selected = reader().with_source(
+            &TableSource::select("id, lower(email) AS email").unwrap(),
+            &pk,
+            &column_types,
+        );
+        assert_eq!(
+            selected.source.as_deref(),
+            Some(
+                "(SELECT id, lower(email) AS email, xmin::text::bigint AS _xmin, ctid AS _tid \
+                 FROM \"public\".\"users\") AS \"users\""
+            )
+        );
+        assert_eq!(
+            selected.select_list.unwrap(),
+            vec![
+                "\"id\"::\"int4\" AS \"id\"",
+                "\"email\"::\"text\" AS \"email\""
+            ]
+        );
+
+        let viewed = reader().with_source(
+            &TableSource::view("staging.users_export").unwrap(),
+            &pk,
+            &column_types,
+        );
+        assert_eq!(
+            viewed.source.as_deref(),
+            Some(
+                "(SELECT _view.*, _base.xmin::text::bigint AS _xmin, _base.ctid AS _tid \
+                 FROM \"public\".\"users\" AS _base JOIN \"staging\".\"users_export\" AS _view \
+                 ON _view.\"id\" = _base.\"id\") AS \"users\""
+            )
+        );
+    }
+
     #[test]
     fn test_column_info() {
         let col = ColumnInfo {
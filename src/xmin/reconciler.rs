@@ -4,10 +4,12 @@
 use anyhow::{Context, Result};
 use std::cmp::Ordering;
 use std::collections::HashSet;
-use tokio_postgres::types::ToSql;
+use tokio_postgres::types::Type;
 use tokio_postgres::Client;
 
 use super::writer::ChangeWriter;
+use crate::postgres::pooler::{self, Param};
+use crate::table_rules::TargetNames;
 
 /// Reconciler detects rows that exist in target but not in source (deletions).
 ///
@@ -17,6 +19,7 @@ use super::writer::ChangeWriter;
 pub struct Reconciler<'a> {
     source_client: &'a Client,
     target_client: &'a Client,
+    target_names: TargetNames,
 }
 
 impl<'a> Reconciler<'a> {
@@ -25,9 +28,31 @@ impl<'a> Reconciler<'a> {
         Self {
             source_client,
             target_client,
+            target_names: TargetNames::default(),
         }
     }
 
+    /// Compare each source table with the target table `names` resolves it to.
+    ///
+    /// Methods still take source schema, table, and column names.
+    pub fn with_target_names(mut self, names: TargetNames) -> Self {
+        self.target_names = names;
+        self
+    }
+
+    /// Target names of a table's primary key columns
+    fn target_key_columns(
+        &self,
+        schema: &str,
+        table: &str,
+        primary_key_columns: &[String],
+    ) -> Vec<String> {
+        primary_key_columns
+            .iter()
+            .map(|column| self.target_names.resolve_column(schema, table, column))
+            .collect()
+    }
+
     /// Find rows that exist in target but not in source (orphaned rows).
     ///
     /// This performs a primary key comparison between source and target tables.
@@ -55,8 +80,15 @@ impl<'a> Reconciler<'a> {
             .context("Failed to get source primary keys")?;
 
         // Get all PKs from target
+        let (target_schema, target_table) = self.target_names.resolve(schema, table);
+        let target_key_columns = self.target_key_columns(schema, table, primary_key_columns);
         let target_pks = self
-            .get_all_primary_keys(self.target_client, schema, table, primary_key_columns)
+            .get_all_primary_keys(
+                self.target_client,
+                &target_schema,
+                &target_table,
+                &target_key_columns,
+            )
             .await
             .context("Failed to get target primary keys")?;
 
@@ -99,20 +131,17 @@ impl<'a> Reconciler<'a> {
             return Ok(0);
         }
 
-        // Convert string PKs to ToSql values
-        let pk_values: Vec<Vec<Box<dyn ToSql + Sync + Send>>> = orphaned
-            .into_iter()
-            .map(|pk| {
-                pk.into_iter()
-                    .map(|v| Box::new(v) as Box<dyn ToSql + Sync + Send>)
-                    .collect()
-            })
-            .collect();
-
         // Delete orphaned rows
+        let (target_schema, target_table) = self.target_names.resolve(schema, table);
+        let target_key_columns = self.target_key_columns(schema, table, primary_key_columns);
         let writer = ChangeWriter::new(self.target_client);
         let deleted = writer
-            .delete_rows(schema, table, primary_key_columns, pk_values)
+            .delete_rows_by_text(
+                &target_schema,
+                &target_table,
+                &target_key_columns,
+                &orphaned,
+            )
             .await?;
 
         tracing::info!(
@@ -150,8 +179,7 @@ impl<'a> Reconciler<'a> {
             pk_cols_text.join(", ")
         );
 
-        let rows = client
-            .query(&query, &[])
+        let rows = pooler::query(client, &query, &[])
             .await
             .with_context(|| format!("Failed to get primary keys from {}.{}", schema, table))?;
 
@@ -170,17 +198,18 @@ impl<'a> Reconciler<'a> {
     /// Get count of rows in source and target for comparison.
     pub async fn get_row_counts(&self, schema: &str, table: &str) -> Result<(i64, i64)> {
         let query = format!("SELECT COUNT(*) FROM \"{}\".\"{}\"", schema, table);
+        let (target_schema, target_table) = self.target_names.resolve(schema, table);
+        let target_query = format!(
+            "SELECT COUNT(*) FROM \"{}\".\"{}\"",
+            target_schema, target_table
+        );
 
-        let source_row = self
-            .source_client
-            .query_one(&query, &[])
+        let source_row = pooler::query_one(self.source_client, &query, &[])
             .await
             .context("Failed to get source row count")?;
         let source_count: i64 = source_row.get(0);
 
-        let target_row = self
-            .target_client
-            .query_one(&query, &[])
+        let target_row = pooler::query_one(self.target_client, &target_query, &[])
             .await
             .context("Failed to get target row count")?;
         let target_count: i64 = target_row.get(0);
@@ -190,16 +219,19 @@ impl<'a> Reconciler<'a> {
 
     /// Check if a table exists in the target database.
     pub async fn table_exists_in_target(&self, schema: &str, table: &str) -> Result<bool> {
+        let (schema, table) = self.target_names.resolve(schema, table);
         let query = "SELECT EXISTS (
             SELECT 1 FROM information_schema.tables
             WHERE table_schema = $1 AND table_name = $2
         )";
 
-        let row = self
-            .target_client
-            .query_one(query, &[&schema, &table])
-            .await
-            .context("Failed to check if table exists")?;
+        let row = pooler::query_one(
+            self.target_client,
+            query,
+            &[(&schema, Type::TEXT), (&table, Type::TEXT)],
+        )
+        .await
+        .context("Failed to check if table exists")?;
 
         Ok(row.get(0))
     }
@@ -235,6 +267,8 @@ impl<'a> Reconciler<'a> {
         );
 
         let writer = ChangeWriter::new(self.target_client);
+        let (target_schema, target_table) = self.target_names.resolve(schema, table);
+        let target_key_columns = self.target_key_columns(schema, table, primary_key_columns);
         let mut total_deleted = 0u64;
         let mut orphans_batch: Vec<Vec<String>> = Vec::new();
 
@@ -248,9 +282,9 @@ impl<'a> Reconciler<'a> {
         );
         let mut target_reader = PkBatchReader::new(
             self.target_client,
-            schema,
-            table,
-            primary_key_columns,
+            &target_schema,
+            &target_table,
+            &target_key_columns,
             batch_size,
         );
 
@@ -295,9 +329,9 @@ impl<'a> Reconciler<'a> {
                         total_deleted += self
                             .delete_orphan_batch(
                                 &writer,
-                                schema,
-                                table,
-                                primary_key_columns,
+                                &target_schema,
+                                &target_table,
+                                &target_key_columns,
                                 &orphans_batch,
                             )
                             .await?;
@@ -344,9 +378,9 @@ impl<'a> Reconciler<'a> {
                         total_deleted += self
                             .delete_orphan_batch(
                                 &writer,
-                                schema,
-                                table,
-                                primary_key_columns,
+                                &target_schema,
+                                &target_table,
+                                &target_key_columns,
                                 &orphans_batch,
                             )
                             .await?;
@@ -370,7 +404,13 @@ impl<'a> Reconciler<'a> {
         // Delete remaining orphans
         if !orphans_batch.is_empty() {
             total_deleted += self
-                .delete_orphan_batch(&writer, schema, table, primary_key_columns, &orphans_batch)
+                .delete_orphan_batch(
+                    &writer,
+                    &target_schema,
+                    &target_table,
+                    &target_key_columns,
+                    &orphans_batch,
+                )
                 .await?;
         }
 
@@ -405,18 +445,8 @@ impl<'a> Reconciler<'a> {
             table
         );
 
-        // Convert string PKs to ToSql values
-        let pk_values: Vec<Vec<Box<dyn ToSql + Sync + Send>>> = orphans
-            .iter()
-            .map(|pk| {
-                pk.iter()
-                    .map(|v| Box::new(v.clone()) as Box<dyn ToSql + Sync + Send>)
-                    .collect()
-            })
-            .collect();
-
         writer
-            .delete_rows(schema, table, primary_key_columns, pk_values)
+            .delete_rows_by_text(schema, table, primary_key_columns, orphans)
             .await
     }
 }
@@ -515,18 +545,19 @@ impl<'a> PkBatchReader<'a> {
         };
 
         // Build parameters for keyset pagination
-        let params: Vec<&(dyn ToSql + Sync)> = if let Some(ref last) = self.last_pk {
-            last.iter().map(|s| s as &(dyn ToSql + Sync)).collect()
-        } else {
-            Vec::new()
+        let params: Vec<Param> = match self.last_pk {
+            Some(ref last) => last.iter().map(|s| (s as _, Type::TEXT)).collect(),
+            None => Vec::new(),
         };
 
-        let rows = self.client.query(&query, &params).await.with_context(|| {
-            format!(
-                "Failed to fetch PK batch from {}.{}",
-                self.schema, self.table
-            )
-        })?;
+        let rows = pooler::query(self.client, &query, &params)
+            .await
+            .with_context(|| {
+                format!(
+                    "Failed to fetch PK batch from {}.{}",
+                    self.schema, self.table
+                )
+            })?;
 
         if rows.len() < self.batch_size {
             self.exhausted = true;
@@ -626,4 +657,63 @@ mod tests {
         };
         assert!(!result.is_in_sync());
     }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_reconcile_sends_unnamed_statements_behind_pooler() {
+        use crate::postgres::pooler::test_hooks;
+        use crate::table_rules::{QualifiedTable, TableRules};
+
+        let source_url = std::env::var("TEST_SOURCE_URL").unwrap();
+        let source = crate::postgres::connect(&source_url).await.unwrap();
+        let target = crate::postgres::connect(&source_url).await.unwrap();
+        source
+            .batch_execute(
+                "CREATE SCHEMA IF NOT EXISTS reconcile_pool_src;
+                 CREATE SCHEMA IF NOT EXISTS reconcile_pool_dst;
+                 DROP TABLE IF EXISTS reconcile_pool_src.items, reconcile_pool_dst.items;
+                 CREATE TABLE reconcile_pool_src.items (id int PRIMARY KEY, name text);
+                 CREATE TABLE reconcile_pool_dst.items (id int PRIMARY KEY, name text);
+                 INSERT INTO reconcile_pool_src.items VALUES (1, 'a'), (2, 'b');
+                 INSERT INTO reconcile_pool_dst.items VALUES (1, 'a'), (2, 'b'), (3, 'orphan');",
+            )
+            .await
+            .unwrap();
+
+        let mut rules = TableRules::default();
+        rules
+            .add_rename(
+                QualifiedTable::parse("app.reconcile_pool_src.items").unwrap(),
+                QualifiedTable::parse("reconcile_pool_dst.items").unwrap(),
+            )
+            .unwrap();
+        let reconciler =
+            Reconciler::new(&source, &target).with_target_names(rules.target_names("app"));
+
+        test_hooks::pool_this_thread();
+        assert!(reconciler
+            .table_exists_in_target("reconcile_pool_src", "items")
+            .await
+            .unwrap());
+        assert_eq!(
+            reconciler
+                .get_row_counts("reconcile_pool_src", "items")
+                .await
+                .unwrap(),
+            (2, 3)
+        );
+        let deleted = reconciler
+            .reconcile_table_batched("reconcile_pool_src", "items", &["id".to_string()], 1)
+            .await
+            .unwrap();
+
+        assert_eq!(deleted, 1);
+        assert!(test_hooks::unnamed_statements() >= 4);
+        let remaining: i64 = target
+            .query_one("SELECT count(*) FROM reconcile_pool_dst.items", &[])
+            .await
+            .unwrap()
+            .get(0);
+        assert_eq!(remaining, 2);
+    }
 }
@@ -0,0 +1,191 @@
+// ABOUTME: Retention for time-partitioned tables on the target of xmin sync
+// ABOUTME: Drops target partitions whose range ended before the retention window
+
+use anyhow::{bail, Context, Result};
+use tokio_postgres::types::Type;
+use tokio_postgres::Client;
+
+use crate::postgres::pooler;
+use crate::table_rules::TimeFilterRule;
+use crate::utils::quote_ident;
+
+/// Retention window of a table, with its partition key as the time column
+///
+/// Fails unless the table is range-partitioned on a single column, since only
+/// then does each partition cover a span of time that can expire.
+pub async fn window(
+    client: &Client,
+    schema: &str,
+    table: &str,
+    interval: &str,
+) -> Result<TimeFilterRule> {
+    let row = pooler::query_opt(
+        client,
+        "SELECT p.partstrat::text, p.partnatts, a.attname::text
+         FROM pg_catalog.pg_partitioned_table p
+         JOIN pg_catalog.pg_class c ON c.oid = p.partrelid
+         JOIN pg_catalog.pg_namespace n ON n.oid = c.relnamespace
+         LEFT JOIN pg_catalog.pg_attribute a
+           ON a.attrelid = p.partrelid AND a.attnum = p.partattrs[0]
+         WHERE n.nspname = $1 AND c.relname = $2",
+        &[(&schema, Type::TEXT), (&table, Type::TEXT)],
+    )
+    .await
+    .with_context(|| format!("Failed to read the partition key of {}.{}", schema, table))?;
+    let Some(row) = row else {
+        bail!(
+            "Retention needs a partitioned table, but {}.{} is not partitioned",
+            schema,
+            table
+        );
+    };
+    let strategy: String = row.get(0);
+    let key_columns: i16 = row.get(1);
+    let column: Option<String> = row.get(2);
+    match column {
+        Some(column) if strategy == "r" && key_columns == 1 => Ok(TimeFilterRule {
+            column,
+            interval: interval.to_string(),
+        }),
+        _ => bail!(
+            "Retention needs {}.{} to be range-partitioned on a single column",
+            schema,
+            table
+        ),
+    }
+}
+
+/// Partitions under a table at any depth, as (schema, table)
+pub async fn partitions(
+    client: &Client,
+    schema: &str,
+    table: &str,
+) -> Result<Vec<(String, String)>> {
+    let parent = format!("{}.{}", quote_ident(schema), quote_ident(table));
+    let rows = pooler::query(
+        client,
+        "SELECT n.nspname::text, c.relname::text
+         FROM pg_catalog.pg_partition_tree($1::text::regclass) t
+         JOIN pg_catalog.pg_class c ON c.oid = t.relid
+         JOIN pg_catalog.pg_namespace n ON n.oid = c.relnamespace
+         WHERE t.level > 0",
+        &[(&parent, Type::TEXT)],
+    )
+    .await
+    .with_context(|| format!("Failed to list partitions of {}.{}", schema, table))?;
+    Ok(rows.iter().map(|row| (row.get(0), row.get(1))).collect())
+}
+
+/// Drop the partitions of a target table whose range ends before the window
+///
+/// Only direct partitions are considered; dropping one drops its
+/// subpartitions too. The default partition and partitions bounded by
+/// `MAXVALUE` are kept. Returns the dropped partitions as `schema.table`.
+pub async fn drop_expired_partitions(
+    client: &Client,
+    schema: &str,
+    table: &str,
+    window: &TimeFilterRule,
+) -> Result<Vec<String>> {
+    let parent = format!("{}.{}", quote_ident(schema), quote_ident(table));
+    let rows = pooler::query(
+        client,
+        "SELECT n.nspname::text, c.relname::text, pg_get_expr(c.relpartbound, c.oid)
+         FROM pg_catalog.pg_inherits i
+         JOIN pg_catalog.pg_class c ON c.oid = i.inhrelid
+         JOIN pg_catalog.pg_namespace n ON n.oid = c.relnamespace
+         WHERE i.inhparent = $1::text::regclass
+         ORDER BY 1, 2",
+        &[(&parent, Type::TEXT)],
+    )
+    .await
+    .with_context(|| format!("Failed to list partitions of target {}.{}", schema, table))?;
+
+    let mut dropped = Vec::new();
+    for row in rows {
+        let partition_schema: String = row.get(0);
+        let partition: String = row.get(1);
+        let bound: String = row.get(2);
+        let Some(upper) = upper_bound(&bound) else {
+            continue;
+        };
+        // The bound comes from the catalog, which prints it as a valid literal
+        let expired: bool = pooler::query_one(
+            client,
+            &format!(
+                "SELECT ({})::timestamptz <= NOW() - INTERVAL '{}'",
+                upper, window.interval
+            ),
+            &[],
+        )
+        .await
+        .with_context(|| {
+            format!(
+                "Cannot compare the upper bound {} of partition {}.{} with a time",
+                upper, partition_schema, partition
+            )
+        })?
+        .get(0);
+        if !expired {
+            continue;
+        }
+        client
+            .batch_execute(&format!(
+                "DROP TABLE {}.{}",
+                quote_ident(&partition_schema),
+                quote_ident(&partition)
+            ))
+            .await
+            .with_context(|| {
+                format!(
+                    "Failed to drop expired partition {}.{}",
+                    partition_schema, partition
+                )
+            })?;
+        dropped.push(format!("{}.{}", partition_schema, partition));
+    }
+    Ok(dropped)
+}
+
+/// Upper bound literal of a single-column range partition, from
+/// `pg_get_expr(relpartbound)`; `None` for other partitions
+fn upper_bound(bound: &str) -> Option<&str> {
+    let upper = bound.split_once(") TO (")?.1.strip_suffix(')')?;
+    let is_single_literal =
+        upper.len() >= 2 && upper.starts_with('\'') && upper.ends_with('\'') && {
+            // Doubled quotes are escapes; any other quote would end the literal early
+            upper[1..upper.len() - 1]
+                .replace("''", "")
+                .find('\'')
+                .is_none()
+        };
+    is_single_literal.then_some(upper)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_upper_bound() {
+        assert_eq!(
+            upper_bound("FOR VALUES FROM ('2024-01-01') TO ('2024-02-01')"),
+            Some("'2024-02-01'")
+        );
+        assert_eq!(
+            upper_bound("FOR VALUES FROM ('2024-01-01 00:00:00+00') TO ('2024-01-02 00:00:00+00')"),
+            Some("'2024-01-02 00:00:00+00'")
+        );
+        assert_eq!(
+            upper_bound("FOR VALUES FROM ('2024-01-01') TO (MAXVALUE)"),
+            None
+        );
+        assert_eq!(upper_bound("DEFAULT"), None);
+        assert_eq!(
+            upper_bound("FOR VALUES FROM ('2024-01-01', 1) TO ('2024-02-01', 1)"),
+            None
+        );
+        assert_eq!(upper_bound("FOR VALUES FROM (1) TO (100)"), None);
+        assert_eq!(upper_bound("FOR VALUES IN ('a')"), None);
+    }
+}
@@ -6,6 +6,14 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::Path;
 use tokio::fs;
+use tokio_postgres::error::SqlState;
+use tokio_postgres::types::{Json, Type};
+use tokio_postgres::Client;
+
+use crate::postgres::pooler;
+
+/// Table in the target's `public` schema holding sync state for `sync --stateless`
+pub const STATE_TABLE: &str = "_replicator_sync_state";
 
 /// Sync state for a single table, tracking the last synced xmin value.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -21,6 +29,21 @@ pub struct TableSyncState {
     pub last_sync_at: chrono::DateTime<chrono::Utc>,
     /// Number of rows synced in last batch
     pub last_row_count: u64,
+    /// Rows applied by the most recent sync cycle, including cycles with no changes
+    #[serde(default)]
+    pub last_cycle_rows: u64,
+    /// Wall-clock duration of the most recent sync cycle in milliseconds
+    #[serde(default)]
+    pub last_cycle_ms: u64,
+    /// When the background verifier last found the table matching the source
+    #[serde(default)]
+    pub last_verified_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Times the background verifier found the table drifted and queued a repair
+    #[serde(default)]
+    pub drift_repairs: u64,
+    /// The next sync cycle copies every row again to repair drift
+    #[serde(default)]
+    pub resync_pending: bool,
 }
 
 impl TableSyncState {
@@ -32,6 +55,11 @@ impl TableSyncState {
             last_xmin: 0,
             last_sync_at: chrono::Utc::now(),
             last_row_count: 0,
+            last_cycle_rows: 0,
+            last_cycle_ms: 0,
+            last_verified_at: None,
+            drift_repairs: 0,
+            resync_pending: false,
         }
     }
 
@@ -42,6 +70,30 @@ impl TableSyncState {
         self.last_row_count = row_count;
     }
 
+    /// Record the rows and duration of a completed sync cycle
+    pub fn record_cycle(&mut self, rows: u64, duration: std::time::Duration) {
+        self.last_cycle_rows = rows;
+        self.last_cycle_ms = duration.as_millis() as u64;
+    }
+
+    /// Record a background verification; drift queues a full resync
+    pub fn record_verification(&mut self, checked_at: chrono::DateTime<chrono::Utc>, drift: bool) {
+        if drift {
+            self.drift_repairs += 1;
+            self.resync_pending = true;
+        } else {
+            self.last_verified_at = Some(checked_at);
+        }
+    }
+
+    /// Throughput of the most recent sync cycle, if it took measurable time
+    pub fn rows_per_sec(&self) -> Option<f64> {
+        if self.last_cycle_ms == 0 {
+            return None;
+        }
+        Some(self.last_cycle_rows as f64 * 1000.0 / self.last_cycle_ms as f64)
+    }
+
     /// Get the qualified table name (schema.table)
     pub fn qualified_name(&self) -> String {
         format!("{}.{}", self.schema, self.table)
@@ -142,24 +194,94 @@ impl SyncState {
         Ok(())
     }
 
+    /// Load the state stored in the target for a source, if there is any
+    ///
+    /// `source_id` is the source's [`super::source_identifier`]. A target
+    /// without the state table has no state.
+    pub async fn load_from_target(client: &Client, source_id: &str) -> Result<Option<Self>> {
+        let result = pooler::query_opt(
+            client,
+            &format!(
+                "SELECT state FROM public.\"{}\" WHERE source_id = $1",
+                STATE_TABLE
+            ),
+            &[(&source_id, Type::TEXT)],
+        )
+        .await;
+        let row = match result {
+            Err(e)
+                if e.downcast_ref::<tokio_postgres::Error>()
+                    .and_then(|e| e.code())
+                    == Some(&SqlState::UNDEFINED_TABLE) =>
+            {
+                return Ok(None)
+            }
+            result => result.with_context(|| {
+                format!("Failed to load sync state for {} from target", source_id)
+            })?,
+        };
+        Ok(row.map(|row| row.get::<_, Json<SyncState>>(0).0))
+    }
+
+    /// Save state to the target, replacing what was stored for the source
+    pub async fn save_to_target(&self, client: &Client, source_id: &str) -> Result<()> {
+        pooler::execute(
+            client,
+            &format!(
+                "INSERT INTO public.\"{}\" (source_id, state) VALUES ($1, $2)
+                 ON CONFLICT (source_id) DO UPDATE SET
+                    state = EXCLUDED.state,
+                    updated_at = now()",
+                STATE_TABLE
+            ),
+            &[(&source_id, Type::TEXT), (&Json(self), Type::JSONB)],
+        )
+        .await
+        .with_context(|| format!("Failed to save sync state for {} to target", source_id))?;
+        Ok(())
+    }
+
     /// Get the default state file path for the current directory
     pub fn default_path() -> std::path::PathBuf {
         std::path::PathBuf::from(".seren-replicator/xmin-sync-state.json")
     }
+
+    /// State file for one of several databases synced together
+    ///
+    /// The database name is added before the extension, e.g.
+    /// `xmin-sync-state.orders.json`.
+    pub fn database_path(path: &Path, database: &str) -> std::path::PathBuf {
+        let stem = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "xmin-sync-state".to_string());
+        let file_name = match path.extension() {
+            Some(ext) => format!("{}.{}.{}", stem, database, ext.to_string_lossy()),
+            None => format!("{}.{}", stem, database),
+        };
+        path.with_file_name(file_name)
+    }
+}
+
+/// Create the state table in the target if it does not exist
+pub async fn ensure_state_table(client: &Client) -> Result<()> {
+    client
+        .batch_execute(&format!(
+            "CREATE TABLE IF NOT EXISTS public.\"{}\" (
+                source_id TEXT PRIMARY KEY,
+                state JSONB NOT NULL,
+                updated_at TIMESTAMPTZ NOT NULL DEFAULT now()
+            )",
+            STATE_TABLE
+        ))
+        .await
+        .context("Failed to create _replicator_sync_state table in target")?;
+    Ok(())
 }
 
 /// Sanitize a database URL by removing the password component
 fn sanitize_url(url: &str) -> String {
-    // Try to parse as URL and remove password
-    if let Ok(mut parsed) = url::Url::parse(url) {
-        if parsed.password().is_some() {
-            let _ = parsed.set_password(Some("***"));
-        }
-        parsed.to_string()
-    } else {
-        // If not a valid URL, return as-is (might be a file path for SQLite)
-        url.to_string()
-    }
+    crate::redact::redact_url(url)
 }
 
 #[cfg(test)]
@@ -183,6 +305,46 @@ mod tests {
         assert_eq!(state.last_row_count, 100);
     }
 
+    #[test]
+    fn test_table_sync_state_record_cycle() {
+        let mut state = TableSyncState::new("public", "users");
+        assert_eq!(state.rows_per_sec(), None);
+
+        state.record_cycle(500, std::time::Duration::from_millis(250));
+        assert_eq!(state.last_cycle_rows, 500);
+        assert_eq!(state.last_cycle_ms, 250);
+        assert_eq!(state.rows_per_sec(), Some(2000.0));
+    }
+
+    #[test]
+    fn test_table_sync_state_without_cycle_fields() {
+        // State files written before cycle metrics were recorded
+        let state: TableSyncState = serde_json::from_str(
+            r#"{"schema":"public","table":"users","last_xmin":7,
+                "last_sync_at":"2024-01-01T00:00:00Z","last_row_count":3}"#,
+        )
+        .unwrap();
+        assert_eq!(state.last_xmin, 7);
+        assert_eq!(state.last_cycle_rows, 0);
+        assert_eq!(state.last_cycle_ms, 0);
+        assert_eq!(state.last_verified_at, None);
+        assert!(!state.resync_pending);
+    }
+
+    #[test]
+    fn test_table_sync_state_record_verification() {
+        let mut state = TableSyncState::new("public", "users");
+        let now = chrono::Utc::now();
+        state.record_verification(now, false);
+        assert_eq!(state.last_verified_at, Some(now));
+        assert!(!state.resync_pending);
+
+        state.record_verification(now, true);
+        assert_eq!(state.drift_repairs, 1);
+        assert!(state.resync_pending);
+        assert_eq!(state.last_verified_at, Some(now));
+    }
+
     #[test]
     fn test_table_sync_state_qualified_name() {
         let state = TableSyncState::new("myschema", "mytable");
@@ -238,6 +400,21 @@ mod tests {
         assert!(state.get_table("public", "users").is_none());
     }
 
+    #[test]
+    fn test_database_path() {
+        assert_eq!(
+            SyncState::database_path(
+                Path::new(".seren-replicator/xmin-sync-state.json"),
+                "orders"
+            ),
+            Path::new(".seren-replicator/xmin-sync-state.orders.json")
+        );
+        assert_eq!(
+            SyncState::database_path(Path::new("/var/lib/state"), "orders"),
+            Path::new("/var/lib/state.orders")
+        );
+    }
+
     #[test]
     fn test_sanitize_url() {
         assert_eq!(
@@ -0,0 +1,537 @@
+// ABOUTME: Shared xmin sync loop for experimental non-PostgreSQL targets (SQLite, MySQL)
+// ABOUTME: Reads changed rows by xmin and hands them to a TargetWriter, tracking per-table state
+
+use super::{
+    detect_wraparound, get_primary_key_columns, get_table_columns, BatchReader, SyncState,
+    SyncStats, WraparoundCheck, XminReader,
+};
+use crate::filters::ReplicationFilter;
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use std::future::Future;
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio_postgres::Row;
+
+/// A target database that receives upserts from the xmin sync loop
+#[async_trait]
+pub trait TargetWriter: Send {
+    /// One converted row, in the form the target's driver takes
+    type Values: Send;
+
+    /// Target name used in log and error messages, e.g. `SQLite`
+    const NAME: &'static str;
+
+    /// Create the target table if it does not exist
+    async fn ensure_table(
+        &mut self,
+        table: &str,
+        columns: &[(String, String)],
+        primary_key_columns: &[String],
+    ) -> Result<()>;
+
+    /// Choose how changed rows are selected from the source
+    fn select_changes(&self, batches: BatchReader, columns: &[(String, String)]) -> BatchReader;
+
+    /// Convert a source row for this target
+    fn convert_row(&self, row: &Row, columns: &[(String, String)]) -> Result<Self::Values>;
+
+    /// Upsert a batch of rows, returning the number written
+    async fn apply_batch(
+        &mut self,
+        table: &str,
+        primary_key_columns: &[String],
+        all_columns: &[String],
+        rows: Vec<Self::Values>,
+    ) -> Result<u64>;
+
+    /// Release the target connection once a cycle is done
+    async fn close(self) -> Result<()>
+    where
+        Self: Sized,
+    {
+        Ok(())
+    }
+}
+
+/// Configuration for xmin sync into an experimental target
+#[derive(Debug, Clone)]
+pub struct TargetSyncConfig {
+    /// Source tables to replicate as `(schema, table)`, see [`tables_from_filter`]
+    pub tables: Vec<(String, String)>,
+    /// Maximum rows read from the source per batch
+    pub batch_size: usize,
+    /// Path to the xmin sync state for this target
+    pub state_path: PathBuf,
+    /// Interval between re-sync cycles
+    pub sync_interval: Duration,
+}
+
+impl TargetSyncConfig {
+    /// Defaults with no tables, keeping state at `state_path`
+    pub fn new(state_path: PathBuf) -> Self {
+        Self {
+            tables: Vec::new(),
+            batch_size: 10_000,
+            state_path,
+            sync_interval: Duration::from_secs(3600),
+        }
+    }
+}
+
+/// Copy every table changed since `state` into the target and save the new state
+///
+/// A table that fails is reported in the returned stats and the others still
+/// sync. The writer is closed once all tables are done.
+pub async fn run_cycle<W: TargetWriter>(
+    source_url: &str,
+    mut writer: W,
+    config: &TargetSyncConfig,
+    mut state: SyncState,
+) -> Result<SyncStats> {
+    let start = std::time::Instant::now();
+    let mut stats = SyncStats::default();
+
+    let source_client = crate::postgres::connect_with_retry(source_url)
+        .await
+        .context("Failed to connect to source database")?;
+    let reader = XminReader::new(&source_client);
+
+    for (schema, table) in &config.tables {
+        match sync_table(&reader, &mut writer, &mut state, config, schema, table).await {
+            Ok(rows) => {
+                stats.tables_synced += 1;
+                stats.rows_synced += rows;
+            }
+            Err(e) => {
+                tracing::error!("Failed to sync {}.{}: {:?}", schema, table, e);
+                stats
+                    .errors
+                    .push(format!("Failed to sync {}.{}: {}", schema, table, e));
+            }
+        }
+    }
+
+    state.save(&config.state_path).await?;
+    writer.close().await?;
+
+    stats.duration_ms = start.elapsed().as_millis() as u64;
+    Ok(stats)
+}
+
+/// Run `cycle` every `interval` until a shutdown signal is received
+///
+/// A failed cycle is logged and retried on the next tick.
+pub async fn run_sync<F, Fut>(
+    name: &str,
+    interval: Duration,
+    mut shutdown: tokio::sync::broadcast::Receiver<()>,
+    mut cycle: F,
+) -> Result<()>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<SyncStats>>,
+{
+    let mut ticker = tokio::time::interval(interval);
+    let mut cycles = 0u64;
+
+    loop {
+        tokio::select! {
+            biased;
+            _ = shutdown.recv() => {
+                tracing::info!("Shutdown signal received, stopping {} sync", name);
+                break;
+            }
+            _ = ticker.tick() => {
+                cycles += 1;
+                match cycle().await {
+                    Ok(stats) => tracing::info!(
+                        "{} sync cycle {} completed: {} tables, {} rows in {}ms",
+                        name,
+                        cycles,
+                        stats.tables_synced,
+                        stats.rows_synced,
+                        stats.duration_ms
+                    ),
+                    Err(e) => tracing::error!("{} sync cycle {} failed: {:?}", name, cycles, e),
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Resolve the source tables of `source_url` that `filter` selects
+///
+/// Both `init` and `sync` of an experimental target go through here, so
+/// filter patterns select the same tables in each. Targets have no schemas,
+/// so each table is created under its bare name. Fails if an include pattern
+/// matches nothing, or if two selected tables in different schemas share a
+/// name.
+pub async fn tables_from_filter(
+    source_url: &str,
+    filter: &ReplicationFilter,
+) -> Result<Vec<(String, String)>> {
+    let filter = filter.resolve_patterns(source_url).await?;
+    let db_name = crate::utils::parse_postgres_url(source_url)?.database;
+    let source_client = crate::postgres::connect_with_retry(source_url)
+        .await
+        .context("Failed to connect to source database")?;
+    let tables = crate::migration::list_tables(&source_client)
+        .await?
+        .into_iter()
+        .map(|table| (table.schema, table.name))
+        .collect();
+    select_tables(tables, &filter, &db_name)
+}
+
+fn select_tables(
+    tables: Vec<(String, String)>,
+    filter: &ReplicationFilter,
+    db_name: &str,
+) -> Result<Vec<(String, String)>> {
+    let selected: Vec<(String, String)> = tables
+        .into_iter()
+        .filter(|(schema, table)| {
+            let filter_name = if schema == "public" {
+                table.clone()
+            } else {
+                format!("{}.{}", schema, table)
+            };
+            filter.should_replicate_table(db_name, &filter_name)
+        })
+        .collect();
+    for (i, (schema, table)) in selected.iter().enumerate() {
+        if let Some((other, _)) = selected[..i].iter().find(|(_, name)| name == table) {
+            bail!(
+                "Tables {}.{} and {}.{} would both be written to target table '{}'; \
+                 exclude one of them with --exclude-tables or --exclude-schemas",
+                other,
+                table,
+                schema,
+                table,
+                table
+            );
+        }
+    }
+    Ok(selected)
+}
+
+async fn sync_table<W: TargetWriter>(
+    reader: &XminReader<'_>,
+    writer: &mut W,
+    state: &mut SyncState,
+    config: &TargetSyncConfig,
+    schema: &str,
+    table: &str,
+) -> Result<u64> {
+    crate::jsonb::validate_table_name(table)
+        .with_context(|| format!("Invalid table name for {} target", W::NAME))?;
+
+    let columns = get_table_columns(reader.client(), schema, table).await?;
+    let pk_columns = get_primary_key_columns(reader.client(), schema, table).await?;
+    let column_names: Vec<String> = columns.iter().map(|(name, _)| name.clone()).collect();
+
+    writer.ensure_table(table, &columns, &pk_columns).await?;
+
+    let stored_xmin = state.get_or_create_table(schema, table).last_xmin;
+    let current_xmin = reader.get_current_xmin().await?;
+    let since_xmin =
+        if detect_wraparound(stored_xmin, current_xmin) == WraparoundCheck::WraparoundDetected {
+            tracing::warn!(
+                "xmin wraparound detected for {}.{} - performing full table sync",
+                schema,
+                table
+            );
+            0
+        } else {
+            stored_xmin
+        };
+
+    let batches = reader
+        .read_changes_batched(schema, table, &column_names, since_xmin, config.batch_size)
+        .await?;
+    let mut batch_reader = writer.select_changes(batches, &columns);
+
+    let mut total_rows = 0u64;
+    while let Some((rows, batch_max_xmin)) = reader.fetch_batch(&mut batch_reader).await? {
+        let values = rows
+            .iter()
+            .map(|row| writer.convert_row(row, &columns))
+            .collect::<Result<Vec<_>>>()?;
+        let written = writer
+            .apply_batch(table, &pk_columns, &column_names, values)
+            .await?;
+        total_rows += written;
+        state.update_table(schema, table, batch_max_xmin, written);
+    }
+
+    if total_rows > 0 {
+        tracing::info!(
+            "Synced {} row(s) from {}.{} to {}",
+            total_rows,
+            schema,
+            table,
+            W::NAME
+        );
+    }
+
+    Ok(total_rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    use std::sync::{Arc, Mutex};
+
+    /// Rows written per table, keyed by the first column's text
+    type Tables = Arc<Mutex<BTreeMap<String, BTreeMap<String, String>>>>;
+
+    /// Keeps upserted rows in memory and can be told to fail one table
+    struct MemoryWriter {
+        tables: Tables,
+        failing_table: Option<String>,
+    }
+
+    #[async_trait]
+    impl TargetWriter for MemoryWriter {
+        type Values = (String, String);
+        const NAME: &'static str = "memory";
+
+        async fn ensure_table(
+            &mut self,
+            table: &str,
+            _columns: &[(String, String)],
+            _primary_key_columns: &[String],
+        ) -> Result<()> {
+            if self.failing_table.as_deref() == Some(table) {
+                anyhow::bail!("table '{}' is read-only", table);
+            }
+            self.tables
+                .lock()
+                .unwrap()
+                .entry(table.to_string())
+                .or_default();
+            Ok(())
+        }
+
+        fn select_changes(
+            &self,
+            batches: BatchReader,
+            columns: &[(String, String)],
+        ) -> BatchReader {
+            batches.with_select_list(
+                columns
+                    .iter()
+                    .map(|(name, _)| format!("\"{}\"::text", name))
+                    .collect(),
+            )
+        }
+
+        fn convert_row(&self, row: &Row, _columns: &[(String, String)]) -> Result<Self::Values> {
+            Ok((row.get(0), row.get(1)))
+        }
+
+        async fn apply_batch(
+            &mut self,
+            table: &str,
+            _primary_key_columns: &[String],
+            _all_columns: &[String],
+            rows: Vec<Self::Values>,
+        ) -> Result<u64> {
+            let written = rows.len() as u64;
+            self.tables
+                .lock()
+                .unwrap()
+                .entry(table.to_string())
+                .or_default()
+                .extend(rows);
+            Ok(written)
+        }
+    }
+
+    fn qualified(tables: &[(&str, &str)]) -> Vec<(String, String)> {
+        tables
+            .iter()
+            .map(|(schema, table)| (schema.to_string(), table.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_select_tables_keeps_schemas_and_applies_excludes() {
+        let tables = qualified(&[
+            ("public", "users"),
+            ("public", "sessions"),
+            ("other", "foo"),
+            ("audit", "log"),
+        ]);
+        let filter =
+            ReplicationFilter::new(None, None, None, Some(vec!["app.sessions".to_string()]))
+                .unwrap()
+                .with_schemas(None, Some(vec!["audit".to_string()]))
+                .unwrap();
+        assert_eq!(
+            select_tables(tables.clone(), &filter, "app").unwrap(),
+            qualified(&[("public", "users"), ("other", "foo")])
+        );
+
+        let filter =
+            ReplicationFilter::new(None, None, Some(vec!["app.other.foo".to_string()]), None)
+                .unwrap();
+        assert_eq!(
+            select_tables(tables, &filter, "app").unwrap(),
+            qualified(&[("other", "foo")])
+        );
+    }
+
+    #[test]
+    fn test_select_tables_rejects_same_name_in_two_schemas() {
+        let tables = qualified(&[("public", "users"), ("other", "users")]);
+        let err = select_tables(tables, &ReplicationFilter::empty(), "app").unwrap_err();
+        assert!(err.to_string().contains("public.users and other.users"));
+    }
+
+    #[tokio::test]
+    async fn test_run_sync_keeps_going_after_failed_cycle() {
+        let (shutdown_tx, shutdown_rx) = tokio::sync::broadcast::channel::<()>(1);
+        let mut calls = 0;
+        run_sync("memory", Duration::from_millis(1), shutdown_rx, || {
+            calls += 1;
+            let outcome = if calls == 1 {
+                Err(anyhow::anyhow!("target unavailable"))
+            } else {
+                shutdown_tx.send(()).unwrap();
+                Ok(SyncStats::default())
+            };
+            async move { outcome }
+        })
+        .await
+        .unwrap();
+        assert_eq!(calls, 2);
+    }
+
+    #[tokio::test]
+    async fn test_run_sync_stops_before_first_cycle_on_shutdown() {
+        let (shutdown_tx, shutdown_rx) = tokio::sync::broadcast::channel::<()>(1);
+        shutdown_tx.send(()).unwrap();
+        let mut calls = 0;
+        run_sync("memory", Duration::from_millis(1), shutdown_rx, || {
+            calls += 1;
+            async { Ok(SyncStats::default()) }
+        })
+        .await
+        .unwrap();
+        assert_eq!(calls, 0);
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_tables_from_filter_resolves_patterns() {
+        let source_url = std::env::var("TEST_SOURCE_URL").unwrap();
+        let db_name = crate::utils::parse_postgres_url(&source_url)
+            .unwrap()
+            .database;
+        let client = crate::postgres::connect(&source_url).await.unwrap();
+        client
+            .batch_execute(
+                "CREATE SCHEMA IF NOT EXISTS xmin_filter_test;
+                 DROP TABLE IF EXISTS xmin_filter_test.items, xmin_filter_test.itemsets,
+                     xmin_filter_test.orders;
+                 CREATE TABLE xmin_filter_test.items (id int PRIMARY KEY);
+                 CREATE TABLE xmin_filter_test.itemsets (id int PRIMARY KEY);
+                 CREATE TABLE xmin_filter_test.orders (id int PRIMARY KEY);",
+            )
+            .await
+            .unwrap();
+
+        let include = |pattern: &str| {
+            ReplicationFilter::new(
+                None,
+                None,
+                Some(vec![format!("{}.xmin_filter_test.{}", db_name, pattern)]),
+                None,
+            )
+            .unwrap()
+        };
+        assert_eq!(
+            tables_from_filter(&source_url, &include("item*"))
+                .await
+                .unwrap(),
+            qualified(&[
+                ("xmin_filter_test", "items"),
+                ("xmin_filter_test", "itemsets")
+            ])
+        );
+        let err = tables_from_filter(&source_url, &include("invoice*"))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("invoice*"));
+
+        client
+            .batch_execute("DROP SCHEMA xmin_filter_test CASCADE")
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_run_cycle_applies_only_changed_rows() {
+        let source_url = std::env::var("TEST_SOURCE_URL").unwrap();
+        let client = crate::postgres::connect(&source_url).await.unwrap();
+        client
+            .batch_execute(
+                "CREATE SCHEMA IF NOT EXISTS xmin_target_test;
+                 DROP TABLE IF EXISTS xmin_target_test.items, xmin_target_test.locked;
+                 CREATE TABLE xmin_target_test.items (id int PRIMARY KEY, name text);
+                 CREATE TABLE xmin_target_test.locked (id int PRIMARY KEY, name text);
+                 INSERT INTO xmin_target_test.items VALUES (1, 'a'), (2, 'b');
+                 INSERT INTO xmin_target_test.locked VALUES (1, 'x');",
+            )
+            .await
+            .unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let config = TargetSyncConfig {
+            tables: vec![
+                ("xmin_target_test".to_string(), "items".to_string()),
+                ("xmin_target_test".to_string(), "locked".to_string()),
+            ],
+            ..TargetSyncConfig::new(dir.path().join("state.json"))
+        };
+        let tables = Tables::default();
+        let writer = || MemoryWriter {
+            tables: tables.clone(),
+            failing_table: Some("locked".to_string()),
+        };
+
+        let state = SyncState::new(&source_url, "memory");
+        let stats = run_cycle(&source_url, writer(), &config, state)
+            .await
+            .unwrap();
+        assert_eq!(stats.tables_synced, 1);
+        assert_eq!(stats.rows_synced, 2);
+        assert_eq!(stats.errors.len(), 1);
+        assert!(stats.errors[0].contains("xmin_target_test.locked"));
+
+        client
+            .batch_execute("UPDATE xmin_target_test.items SET name = 'c' WHERE id = 2")
+            .await
+            .unwrap();
+        let state = SyncState::load(&config.state_path).await.unwrap();
+        let stats = run_cycle(&source_url, writer(), &config, state)
+            .await
+            .unwrap();
+        assert_eq!(stats.rows_synced, 1);
+        assert_eq!(
+            tables.lock().unwrap()["items"].get("2").map(String::as_str),
+            Some("c")
+        );
+
+        client
+            .batch_execute("DROP SCHEMA xmin_target_test CASCADE")
+            .await
+            .unwrap();
+    }
+}
@@ -0,0 +1,155 @@
+// ABOUTME: Background verifier for xmin-based sync - compares table checksums while syncing
+// ABOUTME: Paces reads to a byte rate and confirms drift across a sync cycle before repair
+
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio_postgres::types::Type;
+use tokio_postgres::Client;
+
+use crate::postgres::pooler;
+
+/// A table's checksum comparison, applied to its sync state by the next cycle
+#[derive(Debug, Clone)]
+pub struct TableCheck {
+    pub schema: String,
+    pub table: String,
+    pub checked_at: chrono::DateTime<chrono::Utc>,
+    /// The table was still out of sync after a sync cycle and a repair is queued
+    pub drift: bool,
+}
+
+/// What a checksum comparison means once earlier ones are taken into account
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Finding {
+    /// Source and target match
+    Match,
+    /// They differ, possibly only by changes the next sync cycle will apply
+    Suspect,
+    /// They still differ after a sync cycle ran since the first mismatch
+    Drift,
+}
+
+/// Tables whose checksums differed, keyed by `database/schema.table`
+///
+/// Rows changed on the source since the last cycle make a live table differ
+/// from its copy, so a mismatch is only drift if it is still there after a
+/// sync cycle has completed.
+#[derive(Debug, Default)]
+pub struct DriftTracker {
+    /// Sync cycles completed when each suspect first differed
+    suspects: HashMap<String, u64>,
+}
+
+impl DriftTracker {
+    /// Record a comparison made when `cycles_completed` sync cycles had finished
+    pub fn observe(&mut self, key: &str, matches: bool, cycles_completed: u64) -> Finding {
+        if matches {
+            self.suspects.remove(key);
+            return Finding::Match;
+        }
+        match self.suspects.get(key) {
+            Some(&since) if since < cycles_completed => {
+                self.suspects.remove(key);
+                Finding::Drift
+            }
+            Some(_) => Finding::Suspect,
+            None => {
+                self.suspects.insert(key.to_string(), cycles_completed);
+                Finding::Suspect
+            }
+        }
+    }
+}
+
+/// How long to wait after reading `bytes` in `elapsed` to average `rate` bytes per second
+pub fn pace(rate: u64, bytes: u64, elapsed: Duration) -> Duration {
+    if rate == 0 {
+        return Duration::ZERO;
+    }
+    Duration::from_secs_f64(bytes as f64 / rate as f64).saturating_sub(elapsed)
+}
+
+/// Parse a verification rate such as `5MB`, `512KB/s`, or a plain number of bytes
+///
+/// Units are powers of 1024, as [`crate::migration::format_bytes`] prints them.
+pub fn parse_rate(value: &str) -> Result<u64> {
+    let trimmed = value.trim();
+    let trimmed = trimmed.strip_suffix("/s").unwrap_or(trimmed).trim();
+    let split = trimmed
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(trimmed.len());
+    let (number, unit) = trimmed.split_at(split);
+    let number: f64 = number
+        .parse()
+        .with_context(|| format!("Invalid rate '{}': expected e.g. 5MB", value))?;
+    let multiplier: u64 = match unit.trim().to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" => 1024,
+        "M" | "MB" => 1024 * 1024,
+        "G" | "GB" => 1024 * 1024 * 1024,
+        other => anyhow::bail!(
+            "Invalid rate '{}': unknown unit '{}' (use B, KB, MB, or GB)",
+            value,
+            other
+        ),
+    };
+    let rate = (number * multiplier as f64) as u64;
+    if rate == 0 {
+        anyhow::bail!("Invalid rate '{}': must be greater than zero", value);
+    }
+    Ok(rate)
+}
+
+/// Size of a table's heap on disk, which a checksum reads in full
+pub async fn table_bytes(client: &Client, schema: &str, table: &str) -> Result<u64> {
+    let row = pooler::query_one(
+        client,
+        "SELECT pg_relation_size(format('%I.%I', $1::text, $2::text)::regclass)",
+        &[(&schema, Type::TEXT), (&table, Type::TEXT)],
+    )
+    .await
+    .with_context(|| format!("Failed to get size of {}.{}", schema, table))?;
+    Ok(row.get::<_, i64>(0).max(0) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_drift_needs_a_cycle_between_mismatches() {
+        let mut tracker = DriftTracker::default();
+        assert_eq!(tracker.observe("app/public.t", false, 3), Finding::Suspect);
+        // No sync cycle has run since, so the source may simply be ahead
+        assert_eq!(tracker.observe("app/public.t", false, 3), Finding::Suspect);
+        assert_eq!(tracker.observe("app/public.t", false, 4), Finding::Drift);
+        // A repaired table starts over
+        assert_eq!(tracker.observe("app/public.t", false, 4), Finding::Suspect);
+        assert_eq!(tracker.observe("app/public.t", true, 5), Finding::Match);
+        assert_eq!(tracker.observe("app/public.t", false, 6), Finding::Suspect);
+    }
+
+    #[test]
+    fn test_pace() {
+        let mb = 1024 * 1024;
+        assert_eq!(
+            pace(5 * mb, 10 * mb, Duration::from_millis(500)),
+            Duration::from_millis(1500)
+        );
+        assert_eq!(pace(5 * mb, mb, Duration::from_secs(1)), Duration::ZERO);
+        assert_eq!(pace(0, mb, Duration::ZERO), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_parse_rate() {
+        assert_eq!(parse_rate("5MB").unwrap(), 5 * 1024 * 1024);
+        assert_eq!(parse_rate("5 mb/s").unwrap(), 5 * 1024 * 1024);
+        assert_eq!(parse_rate("512KB").unwrap(), 512 * 1024);
+        assert_eq!(parse_rate("1.5G").unwrap(), 3 * 512 * 1024 * 1024);
+        assert_eq!(parse_rate("4096").unwrap(), 4096);
+        assert!(parse_rate("fast").is_err());
+        assert!(parse_rate("5TB").is_err());
+        assert!(parse_rate("0MB").is_err());
+    }
+}
@@ -3,9 +3,13 @@
 
 use anyhow::{Context, Result};
 use rust_decimal::Decimal;
-use tokio_postgres::types::ToSql;
+use std::collections::HashMap;
+use tokio_postgres::types::{ToSql, Type};
 use tokio_postgres::{Client, Row};
 
+use crate::postgres::pooler::{self, Param};
+use crate::postgres::{pgvector, postgis};
+
 /// Writes changes to the target PostgreSQL database using upsert operations.
 ///
 /// The ChangeWriter handles batched upserts within transactions for efficiency
@@ -13,12 +17,120 @@ use tokio_postgres::{Client, Row};
 /// based on table schema.
 pub struct ChangeWriter<'a> {
     client: &'a Client,
+    identity_columns: Vec<String>,
+    converted_columns: Vec<(String, ColumnConversion)>,
+    value_types: Vec<Type>,
+}
+
+/// Conversion applied to a column's parameter placeholder in the upsert
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ColumnConversion {
+    /// PostGIS value sent as EWKB bytes
+    Spatial(postgis::SpatialType),
+    /// pgvector value sent as text; holds the type name (`vector`, `halfvec`, ...)
+    Vector(String),
+    /// Value sent as the source column's type (`udt_name`) and cast to the
+    /// type a column override gave the target column
+    Cast { from: String, to: String },
+}
+
+impl ColumnConversion {
+    fn wrap(&self, placeholder: &str) -> String {
+        match self {
+            ColumnConversion::Spatial(t) => postgis::insert_expression(placeholder, *t),
+            ColumnConversion::Vector(type_name) => {
+                pgvector::insert_expression(placeholder, type_name)
+            }
+            ColumnConversion::Cast { from, to } => {
+                format!(
+                    "{}::{}::{}",
+                    placeholder,
+                    crate::utils::quote_ident(from),
+                    to
+                )
+            }
+        }
+    }
+}
+
+/// Find the columns whose values need a [`ColumnConversion`] when written.
+///
+/// `columns` are `(column_name, udt_name)` pairs as returned by [`get_table_columns`].
+pub fn column_conversions(columns: &[(String, String)]) -> Vec<(String, ColumnConversion)> {
+    let spatial = postgis::spatial_columns(columns)
+        .into_iter()
+        .map(|(name, t)| (name, ColumnConversion::Spatial(t)));
+    let vector = pgvector::vector_columns(columns)
+        .into_iter()
+        .map(|(name, udt)| (name, ColumnConversion::Vector(udt)));
+    spatial.chain(vector).collect()
 }
 
 impl<'a> ChangeWriter<'a> {
     /// Create a new ChangeWriter for the given PostgreSQL client connection.
     pub fn new(client: &'a Client) -> Self {
-        Self { client }
+        Self {
+            client,
+            identity_columns: Vec::new(),
+            converted_columns: Vec::new(),
+            value_types: Vec::new(),
+        }
+    }
+
+    /// Set the `GENERATED ALWAYS AS IDENTITY` columns being written.
+    ///
+    /// When non-empty, inserts use `OVERRIDING SYSTEM VALUE` so source values are
+    /// kept, and these columns are left out of the `ON CONFLICT` update list
+    /// (PostgreSQL only allows updating them to DEFAULT).
+    pub fn with_identity_columns(mut self, identity_columns: Vec<String>) -> Self {
+        self.identity_columns = identity_columns;
+        self
+    }
+
+    /// Set the extension-typed columns being written (see [`column_conversions`]).
+    ///
+    /// Values must be read with
+    /// [`BatchReader::with_column_types`](super::reader::BatchReader::with_column_types)
+    /// using EWKB: PostGIS values are restored with `ST_GeomFromEWKB`, which keeps
+    /// the SRID, and pgvector values are cast back from text.
+    pub fn with_column_conversions(
+        mut self,
+        converted_columns: Vec<(String, ColumnConversion)>,
+    ) -> Self {
+        self.converted_columns = converted_columns;
+        self
+    }
+
+    /// Set the types each row's values are sent as, in order.
+    ///
+    /// Behind a transaction-mode pooler statements are sent unnamed with typed
+    /// parameters (see [`pooler::query`]), so rows cannot be written there
+    /// without these. The types of the source rows' columns fit.
+    pub fn with_value_types(mut self, value_types: Vec<Type>) -> Self {
+        self.value_types = value_types;
+        self
+    }
+
+    /// Each row's values paired with the type it is sent as
+    fn typed_params<'v>(
+        &self,
+        rows: &'v [Vec<Box<dyn ToSql + Sync + Send>>],
+    ) -> Result<Vec<Param<'v>>> {
+        let width = rows.first().map_or(0, Vec::len);
+        if pooler::transaction_pooling() && self.value_types.len() != width {
+            anyhow::bail!(
+                "Cannot write rows through a transaction-mode pooler without their value types"
+            );
+        }
+        Ok(rows
+            .iter()
+            .flat_map(|row| {
+                row.iter().enumerate().map(|(idx, value)| {
+                    let ty = self.value_types.get(idx).cloned().unwrap_or(Type::UNKNOWN);
+                    (value.as_ref() as &(dyn ToSql + Sync), ty)
+                })
+            })
+            .collect())
     }
 
     /// Get a reference to the underlying client.
@@ -30,9 +142,12 @@ impl<'a> ChangeWriter<'a> {
 
     /// Apply a batch of rows to a table using upsert (INSERT ... ON CONFLICT DO UPDATE).
     ///
-    /// Uses batching internally to stay within PostgreSQL's parameter limits.
-    /// Each batch is executed as a separate query (PostgreSQL auto-commits).
-    /// Automatically retries with smaller batches if "value too large" errors occur.
+    /// The whole batch runs in one transaction. Rows are sent in chunks to stay
+    /// within PostgreSQL's parameter limits, each chunk behind its own savepoint.
+    /// If a chunk fails, it is rolled back to the savepoint and its rows are
+    /// retried one at a time; rows that still fail are quarantined to
+    /// `_replicator_dead_letters` instead of aborting the batch.
+    /// Automatically retries with smaller chunks if "value too large" errors occur.
     ///
     /// # Arguments
     ///
@@ -44,7 +159,7 @@ impl<'a> ChangeWriter<'a> {
     ///
     /// # Returns
     ///
-    /// The number of rows affected.
+    /// The number of rows affected (quarantined rows are not counted).
     pub async fn apply_batch(
         &self,
         schema: &str,
@@ -57,6 +172,100 @@ impl<'a> ChangeWriter<'a> {
             return Ok(0);
         }
 
+        self.apply_in_transaction(schema, table, primary_key_columns, all_columns, &rows, None)
+            .await
+    }
+
+    /// Apply a batch of rows and record the source position in one transaction.
+    ///
+    /// The upserts and the `_replicator_progress` update commit together, so after
+    /// a crash the recorded position always matches the data in the target and
+    /// the batch can be re-applied without skipping or double-counting rows.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn apply_batch_with_progress(
+        &self,
+        schema: &str,
+        table: &str,
+        primary_key_columns: &[String],
+        all_columns: &[String],
+        rows: Vec<Vec<Box<dyn ToSql + Sync + Send>>>,
+        source_id: &str,
+        position: &str,
+    ) -> Result<u64> {
+        self.apply_in_transaction(
+            schema,
+            table,
+            primary_key_columns,
+            all_columns,
+            &rows,
+            Some((source_id, position)),
+        )
+        .await
+    }
+
+    /// Run chunked upserts (and optionally a progress update) in one transaction.
+    async fn apply_in_transaction(
+        &self,
+        schema: &str,
+        table: &str,
+        primary_key_columns: &[String],
+        all_columns: &[String],
+        rows: &[Vec<Box<dyn ToSql + Sync + Send>>],
+        progress: Option<(&str, &str)>,
+    ) -> Result<u64> {
+        self.client
+            .batch_execute("BEGIN")
+            .await
+            .context("Failed to begin apply transaction")?;
+
+        let result = async {
+            let affected = self
+                .apply_chunks(schema, table, primary_key_columns, all_columns, rows)
+                .await?;
+            if let Some((source_id, position)) = progress {
+                super::progress::record_progress(
+                    self.client,
+                    source_id,
+                    schema,
+                    table,
+                    super::progress::PositionKind::Xmin,
+                    position,
+                    affected,
+                )
+                .await?;
+            }
+            Ok::<u64, anyhow::Error>(affected)
+        }
+        .await;
+
+        match result {
+            Ok(affected) => {
+                self.client
+                    .batch_execute("COMMIT")
+                    .await
+                    .with_context(|| format!("Failed to commit batch for {}.{}", schema, table))?;
+                Ok(affected)
+            }
+            Err(e) => {
+                if let Err(rollback_err) = self.client.batch_execute("ROLLBACK").await {
+                    tracing::warn!("Failed to roll back apply transaction: {}", rollback_err);
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// Upsert rows chunk by chunk, each behind a savepoint.
+    ///
+    /// Must be called inside a transaction.
+    async fn apply_chunks(
+        &self,
+        schema: &str,
+        table: &str,
+        primary_key_columns: &[String],
+        all_columns: &[String],
+        rows: &[Vec<Box<dyn ToSql + Sync + Send>>],
+    ) -> Result<u64> {
         // PostgreSQL has a limit of ~65535 parameters per query
         // Calculate batch size based on number of columns, but cap at 100 rows
         // to avoid "value too large to transmit" errors with large JSONB/TEXT columns
@@ -68,7 +277,12 @@ impl<'a> ChangeWriter<'a> {
         let mut total_affected = 0u64;
 
         for chunk in rows.chunks(batch_size) {
-            let affected = self
+            self.client
+                .batch_execute("SAVEPOINT replicator_chunk")
+                .await
+                .context("Failed to create chunk savepoint")?;
+
+            match self
                 .execute_upsert_batch_with_retry(
                     schema,
                     table,
@@ -76,8 +290,105 @@ impl<'a> ChangeWriter<'a> {
                     all_columns,
                     chunk,
                 )
-                .await?;
-            total_affected += affected;
+                .await
+            {
+                Ok(affected) => {
+                    total_affected += affected;
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        "Chunk of {} rows failed for {}.{}, retrying rows individually: {:#}",
+                        chunk.len(),
+                        schema,
+                        table,
+                        e
+                    );
+                    self.client
+                        .batch_execute("ROLLBACK TO SAVEPOINT replicator_chunk")
+                        .await
+                        .context("Failed to roll back chunk savepoint")?;
+                    total_affected += self
+                        .apply_rows_individually(
+                            schema,
+                            table,
+                            primary_key_columns,
+                            all_columns,
+                            chunk,
+                        )
+                        .await?;
+                }
+            }
+
+            self.client
+                .batch_execute("RELEASE SAVEPOINT replicator_chunk")
+                .await
+                .context("Failed to release chunk savepoint")?;
+        }
+
+        Ok(total_affected)
+    }
+
+    /// Retry rows one at a time, quarantining any that still fail.
+    async fn apply_rows_individually(
+        &self,
+        schema: &str,
+        table: &str,
+        primary_key_columns: &[String],
+        all_columns: &[String],
+        rows: &[Vec<Box<dyn ToSql + Sync + Send>>],
+    ) -> Result<u64> {
+        let mut total_affected = 0u64;
+        let mut quarantined = 0usize;
+
+        for row in rows {
+            self.client
+                .batch_execute("SAVEPOINT replicator_row")
+                .await
+                .context("Failed to create row savepoint")?;
+
+            match self
+                .execute_upsert_batch(
+                    schema,
+                    table,
+                    primary_key_columns,
+                    all_columns,
+                    std::slice::from_ref(row),
+                )
+                .await
+            {
+                Ok(affected) => total_affected += affected,
+                Err(e) => {
+                    self.client
+                        .batch_execute("ROLLBACK TO SAVEPOINT replicator_row")
+                        .await
+                        .context("Failed to roll back row savepoint")?;
+                    super::dead_letter::quarantine_row(
+                        self.client,
+                        schema,
+                        table,
+                        all_columns,
+                        row,
+                        &self.value_types,
+                        &format!("{:#}", e),
+                    )
+                    .await?;
+                    quarantined += 1;
+                }
+            }
+
+            self.client
+                .batch_execute("RELEASE SAVEPOINT replicator_row")
+                .await
+                .context("Failed to release row savepoint")?;
+        }
+
+        if quarantined > 0 {
+            tracing::warn!(
+                "Quarantined {} row(s) from {}.{} to _replicator_dead_letters",
+                quarantined,
+                schema,
+                table
+            );
         }
 
         Ok(total_affected)
@@ -156,17 +467,20 @@ impl<'a> ChangeWriter<'a> {
             return Ok(0);
         }
 
-        let query = build_upsert_query(schema, table, primary_key_columns, all_columns, rows.len());
+        let query = build_upsert_query(
+            schema,
+            table,
+            primary_key_columns,
+            all_columns,
+            &self.identity_columns,
+            &self.converted_columns,
+            rows.len(),
+        );
 
         // Flatten all row values into a single params vector
-        let params: Vec<&(dyn ToSql + Sync)> = rows
-            .iter()
-            .flat_map(|row| row.iter().map(|v| v.as_ref() as &(dyn ToSql + Sync)))
-            .collect();
+        let params = self.typed_params(rows)?;
 
-        let affected = self
-            .client
-            .execute(&query, &params)
+        let affected = pooler::execute(self.client, &query, &params)
             .await
             .with_context(|| format!("Failed to upsert batch into {}.{}", schema, table))?;
 
@@ -184,16 +498,20 @@ impl<'a> ChangeWriter<'a> {
         all_columns: &[String],
         values: Vec<Box<dyn ToSql + Sync + Send>>,
     ) -> Result<u64> {
-        let query = build_upsert_query(schema, table, primary_key_columns, all_columns, 1);
+        let query = build_upsert_query(
+            schema,
+            table,
+            primary_key_columns,
+            all_columns,
+            &self.identity_columns,
+            &self.converted_columns,
+            1,
+        );
 
-        let params: Vec<&(dyn ToSql + Sync)> = values
-            .iter()
-            .map(|v| v.as_ref() as &(dyn ToSql + Sync))
-            .collect();
+        let rows = [values];
+        let params = self.typed_params(&rows)?;
 
-        let affected = self
-            .client
-            .execute(&query, &params)
+        let affected = pooler::execute(self.client, &query, &params)
             .await
             .with_context(|| format!("Failed to upsert row into {}.{}", schema, table))?;
 
@@ -241,21 +559,72 @@ impl<'a> ChangeWriter<'a> {
             return Ok(0);
         }
 
-        let query = build_delete_query(schema, table, primary_key_columns, pk_values.len());
+        let query = build_delete_query(schema, table, primary_key_columns, &[], pk_values.len());
 
-        let params: Vec<&(dyn ToSql + Sync)> = pk_values
-            .iter()
-            .flat_map(|row| row.iter().map(|v| v.as_ref() as &(dyn ToSql + Sync)))
-            .collect();
+        let params = self.typed_params(pk_values)?;
 
-        let deleted = self
-            .client
-            .execute(&query, &params)
+        let deleted = pooler::execute(self.client, &query, &params)
             .await
             .with_context(|| format!("Failed to delete rows from {}.{}", schema, table))?;
 
         Ok(deleted)
     }
+
+    /// Delete rows by primary key values given as text.
+    ///
+    /// The reconciler reads and compares keys as text, so each value is cast
+    /// back to its column's type on the target, which keeps the primary key
+    /// index usable.
+    pub async fn delete_rows_by_text(
+        &self,
+        schema: &str,
+        table: &str,
+        primary_key_columns: &[String],
+        pk_values: &[Vec<String>],
+    ) -> Result<u64> {
+        if pk_values.is_empty() {
+            return Ok(0);
+        }
+
+        let rows = pooler::query(
+            self.client,
+            "SELECT a.attname, format_type(a.atttypid, a.atttypmod)
+             FROM pg_attribute a
+             WHERE a.attrelid = format('%I.%I', $1::text, $2::text)::regclass
+               AND a.attname = ANY($3::text[])",
+            &[
+                (&schema, Type::TEXT),
+                (&table, Type::TEXT),
+                (&primary_key_columns, Type::TEXT_ARRAY),
+            ],
+        )
+        .await
+        .with_context(|| format!("Failed to get key types for {}.{}", schema, table))?;
+        let types: HashMap<String, String> = rows.iter().map(|r| (r.get(0), r.get(1))).collect();
+        let pk_types = primary_key_columns
+            .iter()
+            .map(|c| {
+                types
+                    .get(c)
+                    .cloned()
+                    .with_context(|| format!("Column {} not found in {}.{}", c, schema, table))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut total_deleted = 0u64;
+        for chunk in pk_values.chunks(1000) {
+            let query =
+                build_delete_query(schema, table, primary_key_columns, &pk_types, chunk.len());
+            let params: Vec<Param> = chunk
+                .iter()
+                .flat_map(|row| row.iter().map(|v| (v as _, Type::TEXT)))
+                .collect();
+            total_deleted += pooler::execute(self.client, &query, &params)
+                .await
+                .with_context(|| format!("Failed to delete rows from {}.{}", schema, table))?;
+        }
+        Ok(total_deleted)
+    }
 }
 
 /// Build an upsert query for the given table schema and batch size.
@@ -268,11 +637,17 @@ impl<'a> ChangeWriter<'a> {
 ///   "col2" = EXCLUDED."col2",
 ///   "col3" = EXCLUDED."col3"
 /// ```
+///
+/// When `identity_columns` is non-empty, `OVERRIDING SYSTEM VALUE` is added
+/// after the column list and those columns are never updated on conflict.
+/// Placeholders for `converted_columns` are wrapped per [`ColumnConversion`].
 fn build_upsert_query(
     schema: &str,
     table: &str,
     primary_key_columns: &[String],
     all_columns: &[String],
+    identity_columns: &[String],
+    converted_columns: &[(String, ColumnConversion)],
     num_rows: usize,
 ) -> String {
     // Quote identifiers to handle reserved words and special characters
@@ -285,19 +660,36 @@ fn build_upsert_query(
 
     // Build VALUES placeholders: ($1, $2, $3), ($4, $5, $6), ...
     let num_cols = all_columns.len();
+    let conversions: Vec<Option<&ColumnConversion>> = all_columns
+        .iter()
+        .map(|c| {
+            converted_columns
+                .iter()
+                .find(|(name, _)| name == c)
+                .map(|(_, conversion)| conversion)
+        })
+        .collect();
     let value_rows: Vec<String> = (0..num_rows)
         .map(|row_idx| {
-            let placeholders: Vec<String> = (0..num_cols)
-                .map(|col_idx| format!("${}", row_idx * num_cols + col_idx + 1))
+            let placeholders: Vec<String> = conversions
+                .iter()
+                .enumerate()
+                .map(|(col_idx, conversion)| {
+                    let placeholder = format!("${}", row_idx * num_cols + col_idx + 1);
+                    match conversion {
+                        Some(c) => c.wrap(&placeholder),
+                        None => placeholder,
+                    }
+                })
                 .collect();
             format!("({})", placeholders.join(", "))
         })
         .collect();
 
-    // Build UPDATE SET clause for non-PK columns
+    // Build UPDATE SET clause for non-PK, non-identity columns
     let update_columns: Vec<String> = all_columns
         .iter()
-        .filter(|c| !primary_key_columns.contains(c))
+        .filter(|c| !primary_key_columns.contains(c) && !identity_columns.contains(c))
         .map(|c| format!("\"{}\" = EXCLUDED.\"{}\"", c, c))
         .collect();
 
@@ -308,11 +700,18 @@ fn build_upsert_query(
         format!("DO UPDATE SET {}", update_columns.join(", "))
     };
 
+    let overriding = if identity_columns.is_empty() {
+        ""
+    } else {
+        " OVERRIDING SYSTEM VALUE"
+    };
+
     format!(
-        "INSERT INTO \"{}\".\"{}\" ({}) VALUES {} ON CONFLICT ({}) {}",
+        "INSERT INTO \"{}\".\"{}\" ({}){} VALUES {} ON CONFLICT ({}) {}",
         schema,
         table,
         quoted_columns.join(", "),
+        overriding,
         value_rows.join(", "),
         quoted_pk_columns.join(", "),
         update_clause
@@ -330,18 +729,26 @@ fn build_upsert_query(
 /// ```sql
 /// DELETE FROM "schema"."table" WHERE ("pk1", "pk2") IN (($1, $2), ($3, $4), ...)
 /// ```
+///
+/// With `pk_types`, values are passed as text and cast to each column's type
+/// (e.g. `$1::text::integer`).
 fn build_delete_query(
     schema: &str,
     table: &str,
     primary_key_columns: &[String],
+    pk_types: &[String],
     num_rows: usize,
 ) -> String {
     let num_pk_cols = primary_key_columns.len();
+    let placeholder = |n: usize, col_idx: usize| match pk_types.get(col_idx) {
+        Some(pk_type) => format!("${}::text::{}", n, pk_type),
+        None => format!("${}", n),
+    };
 
     if num_pk_cols == 1 {
         // Simple case: single-column primary key
         let pk_col = format!("\"{}\"", primary_key_columns[0]);
-        let placeholders: Vec<String> = (1..=num_rows).map(|i| format!("${}", i)).collect();
+        let placeholders: Vec<String> = (1..=num_rows).map(|i| placeholder(i, 0)).collect();
 
         format!(
             "DELETE FROM \"{}\".\"{}\" WHERE {} IN ({})",
@@ -360,7 +767,7 @@ fn build_delete_query(
         let value_tuples: Vec<String> = (0..num_rows)
             .map(|row_idx| {
                 let placeholders: Vec<String> = (0..num_pk_cols)
-                    .map(|col_idx| format!("${}", row_idx * num_pk_cols + col_idx + 1))
+                    .map(|col_idx| placeholder(row_idx * num_pk_cols + col_idx + 1, col_idx))
                     .collect();
                 format!("({})", placeholders.join(", "))
             })
@@ -386,16 +793,16 @@ pub async fn get_table_columns(
     schema: &str,
     table: &str,
 ) -> Result<Vec<(String, String)>> {
-    let rows = client
-        .query(
-            "SELECT column_name, udt_name
-             FROM information_schema.columns
-             WHERE table_schema = $1 AND table_name = $2
-             ORDER BY ordinal_position",
-            &[&schema, &table],
-        )
-        .await
-        .with_context(|| format!("Failed to get columns for {}.{}", schema, table))?;
+    let rows = pooler::query(
+        client,
+        "SELECT column_name, udt_name
+         FROM information_schema.columns
+         WHERE table_schema = $1 AND table_name = $2
+         ORDER BY ordinal_position",
+        &[(&schema, Type::TEXT), (&table, Type::TEXT)],
+    )
+    .await
+    .with_context(|| format!("Failed to get columns for {}.{}", schema, table))?;
 
     Ok(rows
         .iter()
@@ -415,25 +822,113 @@ pub async fn get_primary_key_columns(
     schema: &str,
     table: &str,
 ) -> Result<Vec<String>> {
-    let rows = client
-        .query(
-            "SELECT a.attname
-             FROM pg_index i
-             JOIN pg_attribute a ON a.attrelid = i.indrelid AND a.attnum = ANY(i.indkey)
-             JOIN pg_class c ON c.oid = i.indrelid
-             JOIN pg_namespace n ON n.oid = c.relnamespace
-             WHERE i.indisprimary
-               AND n.nspname = $1
-               AND c.relname = $2
-             ORDER BY array_position(i.indkey, a.attnum)",
-            &[&schema, &table],
-        )
-        .await
-        .with_context(|| format!("Failed to get primary key for {}.{}", schema, table))?;
+    let rows = pooler::query(
+        client,
+        "SELECT a.attname
+         FROM pg_index i
+         JOIN pg_attribute a ON a.attrelid = i.indrelid AND a.attnum = ANY(i.indkey)
+         JOIN pg_class c ON c.oid = i.indrelid
+         JOIN pg_namespace n ON n.oid = c.relnamespace
+         WHERE i.indisprimary
+           AND n.nspname = $1
+           AND c.relname = $2
+         ORDER BY array_position(i.indkey, a.attnum)",
+        &[(&schema, Type::TEXT), (&table, Type::TEXT)],
+    )
+    .await
+    .with_context(|| format!("Failed to get primary key for {}.{}", schema, table))?;
 
     Ok(rows.iter().map(|row| row.get(0)).collect())
 }
 
+/// Columns whose values the target database generates itself.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GeneratedColumns {
+    /// `GENERATED ALWAYS AS (expr) STORED` columns, which can never be written
+    pub stored: Vec<String>,
+    /// `GENERATED ALWAYS AS IDENTITY` columns, writable only with OVERRIDING SYSTEM VALUE
+    pub identity_always: Vec<String>,
+}
+
+impl GeneratedColumns {
+    /// Filter `columns` down to the ones that should be written to the target.
+    ///
+    /// Stored generated columns are always dropped because the target computes
+    /// them. Identity columns are kept when `overriding_system_value` is set (so
+    /// source values are preserved); otherwise non-key identity columns are left
+    /// for the target to generate. Key identity columns must always be written.
+    pub fn writable_columns(
+        &self,
+        columns: &[(String, String)],
+        primary_key_columns: &[String],
+        overriding_system_value: bool,
+    ) -> Result<Vec<(String, String)>> {
+        if !overriding_system_value {
+            if let Some(key) = self
+                .identity_always
+                .iter()
+                .find(|c| primary_key_columns.contains(c))
+            {
+                anyhow::bail!(
+                    "Primary key column '{}' is GENERATED ALWAYS AS IDENTITY on the target; \
+                     source values can only be written with OVERRIDING SYSTEM VALUE",
+                    key
+                );
+            }
+        }
+
+        Ok(columns
+            .iter()
+            .filter(|(name, _)| !self.stored.contains(name))
+            .filter(|(name, _)| overriding_system_value || !self.identity_always.contains(name))
+            .cloned()
+            .collect())
+    }
+
+    /// Identity columns that remain in `writable` and need OVERRIDING SYSTEM VALUE.
+    pub fn overridden_identity_columns(&self, writable: &[(String, String)]) -> Vec<String> {
+        writable
+            .iter()
+            .filter(|(name, _)| self.identity_always.contains(name))
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+}
+
+/// Get generated and `GENERATED ALWAYS` identity columns for a table.
+///
+/// Identity columns declared `BY DEFAULT` accept explicit values and are not
+/// reported. Returns an empty result if the table does not exist.
+pub async fn get_generated_columns(
+    client: &Client,
+    schema: &str,
+    table: &str,
+) -> Result<GeneratedColumns> {
+    let rows = pooler::query(
+        client,
+        "SELECT column_name,
+                is_generated = 'ALWAYS' AS stored,
+                is_identity = 'YES' AND identity_generation = 'ALWAYS' AS identity_always
+         FROM information_schema.columns
+         WHERE table_schema = $1 AND table_name = $2
+         ORDER BY ordinal_position",
+        &[(&schema, Type::TEXT), (&table, Type::TEXT)],
+    )
+    .await
+    .with_context(|| format!("Failed to get generated columns for {}.{}", schema, table))?;
+
+    let mut generated = GeneratedColumns::default();
+    for row in &rows {
+        let name: String = row.get(0);
+        if row.get::<_, bool>(1) {
+            generated.stored.push(name);
+        } else if row.get::<_, bool>(2) {
+            generated.identity_always.push(name);
+        }
+    }
+    Ok(generated)
+}
+
 /// Convert a tokio_postgres Row to a vector of boxed ToSql values.
 ///
 /// This is a helper for extracting values from source rows to pass to ChangeWriter.
@@ -460,7 +955,9 @@ pub fn row_to_values(
                     let val: Option<i16> = row.get(idx);
                     Box::new(val)
                 }
-                "text" | "varchar" | "bpchar" | "char" | "character" | "name" | "citext" => {
+                // pgvector types are selected as text (see BatchReader::with_column_types)
+                "text" | "varchar" | "bpchar" | "char" | "character" | "name" | "citext"
+                | "vector" | "halfvec" | "sparsevec" => {
                     let val: Option<String> = row.get(idx);
                     Box::new(val)
                 }
@@ -496,7 +993,8 @@ pub fn row_to_values(
                     let val: Option<serde_json::Value> = row.get(idx);
                     Box::new(val)
                 }
-                "bytea" => {
+                // PostGIS values are selected as EWKB (see BatchReader::with_column_types)
+                "bytea" | "geometry" | "geography" => {
                     let val: Option<Vec<u8>> = row.get(idx);
                     Box::new(val)
                 }
@@ -583,6 +1081,8 @@ mod tests {
             "users",
             &["id".to_string()],
             &["id".to_string(), "name".to_string(), "email".to_string()],
+            &[],
+            &[],
             1,
         );
 
@@ -602,6 +1102,8 @@ mod tests {
             "users",
             &["id".to_string()],
             &["id".to_string(), "name".to_string()],
+            &[],
+            &[],
             3,
         );
 
@@ -619,6 +1121,8 @@ mod tests {
                 "item_id".to_string(),
                 "quantity".to_string(),
             ],
+            &[],
+            &[],
             1,
         );
 
@@ -634,6 +1138,8 @@ mod tests {
             "tags",
             &["id".to_string()],
             &["id".to_string()],
+            &[],
+            &[],
             1,
         );
 
@@ -641,20 +1147,136 @@ mod tests {
         assert!(!query.contains("DO UPDATE SET"));
     }
 
+    #[test]
+    fn test_build_upsert_query_identity_columns() {
+        let query = build_upsert_query(
+            "public",
+            "orders",
+            &["id".to_string()],
+            &["id".to_string(), "seq".to_string(), "total".to_string()],
+            &["id".to_string(), "seq".to_string()],
+            &[],
+            1,
+        );
+
+        assert!(query.contains("(\"id\", \"seq\", \"total\") OVERRIDING SYSTEM VALUE VALUES"));
+        assert!(query.contains("DO UPDATE SET \"total\" = EXCLUDED.\"total\""));
+        assert!(!query.contains("\"seq\" = EXCLUDED"));
+    }
+
+    #[test]
+    fn test_build_upsert_query_converted_columns() {
+        let columns = vec![
+            ("id".to_string(), "int4".to_string()),
+            ("geom".to_string(), "geometry".to_string()),
+            ("area".to_string(), "geography".to_string()),
+            ("embedding".to_string(), "vector".to_string()),
+        ];
+        let column_names: Vec<String> = columns.iter().map(|(n, _)| n.clone()).collect();
+        let query = build_upsert_query(
+            "public",
+            "places",
+            &["id".to_string()],
+            &column_names,
+            &[],
+            &column_conversions(&columns),
+            2,
+        );
+
+        assert!(query.contains(
+            "VALUES ($1, ST_GeomFromEWKB($2), ST_GeomFromEWKB($3)::geography, $4::text::vector), \
+             ($5, ST_GeomFromEWKB($6), ST_GeomFromEWKB($7)::geography, $8::text::vector)"
+        ));
+        assert!(query.contains("\"geom\" = EXCLUDED.\"geom\""));
+        assert!(query.contains("\"embedding\" = EXCLUDED.\"embedding\""));
+    }
+
+    #[test]
+    fn test_build_upsert_query_cast_columns() {
+        let query = build_upsert_query(
+            "public",
+            "users",
+            &["id".to_string()],
+            &["id".to_string(), "size".to_string()],
+            &[],
+            &[(
+                "size".to_string(),
+                ColumnConversion::Cast {
+                    from: "int4".to_string(),
+                    to: "bigint".to_string(),
+                },
+            )],
+            1,
+        );
+        assert!(query.contains("VALUES ($1, $2::\"int4\"::bigint)"));
+    }
+
+    #[test]
+    fn test_generated_columns_writable_columns() {
+        let columns = vec![
+            ("id".to_string(), "int8".to_string()),
+            ("seq".to_string(), "int4".to_string()),
+            ("total".to_string(), "numeric".to_string()),
+            ("total_cents".to_string(), "int8".to_string()),
+        ];
+        let pk = vec!["id".to_string()];
+        let generated = GeneratedColumns {
+            stored: vec!["total_cents".to_string()],
+            identity_always: vec!["id".to_string(), "seq".to_string()],
+        };
+
+        let writable = generated.writable_columns(&columns, &pk, true).unwrap();
+        let names: Vec<&str> = writable.iter().map(|(n, _)| n.as_str()).collect();
+        assert_eq!(names, vec!["id", "seq", "total"]);
+        assert_eq!(
+            generated.overridden_identity_columns(&writable),
+            vec!["id".to_string(), "seq".to_string()]
+        );
+
+        // Without overriding, a key identity column cannot be written
+        assert!(generated.writable_columns(&columns, &pk, false).is_err());
+
+        let generated = GeneratedColumns {
+            stored: vec![],
+            identity_always: vec!["seq".to_string()],
+        };
+        let writable = generated.writable_columns(&columns, &pk, false).unwrap();
+        let names: Vec<&str> = writable.iter().map(|(n, _)| n.as_str()).collect();
+        assert_eq!(names, vec!["id", "total", "total_cents"]);
+        assert!(generated.overridden_identity_columns(&writable).is_empty());
+    }
+
     #[test]
     fn test_build_delete_query_single_pk() {
-        let query = build_delete_query("public", "users", &["id".to_string()], 3);
+        let query = build_delete_query("public", "users", &["id".to_string()], &[], 3);
 
         assert!(query.contains("DELETE FROM \"public\".\"users\""));
         assert!(query.contains("WHERE \"id\" IN ($1, $2, $3)"));
     }
 
+    #[test]
+    fn test_build_delete_query_text_keys() {
+        let query = build_delete_query(
+            "public",
+            "order_items",
+            &["order_id".to_string(), "sku".to_string()],
+            &["integer".to_string(), "character varying(20)".to_string()],
+            2,
+        );
+
+        assert!(query.contains(
+            "IN (($1::text::integer, $2::text::character varying(20)), \
+             ($3::text::integer, $4::text::character varying(20)))"
+        ));
+    }
+
     #[test]
     fn test_build_delete_query_composite_pk() {
         let query = build_delete_query(
             "public",
             "order_items",
             &["order_id".to_string(), "item_id".to_string()],
+            &[],
             2,
         );
 
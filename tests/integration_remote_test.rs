@@ -36,14 +36,18 @@ async fn test_remote_job_submission() {
     let job_spec = JobSpec {
         version: "1.0".to_string(),
         command: "validate".to_string(),
-        source_url,
-        target_url: Some(target_url),
+        source_url: source_url.into(),
+        target_url: Some(target_url.into()),
         target_project_id: None,
         target_branch_id: None,
         target_databases: None,
         seren_api_key: None,
         filter: None,
+        table_rules: None,
         options: HashMap::new(),
+        schedule: None,
+        sealed: None,
+        artifact_checksums: None,
     };
 
     // Submit the job
@@ -90,14 +94,18 @@ async fn test_remote_job_polling() {
     let job_spec = JobSpec {
         version: "1.0".to_string(),
         command: "validate".to_string(),
-        source_url,
-        target_url: Some(target_url),
+        source_url: source_url.into(),
+        target_url: Some(target_url.into()),
         target_project_id: None,
         target_branch_id: None,
         target_databases: None,
         seren_api_key: None,
         filter: None,
+        table_rules: None,
         options: HashMap::new(),
+        schedule: None,
+        sealed: None,
+        artifact_checksums: None,
     };
 
     // Submit the job
@@ -193,14 +201,18 @@ async fn test_remote_job_poll_until_complete() {
     let job_spec = JobSpec {
         version: "1.0".to_string(),
         command: "validate".to_string(),
-        source_url,
-        target_url: Some(target_url),
+        source_url: source_url.into(),
+        target_url: Some(target_url.into()),
         target_project_id: None,
         target_branch_id: None,
         target_databases: None,
         seren_api_key: None,
         filter: None,
+        table_rules: None,
         options: HashMap::new(),
+        schedule: None,
+        sealed: None,
+        artifact_checksums: None,
     };
 
     // Submit the job
@@ -289,14 +301,18 @@ async fn test_remote_job_submission_with_filters() {
     let job_spec = JobSpec {
         version: "1.0".to_string(),
         command: "validate".to_string(),
-        source_url,
-        target_url: Some(target_url),
+        source_url: source_url.into(),
+        target_url: Some(target_url.into()),
         target_project_id: None,
         target_branch_id: None,
         target_databases: None,
         seren_api_key: None,
         filter: Some(filter),
+        table_rules: None,
         options: HashMap::new(),
+        schedule: None,
+        sealed: None,
+        artifact_checksums: None,
     };
 
     // Submit the job
@@ -341,14 +357,18 @@ async fn test_remote_job_submission_with_options() {
     let job_spec = JobSpec {
         version: "1.0".to_string(),
         command: "validate".to_string(),
-        source_url,
-        target_url: Some(target_url),
+        source_url: source_url.into(),
+        target_url: Some(target_url.into()),
         target_project_id: None,
         target_branch_id: None,
         target_databases: None,
         seren_api_key: None,
         filter: None,
+        table_rules: None,
         options,
+        schedule: None,
+        sealed: None,
+        artifact_checksums: None,
     };
 
     // Submit the job
@@ -438,3 +438,68 @@ async fn test_sync_with_table_filter() {
         }
     }
 }
+
+#[tokio::test]
+#[ignore]
+async fn test_replicate_ownership_and_grants() {
+    use database_replicator::migration::roles::{replicate_database_access, AccessOptions};
+
+    let (source_url, target_url) =
+        get_test_urls().expect("TEST_SOURCE_URL and TEST_TARGET_URL must be set");
+    let source = database_replicator::postgres::connect(&source_url)
+        .await
+        .unwrap();
+    let target = database_replicator::postgres::connect(&target_url)
+        .await
+        .unwrap();
+
+    // Roles are cluster-wide, so create them on both sides in case they differ
+    let roles = "DO $$ BEGIN
+            CREATE ROLE access_test_owner NOLOGIN;
+        EXCEPTION WHEN duplicate_object THEN NULL; END $$;
+        DO $$ BEGIN
+            CREATE ROLE access_test_reader NOLOGIN;
+        EXCEPTION WHEN duplicate_object THEN NULL; END $$;";
+    source.batch_execute(roles).await.unwrap();
+    target.batch_execute(roles).await.unwrap();
+
+    let table = "DROP TABLE IF EXISTS public.access_test;
+        CREATE TABLE public.access_test (id INTEGER PRIMARY KEY);";
+    source.batch_execute(table).await.unwrap();
+    target.batch_execute(table).await.unwrap();
+    source
+        .batch_execute(
+            "ALTER TABLE public.access_test OWNER TO access_test_owner;
+             GRANT SELECT ON public.access_test TO access_test_reader;",
+        )
+        .await
+        .unwrap();
+
+    let options = AccessOptions {
+        include_roles: true,
+        include_grants: true,
+        generate_passwords: false,
+    };
+    let report = replicate_database_access(&source, &target, &options)
+        .await
+        .expect("access replication failed");
+    assert!(report.applied >= 2);
+
+    let row = target
+        .query_one(
+            "SELECT pg_get_userbyid(relowner),
+                    has_table_privilege('access_test_reader', 'public.access_test', 'SELECT')
+             FROM pg_class WHERE oid = 'public.access_test'::regclass",
+            &[],
+        )
+        .await
+        .unwrap();
+    assert_eq!(row.get::<_, String>(0), "access_test_owner");
+    assert!(row.get::<_, bool>(1));
+
+    for client in [&source, &target] {
+        let _ = client
+            .batch_execute("DROP TABLE IF EXISTS public.access_test")
+            .await;
+    }
+}
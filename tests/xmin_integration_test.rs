@@ -437,9 +437,18 @@ async fn test_sync_daemon_full_cycle() {
         sync_interval: Duration::from_secs(60),
         reconcile_interval: Some(Duration::from_secs(3600)),
         state_path: state_path.clone(),
+        state_in_target: false,
         batch_size: 1000,
         tables: vec![table_name.clone()],
-        schema: "public".to_string(),
+        schemas: vec!["public".to_string()],
+        overriding_system_value: true,
+        exclude_tables: Vec::new(),
+        pause_file: None,
+        audit_schema: false,
+        auto_add_tables: None,
+        matview_refresh_interval: None,
+        verify_rate: None,
+        purge_aged_out: false,
     };
 
     // Create and run single sync cycle
@@ -476,6 +485,76 @@ async fn test_sync_daemon_full_cycle() {
         .expect("Failed to cleanup");
 }
 
+/// Test: Stateless sync keeps its state in the target and resumes from it
+#[tokio::test]
+#[ignore]
+async fn test_sync_daemon_state_in_target() {
+    let (source_url, target_url) =
+        get_test_urls().expect("TEST_SOURCE_URL and TEST_TARGET_URL must be set");
+
+    let source_client = database_replicator::postgres::connect(&source_url)
+        .await
+        .expect("Failed to connect to source");
+    let target_client = database_replicator::postgres::connect(&target_url)
+        .await
+        .expect("Failed to connect to target");
+
+    let table_name = test_table_name("stateless");
+    setup_test_table(&source_client, &target_client, &table_name)
+        .await
+        .expect("Failed to setup test tables");
+    source_client
+        .batch_execute(&format!(
+            r#"INSERT INTO "public"."{}" (name, value) VALUES ('row1', 10), ('row2', 20);"#,
+            table_name
+        ))
+        .await
+        .expect("Failed to insert source data");
+
+    // The state path points nowhere writable; nothing may be written to it
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let state_path = temp_dir.path().join("unused").join("state.json");
+    let config = DaemonConfig {
+        reconcile_interval: None,
+        state_path: state_path.clone(),
+        state_in_target: true,
+        batch_size: 1000,
+        tables: vec![table_name.clone()],
+        ..Default::default()
+    };
+
+    let daemon = SyncDaemon::new(source_url.clone(), target_url.clone(), config.clone());
+    let stats = daemon.run_sync_cycle().await.expect("Sync cycle failed");
+    assert_eq!(stats.rows_synced, 2, "Should sync 2 rows");
+    assert!(!state_path.exists(), "No state file should be written");
+
+    let source_id = database_replicator::xmin::source_identifier(&source_url).unwrap();
+    let state = SyncState::load_from_target(&target_client, &source_id)
+        .await
+        .expect("Failed to load state from target")
+        .expect("State should be stored in the target");
+    let table_state = state
+        .get_table("public", &table_name)
+        .expect("Should have table state");
+    assert!(table_state.last_xmin > 0, "xmin should be recorded");
+
+    // A new daemon, as on a rescheduled pod, picks up from the stored state
+    source_client
+        .batch_execute(&format!(
+            r#"INSERT INTO "public"."{}" (name, value) VALUES ('row3', 30);"#,
+            table_name
+        ))
+        .await
+        .expect("Failed to insert source data");
+    let daemon = SyncDaemon::new(source_url.clone(), target_url.clone(), config);
+    let stats = daemon.run_sync_cycle().await.expect("Sync cycle failed");
+    assert_eq!(stats.rows_synced, 1, "Should only sync the new row");
+
+    cleanup_test_table(&source_client, &target_client, &table_name)
+        .await
+        .expect("Failed to cleanup");
+}
+
 /// Test: End-to-end xmin sync with updates
 #[tokio::test]
 #[ignore]
@@ -505,9 +584,18 @@ async fn test_xmin_sync_with_updates() {
         sync_interval: Duration::from_secs(60),
         reconcile_interval: None, // Disable reconciliation for this test
         state_path,
+        state_in_target: false,
         batch_size: 1000,
         tables: vec![table_name.clone()],
-        schema: "public".to_string(),
+        schemas: vec!["public".to_string()],
+        overriding_system_value: true,
+        exclude_tables: Vec::new(),
+        pause_file: None,
+        audit_schema: false,
+        auto_add_tables: None,
+        matview_refresh_interval: None,
+        verify_rate: None,
+        purge_aged_out: false,
     };
 
     let daemon = SyncDaemon::new(source_url.clone(), target_url.clone(), config);
@@ -564,6 +652,430 @@ async fn test_xmin_sync_with_updates() {
         .expect("Failed to cleanup");
 }
 
+/// Test: rows rejected by the target are quarantined without aborting the batch
+#[tokio::test]
+#[ignore]
+async fn test_change_writer_quarantines_failed_rows() {
+    let (source_url, target_url) =
+        get_test_urls().expect("TEST_SOURCE_URL and TEST_TARGET_URL must be set");
+
+    let source_client = database_replicator::postgres::connect(&source_url)
+        .await
+        .expect("Failed to connect to source");
+    let target_client = database_replicator::postgres::connect(&target_url)
+        .await
+        .expect("Failed to connect to target");
+
+    let table_name = test_table_name("dead_letter");
+    setup_test_table(&source_client, &target_client, &table_name)
+        .await
+        .expect("Failed to setup test table");
+
+    // Only the target rejects negative values
+    target_client
+        .batch_execute(&format!(
+            r#"ALTER TABLE "public"."{}" ADD CONSTRAINT value_non_negative CHECK (value >= 0)"#,
+            table_name
+        ))
+        .await
+        .expect("Failed to add constraint");
+    source_client
+        .batch_execute(&format!(
+            r#"INSERT INTO "public"."{}" (name, value) VALUES ('ok1', 1), ('bad', -1), ('ok2', 2)"#,
+            table_name
+        ))
+        .await
+        .expect("Failed to insert source data");
+
+    let columns = get_table_columns(&source_client, "public", &table_name)
+        .await
+        .expect("Failed to get columns");
+    let pk_columns = get_primary_key_columns(&source_client, "public", &table_name)
+        .await
+        .expect("Failed to get PK");
+    let column_names: Vec<String> = columns.iter().map(|(n, _)| n.clone()).collect();
+
+    let reader = XminReader::new(&source_client);
+    let (rows, _max_xmin) = reader
+        .read_changes("public", &table_name, &column_names, 0)
+        .await
+        .expect("Failed to read source changes");
+    let values: Vec<Vec<Box<dyn tokio_postgres::types::ToSql + Sync + Send>>> = rows
+        .iter()
+        .map(|row| database_replicator::xmin::writer::row_to_values(row, &columns))
+        .collect();
+
+    let writer = ChangeWriter::new(&target_client);
+    let affected = writer
+        .apply_batch("public", &table_name, &pk_columns, &column_names, values)
+        .await
+        .expect("Batch should succeed despite one bad row");
+    assert_eq!(affected, 2, "Good rows should still be applied");
+
+    let row = target_client
+        .query_one(
+            "SELECT row_data->>'name', error FROM public._replicator_dead_letters
+             WHERE table_name = $1 ORDER BY id DESC LIMIT 1",
+            &[&table_name],
+        )
+        .await
+        .expect("Failed to read dead letters");
+    let name: Option<String> = row.get(0);
+    let error: String = row.get(1);
+    assert_eq!(name.as_deref(), Some("bad"));
+    assert!(error.contains("value_non_negative"), "error: {}", error);
+
+    let _ = target_client
+        .execute(
+            "DELETE FROM public._replicator_dead_letters WHERE table_name = $1",
+            &[&table_name],
+        )
+        .await;
+    cleanup_test_table(&source_client, &target_client, &table_name)
+        .await
+        .expect("Failed to cleanup");
+}
+
+/// Test: generated and GENERATED ALWAYS identity columns sync without errors
+#[tokio::test]
+#[ignore]
+async fn test_sync_daemon_generated_columns() {
+    let (source_url, target_url) =
+        get_test_urls().expect("TEST_SOURCE_URL and TEST_TARGET_URL must be set");
+
+    let source_client = database_replicator::postgres::connect(&source_url)
+        .await
+        .expect("Failed to connect to source");
+    let target_client = database_replicator::postgres::connect(&target_url)
+        .await
+        .expect("Failed to connect to target");
+
+    let table_name = test_table_name("generated");
+    let ddl = format!(
+        r#"
+        DROP TABLE IF EXISTS "public"."{0}";
+        CREATE TABLE "public"."{0}" (
+            id BIGINT GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+            price NUMERIC NOT NULL,
+            qty INTEGER NOT NULL,
+            total NUMERIC GENERATED ALWAYS AS (price * qty) STORED
+        )
+        "#,
+        table_name
+    );
+    source_client.batch_execute(&ddl).await.expect("source DDL");
+    target_client.batch_execute(&ddl).await.expect("target DDL");
+
+    source_client
+        .batch_execute(&format!(
+            r#"INSERT INTO "public"."{}" (price, qty) VALUES (2.5, 4), (10, 1)"#,
+            table_name
+        ))
+        .await
+        .expect("Failed to insert source data");
+
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let config = DaemonConfig {
+        sync_interval: Duration::from_secs(60),
+        reconcile_interval: None,
+        state_path: temp_dir.path().join("state.json"),
+        state_in_target: false,
+        batch_size: 1000,
+        tables: vec![table_name.clone()],
+        schemas: vec!["public".to_string()],
+        overriding_system_value: true,
+        exclude_tables: Vec::new(),
+        pause_file: None,
+        audit_schema: false,
+        auto_add_tables: None,
+        matview_refresh_interval: None,
+        verify_rate: None,
+        purge_aged_out: false,
+    };
+    let daemon = SyncDaemon::new(source_url.clone(), target_url.clone(), config);
+
+    let stats = daemon.run_sync_cycle().await.expect("Sync cycle failed");
+    assert!(stats.is_success(), "errors: {:?}", stats.errors);
+
+    // Update a row so the conflict path runs too
+    source_client
+        .batch_execute(&format!(
+            r#"UPDATE "public"."{}" SET qty = 3 WHERE price = 10"#,
+            table_name
+        ))
+        .await
+        .expect("Failed to update source");
+    let stats = daemon.run_sync_cycle().await.expect("Sync cycle failed");
+    assert!(stats.is_success(), "errors: {:?}", stats.errors);
+
+    let rows = target_client
+        .query(
+            &format!(
+                r#"SELECT id, total::text FROM "public"."{}" ORDER BY id"#,
+                table_name
+            ),
+            &[],
+        )
+        .await
+        .expect("Failed to query target");
+    let source_ids: Vec<i64> = source_client
+        .query(
+            &format!(r#"SELECT id FROM "public"."{}" ORDER BY id"#, table_name),
+            &[],
+        )
+        .await
+        .expect("Failed to query source")
+        .iter()
+        .map(|r| r.get(0))
+        .collect();
+    let target_ids: Vec<i64> = rows.iter().map(|r| r.get(0)).collect();
+    let totals: Vec<String> = rows.iter().map(|r| r.get(1)).collect();
+    assert_eq!(
+        target_ids, source_ids,
+        "Identity values should be preserved"
+    );
+    assert_eq!(totals, vec!["10.0".to_string(), "30".to_string()]);
+
+    cleanup_test_table(&source_client, &target_client, &table_name)
+        .await
+        .expect("Failed to cleanup");
+}
+
+/// Test: Geometry and geography columns keep their SRID through xmin sync
+///
+/// Skips when the source server does not have PostGIS available.
+#[tokio::test]
+#[ignore]
+async fn test_sync_daemon_postgis_columns() {
+    let (source_url, target_url) =
+        get_test_urls().expect("TEST_SOURCE_URL and TEST_TARGET_URL must be set");
+
+    let source_client = database_replicator::postgres::connect(&source_url)
+        .await
+        .expect("Failed to connect to source");
+    let target_client = database_replicator::postgres::connect(&target_url)
+        .await
+        .expect("Failed to connect to target");
+
+    if source_client
+        .batch_execute("CREATE EXTENSION IF NOT EXISTS postgis")
+        .await
+        .is_err()
+    {
+        println!("PostGIS not available on source, skipping");
+        return;
+    }
+
+    let table_name = test_table_name("postgis");
+    source_client
+        .batch_execute(&format!(
+            r#"
+            DROP TABLE IF EXISTS "public"."{0}";
+            CREATE TABLE "public"."{0}" (
+                id INTEGER PRIMARY KEY,
+                geom geometry(Point, 3857),
+                area geography(Polygon, 4326)
+            );
+            INSERT INTO "public"."{0}" VALUES
+                (1, ST_SetSRID(ST_MakePoint(1, 2), 3857),
+                 'SRID=4326;POLYGON((0 0, 0 1, 1 1, 1 0, 0 0))'),
+                (2, NULL, NULL);
+            "#,
+            table_name
+        ))
+        .await
+        .expect("Failed to create source table");
+
+    // The daemon creates PostGIS on the target; the table itself comes from init
+    target_client
+        .batch_execute(&format!(
+            r#"DROP TABLE IF EXISTS "public"."{}""#,
+            table_name
+        ))
+        .await
+        .expect("Failed to drop target table");
+
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let config = DaemonConfig {
+        sync_interval: Duration::from_secs(60),
+        reconcile_interval: None,
+        state_path: temp_dir.path().join("state.json"),
+        state_in_target: false,
+        batch_size: 1000,
+        tables: vec![table_name.clone()],
+        schemas: vec!["public".to_string()],
+        overriding_system_value: true,
+        exclude_tables: Vec::new(),
+        pause_file: None,
+        audit_schema: false,
+        auto_add_tables: None,
+        matview_refresh_interval: None,
+        verify_rate: None,
+        purge_aged_out: false,
+    };
+    let daemon = SyncDaemon::new(source_url.clone(), target_url.clone(), config);
+
+    // First cycle only ensures the extension; the table does not exist yet
+    let _ = daemon.run_sync_cycle().await;
+    target_client
+        .batch_execute(&format!(
+            r#"CREATE TABLE "public"."{}" (
+                id INTEGER PRIMARY KEY,
+                geom geometry(Point, 3857),
+                area geography(Polygon, 4326)
+            )"#,
+            table_name
+        ))
+        .await
+        .expect("PostGIS should have been created on the target");
+
+    source_client
+        .batch_execute(&format!(r#"UPDATE "public"."{}" SET id = id"#, table_name))
+        .await
+        .expect("Failed to touch source rows");
+    let stats = daemon.run_sync_cycle().await.expect("Sync cycle failed");
+    assert!(stats.is_success(), "errors: {:?}", stats.errors);
+
+    let rows = target_client
+        .query(
+            &format!(
+                r#"SELECT id, ST_AsEWKT(geom), ST_AsEWKT(area) FROM "public"."{}" ORDER BY id"#,
+                table_name
+            ),
+            &[],
+        )
+        .await
+        .expect("Failed to query target");
+    assert_eq!(rows.len(), 2);
+    assert_eq!(
+        rows[0].get::<_, Option<String>>(1).as_deref(),
+        Some("SRID=3857;POINT(1 2)")
+    );
+    assert_eq!(
+        rows[0].get::<_, Option<String>>(2).as_deref(),
+        Some("SRID=4326;POLYGON((0 0,0 1,1 1,1 0,0 0))")
+    );
+    assert_eq!(rows[1].get::<_, Option<String>>(1), None);
+
+    cleanup_test_table(&source_client, &target_client, &table_name)
+        .await
+        .expect("Failed to cleanup");
+}
+
+/// Test: pgvector embeddings sync and ANN indexes survive deferral
+///
+/// Skips when the source server does not have pgvector available.
+#[tokio::test]
+#[ignore]
+async fn test_sync_daemon_pgvector_columns() {
+    use database_replicator::postgres::pgvector::{defer_vector_indexes, recreate_vector_indexes};
+
+    let (source_url, target_url) =
+        get_test_urls().expect("TEST_SOURCE_URL and TEST_TARGET_URL must be set");
+
+    let source_client = database_replicator::postgres::connect(&source_url)
+        .await
+        .expect("Failed to connect to source");
+    let target_client = database_replicator::postgres::connect(&target_url)
+        .await
+        .expect("Failed to connect to target");
+
+    if source_client
+        .batch_execute("CREATE EXTENSION IF NOT EXISTS vector")
+        .await
+        .is_err()
+    {
+        println!("pgvector not available on source, skipping");
+        return;
+    }
+    target_client
+        .batch_execute("CREATE EXTENSION IF NOT EXISTS vector")
+        .await
+        .expect("pgvector should be available on the target too");
+
+    let table_name = test_table_name("pgvector");
+    let ddl = format!(
+        r#"
+        DROP TABLE IF EXISTS "public"."{0}";
+        CREATE TABLE "public"."{0}" (
+            id INTEGER PRIMARY KEY,
+            embedding vector(3)
+        );
+        CREATE INDEX "{0}_hnsw" ON "public"."{0}" USING hnsw (embedding vector_l2_ops);
+        "#,
+        table_name
+    );
+    source_client.batch_execute(&ddl).await.expect("source DDL");
+    target_client.batch_execute(&ddl).await.expect("target DDL");
+    source_client
+        .batch_execute(&format!(
+            r#"INSERT INTO "public"."{}" VALUES (1, '[0.1,0.2,0.3]'), (2, NULL)"#,
+            table_name
+        ))
+        .await
+        .expect("Failed to insert source data");
+
+    let deferred = defer_vector_indexes(&target_client)
+        .await
+        .expect("Failed to defer indexes");
+    assert!(deferred
+        .iter()
+        .any(|i| i.name == format!("{}_hnsw", table_name)));
+
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let config = DaemonConfig {
+        sync_interval: Duration::from_secs(60),
+        reconcile_interval: None,
+        state_path: temp_dir.path().join("state.json"),
+        state_in_target: false,
+        batch_size: 1000,
+        tables: vec![table_name.clone()],
+        schemas: vec!["public".to_string()],
+        overriding_system_value: true,
+        exclude_tables: Vec::new(),
+        pause_file: None,
+        audit_schema: false,
+        auto_add_tables: None,
+        matview_refresh_interval: None,
+        verify_rate: None,
+        purge_aged_out: false,
+    };
+    let daemon = SyncDaemon::new(source_url.clone(), target_url.clone(), config);
+    let stats = daemon.run_sync_cycle().await.expect("Sync cycle failed");
+    assert!(stats.is_success(), "errors: {:?}", stats.errors);
+
+    recreate_vector_indexes(&target_client, &deferred)
+        .await
+        .expect("Failed to rebuild indexes");
+
+    let rows = target_client
+        .query(
+            &format!(
+                r#"SELECT embedding::text FROM "public"."{}" ORDER BY id"#,
+                table_name
+            ),
+            &[],
+        )
+        .await
+        .expect("Failed to query target");
+    let embeddings: Vec<Option<String>> = rows.iter().map(|r| r.get(0)).collect();
+    assert_eq!(embeddings, vec![Some("[0.1,0.2,0.3]".to_string()), None]);
+
+    let index_count: i64 = target_client
+        .query_one(
+            "SELECT count(*) FROM pg_indexes WHERE indexname = $1",
+            &[&format!("{}_hnsw", table_name)],
+        )
+        .await
+        .expect("Failed to check index")
+        .get(0);
+    assert_eq!(index_count, 1, "hnsw index should be rebuilt");
+
+    cleanup_test_table(&source_client, &target_client, &table_name)
+        .await
+        .expect("Failed to cleanup");
+}
+
 /// Test: Wraparound detection function
 #[test]
 fn test_wraparound_detection_logic() {
@@ -592,3 +1104,288 @@ fn test_wraparound_detection_logic() {
 
     println!("✓ Wraparound detection logic verified");
 }
+
+/// Test: One daemon syncs tables from several schemas
+#[tokio::test]
+#[ignore]
+async fn test_xmin_sync_multiple_schemas() {
+    let (source_url, target_url) =
+        get_test_urls().expect("TEST_SOURCE_URL and TEST_TARGET_URL must be set");
+
+    let source_client = database_replicator::postgres::connect(&source_url)
+        .await
+        .expect("Failed to connect to source");
+    let target_client = database_replicator::postgres::connect(&target_url)
+        .await
+        .expect("Failed to connect to target");
+
+    let ddl = r#"
+        DROP SCHEMA IF EXISTS xmin_test_sales CASCADE;
+        DROP SCHEMA IF EXISTS xmin_test_audit CASCADE;
+        CREATE SCHEMA xmin_test_sales;
+        CREATE SCHEMA xmin_test_audit;
+        CREATE TABLE xmin_test_sales.orders (id INTEGER PRIMARY KEY, total INTEGER);
+        CREATE TABLE xmin_test_audit.events (id INTEGER PRIMARY KEY, kind TEXT);
+    "#;
+    source_client.batch_execute(ddl).await.unwrap();
+    target_client.batch_execute(ddl).await.unwrap();
+    source_client
+        .batch_execute(
+            "INSERT INTO xmin_test_sales.orders VALUES (1, 10), (2, 20);
+             INSERT INTO xmin_test_audit.events VALUES (1, 'login');",
+        )
+        .await
+        .unwrap();
+
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let config = DaemonConfig {
+        sync_interval: Duration::from_secs(60),
+        reconcile_interval: None,
+        state_path: temp_dir.path().join("schemas_state.json"),
+        state_in_target: false,
+        batch_size: 1000,
+        tables: Vec::new(),
+        schemas: vec!["xmin_test_sales".to_string(), "xmin_test_audit".to_string()],
+        overriding_system_value: true,
+        exclude_tables: Vec::new(),
+        pause_file: None,
+        audit_schema: false,
+        auto_add_tables: None,
+        matview_refresh_interval: None,
+        verify_rate: None,
+        purge_aged_out: false,
+    };
+    let daemon = SyncDaemon::new(source_url.clone(), target_url.clone(), config);
+
+    let stats = daemon.run_sync_cycle().await.expect("Sync failed");
+    assert!(stats.is_success(), "Sync errors: {:?}", stats.errors);
+    assert_eq!(stats.tables_synced, 2);
+    assert_eq!(stats.rows_synced, 3);
+
+    let orders: i64 = target_client
+        .query_one("SELECT count(*) FROM xmin_test_sales.orders", &[])
+        .await
+        .unwrap()
+        .get(0);
+    let events: i64 = target_client
+        .query_one("SELECT count(*) FROM xmin_test_audit.events", &[])
+        .await
+        .unwrap()
+        .get(0);
+    assert_eq!((orders, events), (2, 1));
+
+    let cleanup = "DROP SCHEMA xmin_test_sales CASCADE; DROP SCHEMA xmin_test_audit CASCADE;";
+    let _ = source_client.batch_execute(cleanup).await;
+    let _ = target_client.batch_execute(cleanup).await;
+}
+
+/// Test: One daemon syncs two database pairs, each with its own state file
+#[tokio::test]
+#[ignore]
+async fn test_xmin_sync_multiple_databases() {
+    use database_replicator::commands::sync::replace_database_in_url;
+    use database_replicator::xmin::SyncDatabase;
+
+    let (source_url, target_url) =
+        get_test_urls().expect("TEST_SOURCE_URL and TEST_TARGET_URL must be set");
+
+    // Second pair lives in freshly created databases on the same servers
+    let source_server = database_replicator::postgres::connect(&source_url)
+        .await
+        .expect("Failed to connect to source");
+    let target_server = database_replicator::postgres::connect(&target_url)
+        .await
+        .expect("Failed to connect to target");
+    let _ = source_server
+        .batch_execute("DROP DATABASE IF EXISTS xmin_multi_src")
+        .await;
+    let _ = target_server
+        .batch_execute("DROP DATABASE IF EXISTS xmin_multi_tgt")
+        .await;
+    source_server
+        .batch_execute("CREATE DATABASE xmin_multi_src")
+        .await
+        .unwrap();
+    target_server
+        .batch_execute("CREATE DATABASE xmin_multi_tgt")
+        .await
+        .unwrap();
+    let second_source = replace_database_in_url(&source_url, "xmin_multi_src").unwrap();
+    let second_target = replace_database_in_url(&target_url, "xmin_multi_tgt").unwrap();
+
+    let table_name = test_table_name("multi_db");
+    let pairs = [
+        (source_url.clone(), target_url.clone()),
+        (second_source.clone(), second_target.clone()),
+    ];
+    for (index, (source, target)) in pairs.iter().enumerate() {
+        let source_client = database_replicator::postgres::connect(source)
+            .await
+            .unwrap();
+        let target_client = database_replicator::postgres::connect(target)
+            .await
+            .unwrap();
+        setup_test_table(&source_client, &target_client, &table_name)
+            .await
+            .unwrap();
+        for row in 0..=index {
+            source_client
+                .execute(
+                    &format!(
+                        r#"INSERT INTO "public"."{}" (name) VALUES ($1)"#,
+                        table_name
+                    ),
+                    &[&format!("row{}", row)],
+                )
+                .await
+                .unwrap();
+        }
+    }
+
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let config = DaemonConfig {
+        sync_interval: Duration::from_secs(60),
+        reconcile_interval: None,
+        state_path: temp_dir.path().join("state.json"),
+        batch_size: 1000,
+        ..Default::default()
+    };
+    let databases = pairs
+        .iter()
+        .map(|(source, target)| SyncDatabase {
+            source_url: source.clone(),
+            target_url: target.clone(),
+            tables: vec![table_name.clone()],
+            schemas: vec!["public".to_string()],
+            exclude_tables: Vec::new(),
+            transforms: Default::default(),
+            sources: Default::default(),
+            target_names: Default::default(),
+            column_overrides: Default::default(),
+            time_windows: Default::default(),
+            retention: Default::default(),
+        })
+        .collect();
+    let daemon = SyncDaemon::with_databases(databases, config);
+
+    let stats = daemon.run_sync_cycle().await.expect("Sync failed");
+    assert!(stats.is_success(), "Sync errors: {:?}", stats.errors);
+    assert_eq!(stats.tables_synced, 2);
+    assert_eq!(stats.rows_synced, 3);
+
+    let second_target_client = database_replicator::postgres::connect(&second_target)
+        .await
+        .unwrap();
+    let count: i64 = second_target_client
+        .query_one(
+            &format!(r#"SELECT count(*) FROM "public"."{}""#, table_name),
+            &[],
+        )
+        .await
+        .unwrap()
+        .get(0);
+    assert_eq!(count, 2);
+    assert!(temp_dir.path().join("state.xmin_multi_src.json").exists());
+
+    drop(second_target_client);
+    let first_source = database_replicator::postgres::connect(&source_url)
+        .await
+        .unwrap();
+    let first_target = database_replicator::postgres::connect(&target_url)
+        .await
+        .unwrap();
+    let _ = cleanup_test_table(&first_source, &first_target, &table_name).await;
+    let _ = source_server
+        .batch_execute("DROP DATABASE IF EXISTS xmin_multi_src WITH (FORCE)")
+        .await;
+    let _ = target_server
+        .batch_execute("DROP DATABASE IF EXISTS xmin_multi_tgt WITH (FORCE)")
+        .await;
+}
+
+/// Test: With auto_add_tables, a table created on the source after the first
+/// cycle that matches the filter is created on the target and copied
+#[tokio::test]
+#[ignore]
+async fn test_xmin_sync_auto_adds_new_tables() {
+    use database_replicator::filters::ReplicationFilter;
+
+    let (source_url, target_url) =
+        get_test_urls().expect("TEST_SOURCE_URL and TEST_TARGET_URL must be set");
+    let db_name = database_replicator::utils::parse_postgres_url(&source_url)
+        .unwrap()
+        .database;
+
+    let source_client = database_replicator::postgres::connect(&source_url)
+        .await
+        .expect("Failed to connect to source");
+    let target_client = database_replicator::postgres::connect(&target_url)
+        .await
+        .expect("Failed to connect to target");
+
+    let ddl = r#"
+        DROP SCHEMA IF EXISTS xmin_test_auto CASCADE;
+        CREATE SCHEMA xmin_test_auto;
+        CREATE TABLE xmin_test_auto.events_a (id INTEGER PRIMARY KEY, kind TEXT);
+    "#;
+    source_client.batch_execute(ddl).await.unwrap();
+    target_client.batch_execute(ddl).await.unwrap();
+    source_client
+        .batch_execute("INSERT INTO xmin_test_auto.events_a VALUES (1, 'login')")
+        .await
+        .unwrap();
+
+    let filter = ReplicationFilter::new(
+        None,
+        None,
+        Some(vec![format!("{}.xmin_test_auto.events_*", db_name)]),
+        None,
+    )
+    .unwrap();
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let config = DaemonConfig {
+        sync_interval: Duration::from_secs(60),
+        reconcile_interval: None,
+        state_path: temp_dir.path().join("auto_add_state.json"),
+        batch_size: 1000,
+        schemas: vec!["xmin_test_auto".to_string()],
+        auto_add_tables: Some(filter),
+        ..Default::default()
+    };
+    let daemon = SyncDaemon::new(source_url.clone(), target_url.clone(), config);
+
+    let stats = daemon.run_sync_cycle().await.expect("Sync failed");
+    assert!(stats.is_success(), "Sync errors: {:?}", stats.errors);
+    assert_eq!(stats.tables_synced, 1);
+
+    source_client
+        .batch_execute(
+            "CREATE TABLE xmin_test_auto.events_b (id INTEGER PRIMARY KEY, kind TEXT);
+             INSERT INTO xmin_test_auto.events_b VALUES (1, 'click'), (2, 'view');
+             CREATE TABLE xmin_test_auto.users (id INTEGER PRIMARY KEY);",
+        )
+        .await
+        .unwrap();
+
+    let stats = daemon.run_sync_cycle().await.expect("Sync failed");
+    assert!(stats.is_success(), "Sync errors: {:?}", stats.errors);
+    assert_eq!(stats.tables_synced, 2);
+    assert_eq!(stats.rows_synced, 2);
+
+    let count: i64 = target_client
+        .query_one("SELECT count(*) FROM xmin_test_auto.events_b", &[])
+        .await
+        .unwrap()
+        .get(0);
+    assert_eq!(count, 2);
+    let users: Option<String> = target_client
+        .query_one("SELECT to_regclass('xmin_test_auto.users')::text", &[])
+        .await
+        .unwrap()
+        .get(0);
+    assert_eq!(users, None);
+
+    let cleanup = "DROP SCHEMA xmin_test_auto CASCADE;";
+    let _ = source_client.batch_execute(cleanup).await;
+    let _ = target_client.batch_execute(cleanup).await;
+}